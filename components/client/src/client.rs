@@ -1,6 +1,8 @@
 use super::session_manager::SessionManager;
 
-use crate::{composite_session::CompositeSession, heartbeat::HeartbeatData};
+use crate::{
+    composite_session::CompositeSession, heartbeat::HeartbeatData, retry_policy::RetryPolicy,
+};
 
 use bytes::Bytes;
 use log::{error, trace, warn};
@@ -23,7 +25,13 @@ use observation::metrics::{
     uring::UringStatistics,
 };
 use protocol::rpc::header::{ErrorCode, RangeServerState, ResourceType, SealKind, StreamT};
-use std::{cell::UnsafeCell, rc::Rc, sync::Arc, time::Duration};
+use std::{
+    cell::{RefCell, UnsafeCell},
+    collections::HashMap,
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::{sync::broadcast, time};
 
 #[cfg(any(test, feature = "mock"))]
@@ -87,20 +95,25 @@ pub trait Client {
         continuation: &Option<Bytes>,
     ) -> Result<ListResourceResult, EsError>;
 
+    /// Watch changes of resources after `version`. If `stream_id` is given, changes of ranges of
+    /// other streams are left out.
     async fn watch_resource(
         &self,
         types: &[ResourceType],
+        stream_id: Option<u64>,
         version: i64,
         timeout: Duration,
     ) -> Result<WatchResourceResult, EsError>;
 
     async fn target_go_away(&self, target: &str) -> Result<bool, EsError>;
 
+    /// Update metadata of the stream. Fields of `None` are left unchanged.
     async fn update_stream(
         &self,
         stream_id: u64,
         replica_count: Option<u8>,
         ack_count: Option<u8>,
+        retention_period: Option<Duration>,
         epoch: Option<u64>,
     ) -> Result<StreamMetadata, EsError>;
 
@@ -108,12 +121,40 @@ pub trait Client {
         -> Result<(), EsError>;
 
     async fn delete_stream(&self, stream_id: u64, epoch: u64) -> Result<(), EsError>;
+
+    /// Get the key-value pair of `key` from the key-value store of placement driver, or those in
+    /// `[key, range_end)` if `range_end` is given. At most `limit` pairs are returned unless it is 0.
+    async fn kv_range(
+        &self,
+        key: Bytes,
+        range_end: Option<Bytes>,
+        limit: i64,
+    ) -> Result<Vec<(Bytes, Bytes)>, EsError>;
+
+    /// Put a key-value pair into the key-value store of placement driver.
+    async fn kv_put(&self, key: Bytes, value: Bytes) -> Result<(), EsError>;
+
+    /// Put a key-value pair into the key-value store of placement driver, if `key` holds `expected`,
+    /// or does not exist if `expected` is `None`. Fails with `PRECONDITION_FAILED` otherwise.
+    async fn kv_compare_and_put(
+        &self,
+        key: Bytes,
+        expected: Option<Bytes>,
+        value: Bytes,
+    ) -> Result<(), EsError>;
+
+    /// Delete `key`, or keys in `[key, range_end)` if `range_end` is given, from the key-value store
+    /// of placement driver, returning the number of deleted keys.
+    async fn kv_delete_range(&self, key: Bytes, range_end: Option<Bytes>) -> Result<u64, EsError>;
 }
 
 /// `Client` is used to send
 pub struct DefaultClient {
     pub(crate) session_manager: Rc<UnsafeCell<SessionManager>>,
     pub(crate) config: Arc<config::Configuration>,
+
+    /// Retry policies of operations, lazily built from configuration.
+    retry_policies: RefCell<HashMap<&'static str, Rc<RetryPolicy>>>,
 }
 
 impl Client for DefaultClient {
@@ -139,21 +180,26 @@ impl Client for DefaultClient {
         &self,
         criteria: ListRangeCriteria,
     ) -> Result<Vec<RangeMetadata>, EsError> {
-        let session = self.get_pd_session().await?;
-        let future = session.list_range(criteria);
-        time::timeout(self.config.client_io_timeout(), future)
-            .await
-            .map_err(|elapsed| {
-                warn!("Timeout when list range. {}", elapsed);
-                EsError::new(ErrorCode::RPC_TIMEOUT, "list ranges rpc timeout")
-            })?
-            .map_err(|e| {
-                error!(
-                    "Failed to receive response from broken channel. Cause: {:?}",
-                    e
-                );
-                EsError::new(ErrorCode::ERROR_CODE_UNSPECIFIED, "todo")
+        let criteria = &criteria;
+        self.retry_policy("list-ranges")
+            .run(|| async move {
+                let session = self.get_pd_session().await?;
+                let future = session.list_range(criteria.clone());
+                time::timeout(self.config.client_io_timeout(), future)
+                    .await
+                    .map_err(|elapsed| {
+                        warn!("Timeout when list range. {}", elapsed);
+                        EsError::new(ErrorCode::RPC_TIMEOUT, "list ranges rpc timeout")
+                    })?
+                    .map_err(|e| {
+                        error!(
+                            "Failed to receive response from broken channel. Cause: {:?}",
+                            e
+                        );
+                        EsError::new(ErrorCode::ERROR_CODE_UNSPECIFIED, "todo")
+                    })
             })
+            .await
     }
 
     /// Broadcast heartbeats to all sessions in the `CompositeSession`.
@@ -180,20 +226,30 @@ impl Client for DefaultClient {
     }
 
     async fn describe_stream(&self, stream_id: u64) -> Result<StreamMetadata, EsError> {
-        let composite_session = self.get_pd_session().await?;
-        let future = composite_session.describe_stream(stream_id);
-        time::timeout(self.config.client_io_timeout(), future)
+        self.retry_policy("describe-stream")
+            .run(|| async move {
+                let composite_session = self.get_pd_session().await?;
+                let future = composite_session.describe_stream(stream_id);
+                time::timeout(self.config.client_io_timeout(), future)
+                    .await
+                    .map_err(|e| {
+                        error!("Timeout when describe stream[stream-id={stream_id}]. {}", e);
+                        EsError::new(ErrorCode::RPC_TIMEOUT, "describe stream rpc timeout")
+                    })?
+            })
             .await
-            .map_err(|e| {
-                error!("Timeout when describe stream[stream-id={stream_id}]. {}", e);
-                EsError::new(ErrorCode::RPC_TIMEOUT, "describe stream rpc timeout")
-            })?
     }
 
     /// Create a new range by send request to placement driver.
     async fn create_range(&self, range_metadata: RangeMetadata) -> Result<RangeMetadata, EsError> {
-        let composite_session = self.get_pd_session().await?;
-        self.create_range0(composite_session, range_metadata).await
+        let range_metadata = &range_metadata;
+        self.retry_policy("create-range")
+            .run(|| async move {
+                let composite_session = self.get_pd_session().await?;
+                self.create_range0(composite_session, range_metadata.clone())
+                    .await
+            })
+            .await
     }
 
     /// Create a new range replica by send request to range server.
@@ -202,12 +258,17 @@ impl Client for DefaultClient {
         target: &str,
         range_metadata: RangeMetadata,
     ) -> Result<(), EsError> {
-        let session_manager = unsafe { &mut *self.session_manager.get() };
-        let composite_session = session_manager.get_composite_session(target).await?;
-        trace!("Create range replica to composite-channel={}", target);
-        self.create_range0(composite_session, range_metadata)
+        let range_metadata = &range_metadata;
+        self.retry_policy("create-range")
+            .run(|| async move {
+                let session_manager = unsafe { &mut *self.session_manager.get() };
+                let composite_session = session_manager.get_composite_session(target).await?;
+                trace!("Create range replica to composite-channel={}", target);
+                self.create_range0(composite_session, range_metadata.clone())
+                    .await
+                    .map(|_| ())
+            })
             .await
-            .map(|_| ())
     }
 
     async fn seal<'a>(
@@ -244,23 +305,28 @@ impl Client for DefaultClient {
             }
         }
 
-        let session_manager = unsafe { &mut *self.session_manager.get() };
-        let composite_session = match target {
-            None => {
-                session_manager
-                    .get_composite_session(&self.config.placement_driver)
-                    .await?
-            }
-
-            Some(addr) => session_manager.get_composite_session(addr).await?,
-        };
-        let future = composite_session.seal(kind, range);
-        time::timeout(self.config.client_io_timeout(), future)
+        let range = &range;
+        self.retry_policy("seal")
+            .run(|| async move {
+                let session_manager = unsafe { &mut *self.session_manager.get() };
+                let composite_session = match target {
+                    None => {
+                        session_manager
+                            .get_composite_session(&self.config.placement_driver)
+                            .await?
+                    }
+
+                    Some(addr) => session_manager.get_composite_session(addr).await?,
+                };
+                let future = composite_session.seal(kind, range.clone());
+                time::timeout(self.config.client_io_timeout(), future)
+                    .await
+                    .map_err(|_| {
+                        error!("Timeout when seal range");
+                        EsError::new(ErrorCode::RPC_TIMEOUT, "seal range rpc timeout")
+                    })?
+            })
             .await
-            .map_err(|_| {
-                error!("Timeout when seal range");
-                EsError::new(ErrorCode::RPC_TIMEOUT, "seal range rpc timeout")
-            })?
     }
 
     /// Append data to a range.
@@ -269,22 +335,32 @@ impl Client for DefaultClient {
         target: &str,
         buf: Vec<Bytes>,
     ) -> Result<Vec<AppendResultEntry>, EsError> {
-        let session_manager = unsafe { &mut *self.session_manager.get() };
-        let session = session_manager.get_composite_session(target).await?;
-        let future = session.append(buf);
-        time::timeout(self.config.client_io_timeout(), future)
+        let buf = &buf;
+        self.retry_policy("append")
+            .run(|| async move {
+                let session_manager = unsafe { &mut *self.session_manager.get() };
+                let session = session_manager.get_composite_session(target).await?;
+                let future = session.append(buf.clone());
+                time::timeout(self.config.client_io_timeout(), future)
+                    .await
+                    .map_err(|_e| EsError::new(ErrorCode::RPC_TIMEOUT, "append rpc timeout"))?
+            })
             .await
-            .map_err(|_e| EsError::new(ErrorCode::RPC_TIMEOUT, "append rpc timeout"))?
     }
 
     /// Fetch data from a range replica.
     async fn fetch(&self, target: &str, request: FetchRequest) -> Result<FetchResultSet, EsError> {
-        let session_manager = unsafe { &mut *self.session_manager.get() };
-        let session = session_manager.get_composite_session(target).await?;
-        let future = session.fetch(request);
-        time::timeout(self.config.client_io_timeout(), future)
+        let request = &request;
+        self.retry_policy("fetch")
+            .run(|| async move {
+                let session_manager = unsafe { &mut *self.session_manager.get() };
+                let session = session_manager.get_composite_session(target).await?;
+                let future = session.fetch(request.clone());
+                time::timeout(self.config.client_io_timeout(), future)
+                    .await
+                    .map_err(|_e| EsError::new(ErrorCode::RPC_TIMEOUT, "fetch rpc timeout"))?
+            })
             .await
-            .map_err(|_e| EsError::new(ErrorCode::RPC_TIMEOUT, "fetch rpc timeout"))?
     }
 
     /// Report metrics to placement driver
@@ -374,11 +450,12 @@ impl Client for DefaultClient {
     async fn watch_resource(
         &self,
         types: &[ResourceType],
+        stream_id: Option<u64>,
         version: i64,
         timeout: Duration,
     ) -> Result<WatchResourceResult, EsError> {
         let composite_session = self.get_pd_session().await?;
-        let future = composite_session.watch_resource(types, version, timeout);
+        let future = composite_session.watch_resource(types, stream_id, version, timeout);
         // Use the watch timeout instead of client timeout, as this is a long polling request
         time::timeout(timeout, future)
             .await
@@ -403,13 +480,24 @@ impl Client for DefaultClient {
         stream_id: u64,
         replica_count: Option<u8>,
         ack_count: Option<u8>,
+        retention_period: Option<Duration>,
         epoch: Option<u64>,
     ) -> Result<StreamMetadata, EsError> {
-        let composite_session = self.get_pd_session().await?;
-        let future = composite_session.update_stream(stream_id, replica_count, ack_count, epoch);
-        time::timeout(self.config.client_io_timeout(), future)
+        self.retry_policy("update-stream")
+            .run(|| async move {
+                let composite_session = self.get_pd_session().await?;
+                let future = composite_session.update_stream(
+                    stream_id,
+                    replica_count,
+                    ack_count,
+                    retention_period,
+                    epoch,
+                );
+                time::timeout(self.config.client_io_timeout(), future)
+                    .await
+                    .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "update stream timeout"))?
+            })
             .await
-            .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "update stream timeout"))?
     }
 
     async fn trim_stream(
@@ -418,19 +506,104 @@ impl Client for DefaultClient {
         epoch: u64,
         min_offset: u64,
     ) -> Result<(), EsError> {
-        let composite_session = self.get_pd_session().await?;
-        let future = composite_session.trim_stream(stream_id, epoch, min_offset);
-        time::timeout(self.config.client_io_timeout(), future)
+        self.retry_policy("trim-stream")
+            .run(|| async move {
+                let composite_session = self.get_pd_session().await?;
+                let future = composite_session.trim_stream(stream_id, epoch, min_offset);
+                time::timeout(self.config.client_io_timeout(), future)
+                    .await
+                    .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "trim stream timeout"))?
+            })
             .await
-            .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "trim stream timeout"))?
     }
 
     async fn delete_stream(&self, stream_id: u64, epoch: u64) -> Result<(), EsError> {
-        let composite_session = self.get_pd_session().await?;
-        let future = composite_session.delete_stream(stream_id, epoch);
-        time::timeout(self.config.client_io_timeout(), future)
+        self.retry_policy("delete-stream")
+            .run(|| async move {
+                let composite_session = self.get_pd_session().await?;
+                let future = composite_session.delete_stream(stream_id, epoch);
+                time::timeout(self.config.client_io_timeout(), future)
+                    .await
+                    .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "delete stream timeout"))?
+            })
+            .await
+    }
+
+    async fn kv_range(
+        &self,
+        key: Bytes,
+        range_end: Option<Bytes>,
+        limit: i64,
+    ) -> Result<Vec<(Bytes, Bytes)>, EsError> {
+        self.retry_policy("kv-range")
+            .run(|| {
+                let key = key.clone();
+                let range_end = range_end.clone();
+                async move {
+                    let composite_session = self.get_pd_session().await?;
+                    let future = composite_session.kv_range(key, range_end, limit);
+                    time::timeout(self.config.client_io_timeout(), future)
+                        .await
+                        .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "kv range timeout"))?
+                }
+            })
+            .await
+    }
+
+    async fn kv_put(&self, key: Bytes, value: Bytes) -> Result<(), EsError> {
+        self.retry_policy("kv-put")
+            .run(|| {
+                let key = key.clone();
+                let value = value.clone();
+                async move {
+                    let composite_session = self.get_pd_session().await?;
+                    let future = composite_session.kv_put(key, value, None);
+                    time::timeout(self.config.client_io_timeout(), future)
+                        .await
+                        .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "kv put timeout"))?
+                }
+            })
+            .await
+    }
+
+    async fn kv_compare_and_put(
+        &self,
+        key: Bytes,
+        expected: Option<Bytes>,
+        value: Bytes,
+    ) -> Result<(), EsError> {
+        self.retry_policy("kv-put")
+            .run(|| {
+                let key = key.clone();
+                let expected = expected.clone();
+                let value = value.clone();
+                async move {
+                    let composite_session = self.get_pd_session().await?;
+                    let future = composite_session.kv_put(key, value, Some(expected));
+                    time::timeout(self.config.client_io_timeout(), future)
+                        .await
+                        .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "kv put timeout"))?
+                }
+            })
+            .await
+    }
+
+    async fn kv_delete_range(&self, key: Bytes, range_end: Option<Bytes>) -> Result<u64, EsError> {
+        self.retry_policy("kv-delete-range")
+            .run(|| {
+                let key = key.clone();
+                let range_end = range_end.clone();
+                async move {
+                    let composite_session = self.get_pd_session().await?;
+                    let future = composite_session.kv_delete_range(key, range_end);
+                    time::timeout(self.config.client_io_timeout(), future)
+                        .await
+                        .map_err(|_| {
+                            EsError::new(ErrorCode::RPC_TIMEOUT, "kv delete range timeout")
+                        })?
+                }
+            })
             .await
-            .map_err(|_| EsError::new(ErrorCode::RPC_TIMEOUT, "delete stream timeout"))?
     }
 }
 
@@ -441,9 +614,20 @@ impl DefaultClient {
         Self {
             session_manager,
             config,
+            retry_policies: RefCell::new(HashMap::new()),
         }
     }
 
+    /// Retry policy of the given operation, with per-operation overrides applied.
+    fn retry_policy(&self, operation: &'static str) -> Rc<RetryPolicy> {
+        Rc::clone(
+            self.retry_policies
+                .borrow_mut()
+                .entry(operation)
+                .or_insert_with(|| Rc::new(RetryPolicy::new(&self.config, operation))),
+        )
+    }
+
     async fn create_range0(
         &self,
         composite_session: Rc<CompositeSession>,
@@ -483,7 +667,9 @@ mod tests {
         sys::{DiskStatistics, MemoryStatistics},
         uring::UringStatistics,
     };
-    use protocol::rpc::header::{ClientRole, RangeServerState, ResourceType, SealKind, StreamT};
+    use protocol::rpc::header::{
+        ClientRole, ErrorCode, RangeServerState, ResourceType, SealKind, StreamT,
+    };
     use std::{error::Error, sync::Arc};
     use tokio::sync::broadcast;
 
@@ -957,6 +1143,7 @@ mod tests {
                         ResourceType::RESOURCE_OBJECT,
                     ]
                     .as_ref(),
+                    None,
                     version,
                     std::time::Duration::from_secs(100),
                 )
@@ -993,7 +1180,7 @@ mod tests {
             let client = DefaultClient::new(config, tx);
 
             let stream_metadata = client
-                .update_stream(1, None, None, Some(1))
+                .update_stream(1, None, None, None, Some(1))
                 .await
                 .expect("Update stream should not fail");
             assert_eq!(1, stream_metadata.stream_id);
@@ -1052,6 +1239,60 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_kv() -> Result<(), EsError> {
+        ulog::try_init_log();
+        tokio_uring::start(async move {
+            let port = run_listener().await;
+            let config = config::Configuration {
+                placement_driver: format!("127.0.0.1:{}", port),
+                ..Default::default()
+            };
+            let config = Arc::new(config);
+            let (tx, _rx) = broadcast::channel(1);
+            let client = DefaultClient::new(config, tx);
+
+            for key in ["txn/a", "txn/b", "z"] {
+                client
+                    .kv_put(Bytes::from(key), Bytes::from_static(b"v"))
+                    .await?;
+            }
+            let kvs = client.kv_range(Bytes::from("txn/a"), None, 0).await?;
+            assert_eq!(vec![(Bytes::from("txn/a"), Bytes::from_static(b"v"))], kvs);
+            let kvs = client
+                .kv_range(Bytes::from("txn/"), Some(Bytes::from("txn0")), 0)
+                .await?;
+            assert_eq!(2, kvs.len());
+            let deleted = client
+                .kv_delete_range(Bytes::from("txn/"), Some(Bytes::from("txn0")))
+                .await?;
+            assert_eq!(2, deleted);
+            assert!(client
+                .kv_range(Bytes::from("txn/a"), None, 0)
+                .await?
+                .is_empty());
+
+            client
+                .kv_compare_and_put(Bytes::from("txn/c"), None, Bytes::from_static(b"1"))
+                .await?;
+            let e = client
+                .kv_compare_and_put(Bytes::from("txn/c"), None, Bytes::from_static(b"2"))
+                .await
+                .unwrap_err();
+            assert_eq!(ErrorCode::PRECONDITION_FAILED, e.code);
+            client
+                .kv_compare_and_put(
+                    Bytes::from("txn/c"),
+                    Some(Bytes::from_static(b"1")),
+                    Bytes::from_static(b"2"),
+                )
+                .await?;
+            let kvs = client.kv_range(Bytes::from("txn/c"), None, 0).await?;
+            assert_eq!(vec![(Bytes::from("txn/c"), Bytes::from_static(b"2"))], kvs);
+            Ok(())
+        })
+    }
+
     fn check_range_server(resource: &Resource) {
         match resource {
             Resource::RangeServer(range_server) => {
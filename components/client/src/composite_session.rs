@@ -34,22 +34,30 @@ use protocol::rpc::header::{OperationCode, SealKind};
 use rustc_hash::FxHashMap;
 use std::fmt::Display;
 use std::{
-    cell::RefCell,
-    net::{SocketAddr, ToSocketAddrs},
+    cell::{Cell, RefCell},
+    net::ToSocketAddrs,
     rc::Rc,
     sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::sync::broadcast;
 use tokio::time;
+use transport::{Address, UNIX_SCHEME};
 
 pub(crate) struct CompositeSession {
     target: String,
     config: Arc<Configuration>,
     lb_policy: LbPolicy,
-    sessions: Rc<RefCell<FxHashMap<SocketAddr, Session>>>,
+
+    /// Whether the target is the placement driver cluster, whose members are discovered by describing
+    /// the cluster.
+    placement_driver: bool,
+    sessions: Rc<RefCell<FxHashMap<Address, Session>>>,
     shutdown: broadcast::Sender<()>,
     refresh_cluster_instant: RefCell<Instant>,
+
+    /// Cursor of `LbPolicy::RoundRobin`.
+    round_robin: Cell<usize>,
 }
 
 impl CompositeSession {
@@ -57,6 +65,7 @@ impl CompositeSession {
         target: T,
         config: Arc<Configuration>,
         lb_policy: LbPolicy,
+        placement_driver: bool,
         shutdown: broadcast::Sender<()>,
     ) -> Result<Self, EsError>
     where
@@ -64,38 +73,30 @@ impl CompositeSession {
     {
         let sessions = Rc::new(RefCell::new(FxHashMap::default()));
 
-        let addrs = target.to_socket_addrs().map_err(|e| {
-            EsError::new(
-                ErrorCode::BAD_ADDRESS,
-                &format!("Failed to resolve {}: {:?}", target, e),
-            )
-        })?;
-
-        match lb_policy {
-            LbPolicy::LeaderOnly => {
-                for addr in addrs {
-                    let session = Session::new(addr, &config, shutdown.clone());
-                    if let Err(e) = session.connect().await {
-                        warn!("Failed to connect to {}: {}", addr, e);
-                        continue;
-                    }
+        let addrs = Self::resolve(&target)?;
 
-                    if Self::refresh_pd_cluster(&session, &config, &sessions, shutdown.clone())
-                        .await
-                    {
-                        break;
-                    }
+        // Sessions to placement driver nodes are discovered by describing the cluster, regardless of the
+        // load-balancing policy.
+        if lb_policy == LbPolicy::LeaderOnly || placement_driver {
+            for addr in addrs {
+                let session = Session::new(addr.clone(), &config, shutdown.clone());
+                if let Err(e) = session.connect().await {
+                    warn!("Failed to connect to {}: {}", addr, e);
+                    continue;
                 }
 
-                if sessions.borrow().is_empty() {
-                    error!("Failed to describe placement driver cluster. No session is created");
+                if Self::refresh_pd_cluster(&session, &config, &sessions, shutdown.clone()).await {
+                    break;
                 }
             }
-            LbPolicy::PickFirst => {
-                for addr in addrs {
-                    let session = Session::new(addr, &config, shutdown.clone());
-                    sessions.borrow_mut().insert(addr, session);
-                }
+
+            if sessions.borrow().is_empty() {
+                error!("Failed to describe placement driver cluster. No session is created");
+            }
+        } else {
+            for addr in addrs {
+                let session = Session::new(addr.clone(), &config, shutdown.clone());
+                sessions.borrow_mut().insert(addr, session);
             }
         }
 
@@ -103,16 +104,39 @@ impl CompositeSession {
             target: target.to_string(),
             config,
             lb_policy,
+            placement_driver,
             sessions,
             shutdown,
             refresh_cluster_instant: RefCell::new(Instant::now()),
+            round_robin: Cell::new(0),
+        })
+    }
+
+    /// Resolve the target into addresses, which are either `host:port` or `unix://path` of Unix domain socket.
+    fn resolve<T>(target: &T) -> Result<Vec<Address>, EsError>
+    where
+        T: ToSocketAddrs + Display,
+    {
+        let name = target.to_string();
+        let addrs = if name.starts_with(UNIX_SCHEME) {
+            Address::resolve(&name)
+        } else {
+            target
+                .to_socket_addrs()
+                .map(|addrs| addrs.map(Address::from).collect())
+        };
+        addrs.map_err(|e| {
+            EsError::new(
+                ErrorCode::BAD_ADDRESS,
+                &format!("Failed to resolve {}: {:?}", target, e),
+            )
         })
     }
 
     async fn refresh_pd_cluster(
         session: &Session,
         config: &Arc<Configuration>,
-        sessions: &Rc<RefCell<FxHashMap<SocketAddr, Session>>>,
+        sessions: &Rc<RefCell<FxHashMap<Address, Session>>>,
         shutdown: broadcast::Sender<()>,
     ) -> bool {
         match Self::describe_pd_cluster0(session, config).await {
@@ -126,7 +150,7 @@ impl CompositeSession {
                         match resolved {
                             Ok(socket_addrs) => {
                                 for addr in socket_addrs {
-                                    found.insert(addr, node.leader);
+                                    found.insert(Address::from(addr), node.leader);
                                 }
                             }
                             Err(e) => {
@@ -156,7 +180,7 @@ impl CompositeSession {
                     // Add new PD nodes
                     found.retain(|k, _v| !sessions.borrow().contains_key(k));
                     for (addr, leader) in found {
-                        let session = Session::new(addr, config, shutdown.clone());
+                        let session = Session::new(addr.clone(), config, shutdown.clone());
                         let role = if leader {
                             NodeRole::Leader
                         } else {
@@ -184,7 +208,7 @@ impl CompositeSession {
     /// `true` - if the interval has elapsed or the cluster has only one node;
     /// `false` - otherwise
     fn need_refresh_placement_driver_cluster(&self) -> bool {
-        if self.target != self.config.placement_driver {
+        if !self.placement_driver {
             return false;
         }
 
@@ -214,6 +238,7 @@ impl CompositeSession {
                 .to_socket_addrs()
                 .into_iter()
                 .flatten()
+                .map(Address::from)
                 .for_each(|addr| {
                     if let Some((_, session)) = self
                         .sessions
@@ -275,8 +300,8 @@ impl CompositeSession {
                 error!("Failed to resolve PD cluster advertise addresses: {}", e);
                 EsError::new(ErrorCode::BAD_ADDRESS, "Invalid PD advertise address")
             })?;
-            for addr in iter {
-                let session = Session::new(addr, &self.config, self.shutdown.clone());
+            for addr in iter.map(Address::from) {
+                let session = Session::new(addr.clone(), &self.config, self.shutdown.clone());
                 if let Err(e) = session.connect().await {
                     error!("Failed to connect {}: {}", addr, e);
                     continue;
@@ -329,30 +354,77 @@ impl CompositeSession {
                     }
                 }
             }
-            LbPolicy::PickFirst => {
-                // Pick the first session that is active
-                let session = self.pick_active_session();
-                if session.is_none() {
-                    let sessions = self
-                        .sessions
-                        .borrow()
+            LbPolicy::PickFirst => self.pick_first_session().await,
+            LbPolicy::RoundRobin | LbPolicy::LeastOutstandingRequests | LbPolicy::LatencyEwma => {
+                let mut candidates = self
+                    .sessions
+                    .borrow()
+                    .iter()
+                    .filter(|(_, session)| session.active() && !session.going_away())
+                    .map(|(addr, session)| (addr.clone(), session.clone()))
+                    .collect::<Vec<_>>();
+
+                if candidates.is_empty() {
+                    // No session is active, fall back to connect the first reachable one.
+                    return self.pick_first_session().await;
+                }
+
+                // Sort by address such that the round-robin cursor is stable across calls.
+                candidates.sort_by(|(a, _), (b, _)| a.cmp(b));
+                let index = match lb_policy {
+                    LbPolicy::RoundRobin => {
+                        let cursor = self.round_robin.get();
+                        self.round_robin.set(cursor.wrapping_add(1));
+                        cursor % candidates.len()
+                    }
+                    LbPolicy::LeastOutstandingRequests => candidates
                         .iter()
-                        .map(|(addr, session)| (*addr, session.clone()))
-                        .collect::<FxHashMap<_, _>>();
-                    for (addr, session) in sessions.into_iter() {
-                        match session.connect().await {
-                            Ok(()) => {
-                                return Some(session);
-                            }
-                            Err(e) => {
-                                warn!("Failed to connect to {addr}: {e}");
-                            }
-                        }
+                        .enumerate()
+                        .min_by_key(|(_, (_, session))| session.inflight())
+                        .map(|(index, _)| index)
+                        .unwrap_or_default(),
+                    _ => candidates
+                        .iter()
+                        .enumerate()
+                        .min_by(|(_, (_, a)), (_, (_, b))| {
+                            a.latency_cost().total_cmp(&b.latency_cost())
+                        })
+                        .map(|(index, _)| index)
+                        .unwrap_or_default(),
+                };
+                let (addr, session) = candidates.swap_remove(index);
+                trace!(
+                    "Picked session to {addr} by {lb_policy:?}, inflight={}, latency={:?}",
+                    session.inflight(),
+                    session.latency()
+                );
+                Some(session)
+            }
+        }
+    }
+
+    async fn pick_first_session(&self) -> Option<Session> {
+        // Pick the first session that is active
+        let session = self.pick_active_session();
+        if session.is_none() {
+            let sessions = self
+                .sessions
+                .borrow()
+                .iter()
+                .map(|(addr, session)| (addr.clone(), session.clone()))
+                .collect::<FxHashMap<_, _>>();
+            for (addr, session) in sessions.into_iter() {
+                match session.connect().await {
+                    Ok(()) => {
+                        return Some(session);
+                    }
+                    Err(e) => {
+                        warn!("Failed to connect to {addr}: {e}");
                     }
                 }
-                session
             }
         }
+        session
     }
 
     fn pick_active_session(&self) -> Option<Session> {
@@ -717,12 +789,14 @@ impl CompositeSession {
         if let Some(response::Headers::Fetch {
             throttle,
             object_metadata_list,
+            committed,
         }) = response.headers
         {
             Ok(FetchResultSet {
                 throttle,
                 payload: response.payload,
                 object_metadata_list,
+                committed,
             })
         } else {
             Err(EsError::new(
@@ -892,6 +966,7 @@ impl CompositeSession {
     pub async fn watch_resource(
         &self,
         types: &[ResourceType],
+        stream_id: Option<u64>,
         version: i64,
         timeout: Duration,
     ) -> Result<WatchResourceResult, EsError> {
@@ -899,6 +974,7 @@ impl CompositeSession {
             timeout,
             headers: request::Headers::WatchResource {
                 resource_type: types.to_owned(),
+                stream_id,
                 version,
             },
             body: None,
@@ -926,6 +1002,7 @@ impl CompositeSession {
         stream_id: u64,
         replica_count: Option<u8>,
         ack_count: Option<u8>,
+        retention_period: Option<Duration>,
         epoch: Option<u64>,
     ) -> Result<StreamMetadata, EsError> {
         let request = request::Request {
@@ -934,6 +1011,7 @@ impl CompositeSession {
                 stream_id,
                 replica_count,
                 ack_count,
+                retention_period,
                 epoch,
             },
             body: None,
@@ -986,6 +1064,76 @@ impl CompositeSession {
         }
     }
 
+    pub(crate) async fn kv_range(
+        &self,
+        key: Bytes,
+        range_end: Option<Bytes>,
+        limit: i64,
+    ) -> Result<Vec<(Bytes, Bytes)>, EsError> {
+        let request = request::Request {
+            timeout: self.config.client_io_timeout(),
+            headers: request::Headers::KvRange {
+                key,
+                range_end,
+                limit,
+            },
+            body: None,
+        };
+        let response = self.request(request).await?;
+        if response.ok() {
+            match response.headers {
+                Some(response::Headers::KvRange { kvs }) => Ok(kvs),
+                _ => unreachable!(),
+            }
+        } else {
+            Err(EsError::from(&response))
+        }
+    }
+
+    pub(crate) async fn kv_put(
+        &self,
+        key: Bytes,
+        value: Bytes,
+        expected: Option<Option<Bytes>>,
+    ) -> Result<(), EsError> {
+        let request = request::Request {
+            timeout: self.config.client_io_timeout(),
+            headers: request::Headers::KvPut {
+                key,
+                value,
+                expected,
+            },
+            body: None,
+        };
+        let response = self.request(request).await?;
+        if response.ok() {
+            Ok(())
+        } else {
+            Err(EsError::from(&response))
+        }
+    }
+
+    pub(crate) async fn kv_delete_range(
+        &self,
+        key: Bytes,
+        range_end: Option<Bytes>,
+    ) -> Result<u64, EsError> {
+        let request = request::Request {
+            timeout: self.config.client_io_timeout(),
+            headers: request::Headers::KvDeleteRange { key, range_end },
+            body: None,
+        };
+        let response = self.request(request).await?;
+        if response.ok() {
+            match response.headers {
+                Some(response::Headers::KvDeleteRange { deleted }) => Ok(deleted),
+                _ => unreachable!(),
+            }
+        } else {
+            Err(EsError::from(&response))
+        }
+    }
+
     async fn broadcast_to_pd(
         &self,
         request: &Request,
@@ -1014,8 +1162,9 @@ impl CompositeSession {
     }
 
     async fn request(&self, request: Request) -> Result<Response, EsError> {
+        let mut lb_policy = self.lb_policy;
         loop {
-            let session = self.pick_session(self.lb_policy).await.ok_or(EsError::new(
+            let session = self.pick_session(lb_policy).await.ok_or(EsError::new(
                 ErrorCode::CONNECT_FAIL,
                 &format!("{:?}", self.target),
             ))?;
@@ -1040,7 +1189,9 @@ impl CompositeSession {
                 && ErrorCode::PD_NOT_LEADER == response.status.code
                 && self.refresh_leadership_on_demand(&response.status).await
             {
-                // Retry after refresh leadership
+                // Retry against the leader after refresh leadership, as the request may be served by a follower
+                // under other load-balancing policies.
+                lb_policy = LbPolicy::LeaderOnly;
                 continue;
             }
             return Ok(response);
@@ -1060,10 +1211,13 @@ impl CompositeSession {
 #[cfg(test)]
 mod tests {
     use super::CompositeSession;
-    use crate::lb_policy::LbPolicy;
+    use crate::{lb_policy::LbPolicy, request, session::Session};
+    use local_sync::oneshot;
     use mock_server::run_listener;
-    use std::{error::Error, sync::Arc};
+    use protocol::rpc::header::ClientRole;
+    use std::{error::Error, rc::Rc, sync::Arc, time::Duration};
     use tokio::sync::broadcast;
+    use transport::Address;
 
     #[test]
     fn test_new() -> Result<(), Box<dyn Error>> {
@@ -1073,9 +1227,108 @@ mod tests {
             let target = format!("{}:{}", "localhost", port);
             let (shutdown_tx, _shutdown_rx) = broadcast::channel(1);
             let _session =
-                CompositeSession::new(&target, config, LbPolicy::PickFirst, shutdown_tx).await?;
+                CompositeSession::new(&target, config, LbPolicy::PickFirst, false, shutdown_tx)
+                    .await?;
 
             Ok(())
         })
     }
+
+    /// Create a composite session of two connected sessions, each to a range server.
+    async fn new_composite_session(
+        lb_policy: LbPolicy,
+    ) -> Result<CompositeSession, Box<dyn Error>> {
+        let config = Arc::new(config::Configuration::default());
+        let (shutdown_tx, _shutdown_rx) = broadcast::channel(1);
+        let target = format!("127.0.0.1:{}", run_listener().await);
+        let composite_session = CompositeSession::new(
+            &target,
+            Arc::clone(&config),
+            lb_policy,
+            false,
+            shutdown_tx.clone(),
+        )
+        .await?;
+        let addr: Address = format!("127.0.0.1:{}", run_listener().await).parse()?;
+        let session = Session::new(addr.clone(), &config, shutdown_tx);
+        composite_session
+            .sessions
+            .borrow_mut()
+            .insert(addr, session);
+
+        let sessions = composite_session
+            .sessions
+            .borrow()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(2, sessions.len());
+        for session in sessions {
+            session.connect().await?;
+        }
+        Ok(composite_session)
+    }
+
+    fn same(a: &Session, b: &Session) -> bool {
+        Rc::ptr_eq(&a.connection, &b.connection)
+    }
+
+    #[test]
+    fn test_round_robin() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async {
+            let composite_session = new_composite_session(LbPolicy::RoundRobin).await?;
+            let mut picked = vec![];
+            for _ in 0..4 {
+                picked.push(
+                    composite_session
+                        .pick_session(LbPolicy::RoundRobin)
+                        .await
+                        .ok_or("No session is picked")?,
+                );
+            }
+            assert!(!same(&picked[0], &picked[1]));
+            assert!(same(&picked[0], &picked[2]));
+            assert!(same(&picked[1], &picked[3]));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_least_outstanding_requests() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async {
+            let lb_policy = LbPolicy::LeastOutstandingRequests;
+            let composite_session = new_composite_session(lb_policy).await?;
+            let busy = composite_session
+                .pick_session(lb_policy)
+                .await
+                .ok_or("No session is picked")?;
+
+            // Mock server responds heartbeats after 500ms, till when the heartbeat remains outstanding.
+            let request = request::Request {
+                timeout: Duration::from_secs(3),
+                headers: request::Headers::Heartbeat {
+                    client_id: "test".to_owned(),
+                    role: ClientRole::CLIENT_ROLE_FRONTEND,
+                    range_server: None,
+                },
+                body: None,
+            };
+            let (tx, _rx) = oneshot::channel();
+            busy.write(request, tx)
+                .await
+                .map_err(|_| "Failed to write heartbeat")?;
+            assert_eq!(1, busy.inflight());
+
+            for _ in 0..3 {
+                let session = composite_session
+                    .pick_session(lb_policy)
+                    .await
+                    .ok_or("No session is picked")?;
+                assert!(!same(&busy, &session));
+            }
+            // Sessions are picked before the heartbeat is responded.
+            assert_eq!(1, busy.inflight());
+            Ok(())
+        })
+    }
 }
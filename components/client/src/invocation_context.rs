@@ -1,19 +1,24 @@
-use std::{cell::OnceCell, net::SocketAddr};
+use std::{
+    cell::OnceCell,
+    time::{Duration, Instant},
+};
 
 use crate::{request, response};
 use local_sync::oneshot;
 use log::error;
+use transport::Address;
 
 #[derive(Debug)]
 pub struct InvocationContext {
-    target: SocketAddr,
+    target: Address,
     request: request::Request,
     pub(crate) response_observer: OnceCell<oneshot::Sender<response::Response>>,
+    created_at: Instant,
 }
 
 impl InvocationContext {
     pub(crate) fn new(
-        target: SocketAddr,
+        target: Address,
         request: request::Request,
         response_observer: oneshot::Sender<response::Response>,
     ) -> Self {
@@ -23,17 +28,26 @@ impl InvocationContext {
             target,
             request,
             response_observer: cell,
+            created_at: Instant::now(),
         }
     }
 
-    pub(crate) fn target(&self) -> SocketAddr {
-        self.target
+    pub(crate) fn target(&self) -> &Address {
+        &self.target
     }
 
     pub(crate) fn request(&self) -> &request::Request {
         &self.request
     }
 
+    /// Time elapsed since the request is written to the connection.
+    ///
+    /// Contexts are created once the request is no longer delayed by throttling, so the elapsed time
+    /// measures the round trip only, as sampled by latency-aware load-balancing policies.
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.created_at.elapsed()
+    }
+
     pub(crate) fn is_closed(&self) -> bool {
         self.response_observer
             .get()
@@ -66,7 +80,7 @@ mod tests {
 
     #[test]
     fn test_invocation_new() -> Result<(), Box<dyn Error>> {
-        let target = "127.0.0.1:80".parse()?;
+        let target: transport::Address = "127.0.0.1:80".parse()?;
         let (tx, mut rx) = oneshot::channel();
         let request = crate::request::Request {
             timeout: Duration::from_millis(1),
@@ -75,8 +89,9 @@ mod tests {
         };
 
         let mut ctx = super::InvocationContext::new(target, request, tx);
-        assert_eq!(ctx.target().ip(), IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
-        assert_eq!(ctx.target().port(), 80);
+        let socket_addr = ctx.target().socket_addr().unwrap();
+        assert_eq!(socket_addr.ip(), IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
+        assert_eq!(socket_addr.port(), 80);
         assert!(!ctx.is_closed());
 
         let observer = ctx.response_observer();
@@ -1,6 +1,58 @@
+use std::time::Duration;
+
 /// Load-balancing policy among sessions within `CompositeSession`.
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
-pub(crate) enum LbPolicy {
-    PickFirst,
-    LeaderOnly,
+pub(crate) use config::LbPolicy;
+
+/// Smoothing factor of latency EWMA. The larger, the more weight on recent samples.
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Exponentially weighted moving average of request latency of a session.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct LatencyEwma {
+    /// Average latency in microseconds, `None` if no sample is observed yet.
+    value: Option<f64>,
+}
+
+impl LatencyEwma {
+    pub(crate) fn observe(&mut self, latency: Duration) {
+        let sample = latency.as_micros() as f64;
+        self.value = Some(match self.value {
+            Some(value) => value + EWMA_ALPHA * (sample - value),
+            None => sample,
+        });
+    }
+
+    pub(crate) fn get(&self) -> Option<Duration> {
+        self.value.map(|value| Duration::from_micros(value as u64))
+    }
+
+    /// Cost of routing a request to the session, which is the average latency weighted by in-flight requests.
+    ///
+    /// Sessions without any latency sample cost zero, such that they get probed first.
+    pub(crate) fn cost(&self, inflight: usize) -> f64 {
+        self.value.unwrap_or(0.0) * (inflight + 1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::LatencyEwma;
+
+    #[test]
+    fn test_latency_ewma() {
+        let mut ewma = LatencyEwma::default();
+        assert_eq!(None, ewma.get());
+        assert_eq!(0.0, ewma.cost(10));
+
+        ewma.observe(Duration::from_millis(10));
+        assert_eq!(Some(Duration::from_millis(10)), ewma.get());
+        assert_eq!(20_000.0, ewma.cost(1));
+
+        // Moves towards, but does not jump to, the latest sample.
+        ewma.observe(Duration::from_millis(20));
+        let average = ewma.get().unwrap();
+        assert!(average > Duration::from_millis(12) && average < Duration::from_millis(14));
+    }
 }
@@ -23,6 +23,7 @@ pub(crate) mod lb_policy;
 mod naming;
 pub mod request;
 pub mod response;
+pub(crate) mod retry_policy;
 pub(crate) mod role;
 mod session;
 mod session_manager;
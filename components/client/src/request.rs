@@ -6,11 +6,12 @@ use model::{
 };
 use protocol::rpc::header::{
     AppendRequestT, ClientRole, CommitObjectRequestT, CreateRangeRequestT, CreateStreamRequestT,
-    DeleteStreamRequestT, DescribePlacementDriverClusterRequestT, DescribeStreamRequestT,
-    FetchRequestT, HeartbeatRequestT, IdAllocationRequestT, ListRangeCriteriaT, ListRangeRequestT,
-    ListResourceRequestT, ObjT, RangeProgressT, RangeServerMetricsT, RangeT, ReportMetricsRequestT,
-    ReportRangeProgressRequestT, ResourceType, SealKind, SealRangeRequestT, StreamT,
-    TrimStreamRequestT, UpdateStreamRequestT, WatchResourceRequestT,
+    DeleteRangeRequestT, DeleteStreamRequestT, DescribePlacementDriverClusterRequestT,
+    DescribeStreamRequestT, FetchRequestT, HeartbeatRequestT, IdAllocationRequestT,
+    ListRangeCriteriaT, ListRangeRequestT, ListResourceRequestT, ObjT, PutRequestT, RangeProgressT,
+    RangeRequestT, RangeServerMetricsT, RangeT, ReportMetricsRequestT, ReportRangeProgressRequestT,
+    ResourceType, SealKind, SealRangeRequestT, StreamT, TrimStreamRequestT, UpdateStreamRequestT,
+    WatchResourceRequestT,
 };
 use std::fmt;
 use std::time::Duration;
@@ -107,6 +108,7 @@ pub enum Headers {
 
     WatchResource {
         resource_type: Vec<ResourceType>,
+        stream_id: Option<u64>,
         version: i64,
     },
 
@@ -114,6 +116,7 @@ pub enum Headers {
         stream_id: u64,
         replica_count: Option<u8>,
         ack_count: Option<u8>,
+        retention_period: Option<Duration>,
         epoch: Option<u64>,
     },
 
@@ -127,6 +130,26 @@ pub enum Headers {
         stream_id: u64,
         epoch: u64,
     },
+
+    KvRange {
+        key: Bytes,
+        range_end: Option<Bytes>,
+        limit: i64,
+    },
+
+    KvPut {
+        key: Bytes,
+        value: Bytes,
+
+        /// If set, the put is applied only if the key holds the inner value, or does not exist if it
+        /// is `None`.
+        expected: Option<Option<Bytes>>,
+    },
+
+    KvDeleteRange {
+        key: Bytes,
+        range_end: Option<Bytes>,
+    },
 }
 
 impl From<&Request> for Bytes {
@@ -309,11 +332,13 @@ impl From<&Request> for Bytes {
             }
             Headers::WatchResource {
                 resource_type,
+                stream_id,
                 version,
             } => {
                 let mut request = WatchResourceRequestT::default();
                 request.timeout_ms = req.timeout.as_millis() as i32;
                 request.resource_type = resource_type.clone();
+                request.stream_id = stream_id.map_or(-1, |id| id as i64);
                 request.resource_version = *version;
                 let request = request.pack(&mut builder);
                 builder.finish(request, None);
@@ -322,6 +347,7 @@ impl From<&Request> for Bytes {
                 stream_id,
                 replica_count,
                 ack_count,
+                retention_period,
                 epoch,
             } => {
                 let mut request = UpdateStreamRequestT::default();
@@ -330,6 +356,7 @@ impl From<&Request> for Bytes {
                 stream.stream_id = *stream_id as i64;
                 replica_count.map(|c| stream.replica = c as i8);
                 ack_count.map(|c| stream.ack_count = c as i8);
+                retention_period.map(|r| stream.retention_period_ms = r.as_millis() as i64);
                 epoch.map(|c| stream.epoch = c as i64);
                 request.stream = Box::new(stream);
                 let request = request.pack(&mut builder);
@@ -363,6 +390,46 @@ impl From<&Request> for Bytes {
                 let request = request.pack(&mut builder);
                 builder.finish(request, None);
             }
+
+            Headers::KvRange {
+                key,
+                range_end,
+                limit,
+            } => {
+                let mut request = RangeRequestT::default();
+                request.timeout_ms = req.timeout.as_millis() as i32;
+                request.key = Some(key.to_vec());
+                request.range_end = range_end.as_ref().map(|end| end.to_vec());
+                request.limit = *limit;
+                let request = request.pack(&mut builder);
+                builder.finish(request, None);
+            }
+
+            Headers::KvPut {
+                key,
+                value,
+                expected,
+            } => {
+                let mut request = PutRequestT::default();
+                request.timeout_ms = req.timeout.as_millis() as i32;
+                request.key = Some(key.to_vec());
+                request.value = Some(value.to_vec());
+                if let Some(expected) = expected {
+                    request.compare = true;
+                    request.expected_value = expected.as_ref().map(|value| value.to_vec());
+                }
+                let request = request.pack(&mut builder);
+                builder.finish(request, None);
+            }
+
+            Headers::KvDeleteRange { key, range_end } => {
+                let mut request = DeleteRangeRequestT::default();
+                request.timeout_ms = req.timeout.as_millis() as i32;
+                request.key = Some(key.to_vec());
+                request.range_end = range_end.as_ref().map(|end| end.to_vec());
+                let request = request.pack(&mut builder);
+                builder.finish(request, None);
+            }
         };
         let buf = builder.finished_data();
         let mut buffer = BytesMut::with_capacity(buf.len());
@@ -370,3 +437,46 @@ impl From<&Request> for Bytes {
         buffer.freeze()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use protocol::rpc::header::UpdateStreamRequest;
+
+    use super::{Headers, Request};
+
+    fn update_stream(retention_period: Option<Duration>) -> Bytes {
+        let request = Request {
+            timeout: Duration::from_secs(3),
+            headers: Headers::UpdateStream {
+                stream_id: 1,
+                replica_count: None,
+                ack_count: Some(2),
+                retention_period,
+                epoch: None,
+            },
+            body: None,
+        };
+        Into::into(&request)
+    }
+
+    #[test]
+    fn test_update_stream_retention() {
+        let buf = update_stream(Some(Duration::from_secs(3600)));
+        let request = flatbuffers::root::<UpdateStreamRequest>(&buf).unwrap();
+        let stream = request.stream();
+        assert_eq!(3000, request.timeout_ms());
+        assert_eq!(1, stream.stream_id());
+        assert_eq!(3_600_000, stream.retention_period_ms());
+        assert_eq!(2, stream.ack_count());
+        // Fields of `None` are sent as defaults, which placement driver leaves unchanged.
+        assert_eq!(-1, stream.replica());
+        assert_eq!(-1, stream.epoch());
+
+        let buf = update_stream(None);
+        let request = flatbuffers::root::<UpdateStreamRequest>(&buf).unwrap();
+        assert_eq!(-1, request.stream().retention_period_ms());
+    }
+}
@@ -16,6 +16,7 @@ use protocol::rpc::header::AppendResponse;
 use protocol::rpc::header::CommitObjectResponse;
 use protocol::rpc::header::CreateRangeResponse;
 use protocol::rpc::header::CreateStreamResponse;
+use protocol::rpc::header::DeleteRangeResponse;
 use protocol::rpc::header::DeleteStreamResponse;
 use protocol::rpc::header::DescribePlacementDriverClusterResponse;
 use protocol::rpc::header::DescribeStreamResponse;
@@ -26,6 +27,8 @@ use protocol::rpc::header::IdAllocationResponse;
 use protocol::rpc::header::ListRangeResponse;
 use protocol::rpc::header::ListResourceResponse;
 use protocol::rpc::header::OperationCode;
+use protocol::rpc::header::PutResponse;
+use protocol::rpc::header::RangeResponse;
 use protocol::rpc::header::ReportMetricsResponse;
 use protocol::rpc::header::ReportRangeProgressResponse;
 use protocol::rpc::header::SealRangeResponse;
@@ -52,6 +55,9 @@ pub struct Response {
     pub headers: Option<Headers>,
 
     pub payload: Option<Bytes>,
+
+    /// Duration the client should back off before sending subsequent requests, due to quota violation.
+    pub throttle: Option<std::time::Duration>,
 }
 
 impl From<&Response> for EsError {
@@ -93,6 +99,7 @@ pub enum Headers {
     Fetch {
         throttle: Option<std::time::Duration>,
         object_metadata_list: Option<Vec<ObjectMetadata>>,
+        committed: Option<u64>,
     },
 
     CreateRange {
@@ -113,6 +120,23 @@ pub enum Headers {
     UpdateStream {
         metadata: StreamMetadata,
     },
+
+    KvRange {
+        kvs: Vec<(Bytes, Bytes)>,
+    },
+
+    KvDeleteRange {
+        deleted: u64,
+    },
+}
+
+/// Convert `throttle_time_ms` of responses, where negative values mean not applicable.
+fn throttle_of(throttle_time_ms: i32) -> Option<std::time::Duration> {
+    if throttle_time_ms < 0 {
+        None
+    } else {
+        Some(std::time::Duration::from_millis(throttle_time_ms as u64))
+    }
 }
 
 impl Response {
@@ -122,6 +146,7 @@ impl Response {
             status: Status::decode(),
             headers: None,
             payload: None,
+            throttle: None,
         }
     }
 
@@ -214,6 +239,7 @@ impl Response {
             match flatbuffers::root::<AppendResponse>(buf) {
                 Ok(response) => {
                     let response = response.unpack();
+                    self.throttle = throttle_of(response.throttle_time_ms);
                     if response.status.code != ErrorCode::OK {
                         self.status = response.status.as_ref().into();
                         return;
@@ -243,18 +269,13 @@ impl Response {
             match flatbuffers::root::<FetchResponse>(buf) {
                 Ok(response) => {
                     let response = response.unpack();
+                    let throttle = throttle_of(response.throttle_time_ms);
+                    self.throttle = throttle;
                     if response.status.code != ErrorCode::OK {
                         self.status = response.status.as_ref().into();
                         return;
                     }
                     self.status = Status::ok();
-                    let throttle = if response.throttle_time_ms < 0 {
-                        None
-                    } else {
-                        Some(std::time::Duration::from_millis(
-                            response.throttle_time_ms as u64,
-                        ))
-                    };
                     let object_metadata_list = response.object_metadata_list.map(|items| {
                         items
                             .iter()
@@ -264,6 +285,7 @@ impl Response {
                     self.headers = Some(Headers::Fetch {
                         throttle,
                         object_metadata_list,
+                        committed: u64::try_from(response.committed).ok(),
                     });
                     self.payload = frame.get_response_payload();
                 }
@@ -573,4 +595,68 @@ impl Response {
             }
         }
     }
+
+    pub fn on_kv_range(&mut self, frame: &Frame) {
+        if let Some(buf) = frame.header.as_ref() {
+            match flatbuffers::root::<RangeResponse>(buf) {
+                Ok(response) => {
+                    self.status = Into::<Status>::into(&response.status().unpack());
+                    if self.status.code == ErrorCode::OK {
+                        let kvs = response
+                            .kvs()
+                            .map(|kvs| {
+                                kvs.iter()
+                                    .map(|kv| {
+                                        (
+                                            kv.key().map_or_else(Bytes::new, |key| {
+                                                Bytes::copy_from_slice(key.bytes())
+                                            }),
+                                            kv.value().map_or_else(Bytes::new, |value| {
+                                                Bytes::copy_from_slice(value.bytes())
+                                            }),
+                                        )
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        self.headers = Some(Headers::KvRange { kvs });
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to parse the response header: {:?}", e);
+                }
+            }
+        }
+    }
+
+    pub fn on_kv_put(&mut self, frame: &Frame) {
+        if let Some(buf) = frame.header.as_ref() {
+            match flatbuffers::root::<PutResponse>(buf) {
+                Ok(response) => {
+                    self.status = Into::<Status>::into(&response.status().unpack());
+                }
+                Err(e) => {
+                    error!("Failed to parse the response header: {:?}", e);
+                }
+            }
+        }
+    }
+
+    pub fn on_kv_delete_range(&mut self, frame: &Frame) {
+        if let Some(buf) = frame.header.as_ref() {
+            match flatbuffers::root::<DeleteRangeResponse>(buf) {
+                Ok(response) => {
+                    self.status = Into::<Status>::into(&response.status().unpack());
+                    if self.status.code == ErrorCode::OK {
+                        self.headers = Some(Headers::KvDeleteRange {
+                            deleted: response.deleted() as u64,
+                        });
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to parse the response header: {:?}", e);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,232 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use model::error::EsError;
+use protocol::rpc::header::ErrorCode;
+use rand::Rng;
+
+/// Retry policy of an operation, with exponential backoff and jitter.
+///
+/// Failed attempts are retried only if the error code is retryable and not explicitly non-retryable,
+/// as long as neither max attempts nor the total deadline is exceeded.
+#[derive(Debug, Clone)]
+pub(crate) struct RetryPolicy {
+    operation: &'static str,
+    max_attempt: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    deadline: Option<Duration>,
+    retryable: Vec<ErrorCode>,
+    non_retryable: Vec<ErrorCode>,
+}
+
+fn parse_codes(names: &[String]) -> Vec<ErrorCode> {
+    names
+        .iter()
+        .filter_map(|name| {
+            ErrorCode::ENUM_VALUES
+                .iter()
+                .find(|code| code.variant_name() == Some(name.as_str()))
+                .copied()
+        })
+        .collect()
+}
+
+impl RetryPolicy {
+    pub(crate) fn new(config: &config::Configuration, operation: &'static str) -> Self {
+        let retry = config.client.retry.of(operation);
+        Self {
+            operation,
+            max_attempt: retry.max_attempt.max(1),
+            initial_backoff: Duration::from_millis(config.tick * retry.initial_backoff),
+            max_backoff: Duration::from_millis(config.tick * retry.max_backoff),
+            multiplier: retry.multiplier.max(1.0),
+            jitter: retry.jitter.clamp(0.0, 1.0),
+            deadline: if retry.deadline > 0 {
+                Some(Duration::from_millis(config.tick * retry.deadline))
+            } else {
+                None
+            },
+            retryable: parse_codes(&retry.retryable_codes),
+            non_retryable: parse_codes(&retry.non_retryable_codes),
+        }
+    }
+
+    pub(crate) fn retryable(&self, code: ErrorCode) -> bool {
+        !self.non_retryable.contains(&code) && self.retryable.contains(&code)
+    }
+
+    /// Backoff, before jitter, after the specified number of failed attempts.
+    fn base_backoff(&self, attempt: usize) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as usize) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+    }
+
+    /// Backoff after the specified number of failed attempts, randomized by jitter.
+    pub(crate) fn backoff(&self, attempt: usize) -> Duration {
+        let base = self.base_backoff(attempt);
+        if self.jitter == 0.0 {
+            return base;
+        }
+        let factor = 1.0 + rand::thread_rng().gen_range(-self.jitter..=self.jitter);
+        base.mul_f64(factor)
+    }
+
+    /// Run the operation, retrying on failures according to this policy.
+    pub(crate) async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T, EsError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, EsError>>,
+    {
+        let start = Instant::now();
+        let mut attempt = 1;
+        loop {
+            let e = match operation().await {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+
+            if attempt >= self.max_attempt || !self.retryable(e.code) {
+                return Err(e);
+            }
+
+            let backoff = self.backoff(attempt);
+            if let Some(deadline) = self.deadline {
+                if start.elapsed() + backoff >= deadline {
+                    warn!(
+                        "Give up {} after {attempt} attempts, as deadline of {}ms would be exceeded. Cause: {e}",
+                        self.operation,
+                        deadline.as_millis()
+                    );
+                    return Err(e);
+                }
+            }
+
+            warn!(
+                "Attempt {attempt}/{} of {} failed, retry in {}ms. Cause: {e}",
+                self.max_attempt,
+                self.operation,
+                backoff.as_millis()
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, error::Error, time::Duration};
+
+    use model::error::EsError;
+    use protocol::rpc::header::ErrorCode;
+
+    use super::RetryPolicy;
+
+    fn config() -> config::Configuration {
+        let mut config = config::Configuration::default();
+        config.client.retry.max_attempt = 4;
+        config.client.retry.jitter = 0.0;
+        config.client.retry.deadline = 0;
+        config
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = config();
+        let policy = RetryPolicy::new(&config, "fetch");
+        assert_eq!(Duration::from_millis(100), policy.backoff(1));
+        assert_eq!(Duration::from_millis(200), policy.backoff(2));
+        assert_eq!(Duration::from_millis(400), policy.backoff(3));
+        // Capped by max-backoff
+        assert_eq!(Duration::from_millis(1000), policy.backoff(10));
+
+        let mut config = config;
+        config.client.retry.jitter = 0.5;
+        let policy = RetryPolicy::new(&config, "fetch");
+        for _ in 0..100 {
+            let backoff = policy.backoff(2);
+            assert!(backoff >= Duration::from_millis(100) && backoff <= Duration::from_millis(300));
+        }
+    }
+
+    #[test]
+    fn test_retryable() {
+        let mut config = config();
+        config
+            .client
+            .retry
+            .non_retryable_codes
+            .push("CONNECT_TIMEOUT".to_owned());
+        let policy = RetryPolicy::new(&config, "seal");
+        assert!(policy.retryable(ErrorCode::CONNECTION_BROKEN));
+        assert!(!policy.retryable(ErrorCode::CONNECT_TIMEOUT));
+        assert!(!policy.retryable(ErrorCode::PD_NOT_LEADER));
+        assert!(!policy.retryable(ErrorCode::EXPIRED_STREAM_EPOCH));
+        assert!(!policy.retryable(ErrorCode::OK));
+    }
+
+    #[test]
+    fn test_run() -> Result<(), Box<dyn Error>> {
+        let mut config = config();
+        config.client.retry.initial_backoff = 0;
+        tokio_uring::start(async {
+            let policy = RetryPolicy::new(&config, "seal");
+
+            // Retry till success
+            let attempts = &Cell::new(0);
+            let value = policy
+                .run(|| async move {
+                    attempts.set(attempts.get() + 1);
+                    if attempts.get() < 3 {
+                        Err(EsError::new(ErrorCode::CONNECTION_BROKEN, "broken"))
+                    } else {
+                        Ok(attempts.get())
+                    }
+                })
+                .await?;
+            assert_eq!(3, value);
+
+            // Never retry non-retryable errors
+            attempts.set(0);
+            let res: Result<(), EsError> = policy
+                .run(|| async move {
+                    attempts.set(attempts.get() + 1);
+                    Err(EsError::new(ErrorCode::EXPIRED_STREAM_EPOCH, "fenced"))
+                })
+                .await;
+            assert_eq!(ErrorCode::EXPIRED_STREAM_EPOCH, res.unwrap_err().code);
+            assert_eq!(1, attempts.get());
+
+            // Give up after max attempts
+            attempts.set(0);
+            let res: Result<(), EsError> = policy
+                .run(|| async move {
+                    attempts.set(attempts.get() + 1);
+                    Err(EsError::new(ErrorCode::RPC_TIMEOUT, "timeout"))
+                })
+                .await;
+            assert!(res.is_err());
+            assert_eq!(4, attempts.get());
+
+            // Per-operation override
+            attempts.set(0);
+            let policy = RetryPolicy::new(&config, "append");
+            let res: Result<(), EsError> = policy
+                .run(|| async move {
+                    attempts.set(attempts.get() + 1);
+                    Err(EsError::new(ErrorCode::CONNECTION_BROKEN, "broken"))
+                })
+                .await;
+            assert!(res.is_err());
+            assert_eq!(1, attempts.get());
+            Ok(())
+        })
+    }
+}
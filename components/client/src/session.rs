@@ -1,20 +1,20 @@
 use crate::{
     error::ClientError,
     heartbeat::HeartbeatData,
+    lb_policy::LatencyEwma,
     request::{self, Request},
     response::{self, Response},
     NodeRole,
 };
-use codec::{error::FrameError, frame::Frame};
+use codec::{compression::Compression, error::FrameError, frame::Frame};
 use futures::Future;
 use local_sync::oneshot;
 use log::{error, info, trace, warn};
 use protocol::rpc::header::{ClientRole, GoAwayFlags, OperationCode, RangeServerState};
 use std::{
-    cell::{RefCell, UnsafeCell},
+    cell::{Cell, RefCell, UnsafeCell},
     collections::HashMap,
     fmt::Display,
-    net::SocketAddr,
     rc::Rc,
     sync::Arc,
     task::{Context, Poll},
@@ -22,7 +22,9 @@ use std::{
 };
 use tokio::sync::broadcast::{self, error::RecvError};
 use tower::Service;
-use transport::{connection::Connection, connection_state::ConnectionState, ConnectionError};
+use transport::{
+    connection::Connection, connection_state::ConnectionState, Address, ConnectionError,
+};
 
 use crate::invocation_context::InvocationContext;
 
@@ -41,6 +43,15 @@ pub(crate) struct Session {
     /// Role of the peer node in its cluster.
     role: Rc<RefCell<NodeRole>>,
 
+    /// Instant till which subsequent requests are delayed, as requested by `throttle_time_ms` of responses.
+    throttled_until: Rc<RefCell<Option<Instant>>>,
+
+    /// Moving average of request latency, used by latency-aware load-balancing policies.
+    latency: Rc<RefCell<LatencyEwma>>,
+
+    /// Stream id of the in-flight PING request proposing compression, if any.
+    negotiation: Rc<Cell<Option<u32>>>,
+
     shutdown: broadcast::Sender<()>,
 }
 
@@ -49,6 +60,9 @@ impl Session {
     fn spawn_read_loop(
         connection: Rc<UnsafeCell<Connection>>,
         inflight_requests: Rc<UnsafeCell<HashMap<u32, InvocationContext>>>,
+        throttled_until: Rc<RefCell<Option<Instant>>>,
+        latency: Rc<RefCell<LatencyEwma>>,
+        negotiation: Rc<Cell<Option<u32>>>,
         mut shutdown: broadcast::Receiver<()>,
     ) {
         tokio_uring::spawn(async move {
@@ -104,8 +118,18 @@ impl Session {
                             Ok(Some(frame)) => {
                                 trace!( "Read a frame from channel {}", connection);
                                 let inflight = unsafe { &mut *inflight_requests.get() };
-                                if frame.is_response() {
-                                    Session::handle_response(inflight, frame, connection.remote_addr());
+                                if frame.is_response() && frame.operation_code == OperationCode::PING {
+                                    if negotiation.get() == Some(frame.stream_id) {
+                                        // Server accepts the proposed compression by flagging the same on the pong.
+                                        negotiation.set(None);
+                                        let compression = frame.compression();
+                                        info!("Compression of connection {} is negotiated as {:?}", connection, compression);
+                                        connection.set_compression(compression);
+                                    } else {
+                                        trace!("Ignore pong[stream-id={}] from {}, which is not a reply to compression proposal", frame.stream_id, connection);
+                                    }
+                                } else if frame.is_response() {
+                                    Session::handle_response(inflight, frame, connection.remote_addr(), &throttled_until, &latency);
                                 } else if frame.operation_code == OperationCode::GOAWAY {
                                     connection.set_state(ConnectionState::GoingAway);
                                     let reason = if frame.has_go_away_flag(GoAwayFlags::SERVER_MAINTENANCE) {
@@ -144,7 +168,7 @@ impl Session {
     }
 
     pub(crate) fn new(
-        remote_addr: SocketAddr,
+        remote_addr: Address,
         config: &Arc<config::Configuration>,
         shutdown: broadcast::Sender<()>,
     ) -> Self {
@@ -157,6 +181,9 @@ impl Session {
             inflight_requests: inflight,
             idle_since: Rc::new(RefCell::new(Instant::now())),
             role: Rc::new(RefCell::new(NodeRole::Unknown)),
+            throttled_until: Rc::new(RefCell::new(None)),
+            latency: Rc::new(RefCell::new(LatencyEwma::default())),
+            negotiation: Rc::new(Cell::new(None)),
             shutdown,
         }
     }
@@ -170,7 +197,7 @@ impl Session {
         unsafe { &mut *self.connection.get() }
     }
 
-    /// Establish TCP connection with configured timeout
+    /// Establish TCP or Unix domain socket connection with configured timeout
     pub(crate) async fn connect(&self) -> Result<(), ClientError> {
         match self.connection().state() {
             ConnectionState::Active | ConnectionState::Connecting | ConnectionState::GoingAway => {
@@ -178,14 +205,14 @@ impl Session {
             }
             ConnectionState::Unspecified => {
                 trace!(
-                    "Establish TCP connection to {}",
+                    "Establish connection to {}",
                     self.connection().remote_addr()
                 );
             }
 
             ConnectionState::Closed => {
                 info!(
-                    "Re-connect TCP connection to {} as it has been closed",
+                    "Re-connect to {} as the connection has been closed",
                     self.connection().remote_addr()
                 );
             }
@@ -210,12 +237,40 @@ impl Session {
         Self::spawn_read_loop(
             Rc::clone(&self.connection),
             Rc::clone(&self.inflight_requests),
+            Rc::clone(&self.throttled_until),
+            Rc::clone(&self.latency),
+            Rc::clone(&self.negotiation),
             self.shutdown.subscribe(),
         );
 
+        self.propose_compression().await;
+
         Ok(())
     }
 
+    /// Propose the configured compression of frame payloads to the server, using a PING request flagged with it.
+    ///
+    /// Payloads are sent uncompressed till the server accepts the proposal.
+    async fn propose_compression(&self) {
+        let compression = self.config.client.compression;
+        if compression == Compression::None {
+            return;
+        }
+
+        let mut frame = Frame::new(OperationCode::PING);
+        frame.flag_compression(compression);
+        self.negotiation.set(Some(frame.stream_id));
+        if let Err(e) = self.connection().write_frame(frame).await {
+            self.negotiation.set(None);
+            warn!(
+                "Failed to propose compression {:?} to {}: {:?}",
+                compression,
+                self.connection().remote_addr(),
+                e
+            );
+        }
+    }
+
     pub(crate) async fn write(
         &self,
         request: request::Request,
@@ -223,6 +278,21 @@ impl Session {
     ) -> Result<(), InvocationContext> {
         trace!("Sending {} to {}", request, self.connection());
 
+        // Back off if the server has throttled this session due to quota violation.
+        let throttled_until = *self.throttled_until.borrow();
+        if let Some(until) = throttled_until {
+            let now = Instant::now();
+            if until > now {
+                trace!(
+                    "Delay {} for {}ms as {} throttled the session",
+                    request,
+                    (until - now).as_millis(),
+                    self.connection().remote_addr()
+                );
+                tokio::time::sleep(until - now).await;
+            }
+        }
+
         // Update last read/write instant.
         *self.idle_since.borrow_mut() = Instant::now();
         let mut frame = Frame::new(OperationCode::UNKNOWN);
@@ -300,13 +370,23 @@ impl Session {
             request::Headers::DeleteStream { .. } => {
                 frame.operation_code = OperationCode::DELETE_STREAM;
             }
+            request::Headers::KvRange { .. } => {
+                frame.operation_code = OperationCode::KV_RANGE;
+            }
+            request::Headers::KvPut { .. } => {
+                frame.operation_code = OperationCode::KV_PUT;
+            }
+            request::Headers::KvDeleteRange { .. } => {
+                frame.operation_code = OperationCode::KV_DELETE_RANGE;
+            }
         };
 
         frame.payload = request.body.clone();
 
+        // Created after the throttle delay above, such that latency samples measure the RPC only.
         let inflight_requests = unsafe { &mut *self.inflight_requests.get() };
         let context = InvocationContext::new(
-            self.connection().remote_addr(),
+            self.connection().remote_addr().clone(),
             request.clone(),
             response_observer,
         );
@@ -402,6 +482,21 @@ impl Session {
         }
     }
 
+    /// Number of requests that are sent but not yet responded.
+    pub(crate) fn inflight(&self) -> usize {
+        unsafe { &*self.inflight_requests.get() }.len()
+    }
+
+    /// Moving average of request latency, `None` if no response is received yet.
+    pub(crate) fn latency(&self) -> Option<std::time::Duration> {
+        self.latency.borrow().get()
+    }
+
+    /// Cost of routing a new request to this session, in terms of latency and in-flight requests.
+    pub(crate) fn latency_cost(&self) -> f64 {
+        self.latency.borrow().cost(self.inflight())
+    }
+
     /// Flag whether the underlying TCP connection is going to shutdown in the near future.
     pub fn going_away(&self) -> bool {
         self.connection().state() == ConnectionState::GoingAway
@@ -422,7 +517,9 @@ impl Session {
     fn handle_response(
         inflight: &mut HashMap<u32, InvocationContext>,
         frame: Frame,
-        target: SocketAddr,
+        target: &Address,
+        throttled_until: &RefCell<Option<Instant>>,
+        latency: &RefCell<LatencyEwma>,
     ) {
         let stream_id = frame.stream_id;
         trace!(
@@ -441,6 +538,7 @@ impl Session {
 
         match inflight.remove(&stream_id) {
             Some(mut ctx) => {
+                latency.borrow_mut().observe(ctx.elapsed());
                 let mut response = response::Response::new(frame.operation_code);
                 if frame.system_error() {
                     response.on_system_error(&frame);
@@ -504,6 +602,18 @@ impl Session {
                             response.on_trim_stream(&frame);
                         }
 
+                        OperationCode::KV_RANGE => {
+                            response.on_kv_range(&frame);
+                        }
+
+                        OperationCode::KV_PUT => {
+                            response.on_kv_put(&frame);
+                        }
+
+                        OperationCode::KV_DELETE_RANGE => {
+                            response.on_kv_delete_range(&frame);
+                        }
+
                         OperationCode::REPORT_METRICS => {
                             response.on_report_metrics(&frame);
                         }
@@ -537,6 +647,19 @@ impl Session {
                     }
                 }
 
+                if let Some(throttle) = response.throttle.filter(|throttle| !throttle.is_zero()) {
+                    trace!(
+                        "{} throttles subsequent requests for {}ms",
+                        target,
+                        throttle.as_millis()
+                    );
+                    let until = Instant::now() + throttle;
+                    let mut throttled_until = throttled_until.borrow_mut();
+                    if throttled_until.map_or(true, |current| current < until) {
+                        *throttled_until = Some(until);
+                    }
+                }
+
                 ctx.write_response(response);
             }
             None => {
@@ -557,6 +680,8 @@ impl Clone for Session {
             inflight_requests: Rc::clone(&self.inflight_requests),
             idle_since: Rc::clone(&self.idle_since),
             role: Rc::clone(&self.role),
+            throttled_until: Rc::clone(&self.throttled_until),
+            latency: Rc::clone(&self.latency),
             shutdown: self.shutdown.clone(),
         }
     }
@@ -600,8 +725,10 @@ impl Service<Request> for Session {
 mod tests {
 
     use super::*;
+    use bytes::Bytes;
     use log::debug;
     use mock_server::run_listener;
+    use protocol::rpc::header::{AppendResponseT, ErrorCode, StatusT};
     use std::{error::Error, time::Duration};
     use tower::timeout::Timeout;
 
@@ -716,4 +843,116 @@ mod tests {
             Ok(())
         })
     }
+
+    /// Verify a response carrying `throttle_time_ms` delays subsequent requests of the session.
+    #[test]
+    fn test_session_throttled() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        tokio_uring::start(async {
+            let port = run_listener().await;
+            let target = format!("127.0.0.1:{}", port);
+            let config = Arc::new(config::Configuration::default());
+            let (tx, _rx) = broadcast::channel(1);
+            let session = Session::new(target.parse()?, &config, tx);
+            session.connect().await?;
+
+            let heartbeat = || crate::request::Request {
+                timeout: Duration::from_secs(1),
+                headers: request::Headers::Heartbeat {
+                    client_id: "test".to_owned(),
+                    role: ClientRole::CLIENT_ROLE_FRONTEND,
+                    range_server: None,
+                },
+                body: None,
+            };
+
+            // An append response of the range server, asking to back off for 200ms.
+            let mut response = AppendResponseT::default();
+            let mut status = StatusT::default();
+            status.code = ErrorCode::OK;
+            response.status = Box::new(status);
+            response.entries = Some(vec![]);
+            response.throttle_time_ms = 200;
+            let mut builder = flatbuffers::FlatBufferBuilder::new();
+            let response = response.pack(&mut builder);
+            builder.finish(response, None);
+            let mut frame = Frame::new(OperationCode::APPEND);
+            frame.stream_id = 1;
+            frame.flag_response();
+            frame.header = Some(Bytes::copy_from_slice(builder.finished_data()));
+
+            let address: Address = target.parse()?;
+            let (observer, rx) = oneshot::channel();
+            let mut inflight = HashMap::new();
+            inflight.insert(
+                1,
+                InvocationContext::new(address.clone(), heartbeat(), observer),
+            );
+            Session::handle_response(
+                &mut inflight,
+                frame,
+                &address,
+                &session.throttled_until,
+                &session.latency,
+            );
+            let response = rx.await?;
+            assert!(response.ok());
+            assert_eq!(Some(Duration::from_millis(200)), response.throttle);
+
+            let start = Instant::now();
+            let (observer, _rx) = oneshot::channel();
+            session
+                .write(heartbeat(), observer)
+                .await
+                .map_err(|_| "Failed to write heartbeat")?;
+            assert!(start.elapsed() >= Duration::from_millis(150));
+
+            // Requests are no longer delayed once the throttle time elapses.
+            let start = Instant::now();
+            let (observer, _rx) = oneshot::channel();
+            session
+                .write(heartbeat(), observer)
+                .await
+                .map_err(|_| "Failed to write heartbeat")?;
+            assert!(start.elapsed() < Duration::from_millis(150));
+            Ok(())
+        })
+    }
+
+    /// Verify latency samples of a throttled session exclude the delay of throttling.
+    #[test]
+    fn test_latency_excludes_throttle() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        tokio_uring::start(async {
+            let port = run_listener().await;
+            let target = format!("127.0.0.1:{}", port);
+            let config = Arc::new(config::Configuration::default());
+            let (tx, _rx) = broadcast::channel(1);
+            let session = Session::new(target.parse()?, &config, tx);
+            session.connect().await?;
+
+            *session.throttled_until.borrow_mut() =
+                Some(Instant::now() + Duration::from_millis(200));
+            let request = crate::request::Request {
+                timeout: Duration::from_secs(1),
+                headers: request::Headers::Heartbeat {
+                    client_id: "test".to_owned(),
+                    role: ClientRole::CLIENT_ROLE_FRONTEND,
+                    range_server: None,
+                },
+                body: None,
+            };
+            let (observer, _rx) = oneshot::channel();
+            session
+                .write(request, observer)
+                .await
+                .map_err(|_| "Failed to write heartbeat")?;
+
+            // Mock server never responds heartbeats, so the context remains in flight.
+            let inflight = unsafe { &*session.inflight_requests.get() };
+            let ctx = inflight.values().next().ok_or("No in-flight request")?;
+            assert!(ctx.elapsed() < Duration::from_millis(100));
+            Ok(())
+        })
+    }
 }
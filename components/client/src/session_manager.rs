@@ -1,6 +1,6 @@
 use super::{composite_session::CompositeSession, lb_policy::LbPolicy};
 use crate::{heartbeat::HeartbeatData, naming::Naming};
-use log::error;
+use log::{error, warn};
 use model::error::EsError;
 use std::{cell::UnsafeCell, collections::HashMap, rc::Rc, sync::Arc};
 use tokio::sync::broadcast;
@@ -54,7 +54,8 @@ impl SessionManager {
                     let composite_session = CompositeSession::new(
                         naming,
                         Arc::clone(&self.config),
-                        LbPolicy::LeaderOnly,
+                        self.config.client.pd_lb_policy,
+                        true,
                         self.shutdown.clone(),
                     )
                     .await?;
@@ -68,10 +69,18 @@ impl SessionManager {
                     }
                     composite_session
                 } else {
+                    let lb_policy = match self.config.client.range_server_lb_policy {
+                        LbPolicy::LeaderOnly => {
+                            warn!("LeaderOnly is not applicable to range servers, use PickFirst instead");
+                            LbPolicy::PickFirst
+                        }
+                        lb_policy => lb_policy,
+                    };
                     CompositeSession::new(
                         target,
                         Arc::clone(&self.config),
-                        LbPolicy::PickFirst,
+                        lb_policy,
+                        false,
                         self.shutdown.clone(),
                     )
                     .await?
@@ -0,0 +1,114 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use protocol::rpc::header::CommonFlags;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::FrameError, frame::MAX_FRAME_LENGTH};
+
+/// Bits of frame flag that signal the compression codec of the payload.
+pub(crate) const COMPRESSION_MASK: u8 =
+    CommonFlags::COMPRESSION_LZ4.0 as u8 | CommonFlags::COMPRESSION_ZSTD.0 as u8;
+
+/// Compression codec of frame payloads.
+///
+/// Compression is negotiated per connection and signalled by frame flag, such that frames are
+/// compressed and decompressed transparently to request handlers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    pub(crate) fn from_flag(flag: u8) -> Self {
+        match flag & COMPRESSION_MASK {
+            f if f == CommonFlags::COMPRESSION_LZ4.0 as u8 => Compression::Lz4,
+            f if f == CommonFlags::COMPRESSION_ZSTD.0 as u8 => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    pub(crate) fn flag(&self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Lz4 => CommonFlags::COMPRESSION_LZ4.0 as u8,
+            Compression::Zstd => CommonFlags::COMPRESSION_ZSTD.0 as u8,
+        }
+    }
+
+    pub(crate) fn compress(&self, payload: &[Bytes]) -> Result<Bytes, FrameError> {
+        let len = payload.iter().map(|b| b.len()).sum::<usize>();
+        let mut buf = BytesMut::with_capacity(len);
+        payload.iter().for_each(|b| buf.put_slice(b));
+        match self {
+            Compression::None => Ok(buf.freeze()),
+            Compression::Lz4 => Ok(Bytes::from(lz4_flex::compress_prepend_size(&buf))),
+            Compression::Zstd => zstd::bulk::compress(&buf, 0)
+                .map(Bytes::from)
+                .map_err(|e| FrameError::BadFrame(format!("Failed to compress payload: {}", e))),
+        }
+    }
+
+    pub(crate) fn decompress(&self, payload: &[u8]) -> Result<Bytes, FrameError> {
+        match self {
+            Compression::None => Ok(Bytes::copy_from_slice(payload)),
+            Compression::Lz4 => {
+                // Guard against allocation of absurd size prepended by a malformed payload.
+                let size = payload
+                    .get(..4)
+                    .map(|size| u32::from_le_bytes([size[0], size[1], size[2], size[3]]))
+                    .ok_or_else(|| FrameError::BadFrame("Truncated LZ4 payload".to_owned()))?;
+                if size > MAX_FRAME_LENGTH {
+                    return Err(FrameError::TooLongFrame {
+                        found: size,
+                        max: MAX_FRAME_LENGTH,
+                    });
+                }
+                lz4_flex::decompress_size_prepended(payload)
+                    .map(Bytes::from)
+                    .map_err(|e| {
+                        FrameError::BadFrame(format!("Failed to decompress LZ4 payload: {}", e))
+                    })
+            }
+            Compression::Zstd => zstd::bulk::decompress(payload, MAX_FRAME_LENGTH as usize)
+                .map(Bytes::from)
+                .map_err(|e| {
+                    FrameError::BadFrame(format!("Failed to decompress Zstd payload: {}", e))
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::Compression;
+
+    #[test]
+    fn test_flag() {
+        for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+            assert_eq!(compression, Compression::from_flag(compression.flag()));
+            // Other flag bits do not interfere
+            assert_eq!(compression, Compression::from_flag(compression.flag() | 1));
+        }
+    }
+
+    #[test]
+    fn test_compress() {
+        let payload = vec![
+            Bytes::from(vec![1u8; 4096]),
+            Bytes::from_static(b"elastic-stream"),
+        ];
+        for compression in [Compression::Lz4, Compression::Zstd] {
+            let compressed = compression.compress(&payload).unwrap();
+            assert!(compressed.len() < 4096);
+            let decompressed = compression.decompress(&compressed).unwrap();
+            assert_eq!(4096 + 14, decompressed.len());
+            assert_eq!(b"elastic-stream", &decompressed[4096..]);
+
+            assert!(compression.decompress(&[1, 2, 3, 4, 5]).is_err());
+        }
+    }
+}
@@ -6,6 +6,7 @@ use protocol::rpc::header::{CommonFlags, GoAwayFlags, OperationCode};
 use std::cell::RefCell;
 use std::io::Cursor;
 
+use crate::compression::{Compression, COMPRESSION_MASK};
 use crate::error::FrameError;
 
 pub(crate) const MAGIC_CODE: u8 = 23;
@@ -95,6 +96,16 @@ impl Frame {
         self.flag & flag.0 as u8 == flag.0 as u8
     }
 
+    /// Compression codec of the payload, as signalled by frame flag.
+    pub fn compression(&self) -> Compression {
+        Compression::from_flag(self.flag)
+    }
+
+    /// Flag the payload to be compressed by the specified codec on encoding.
+    pub fn flag_compression(&mut self, compression: Compression) {
+        self.flag = (self.flag & !COMPRESSION_MASK) | compression.flag();
+    }
+
     #[inline]
     pub fn flag_go_away(&mut self, flag: GoAwayFlags) {
         self.flag |= flag.0 as u8;
@@ -283,6 +294,10 @@ impl Frame {
         let payload_length = remaining - 4;
         if payload_length > 0 {
             let payload = src.copy_to_bytes(payload_length as usize);
+            let payload = match frame.compression() {
+                Compression::None => payload,
+                compression => compression.decompress(&payload)?,
+            };
             frame.payload = Some(vec![payload]);
         }
         remaining -= payload_length;
@@ -308,6 +323,25 @@ impl Frame {
             .flatten()
             .map(|b| b.len())
             .sum::<usize>();
+
+        // Uncompressed payload should also fit into a frame, so that peers are able to decompress it.
+        if payload_len > MAX_FRAME_LENGTH as usize {
+            return Err(FrameError::TooLongFrame {
+                found: payload_len as u32,
+                max: MAX_FRAME_LENGTH,
+            });
+        }
+
+        let compressed;
+        let payload = match &self.payload {
+            Some(payload) if self.compression() != Compression::None => {
+                compressed = Some(vec![self.compression().compress(payload)?]);
+                &compressed
+            }
+            payload => payload,
+        };
+
+        let payload_len = payload.iter().flatten().map(|b| b.len()).sum::<usize>();
         frame_length += payload_len;
 
         if frame_length > crate::frame::MAX_FRAME_LENGTH as usize {
@@ -339,7 +373,7 @@ impl Frame {
 
         encode_result.push(basic_part.freeze());
 
-        if let Some(payload) = &self.payload {
+        if let Some(payload) = payload {
             for p in payload {
                 encode_result.push(p.clone());
             }
@@ -573,6 +607,40 @@ mod tests {
         assert_eq!(0, buf.len());
     }
 
+    #[test]
+    fn test_compression() {
+        for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+            let mut frame = Frame::new(OperationCode::APPEND);
+            frame.flag_response();
+            frame.flag_compression(compression);
+            frame.header = Some(Bytes::from_static(b"abc"));
+            frame.payload = Some(vec![
+                Bytes::from(vec![1u8; 4096]),
+                Bytes::from(vec![2u8; 4096]),
+            ]);
+
+            let mut buf = BytesMut::new();
+            for b in frame.encode().unwrap() {
+                buf.put_slice(&b);
+            }
+            if compression != Compression::None {
+                assert!(buf.len() < 4096);
+            }
+
+            let mut cursor = Cursor::new(&buf[..]);
+            Frame::check(&mut cursor).unwrap();
+            cursor.set_position(0);
+            let parsed = Frame::parse(&mut cursor).unwrap();
+            assert!(parsed.is_response());
+            assert_eq!(compression, parsed.compression());
+            assert_eq!(Some(Bytes::from_static(b"abc")), parsed.header);
+            let payload = parsed.get_response_payload().unwrap();
+            assert_eq!(8192, payload.len());
+            assert_eq!(&[1u8; 4096][..], &payload[..4096]);
+            assert_eq!(&[2u8; 4096][..], &payload[4096..]);
+        }
+    }
+
     #[test]
     fn test_too_long_header_length() {
         let mut raw_frame = BytesMut::with_capacity(16);
@@ -13,4 +13,16 @@ pub enum ConfigurationError {
 
     #[error("System errno `{0}`")]
     System(i32),
+
+    #[error("Invalid retry policy: {0}")]
+    InvalidRetryPolicy(String),
+
+    #[error("Unknown configuration item `{0}`")]
+    UnknownItem(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidValue(String),
+
+    #[error("Invalid YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
 }
@@ -1,14 +1,18 @@
 use std::{
+    collections::HashMap,
+    fs::File,
+    path::Path,
     process,
     sync::atomic::{AtomicUsize, Ordering},
     time::Duration,
 };
 
+pub use codec::compression::Compression;
 use error::ConfigurationError;
 use model::RangeServer;
 use nix::sys::stat;
-use protocol::rpc::header::RangeServerState;
-use serde::{Deserialize, Serialize};
+use protocol::rpc::header::{ErrorCode, RangeServerState};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 pub mod error;
 
 lazy_static::lazy_static! {
@@ -61,7 +65,14 @@ fn client_id() -> String {
     )
 }
 
+/// Parse value of the configuration item at `path` as YAML.
+fn parse_item<T: DeserializeOwned>(path: &str, value: &str) -> Result<T, ConfigurationError> {
+    serde_yaml::from_str(value)
+        .map_err(|e| ConfigurationError::InvalidValue(format!("{path}: {e}")))
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Client {
     /// Establish connection timeout in ticks
     #[serde(rename = "connect-timeout")]
@@ -75,15 +86,69 @@ pub struct Client {
     #[serde(rename = "client-id")]
     pub client_id: String,
 
-    /// Max transparent client retries
-    #[serde(rename = "max-attempt")]
-    pub max_attempt: usize,
+    /// Retry and backoff policy of client requests
+    #[serde(default)]
+    pub retry: Retry,
+
+    /// Deprecated, use `retry.max-attempt` instead, which this overrides if present
+    #[serde(
+        rename = "max-attempt",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_attempt: Option<usize>,
 
     #[serde(rename = "heartbeat-interval")]
     pub heartbeat_interval: u64,
 
     #[serde(rename = "refresh-pd-cluster-interval")]
     pub refresh_pd_cluster_interval: u64,
+
+    /// Load-balancing policy among placement driver nodes
+    #[serde(rename = "pd-lb-policy", default = "default_pd_lb_policy")]
+    pub pd_lb_policy: LbPolicy,
+
+    /// Load-balancing policy among addresses of a range server
+    #[serde(rename = "range-server-lb-policy", default)]
+    pub range_server_lb_policy: LbPolicy,
+
+    /// Compression of frame payloads to propose to servers on connect
+    #[serde(default)]
+    pub compression: Compression,
+
+    /// Availability zone of the client. Reads prefer replicas on range servers of the same zone
+    #[serde(default)]
+    pub zone: Option<String>,
+}
+
+/// Load-balancing policy among sessions to the resolved addresses of a target.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LbPolicy {
+    /// Use the first active session.
+    PickFirst,
+
+    /// Use the session to the placement driver leader. Only applicable to placement drivers.
+    LeaderOnly,
+
+    /// Rotate among active sessions.
+    RoundRobin,
+
+    /// Use the active session with the fewest in-flight requests.
+    LeastOutstandingRequests,
+
+    /// Use the active session with the lowest exponentially weighted moving average of latency,
+    /// weighted by in-flight requests.
+    LatencyEwma,
+}
+
+impl Default for LbPolicy {
+    fn default() -> Self {
+        Self::PickFirst
+    }
+}
+
+fn default_pd_lb_policy() -> LbPolicy {
+    LbPolicy::LeaderOnly
 }
 
 impl Default for Client {
@@ -92,9 +157,193 @@ impl Default for Client {
             connect_timeout: 20,
             io_timeout: 10,
             client_id: "".to_owned(),
-            max_attempt: 3,
+            retry: Retry::default(),
+            max_attempt: None,
             heartbeat_interval: 30,
             refresh_pd_cluster_interval: 300,
+            pd_lb_policy: default_pd_lb_policy(),
+            range_server_lb_policy: LbPolicy::default(),
+            compression: Compression::default(),
+            zone: None,
+        }
+    }
+}
+
+impl Client {
+    /// Move settings of deprecated keys onto their replacements.
+    fn apply_deprecated(&mut self) {
+        if let Some(max_attempt) = self.max_attempt.take() {
+            self.retry.max_attempt = max_attempt;
+        }
+    }
+}
+
+/// Retry policy of client requests, with exponential backoff and jitter.
+///
+/// `PD_NOT_LEADER` must not be retryable, as sessions to placement driver redirect requests to the
+/// new leader themselves.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Retry {
+    /// Max attempts, including the first one
+    #[serde(rename = "max-attempt")]
+    pub max_attempt: usize,
+
+    /// Backoff before the first retry, in ticks
+    #[serde(rename = "initial-backoff")]
+    pub initial_backoff: u64,
+
+    /// Upper bound of backoff between attempts, in ticks
+    #[serde(rename = "max-backoff")]
+    pub max_backoff: u64,
+
+    /// Factor by which backoff grows after each attempt
+    pub multiplier: f64,
+
+    /// Ratio of backoff to randomize, in the range of [0, 1]
+    pub jitter: f64,
+
+    /// Total deadline of all attempts in ticks, 0 means no deadline other than max attempts
+    pub deadline: u64,
+
+    /// Names of error codes, on which requests are retried
+    #[serde(rename = "retryable-codes")]
+    pub retryable_codes: Vec<String>,
+
+    /// Names of error codes, on which requests are never retried, even if they are retryable
+    #[serde(rename = "non-retryable-codes")]
+    pub non_retryable_codes: Vec<String>,
+
+    /// Per-operation overrides, keyed by operation name, for example, `append`, `fetch`, `seal` and `create-range`
+    #[serde(default)]
+    pub overrides: HashMap<String, RetryOverride>,
+}
+
+/// Overrides of `Retry` for a specific operation. Absent fields fall back to the default policy.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct RetryOverride {
+    #[serde(rename = "max-attempt", default)]
+    pub max_attempt: Option<usize>,
+
+    #[serde(rename = "initial-backoff", default)]
+    pub initial_backoff: Option<u64>,
+
+    #[serde(rename = "max-backoff", default)]
+    pub max_backoff: Option<u64>,
+
+    #[serde(default)]
+    pub deadline: Option<u64>,
+}
+
+impl Retry {
+    /// Effective retry policy of the given operation, with its overrides applied.
+    pub fn of(&self, operation: &str) -> Retry {
+        let mut retry = self.clone();
+        retry.overrides.clear();
+        if let Some(overrides) = self.overrides.get(operation) {
+            if let Some(max_attempt) = overrides.max_attempt {
+                retry.max_attempt = max_attempt;
+            }
+            if let Some(initial_backoff) = overrides.initial_backoff {
+                retry.initial_backoff = initial_backoff;
+            }
+            if let Some(max_backoff) = overrides.max_backoff {
+                retry.max_backoff = max_backoff;
+            }
+            if let Some(deadline) = overrides.deadline {
+                retry.deadline = deadline;
+            }
+        }
+        retry
+    }
+
+    fn check(&self) -> Result<(), ConfigurationError> {
+        if self.max_attempt == 0 {
+            return Err(ConfigurationError::InvalidRetryPolicy(
+                "max-attempt should be positive".to_owned(),
+            ));
+        }
+
+        if self.multiplier < 1.0 {
+            return Err(ConfigurationError::InvalidRetryPolicy(
+                "multiplier should not be less than 1".to_owned(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(ConfigurationError::InvalidRetryPolicy(
+                "jitter should be in the range of [0, 1]".to_owned(),
+            ));
+        }
+
+        for code in self
+            .retryable_codes
+            .iter()
+            .chain(self.non_retryable_codes.iter())
+        {
+            if !ErrorCode::ENUM_VALUES
+                .iter()
+                .any(|value| value.variant_name() == Some(code.as_str()))
+            {
+                return Err(ConfigurationError::InvalidRetryPolicy(format!(
+                    "unknown error code `{code}`"
+                )));
+            }
+        }
+
+        if self
+            .retryable_codes
+            .iter()
+            .any(|code| Some(code.as_str()) == ErrorCode::PD_NOT_LEADER.variant_name())
+        {
+            return Err(ConfigurationError::InvalidRetryPolicy(
+                "PD_NOT_LEADER should not be retryable, as requests are redirected to the new leader"
+                    .to_owned(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            max_attempt: 3,
+            initial_backoff: 1,
+            max_backoff: 10,
+            multiplier: 2.0,
+            jitter: 0.2,
+            deadline: 100,
+            retryable_codes: [
+                ErrorCode::CONNECTION_BROKEN,
+                ErrorCode::CONNECT_REFUSED,
+                ErrorCode::CONNECT_FAIL,
+                ErrorCode::CONNECT_TIMEOUT,
+                ErrorCode::RPC_TIMEOUT,
+                ErrorCode::TOO_MANY_REQUESTS,
+            ]
+            .iter()
+            .filter_map(|code| code.variant_name())
+            .map(|name| name.to_owned())
+            .collect(),
+            non_retryable_codes: [
+                ErrorCode::EXPIRED_STREAM_EPOCH,
+                ErrorCode::BAD_REQUEST,
+                ErrorCode::STREAM_NOT_EXIST,
+                ErrorCode::RANGE_ALREADY_SEALED,
+            ]
+            .iter()
+            .filter_map(|code| code.variant_name())
+            .map(|name| name.to_owned())
+            .collect(),
+            // Appends are retried by the replication layer, which tracks offsets of in-flight appends.
+            overrides: HashMap::from([(
+                "append".to_owned(),
+                RetryOverride {
+                    max_attempt: Some(1),
+                    ..Default::default()
+                },
+            )]),
         }
     }
 }
@@ -107,6 +356,10 @@ pub struct Server {
     #[serde(skip_serializing, skip_deserializing)]
     pub advertise_addr: String,
 
+    /// Path of the Unix domain socket served alongside `addr`, for clients on the same host.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub unix_addr: Option<String>,
+
     /// Range Server ID
     #[serde(default)]
     pub server_id: i32,
@@ -121,6 +374,21 @@ pub struct Server {
 
     #[serde(rename = "grace-period")]
     pub grace_period: u64,
+
+    #[serde(default)]
+    pub quota: Quota,
+
+    /// Compressions of frame payloads that the server accepts when proposed by clients
+    #[serde(default = "default_compressions")]
+    pub compressions: Vec<Compression>,
+
+    /// Availability zone of the range server, which is reported to placement driver
+    #[serde(default)]
+    pub zone: Option<String>,
+}
+
+fn default_compressions() -> Vec<Compression> {
+    vec![Compression::Lz4, Compression::Zstd]
 }
 
 impl Server {
@@ -129,6 +397,8 @@ impl Server {
             server_id: self.server_id,
             advertise_address: self.advertise_addr.clone(),
             state: RangeServerState::RANGE_SERVER_STATE_READ_WRITE,
+            zone: self.zone.clone(),
+            unix_address: self.unix_addr.clone(),
         }
     }
 }
@@ -138,11 +408,70 @@ impl Default for Server {
         Self {
             addr: String::from("127.0.0.1:10911"),
             advertise_addr: String::from("127.0.0.1:10911"),
+            unix_addr: None,
             server_id: 0,
             worker_cpu_set: String::from("0"),
             uring: Uring::default(),
             connection_idle_duration: 60,
             grace_period: 120,
+            quota: Quota::default(),
+            compressions: default_compressions(),
+            zone: None,
+        }
+    }
+}
+
+/// Quotas enforced by range servers, in the unit of per-second.
+///
+/// A rate of `0` means unlimited. Note quotas are enforced by each worker independently, thus
+/// the effective limit of a range server is the configured rate multiplied by the number of workers
+/// that serve the client or stream.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Quota {
+    /// Max bytes per second of each client, identified by its client-id.
+    #[serde(rename = "client-byte-rate", default)]
+    pub client_byte_rate: u64,
+
+    /// Max requests per second of each client.
+    #[serde(rename = "client-request-rate", default)]
+    pub client_request_rate: u64,
+
+    /// Max bytes per second of each stream.
+    #[serde(rename = "stream-byte-rate", default)]
+    pub stream_byte_rate: u64,
+
+    /// Max requests per second of each stream.
+    #[serde(rename = "stream-request-rate", default)]
+    pub stream_request_rate: u64,
+
+    /// Max throttle time in ticks. Requests that would be throttled longer than this are rejected
+    /// with `TOO_MANY_REQUESTS`.
+    #[serde(rename = "max-throttle-time", default = "default_max_throttle_time")]
+    pub max_throttle_time: u64,
+}
+
+fn default_max_throttle_time() -> u64 {
+    50
+}
+
+impl Quota {
+    /// Return true if any of the quotas is configured.
+    pub fn enabled(&self) -> bool {
+        self.client_byte_rate > 0
+            || self.client_request_rate > 0
+            || self.stream_byte_rate > 0
+            || self.stream_request_rate > 0
+    }
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self {
+            client_byte_rate: 0,
+            client_request_rate: 0,
+            stream_byte_rate: 0,
+            stream_request_rate: 0,
+            max_throttle_time: default_max_throttle_time(),
         }
     }
 }
@@ -454,12 +783,25 @@ impl Default for Profiles {
 
 /// Configurable items of the replication layer.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Replication {
     #[serde(rename = "connection-pool-size")]
     pub connection_pool_size: usize,
 
     #[serde(rename = "thread-count")]
     pub thread_count: usize,
+
+    /// Default rollover policy of streams, which may be overridden per stream on open
+    #[serde(default)]
+    pub rollover: Rollover,
+
+    /// Limits on in-flight appends
+    #[serde(default)]
+    pub inflight: Inflight,
+
+    /// Hedging of reads to another replica
+    #[serde(default)]
+    pub hedge: Hedge,
 }
 
 impl Default for Replication {
@@ -467,11 +809,104 @@ impl Default for Replication {
         Self {
             connection_pool_size: 2,
             thread_count: 4,
+            rollover: Rollover::default(),
+            inflight: Inflight::default(),
+            hedge: Hedge::default(),
+        }
+    }
+}
+
+/// Limits on appends that are sent but not yet acknowledged, guarding memory of clients against slow
+/// range servers.
+///
+/// Zero means unlimited.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Inflight {
+    /// Max in-flight append requests of all streams
+    #[serde(rename = "max-requests")]
+    pub max_requests: usize,
+
+    /// Max in-flight append bytes of all streams
+    #[serde(rename = "max-bytes")]
+    pub max_bytes: usize,
+
+    /// Max in-flight append requests of each stream
+    #[serde(rename = "stream-max-requests")]
+    pub stream_max_requests: usize,
+
+    /// Max in-flight append bytes of each stream
+    #[serde(rename = "stream-max-bytes")]
+    pub stream_max_bytes: usize,
+
+    /// If true, appends wait till in-flight ones complete once limits are reached; Otherwise, they fail fast
+    /// with `TOO_MANY_REQUESTS`.
+    pub block: bool,
+}
+
+impl Default for Inflight {
+    fn default() -> Self {
+        Self {
+            max_requests: 65536,
+            max_bytes: 512 * 1024 * 1024,
+            stream_max_requests: 8192,
+            stream_max_bytes: 64 * 1024 * 1024,
+            block: true,
+        }
+    }
+}
+
+/// Thresholds on which the writer of a stream seals the current range and rolls over to a new one.
+///
+/// Zero means unlimited.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Rollover {
+    /// Max bytes of record batches appended to a range
+    #[serde(rename = "max-bytes")]
+    pub max_bytes: u64,
+
+    /// Max records appended to a range
+    #[serde(rename = "max-records")]
+    pub max_records: u64,
+
+    /// Max age of a range since its creation, in ticks
+    #[serde(rename = "max-age")]
+    pub max_age: u64,
+}
+
+impl Default for Rollover {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1024 * 1024 * 1024,
+            max_records: 0,
+            // 1 hour
+            max_age: 36000,
+        }
+    }
+}
+
+/// Policy on which a read that is slow to answer is hedged to another replica, taking whichever answers
+/// first.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Hedge {
+    /// Percentile of recent replica read latency, beyond which the read is hedged. 0 disables hedging
+    pub percentile: f64,
+
+    /// Min delay in milliseconds before hedging a read
+    #[serde(rename = "min-delay-ms")]
+    pub min_delay_ms: u64,
+}
+
+impl Default for Hedge {
+    fn default() -> Self {
+        Self {
+            percentile: 99.0,
+            min_delay_ms: 5,
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct ObjectStorageConfig {
     #[serde(default = "default_cluster")]
     pub cluster: String,
@@ -488,12 +923,22 @@ pub struct ObjectStorageConfig {
     pub cache_low_watermark: u64,
     #[serde(rename = "force-flush-secs")]
     pub force_flush_secs: u64,
+    /// Interval to compact offloaded objects of compacted streams.
+    #[serde(
+        rename = "compaction-interval-secs",
+        default = "default_compaction_interval_secs"
+    )]
+    pub compaction_interval_secs: u64,
 }
 
 fn default_cluster() -> String {
     "elasticstream".to_owned()
 }
 
+fn default_compaction_interval_secs() -> u64 {
+    60 * 10
+}
+
 impl Default for ObjectStorageConfig {
     fn default() -> Self {
         Self {
@@ -506,11 +951,13 @@ impl Default for ObjectStorageConfig {
             max_cache_size: 1024 * 1024 * 1024,
             cache_low_watermark: 7 * 128 * 1024 * 1024,
             force_flush_secs: 60 * 20,
+            compaction_interval_secs: default_compaction_interval_secs(),
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Configuration {
     /// Unit of time in milliseconds.
     pub tick: u64,
@@ -528,7 +975,7 @@ pub struct Configuration {
 
     pub observation: Observation,
 
-    #[serde(rename = "object-storage", default = "ObjectStorageConfig::default")]
+    #[serde(rename = "object-storage")]
     pub object_storage: ObjectStorageConfig,
 }
 
@@ -564,6 +1011,9 @@ impl Configuration {
             return Err(ConfigurationError::InvalidCoreId(self.store.io_cpu));
         }
 
+        self.client.apply_deprecated();
+        self.client.retry.check()?;
+
         if self.client.client_id.is_empty() {
             let client_id = client_id();
             self.client.client_id.push_str(&client_id);
@@ -614,6 +1064,178 @@ impl Configuration {
         Ok(())
     }
 
+    /// Load configuration from a YAML file. Absent sections of the file fall back to defaults.
+    pub fn from_yaml_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigurationError> {
+        Ok(serde_yaml::from_reader(File::open(path)?)?)
+    }
+
+    /// Set a client configuration item by its dot-separated path, for example, `client.io-timeout`.
+    ///
+    /// `value` is parsed as YAML, so nested items may be set at once with flow style, for example,
+    /// `client.retry.overrides` to `{append: {max-attempt: 1}}`. Dashes and underscores of item names
+    /// are interchangeable. Items of server, store and observation do not apply to clients, hence are
+    /// unknown.
+    pub fn set(&mut self, path: &str, value: &str) -> Result<(), ConfigurationError> {
+        let client = &mut self.client;
+        let retry = &mut client.retry;
+        let replication = &mut self.replication;
+        let object_storage = &mut self.object_storage;
+        match path.replace('_', "-").as_str() {
+            "tick" => self.tick = parse_item(path, value)?,
+            "placement-driver" => self.placement_driver = value.to_owned(),
+
+            "client.connect-timeout" => client.connect_timeout = parse_item(path, value)?,
+            "client.io-timeout" => client.io_timeout = parse_item(path, value)?,
+            "client.client-id" => client.client_id = value.to_owned(),
+            "client.max-attempt" => client.max_attempt = parse_item(path, value)?,
+            "client.heartbeat-interval" => client.heartbeat_interval = parse_item(path, value)?,
+            "client.refresh-pd-cluster-interval" => {
+                client.refresh_pd_cluster_interval = parse_item(path, value)?
+            }
+            "client.pd-lb-policy" => client.pd_lb_policy = parse_item(path, value)?,
+            "client.range-server-lb-policy" => {
+                client.range_server_lb_policy = parse_item(path, value)?
+            }
+            "client.compression" => client.compression = parse_item(path, value)?,
+            "client.zone" => client.zone = parse_item(path, value)?,
+            "client.retry" => *retry = parse_item(path, value)?,
+            "client.retry.max-attempt" => retry.max_attempt = parse_item(path, value)?,
+            "client.retry.initial-backoff" => retry.initial_backoff = parse_item(path, value)?,
+            "client.retry.max-backoff" => retry.max_backoff = parse_item(path, value)?,
+            "client.retry.multiplier" => retry.multiplier = parse_item(path, value)?,
+            "client.retry.jitter" => retry.jitter = parse_item(path, value)?,
+            "client.retry.deadline" => retry.deadline = parse_item(path, value)?,
+            "client.retry.retryable-codes" => retry.retryable_codes = parse_item(path, value)?,
+            "client.retry.non-retryable-codes" => {
+                retry.non_retryable_codes = parse_item(path, value)?
+            }
+            "client.retry.overrides" => retry.overrides = parse_item(path, value)?,
+
+            "replication.connection-pool-size" => {
+                replication.connection_pool_size = parse_item(path, value)?
+            }
+            "replication.thread-count" => replication.thread_count = parse_item(path, value)?,
+            "replication.rollover" => replication.rollover = parse_item(path, value)?,
+            "replication.rollover.max-bytes" => {
+                replication.rollover.max_bytes = parse_item(path, value)?
+            }
+            "replication.rollover.max-records" => {
+                replication.rollover.max_records = parse_item(path, value)?
+            }
+            "replication.rollover.max-age" => {
+                replication.rollover.max_age = parse_item(path, value)?
+            }
+            "replication.inflight" => replication.inflight = parse_item(path, value)?,
+            "replication.inflight.max-requests" => {
+                replication.inflight.max_requests = parse_item(path, value)?
+            }
+            "replication.inflight.max-bytes" => {
+                replication.inflight.max_bytes = parse_item(path, value)?
+            }
+            "replication.inflight.stream-max-requests" => {
+                replication.inflight.stream_max_requests = parse_item(path, value)?
+            }
+            "replication.inflight.stream-max-bytes" => {
+                replication.inflight.stream_max_bytes = parse_item(path, value)?
+            }
+            "replication.inflight.block" => replication.inflight.block = parse_item(path, value)?,
+            "replication.hedge" => replication.hedge = parse_item(path, value)?,
+            "replication.hedge.percentile" => {
+                replication.hedge.percentile = parse_item(path, value)?
+            }
+            "replication.hedge.min-delay-ms" => {
+                replication.hedge.min_delay_ms = parse_item(path, value)?
+            }
+
+            "object-storage.cluster" => object_storage.cluster = value.to_owned(),
+            "object-storage.endpoint" => object_storage.endpoint = value.to_owned(),
+            "object-storage.bucket" => object_storage.bucket = value.to_owned(),
+            "object-storage.region" => object_storage.region = value.to_owned(),
+            "object-storage.object-size" => object_storage.object_size = parse_item(path, value)?,
+            "object-storage.part-size" => object_storage.part_size = parse_item(path, value)?,
+            "object-storage.max-cache-size" => {
+                object_storage.max_cache_size = parse_item(path, value)?
+            }
+            "object-storage.cache-low-watermark" => {
+                object_storage.cache_low_watermark = parse_item(path, value)?
+            }
+            "object-storage.force-flush-secs" => {
+                object_storage.force_flush_secs = parse_item(path, value)?
+            }
+            "object-storage.compaction-interval-secs" => {
+                object_storage.compaction_interval_secs = parse_item(path, value)?
+            }
+
+            _ => return Err(ConfigurationError::UnknownItem(path.to_owned())),
+        }
+        Ok(())
+    }
+
+    /// Apply environment variables starting with `{prefix}_` on top of current configuration.
+    ///
+    /// Sections of the item path are separated by double underscores and names are case-insensitive,
+    /// for example, `ES_CLIENT__IO_TIMEOUT=20` sets `client.io-timeout` with prefix `ES`. Variables
+    /// that do not name any configuration item are ignored, as environment is shared with others.
+    pub fn apply_env(&mut self, prefix: &str) -> Result<(), ConfigurationError> {
+        let prefix = format!("{prefix}_");
+        let mut vars = std::env::vars()
+            .filter_map(|(name, value)| {
+                name.strip_prefix(&prefix)
+                    .map(|name| (name.split("__").collect::<Vec<_>>().join("."), value))
+            })
+            .collect::<Vec<_>>();
+        // Apply in a deterministic order, such that items of a section override the section itself.
+        vars.sort();
+        for (path, value) in vars {
+            match self.set(&path.to_lowercase(), &value) {
+                Ok(()) | Err(ConfigurationError::UnknownItem(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Check and apply configuration of clients, e.g. SDKs, which have neither server nor store.
+    pub fn check_client(&mut self) -> Result<(), ConfigurationError> {
+        if self.placement_driver.is_empty() {
+            return Err(ConfigurationError::InvalidValue(
+                "placement driver address is empty".to_owned(),
+            ));
+        }
+
+        if self.tick == 0 {
+            return Err(ConfigurationError::InvalidValue(
+                "tick should be positive".to_owned(),
+            ));
+        }
+
+        if self.client.connect_timeout == 0 || self.client.io_timeout == 0 {
+            return Err(ConfigurationError::InvalidValue(
+                "client connect-timeout and io-timeout should be positive".to_owned(),
+            ));
+        }
+
+        self.client.apply_deprecated();
+        self.client.retry.check()?;
+
+        if self.client.client_id.is_empty() {
+            let client_id = client_id();
+            self.client.client_id.push_str(&client_id);
+        }
+
+        if self.replication.thread_count == 0 {
+            return Err(ConfigurationError::InvalidValue(
+                "replication thread-count should be positive".to_owned(),
+            ));
+        }
+
+        if self.replication.connection_pool_size == 0 {
+            self.replication.connection_pool_size = num_cpus::get();
+        }
+
+        Ok(())
+    }
+
     pub fn connection_idle_duration(&self) -> Duration {
         Duration::from_millis(self.tick * self.server.connection_idle_duration)
     }
@@ -637,6 +1259,10 @@ impl Configuration {
     pub fn server_grace_period(&self) -> Duration {
         Duration::from_millis(self.tick * self.server.grace_period)
     }
+
+    pub fn server_max_throttle_time(&self) -> Duration {
+        Duration::from_millis(self.tick * self.server.quota.max_throttle_time)
+    }
 }
 
 #[cfg(test)]
@@ -665,6 +1291,34 @@ mod tests {
         assert_eq!(655360, config.store.rocksdb.flush_threshold);
 
         assert_eq!(2, config.replication.connection_pool_size);
+        assert_eq!(1024 * 1024 * 1024, config.replication.rollover.max_bytes);
+        assert_eq!(0, config.replication.rollover.max_records);
+        assert_eq!(36000, config.replication.rollover.max_age);
+        // Defaults in code agree with the shipped configuration file.
+        assert_eq!(super::Rollover::default(), config.replication.rollover);
+        assert_eq!(65536, config.replication.inflight.max_requests);
+        assert_eq!(512 * 1024 * 1024, config.replication.inflight.max_bytes);
+        assert_eq!(8192, config.replication.inflight.stream_max_requests);
+        assert_eq!(
+            64 * 1024 * 1024,
+            config.replication.inflight.stream_max_bytes
+        );
+        assert!(config.replication.inflight.block);
+        assert_eq!(99.0, config.replication.hedge.percentile);
+        assert_eq!(5, config.replication.hedge.min_delay_ms);
+        assert_eq!(None, config.client.zone);
+        assert!(!config.server.quota.enabled());
+        assert_eq!(super::LbPolicy::LeaderOnly, config.client.pd_lb_policy);
+        assert_eq!(
+            super::LbPolicy::PickFirst,
+            config.client.range_server_lb_policy
+        );
+        assert_eq!(50, config.server.quota.max_throttle_time);
+        assert_eq!(super::Compression::None, config.client.compression);
+        assert_eq!(
+            vec![super::Compression::Lz4, super::Compression::Zstd],
+            config.server.compressions
+        );
         Ok(())
     }
 
@@ -702,6 +1356,152 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_retry() -> Result<(), Box<dyn Error>> {
+        let s = r#"
+            max-attempt: 5
+            initial-backoff: 1
+            max-backoff: 20
+            multiplier: 2.0
+            jitter: 0.1
+            deadline: 0
+            retryable-codes: ["CONNECT_TIMEOUT", "CONNECTION_BROKEN"]
+            non-retryable-codes: ["EXPIRED_STREAM_EPOCH"]
+            overrides:
+              append:
+                max-attempt: 1
+        "#;
+        let retry: super::Retry = serde_yaml::from_str(s)?;
+        retry.check()?;
+        assert_eq!(5, retry.of("fetch").max_attempt);
+        assert_eq!(1, retry.of("append").max_attempt);
+        assert_eq!(20, retry.of("append").max_backoff);
+
+        let mut invalid = retry.clone();
+        invalid.retryable_codes.push("NO_SUCH_CODE".to_owned());
+        assert!(invalid.check().is_err());
+        let mut invalid = retry.clone();
+        invalid.retryable_codes.push("PD_NOT_LEADER".to_owned());
+        assert!(invalid.check().is_err());
+
+        super::Retry::default().check()?;
+
+        // Absent fields of a partial block fall back to defaults.
+        let retry: super::Retry = serde_yaml::from_str("max-attempt: 7")?;
+        assert_eq!(7, retry.max_attempt);
+        assert_eq!(super::Retry::default().max_backoff, retry.max_backoff);
+        assert_eq!(
+            super::Retry::default().retryable_codes,
+            retry.retryable_codes
+        );
+
+        // The deprecated `client.max-attempt` is moved onto the retry policy.
+        let mut client: super::Client = serde_yaml::from_str(
+            r#"
+            connect-timeout: 20
+            io-timeout: 10
+            client-id: ""
+            max-attempt: 5
+            heartbeat-interval: 30
+            refresh-pd-cluster-interval: 300
+        "#,
+        )?;
+        assert_eq!(Some(5), client.max_attempt);
+        client.apply_deprecated();
+        assert_eq!(None, client.max_attempt);
+        assert_eq!(5, client.retry.max_attempt);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set() -> Result<(), Box<dyn Error>> {
+        let mut config = Configuration {
+            placement_driver: "10.0.0.1:12378".to_owned(),
+            ..Default::default()
+        };
+        config.set("client.io-timeout", "20")?;
+        config.set("client.retry.max_attempt", "5")?;
+        config.set("client.retry.overrides", "{append: {max-attempt: 1}}")?;
+        config.set("object-storage.max-cache-size", "1024")?;
+        assert_eq!(20, config.client.io_timeout);
+        assert_eq!(5, config.client.retry.of("fetch").max_attempt);
+        assert_eq!(1, config.client.retry.of("append").max_attempt);
+        assert_eq!(1024, config.object_storage.max_cache_size);
+        assert_eq!("10.0.0.1:12378", config.placement_driver);
+
+        assert!(matches!(
+            config.set("client.no-such-item", "1"),
+            Err(super::ConfigurationError::UnknownItem(_))
+        ));
+        assert!(matches!(
+            config.set("server.worker-cpu-set", "1"),
+            Err(super::ConfigurationError::UnknownItem(_))
+        ));
+        assert!(config.set("client.io-timeout", "abc").is_err());
+        assert_eq!(20, config.client.io_timeout);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_partial_yaml_file() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("es-partial-{}.yaml", std::process::id()));
+        std::fs::write(
+            &path,
+            "client:\n  io-timeout: 5\n  retry:\n    max-attempt: 3\nobject-storage:\n  endpoint: http://127.0.0.1:9000\n",
+        )?;
+        let config = Configuration::from_yaml_file(&path);
+        std::fs::remove_file(&path)?;
+        let config = config?;
+        assert_eq!(5, config.client.io_timeout);
+        assert_eq!(3, config.client.retry.max_attempt);
+        assert_eq!("http://127.0.0.1:9000", config.object_storage.endpoint);
+
+        // Absent items and sections fall back to defaults.
+        let default = Configuration::default();
+        assert_eq!(
+            default.client.connect_timeout,
+            config.client.connect_timeout
+        );
+        assert_eq!(default.tick, config.tick);
+        assert_eq!(
+            default.replication.thread_count,
+            config.replication.thread_count
+        );
+        assert_eq!(
+            default.object_storage.max_cache_size,
+            config.object_storage.max_cache_size
+        );
+        assert_eq!(default.store.segment_size, config.store.segment_size);
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_env() -> Result<(), Box<dyn Error>> {
+        std::env::set_var("ES_TEST_CONFIG_CLIENT__CONNECT_TIMEOUT", "30");
+        std::env::set_var("ES_TEST_CONFIG_REPLICATION__THREAD_COUNT", "8");
+        std::env::set_var("ES_TEST_CONFIG_UNKNOWN", "1");
+        let mut config = Configuration::default();
+        config.apply_env("ES_TEST_CONFIG")?;
+        assert_eq!(30, config.client.connect_timeout);
+        assert_eq!(8, config.replication.thread_count);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_client() -> Result<(), Box<dyn Error>> {
+        let mut config = Configuration::default();
+        config.check_client()?;
+        assert!(!config.client.client_id.is_empty());
+
+        config.replication.thread_count = 0;
+        assert!(config.check_client().is_err());
+
+        let mut config = Configuration::default();
+        config.client.retry.max_attempt = 0;
+        assert!(config.check_client().is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_parse_cpu_set() {
         assert_eq!(vec![0], super::parse_cpu_set("0"));
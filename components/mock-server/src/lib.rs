@@ -7,17 +7,20 @@ use log::{debug, error, info, trace, warn};
 use model::payload::Payload;
 use protocol::rpc::header::{
     AppendResponseT, AppendResultEntryT, CreateRangeRequest, CreateRangeResponseT,
-    CreateStreamRequest, CreateStreamResponseT, DeleteStreamRequest, DeleteStreamResponseT,
-    DescribePlacementDriverClusterRequest, DescribePlacementDriverClusterResponseT,
-    DescribeStreamRequest, DescribeStreamResponseT, ErrorCode, EventType, HeartbeatRequest,
-    HeartbeatResponseT, IdAllocationRequest, IdAllocationResponseT, ListRangeRequest,
-    ListRangeResponseT, ListResourceRequest, ListResourceResponseT, ObjT, OffloadOwnerT,
-    OperationCode, PlacementDriverClusterT, PlacementDriverNodeT, RangeServerT, RangeT,
-    ReportMetricsRequest, ReportMetricsResponseT, ResourceEventT, ResourceT, ResourceType,
+    CreateStreamRequest, CreateStreamResponseT, DeleteRangeRequest, DeleteRangeResponseT,
+    DeleteStreamRequest, DeleteStreamResponseT, DescribePlacementDriverClusterRequest,
+    DescribePlacementDriverClusterResponseT, DescribeStreamRequest, DescribeStreamResponseT,
+    ErrorCode, EventType, HeartbeatRequest, HeartbeatResponseT, IdAllocationRequest,
+    IdAllocationResponseT, KeyValueT, ListRangeRequest, ListRangeResponseT, ListResourceRequest,
+    ListResourceResponseT, ObjT, OffloadOwnerT, OperationCode, PlacementDriverClusterT,
+    PlacementDriverNodeT, PutRequest, PutResponseT, RangeRequest, RangeResponseT, RangeServerT,
+    RangeT, ReportMetricsRequest, ReportMetricsResponseT, ResourceEventT, ResourceT, ResourceType,
     SealKind, SealRangeRequest, SealRangeResponseT, StatusT, StreamT, TrimStreamRequest,
     TrimStreamResponseT, UpdateStreamRequest, UpdateStreamResponseT, WatchResourceRequest,
     WatchResourceResponseT,
 };
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::time::{self, Duration, UNIX_EPOCH};
 
 use tokio::sync::oneshot;
@@ -354,6 +357,43 @@ pub async fn run_listener() -> u16 {
                                     }
                                 }
 
+                                OperationCode::KV_RANGE => {
+                                    response_frame.operation_code = OperationCode::KV_RANGE;
+                                    if let Some(buf) = frame.header.as_ref() {
+                                        if let Ok(req) = flatbuffers::root::<RangeRequest>(buf) {
+                                            serve_kv_range(&req, &mut response_frame);
+                                        } else {
+                                            error!("Failed to decode kv-range-request header");
+                                        }
+                                    }
+                                }
+
+                                OperationCode::KV_PUT => {
+                                    response_frame.operation_code = OperationCode::KV_PUT;
+                                    if let Some(buf) = frame.header.as_ref() {
+                                        if let Ok(req) = flatbuffers::root::<PutRequest>(buf) {
+                                            serve_kv_put(&req, &mut response_frame);
+                                        } else {
+                                            error!("Failed to decode kv-put-request header");
+                                        }
+                                    }
+                                }
+
+                                OperationCode::KV_DELETE_RANGE => {
+                                    response_frame.operation_code = OperationCode::KV_DELETE_RANGE;
+                                    if let Some(buf) = frame.header.as_ref() {
+                                        if let Ok(req) =
+                                            flatbuffers::root::<DeleteRangeRequest>(buf)
+                                        {
+                                            serve_kv_delete_range(&req, &mut response_frame);
+                                        } else {
+                                            error!(
+                                                "Failed to decode kv-delete-range-request header"
+                                            );
+                                        }
+                                    }
+                                }
+
                                 OperationCode::DELETE_STREAM => {
                                     response_frame.operation_code = OperationCode::DELETE_STREAM;
                                     if let Some(buf) = frame.header.as_ref() {
@@ -672,6 +712,109 @@ fn serve_trim_stream(_req: &TrimStreamRequest, response_frame: &mut Frame) {
     response_frame.header = Some(Bytes::copy_from_slice(data));
 }
 
+thread_local! {
+    /// Key-value pairs put to the mock placement driver.
+    static KV: RefCell<BTreeMap<Vec<u8>, Vec<u8>>> = RefCell::new(BTreeMap::new());
+}
+
+/// Keys of `[key, range_end)`, or `key` only if `range_end` is absent.
+fn kv_keys(key: &[u8], range_end: Option<&[u8]>) -> Vec<Vec<u8>> {
+    KV.with(|kv| {
+        kv.borrow()
+            .keys()
+            .filter(|k| match range_end {
+                Some([0]) => k.as_slice() >= key,
+                Some(end) => k.as_slice() >= key && k.as_slice() < end,
+                None => k.as_slice() == key,
+            })
+            .cloned()
+            .collect()
+    })
+}
+
+fn ok_status() -> Box<StatusT> {
+    let mut status = StatusT::default();
+    status.code = ErrorCode::OK;
+    status.message = Some("OK".to_string());
+    Box::new(status)
+}
+
+fn serve_kv_range(req: &RangeRequest, response_frame: &mut Frame) {
+    let key = req.key().map(|k| k.bytes()).unwrap_or_default();
+    let mut keys = kv_keys(key, req.range_end().map(|end| end.bytes()));
+    if req.limit() > 0 {
+        keys.truncate(req.limit() as usize);
+    }
+    let mut response = RangeResponseT::default();
+    response.status = ok_status();
+    response.kvs = Some(KV.with(|kv| {
+        let kv = kv.borrow();
+        keys.into_iter()
+            .map(|k| {
+                let mut key_value = KeyValueT::default();
+                key_value.value = kv.get(&k).cloned();
+                key_value.key = Some(k);
+                key_value
+            })
+            .collect()
+    }));
+
+    let mut builder = flatbuffers::FlatBufferBuilder::new();
+    let resp = response.pack(&mut builder);
+    builder.finish(resp, None);
+    let data = builder.finished_data();
+    response_frame.flag_response();
+    response_frame.header = Some(Bytes::copy_from_slice(data));
+}
+
+fn serve_kv_put(req: &PutRequest, response_frame: &mut Frame) {
+    let key = req.key().map(|k| k.bytes().to_vec()).unwrap_or_default();
+    let value = req.value().map(|v| v.bytes().to_vec()).unwrap_or_default();
+    let expected = req.expected_value().map(|v| v.bytes());
+    let applied = KV.with(|kv| {
+        let mut kv = kv.borrow_mut();
+        if req.compare() && kv.get(&key).map(|v| &v[..]) != expected {
+            return false;
+        }
+        kv.insert(key, value);
+        true
+    });
+    let mut response = PutResponseT::default();
+    response.status = ok_status();
+    if !applied {
+        response.status.code = ErrorCode::PRECONDITION_FAILED;
+        response.status.message = Some("compare failed".to_string());
+    }
+
+    let mut builder = flatbuffers::FlatBufferBuilder::new();
+    let resp = response.pack(&mut builder);
+    builder.finish(resp, None);
+    let data = builder.finished_data();
+    response_frame.flag_response();
+    response_frame.header = Some(Bytes::copy_from_slice(data));
+}
+
+fn serve_kv_delete_range(req: &DeleteRangeRequest, response_frame: &mut Frame) {
+    let key = req.key().map(|k| k.bytes()).unwrap_or_default();
+    let keys = kv_keys(key, req.range_end().map(|end| end.bytes()));
+    KV.with(|kv| {
+        let mut kv = kv.borrow_mut();
+        keys.iter().for_each(|k| {
+            kv.remove(k);
+        });
+    });
+    let mut response = DeleteRangeResponseT::default();
+    response.status = ok_status();
+    response.deleted = keys.len() as i64;
+
+    let mut builder = flatbuffers::FlatBufferBuilder::new();
+    let resp = response.pack(&mut builder);
+    builder.finish(resp, None);
+    let data = builder.finished_data();
+    response_frame.flag_response();
+    response_frame.header = Some(Bytes::copy_from_slice(data));
+}
+
 fn serve_delete_stream(_req: &DeleteStreamRequest, response_frame: &mut Frame) {
     let mut response = DeleteStreamResponseT::default();
     let mut status = StatusT::default();
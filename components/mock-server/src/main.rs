@@ -0,0 +1,10 @@
+//! Standalone mock server, which prints its listening port to stdout, for testing clients that
+//! are not written in Rust, e.g. the C binding of the frontend.
+
+fn main() {
+    tokio_uring::start(async {
+        let port = mock_server::run_listener().await;
+        println!("{port}");
+        std::future::pending::<()>().await;
+    });
+}
@@ -13,6 +13,8 @@ pub struct ObjectMetadata {
     pub end_offset_delta: u32,
     pub data_len: u32,
     pub sparse_index: Bytes,
+    /// Times the object is rewritten by compaction.
+    pub generation: u16,
     pub key: Option<String>,
 }
 
@@ -26,6 +28,7 @@ impl ObjectMetadata {
             end_offset_delta: 0,
             data_len: 0,
             sparse_index: Bytes::new(),
+            generation: 0,
             key: None,
         }
     }
@@ -111,16 +114,20 @@ impl ObjectMetadata {
             self.range_index,
             self.epoch,
             self.start_offset,
+            self.generation,
         ));
     }
 }
 
+/// Key of the object in object storage. Objects rewritten by compaction are suffixed by their
+/// generation, keeping keys of objects never compacted.
 pub fn gen_object_key(
     cluster: &str,
     stream_id: u64,
     range_index: u32,
     epoch: u16,
     start_offset: u64,
+    generation: u16,
 ) -> String {
     // reverse the ((stream_id * 31 + range_index) * 31 + start_offset) as prefix to make the object key dispersed.
     // prefix_number calculate formula references the Java hash code algorithm.
@@ -130,7 +137,13 @@ pub fn gen_object_key(
         .0;
     prefix_number = prefix_number.overflowing_add(start_offset).0;
     let prefix: String = format!("{:x}", prefix_number).chars().rev().collect();
-    format!("{prefix}_{cluster}_{stream_id:x}_{range_index:x}_{epoch:x}_{start_offset:x}",)
+    let key =
+        format!("{prefix}_{cluster}_{stream_id:x}_{range_index:x}_{epoch:x}_{start_offset:x}",);
+    if generation > 0 {
+        format!("{key}_{generation:x}")
+    } else {
+        key
+    }
 }
 
 impl From<&ObjectMetadataT> for ObjectMetadata {
@@ -148,6 +161,7 @@ impl From<&ObjectMetadataT> for ObjectMetadata {
             end_offset_delta: t.end_offset_delta as u32,
             data_len: t.data_len as u32,
             sparse_index,
+            generation: 0,
             key: Some(t.key.clone()),
         }
     }
@@ -175,6 +189,7 @@ impl From<&ObjT> for ObjectMetadata {
             end_offset_delta: t.end_offset_delta as u32,
             data_len: t.data_len as u32,
             sparse_index: t.sparse_index.clone().map(Bytes::from).unwrap_or_default(),
+            generation: t.generation as u16,
             key: None,
         }
     }
@@ -190,6 +205,7 @@ impl From<&ObjectMetadata> for ObjT {
         t.end_offset_delta = m.end_offset_delta as i32;
         t.data_len = m.data_len as i32;
         t.sparse_index = Some(m.sparse_index.to_vec());
+        t.generation = m.generation as i16;
         t
     }
 }
@@ -38,7 +38,7 @@ impl Payload {
         }
 
         let magic_code = cursor.get_i8();
-        if magic_code != RecordMagic::Magic0 as i8 {
+        if RecordMagic::try_from(magic_code).is_err() {
             return Err(DecodeError::InvalidMagic);
         }
 
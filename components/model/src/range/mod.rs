@@ -2,7 +2,7 @@ use std::fmt::{self, Display, Formatter};
 
 use derivative::Derivative;
 use log::info;
-use protocol::rpc::header::{OffloadOwnerT, RangeServerT, RangeT};
+use protocol::rpc::header::{OffloadOwnerT, RangeServerT, RangeT, ReplicaLayout as ReplicaLayoutT};
 
 use crate::range_server::RangeServer;
 
@@ -36,6 +36,18 @@ impl From<&OffloadOwnerT> for OffloadOwner {
     }
 }
 
+/// Layout of range replicas.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ReplicaLayout {
+    /// Each replica holds a full copy of the records.
+    #[default]
+    Full,
+
+    /// Each record batch is Reed-Solomon encoded into `data_shards` data shards and
+    /// `replica_count - data_shards` parity shards, each of which is held by a replica.
+    ReedSolomon { data_shards: u8 },
+}
+
 /// Representation of a stream range in form of `[start, end)` in which `start` is inclusive and `end` is exclusive.
 /// If `start` == `end`, there will be no valid records in the range.
 ///
@@ -73,6 +85,9 @@ pub struct RangeMetadata {
     /// The owner of the range which offloads the data to the object storage.
     /// If [`replica`] is empty, [`offload_owner`] will be None.
     offload_owner: Option<OffloadOwner>,
+
+    /// Layout of the range replicas.
+    layout: ReplicaLayout,
 }
 
 impl RangeMetadata {
@@ -91,6 +106,7 @@ impl RangeMetadata {
                 server_id: 0,
                 epoch: 0,
             }),
+            layout: ReplicaLayout::Full,
         }
     }
 
@@ -116,6 +132,7 @@ impl RangeMetadata {
                 server_id: 0,
                 epoch: 0,
             }),
+            layout: ReplicaLayout::Full,
         }
     }
 
@@ -186,6 +203,14 @@ impl RangeMetadata {
         &self.offload_owner
     }
 
+    pub fn layout(&self) -> ReplicaLayout {
+        self.layout
+    }
+
+    pub fn set_layout(&mut self, layout: ReplicaLayout) {
+        self.layout = layout;
+    }
+
     pub fn has_end(&self) -> bool {
         self.end.is_some()
     }
@@ -236,6 +261,15 @@ impl From<&RangeMetadata> for RangeT {
             .offload_owner
             .as_ref()
             .map(|o| Box::new(OffloadOwnerT::from(o)));
+        match value.layout {
+            ReplicaLayout::Full => {
+                range.replica_layout = ReplicaLayoutT::REPLICA_LAYOUT_FULL;
+            }
+            ReplicaLayout::ReedSolomon { data_shards } => {
+                range.replica_layout = ReplicaLayoutT::REPLICA_LAYOUT_REED_SOLOMON;
+                range.data_shards = data_shards as i8;
+            }
+        }
         range
     }
 }
@@ -264,6 +298,12 @@ impl From<&RangeT> for RangeMetadata {
                 .offload_owner
                 .as_ref()
                 .map(|o| OffloadOwner::from(o.as_ref())),
+            layout: match value.replica_layout {
+                ReplicaLayoutT::REPLICA_LAYOUT_REED_SOLOMON => ReplicaLayout::ReedSolomon {
+                    data_shards: value.data_shards as u8,
+                },
+                _ => ReplicaLayout::Full,
+            },
         }
     }
 }
@@ -5,6 +5,10 @@ pub struct RangeServer {
     pub server_id: i32,
     pub advertise_address: String,
     pub state: RangeServerState,
+    /// Availability zone of the range server, if labeled.
+    pub zone: Option<String>,
+    /// Unix domain socket the range server listens on alongside `advertise_address`, as `unix://path`.
+    pub unix_address: Option<String>,
 }
 
 impl RangeServer {
@@ -16,6 +20,8 @@ impl RangeServer {
             server_id: id,
             advertise_address: address.as_ref().to_owned(),
             state,
+            zone: None,
+            unix_address: None,
         }
     }
 }
@@ -26,6 +32,8 @@ impl From<&RangeServer> for RangeServerT {
         ret.server_id = value.server_id;
         ret.advertise_addr = value.advertise_address.clone();
         ret.state = value.state;
+        ret.zone = value.zone.clone();
+        ret.unix_addr = value.unix_address.clone();
         ret
     }
 }
@@ -36,6 +44,8 @@ impl From<&RangeServerT> for RangeServer {
             server_id: value.server_id,
             advertise_address: value.advertise_addr.clone(),
             state: value.state,
+            zone: value.zone.clone(),
+            unix_address: value.unix_addr.clone(),
         }
     }
 }
@@ -5,10 +5,23 @@ use std::io::Cursor;
 
 use crate::{error::DecodeError, RecordBatch};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(i8)]
 pub enum RecordMagic {
     Magic0 = 0x22, // The first version of the record batch format.
+    Magic1 = 0x23, // The payload is a sequence of records, see `crate::record::magic1`.
+}
+
+impl TryFrom<i8> for RecordMagic {
+    type Error = DecodeError;
+
+    fn try_from(magic: i8) -> Result<Self, Self::Error> {
+        match magic {
+            x if x == RecordMagic::Magic0 as i8 => Ok(RecordMagic::Magic0),
+            x if x == RecordMagic::Magic1 as i8 => Ok(RecordMagic::Magic1),
+            _ => Err(DecodeError::InvalidMagic),
+        }
+    }
 }
 
 /// Relative offset of `BaseOffset` within `RecordBatch`.
@@ -27,7 +40,10 @@ pub const MIN_RECORD_BATCH_LEN: usize = 1 + 4 + 4;
 ///  PayloadLength => Int32
 ///  BatchPayload => Bytes
 ///
-/// Currently, the payload of the record batch is a raw bytes buffer, we may support a specific format in the future.
+/// The layout is shared by all magics. With magic 0, the payload of the record batch is a raw bytes buffer
+/// whose format is up to applications. With magic 1, the payload is a sequence of records, each of which has
+/// its own offset delta, timestamp delta, key, value and headers, see `crate::record::magic1`. Either way,
+/// the storage and replication layers treat the payload as opaque bytes.
 ///
 /// The RecordBatchMeta is complying with the layout of the FlatBuffers schema, please refer to the model.fbs in the protocol crate.
 #[derive(Debug, Default)]
@@ -53,7 +69,7 @@ impl FlatRecordBatch {
         let meta_buf = fbb.finished_data();
 
         FlatRecordBatch {
-            magic: Some(RecordMagic::Magic0 as i8),
+            magic: Some(record_batch.magic as i8),
             metadata: Bytes::copy_from_slice(meta_buf),
             payload: record_batch.payload,
         }
@@ -71,10 +87,7 @@ impl FlatRecordBatch {
         // Read the magic
         let magic = cursor.get_i8();
 
-        // We only support the Magic0 for now.
-        if magic != RecordMagic::Magic0 as i8 {
-            return Err(DecodeError::InvalidMagic);
-        }
+        RecordMagic::try_from(magic)?;
 
         // Read the metadata length from the given buf
         let metadata_len = cursor.get_i32() as usize;
@@ -152,8 +165,13 @@ impl FlatRecordBatch {
             .map_err(|_| DecodeError::InvalidDataFormat)?;
 
         let batch_meta_t = batch_meta.unpack();
+        let magic = match self.magic {
+            Some(magic) => RecordMagic::try_from(magic)?,
+            None => RecordMagic::Magic0,
+        };
 
         Ok(RecordBatch {
+            magic,
             metadata: batch_meta_t,
             payload: self.payload,
         })
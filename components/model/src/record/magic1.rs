@@ -0,0 +1,371 @@
+//! Payload format of record batches with `RecordMagic::Magic1`.
+//!
+//! The payload is a sequence of records, whose fields are big-endian:
+//!
+//! ```text
+//! Record =>
+//!   Length => Int32, number of bytes following this field
+//!   OffsetDelta => Int32, relative to the base offset of the batch
+//!   TimestampDelta => Int64, relative to the base timestamp of the batch
+//!   KeyLength => Int32, -1 for a null key
+//!   Key => Bytes
+//!   ValueLength => Int32, -1 for a null value
+//!   Value => Bytes
+//!   HeaderCount => Int32
+//!   Headers => [Header]
+//!
+//! Header =>
+//!   KeyLength => Int32
+//!   Key => UTF-8 String
+//!   ValueLength => Int32, -1 for a null value
+//!   Value => Bytes
+//! ```
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use super::{flat_record::RecordMagic, RecordBatch};
+use crate::error::{DecodeError, RecordError};
+
+const NULL_LENGTH: i32 = -1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub key: String,
+    pub value: Option<Bytes>,
+}
+
+impl Header {
+    pub fn new(key: String, value: Option<Bytes>) -> Self {
+        Self { key, value }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub offset: i64,
+    pub timestamp: i64,
+    pub key: Option<Bytes>,
+    pub value: Option<Bytes>,
+    pub headers: Vec<Header>,
+}
+
+/// Builds a record batch of `RecordMagic::Magic1` from individual records.
+#[derive(Debug, Default)]
+pub struct RecordsBuilder {
+    base_timestamp: Option<i64>,
+    count: i32,
+    payload: BytesMut,
+}
+
+impl RecordsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a record to the batch. Base timestamp of the batch is the timestamp of its first record.
+    ///
+    /// # Returns
+    /// Offset delta of the record within the batch.
+    pub fn append(
+        &mut self,
+        timestamp: i64,
+        key: Option<Bytes>,
+        value: Option<Bytes>,
+        headers: &[Header],
+    ) -> i32 {
+        let base_timestamp = *self.base_timestamp.get_or_insert(timestamp);
+        let offset_delta = self.count;
+
+        let mut body = BytesMut::new();
+        body.put_i32(offset_delta);
+        body.put_i64(timestamp - base_timestamp);
+        put_nullable(&mut body, key.as_deref());
+        put_nullable(&mut body, value.as_deref());
+        body.put_i32(headers.len() as i32);
+        for header in headers {
+            body.put_i32(header.key.len() as i32);
+            body.extend_from_slice(header.key.as_bytes());
+            put_nullable(&mut body, header.value.as_deref());
+        }
+
+        self.payload.put_i32(body.len() as i32);
+        self.payload.extend_from_slice(&body);
+        self.count += 1;
+        offset_delta
+    }
+
+    /// Number of records appended.
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Bytes of the payload of records appended.
+    pub fn size(&self) -> usize {
+        self.payload.len()
+    }
+
+    /// Build the record batch. Range index and base offset are placeholders, which are assigned on
+    /// append.
+    pub fn build(self, stream_id: i64) -> Result<RecordBatch, RecordError> {
+        let mut builder = RecordBatch::new_builder()
+            .with_magic(RecordMagic::Magic1)
+            .with_stream_id(stream_id)
+            .with_range_index(0)
+            .with_base_offset(0)
+            .with_last_offset_delta(self.count)
+            .with_payload(self.payload.freeze());
+        if let Some(base_timestamp) = self.base_timestamp {
+            builder = builder.with_base_timestamp(base_timestamp);
+        }
+        builder.build()
+    }
+}
+
+fn put_nullable(buf: &mut BytesMut, data: Option<&[u8]>) {
+    match data {
+        Some(data) => {
+            buf.put_i32(data.len() as i32);
+            buf.extend_from_slice(data);
+        }
+        None => buf.put_i32(NULL_LENGTH),
+    }
+}
+
+/// Iterator over records in the payload of a record batch with `RecordMagic::Magic1`.
+///
+/// Iteration stops after the first decode error.
+#[derive(Debug, Clone)]
+pub struct RecordIter {
+    base_offset: i64,
+    base_timestamp: i64,
+    payload: Bytes,
+}
+
+impl RecordIter {
+    /// Iterate records in `payload` of a record batch with the given base offset and timestamp.
+    pub fn new(base_offset: i64, base_timestamp: i64, payload: Bytes) -> Self {
+        Self {
+            base_offset,
+            base_timestamp,
+            payload,
+        }
+    }
+
+    fn decode_next(&mut self) -> Result<Record, DecodeError> {
+        if self.payload.remaining() < 4 {
+            return Err(DecodeError::DataLengthMismatch);
+        }
+        let len = self.payload.get_i32();
+        if len < 0 || self.payload.remaining() < len as usize {
+            return Err(DecodeError::DataLengthMismatch);
+        }
+        let mut body = self.payload.split_to(len as usize);
+
+        ensure_remaining(&body, 4 + 8)?;
+        let offset_delta = body.get_i32();
+        let timestamp_delta = body.get_i64();
+        let key = get_nullable(&mut body)?;
+        let value = get_nullable(&mut body)?;
+        ensure_remaining(&body, 4)?;
+        let header_count = body.get_i32();
+        let mut headers = Vec::with_capacity(header_count.max(0) as usize);
+        for _ in 0..header_count {
+            let key = get_nullable(&mut body)?.ok_or(DecodeError::InvalidDataFormat)?;
+            let key =
+                String::from_utf8(key.to_vec()).map_err(|_| DecodeError::InvalidDataFormat)?;
+            let value = get_nullable(&mut body)?;
+            headers.push(Header { key, value });
+        }
+
+        Ok(Record {
+            offset: self.base_offset + i64::from(offset_delta),
+            timestamp: self.base_timestamp + timestamp_delta,
+            key,
+            value,
+            headers,
+        })
+    }
+}
+
+impl Iterator for RecordIter {
+    type Item = Result<Record, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.payload.is_empty() {
+            return None;
+        }
+        let record = self.decode_next();
+        if record.is_err() {
+            self.payload.clear();
+        }
+        Some(record)
+    }
+}
+
+/// Rebuild `payload` of a record batch with `RecordMagic::Magic1`, keeping records for which `retain`
+/// returns true. Records kept are copied as is, so they keep their offsets and timestamps.
+///
+/// # Returns
+/// The new payload, or `None` if all records are kept.
+pub fn retain_records<F>(
+    base_offset: i64,
+    base_timestamp: i64,
+    payload: &Bytes,
+    mut retain: F,
+) -> Result<Option<Bytes>, DecodeError>
+where
+    F: FnMut(&Record) -> bool,
+{
+    let mut iter = RecordIter::new(base_offset, base_timestamp, payload.clone());
+    let mut kept = BytesMut::with_capacity(payload.len());
+    let mut position = 0;
+    let mut dropped = false;
+    loop {
+        let Some(record) = iter.next() else {
+            break;
+        };
+        let record = record?;
+        let end = payload.len() - iter.payload.len();
+        if retain(&record) {
+            kept.extend_from_slice(&payload[position..end]);
+        } else {
+            dropped = true;
+        }
+        position = end;
+    }
+    Ok(dropped.then(|| kept.freeze()))
+}
+
+fn ensure_remaining(buf: &Bytes, len: usize) -> Result<(), DecodeError> {
+    if buf.remaining() < len {
+        return Err(DecodeError::DataLengthMismatch);
+    }
+    Ok(())
+}
+
+fn get_nullable(buf: &mut Bytes) -> Result<Option<Bytes>, DecodeError> {
+    ensure_remaining(buf, 4)?;
+    let len = buf.get_i32();
+    if len == NULL_LENGTH {
+        return Ok(None);
+    }
+    if len < 0 {
+        return Err(DecodeError::InvalidDataFormat);
+    }
+    ensure_remaining(buf, len as usize)?;
+    Ok(Some(buf.split_to(len as usize)))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{retain_records, Header, Record, RecordIter, RecordsBuilder};
+    use crate::{error::DecodeError, record::flat_record::FlatRecordBatch, RecordBatch};
+
+    #[test]
+    fn test_encode_and_decode_records() {
+        let mut builder = RecordsBuilder::new();
+        assert_eq!(
+            0,
+            builder.append(
+                1000,
+                Some(Bytes::from("k1")),
+                Some(Bytes::from("v1")),
+                &[Header::new("h".to_owned(), Some(Bytes::from("hv")))],
+            )
+        );
+        assert_eq!(1, builder.append(1005, None, None, &[]));
+        assert_eq!(2, builder.len());
+        let record_batch = builder.build(1).unwrap();
+        assert_eq!(1000, record_batch.base_timestamp());
+        assert_eq!(2, record_batch.last_offset_delta());
+
+        // Round trip through the storage and network layout, with offset assigned.
+        let (buffers, _) = FlatRecordBatch::from(record_batch).encode();
+        let mut buf = Bytes::from(buffers.concat());
+        let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf).unwrap();
+
+        let records: Vec<_> = record_batch
+            .records()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                Record {
+                    offset: 0,
+                    timestamp: 1000,
+                    key: Some(Bytes::from("k1")),
+                    value: Some(Bytes::from("v1")),
+                    headers: vec![Header::new("h".to_owned(), Some(Bytes::from("hv")))],
+                },
+                Record {
+                    offset: 1,
+                    timestamp: 1005,
+                    key: None,
+                    value: None,
+                    headers: vec![],
+                },
+            ],
+            records
+        );
+    }
+
+    #[test]
+    fn test_records_of_magic0() {
+        let record_batch = RecordBatch::new_builder()
+            .with_stream_id(1)
+            .with_range_index(0)
+            .with_base_offset(0)
+            .with_last_offset_delta(1)
+            .with_payload(Bytes::from("opaque"))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            record_batch.records(),
+            Err(DecodeError::InvalidMagic)
+        ));
+    }
+
+    #[test]
+    fn test_truncated_records() {
+        let mut builder = RecordsBuilder::new();
+        builder.append(0, Some(Bytes::from("k")), Some(Bytes::from("v")), &[]);
+        let record_batch = builder.build(1).unwrap();
+        let payload = record_batch.payload();
+        let mut iter = super::RecordIter::new(0, 0, payload.slice(..payload.len() - 1));
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_retain_records() {
+        let mut builder = RecordsBuilder::new();
+        builder.append(1000, Some(Bytes::from("a")), Some(Bytes::from("1")), &[]);
+        builder.append(1005, Some(Bytes::from("b")), None, &[]);
+        builder.append(1010, Some(Bytes::from("a")), Some(Bytes::from("2")), &[]);
+        let payload = builder.build(1).unwrap().payload();
+
+        assert_eq!(None, retain_records(10, 1000, &payload, |_| true).unwrap());
+        let kept = retain_records(10, 1000, &payload, |record| record.offset != 10)
+            .unwrap()
+            .unwrap();
+        let records: Vec<_> = RecordIter::new(10, 1000, kept)
+            .map(|record| record.map(|record| (record.offset, record.timestamp, record.key)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                (11, 1005, Some(Bytes::from("b"))),
+                (12, 1010, Some(Bytes::from("a")))
+            ],
+            records
+        );
+        assert!(retain_records(10, 1000, &payload.slice(..payload.len() - 1), |_| true).is_err());
+    }
+}
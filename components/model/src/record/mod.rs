@@ -1,13 +1,26 @@
 pub mod flat_record;
-use crate::error::RecordError;
+pub mod magic1;
+use crate::error::{DecodeError, RecordError};
 use bytes::Bytes;
 use chrono::prelude::*;
+use flat_record::RecordMagic;
 use protocol::flat_model::records::{KeyValueT, RecordBatchMetaT};
 use std::collections::HashMap;
 use std::fmt::{self};
 
+/// Bits of [`RecordBatch::flags`].
+pub mod flags {
+    /// The record batch is appended within a transaction, whose id is carried by the
+    /// `txn.id` property.
+    pub const TRANSACTIONAL: i16 = 1;
+
+    /// The record batch is a control record of a transaction rather than user data.
+    pub const CONTROL: i16 = 1 << 1;
+}
+
 #[derive(Debug, Clone)]
 pub struct RecordBatch {
+    magic: RecordMagic,
     metadata: RecordBatchMetaT,
     payload: Bytes,
 }
@@ -24,6 +37,23 @@ impl RecordBatch {
         RecordBatchBuilder::default()
     }
 
+    /// Return the magic of the record batch, which tells the format of its payload.
+    pub fn magic(&self) -> RecordMagic {
+        self.magic
+    }
+
+    /// Iterate records of the record batch, whose payload must be in the format of `RecordMagic::Magic1`.
+    pub fn records(&self) -> Result<magic1::RecordIter, DecodeError> {
+        if self.magic != RecordMagic::Magic1 {
+            return Err(DecodeError::InvalidMagic);
+        }
+        Ok(magic1::RecordIter::new(
+            self.base_offset(),
+            self.base_timestamp(),
+            self.payload.clone(),
+        ))
+    }
+
     /// Return the stream id of the record batch.
     pub fn stream_id(&self) -> i64 {
         self.metadata.stream_id
@@ -58,6 +88,24 @@ impl RecordBatch {
         self.metadata.properties.as_ref()
     }
 
+    /// Return value of the property named `key`, if any.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .properties
+            .as_ref()?
+            .iter()
+            .find(|kv| kv.key == key)
+            .map(|kv| kv.value.as_str())
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        self.flags() & flags::TRANSACTIONAL != 0
+    }
+
+    pub fn is_control(&self) -> bool {
+        self.flags() & flags::CONTROL != 0
+    }
+
     pub fn payload(&self) -> Bytes {
         self.payload.clone()
     }
@@ -65,6 +113,7 @@ impl RecordBatch {
 
 #[derive(Debug, Default)]
 pub struct RecordBatchBuilder {
+    magic: Option<RecordMagic>,
     stream_id: Option<i64>,
     range_index: Option<i32>,
     flags: Option<i16>,
@@ -76,6 +125,11 @@ pub struct RecordBatchBuilder {
 }
 
 impl RecordBatchBuilder {
+    pub fn with_magic(mut self, magic: RecordMagic) -> Self {
+        self.magic = Some(magic);
+        self
+    }
+
     pub fn with_stream_id(mut self, stream_id: i64) -> Self {
         self.stream_id = Some(stream_id);
         self
@@ -148,7 +202,11 @@ impl RecordBatchBuilder {
         metadata.base_timestamp = self.base_timestamp.unwrap_or(Utc::now().timestamp());
         metadata.properties = properties;
 
-        Ok(RecordBatch { metadata, payload })
+        Ok(RecordBatch {
+            magic: self.magic.unwrap_or(RecordMagic::Magic0),
+            metadata,
+            payload,
+        })
     }
 }
 
@@ -91,7 +91,8 @@ pub enum EventType {
 ///
 /// ### [`Modified`]
 /// A [`RangeServer`] with existing [`RangeServer::server_id`] sends a heartbeat to PD,
-/// and [`RangeServer::advertise_address`] or [`RangeServer::state`] is changed.
+/// and [`RangeServer::advertise_address`], [`RangeServer::state`], [`RangeServer::zone`] or
+/// [`RangeServer::unix_address`] is changed.
 ///
 /// ### [`Deleted`]
 /// Will never happen.
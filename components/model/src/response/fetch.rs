@@ -8,4 +8,7 @@ pub struct FetchResultSet {
     pub throttle: Option<Duration>,
     pub payload: Option<Bytes>,
     pub object_metadata_list: Option<Vec<ObjectMetadata>>,
+
+    /// Records of the range before this offset are persisted by the range server.
+    pub committed: Option<u64>,
 }
@@ -24,6 +24,49 @@ pub struct StreamMetadata {
     pub epoch: u64,
 
     pub deleted: bool,
+
+    /// Only the latest record of each key is kept in a compacted stream.
+    pub compacted: bool,
+
+    /// Time to keep tombstones of a compacted stream before they are removed by compaction.
+    pub delete_retention: Duration,
+
+    /// Rollover policy persisted on creation. Writers fall back to their configured default if absent.
+    pub rollover: Option<RolloverPolicy>,
+}
+
+/// Policy on which the writer of a stream proactively seals the current range and rolls over to a
+/// new one.
+///
+/// Placement driver picks a fresh replica set for the new range, so that load of long-living streams
+/// spreads across range servers and offload and trim work at a finer granularity.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RolloverPolicy {
+    /// Max bytes of record batches appended to a range.
+    pub max_bytes: Option<u64>,
+
+    /// Max records appended to a range.
+    pub max_records: Option<u64>,
+
+    /// Max age of a range since its creation.
+    pub max_age: Option<Duration>,
+}
+
+impl RolloverPolicy {
+    /// Check if a range with the given usage should be rolled over. Empty ranges are never rolled over.
+    pub fn due(&self, bytes: u64, records: u64, age: Duration) -> bool {
+        records > 0
+            && (self.max_bytes.is_some_and(|max| bytes >= max)
+                || self.max_records.is_some_and(|max| records >= max)
+                || self.max_age.is_some_and(|max| age >= max))
+    }
+
+    /// Persist the policy in `stream`, where zero thresholds mean unlimited.
+    pub fn encode(&self, stream: &mut StreamT) {
+        stream.rollover_max_bytes = self.max_bytes.unwrap_or(0) as i64;
+        stream.rollover_max_records = self.max_records.unwrap_or(0) as i64;
+        stream.rollover_max_age_ms = self.max_age.map_or(0, |age| age.as_millis() as i64);
+    }
 }
 
 /// Converter from `StreamT` to `Stream`.
@@ -37,6 +80,97 @@ impl From<&StreamT> for StreamMetadata {
             start_offset: stream.start_offset as u64,
             epoch: stream.epoch as u64,
             deleted: stream.deleted,
+            compacted: stream.compacted,
+            delete_retention: Duration::from_millis(stream.delete_retention_ms.max(0) as u64),
+            rollover: rollover_of(stream),
         }
     }
 }
+
+/// Rollover policy persisted in `StreamT`, where negative thresholds mean absent and zero unlimited.
+fn rollover_of(stream: &StreamT) -> Option<RolloverPolicy> {
+    if stream.rollover_max_bytes < 0
+        && stream.rollover_max_records < 0
+        && stream.rollover_max_age_ms < 0
+    {
+        return None;
+    }
+    let limit = |value: i64| u64::try_from(value).ok().filter(|v| *v > 0);
+    Some(RolloverPolicy {
+        max_bytes: limit(stream.rollover_max_bytes),
+        max_records: limit(stream.rollover_max_records),
+        max_age: limit(stream.rollover_max_age_ms).map(Duration::from_millis),
+    })
+}
+
+/// Converter from `StreamMetadata` to `StreamT`.
+impl From<&StreamMetadata> for StreamT {
+    fn from(stream: &StreamMetadata) -> Self {
+        let mut t = StreamT::default();
+        t.stream_id = stream.stream_id as i64;
+        t.replica = stream.replica as i8;
+        t.ack_count = stream.ack_count as i8;
+        t.retention_period_ms = stream.retention_period.as_millis() as i64;
+        t.start_offset = stream.start_offset as i64;
+        t.epoch = stream.epoch as i64;
+        t.deleted = stream.deleted;
+        t.compacted = stream.compacted;
+        t.delete_retention_ms = stream.delete_retention.as_millis() as i64;
+        if let Some(rollover) = &stream.rollover {
+            rollover.encode(&mut t);
+        }
+        t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use protocol::rpc::header::StreamT;
+
+    use super::{RolloverPolicy, StreamMetadata};
+
+    #[test]
+    fn test_due() {
+        let policy = RolloverPolicy {
+            max_bytes: Some(1024),
+            max_records: Some(10),
+            max_age: Some(Duration::from_secs(60)),
+        };
+        assert!(!policy.due(512, 5, Duration::from_secs(1)));
+        assert!(policy.due(1024, 5, Duration::from_secs(1)));
+        assert!(policy.due(512, 10, Duration::from_secs(1)));
+        assert!(policy.due(512, 5, Duration::from_secs(60)));
+        // Empty range is never rolled over
+        assert!(!policy.due(0, 0, Duration::from_secs(120)));
+        // Unlimited
+        assert!(!RolloverPolicy::default().due(u64::MAX, u64::MAX, Duration::MAX));
+    }
+
+    #[test]
+    fn test_rollover_conversion() {
+        // Streams created without a rollover policy
+        let t = StreamT::default();
+        assert_eq!(None, StreamMetadata::from(&t).rollover);
+        assert_eq!(
+            -1,
+            StreamT::from(&StreamMetadata::from(&t)).rollover_max_bytes
+        );
+
+        let rollover = RolloverPolicy {
+            max_bytes: None,
+            max_records: Some(100),
+            max_age: Some(Duration::from_secs(60)),
+        };
+        let metadata = StreamMetadata {
+            rollover: Some(rollover.clone()),
+            ..Default::default()
+        };
+        let t = StreamT::from(&metadata);
+        assert_eq!(0, t.rollover_max_bytes);
+        assert_eq!(100, t.rollover_max_records);
+        assert_eq!(60_000, t.rollover_max_age_ms);
+        assert_eq!(Some(rollover), StreamMetadata::from(&t).rollover);
+    }
+}
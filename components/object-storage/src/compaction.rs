@@ -0,0 +1,547 @@
+use std::{
+    collections::HashMap,
+    rc::Rc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use log::{debug, info, warn};
+use model::{
+    error::EsError,
+    object::{gen_footer, ObjectMetadata, BLOCK_DELIMITER},
+    record::{
+        flat_record::{FlatRecordBatch, RecordMagic},
+        magic1::{self, Record, RecordIter},
+    },
+};
+use opendal::Operator;
+use protocol::{flat_model::records::RecordBatchMeta, rpc::header::ErrorCode};
+
+use crate::{
+    range_offload::{gen_sparse_index, SPARSE_SIZE},
+    CompactedStream, ObjectManager, ShutdownRx,
+};
+
+/// Bytes of keys a compaction window tracks, beyond which the window ends at the current object.
+const WINDOW_BYTES: usize = 64 * 1024 * 1024;
+
+/// Bytes tracked per key besides the key itself, for its offset and the hash map entry.
+const KEY_OVERHEAD: usize = 32;
+
+/// Compactor rewrites offloaded objects of compacted streams, keeping only the latest record of
+/// each key. Records are in the format of `RecordMagic::Magic1`; those without a key, as well as
+/// record batches of other magics, are kept as is.
+///
+/// A stream is compacted window by window to bound memory. A window spans objects, in offset
+/// order, until keys of their records exceed `window_bytes`, and tracks the latest offset of each of
+/// these keys. Then objects owned by current server up to the end of the window are rewritten one
+/// by one, replacing records superseded within the window, as well as tombstones older than the
+/// delete retention. Records keep their offsets, so does the sparse index of objects.
+///
+/// A tombstone is removed only if older records of its key are removed along with it, that is, all
+/// objects before it are contiguous from the start of the stream, owned by current server and
+/// rewritten. Otherwise, removing it would bring back an older value of its key.
+///
+/// A rewritten object is written under the key of its next generation and committed before the
+/// previous one is deleted, so that readers never see an object partially written.
+pub(crate) struct Compactor<M: ObjectManager + 'static> {
+    op: Operator,
+    object_manager: Rc<M>,
+    cluster: String,
+    window_bytes: usize,
+}
+
+impl<M> Compactor<M>
+where
+    M: ObjectManager + 'static,
+{
+    pub(crate) fn new(op: Operator, object_manager: Rc<M>, cluster: &str) -> Self {
+        Self {
+            op,
+            object_manager,
+            cluster: cluster.to_owned(),
+            window_bytes: WINDOW_BYTES,
+        }
+    }
+
+    pub(crate) fn run(self, interval: Duration, shutdown_rx: ShutdownRx) {
+        tokio_uring::spawn(async move {
+            let mut notify_shutdown_rx = shutdown_rx.subscribe();
+            loop {
+                tokio::select! {
+                    _ = notify_shutdown_rx.recv() => {
+                        break;
+                    }
+                    () = tokio::time::sleep(interval) => {
+                        self.compact().await;
+                    }
+                }
+            }
+            info!("object storage compaction task shutdown");
+        });
+    }
+
+    async fn compact(&self) {
+        for stream in self.object_manager.compacted_streams() {
+            if let Err(e) = self.compact_stream(&stream).await {
+                warn!("compact stream {} fail, {e:?}", stream.stream_id);
+            }
+        }
+    }
+
+    async fn compact_stream(&self, stream: &CompactedStream) -> Result<(), EsError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        let tombstone_deadline = now - stream.delete_retention.as_millis() as i64;
+
+        // Objects are replaced by their rewrites as the compaction goes.
+        let mut objects = stream.objects.clone();
+        let mut start = 0;
+        while start < objects.len() {
+            let mut latest = HashMap::new();
+            let mut bytes = 0;
+            let mut end = start;
+            while end < objects.len() && bytes < self.window_bytes {
+                for batch in self.read_batches(&objects[end].0).await? {
+                    let Some(records) = records(&batch)? else {
+                        continue;
+                    };
+                    for record in records {
+                        let record = record.map_err(decode_error)?;
+                        if let Some(key) = record.key {
+                            let len = key.len();
+                            if latest.insert(key, record.offset).is_none() {
+                                bytes += len + KEY_OVERHEAD;
+                            }
+                        }
+                    }
+                }
+                end += 1;
+            }
+            debug!(
+                "compact stream {} up to object {end} with {} keys in objects [{start}, {end})",
+                stream.stream_id,
+                latest.len()
+            );
+
+            let mut next_offset = 0;
+            let mut prefix_compacted = true;
+            for (object, owned) in objects[..end].iter_mut() {
+                prefix_compacted &= object.start_offset == next_offset;
+                next_offset = object.start_offset + object.end_offset_delta as u64;
+                let deadline = prefix_compacted.then_some(tombstone_deadline);
+                // Objects of the max generation are not rewritten.
+                prefix_compacted &= *owned && object.generation < u16::MAX;
+                if !*owned {
+                    continue;
+                }
+                if let Some(rewritten) = self.rewrite(object, &latest, deadline).await? {
+                    *object = rewritten;
+                }
+            }
+            start = end;
+        }
+        Ok(())
+    }
+
+    /// Rewrite the object without records superseded by `latest`, and tombstones before
+    /// `tombstone_deadline` if it is given.
+    ///
+    /// # Returns
+    /// Metadata of the rewritten object, or `None` if nothing is removed.
+    async fn rewrite(
+        &self,
+        object: &ObjectMetadata,
+        latest: &HashMap<Bytes, i64>,
+        tombstone_deadline: Option<i64>,
+    ) -> Result<Option<ObjectMetadata>, EsError> {
+        let retain = |record: &Record| {
+            let Some(key) = &record.key else {
+                return true;
+            };
+            match latest.get(key) {
+                Some(&offset) if offset > record.offset => false,
+                Some(&offset) if offset == record.offset => {
+                    record.value.is_some()
+                        || tombstone_deadline.map_or(true, |deadline| record.timestamp >= deadline)
+                }
+                _ => true,
+            }
+        };
+        let mut changed = false;
+        let mut data = BytesMut::with_capacity(object.data_len as usize);
+        for mut batch in self.read_batches(object).await? {
+            if batch.magic == Some(RecordMagic::Magic1 as i8) {
+                let meta = batch_meta(&batch)?;
+                let compacted = magic1::retain_records(
+                    meta.base_offset(),
+                    meta.base_timestamp(),
+                    &batch.payload,
+                    retain,
+                )
+                .map_err(decode_error)?;
+                if let Some(payload) = compacted {
+                    batch.payload = payload;
+                    changed = true;
+                }
+            }
+            for buf in batch.encode().0 {
+                data.extend_from_slice(&buf);
+            }
+        }
+        if !changed {
+            return Ok(None);
+        }
+        let Some(generation) = object.generation.checked_add(1) else {
+            warn!(
+                "{:?} reaches the max generation, skip compacting it",
+                object.key
+            );
+            return Ok(None);
+        };
+
+        let data = data.freeze();
+        let (sparse_index, _, _) =
+            gen_sparse_index(object.start_offset, &vec![data.clone()], 0, SPARSE_SIZE)
+                .map_err(decode_error)?;
+        let mut bytes = BytesMut::with_capacity(data.len() + 256);
+        bytes.extend_from_slice(&data);
+        bytes.put_u8(BLOCK_DELIMITER);
+        bytes.extend_from_slice(&sparse_index);
+        bytes.extend_from_slice(&gen_footer(data.len() as u32, sparse_index.len() as u32));
+
+        let mut metadata = object.clone();
+        metadata.data_len = data.len() as u32;
+        metadata.sparse_index = sparse_index;
+        metadata.generation = generation;
+        metadata.gen_object_key(&self.cluster);
+        let key = object_key(&metadata)?;
+        self.op.write(key, bytes.freeze()).await.map_err(|e| {
+            EsError::new(
+                ErrorCode::UNEXPECTED,
+                &format!("write object {key} fail, {e}"),
+            )
+        })?;
+        self.object_manager.commit_object(metadata.clone()).await?;
+        debug!(
+            "{key} compacted from {} to {} bytes",
+            object.data_len,
+            data.len()
+        );
+
+        // Readers holding metadata of the previous object fail to read it and retry with the
+        // committed one.
+        let previous = object_key(object)?;
+        if let Err(e) = self.op.delete(previous).await {
+            warn!("delete object {previous} compacted to {key} fail, {e}");
+        }
+        Ok(Some(metadata))
+    }
+
+    async fn read_batches(&self, object: &ObjectMetadata) -> Result<Vec<FlatRecordBatch>, EsError> {
+        let key = object_key(object)?;
+        let bytes = self.op.read(key).await.map_err(|e| {
+            EsError::new(
+                ErrorCode::UNEXPECTED,
+                &format!("read object {key} fail, {e}"),
+            )
+        })?;
+        let mut data = Bytes::from(bytes);
+        if data.len() < object.data_len as usize {
+            return Err(EsError::new(
+                ErrorCode::UNEXPECTED,
+                &format!("object {key} is shorter than its data length"),
+            ));
+        }
+        data.truncate(object.data_len as usize);
+        let mut batches = vec![];
+        while !data.is_empty() {
+            batches.push(FlatRecordBatch::init_from_buf(&mut data).map_err(decode_error)?);
+        }
+        Ok(batches)
+    }
+}
+
+/// Records of the batch, or `None` if it is not in the format of `RecordMagic::Magic1`.
+fn records(batch: &FlatRecordBatch) -> Result<Option<RecordIter>, EsError> {
+    if batch.magic != Some(RecordMagic::Magic1 as i8) {
+        return Ok(None);
+    }
+    let meta = batch_meta(batch)?;
+    Ok(Some(RecordIter::new(
+        meta.base_offset(),
+        meta.base_timestamp(),
+        batch.payload.clone(),
+    )))
+}
+
+fn object_key(object: &ObjectMetadata) -> Result<&str, EsError> {
+    object
+        .key
+        .as_deref()
+        .ok_or_else(|| EsError::unexpected("object key is not generated"))
+}
+
+fn batch_meta(batch: &FlatRecordBatch) -> Result<RecordBatchMeta<'_>, EsError> {
+    flatbuffers::root::<RecordBatchMeta>(&batch.metadata).map_err(|e| {
+        EsError::new(
+            ErrorCode::UNEXPECTED,
+            &format!("invalid record batch metadata, {e}"),
+        )
+    })
+}
+
+fn decode_error<E: std::fmt::Display>(e: E) -> EsError {
+    EsError::new(
+        ErrorCode::RECORDS_PARSE_ERROR,
+        &format!("invalid records of compacted stream, {e}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        rc::Rc,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use bytes::{Bytes, BytesMut};
+    use model::{
+        object::ObjectMetadata,
+        record::{
+            flat_record::{FlatRecordBatch, RecordMagic},
+            magic1::RecordsBuilder,
+        },
+        RecordBatch,
+    };
+    use opendal::{services::Fs, Operator};
+
+    use crate::{CompactedStream, MockObjectManager};
+
+    use super::Compactor;
+
+    const CLUSTER: &str = "test_compact_stream";
+
+    fn batch(base_offset: i64, records: &[(i64, &str, Option<&str>)]) -> Bytes {
+        let mut builder = RecordsBuilder::new();
+        for &(timestamp, key, value) in records {
+            builder.append(
+                timestamp,
+                Some(Bytes::copy_from_slice(key.as_bytes())),
+                value.map(|v| Bytes::copy_from_slice(v.as_bytes())),
+                &[],
+            );
+        }
+        let records = builder.build(1).unwrap();
+        let record_batch = RecordBatch::new_builder()
+            .with_magic(RecordMagic::Magic1)
+            .with_stream_id(1)
+            .with_range_index(0)
+            .with_base_offset(base_offset)
+            .with_last_offset_delta(records.last_offset_delta() as i32)
+            .with_base_timestamp(records.base_timestamp())
+            .with_payload(records.payload())
+            .build()
+            .unwrap();
+        let (encoded, _) = FlatRecordBatch::from(record_batch).encode();
+        let mut buf = BytesMut::new();
+        encoded.iter().for_each(|b| buf.extend_from_slice(b));
+        buf.freeze()
+    }
+
+    /// Write an object of the batches, starting from `start_offset`.
+    async fn write_object(
+        op: &Operator,
+        start_offset: u64,
+        end_offset: u64,
+        batches: &[Bytes],
+    ) -> ObjectMetadata {
+        let data = batches.concat();
+        let mut object = ObjectMetadata::new(1, 0, 0, start_offset);
+        object.end_offset_delta = (end_offset - start_offset) as u32;
+        object.data_len = data.len() as u32;
+        object.gen_object_key(CLUSTER);
+        op.write(object.key.as_deref().unwrap(), data)
+            .await
+            .unwrap();
+        object
+    }
+
+    /// Records of the object as `(offset, key, value)`.
+    async fn read_object(
+        op: &Operator,
+        object: &ObjectMetadata,
+    ) -> Vec<(i64, Bytes, Option<Bytes>)> {
+        let mut data = Bytes::from(op.read(object.key.as_deref().unwrap()).await.unwrap());
+        data.truncate(object.data_len as usize);
+        let mut records = vec![];
+        while !data.is_empty() {
+            let batch = FlatRecordBatch::decode_to_record_batch(&mut data).unwrap();
+            for record in batch.records().unwrap() {
+                let record = record.unwrap();
+                records.push((record.offset, record.key.unwrap(), record.value));
+            }
+        }
+        records
+    }
+
+    fn compact_stream(window_bytes: usize, committed_count: usize) {
+        tokio_uring::start(async move {
+            let mut fs_builder = Fs::default();
+            fs_builder.root(&format!("/tmp/estest/{window_bytes}/"));
+            let op = Operator::new(fs_builder).unwrap().finish();
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as i64;
+            let old = now - Duration::from_secs(3600).as_millis() as i64;
+            let first = write_object(
+                &op,
+                0,
+                4,
+                &[
+                    batch(0, &[(old, "a", Some("1")), (old, "b", Some("2"))]),
+                    batch(2, &[(old, "a", Some("3")), (old, "b", None)]),
+                ],
+            )
+            .await;
+            let second = write_object(
+                &op,
+                4,
+                6,
+                &[batch(4, &[(now, "c", None), (old, "a", Some("4"))])],
+            )
+            .await;
+
+            let committed = Rc::new(RefCell::new(vec![]));
+            let committed_ = Rc::clone(&committed);
+            let mut object_manager = MockObjectManager::new();
+            object_manager
+                .expect_commit_object()
+                .times(committed_count)
+                .returning(move |metadata| {
+                    committed_.borrow_mut().push(metadata);
+                    Ok(())
+                });
+
+            let stream = CompactedStream {
+                stream_id: 1,
+                delete_retention: Duration::from_secs(60),
+                objects: vec![(first.clone(), true), (second.clone(), true)],
+            };
+            let mut compactor = Compactor::new(op.clone(), Rc::new(object_manager), CLUSTER);
+            compactor.window_bytes = window_bytes;
+            compactor.compact_stream(&stream).await.unwrap();
+
+            // Only the first object is rewritten, once per window ending at or after it, under the
+            // key of its next generation, with the previous one deleted.
+            let metadata = committed.borrow_mut().pop().unwrap();
+            assert_eq!(committed_count as u16, metadata.generation);
+            assert_eq!((0, 4), (metadata.start_offset, metadata.end_offset_delta));
+            assert!(metadata.data_len < first.data_len);
+            assert!(op.read(first.key.as_deref().unwrap()).await.is_err());
+
+            // Superseded records and the expired tombstone are removed, while the tombstone within
+            // delete retention is kept.
+            assert!(read_object(&op, &metadata).await.is_empty());
+            let value = |v: &str| Some(Bytes::copy_from_slice(v.as_bytes()));
+            assert_eq!(
+                vec![
+                    (4, Bytes::from("c"), None),
+                    (5, Bytes::from("a"), value("4"))
+                ],
+                read_object(&op, &second).await
+            );
+        });
+    }
+
+    #[test]
+    fn test_compact_stream_with_others_objects() {
+        tokio_uring::start(async move {
+            let mut fs_builder = Fs::default();
+            fs_builder.root("/tmp/estest/others/");
+            let op = Operator::new(fs_builder).unwrap().finish();
+
+            let old = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as i64
+                - Duration::from_secs(3600).as_millis() as i64;
+            let first = write_object(
+                &op,
+                0,
+                2,
+                &[batch(0, &[(old, "a", Some("1")), (old, "b", Some("2"))])],
+            )
+            .await;
+            let second = write_object(
+                &op,
+                2,
+                5,
+                &[batch(
+                    2,
+                    &[
+                        (old, "a", None),
+                        (old, "c", Some("3")),
+                        (old, "c", Some("4")),
+                    ],
+                )],
+            )
+            .await;
+
+            let committed = Rc::new(RefCell::new(vec![]));
+            let committed_ = Rc::clone(&committed);
+            let mut object_manager = MockObjectManager::new();
+            object_manager
+                .expect_commit_object()
+                .times(1)
+                .returning(move |metadata| {
+                    committed_.borrow_mut().push(metadata);
+                    Ok(())
+                });
+
+            // The first object is owned by another server.
+            let stream = CompactedStream {
+                stream_id: 1,
+                delete_retention: Duration::from_secs(60),
+                objects: vec![(first.clone(), false), (second.clone(), true)],
+            };
+            let compactor = Compactor::new(op.clone(), Rc::new(object_manager), CLUSTER);
+            compactor.compact_stream(&stream).await.unwrap();
+
+            // The expired tombstone is kept, as the value it deletes is left in the first object.
+            let metadata = committed.borrow_mut().pop().unwrap();
+            assert_eq!((2, 3), (metadata.start_offset, metadata.end_offset_delta));
+            let value = |v: &str| Some(Bytes::copy_from_slice(v.as_bytes()));
+            assert_eq!(
+                vec![
+                    (2, Bytes::from("a"), None),
+                    (4, Bytes::from("c"), value("4"))
+                ],
+                read_object(&op, &metadata).await
+            );
+            assert_eq!(
+                vec![
+                    (0, Bytes::from("a"), value("1")),
+                    (1, Bytes::from("b"), value("2"))
+                ],
+                read_object(&op, &first).await
+            );
+        });
+    }
+
+    #[test]
+    fn test_compact_stream() {
+        compact_stream(usize::MAX, 1);
+    }
+
+    #[test]
+    fn test_compact_stream_by_window() {
+        // Each window spans a single object.
+        compact_stream(1, 2);
+    }
+}
@@ -7,13 +7,14 @@
 #![allow(clippy::module_name_repetitions)]
 #![allow(clippy::must_use_candidate)]
 
+mod compaction;
 pub mod object_manager;
 pub mod object_storage;
 mod range_accumulator;
 pub mod range_fetcher;
 mod range_offload;
 
-use std::cell::UnsafeCell;
+use std::{cell::UnsafeCell, time::Duration};
 
 use model::{error::EsError, object::ObjectMetadata};
 
@@ -70,6 +71,22 @@ pub trait ObjectManager {
 
     /// Watch the range offload progress which range is held by current server.
     fn watch_offload_progress(&self) -> OffloadProgressListener;
+
+    /// Returns compacted streams with at least one range offloaded by current server.
+    fn compacted_streams(&self) -> Vec<CompactedStream>;
+}
+
+/// Offloaded objects of a compacted stream.
+#[derive(Debug, Clone)]
+pub struct CompactedStream {
+    pub stream_id: u64,
+
+    /// Time to keep tombstones before they are removed.
+    pub delete_retention: Duration,
+
+    /// Objects of all ranges of the stream in offset order, each along with whether current server
+    /// owns it and thus rewrites it on compaction.
+    pub objects: Vec<(ObjectMetadata, bool)>,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -2,14 +2,20 @@ use std::{
     cell::RefCell,
     collections::{BTreeMap, HashMap},
     rc::Rc,
+    time::Duration,
 };
 
-use crate::{ObjectManager, OffloadProgress, OffloadProgressListener, Owner, OwnerEvent, RangeKey};
+use crate::{
+    CompactedStream, ObjectManager, OffloadProgress, OffloadProgressListener, Owner, OwnerEvent,
+    RangeKey,
+};
 use bytes::Bytes;
 use model::{
     error::EsError,
     object::ObjectMetadata,
+    range::ReplicaLayout,
     resource::{EventType, Resource, ResourceEvent},
+    stream::StreamMetadata,
 };
 use pd_client::PlacementDriverClient;
 use protocol::rpc::header::ResourceType;
@@ -36,6 +42,7 @@ struct Object {
     end_offset_delta: u32,
     data_len: u32,
     sparse_index: Bytes,
+    generation: u16,
 }
 
 impl From<&ObjectMetadata> for Object {
@@ -44,6 +51,7 @@ impl From<&ObjectMetadata> for Object {
             end_offset_delta: value.end_offset_delta,
             data_len: value.data_len,
             sparse_index: value.sparse_index.clone(),
+            generation: value.generation,
         }
     }
 }
@@ -57,6 +65,7 @@ fn gen_object_metadata(key: &ObjectKey, object: &Object) -> ObjectMetadata {
         end_offset_delta: object.end_offset_delta,
         data_len: object.data_len,
         sparse_index: object.sparse_index.clone(),
+        generation: object.generation,
         key: None,
     }
 }
@@ -65,6 +74,16 @@ fn gen_object_metadata(key: &ObjectKey, object: &Object) -> ObjectMetadata {
 struct Objects(BTreeMap<ObjectKey, Object>);
 
 impl Objects {
+    /// Add the object, unless a later generation of it, rewritten by compaction, is present.
+    fn insert(&mut self, object: &ObjectMetadata) {
+        match self.0.get(&object.into()) {
+            Some(present) if present.generation > object.generation => {}
+            _ => {
+                self.0.insert(object.into(), object.into());
+            }
+        }
+    }
+
     /// Get a list of objects that
     /// * continuous (`object[i].start_offset` + `object[i].end_offset_delta` == `object[i+1].start_offset`)
     /// * start from `start_offset` (`object[0].start_offset` <= `start_offset`, if `object[0]` exists)
@@ -138,6 +157,17 @@ impl Objects {
             || size >= size_hint.unwrap_or(u32::MAX);
         (objects, cover_all)
     }
+
+    /// All continuous objects from the first one.
+    fn all(&self) -> Vec<ObjectMetadata> {
+        self.0
+            .keys()
+            .map(|key| key.start_offset)
+            .min()
+            .map_or(vec![], |start_offset| {
+                self.get_continuous_objects(start_offset, None, None).0
+            })
+    }
 }
 
 #[derive(Debug, Default)]
@@ -192,6 +222,9 @@ struct Metadata {
 
     /// Listeners of range offload progress.
     offload_progress_listeners: Vec<mpsc::UnboundedSender<OffloadProgress>>,
+
+    /// Delete retention of compacted streams.
+    compacted: HashMap<u64, Duration>,
 }
 
 impl Metadata {
@@ -201,16 +234,12 @@ impl Metadata {
         let key = RangeKey::new(object.stream_id, object.range_index);
         match self.managed.get_mut(&key) {
             Some(managed) => {
-                managed.objects.0.insert(object.into(), object.into());
+                managed.objects.insert(object);
                 return Some(managed.offload_offset());
             }
             None => {
                 // range not held by this server
-                self.other
-                    .entry(key)
-                    .or_default()
-                    .0
-                    .insert(object.into(), object.into());
+                self.other.entry(key).or_default().insert(object);
             }
         }
         None
@@ -241,6 +270,54 @@ impl Metadata {
     fn reset(&mut self) {
         self.managed.clear();
         self.other.clear();
+        self.compacted.clear();
+    }
+
+    fn update_stream(&mut self, stream: &StreamMetadata) {
+        if stream.compacted && !stream.deleted {
+            self.compacted
+                .insert(stream.stream_id, stream.delete_retention);
+        } else {
+            self.compacted.remove(&stream.stream_id);
+        }
+    }
+
+    /// Compacted streams with at least one range offloaded by this server.
+    fn compacted_streams(&self) -> Vec<CompactedStream> {
+        let mut streams = vec![];
+        for (&stream_id, &delete_retention) in &self.compacted {
+            let mut ranges: Vec<_> = self
+                .managed
+                .iter()
+                .filter(|(key, _)| key.stream_id == stream_id)
+                .map(|(key, managed)| (*key, &managed.objects, managed.owner))
+                .chain(
+                    self.other
+                        .iter()
+                        .filter(|(key, _)| key.stream_id == stream_id)
+                        .map(|(key, objects)| (*key, objects, false)),
+                )
+                .collect();
+            if !ranges.iter().any(|(_, _, owned)| *owned) {
+                continue;
+            }
+            ranges.sort_by_key(|(key, _, _)| key.range_index);
+
+            let mut objects = vec![];
+            for (key, range_objects, owned) in ranges {
+                for mut object in range_objects.all() {
+                    object.stream_id = key.stream_id;
+                    object.range_index = key.range_index;
+                    objects.push((object, owned));
+                }
+            }
+            streams.push(CompactedStream {
+                stream_id,
+                delete_retention,
+                objects,
+            });
+        }
+        streams
     }
 }
 
@@ -262,6 +339,7 @@ where
         let token = CancellationToken::new();
         let metadata = Rc::new(RefCell::new(Metadata::default()));
         let rx = pd_client.list_and_watch_resource(&[
+            ResourceType::RESOURCE_STREAM,
             ResourceType::RESOURCE_RANGE,
             ResourceType::RESOURCE_OBJECT,
         ]);
@@ -320,11 +398,20 @@ where
         metadata: &Rc<RefCell<Metadata>>,
     ) {
         match resource {
+            Resource::Stream(stream) => {
+                metadata.borrow_mut().update_stream(stream);
+            }
             Resource::Range(range) => {
                 let mut metadata = metadata.borrow_mut();
                 let stream_id = range.stream_id();
                 let range_index = range.index() as u32;
                 let key = RangeKey::new(stream_id, range_index);
+                if let ReplicaLayout::ReedSolomon { .. } = range.layout() {
+                    // Replicas of erasure coded ranges hold shards rather than record batches,
+                    // which are read back by reconstruction only, so they are not offloaded.
+                    log::debug!("range {key:?} is erasure coded, skip offloading it");
+                    return;
+                }
                 if range.held_by(server_id) {
                     if let Some(owner) = range.offload_owner() {
                         let objects = metadata.managed.entry(key).or_default();
@@ -367,12 +454,25 @@ where
         }
     }
 
-    fn handle_modified_resource(_resource: &Resource, _metadata: &Rc<RefCell<Metadata>>) {
-        // TODO: handle modified resource
+    fn handle_modified_resource(resource: &Resource, metadata: &Rc<RefCell<Metadata>>) {
+        // TODO: handle modified range
+        match resource {
+            Resource::Stream(stream) => {
+                metadata.borrow_mut().update_stream(stream);
+            }
+            // Objects rewritten by compaction.
+            Resource::Object(object) => {
+                metadata.borrow_mut().add_object(object);
+            }
+            _ => (),
+        }
     }
 
-    fn handle_deleted_resource(_resource: &Resource, _metadata: &Rc<RefCell<Metadata>>) {
-        // TODO: handle deleted resource
+    fn handle_deleted_resource(resource: &Resource, metadata: &Rc<RefCell<Metadata>>) {
+        // TODO: handle deleted range and object
+        if let Resource::Stream(stream) = resource {
+            metadata.borrow_mut().compacted.remove(&stream.stream_id);
+        }
     }
 }
 
@@ -428,6 +528,16 @@ where
             .collect::<Vec<_>>()
     }
 
+    fn compacted_streams(&self) -> Vec<CompactedStream> {
+        let mut streams = self.metadata.borrow().compacted_streams();
+        for stream in &mut streams {
+            for (object, _) in &mut stream.objects {
+                object.gen_object_key(&self.cluster);
+            }
+        }
+        streams
+    }
+
     fn watch_offload_progress(&self) -> OffloadProgressListener {
         let (tx, rx) = mpsc::unbounded_channel();
         let mut metadata = self.metadata.borrow_mut();
@@ -464,7 +574,9 @@ mod tests {
 
     use super::*;
     use model::{object::ObjectMetadata, range::RangeMetadata};
-    use protocol::rpc::header::{OffloadOwnerT, RangeServerT, RangeT};
+    use protocol::rpc::header::{
+        OffloadOwnerT, RangeServerT, RangeT, ReplicaLayout as ReplicaLayoutT,
+    };
     use tokio::sync::mpsc;
 
     fn new_object_with_epoch(
@@ -854,6 +966,69 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_skip_erasure_coded_range() {
+        tokio_uring::start(async {
+            let (tx, rx) = mpsc::channel(16);
+            let mut mock_pd_client = pd_client::MockPlacementDriverClient::new();
+            mock_pd_client
+                .expect_list_and_watch_resource()
+                .times(1)
+                .return_once(|_| rx);
+            let object_manager = DefaultObjectManager::<pd_client::MockPlacementDriverClient>::new(
+                "testcluster",
+                Rc::new(mock_pd_client),
+                42,
+            );
+
+            // Ranges 1#0 and 1#1 are both offloaded by this server, but 1#0 is erasure coded.
+            for (index, layout) in [
+                (0, ReplicaLayoutT::REPLICA_LAYOUT_REED_SOLOMON),
+                (1, ReplicaLayoutT::REPLICA_LAYOUT_FULL),
+            ] {
+                let mut range_t = RangeT::default();
+                range_t.stream_id = 1;
+                range_t.index = index;
+                range_t.start = 100;
+                range_t.replica_layout = layout;
+                range_t.data_shards = 2;
+                let mut range_server_t = RangeServerT::default();
+                range_server_t.server_id = 42;
+                range_t.servers = Some(vec![range_server_t]);
+                let mut offload_owner_t = OffloadOwnerT::default();
+                offload_owner_t.server_id = 42;
+                offload_owner_t.epoch = 3;
+                range_t.offload_owner = Some(Box::new(offload_owner_t));
+                tx.send(ResourceEvent {
+                    event_type: EventType::Listed,
+                    resource: Resource::Range(RangeMetadata::from(&range_t)),
+                })
+                .await
+                .unwrap();
+            }
+            while tx.capacity() < 16 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            let mut owner_watcher = object_manager.owner_watcher();
+            assert_eq!(
+                Some(OwnerEvent {
+                    range_key: RangeKey::new(1, 1),
+                    owner: Some(Owner {
+                        epoch: 3,
+                        start_offset: 100
+                    })
+                }),
+                owner_watcher.recv().await
+            );
+            assert!(owner_watcher.try_recv().is_err());
+            assert_eq!(
+                vec![RangeKey::new(1, 1)],
+                object_manager.get_offloading_range()
+            );
+        });
+    }
+
     #[test]
     fn test_metadata() {
         // TODO: test `list_and_watch`
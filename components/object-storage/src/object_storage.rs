@@ -17,6 +17,7 @@ use store::Store;
 use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio::time::sleep;
 
+use crate::compaction::Compactor;
 use crate::object_manager::DefaultObjectManager;
 use crate::range_accumulator::{DefaultRangeAccumulator, RangeAccumulator};
 use crate::range_fetcher::{DefaultRangeFetcher, RangeFetcher};
@@ -360,8 +361,14 @@ where
         Self::run_force_flush_task(
             this.ranges.clone(),
             Duration::from_secs(force_flush_secs),
-            shutdown_rx,
+            shutdown_rx.clone(),
         );
+        if let Some(op) = this.op.as_ref() {
+            Compactor::new(op.clone(), this.object_manager.clone(), &config.cluster).run(
+                Duration::from_secs(config.compaction_interval_secs),
+                shutdown_rx,
+            );
+        }
         this
     }
 
@@ -31,7 +31,7 @@ use util::bytes::vec_bytes_to_bytes;
 use crate::ObjectManager;
 use model::object::ObjectMetadata;
 
-const SPARSE_SIZE: u32 = 16 * 1024 * 1024;
+pub(crate) const SPARSE_SIZE: u32 = 16 * 1024 * 1024;
 lazy_static! {
     static ref OBJECT_WRITE_LIMITER: Semaphore = Semaphore::new(100);
 }
@@ -95,6 +95,7 @@ where
             self.range_index,
             self.epoch,
             start_offset,
+            0,
         );
 
         {
@@ -441,7 +442,7 @@ where
 ///   )*
 ///
 /// return (sparse index bytes, record end offset, remain pass through size)
-fn gen_sparse_index(
+pub(crate) fn gen_sparse_index(
     start_offset: u64,
     payload: &Vec<Bytes>,
     init_pass_through_size: u32,
@@ -463,7 +464,7 @@ fn gen_sparse_index(
             return Err(DecodeError::DataLengthMismatch);
         }
         let magic_code = cursor.get_i8();
-        if magic_code != RecordMagic::Magic0 as i8 {
+        if RecordMagic::try_from(magic_code).is_err() {
             return Err(DecodeError::InvalidMagic);
         }
         let metadata_len = cursor.get_i32() as usize;
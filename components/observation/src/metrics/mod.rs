@@ -25,6 +25,7 @@ use config::Configuration;
 pub mod object;
 mod otlp;
 pub mod range_server;
+pub mod replication;
 pub mod store;
 pub mod sys;
 mod tonic;
@@ -0,0 +1,39 @@
+use lazy_static::*;
+use opentelemetry::metrics::ObservableGauge;
+#[cfg(feature = "metrics")]
+use opentelemetry::KeyValue;
+
+use crate::metrics::get_meter;
+
+lazy_static! {
+    static ref GAUGE_INFLIGHT_REQUESTS: ObservableGauge<u64> = get_meter()
+        .u64_observable_gauge("replication.inflight.requests")
+        .with_description("Number of appends sent but not yet acknowledged")
+        .init();
+    static ref GAUGE_INFLIGHT_BYTES: ObservableGauge<u64> = get_meter()
+        .u64_observable_gauge("replication.inflight.bytes")
+        .with_description("Bytes of appends sent but not yet acknowledged")
+        .init();
+}
+
+#[cfg(feature = "metrics")]
+const LABEL_STREAM: &str = "stream";
+
+/// Record in-flight appends of all streams.
+pub fn record_inflight(_requests: u64, _bytes: u64) {
+    #[cfg(feature = "metrics")]
+    {
+        GAUGE_INFLIGHT_REQUESTS.observe(_requests, &[]);
+        GAUGE_INFLIGHT_BYTES.observe(_bytes, &[]);
+    }
+}
+
+/// Record in-flight appends of the given stream.
+pub fn record_stream_inflight(_stream_id: u64, _requests: u64, _bytes: u64) {
+    #[cfg(feature = "metrics")]
+    {
+        let labels = [KeyValue::new(LABEL_STREAM, _stream_id as i64)];
+        GAUGE_INFLIGHT_REQUESTS.observe(_requests, &labels);
+        GAUGE_INFLIGHT_BYTES.observe(_bytes, &labels);
+    }
+}
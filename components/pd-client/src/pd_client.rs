@@ -173,7 +173,10 @@ where
     ) -> ListAndWatchError {
         let mut version = start_version;
         loop {
-            match client.watch_resource(types, version, WATCH_TIMEOUT).await {
+            match client
+                .watch_resource(types, None, version, WATCH_TIMEOUT)
+                .await
+            {
                 Ok(result) => {
                     log::trace!("watch resource success. result: {result:?}");
                     version = result.version;
@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use config::Configuration;
+use model::error::EsError;
+use observation::metrics::replication::{record_inflight, record_stream_inflight};
+use protocol::rpc::header::ErrorCode;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Bounds appends that are sent but not yet acknowledged, both of all streams and of each stream.
+///
+/// `InflightLimiter` is `Send` and `Sync`, such that stream clients of different runtime threads may share
+/// the same global limits.
+#[derive(Debug)]
+pub struct InflightLimiter {
+    block: bool,
+    global: Limit,
+    stream_max_requests: usize,
+    stream_max_bytes: usize,
+    streams: Mutex<HashMap<u64, StreamLimit>>,
+}
+
+/// Limit of a stream, shared by writers of the stream opened in the same process.
+#[derive(Debug)]
+struct StreamLimit {
+    writers: usize,
+    limit: Arc<Limit>,
+}
+
+/// Number and bytes of in-flight appends.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InflightUsage {
+    pub requests: usize,
+    pub bytes: usize,
+}
+
+/// Permits of an in-flight append, which are released on drop.
+#[derive(Debug)]
+pub(crate) struct InflightPermit {
+    _permits: Vec<OwnedSemaphorePermit>,
+}
+
+impl InflightLimiter {
+    pub fn new(config: &Configuration) -> Self {
+        let inflight = &config.replication.inflight;
+        Self {
+            block: inflight.block,
+            global: Limit::new(inflight.max_requests, inflight.max_bytes),
+            stream_max_requests: inflight.stream_max_requests,
+            stream_max_bytes: inflight.stream_max_bytes,
+            streams: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Acquire permits to append a record batch of the given size to the stream.
+    ///
+    /// Once limits are reached, wait till in-flight appends complete or fail with `TOO_MANY_REQUESTS`,
+    /// depending on configuration.
+    pub(crate) async fn acquire(
+        &self,
+        stream_id: u64,
+        bytes: usize,
+    ) -> Result<InflightPermit, EsError> {
+        let mut permits = Vec::with_capacity(4);
+        // Acquire stream permits first, so that a stream blocked by its own limits does not hold global ones.
+        // Streams not opened for write have no limits of their own, whose appends are rejected anyway.
+        if let Some(stream) = self.stream(stream_id) {
+            stream.acquire(bytes, self.block, &mut permits).await?;
+        }
+        self.global.acquire(bytes, self.block, &mut permits).await?;
+        Ok(InflightPermit { _permits: permits })
+    }
+
+    /// Track limits of the stream once it is opened for write.
+    pub(crate) fn open(&self, stream_id: u64) {
+        let mut streams = self
+            .streams
+            .lock()
+            .expect("Lock of in-flight streams should not be poisoned");
+        streams
+            .entry(stream_id)
+            .or_insert_with(|| StreamLimit {
+                writers: 0,
+                limit: Arc::new(Limit::new(self.stream_max_requests, self.stream_max_bytes)),
+            })
+            .writers += 1;
+    }
+
+    /// Release limits of the stream once its last writer is closed.
+    ///
+    /// Closing a stream completes or fails its in-flight appends, so call this after the close, lest
+    /// the stream reopened meanwhile get fresh limits while appends of the closed one are in flight.
+    pub(crate) fn close(&self, stream_id: u64) {
+        let mut streams = self
+            .streams
+            .lock()
+            .expect("Lock of in-flight streams should not be poisoned");
+        if let Some(stream) = streams.get_mut(&stream_id) {
+            stream.writers -= 1;
+            if stream.writers == 0 {
+                streams.remove(&stream_id);
+            }
+        }
+    }
+
+    /// Usage of all streams.
+    pub fn usage(&self) -> InflightUsage {
+        self.global.usage()
+    }
+
+    /// Usage of the given stream.
+    pub fn stream_usage(&self, stream_id: u64) -> InflightUsage {
+        self.streams
+            .lock()
+            .expect("Lock of in-flight streams should not be poisoned")
+            .get(&stream_id)
+            .map(|stream| stream.limit.usage())
+            .unwrap_or_default()
+    }
+
+    /// Report usage of all streams and of each stream to metrics.
+    pub(crate) fn report(&self) {
+        let usage = self.usage();
+        record_inflight(usage.requests as u64, usage.bytes as u64);
+        let streams = self
+            .streams
+            .lock()
+            .expect("Lock of in-flight streams should not be poisoned");
+        for (stream_id, stream) in streams.iter() {
+            let usage = stream.limit.usage();
+            record_stream_inflight(*stream_id, usage.requests as u64, usage.bytes as u64);
+        }
+    }
+
+    fn stream(&self, stream_id: u64) -> Option<Arc<Limit>> {
+        self.streams
+            .lock()
+            .expect("Lock of in-flight streams should not be poisoned")
+            .get(&stream_id)
+            .map(|stream| Arc::clone(&stream.limit))
+    }
+}
+
+#[derive(Debug)]
+struct Limit {
+    max_requests: usize,
+    max_bytes: usize,
+    requests: Option<Arc<Semaphore>>,
+    bytes: Option<Arc<Semaphore>>,
+}
+
+impl Limit {
+    /// Zero means unlimited.
+    fn new(max_requests: usize, max_bytes: usize) -> Self {
+        let semaphore = |permits: usize| {
+            if permits > 0 {
+                Some(Arc::new(Semaphore::new(
+                    permits.min(Semaphore::MAX_PERMITS),
+                )))
+            } else {
+                None
+            }
+        };
+        Self {
+            max_requests,
+            max_bytes,
+            requests: semaphore(max_requests),
+            bytes: semaphore(max_bytes),
+        }
+    }
+
+    async fn acquire(
+        &self,
+        bytes: usize,
+        block: bool,
+        permits: &mut Vec<OwnedSemaphorePermit>,
+    ) -> Result<(), EsError> {
+        if let Some(semaphore) = &self.requests {
+            permits.push(Self::acquire0(semaphore, 1, block).await?);
+        }
+        if let Some(semaphore) = &self.bytes {
+            // A record batch larger than the limit is allowed once there is no other in-flight one.
+            let n = bytes.min(self.max_bytes).min(u32::MAX as usize) as u32;
+            permits.push(Self::acquire0(semaphore, n, block).await?);
+        }
+        Ok(())
+    }
+
+    async fn acquire0(
+        semaphore: &Arc<Semaphore>,
+        n: u32,
+        block: bool,
+    ) -> Result<OwnedSemaphorePermit, EsError> {
+        if block {
+            Arc::clone(semaphore)
+                .acquire_many_owned(n)
+                .await
+                .map_err(|_| EsError::unexpected("In-flight semaphore is closed"))
+        } else {
+            Arc::clone(semaphore)
+                .try_acquire_many_owned(n)
+                .map_err(|_| {
+                    EsError::new(ErrorCode::TOO_MANY_REQUESTS, "Too many in-flight appends")
+                })
+        }
+    }
+
+    fn usage(&self) -> InflightUsage {
+        let used = |semaphore: &Option<Arc<Semaphore>>, max: usize| {
+            semaphore.as_ref().map_or(0, |s| {
+                max.min(Semaphore::MAX_PERMITS) - s.available_permits()
+            })
+        };
+        InflightUsage {
+            requests: used(&self.requests, self.max_requests),
+            bytes: used(&self.bytes, self.max_bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, time::Duration};
+
+    use protocol::rpc::header::ErrorCode;
+
+    use super::{InflightLimiter, InflightUsage};
+
+    fn config(block: bool) -> config::Configuration {
+        let mut config = config::Configuration::default();
+        config.replication.inflight.max_requests = 3;
+        config.replication.inflight.max_bytes = 0;
+        config.replication.inflight.stream_max_requests = 2;
+        config.replication.inflight.stream_max_bytes = 100;
+        config.replication.inflight.block = block;
+        config
+    }
+
+    #[test]
+    fn test_fail_fast() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async {
+            let limiter = InflightLimiter::new(&config(false));
+            (1..=3).for_each(|stream_id| limiter.open(stream_id));
+            let p1 = limiter.acquire(1, 60).await?;
+            // Stream bytes limit
+            assert_eq!(
+                ErrorCode::TOO_MANY_REQUESTS,
+                limiter.acquire(1, 60).await.unwrap_err().code
+            );
+            let _p2 = limiter.acquire(1, 40).await?;
+            // Stream requests limit
+            assert_eq!(
+                ErrorCode::TOO_MANY_REQUESTS,
+                limiter.acquire(1, 1).await.unwrap_err().code
+            );
+            assert_eq!(
+                InflightUsage {
+                    requests: 2,
+                    bytes: 100
+                },
+                limiter.stream_usage(1)
+            );
+
+            // Global requests limit
+            let _p3 = limiter.acquire(2, 200).await?;
+            assert_eq!(
+                ErrorCode::TOO_MANY_REQUESTS,
+                limiter.acquire(3, 1).await.unwrap_err().code
+            );
+            assert_eq!(3, limiter.usage().requests);
+            // Unlimited global bytes
+            assert_eq!(0, limiter.usage().bytes);
+
+            drop(p1);
+            assert_eq!(2, limiter.usage().requests);
+            let _p4 = limiter.acquire(3, 1).await?;
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_block() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async {
+            let limiter = std::rc::Rc::new(InflightLimiter::new(&config(true)));
+            limiter.open(1);
+            let p1 = limiter.acquire(1, 100).await?;
+            let l = std::rc::Rc::clone(&limiter);
+            let handle = tokio_uring::spawn(async move { l.acquire(1, 10).await.map(|_| ()) });
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert!(!handle.is_finished());
+            drop(p1);
+            handle.await??;
+            assert_eq!(InflightUsage::default(), limiter.stream_usage(1));
+            limiter.close(1);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_close() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async {
+            let limiter = InflightLimiter::new(&config(false));
+            // Stream opened twice, e.g. by two handles of the same process.
+            limiter.open(1);
+            limiter.open(1);
+            let p1 = limiter.acquire(1, 100).await?;
+            limiter.close(1);
+            // Limits are kept while other writers are open, so the stream bytes limit still holds.
+            assert_eq!(
+                ErrorCode::TOO_MANY_REQUESTS,
+                limiter.acquire(1, 1).await.unwrap_err().code
+            );
+            assert_eq!(100, limiter.stream_usage(1).bytes);
+            drop(p1);
+            limiter.close(1);
+            assert_eq!(InflightUsage::default(), limiter.stream_usage(1));
+            // Appends of streams not opened for write are bounded by global limits only.
+            let _p2 = limiter.acquire(1, 1000).await?;
+            assert_eq!(1, limiter.usage().requests);
+            Ok(())
+        })
+    }
+}
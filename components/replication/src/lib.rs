@@ -4,9 +4,13 @@
 #![feature(async_fn_in_trait)]
 
 pub mod error;
+pub mod inflight;
 pub mod request;
+pub mod rollover;
 mod stream;
 pub mod stream_client;
 
 pub use error::ReplicationError;
+pub use inflight::{InflightLimiter, InflightUsage};
+pub use rollover::RolloverPolicy;
 pub use stream_client::StreamClient;
@@ -1,8 +1,25 @@
 use bytes::Bytes;
-use model::{error::EsError, RecordBatch};
+use model::{
+    error::EsError,
+    range::{RangeMetadata, ReplicaLayout},
+    stream::StreamMetadata,
+    RecordBatch,
+};
 use std::time::Duration;
 use tokio::sync::oneshot;
 
+use crate::rollover::RolloverPolicy;
+
+/// Mode in which a stream is opened. A process may open a stream in both modes at the same time,
+/// e.g. a writer along with consumers reading the stream, each of which is kept apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpenMode {
+    /// Own the stream for write, fencing writers of lower epochs.
+    ReadWrite,
+    /// Read the stream without fencing the active writer.
+    ReadOnly,
+}
+
 #[derive(Debug)]
 pub struct AppendRequest {
     pub stream_id: u64,
@@ -17,6 +34,7 @@ pub struct AppendResponse {
 #[derive(Debug)]
 pub struct ReadRequest {
     pub stream_id: u64,
+    pub mode: OpenMode,
     pub start_offset: u64,
     pub end_offset: u64,
     pub batch_max_bytes: u32,
@@ -32,6 +50,11 @@ pub struct CreateStreamRequest {
     pub replica: u8,
     pub ack_count: u8,
     pub retention_period: Duration,
+    /// Keep only the latest record of each key, whose tombstones are removed after
+    /// `delete_retention`. `None` for an ordinary stream.
+    pub compaction: Option<Duration>,
+    /// Rollover policy persisted with the stream, which writers apply unless given one on open.
+    pub rollover: Option<RolloverPolicy>,
 }
 
 #[derive(Debug)]
@@ -43,6 +66,12 @@ pub struct CreateStreamResponse {
 pub struct OpenStreamRequest {
     pub stream_id: u64,
     pub epoch: u64,
+    pub mode: OpenMode,
+    /// Rollover policy of the stream. If absent, the policy persisted with the stream applies, or
+    /// else the configured default one.
+    pub rollover: Option<RolloverPolicy>,
+    /// Layout of replicas of ranges created by the stream.
+    pub layout: ReplicaLayout,
 }
 
 #[derive(Debug)]
@@ -51,6 +80,7 @@ pub struct OpenStreamResponse {}
 #[derive(Debug)]
 pub struct CloseStreamRequest {
     pub stream_id: u64,
+    pub mode: OpenMode,
 }
 
 #[derive(Debug)]
@@ -64,6 +94,77 @@ pub struct DeleteRequest {
     pub stream_id: u64,
 }
 
+/// Request to the key-value store of placement driver.
+#[derive(Debug)]
+pub enum KvRequest {
+    /// Get the key-value pair of `key`, or those in `[key, range_end)` if `range_end` is given.
+    Range {
+        key: Bytes,
+        range_end: Option<Bytes>,
+    },
+    Put {
+        key: Bytes,
+        value: Bytes,
+    },
+    /// Put the key-value pair if `key` holds `expected`, or does not exist if `expected` is `None`.
+    CompareAndPut {
+        key: Bytes,
+        expected: Option<Bytes>,
+        value: Bytes,
+    },
+    /// Delete `key`, or keys in `[key, range_end)` if `range_end` is given.
+    DeleteRange {
+        key: Bytes,
+        range_end: Option<Bytes>,
+    },
+}
+
+#[derive(Debug)]
+pub enum KvResponse {
+    Range { kvs: Vec<(Bytes, Bytes)> },
+    Put,
+    DeleteRange { deleted: u64 },
+}
+
+/// Request to administrate streams through placement driver, regardless of whether they are opened.
+#[derive(Debug)]
+pub enum AdminRequest {
+    DescribeStream {
+        stream_id: u64,
+    },
+    /// List ranges of the stream, along with range servers of their replicas.
+    ListRanges {
+        stream_id: u64,
+    },
+    /// Update metadata of the stream. Fields of `None` are left unchanged.
+    UpdateStream {
+        stream_id: u64,
+        replica: Option<u8>,
+        ack_count: Option<u8>,
+        retention_period: Option<Duration>,
+    },
+    /// List at most `limit` streams, continuing from a previous list if `continuation` is given.
+    ListStreams {
+        limit: i32,
+        continuation: Option<Bytes>,
+    },
+}
+
+#[derive(Debug)]
+pub enum AdminResponse {
+    Stream {
+        stream: StreamMetadata,
+    },
+    Ranges {
+        ranges: Vec<RangeMetadata>,
+    },
+    /// `continuation` is `None` if no more streams are left to list.
+    Streams {
+        streams: Vec<StreamMetadata>,
+        continuation: Option<Bytes>,
+    },
+}
+
 #[derive(Debug)]
 pub(crate) enum Request {
     Append {
@@ -89,11 +190,13 @@ pub(crate) enum Request {
     StartOffset {
         // stream id
         request: u64,
+        mode: OpenMode,
         tx: oneshot::Sender<Result<u64, EsError>>,
     },
     NextOffset {
         // stream id
         request: u64,
+        mode: OpenMode,
         tx: oneshot::Sender<Result<u64, EsError>>,
     },
     Trim {
@@ -104,4 +207,12 @@ pub(crate) enum Request {
         request: DeleteRequest,
         tx: oneshot::Sender<Result<(), EsError>>,
     },
+    Kv {
+        request: KvRequest,
+        tx: oneshot::Sender<Result<KvResponse, EsError>>,
+    },
+    Admin {
+        request: AdminRequest,
+        tx: oneshot::Sender<Result<AdminResponse, EsError>>,
+    },
 }
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use config::Configuration;
+
+pub use model::stream::RolloverPolicy;
+
+/// Default rollover policy of streams, as configured in the replication section.
+pub(crate) fn configured(config: &Configuration) -> RolloverPolicy {
+    let rollover = &config.replication.rollover;
+    let positive = |value: u64| if value > 0 { Some(value) } else { None };
+    RolloverPolicy {
+        max_bytes: positive(rollover.max_bytes),
+        max_records: positive(rollover.max_records),
+        max_age: positive(rollover.max_age).map(|ticks| Duration::from_millis(config.tick * ticks)),
+    }
+}
+
+/// Rollover policy a writer opens a stream with.
+#[derive(Debug, Clone)]
+pub(crate) enum OpenRollover {
+    /// Given on open, which takes precedence over the policy persisted with the stream.
+    Given(RolloverPolicy),
+    /// Configured default, which applies if the stream persists no policy of its own.
+    Default(RolloverPolicy),
+}
+
+impl OpenRollover {
+    pub(crate) fn resolve(&self, persisted: Option<&RolloverPolicy>) -> RolloverPolicy {
+        match (self, persisted) {
+            (OpenRollover::Given(policy), _) => policy.clone(),
+            (OpenRollover::Default(_), Some(persisted)) => persisted.clone(),
+            (OpenRollover::Default(policy), None) => policy.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{OpenRollover, RolloverPolicy};
+
+    #[test]
+    fn test_configured() {
+        let mut config = config::Configuration::default();
+        config.replication.rollover.max_age = 10;
+        let policy = super::configured(&config);
+        assert_eq!(Some(1024 * 1024 * 1024), policy.max_bytes);
+        assert_eq!(None, policy.max_records);
+        assert_eq!(Some(Duration::from_secs(1)), policy.max_age);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let policy = |max_records| RolloverPolicy {
+            max_records: Some(max_records),
+            ..Default::default()
+        };
+        let persisted = policy(20);
+        assert_eq!(
+            policy(10),
+            OpenRollover::Given(policy(10)).resolve(Some(&persisted))
+        );
+        assert_eq!(
+            persisted,
+            OpenRollover::Default(policy(30)).resolve(Some(&persisted))
+        );
+        assert_eq!(policy(30), OpenRollover::Default(policy(30)).resolve(None));
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+
+use bytes::{Bytes, BytesMut};
+use log::warn;
+use model::{error::EsError, record::flat_record::FlatRecordBatch, RecordBatch};
+use protocol::rpc::header::ErrorCode;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+/// Property of a shard record batch, holding length of the original payload.
+const PAYLOAD_LENGTH_KEY: &str = "ec.len";
+
+/// Property of a shard record batch, holding index of the shard.
+const SHARD_INDEX_KEY: &str = "ec.shard";
+
+/// Reed-Solomon coder of record batches of an erasure coded range.
+///
+/// Each record batch is encoded into `data_shards + parity_shards` shard record batches, which share
+/// metadata, including offsets, of the original one and carry a shard of its payload. Shard `i` is
+/// held by replica `i` of the range, such that range servers serve shards as ordinary record batches.
+pub(crate) struct ErasureCoder {
+    data_shards: usize,
+    total_shards: usize,
+    rs: ReedSolomon,
+}
+
+impl ErasureCoder {
+    pub(crate) fn new(data_shards: u8, replica_count: u8) -> Result<Self, EsError> {
+        if data_shards == 0 || data_shards >= replica_count {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                &format!("Invalid erasure coding of {data_shards} data shards among {replica_count} replicas"),
+            ));
+        }
+        let rs = ReedSolomon::new(data_shards as usize, (replica_count - data_shards) as usize)
+            .map_err(|e| {
+                EsError::new(
+                    ErrorCode::BAD_REQUEST,
+                    &format!("Invalid erasure coding: {e:?}"),
+                )
+            })?;
+        Ok(Self {
+            data_shards: data_shards as usize,
+            total_shards: replica_count as usize,
+            rs,
+        })
+    }
+
+    pub(crate) fn data_shards(&self) -> usize {
+        self.data_shards
+    }
+
+    /// Encode the record batch into shard record batches, indexed by shard.
+    pub(crate) fn encode(&self, record_batch: &RecordBatch) -> Result<Vec<RecordBatch>, EsError> {
+        let payload = record_batch.payload();
+        // Shards are never empty, so that empty payloads are encoded as well.
+        let shard_len = payload.len().div_ceil(self.data_shards).max(1);
+        let mut shards = vec![vec![0u8; shard_len]; self.total_shards];
+        for (shard, chunk) in shards.iter_mut().zip(payload.chunks(shard_len)) {
+            shard[..chunk.len()].copy_from_slice(chunk);
+        }
+        self.rs
+            .encode(&mut shards)
+            .map_err(|e| EsError::unexpected(&format!("Failed to encode shards: {e:?}")))?;
+        shards
+            .into_iter()
+            .enumerate()
+            .map(|(index, shard)| {
+                shard_builder(record_batch)
+                    .with_property(PAYLOAD_LENGTH_KEY.to_owned(), payload.len().to_string())
+                    .with_property(SHARD_INDEX_KEY.to_owned(), index.to_string())
+                    .with_payload(Bytes::from(shard))
+                    .build()
+                    .map_err(|e| EsError::unexpected(&format!("Invalid shard: {e:?}")))
+            })
+            .collect()
+    }
+
+    /// Decode record batches from payloads fetched from replicas, indexed by replica, and reconstruct
+    /// the original record batches in order of offset.
+    ///
+    /// Record batches are reconstructed as long as there are `data_shards` shards of them. Decoding
+    /// stops at the first record batch that lacks shards, as payloads of replicas may end at different
+    /// offsets due to the fetch size limit.
+    pub(crate) fn decode(&self, payloads: Vec<Option<Bytes>>) -> Result<Vec<RecordBatch>, EsError> {
+        // Shards of each record batch by base offset
+        let mut batches: BTreeMap<i64, Vec<Option<RecordBatch>>> = BTreeMap::new();
+        for (replica_index, payload) in payloads.into_iter().enumerate() {
+            let mut payload = match payload {
+                Some(payload) => payload,
+                None => continue,
+            };
+            while !payload.is_empty() {
+                let shard = FlatRecordBatch::decode_to_record_batch(&mut payload).map_err(|e| {
+                    EsError::new(
+                        ErrorCode::RECORDS_PARSE_ERROR,
+                        &format!("Failed to decode shard: {e:?}"),
+                    )
+                })?;
+                let index = property(&shard, SHARD_INDEX_KEY).unwrap_or(replica_index);
+                if index >= self.total_shards {
+                    warn!("Ignore shard with invalid index {index}");
+                    continue;
+                }
+                batches
+                    .entry(shard.base_offset())
+                    .or_insert_with(|| vec![None; self.total_shards])[index] = Some(shard);
+            }
+        }
+
+        let mut record_batches = vec![];
+        let mut next_offset = None;
+        for (base_offset, shards) in batches {
+            if next_offset.is_some_and(|offset| offset != base_offset) {
+                break;
+            }
+            if shards.iter().flatten().count() < self.data_shards {
+                break;
+            }
+            let record_batch = self.reconstruct(shards)?;
+            next_offset = Some(base_offset + record_batch.last_offset_delta() as i64);
+            record_batches.push(record_batch);
+        }
+        Ok(record_batches)
+    }
+
+    /// Reconstruct the original record batch from its shards, at least `data_shards` of which are present.
+    fn reconstruct(&self, shards: Vec<Option<RecordBatch>>) -> Result<RecordBatch, EsError> {
+        let first = shards
+            .iter()
+            .flatten()
+            .next()
+            .cloned()
+            .ok_or_else(|| EsError::unexpected("No shard to reconstruct"))?;
+        let payload_len = property(&first, PAYLOAD_LENGTH_KEY).ok_or_else(|| {
+            EsError::new(
+                ErrorCode::RECORDS_PARSE_ERROR,
+                "Shard without payload length",
+            )
+        })?;
+        let mut shards: Vec<Option<Vec<u8>>> = shards
+            .into_iter()
+            .map(|shard| shard.map(|shard| shard.payload().to_vec()))
+            .collect();
+        self.rs.reconstruct_data(&mut shards).map_err(|e| {
+            EsError::new(
+                ErrorCode::RECORDS_PARSE_ERROR,
+                &format!("Failed to reconstruct shards: {e:?}"),
+            )
+        })?;
+        let mut payload = BytesMut::with_capacity(payload_len);
+        for shard in shards.into_iter().take(self.data_shards).flatten() {
+            payload.extend_from_slice(&shard);
+        }
+        payload.truncate(payload_len);
+        shard_builder(&first)
+            .with_payload(payload.freeze())
+            .build()
+            .map_err(|e| EsError::unexpected(&format!("Invalid record batch: {e:?}")))
+    }
+}
+
+/// Builder with metadata of the given record batch, except properties of shards.
+fn shard_builder(record_batch: &RecordBatch) -> model::record::RecordBatchBuilder {
+    let mut builder = RecordBatch::new_builder()
+        .with_magic(record_batch.magic())
+        .with_stream_id(record_batch.stream_id())
+        .with_range_index(record_batch.range_index())
+        .with_flags(record_batch.flags())
+        .with_base_offset(record_batch.base_offset())
+        .with_last_offset_delta(record_batch.last_offset_delta() as i32)
+        .with_base_timestamp(record_batch.base_timestamp());
+    if let Some(properties) = record_batch.properties() {
+        for kv in properties
+            .iter()
+            .filter(|kv| kv.key != PAYLOAD_LENGTH_KEY && kv.key != SHARD_INDEX_KEY)
+        {
+            builder = builder.with_property(kv.key.clone(), kv.value.clone());
+        }
+    }
+    builder
+}
+
+fn property(record_batch: &RecordBatch, key: &str) -> Option<usize> {
+    record_batch
+        .properties()?
+        .iter()
+        .find(|kv| kv.key == key)
+        .and_then(|kv| kv.value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use model::{
+        record::{
+            flat_record::{FlatRecordBatch, RecordMagic},
+            magic1::RecordsBuilder,
+        },
+        RecordBatch,
+    };
+
+    use super::ErasureCoder;
+    use crate::stream::replication_range::vec_bytes_to_bytes;
+
+    fn record_batch(base_offset: i64, payload: &'static [u8]) -> RecordBatch {
+        RecordBatch::new_builder()
+            .with_stream_id(1)
+            .with_range_index(0)
+            .with_base_offset(base_offset)
+            .with_last_offset_delta(2)
+            .with_base_timestamp(0)
+            .with_property("key".to_owned(), "value".to_owned())
+            .with_payload(Bytes::from_static(payload))
+            .build()
+            .unwrap()
+    }
+
+    fn to_bytes(record_batches: &[RecordBatch]) -> Bytes {
+        let bytes = record_batches
+            .iter()
+            .flat_map(|record_batch| FlatRecordBatch::from(record_batch.clone()).encode().0)
+            .collect();
+        vec_bytes_to_bytes(&bytes)
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(ErasureCoder::new(0, 3).is_err());
+        assert!(ErasureCoder::new(3, 3).is_err());
+        assert_eq!(2, ErasureCoder::new(2, 3).unwrap().data_shards());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let coder = ErasureCoder::new(3, 5).unwrap();
+        let batches = [
+            record_batch(10, b"hello, erasure coded world"),
+            record_batch(12, b""),
+            record_batch(14, b"abc"),
+        ];
+        let shards: Vec<Vec<RecordBatch>> = batches
+            .iter()
+            .map(|batch| coder.encode(batch).unwrap())
+            .collect();
+        assert!(shards.iter().all(|shards| shards.len() == 5));
+
+        // Payloads held by each replica
+        let payloads: Vec<Bytes> = (0..5)
+            .map(|i| to_bytes(&shards.iter().map(|s| s[i].clone()).collect::<Vec<_>>()))
+            .collect();
+
+        // Lose two replicas
+        let decoded = coder
+            .decode(vec![
+                None,
+                Some(payloads[1].clone()),
+                None,
+                Some(payloads[3].clone()),
+                Some(payloads[4].clone()),
+            ])
+            .unwrap();
+        assert_eq!(3, decoded.len());
+        for (decoded, batch) in decoded.iter().zip(batches.iter()) {
+            assert_eq!(batch.base_offset(), decoded.base_offset());
+            assert_eq!(batch.payload(), decoded.payload());
+            assert_eq!(1, decoded.properties().unwrap().len());
+        }
+
+        // The last record batch lacks shards on one of the replicas
+        let truncated = to_bytes(&[shards[0][3].clone(), shards[1][3].clone()]);
+        let decoded = coder
+            .decode(vec![
+                Some(payloads[0].clone()),
+                None,
+                None,
+                Some(truncated),
+                Some(payloads[4].clone()),
+            ])
+            .unwrap();
+        assert_eq!(2, decoded.len());
+
+        // Not enough shards
+        let decoded = coder
+            .decode(vec![
+                Some(payloads[0].clone()),
+                None,
+                None,
+                None,
+                Some(payloads[4].clone()),
+            ])
+            .unwrap();
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn test_encode_decode_records() {
+        let coder = ErasureCoder::new(2, 3).unwrap();
+        let mut builder = RecordsBuilder::new();
+        builder.append(1, Some(Bytes::from_static(b"key")), None, &[]);
+        builder.append(2, None, Some(Bytes::from_static(b"value")), &[]);
+        let batch = builder.build(1).unwrap();
+        let shards = coder.encode(&batch).unwrap();
+
+        let decoded = coder
+            .decode(vec![
+                Some(to_bytes(&shards[0..1])),
+                None,
+                Some(to_bytes(&shards[2..3])),
+            ])
+            .unwrap();
+        assert_eq!(1, decoded.len());
+        assert_eq!(RecordMagic::Magic1, decoded[0].magic());
+        let records: Vec<_> = decoded[0]
+            .records()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(2, records.len());
+        assert_eq!(Some(Bytes::from_static(b"key")), records[0].key);
+        assert_eq!(Some(Bytes::from_static(b"value")), records[1].value);
+    }
+}
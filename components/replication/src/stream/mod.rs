@@ -7,9 +7,11 @@ use mockall::automock;
 
 pub(crate) mod cache;
 pub(crate) mod cache_stream;
+pub(crate) mod erasure;
 pub(crate) mod metrics;
 pub(crate) mod object_reader;
 pub(crate) mod object_stream;
+pub(crate) mod read_policy;
 pub(crate) mod records_block;
 pub(crate) mod replication_range;
 pub(crate) mod replication_replica;
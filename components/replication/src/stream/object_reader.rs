@@ -229,9 +229,18 @@ impl AsyncObjectReader {
 #[cfg(test)]
 mod tests {
     use bytes::{BufMut, Bytes, BytesMut};
-    use model::{object::gen_footer, record::flat_record::FlatRecordBatch, RecordBatch};
+    use model::{
+        object::gen_footer,
+        record::{
+            flat_record::{FlatRecordBatch, RecordMagic},
+            magic1::RecordsBuilder,
+        },
+        RecordBatch,
+    };
 
-    use crate::stream::replication_range::vec_bytes_to_bytes;
+    use crate::stream::replication_range::{
+        record_batch_to_bytes, vec_bytes_to_bytes, RangeAppendContext,
+    };
 
     use super::*;
     use std::{env, error::Error};
@@ -283,6 +292,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_read_records() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let path = "test_read_records";
+            let mut builder = RecordsBuilder::new();
+            builder.append(1, Some(Bytes::from_static(b"k0")), None, &[]);
+            builder.append(2, None, Some(Bytes::from_static(b"v1")), &[]);
+            let record_batch = builder.build(1).unwrap();
+            let data = vec_bytes_to_bytes(&record_batch_to_bytes(
+                &record_batch,
+                &RangeAppendContext::new(100),
+                1,
+                0,
+            ));
+            let data_len = data.len() as u32;
+            let mut object_bytes = BytesMut::from(&data[..]);
+            object_bytes.put(gen_footer(data_len, 0));
+            let mut fs_builder = Fs::default();
+            fs_builder.root("/tmp/");
+            let op = Operator::new(fs_builder).unwrap().finish();
+            op.write(path, object_bytes.freeze()).await.unwrap();
+
+            env::set_var("ES_OBJ_ENDPOINT", "fs://");
+            let obj_reader = AsyncObjectReader::new();
+            let mut object_metadata = ObjectMetadata::new(1, 2, 3, 100);
+            object_metadata.key = Some(path.to_owned());
+            object_metadata.data_len = data_len;
+            let blocks = obj_reader
+                .read(&object_metadata, (0, data_len))
+                .await
+                .unwrap();
+            assert_eq!(1, blocks.len());
+            assert_eq!(100, blocks[0].start_offset());
+            assert_eq!(102, blocks[0].end_offset());
+
+            // Payload of objects is kept as is, so records decode the same as appended.
+            let mut buf = vec_bytes_to_bytes(&blocks[0].records[0].data);
+            let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf).unwrap();
+            assert_eq!(RecordMagic::Magic1, record_batch.magic());
+            let records: Vec<_> = record_batch
+                .records()
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(2, records.len());
+            assert_eq!(100, records[0].offset);
+            assert_eq!(Some(Bytes::from_static(b"k0")), records[0].key);
+            assert_eq!(101, records[1].offset);
+            assert_eq!(Some(Bytes::from_static(b"v1")), records[1].value);
+        });
+        Ok(())
+    }
+
     async fn write_object(path: &str) -> u32 {
         let mut object_bytes = BytesMut::new();
         let mut data_len = 0;
@@ -307,6 +369,7 @@ mod tests {
 
     fn new_record_batch_bytes(base_offset: u64, count: u32, payload_size: u32) -> Bytes {
         let builder = RecordBatch::new_builder()
+            .with_magic(RecordMagic::Magic0)
             .with_stream_id(1)
             .with_range_index(0)
             .with_base_offset(base_offset as i64)
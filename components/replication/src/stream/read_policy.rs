@@ -0,0 +1,164 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use config::Configuration;
+use hdrhistogram::Histogram;
+use model::RangeServer;
+
+thread_local! {
+    static READ_POLICY: RefCell<Rc<ReadPolicy>> = RefCell::new(Rc::new(ReadPolicy::default()));
+}
+
+/// Latency samples required before reads are hedged.
+const MIN_SAMPLES: u64 = 100;
+
+/// Latency samples kept, beyond which the histogram restarts, so that the hedge delay follows recent
+/// latency of range servers.
+const MAX_SAMPLES: u64 = 8192;
+
+/// Policy on selecting replicas to read from, shared by ranges of the same runtime thread.
+///
+/// Replicas in the zone of the client are preferred, and a read that is slow to answer is hedged to
+/// another replica once it exceeds the configured percentile of recent read latency.
+pub(crate) struct ReadPolicy {
+    zone: Option<String>,
+    hedge_percentile: Option<f64>,
+    hedge_min_delay: Duration,
+    latency: RefCell<Histogram<u64>>,
+}
+
+impl Default for ReadPolicy {
+    fn default() -> Self {
+        Self {
+            zone: None,
+            hedge_percentile: None,
+            hedge_min_delay: Duration::ZERO,
+            latency: RefCell::new(Histogram::new_with_max(u64::MAX, 3).unwrap()),
+        }
+    }
+}
+
+impl ReadPolicy {
+    pub(crate) fn new(config: &Configuration) -> Self {
+        let hedge = &config.replication.hedge;
+        Self {
+            zone: config.client.zone.clone(),
+            hedge_percentile: if hedge.percentile > 0.0 {
+                Some(hedge.percentile.min(100.0))
+            } else {
+                None
+            },
+            hedge_min_delay: Duration::from_millis(hedge.min_delay_ms),
+            ..Default::default()
+        }
+    }
+
+    /// Install the policy for ranges created by the current runtime thread.
+    pub(crate) fn install(self) {
+        READ_POLICY.with(|policy| *policy.borrow_mut() = Rc::new(self));
+    }
+
+    /// Policy of the current runtime thread.
+    pub(crate) fn current() -> Rc<ReadPolicy> {
+        READ_POLICY.with(|policy| Rc::clone(&policy.borrow()))
+    }
+
+    /// Order in which replicas on the given range servers are read.
+    ///
+    /// Replicas in the zone of the client come first, then the others. Each group is rotated by
+    /// `sticky`, which moves on once a replica fails to read.
+    pub(crate) fn order(&self, servers: &[RangeServer], sticky: usize) -> Vec<usize> {
+        let (mut local, mut remote): (Vec<_>, Vec<_>) = (0..servers.len()).partition(|&i| {
+            match &self.zone {
+                Some(zone) => servers[i].zone.as_ref() == Some(zone),
+                // Without zone of the client, all replicas are treated alike.
+                None => true,
+            }
+        });
+        if !local.is_empty() {
+            let n = sticky % local.len();
+            local.rotate_left(n);
+        }
+        if !remote.is_empty() {
+            let n = sticky % remote.len();
+            remote.rotate_left(n);
+        }
+        local.extend(remote);
+        local
+    }
+
+    /// Delay after which a read is hedged to another replica, or `None` if hedging is disabled or
+    /// there are not enough latency samples yet.
+    pub(crate) fn hedge_delay(&self) -> Option<Duration> {
+        let percentile = self.hedge_percentile?;
+        let latency = self.latency.borrow();
+        if latency.len() < MIN_SAMPLES {
+            return None;
+        }
+        let delay = Duration::from_micros(latency.value_at_percentile(percentile));
+        Some(delay.max(self.hedge_min_delay))
+    }
+
+    /// Record latency of a read from a replica.
+    pub(crate) fn record(&self, elapsed: Duration) {
+        let mut latency = self.latency.borrow_mut();
+        if latency.len() >= MAX_SAMPLES {
+            latency.reset();
+        }
+        let _ = latency.record(elapsed.as_micros() as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use model::RangeServer;
+    use protocol::rpc::header::RangeServerState;
+
+    use super::{ReadPolicy, MIN_SAMPLES};
+
+    fn server(id: i32, zone: Option<&str>) -> RangeServer {
+        let mut server =
+            RangeServer::new(id, "addr", RangeServerState::RANGE_SERVER_STATE_READ_WRITE);
+        server.zone = zone.map(|zone| zone.to_owned());
+        server
+    }
+
+    #[test]
+    fn test_order() {
+        let servers = vec![
+            server(0, Some("az-1")),
+            server(1, Some("az-2")),
+            server(2, None),
+            server(3, Some("az-2")),
+        ];
+        let policy = ReadPolicy::default();
+        assert_eq!(vec![0, 1, 2, 3], policy.order(&servers, 0));
+        assert_eq!(vec![1, 2, 3, 0], policy.order(&servers, 1));
+
+        let mut config = config::Configuration::default();
+        config.client.zone = Some("az-2".to_owned());
+        let policy = ReadPolicy::new(&config);
+        assert_eq!(vec![1, 3, 0, 2], policy.order(&servers, 0));
+        assert_eq!(vec![3, 1, 2, 0], policy.order(&servers, 1));
+    }
+
+    #[test]
+    fn test_hedge_delay() {
+        let mut config = config::Configuration::default();
+        config.replication.hedge.percentile = 90.0;
+        config.replication.hedge.min_delay_ms = 1;
+        let policy = ReadPolicy::new(&config);
+        assert_eq!(None, policy.hedge_delay());
+        for i in 0..MIN_SAMPLES {
+            policy.record(Duration::from_millis(if i < 95 { 2 } else { 100 }));
+        }
+        let delay = policy.hedge_delay().unwrap();
+        assert!(delay >= Duration::from_millis(2) && delay < Duration::from_millis(3));
+
+        config.replication.hedge.percentile = 0.0;
+        let policy = ReadPolicy::new(&config);
+        policy.record(Duration::from_millis(1));
+        assert_eq!(None, policy.hedge_delay());
+    }
+}
@@ -111,7 +111,7 @@ impl RecordsBlock {
                 break;
             }
             let magic_code = cursor.get_i8();
-            if magic_code != RecordMagic::Magic0 as i8 {
+            if RecordMagic::try_from(magic_code).is_err() {
                 return Err(DecodeError::InvalidMagic);
             }
             let metadata_len = cursor.get_i32() as usize;
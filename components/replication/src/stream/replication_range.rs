@@ -11,13 +11,22 @@ use itertools::Itertools;
 use log::{debug, error, info, warn};
 
 use model::record::{flat_record::FlatRecordBatch, RecordBatch};
-use model::{error::EsError, range::RangeMetadata};
+use model::{
+    error::EsError,
+    range::{RangeMetadata, ReplicaLayout},
+    response::fetch::FetchResultSet,
+};
 
 use tokio::sync::broadcast;
 
 use super::{
-    cache::HotCache, metrics::METRICS, records_block::RecordsBlock,
-    replication_replica::ReplicationReplica, FetchDataset,
+    cache::HotCache,
+    erasure::ErasureCoder,
+    metrics::METRICS,
+    read_policy::ReadPolicy,
+    records_block::RecordsBlock,
+    replication_replica::{target, ReplicationReplica},
+    FetchDataset,
 };
 
 use protocol::rpc::header::{ErrorCode, SealKind};
@@ -38,6 +47,7 @@ where
         epoch: u64,
         index: u32,
         start_offset: u64,
+        layout: ReplicaLayout,
     ) -> Result<RangeMetadata, EsError>;
 
     fn new(
@@ -63,6 +73,10 @@ where
 
     async fn seal(&self) -> Result<u64, EsError>;
 
+    /// Refresh the confirm offset of a range that is not open for write, from the offsets its
+    /// replicas persist, so that readers follow a range being written by another stream.
+    async fn refresh_confirm_offset(&self) -> Result<u64, EsError>;
+
     fn is_sealed(&self) -> bool;
 
     fn is_writable(&self) -> bool;
@@ -104,6 +118,11 @@ where
 
     sticky_read_index: RefCell<u64>,
 
+    read_policy: Rc<ReadPolicy>,
+
+    /// Coder of record batches if the range is erasure coded.
+    coder: Option<ErasureCoder>,
+
     ack_callback: Box<dyn Fn() + 'static>,
 }
 
@@ -221,6 +240,191 @@ where
             "range get client fail, client is dropped",
         ))
     }
+
+    /// Fetch shards from `data_shards` replicas, preferring those in the zone of the client, and
+    /// reconstruct record batches. Shards are fetched from the other replicas as well if some of
+    /// them fail or lack shards. Erasure coded ranges are never offloaded, see `DefaultObjectManager`.
+    async fn fetch_erasure_coded(
+        &self,
+        coder: &ErasureCoder,
+        start_offset: u64,
+        end_offset: u64,
+        batch_max_bytes: u32,
+    ) -> Result<FetchDataset, EsError> {
+        let order = self.read_policy.order(
+            self.metadata.replica(),
+            *self.sticky_read_index.borrow() as usize,
+        );
+        let mut payloads = vec![None; self.replicas.len()];
+        let mut object_metadata_list = None;
+        let mut last_read_err = None;
+        let mut record_batches = vec![];
+        let mut fetched = 0;
+        while fetched < order.len() {
+            // Fetch from `data_shards` replicas first, then the others if shards are not enough.
+            let to_fetch = if fetched == 0 {
+                coder.data_shards().min(order.len())
+            } else {
+                order.len() - fetched
+            };
+            let mut tasks = Vec::with_capacity(to_fetch);
+            for &read_index in &order[fetched..fetched + to_fetch] {
+                let replica = Rc::clone(&self.replicas[read_index]);
+                tasks.push((
+                    read_index,
+                    tokio_uring::spawn(async move {
+                        replica
+                            .fetch(start_offset, end_offset, batch_max_bytes)
+                            .await
+                    }),
+                ));
+            }
+            fetched += to_fetch;
+            let mut failed = false;
+            for (read_index, task) in tasks {
+                let result = task
+                    .await
+                    .unwrap_or_else(|e| Err(EsError::unexpected(&format!("Fetch task fail: {e}"))));
+                match result {
+                    Ok(fetch_result) => {
+                        payloads[read_index] = Some(fetch_result.payload.unwrap_or_default());
+                        if object_metadata_list.is_none() {
+                            object_metadata_list = fetch_result.object_metadata_list;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("{}Fetch shards [{start_offset}, {end_offset}) from replica#{read_index} fail, err: {e}", self.log_ident);
+                        last_read_err = Some(e);
+                        failed = true;
+                        *self.sticky_read_index.borrow_mut() += 1;
+                    }
+                }
+            }
+            record_batches = coder.decode(payloads.clone()).map_err(|e| {
+                error!(
+                    "{}Fetch [{}, {}) decode shards fail, err: {}",
+                    self.log_ident, start_offset, end_offset, e
+                );
+                e
+            })?;
+            let all_empty = payloads.iter().flatten().all(|payload| payload.is_empty());
+            if !record_batches.is_empty() || (!failed && all_empty) {
+                break;
+            }
+        }
+        if record_batches.is_empty() && payloads.iter().flatten().any(|p| !p.is_empty()) {
+            // Shards are present but not enough to reconstruct any record batch.
+            return Err(last_read_err.unwrap_or_else(|| {
+                EsError::new(
+                    ErrorCode::ALL_REPLICAS_FETCH_FAILED,
+                    "not enough shards to reconstruct records",
+                )
+            }));
+        }
+        if payloads.iter().all(|payload| payload.is_none()) {
+            return Err(last_read_err.unwrap_or_else(|| {
+                EsError::new(
+                    ErrorCode::ALL_REPLICAS_FETCH_FAILED,
+                    "all replicas fetch fail",
+                )
+            }));
+        }
+        let blocks = if !record_batches.is_empty() {
+            let mut bytes = vec![];
+            for record_batch in record_batches {
+                let flat_record_batch: FlatRecordBatch = Into::into(record_batch);
+                bytes.extend(flat_record_batch.encode().0);
+            }
+            RecordsBlock::parse(vec_bytes_to_bytes(&bytes), 1024 * 1024, false).map_err(|e| {
+                error!(
+                    "{}Fetch [{}, {}) decode fail, err: {}",
+                    self.log_ident, start_offset, end_offset, e
+                );
+                EsError::new(ErrorCode::RECORDS_PARSE_ERROR, "parse records fail")
+            })?
+        } else {
+            vec![RecordsBlock::empty_block(end_offset)]
+        };
+        Ok(if let Some(object) = object_metadata_list {
+            FetchDataset::Mixin(blocks, object)
+        } else {
+            FetchDataset::Full(blocks)
+        })
+    }
+
+    /// Fetch from the replica of `read_index`. If it does not answer within the hedge delay, fetch
+    /// from the replica of `hedge_index` as well and take whichever succeeds first. Once one of them
+    /// fails, the other one still in flight is awaited.
+    ///
+    /// # Returns
+    /// The fetch result, or the last error along with indexes of replicas that failed to fetch.
+    async fn fetch_with_hedge(
+        &self,
+        read_index: usize,
+        hedge_index: Option<usize>,
+        start_offset: u64,
+        end_offset: u64,
+        batch_max_bytes: u32,
+    ) -> Result<FetchResultSet, (Vec<usize>, EsError)> {
+        let start = Instant::now();
+        let fetch = self.replicas[read_index].fetch(start_offset, end_offset, batch_max_bytes);
+        let (hedge_index, delay) = match (hedge_index, self.read_policy.hedge_delay()) {
+            (Some(hedge_index), Some(delay)) => (hedge_index, delay),
+            _ => {
+                let result = fetch.await;
+                return self.complete_fetch(start, result, vec![read_index]);
+            }
+        };
+        tokio::pin!(fetch);
+        tokio::select! {
+            result = &mut fetch => {
+                return self.complete_fetch(start, result, vec![read_index]);
+            }
+            _ = tokio::time::sleep(delay) => {}
+        }
+        debug!(
+            "{}Fetch [{start_offset}, {end_offset}) from replica#{read_index} exceeds {delay:?}, hedge to replica#{hedge_index}",
+            self.log_ident
+        );
+        let hedge = self.replicas[hedge_index].fetch(start_offset, end_offset, batch_max_bytes);
+        tokio::pin!(hedge);
+        let (failed_index, e) = tokio::select! {
+            result = &mut fetch => match result {
+                Ok(fetch_result) => return self.complete_fetch(start, Ok(fetch_result), vec![]),
+                Err(e) => (read_index, e),
+            },
+            result = &mut hedge => match result {
+                Ok(fetch_result) => return self.complete_fetch(start, Ok(fetch_result), vec![]),
+                Err(e) => (hedge_index, e),
+            },
+        };
+        warn!(
+            "{}Fetch [{start_offset}, {end_offset}) from replica#{failed_index} fail, wait for the other replica in flight, err: {e}",
+            self.log_ident
+        );
+        let (other_index, result) = if failed_index == read_index {
+            (hedge_index, hedge.await)
+        } else {
+            (read_index, fetch.await)
+        };
+        self.complete_fetch(start, result, vec![failed_index, other_index])
+    }
+
+    /// Record latency of a successful fetch, or attach replicas that failed to the error.
+    fn complete_fetch(
+        &self,
+        start: Instant,
+        result: Result<FetchResultSet, EsError>,
+        failed: Vec<usize>,
+    ) -> Result<FetchResultSet, (Vec<usize>, EsError)> {
+        match result {
+            Ok(fetch_result) => {
+                self.read_policy.record(start.elapsed());
+                Ok(fetch_result)
+            }
+            Err(e) => Err((failed, e)),
+        }
+    }
 }
 
 fn replicas_seal0(
@@ -265,14 +469,16 @@ where
         epoch: u64,
         index: u32,
         start_offset: u64,
+        layout: ReplicaLayout,
     ) -> Result<RangeMetadata, EsError> {
         // 1. request placement driver to create range and get the range metadata.
         let mut metadata = RangeMetadata::new(stream_id, index as i32, epoch, start_offset, None);
+        metadata.set_layout(layout);
         metadata = client.create_range(metadata).await?;
         // 2. request range server to create range replica.
         let mut create_replica_tasks = vec![];
         for server in metadata.replica().iter() {
-            let address = server.advertise_address.clone();
+            let address = target(server);
             let metadata = metadata.clone();
             let client = client.clone();
             create_replica_tasks.push(tokio_uring::spawn(async move {
@@ -310,6 +516,27 @@ where
         let (seal_task_tx, _) = broadcast::channel::<Result<u64, Rc<EsError>>>(1);
 
         let log_ident = format!("Range[{}#{}] ", metadata.stream_id(), metadata.index());
+        let coder = match metadata.layout() {
+            ReplicaLayout::Full => None,
+            ReplicaLayout::ReedSolomon { data_shards } => {
+                match ErasureCoder::new(data_shards, metadata.replica_count()) {
+                    Ok(coder) => Some(coder),
+                    Err(e) => {
+                        // The range is marked corrupted below, so that it is neither written nor read.
+                        error!(
+                            "{log_ident}Invalid replica layout {:?}, err: {e}",
+                            metadata.layout()
+                        );
+                        None
+                    }
+                }
+            }
+        };
+        let status = if metadata.layout() != ReplicaLayout::Full && coder.is_none() {
+            status | CORRUPTED_FLAG
+        } else {
+            status
+        };
         let mut this = Rc::new(Self {
             log_ident,
             weak_self: Weak::new(),
@@ -323,6 +550,8 @@ where
             status: RefCell::new(status),
             seal_task_tx: Rc::new(seal_task_tx),
             sticky_read_index: RefCell::new(0),
+            read_policy: ReadPolicy::current(),
+            coder,
             ack_callback,
         });
 
@@ -384,6 +613,30 @@ where
             // will be reused in future appends.
             vec![vec_bytes_to_bytes(&flat_record_batch_bytes)],
         );
+        if let Some(coder) = &self.coder {
+            // Each replica holds a shard of the record batch.
+            let shards = match coder.encode(record_batch) {
+                Ok(shards) => shards,
+                Err(e) => {
+                    error!("{}Failed to encode record batch, err: {e}", self.log_ident);
+                    self.mark_corrupted();
+                    return;
+                }
+            };
+            for (replica, shard) in (*self.replicas).iter().zip(shards.iter()) {
+                replica.append(
+                    record_batch_to_bytes(
+                        shard,
+                        &context,
+                        self.metadata().stream_id(),
+                        self.metadata().index() as u32,
+                    ),
+                    base_offset,
+                    base_offset + last_offset_delta as u64,
+                );
+            }
+            return;
+        }
         for replica in (*self.replicas).iter() {
             replica.append(
                 flat_record_batch_bytes.clone(),
@@ -400,24 +653,43 @@ where
         batch_max_bytes: u32,
     ) -> Result<FetchDataset, EsError> {
         let now: Instant = Instant::now();
-        let mut read_times = 0;
-        let replicas_len = self.replicas.len();
+        if let Some(coder) = &self.coder {
+            return self
+                .fetch_erasure_coded(coder, start_offset, end_offset, batch_max_bytes)
+                .await
+                .map(|dataset| {
+                    METRICS.with(|m| m.record_fetch_stream(now.elapsed().as_micros() as u64));
+                    dataset
+                });
+        }
+        let order = self.read_policy.order(
+            self.metadata.replica(),
+            *self.sticky_read_index.borrow() as usize,
+        );
         let mut last_read_err = None;
-        loop {
-            read_times += 1;
-            if read_times > replicas_len {
-                break;
+        // Replicas that failed to fetch, either read or hedged to, are not retried.
+        let mut failed = vec![false; self.replicas.len()];
+        for (i, &read_index) in order.iter().enumerate() {
+            if failed[read_index] {
+                continue;
             }
-            let read_index = *self.sticky_read_index.borrow() % replicas_len as u64;
-            let replica = &self.replicas[read_index as usize];
-
-            let result = replica
-                .fetch(start_offset, end_offset, batch_max_bytes)
+            let hedge_index = order[i + 1..].iter().copied().find(|&index| !failed[index]);
+            let result = self
+                .fetch_with_hedge(
+                    read_index,
+                    hedge_index,
+                    start_offset,
+                    end_offset,
+                    batch_max_bytes,
+                )
                 .await;
             let fetch_result = match result {
                 Ok(rs) => rs,
-                Err(e) => {
+                Err((failed_indexes, e)) => {
                     warn!("{}Fetch [{start_offset}, {end_offset}) with batch_max_bytes={batch_max_bytes} fail, err: {e}", self.log_ident);
+                    failed_indexes
+                        .into_iter()
+                        .for_each(|index| failed[index] = true);
                     last_read_err = Some(Err(e));
                     *self.sticky_read_index.borrow_mut() += 1;
                     continue;
@@ -592,6 +864,36 @@ where
         }
     }
 
+    async fn refresh_confirm_offset(&self) -> Result<u64, EsError> {
+        if self.open_for_write || self.is_sealed() {
+            return Ok(self.confirm_offset());
+        }
+        let tasks = self
+            .replicas
+            .iter()
+            .map(|replica| {
+                let replica = replica.clone();
+                tokio_uring::spawn(async move { replica.refresh_confirm_offset().await })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            if let Ok(Err(e)) = task.await {
+                // Offsets of the replica are stale, which only holds the confirm offset back.
+                debug!(
+                    "{}Failed to refresh replica offset, err: {e}",
+                    self.log_ident
+                );
+            }
+        }
+        let new_confirm_offset = self.calculate_confirm_offset()?;
+        let mut confirm_offset = self.confirm_offset.borrow_mut();
+        if new_confirm_offset > *confirm_offset {
+            *confirm_offset = new_confirm_offset;
+            *self.next_offset.borrow_mut() = new_confirm_offset;
+        }
+        Ok(*confirm_offset)
+    }
+
     fn is_sealed(&self) -> bool {
         *self.status.borrow() & SEALED_FLAG != 0
     }
@@ -639,6 +941,7 @@ pub(crate) fn record_batch_to_bytes(
 ) -> Vec<Bytes> {
     let base_offset = context.base_offset;
     let mut record_batch_builder = RecordBatch::new_builder()
+        .with_magic(record_batch.magic())
         .with_stream_id(stream_id as i64)
         // use current range index.
         .with_range_index(range_index as i32)
@@ -662,7 +965,7 @@ pub(crate) fn record_batch_to_bytes(
 
 #[cfg(test)]
 mod tests {
-    use std::error::Error;
+    use std::{collections::HashMap, error::Error, time::Duration};
 
     use client::client::MockClient;
     use model::{range_server::RangeServer, response::fetch::FetchResultSet};
@@ -830,6 +1133,7 @@ mod tests {
                         throttle: None,
                         object_metadata_list: None,
                         payload: Some(vec_bytes_to_bytes(&payload)),
+                        committed: None,
                     };
                     Ok(rst)
                 });
@@ -853,6 +1157,7 @@ mod tests {
                         throttle: None,
                         object_metadata_list: None,
                         payload: Some(BytesMut::zeroed(10).freeze()),
+                        committed: None,
                     };
                     Ok(rst)
                 });
@@ -864,6 +1169,122 @@ mod tests {
         Ok(())
     }
 
+    /// Replica whose fetch answers after the delay scripted for its range server.
+    struct ScriptedReplica {
+        server_id: i32,
+    }
+
+    thread_local! {
+        /// Delay and whether to succeed, of fetches from each range server.
+        static FETCH_SCRIPT: RefCell<HashMap<i32, (Duration, bool)>> = RefCell::new(HashMap::new());
+        /// Range servers fetched from, in order.
+        static FETCHED: RefCell<Vec<i32>> = RefCell::new(vec![]);
+    }
+
+    impl ReplicationReplica<MockClient> for ScriptedReplica {
+        fn new(
+            _metadata: RangeMetadata,
+            range_server: RangeServer,
+            _ack: Box<dyn Fn()>,
+            _client: Weak<MockClient>,
+        ) -> Self {
+            Self {
+                server_id: range_server.server_id,
+            }
+        }
+
+        fn confirm_offset(&self) -> u64 {
+            0
+        }
+
+        fn append(&self, _: Vec<Bytes>, _: u64, _: u64) {}
+
+        async fn fetch(&self, _: u64, _: u64, _: u32) -> Result<FetchResultSet, EsError> {
+            FETCHED.with(|fetched| fetched.borrow_mut().push(self.server_id));
+            let (delay, success) = FETCH_SCRIPT.with(|script| script.borrow()[&self.server_id]);
+            tokio::time::sleep(delay).await;
+            if !success {
+                return Err(EsError::unexpected("test mock error"));
+            }
+            let payload =
+                record_batch_to_bytes(&new_record(233, 10), &RangeAppendContext::new(233), 0, 1);
+            Ok(FetchResultSet {
+                throttle: None,
+                object_metadata_list: None,
+                payload: Some(vec_bytes_to_bytes(&payload)),
+                committed: None,
+            })
+        }
+
+        async fn seal(&self, _: Option<u64>) -> Result<u64, EsError> {
+            unimplemented!()
+        }
+
+        async fn refresh_confirm_offset(&self) -> Result<u64, EsError> {
+            unimplemented!()
+        }
+
+        fn corrupted(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_fetch_with_hedge() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let mut config = config::Configuration::default();
+            config.replication.hedge.percentile = 50.0;
+            config.replication.hedge.min_delay_ms = 10;
+            let policy = ReadPolicy::new(&config);
+            (0..100).for_each(|_| policy.record(Duration::from_millis(1)));
+            policy.install();
+
+            let mut metadata = RangeMetadata::new_range(0, 1, 2, 233, None, 3, 2);
+            for i in 0..3 {
+                metadata.replica_mut().push(RangeServer::new(
+                    233 + i,
+                    "addr",
+                    RangeServerState::RANGE_SERVER_STATE_READ_WRITE,
+                ));
+            }
+            let client = Rc::new(MockClient::default());
+            let range = DefaultReplicationRange::<ScriptedReplica, MockClient>::new(
+                metadata,
+                false,
+                Box::new(|| {}),
+                Rc::downgrade(&client),
+                Rc::new(HotCache::new(1024 * 1024)),
+            );
+            let fetch = |script: [(u64, bool); 3]| {
+                FETCH_SCRIPT.with(|s| {
+                    *s.borrow_mut() = script
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, (delay, success))| {
+                            (233 + i as i32, (Duration::from_millis(delay), success))
+                        })
+                        .collect()
+                });
+                FETCHED.with(|fetched| fetched.borrow_mut().clear());
+                range.fetch(233, 240, 1234)
+            };
+            let fetched = || FETCHED.with(|fetched| fetched.borrow().clone());
+
+            // The slow primary is hedged, and awaited once the hedge fails.
+            let dataset = fetch([(100, true), (0, false), (0, true)]).await.unwrap();
+            assert!(matches!(dataset, FetchDataset::Full(blocks) if blocks[0].end_offset() == 243));
+            assert_eq!(vec![233, 234], fetched());
+            assert_eq!(0, *range.sticky_read_index.borrow());
+
+            // Both the primary and the hedge fail, so the retry skips the hedged replica.
+            let dataset = fetch([(50, false), (0, false), (0, true)]).await.unwrap();
+            assert!(matches!(dataset, FetchDataset::Full(_)));
+            assert_eq!(vec![233, 234, 235], fetched());
+            assert_eq!(1, *range.sticky_read_index.borrow());
+        });
+        Ok(())
+    }
+
     #[test]
     fn test_seal_with_range_created_by_current_stream() -> Result<(), Box<dyn Error>> {
         tokio_uring::start(async move {
@@ -938,6 +1359,33 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_refresh_confirm_offset() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let metadata = RangeMetadata::new_range(0, 1, 2, 233, None, 3, 2);
+            let client = Rc::new(MockClient::default());
+            let mut range = new_range(metadata, false, Rc::new(RefCell::new(0)), client.clone());
+            for (index, offset) in [(0, Some(240)), (1, Some(250)), (2, None)] {
+                let replica = get_replica(&mut range, index);
+                replica.expect_corrupted().return_const(false);
+                replica
+                    .expect_confirm_offset()
+                    .return_const(offset.unwrap_or(233));
+                replica
+                    .expect_refresh_confirm_offset()
+                    .times(1)
+                    .returning(move || {
+                        offset.ok_or_else(|| EsError::unexpected("test mock error"))
+                    });
+            }
+            // The 2nd largest offset of replicas is confirmed, as ack count is 2.
+            assert_eq!(240, range.refresh_confirm_offset().await.unwrap());
+            assert_eq!(240, range.confirm_offset());
+            assert!(!range.is_writable());
+        });
+        Ok(())
+    }
+
     #[test]
     fn test_replica_seal0() {
         // Example1: replicas confirmOffset = [1, 2, 3]
@@ -14,6 +14,7 @@ use model::{
 use protocol::rpc::header::ErrorCode;
 use protocol::rpc::header::SealKind;
 use tokio::time::sleep;
+use transport::Address;
 
 #[cfg(test)]
 use mockall::automock;
@@ -45,6 +46,10 @@ where
 
     async fn seal(&self, end_offset: Option<u64>) -> Result<u64, EsError>;
 
+    /// Learn the offset up to which the range server persists records of the replica, for
+    /// readers that do not own the range and thus never receive append acks.
+    async fn refresh_confirm_offset(&self) -> Result<u64, EsError>;
+
     fn corrupted(&self) -> bool;
 }
 
@@ -59,6 +64,8 @@ where
     metadata: RangeMetadata,
     confirm_offset: Rc<RefCell<u64>>,
     range_server: RangeServer,
+    /// Address to connect `range_server`.
+    target: String,
     corrupted: Rc<RefCell<bool>>,
     writable: Rc<RefCell<bool>>,
     ack_callback: Rc<Box<dyn Fn()>>,
@@ -104,6 +111,7 @@ where
             ),
             metadata: metadata.clone(),
             confirm_offset: Rc::new(RefCell::new(confirm_offset)),
+            target: target(&range_server),
             range_server,
             corrupted: Rc::new(RefCell::new(false)),
             writable: Rc::new(RefCell::new(true)),
@@ -127,7 +135,7 @@ where
             }
         };
         let offset = Rc::clone(&self.confirm_offset);
-        let target = self.range_server.advertise_address.clone();
+        let target = self.target.clone();
         let corrupted = self.corrupted.clone();
         let writable = self.writable.clone();
         let ack = self.ack_callback.clone();
@@ -202,7 +210,7 @@ where
         let client = self.get_client()?;
         client
             .fetch(
-                &self.range_server.advertise_address,
+                &self.target,
                 FetchRequest {
                     max_wait: std::time::Duration::from_secs(3),
                     range: self.metadata.clone(),
@@ -231,11 +239,7 @@ where
         }
 
         return match client
-            .seal(
-                Some(&self.range_server.advertise_address),
-                SealKind::RANGE_SERVER,
-                metadata,
-            )
+            .seal(Some(&self.target), SealKind::RANGE_SERVER, metadata)
             .await
         {
             Ok(metadata) => {
@@ -257,11 +261,36 @@ where
         };
     }
 
+    async fn refresh_confirm_offset(&self) -> Result<u64, EsError> {
+        // Fetch nothing, so that the range server responds at once with its committed offset only.
+        let start_offset = self.metadata.start();
+        let result = self.fetch(start_offset, start_offset, 0).await?;
+        if let Some(committed) = result.committed {
+            let mut confirm_offset = self.confirm_offset.borrow_mut();
+            *confirm_offset = (*confirm_offset).max(committed);
+        }
+        Ok(self.confirm_offset())
+    }
+
     fn corrupted(&self) -> bool {
         *self.corrupted.borrow()
     }
 }
 
+/// Address to connect the range server: the Unix domain socket it listens on if it is on the current
+/// host, or its advertise address otherwise.
+pub(crate) fn target(range_server: &RangeServer) -> String {
+    if let Some(unix_address) = &range_server.unix_address {
+        let local = Address::resolve(&range_server.advertise_address).map_or(false, |addrs| {
+            addrs.first().map_or(false, Address::is_local)
+        });
+        if local {
+            return unix_address.clone();
+        }
+    }
+    range_server.advertise_address.clone()
+}
+
 #[cfg(test)]
 mod tests {
     use std::error::Error;
@@ -275,6 +304,26 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_target() {
+        let server = |address: &str, unix_address: Option<&str>| {
+            let mut server =
+                RangeServer::new(1, address, RangeServerState::RANGE_SERVER_STATE_READ_WRITE);
+            server.unix_address = unix_address.map(ToOwned::to_owned);
+            server
+        };
+        assert_eq!("127.0.0.1:10911", target(&server("127.0.0.1:10911", None)));
+        assert_eq!(
+            "unix:///tmp/es.sock",
+            target(&server("127.0.0.1:10911", Some("unix:///tmp/es.sock")))
+        );
+        // Range servers on other hosts are connected by their advertise address.
+        assert_eq!(
+            "192.0.2.1:10911",
+            target(&server("192.0.2.1:10911", Some("unix:///tmp/es.sock")))
+        );
+    }
+
     #[test]
     fn test_append() -> Result<(), Box<dyn Error>> {
         tokio_uring::start(async move {
@@ -391,4 +440,39 @@ mod tests {
         });
         Ok(())
     }
+
+    #[test]
+    fn test_refresh_confirm_offset() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let metadata = RangeMetadata::new_range(0, 1, 2, 233, None, 1, 1);
+            let client = MockClient::default();
+            let mut client = Rc::new(client);
+            let replica = DefaultReplicationReplica::new(
+                metadata,
+                RangeServer::new(233, "addr", RangeServerState::RANGE_SERVER_STATE_READ_WRITE),
+                Box::new(move || {}),
+                Rc::downgrade(&client),
+            );
+
+            unsafe {
+                let client = Rc::get_mut_unchecked(&mut client);
+                client.expect_fetch().times(2).returning(|_, r| {
+                    assert_eq!(233, r.offset);
+                    assert_eq!(233, r.limit);
+                    assert_eq!(None, r.max_bytes);
+                    Ok(FetchResultSet {
+                        throttle: None,
+                        payload: None,
+                        object_metadata_list: None,
+                        committed: Some(240),
+                    })
+                });
+            }
+            assert_eq!(240, replica.refresh_confirm_offset().await.unwrap());
+            // The confirm offset never goes backwards.
+            *replica.confirm_offset.borrow_mut() = 250;
+            assert_eq!(250, replica.refresh_confirm_offset().await.unwrap());
+        });
+        Ok(())
+    }
 }
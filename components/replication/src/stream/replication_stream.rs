@@ -1,3 +1,4 @@
+use crate::rollover::{OpenRollover, RolloverPolicy};
 use crate::stream::replication_range::RangeAppendContext;
 use crate::stream::replication_range::ReplicationRange;
 
@@ -9,8 +10,10 @@ use itertools::Itertools;
 use local_sync::{mpsc, oneshot};
 use log::{error, info, trace, warn};
 use model::error::EsError;
+use model::range::{RangeMetadata, ReplicaLayout};
+use model::resource::{EventType, Resource, ResourceEvent};
 use model::RecordBatch;
-use protocol::rpc::header::ErrorCode;
+use protocol::rpc::header::{ErrorCode, ResourceType};
 use std::cell::OnceCell;
 use std::cell::RefCell;
 use std::cmp::min;
@@ -21,6 +24,16 @@ use std::time::Instant;
 use tokio::sync::broadcast;
 use tokio::time::{sleep, Duration};
 
+/// Timeout of each range metadata watch issued by read-only streams.
+const RANGE_WATCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval at which read-only streams refresh the confirm offset of the active range.
+const CONFIRM_OFFSET_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Interval at which writable streams check whether the last range is due to roll over by age,
+/// so that idle streams roll over without waiting for the next append.
+const ROLLOVER_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 pub(crate) struct ReplicationStream<R, C>
 where
     R: ReplicationRange<C> + 'static,
@@ -30,6 +43,16 @@ where
     weak_self: RefCell<Weak<Self>>,
     id: u64,
     epoch: u64,
+    /// Read-only streams never fence the writer. They follow range changes through
+    /// WATCH_RESOURCE and reject append, trim and delete.
+    read_only: bool,
+    rollover: OpenRollover,
+    /// Rollover policy in effect, resolved against the one persisted with the stream on open.
+    rollover_policy: RefCell<RolloverPolicy>,
+    /// Layout of replicas of ranges created by this stream.
+    layout: ReplicaLayout,
+    /// Usage of the last range created by this stream, against which rollover policy is checked.
+    last_range_usage: RefCell<RangeUsage>,
     ranges: RefCell<BTreeMap<u64, Rc<R>>>,
     client: Weak<C>,
     next_offset: RefCell<u64>,
@@ -52,7 +75,40 @@ where
     R: ReplicationRange<C> + 'static,
     C: Client + 'static,
 {
-    pub(crate) fn new(id: u64, epoch: u64, client: Weak<C>, cache: Rc<HotCache>) -> Rc<Self> {
+    pub(crate) fn new(
+        id: u64,
+        epoch: u64,
+        rollover: OpenRollover,
+        layout: ReplicaLayout,
+        client: Weak<C>,
+        cache: Rc<HotCache>,
+    ) -> Rc<Self> {
+        Self::new0(id, epoch, false, rollover, layout, client, cache)
+    }
+
+    /// Create a read-only stream, which loads ranges without bumping the stream epoch, such that
+    /// it may be opened by many readers concurrently with the active writer.
+    pub(crate) fn new_read_only(id: u64, client: Weak<C>, cache: Rc<HotCache>) -> Rc<Self> {
+        Self::new0(
+            id,
+            0,
+            true,
+            OpenRollover::Default(RolloverPolicy::default()),
+            ReplicaLayout::Full,
+            client,
+            cache,
+        )
+    }
+
+    fn new0(
+        id: u64,
+        epoch: u64,
+        read_only: bool,
+        rollover: OpenRollover,
+        layout: ReplicaLayout,
+        client: Weak<C>,
+        cache: Rc<HotCache>,
+    ) -> Rc<Self> {
         let (append_requests_tx, append_requests_rx) = mpsc::unbounded::channel();
         let (append_tasks_tx, append_tasks_rx) = mpsc::unbounded::channel();
         let (shutdown_signal_tx, shutdown_signal_rx) = broadcast::channel(1);
@@ -61,6 +117,11 @@ where
             weak_self: RefCell::new(Weak::new()),
             id,
             epoch,
+            read_only,
+            rollover,
+            rollover_policy: RefCell::new(RolloverPolicy::default()),
+            layout,
+            last_range_usage: RefCell::new(RangeUsage::new()),
             ranges: RefCell::new(BTreeMap::new()),
             client,
             next_offset: RefCell::new(0),
@@ -75,6 +136,11 @@ where
 
         *(this.weak_self.borrow_mut()) = Rc::downgrade(&this);
 
+        if read_only {
+            // Read-only stream never creates ranges, so there is no need of append task.
+            return this;
+        }
+
         let weak_this = this.weak_self.borrow().clone();
         let closed = this.closed.clone();
         tokio_uring::spawn(async move {
@@ -93,23 +159,20 @@ where
 
     async fn new_range(&self, range_index: u32, start_offset: u64) -> Result<Rc<R>, EsError> {
         if let Some(client) = self.client.upgrade() {
-            let range_metadata =
-                R::create(client, self.id, self.epoch, range_index, start_offset).await?;
-            let weak_this = self.weak_self.borrow().clone();
-            let range = R::new(
-                range_metadata,
-                true,
-                Box::new(move || {
-                    if let Some(stream) = weak_this.upgrade() {
-                        stream.try_ack();
-                    }
-                }),
-                self.client.clone(),
-                self.cache.clone(),
-            );
+            let range_metadata = R::create(
+                client,
+                self.id,
+                self.epoch,
+                range_index,
+                start_offset,
+                self.layout,
+            )
+            .await?;
+            let range = self.load_range(range_metadata, true);
             info!("{}Create new range: {:?}", self.log_ident, range.metadata());
             self.ranges.borrow_mut().insert(start_offset, range.clone());
             *self.last_range.borrow_mut() = Some(range.clone());
+            *self.last_range_usage.borrow_mut() = RangeUsage::new();
             Ok(range)
         } else {
             Err(EsError::new(
@@ -119,6 +182,212 @@ where
         }
     }
 
+    fn load_range(&self, metadata: RangeMetadata, open_for_write: bool) -> Rc<R> {
+        let weak_this = self.weak_self.borrow().clone();
+        R::new(
+            metadata,
+            open_for_write,
+            Box::new(move || {
+                if let Some(stream) = weak_this.upgrade() {
+                    stream.try_ack();
+                }
+            }),
+            self.client.clone(),
+            self.cache.clone(),
+        )
+    }
+
+    /// Resource version of ranges, from which range changes are watched.
+    async fn range_version(client: &Rc<C>) -> Result<i64, EsError> {
+        client
+            .list_resource(&[ResourceType::RESOURCE_RANGE], 1, &None)
+            .await
+            .map(|result| result.version)
+    }
+
+    async fn list_ranges(&self, client: &Rc<C>) -> Result<Vec<RangeMetadata>, EsError> {
+        client
+            .list_ranges(model::ListRangeCriteria::new(None, Some(self.id)))
+            .await
+            .map_err(|e| {
+                error!(
+                    "{}Failed to list ranges from placement-driver: {e}",
+                    self.log_ident
+                );
+                e
+            })
+    }
+
+    /// Apply the latest metadata of a range to a read-only stream.
+    fn refresh_range(&self, metadata: RangeMetadata) {
+        {
+            let mut ranges = self.ranges.borrow_mut();
+            let current = ranges
+                .iter()
+                .find(|(_, range)| range.metadata().index() == metadata.index())
+                .map(|(start, range)| (*start, range.metadata().end()));
+            if let Some((start, end)) = current {
+                if start == metadata.start() && end == metadata.end() {
+                    // Nothing visible to readers changes.
+                    return;
+                }
+                ranges.remove(&start);
+            }
+            // Skip old empty range when two ranges have the same start offset
+            if let Some(range) = ranges.get(&metadata.start()) {
+                if range.metadata().index() > metadata.index() {
+                    return;
+                }
+            }
+        }
+        trace!("{}Refresh range: {:?}", self.log_ident, metadata);
+        let start = metadata.start();
+        let range = self.load_range(metadata, false);
+        self.ranges.borrow_mut().insert(start, range);
+        self.refresh_offsets();
+    }
+
+    fn remove_range(&self, metadata: &RangeMetadata) {
+        self.ranges
+            .borrow_mut()
+            .retain(|_, range| range.metadata().index() != metadata.index());
+        self.refresh_offsets();
+    }
+
+    /// Read-only streams can read up to the confirm offset of the last known range.
+    fn refresh_offsets(&self) {
+        let last_range = self
+            .ranges
+            .borrow_mut()
+            .last_entry()
+            .map(|e| e.get().clone());
+        if let Some((start_offset, _)) = self.ranges.borrow().first_key_value() {
+            let mut current = self.start_offset.borrow_mut();
+            *current = (*current).max(*start_offset);
+        }
+        *self.next_offset.borrow_mut() = last_range.as_ref().map_or(0, |r| r.confirm_offset());
+        *self.last_range.borrow_mut() = last_range;
+    }
+
+    fn on_range_event(&self, event: &ResourceEvent) {
+        let metadata = match &event.resource {
+            Resource::Range(metadata) if metadata.stream_id() == self.id => metadata,
+            _ => return,
+        };
+        match event.event_type {
+            EventType::Listed | EventType::Added | EventType::Modified => {
+                self.refresh_range(metadata.clone());
+            }
+            EventType::Deleted => self.remove_range(metadata),
+            _ => {}
+        }
+    }
+
+    /// Reload all ranges of a read-only stream, returning the resource version to watch from.
+    async fn reload_ranges(&self) -> Result<i64, EsError> {
+        let client = self.get_client()?;
+        // Fetch version first, then changes between it and the listing are replayed harmlessly.
+        let version = Self::range_version(&client).await?;
+        let ranges = self.list_ranges(&client).await?;
+        self.ranges
+            .borrow_mut()
+            .retain(|_, range| ranges.iter().any(|m| m.index() == range.metadata().index()));
+        ranges
+            .into_iter()
+            .sorted_by(|a, b| Ord::cmp(&a.index(), &b.index()))
+            .for_each(|range| self.refresh_range(range));
+        self.refresh_offsets();
+        Ok(version)
+    }
+
+    /// Keep range metadata of the read-only stream up to date by watching range changes.
+    fn spawn_watch_task(&self, version: i64) {
+        let stream_id = self.id;
+        let stream = self.weak_self.borrow().clone();
+        let client = self.client.clone();
+        let log_ident = self.log_ident.clone();
+        let mut shutdown_signal_rx = self.shutdown_signal_tx.subscribe();
+        tokio_uring::spawn(async move {
+            let mut version = version;
+            loop {
+                let client = match client.upgrade() {
+                    Some(client) => client,
+                    None => break,
+                };
+                let result = tokio::select! {
+                    _ = shutdown_signal_rx.recv() => break,
+                    result = client.watch_resource(&[ResourceType::RESOURCE_RANGE], Some(stream_id), version, RANGE_WATCH_TIMEOUT) => result,
+                };
+                let stream = match stream.upgrade() {
+                    Some(stream) => stream,
+                    None => break,
+                };
+                match result {
+                    Ok(result) => {
+                        result
+                            .events
+                            .iter()
+                            .for_each(|event| stream.on_range_event(event));
+                        version = result.version;
+                    }
+                    // No range changes during the watch.
+                    Err(e) if e.code == ErrorCode::RPC_TIMEOUT => {}
+                    Err(e) => {
+                        // The version may have been compacted, so reload ranges from scratch.
+                        warn!(
+                            "{}Failed to watch ranges, reload later, err[{e}]",
+                            log_ident
+                        );
+                        sleep(Duration::from_millis(1000)).await;
+                        match stream.reload_ranges().await {
+                            Ok(v) => version = v,
+                            Err(e) => {
+                                warn!(
+                                    "{}Failed to reload ranges, retry later, err[{e}]",
+                                    log_ident
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            info!("{}Range watch task exits", log_ident);
+        });
+    }
+
+    /// Follow the active range of the read-only stream, which is written by another stream, by
+    /// polling the confirm offset its replicas persist.
+    fn spawn_tail_task(&self) {
+        let stream = self.weak_self.borrow().clone();
+        let log_ident = self.log_ident.clone();
+        let mut shutdown_signal_rx = self.shutdown_signal_tx.subscribe();
+        tokio_uring::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown_signal_rx.recv() => break,
+                    _ = sleep(CONFIRM_OFFSET_POLL_INTERVAL) => {}
+                }
+                let stream = match stream.upgrade() {
+                    Some(stream) => stream,
+                    None => break,
+                };
+                let last_range = stream.last_range.borrow().clone();
+                let last_range = match last_range {
+                    // Sealed ranges are followed by the range watch.
+                    Some(range) if range.metadata().end().is_none() => range,
+                    _ => continue,
+                };
+                match last_range.refresh_confirm_offset().await {
+                    Ok(_) => stream.refresh_offsets(),
+                    Err(e) => {
+                        trace!("{}Failed to refresh confirm offset, err[{e}]", log_ident);
+                    }
+                }
+            }
+            info!("{}Confirm offset tail task exits", log_ident);
+        });
+    }
+
     pub(crate) fn try_ack(&self) {
         self.trigger_append_task();
     }
@@ -144,6 +413,7 @@ where
         let mut inflight: BTreeMap<u64, Rc<StreamAppendRequest>> = BTreeMap::new();
         let mut next_append_start_offset: u64 = 0;
         let mut append_error = None;
+        let mut rollover_check = tokio::time::interval(ROLLOVER_CHECK_INTERVAL);
 
         loop {
             tokio::select! {
@@ -157,6 +427,12 @@ where
                 Some(_) = append_tasks_rx.recv() => {
                     // usually send by range ack / delay retry
                 }
+                _ = rollover_check.tick() => {
+                    // Roll over an aged range of the idle stream, which is otherwise checked on append.
+                    if append_error.is_some() || !stream.aged_range_due() {
+                        continue;
+                    }
+                }
                 _ = shutdown_signal_rx.recv() => {
                     let inflight_count = inflight.len();
                     info!("{}Receive shutdown signal, then quick fail {inflight_count} inflight requests with AlreadyClosed err.", log_ident);
@@ -234,6 +510,10 @@ where
                 &append_request.record_batch,
                 RangeAppendContext::new(*base_offset),
             );
+            stream
+                .last_range_usage
+                .borrow_mut()
+                .record(&append_request.record_batch);
             trace!(
                 "{}Try append record[{base_offset}] to range[{range_index}]",
                 log_ident
@@ -252,8 +532,13 @@ where
             let last_writable_range = match last_range {
                 Some(last_range) => {
                     let range_index = last_range.metadata().index() as u32;
-                    if !last_range.is_writable() {
-                        info!("{}The last range[{range_index}] is not writable, try create a new range.", log_ident);
+                    let rollover = last_range.is_writable() && stream.rollover_due();
+                    if !last_range.is_writable() || rollover {
+                        if rollover {
+                            info!("{}The last range[{range_index}] reaches rollover threshold with {:?}, try roll over to a new range.", log_ident, stream.last_range_usage.borrow());
+                        } else {
+                            info!("{}The last range[{range_index}] is not writable, try create a new range.", log_ident);
+                        }
                         // if last range is not writable, try to seal it and create a new range and retry append in next round.
                         match last_range.seal().await {
                             Ok(end_offset) => {
@@ -306,6 +591,23 @@ where
         }
     }
 
+    /// Whether the last range, which is writable, is due to roll over by age.
+    fn aged_range_due(&self) -> bool {
+        let writable = self
+            .last_range
+            .borrow()
+            .as_ref()
+            .is_some_and(|range| range.is_writable());
+        writable && self.rollover_policy.borrow().max_age.is_some() && self.rollover_due()
+    }
+
+    fn rollover_due(&self) -> bool {
+        let usage = self.last_range_usage.borrow();
+        self.rollover_policy
+            .borrow()
+            .due(usage.bytes, usage.records, usage.created.elapsed())
+    }
+
     fn get_client(&self) -> Result<Rc<C>, EsError> {
         self.client.upgrade().ok_or(EsError::new(
             ErrorCode::UNEXPECTED,
@@ -321,41 +623,33 @@ where
 {
     async fn open(&self) -> Result<(), EsError> {
         info!("{}Opening...", self.log_ident);
+        if self.read_only {
+            // Load ranges without fencing the writer, then follow range changes.
+            let version = self.reload_ranges().await?;
+            self.spawn_watch_task(version);
+            self.spawn_tail_task();
+            let range_count = self.ranges.borrow().len();
+            let start_offset = self.start_offset();
+            let next_offset = self.next_offset();
+            info!("{}Opened read-only with range_count={range_count} start_offset={start_offset} next_offset={next_offset}", self.log_ident);
+            return Ok(());
+        }
         let client = self.get_client()?;
         // 1. fence the stream with new epoch.
-        let _ = client
-            .update_stream(self.id, None, None, Some(self.epoch))
+        let metadata = client
+            .update_stream(self.id, None, None, None, Some(self.epoch))
             .await?;
+        *self.rollover_policy.borrow_mut() = self.rollover.resolve(metadata.rollover.as_ref());
         // 2. load all ranges
-        client
-            .list_ranges(model::ListRangeCriteria::new(None, Some(self.id)))
-            .await
-            .map_err(|e| {
-                error!(
-                    "{}Failed to list ranges from placement-driver: {e}",
-                    self.log_ident
-                );
-                e
-            })?
+        self.list_ranges(&client)
+            .await?
             .into_iter()
             // skip old empty range when two range has the same start offset
             .sorted_by(|a, b| Ord::cmp(&a.index(), &b.index()))
             .for_each(|range| {
-                let this = self.weak_self.borrow().clone();
-                self.ranges.borrow_mut().insert(
-                    range.start(),
-                    R::new(
-                        range,
-                        false,
-                        Box::new(move || {
-                            if let Some(stream) = this.upgrade() {
-                                stream.try_ack();
-                            }
-                        }),
-                        self.client.clone(),
-                        self.cache.clone(),
-                    ),
-                );
+                let start = range.start();
+                let range = self.load_range(range, false);
+                self.ranges.borrow_mut().insert(start, range);
             });
         // 3. seal the last range
         let last_range = self
@@ -399,6 +693,11 @@ where
         info!("{}Closing...", self.log_ident);
         *self.closed.borrow_mut() = true;
         let _ = self.shutdown_signal_tx.send(());
+        if self.read_only {
+            // Ranges are owned by the writer, never seal them.
+            info!("{}Closed...", self.log_ident);
+            return;
+        }
         // TODO: await append task to stop.
         let last_range = self.last_range.borrow().as_ref().cloned();
         if let Some(range) = last_range {
@@ -427,6 +726,9 @@ where
 
     async fn append(&self, record_batch: RecordBatch) -> Result<u64, EsError> {
         let start_timestamp = Instant::now();
+        if self.read_only {
+            return Err(read_only_rejected("append"));
+        }
         if *self.closed.borrow() {
             warn!("{}Keep append to a closed stream.", self.log_ident);
             return Err(EsError::new(
@@ -556,6 +858,9 @@ where
     }
 
     async fn trim(&self, new_start_offset: u64) -> Result<(), EsError> {
+        if self.read_only {
+            return Err(read_only_rejected("trim"));
+        }
         *self.start_offset.borrow_mut() = new_start_offset;
         {
             // Remove deleted ranges from ranges besides the last range.
@@ -579,12 +884,44 @@ where
     }
 
     async fn delete(&self) -> Result<(), EsError> {
+        if self.read_only {
+            return Err(read_only_rejected("delete"));
+        }
         self.ranges.borrow_mut().clear();
         let _ = self.last_range.borrow_mut().take();
         self.get_client()?.delete_stream(self.id, self.epoch).await
     }
 }
 
+#[derive(Debug)]
+struct RangeUsage {
+    bytes: u64,
+    records: u64,
+    created: Instant,
+}
+
+impl RangeUsage {
+    fn new() -> Self {
+        Self {
+            bytes: 0,
+            records: 0,
+            created: Instant::now(),
+        }
+    }
+
+    fn record(&mut self, record_batch: &RecordBatch) {
+        self.bytes += record_batch.payload().len() as u64;
+        self.records += record_batch.last_offset_delta() as u64;
+    }
+}
+
+fn read_only_rejected(operation: &str) -> EsError {
+    EsError::new(
+        ErrorCode::UNSUPPORTED_OPERATION,
+        &format!("{operation} is not allowed on read-only stream"),
+    )
+}
+
 struct StreamAppendRequest {
     base_offset: u64,
     record_batch: RecordBatch,
@@ -631,9 +968,23 @@ impl StreamAppendRequest {
 mod tests {
     use std::error::Error;
 
-    use bytes::BytesMut;
+    use bytes::{Bytes, BytesMut};
     use client::client::MockClient;
-    use model::{range::RangeMetadata, stream::StreamMetadata};
+    use model::{
+        record::{
+            flat_record::{FlatRecordBatch, RecordMagic},
+            magic1::{Header, RecordsBuilder},
+        },
+        response::resource::{ListResourceResult, WatchResourceResult},
+        stream::StreamMetadata,
+    };
+    use std::{
+        cell::Cell,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
 
     use crate::stream::{
         records_block::RecordsBlock,
@@ -650,7 +1001,7 @@ mod tests {
             let mut client = MockClient::new();
             client
                 .expect_update_stream()
-                .returning(|_, _, _, _| Ok(StreamMetadata::default()));
+                .returning(|_, _, _, _, _| Ok(StreamMetadata::default()));
             client.expect_list_ranges().returning(|_| {
                 Ok(vec![
                     RangeMetadata::new(0, 0, 0, 0, Some(100)),
@@ -678,7 +1029,14 @@ mod tests {
                 }
             });
             let stream: Rc<ReplicationStream<MockReplicationRange<MockClient>, MockClient>> =
-                ReplicationStream::new(0, 1, Rc::downgrade(&client), Rc::new(HotCache::new(4096)));
+                ReplicationStream::new(
+                    0,
+                    1,
+                    OpenRollover::Default(RolloverPolicy::default()),
+                    ReplicaLayout::Full,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
             stream.open().await.unwrap();
             assert_eq!(0, stream.start_offset());
             assert_eq!(200, stream.confirm_offset());
@@ -693,13 +1051,20 @@ mod tests {
             let mut client = MockClient::new();
             client
                 .expect_update_stream()
-                .returning(|_, _, _, _| Ok(StreamMetadata::default()));
+                .returning(|_, _, _, _, _| Ok(StreamMetadata::default()));
             client
                 .expect_list_ranges()
                 .returning(|_| Ok(vec![RangeMetadata::new(0, 0, 0, 0, Some(100))]));
             let client = Rc::new(client);
             let stream: Rc<ReplicationStream<MemoryReplicationRange, MockClient>> =
-                ReplicationStream::new(0, 1, Rc::downgrade(&client), Rc::new(HotCache::new(4096)));
+                ReplicationStream::new(
+                    0,
+                    1,
+                    OpenRollover::Default(RolloverPolicy::default()),
+                    ReplicaLayout::Full,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
             stream.open().await.unwrap();
             assert_eq!(1, stream.ranges.borrow().len());
             let offset = stream.append(new_record(1)).await.unwrap();
@@ -753,11 +1118,18 @@ mod tests {
             let mut client = MockClient::new();
             client
                 .expect_update_stream()
-                .returning(|_, _, _, _| Ok(StreamMetadata::default()));
+                .returning(|_, _, _, _, _| Ok(StreamMetadata::default()));
             client.expect_list_ranges().returning(|_| Ok(vec![]));
             let client = Rc::new(client);
             let stream: Rc<ReplicationStream<MemoryReplicationRange, MockClient>> =
-                ReplicationStream::new(0, 1, Rc::downgrade(&client), Rc::new(HotCache::new(4096)));
+                ReplicationStream::new(
+                    0,
+                    1,
+                    OpenRollover::Default(RolloverPolicy::default()),
+                    ReplicaLayout::Full,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
             stream.open().await.unwrap();
             let _ = stream.append(new_record(1)).await.unwrap();
             let _ = stream.append(new_record(1)).await.unwrap();
@@ -814,13 +1186,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_append_fetch_records() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let mut client = MockClient::new();
+            client
+                .expect_update_stream()
+                .returning(|_, _, _, _, _| Ok(StreamMetadata::default()));
+            client.expect_list_ranges().returning(|_| Ok(vec![]));
+            let client = Rc::new(client);
+            let stream: Rc<ReplicationStream<MemoryReplicationRange, MockClient>> =
+                ReplicationStream::new(
+                    0,
+                    1,
+                    OpenRollover::Default(RolloverPolicy::default()),
+                    ReplicaLayout::Full,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
+            stream.open().await.unwrap();
+            let _ = stream.append(new_record(2)).await.unwrap();
+
+            let mut builder = RecordsBuilder::new();
+            builder.append(10, Some(Bytes::from_static(b"k0")), None, &[]);
+            builder.append(
+                12,
+                None,
+                Some(Bytes::from_static(b"v1")),
+                &[Header::new("h".to_owned(), None)],
+            );
+            let base_offset = stream.append(builder.build(0).unwrap()).await.unwrap();
+            assert_eq!(2, base_offset);
+
+            let blocks = get_records_blocks(stream.fetch(2, 4, 1024).await.unwrap());
+            assert_eq!(1, blocks.len());
+            let mut buf = vec_bytes_to_bytes(&blocks[0].records[0].data);
+            let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf).unwrap();
+            assert_eq!(RecordMagic::Magic1, record_batch.magic());
+            let records: Vec<_> = record_batch
+                .records()
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(2, records.len());
+            assert_eq!(2, records[0].offset);
+            assert_eq!(10, records[0].timestamp);
+            assert_eq!(Some(Bytes::from_static(b"k0")), records[0].key);
+            assert_eq!(None, records[0].value);
+            assert_eq!(3, records[1].offset);
+            assert_eq!(12, records[1].timestamp);
+            assert_eq!(Some(Bytes::from_static(b"v1")), records[1].value);
+            assert_eq!(vec![Header::new("h".to_owned(), None)], records[1].headers);
+        });
+        Ok(())
+    }
+
     #[test]
     fn test_trim() -> Result<(), Box<dyn Error>> {
         tokio_uring::start(async move {
             let mut client = MockClient::new();
             client
                 .expect_update_stream()
-                .returning(|_, _, _, _| Ok(StreamMetadata::default()));
+                .returning(|_, _, _, _, _| Ok(StreamMetadata::default()));
             client.expect_list_ranges().returning(|_| {
                 Ok(vec![
                     RangeMetadata::new(0, 0, 0, 0, Some(100)),
@@ -831,7 +1258,14 @@ mod tests {
 
             let client = Rc::new(client);
             let stream: Rc<ReplicationStream<MemoryReplicationRange, MockClient>> =
-                ReplicationStream::new(0, 1, Rc::downgrade(&client), Rc::new(HotCache::new(4096)));
+                ReplicationStream::new(
+                    0,
+                    1,
+                    OpenRollover::Default(RolloverPolicy::default()),
+                    ReplicaLayout::Full,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
             stream.open().await.unwrap();
             assert_eq!(2, stream.ranges.borrow().len());
             assert_eq!(0, stream.start_offset());
@@ -848,7 +1282,7 @@ mod tests {
             let mut client = MockClient::new();
             client
                 .expect_update_stream()
-                .returning(|_, _, _, _| Ok(StreamMetadata::default()));
+                .returning(|_, _, _, _, _| Ok(StreamMetadata::default()));
             client.expect_list_ranges().returning(|_| {
                 Ok(vec![
                     RangeMetadata::new(0, 0, 0, 0, Some(100)),
@@ -859,7 +1293,14 @@ mod tests {
 
             let client = Rc::new(client);
             let stream: Rc<ReplicationStream<MemoryReplicationRange, MockClient>> =
-                ReplicationStream::new(0, 1, Rc::downgrade(&client), Rc::new(HotCache::new(4096)));
+                ReplicationStream::new(
+                    0,
+                    1,
+                    OpenRollover::Default(RolloverPolicy::default()),
+                    ReplicaLayout::Full,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
             stream.open().await.unwrap();
             stream.delete().await.unwrap();
             assert_eq!(0, stream.ranges.borrow().len());
@@ -868,6 +1309,184 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_rollover() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let mut client = MockClient::new();
+            // Rollover policy persisted on creation takes precedence over the configured default.
+            client.expect_update_stream().returning(|_, _, _, _, _| {
+                Ok(StreamMetadata {
+                    rollover: Some(RolloverPolicy {
+                        max_records: Some(2),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+            });
+            client
+                .expect_list_ranges()
+                .returning(|_| Ok(vec![RangeMetadata::new(0, 0, 0, 0, Some(100))]));
+            let client = Rc::new(client);
+            let stream: Rc<ReplicationStream<MemoryReplicationRange, MockClient>> =
+                ReplicationStream::new(
+                    0,
+                    1,
+                    OpenRollover::Default(RolloverPolicy::default()),
+                    ReplicaLayout::Full,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
+            stream.open().await.unwrap();
+            assert_eq!(100, stream.append(new_record(1)).await.unwrap());
+            assert_eq!(2, stream.ranges.borrow().len());
+
+            // Range 1 is healthy but full, so it is sealed and rolled over to range 2.
+            assert_eq!(101, stream.append(new_record(1)).await.unwrap());
+            assert_eq!(3, stream.ranges.borrow().len());
+            let last_range = stream.last_range.borrow().clone().unwrap();
+            assert_eq!(2, last_range.metadata().index());
+            assert_eq!(102, last_range.start_offset());
+
+            assert_eq!(102, stream.append(new_record(1)).await.unwrap());
+            assert_eq!(3, stream.ranges.borrow().len());
+            assert_eq!(103, stream.confirm_offset());
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollover_by_age() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let mut client = MockClient::new();
+            client
+                .expect_update_stream()
+                .returning(|_, _, _, _, _| Ok(StreamMetadata::default()));
+            client
+                .expect_list_ranges()
+                .returning(|_| Ok(vec![RangeMetadata::new(0, 0, 0, 0, Some(100))]));
+            let client = Rc::new(client);
+            let stream: Rc<ReplicationStream<MemoryReplicationRange, MockClient>> =
+                ReplicationStream::new(
+                    0,
+                    1,
+                    OpenRollover::Given(RolloverPolicy {
+                        max_age: Some(Duration::from_millis(100)),
+                        ..Default::default()
+                    }),
+                    ReplicaLayout::Full,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
+            stream.open().await.unwrap();
+            assert_eq!(100, stream.append(new_record(1)).await.unwrap());
+            assert_eq!(2, stream.ranges.borrow().len());
+
+            // Range 1 rolls over once aged, without any further append.
+            wait_until(|| stream.ranges.borrow().len() == 3).await;
+            let last_range = stream.last_range.borrow().clone().unwrap();
+            assert_eq!(2, last_range.metadata().index());
+            assert_eq!(101, last_range.start_offset());
+
+            // The new range is empty, so it never rolls over by age.
+            sleep(Duration::from_millis(1500)).await;
+            assert_eq!(3, stream.ranges.borrow().len());
+            assert_eq!(101, stream.append(new_record(1)).await.unwrap());
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_read_only() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let mut client = MockClient::new();
+            // Read-only stream never fences the writer.
+            client.expect_update_stream().times(0);
+            client.expect_list_resource().returning(|_, _, _| {
+                Ok(ListResourceResult {
+                    resources: vec![],
+                    version: 1,
+                    continuation: None,
+                })
+            });
+            client.expect_list_ranges().returning(|_| {
+                Ok(vec![
+                    RangeMetadata::new(0, 0, 0, 0, Some(100)),
+                    RangeMetadata::new(0, 1, 0, 100, None),
+                ])
+            });
+            let watches = Arc::new(AtomicUsize::new(0));
+            client
+                .expect_watch_resource()
+                .returning(move |_, stream_id, version, _| {
+                    // Each reader only watches ranges of its own stream.
+                    assert_eq!(Some(0), stream_id);
+                    if watches.fetch_add(1, Ordering::Relaxed) > 0 {
+                        return Err(EsError::new(ErrorCode::UNEXPECTED, "watch fail"));
+                    }
+                    assert_eq!(1, version);
+                    let event = |event_type, range| ResourceEvent {
+                        event_type,
+                        resource: Resource::Range(range),
+                    };
+                    Ok(WatchResourceResult {
+                        events: vec![
+                            // Writer seals range 1 and creates range 2.
+                            event(
+                                EventType::Modified,
+                                RangeMetadata::new(0, 1, 1, 100, Some(150)),
+                            ),
+                            event(EventType::Added, RangeMetadata::new(0, 2, 1, 150, None)),
+                            // Ranges of other streams are ignored.
+                            event(EventType::Added, RangeMetadata::new(1, 0, 0, 0, None)),
+                        ],
+                        version: 2,
+                    })
+                });
+            let client = Rc::new(client);
+            let stream: Rc<ReplicationStream<MemoryReplicationRange, MockClient>> =
+                ReplicationStream::new_read_only(
+                    0,
+                    Rc::downgrade(&client),
+                    Rc::new(HotCache::new(4096)),
+                );
+            stream.open().await.unwrap();
+            assert_eq!(2, stream.ranges.borrow().len());
+            assert_eq!(0, stream.start_offset());
+            assert_eq!(100, stream.next_offset());
+
+            wait_until(|| stream.ranges.borrow().len() == 3).await;
+            assert_eq!(150, stream.next_offset());
+            assert_eq!(150, stream.confirm_offset());
+
+            // The writer appends to the active range 2, which the reader follows.
+            REMOTE_CONFIRM_OFFSET.with(|offset| offset.set(180));
+            wait_until(|| stream.next_offset() == 180).await;
+            assert_eq!(180, stream.confirm_offset());
+
+            for code in [
+                stream.append(new_record(1)).await.unwrap_err().code,
+                stream.trim(10).await.unwrap_err().code,
+                stream.delete().await.unwrap_err().code,
+            ] {
+                assert_eq!(ErrorCode::UNSUPPORTED_OPERATION, code);
+            }
+            assert_eq!(3, stream.ranges.borrow().len());
+            stream.close().await;
+        });
+        Ok(())
+    }
+
+    /// Wait until the condition holds, which is set by tasks running in the background.
+    async fn wait_until(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("Condition should hold before timeout");
+    }
+
     fn get_records_blocks(dataset: FetchDataset) -> Vec<RecordsBlock> {
         match dataset {
             FetchDataset::Full(blocks) => blocks,
@@ -899,6 +1518,11 @@ mod tests {
 
     static mut FENCED: bool = false;
 
+    thread_local! {
+        /// Confirm offset of the active range, as persisted by replicas on behalf of another writer.
+        static REMOTE_CONFIRM_OFFSET: Cell<u64> = Cell::new(0);
+    }
+
     impl ReplicationRange<MockClient> for MemoryReplicationRange {
         async fn create(
             _client: Rc<MockClient>,
@@ -906,6 +1530,7 @@ mod tests {
             epoch: u64,
             index: u32,
             start_offset: u64,
+            _layout: ReplicaLayout,
         ) -> Result<RangeMetadata, EsError> {
             if unsafe { FENCED } {
                 Err(EsError::new(
@@ -992,6 +1617,13 @@ mod tests {
             Ok(self.confirm_offset())
         }
 
+        async fn refresh_confirm_offset(&self) -> Result<u64, EsError> {
+            let remote = REMOTE_CONFIRM_OFFSET.with(|offset| offset.get());
+            let mut confirm_offset = self.confirm_offset.borrow_mut();
+            *confirm_offset = (*confirm_offset).max(remote);
+            Ok(*confirm_offset)
+        }
+
         fn is_sealed(&self) -> bool {
             todo!()
         }
@@ -9,19 +9,21 @@ use std::{
 use client::{client::Client, heartbeat::HeartbeatData, DefaultClient};
 use config::Configuration;
 use log::{error, warn};
-use model::error::EsError;
-use protocol::rpc::header::{ClientRole, ErrorCode, StreamT};
+use model::{error::EsError, resource::Resource, ListRangeCriteria};
+use protocol::rpc::header::{ClientRole, ErrorCode, ResourceType, StreamT};
 use tokio::{
     sync::{broadcast, oneshot},
     time::{sleep, Instant},
 };
 
 use crate::{
+    inflight::InflightLimiter,
     request::{
-        AppendRequest, AppendResponse, CloseStreamRequest, CreateStreamRequest,
-        CreateStreamResponse, DeleteRequest, OpenStreamRequest, OpenStreamResponse, ReadRequest,
-        ReadResponse, TrimRequest,
+        AdminRequest, AdminResponse, AppendRequest, AppendResponse, CloseStreamRequest,
+        CreateStreamRequest, CreateStreamResponse, DeleteRequest, KvRequest, KvResponse, OpenMode,
+        OpenStreamRequest, OpenStreamResponse, ReadRequest, ReadResponse, TrimRequest,
     },
+    rollover::{self, OpenRollover, RolloverPolicy},
     stream::replication_stream::ReplicationStream,
 };
 
@@ -31,6 +33,7 @@ use super::{
     metrics::METRICS,
     object_reader::{AsyncObjectReader, DefaultObjectReader},
     object_stream::ObjectStream,
+    read_policy::ReadPolicy,
     replication_range::DefaultReplicationRange,
     replication_replica::DefaultReplicationReplica,
     FetchDataset, Stream,
@@ -50,15 +53,21 @@ type FStream = CacheStream<
 /// `StreamManager` is intended to be used in thread-per-core usage case. It is NOT `Send`.
 pub(crate) struct StreamManager {
     round_robin: usize,
-    streams: Rc<RefCell<HashMap<u64, Rc<FStream>>>>,
+    /// Opened streams keyed by stream id and open mode, so that a read-only handle never shadows
+    /// the writer of the same stream, and vice versa.
+    streams: Rc<RefCell<HashMap<(u64, OpenMode), Rc<FStream>>>>,
     hot_cache: Rc<HotCache>,
     block_cache: Rc<BlockCache>,
     object_reader: Rc<AsyncObjectReader>,
     clients: Vec<Rc<DefaultClient>>,
+    /// Default rollover policy of streams opened without one.
+    rollover: RolloverPolicy,
 }
 
 impl StreamManager {
-    pub(crate) fn new(config: Arc<Configuration>) -> Self {
+    /// Usage of in-flight appends is reported to metrics if `limiter` is given, which is shared by all
+    /// runtime threads.
+    pub(crate) fn new(config: Arc<Configuration>, limiter: Option<Arc<InflightLimiter>>) -> Self {
         let (shutdown, _rx) = broadcast::channel(1);
         let streams = Rc::new(RefCell::new(HashMap::new()));
         let cache = Rc::new(HotCache::new(Self::get_max_cache_size() * 2 / 3));
@@ -72,11 +81,16 @@ impl StreamManager {
         }
 
         let object_reader = Rc::new(AsyncObjectReader::new());
+        let rollover = rollover::configured(&config);
+        ReadPolicy::new(&config).install();
 
         tokio_uring::spawn(async move {
             loop {
                 sleep(Duration::from_secs(60)).await;
                 report_metrics();
+                if let Some(limiter) = &limiter {
+                    limiter.report();
+                }
             }
         });
 
@@ -87,6 +101,7 @@ impl StreamManager {
             object_reader,
             block_cache,
             clients,
+            rollover,
         }
     }
 
@@ -121,7 +136,7 @@ impl StreamManager {
         request: AppendRequest,
         tx: oneshot::Sender<Result<AppendResponse, EsError>>,
     ) {
-        let stream = self.streams.borrow().get(&request.stream_id).map(Rc::clone);
+        let stream = self.writable_stream(request.stream_id);
         if let Some(stream) = stream {
             tokio_uring::spawn(async move {
                 let start = Instant::now();
@@ -142,7 +157,11 @@ impl StreamManager {
         request: ReadRequest,
         tx: oneshot::Sender<Result<ReadResponse, EsError>>,
     ) {
-        let stream = self.streams.borrow().get(&request.stream_id).map(Rc::clone);
+        let stream = self
+            .streams
+            .borrow()
+            .get(&(request.stream_id, request.mode))
+            .map(Rc::clone);
         if let Some(stream) = stream {
             tokio_uring::spawn(async move {
                 let start = Instant::now();
@@ -191,6 +210,13 @@ impl StreamManager {
         stream.replica = request.replica as i8;
         stream.ack_count = request.ack_count as i8;
         stream.retention_period_ms = request.retention_period.as_millis() as i64;
+        if let Some(delete_retention) = request.compaction {
+            stream.compacted = true;
+            stream.delete_retention_ms = delete_retention.as_millis() as i64;
+        }
+        if let Some(rollover) = &request.rollover {
+            rollover.encode(&mut stream);
+        }
         stream.start_offset = 0;
         stream.epoch = 0;
 
@@ -229,11 +255,15 @@ impl StreamManager {
         let hot_cache = self.hot_cache.clone();
         let block_cache = self.block_cache.clone();
         let object_reader = self.object_reader.clone();
+        let rollover = match &request.rollover {
+            Some(rollover) => OpenRollover::Given(rollover.clone()),
+            None => OpenRollover::Default(self.rollover.clone()),
+        };
         tokio_uring::spawn(async move {
             let client = Rc::downgrade(&client);
             let stream = Self::new_stream(
-                request.stream_id,
-                request.epoch,
+                &request,
+                rollover,
                 client,
                 hot_cache,
                 block_cache,
@@ -245,7 +275,9 @@ impl StreamManager {
                 let _ = tx.send(Err(e));
                 return;
             }
-            streams.borrow_mut().insert(request.stream_id, stream);
+            streams
+                .borrow_mut()
+                .insert((request.stream_id, request.mode), stream);
             let _ = tx.send(Ok(OpenStreamResponse {}));
         });
     }
@@ -254,7 +286,7 @@ impl StreamManager {
         let stream = self
             .streams
             .borrow_mut()
-            .remove(&request.stream_id)
+            .remove(&(request.stream_id, request.mode))
             .map(|stream| Rc::clone(&stream));
         if let Some(stream) = stream {
             tokio_uring::spawn(async move {
@@ -266,8 +298,13 @@ impl StreamManager {
         }
     }
 
-    pub fn start_offset(&mut self, stream_id: u64, tx: oneshot::Sender<Result<u64, EsError>>) {
-        let result = if let Some(stream) = self.streams.borrow().get(&stream_id) {
+    pub fn start_offset(
+        &mut self,
+        stream_id: u64,
+        mode: OpenMode,
+        tx: oneshot::Sender<Result<u64, EsError>>,
+    ) {
+        let result = if let Some(stream) = self.streams.borrow().get(&(stream_id, mode)) {
             Ok(stream.start_offset())
         } else {
             Err(stream_not_exist(stream_id))
@@ -275,8 +312,13 @@ impl StreamManager {
         let _ = tx.send(result);
     }
 
-    pub fn next_offset(&mut self, stream_id: u64, tx: oneshot::Sender<Result<u64, EsError>>) {
-        let result = if let Some(stream) = self.streams.borrow().get(&stream_id) {
+    pub fn next_offset(
+        &mut self,
+        stream_id: u64,
+        mode: OpenMode,
+        tx: oneshot::Sender<Result<u64, EsError>>,
+    ) {
+        let result = if let Some(stream) = self.streams.borrow().get(&(stream_id, mode)) {
             Ok(stream.next_offset())
         } else {
             Err(stream_not_exist(stream_id))
@@ -285,7 +327,7 @@ impl StreamManager {
     }
 
     pub fn trim(&mut self, request: TrimRequest, tx: oneshot::Sender<Result<(), EsError>>) {
-        let stream = self.streams.borrow().get(&request.stream_id).map(Rc::clone);
+        let stream = self.writable_stream(request.stream_id);
         if let Some(stream) = stream {
             tokio_uring::spawn(async move {
                 let _ = tx.send(stream.trim(request.new_start_offset).await);
@@ -296,7 +338,7 @@ impl StreamManager {
     }
 
     pub fn delete(&mut self, request: DeleteRequest, tx: oneshot::Sender<Result<(), EsError>>) {
-        let stream = self.streams.borrow().get(&request.stream_id).map(Rc::clone);
+        let stream = self.writable_stream(request.stream_id);
         if let Some(stream) = stream {
             tokio_uring::spawn(async move {
                 let _ = tx.send(stream.delete().await);
@@ -306,15 +348,88 @@ impl StreamManager {
         }
     }
 
+    pub fn kv(&mut self, request: KvRequest, tx: oneshot::Sender<Result<KvResponse, EsError>>) {
+        let client = match self.route_client() {
+            Ok(client) => client,
+            Err(e) => {
+                let _ = tx.send(Err(e));
+                return;
+            }
+        };
+        tokio_uring::spawn(async move {
+            let result = match request {
+                KvRequest::Range { key, range_end } => client
+                    .kv_range(key, range_end, 0)
+                    .await
+                    .map(|kvs| KvResponse::Range { kvs }),
+                KvRequest::Put { key, value } => {
+                    client.kv_put(key, value).await.map(|_| KvResponse::Put)
+                }
+                KvRequest::CompareAndPut {
+                    key,
+                    expected,
+                    value,
+                } => client
+                    .kv_compare_and_put(key, expected, value)
+                    .await
+                    .map(|_| KvResponse::Put),
+                KvRequest::DeleteRange { key, range_end } => client
+                    .kv_delete_range(key, range_end)
+                    .await
+                    .map(|deleted| KvResponse::DeleteRange { deleted }),
+            };
+            let _ = tx.send(result);
+        });
+    }
+
+    pub fn admin(
+        &mut self,
+        request: AdminRequest,
+        tx: oneshot::Sender<Result<AdminResponse, EsError>>,
+    ) {
+        let client = match self.route_client() {
+            Ok(client) => client,
+            Err(e) => {
+                let _ = tx.send(Err(e));
+                return;
+            }
+        };
+        tokio_uring::spawn(async move {
+            let _ = tx.send(admin0(client.as_ref(), request).await);
+        });
+    }
+
+    /// Get the stream to serve a write request. Falls back to the read-only handle, if any, so that
+    /// the request is rejected as an unsupported operation rather than a missing stream.
+    fn writable_stream(&self, stream_id: u64) -> Option<Rc<FStream>> {
+        let streams = self.streams.borrow();
+        streams
+            .get(&(stream_id, OpenMode::ReadWrite))
+            .or_else(|| streams.get(&(stream_id, OpenMode::ReadOnly)))
+            .map(Rc::clone)
+    }
+
     fn new_stream(
-        stream_id: u64,
-        epoch: u64,
+        request: &OpenStreamRequest,
+        rollover: OpenRollover,
         client: Weak<DefaultClient>,
         hot_cache: Rc<HotCache>,
         block_cache: Rc<BlockCache>,
         object_reader: Rc<AsyncObjectReader>,
     ) -> Rc<FStream> {
-        let stream = ReplicationStream::new(stream_id, epoch, client, hot_cache.clone());
+        let stream_id = request.stream_id;
+        let stream = if request.mode == OpenMode::ReadOnly {
+            ReplicationStream::new_read_only(stream_id, client, hot_cache.clone())
+        } else {
+            ReplicationStream::new(
+                stream_id,
+                request.epoch,
+                rollover,
+                request.layout,
+                client,
+                hot_cache.clone(),
+            )
+        };
 
         let object_reader = DefaultObjectReader::new(object_reader);
         let stream = ObjectStream::new(stream, object_reader);
@@ -343,6 +458,187 @@ fn stream_not_exist(stream_id: u64) -> EsError {
     )
 }
 
+/// Serve the admin request through placement driver.
+async fn admin0<C: Client>(client: &C, request: AdminRequest) -> Result<AdminResponse, EsError> {
+    match request {
+        AdminRequest::DescribeStream { stream_id } => client
+            .describe_stream(stream_id)
+            .await
+            .map(|stream| AdminResponse::Stream { stream }),
+        AdminRequest::ListRanges { stream_id } => client
+            .list_ranges(ListRangeCriteria::new(None, Some(stream_id)))
+            .await
+            .map(|ranges| AdminResponse::Ranges { ranges }),
+        AdminRequest::UpdateStream {
+            stream_id,
+            replica,
+            ack_count,
+            retention_period,
+        } => client
+            .update_stream(stream_id, replica, ack_count, retention_period, None)
+            .await
+            .map(|stream| AdminResponse::Stream { stream }),
+        AdminRequest::ListStreams {
+            limit,
+            continuation,
+        } => client
+            .list_resource(&[ResourceType::RESOURCE_STREAM], limit, &continuation)
+            .await
+            .map(|result| AdminResponse::Streams {
+                streams: result
+                    .resources
+                    .into_iter()
+                    .filter_map(|resource| match resource {
+                        Resource::Stream(stream) if !stream.deleted => Some(stream),
+                        _ => None,
+                    })
+                    .collect(),
+                continuation: result.continuation,
+            }),
+    }
+}
+
 fn report_metrics() {
     METRICS.with(|m| m.report());
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, time::Duration};
+
+    use bytes::Bytes;
+    use client::client::MockClient;
+    use model::{
+        range::RangeMetadata, resource::Resource, response::resource::ListResourceResult,
+        stream::StreamMetadata,
+    };
+    use protocol::rpc::header::ResourceType;
+
+    use super::admin0;
+    use crate::request::{AdminRequest, AdminResponse};
+
+    fn stream(stream_id: u64, deleted: bool) -> Resource {
+        Resource::Stream(StreamMetadata {
+            stream_id,
+            deleted,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_admin() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let mut client = MockClient::new();
+            client.expect_describe_stream().returning(|stream_id| {
+                Ok(StreamMetadata {
+                    stream_id,
+                    replica: 3,
+                    ..Default::default()
+                })
+            });
+            client.expect_list_ranges().returning(|criteria| {
+                assert_eq!(Some(1), criteria.stream_id);
+                assert_eq!(None, criteria.server_id);
+                Ok(vec![RangeMetadata::new(1, 0, 0, 0, Some(100))])
+            });
+            client.expect_update_stream().returning(
+                |stream_id, replica, ack_count, retention_period, epoch| {
+                    // Updates through admin never fence writers of the stream.
+                    assert_eq!(None, epoch);
+                    Ok(StreamMetadata {
+                        stream_id,
+                        replica: replica.unwrap_or(3),
+                        ack_count: ack_count.unwrap_or(2),
+                        retention_period: retention_period.unwrap_or_default(),
+                        ..Default::default()
+                    })
+                },
+            );
+
+            match admin0(&client, AdminRequest::DescribeStream { stream_id: 1 }).await? {
+                AdminResponse::Stream { stream } => {
+                    assert_eq!(1, stream.stream_id);
+                    assert_eq!(3, stream.replica);
+                }
+                response => panic!("Unexpected response {response:?}"),
+            }
+
+            match admin0(&client, AdminRequest::ListRanges { stream_id: 1 }).await? {
+                AdminResponse::Ranges { ranges } => {
+                    assert_eq!(1, ranges.len());
+                    assert_eq!(Some(100), ranges[0].end());
+                }
+                response => panic!("Unexpected response {response:?}"),
+            }
+
+            let request = AdminRequest::UpdateStream {
+                stream_id: 1,
+                replica: None,
+                ack_count: Some(1),
+                retention_period: Some(Duration::from_secs(60)),
+            };
+            match admin0(&client, request).await? {
+                AdminResponse::Stream { stream } => {
+                    assert_eq!(3, stream.replica);
+                    assert_eq!(1, stream.ack_count);
+                    assert_eq!(Duration::from_secs(60), stream.retention_period);
+                }
+                response => panic!("Unexpected response {response:?}"),
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_admin_list_streams() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async move {
+            let mut client = MockClient::new();
+            client
+                .expect_list_resource()
+                .returning(|types, limit, continuation| {
+                    assert_eq!(&[ResourceType::RESOURCE_STREAM], types);
+                    assert_eq!(2, limit);
+                    // Page through streams 1, 2 and 3, of which stream 2 is deleted.
+                    Ok(match continuation.as_deref() {
+                        None => ListResourceResult {
+                            resources: vec![stream(1, false), stream(2, true)],
+                            version: 1,
+                            continuation: Some(Bytes::from_static(b"3")),
+                        },
+                        Some(b"3") => ListResourceResult {
+                            resources: vec![stream(3, false)],
+                            version: 1,
+                            continuation: None,
+                        },
+                        Some(other) => panic!("Unexpected continuation {other:?}"),
+                    })
+                });
+
+            let mut streams = vec![];
+            let mut continuation = None;
+            let mut pages = 0;
+            loop {
+                let request = AdminRequest::ListStreams {
+                    limit: 2,
+                    continuation,
+                };
+                let AdminResponse::Streams {
+                    streams: page,
+                    continuation: next,
+                } = admin0(&client, request).await?
+                else {
+                    panic!("Streams are expected");
+                };
+                pages += 1;
+                streams.extend(page.into_iter().map(|stream| stream.stream_id));
+                match next {
+                    Some(next) => continuation = Some(next),
+                    None => break,
+                }
+            }
+            assert_eq!(2, pages);
+            assert_eq!(vec![1, 3], streams);
+            Ok(())
+        })
+    }
+}
@@ -1,35 +1,82 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use model::error::EsError;
+use bytes::Bytes;
+use model::{
+    error::EsError,
+    range::{RangeMetadata, ReplicaLayout},
+    stream::StreamMetadata,
+};
 use tokio::sync::{mpsc, oneshot};
 
 use crate::{
+    inflight::{InflightLimiter, InflightUsage},
     request::{
-        AppendRequest, AppendResponse, CloseStreamRequest, CreateStreamRequest, DeleteRequest,
-        OpenStreamRequest, ReadRequest, ReadResponse, Request, TrimRequest,
+        AdminRequest, AdminResponse, AppendRequest, AppendResponse, CloseStreamRequest,
+        CreateStreamRequest, DeleteRequest, KvRequest, KvResponse, OpenMode, OpenStreamRequest,
+        ReadRequest, ReadResponse, Request, TrimRequest,
     },
     stream::stream_manager::StreamManager,
 };
 
 /// `StreamClient` is designed to be `Send`
+///
+/// It runs `replication.thread-count` runtime threads, each of which has its own `StreamManager`,
+/// connections and caches. Streams are sharded across runtime threads by stream id, such that all
+/// requests of a stream are served by the same thread.
 #[derive(Debug, Clone)]
 pub struct StreamClient {
-    tx: mpsc::UnboundedSender<Request>,
+    shards: Arc<Vec<mpsc::UnboundedSender<Request>>>,
+    round_robin: Arc<AtomicUsize>,
+    limiter: Arc<InflightLimiter>,
 }
 
 impl StreamClient {
     pub fn new(config: Arc<config::Configuration>, id: usize) -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
-        let _ = std::thread::Builder::new()
-            .name(format!("Runtime-{}", id))
-            .spawn(move || {
-                tokio_uring::builder().entries(32768).start(async move {
-                    let stream_manager = StreamManager::new(config);
-                    Self::spawn_loop(stream_manager, rx).await;
-                })
-            });
+        let limiter = Arc::new(InflightLimiter::new(&config));
+        Self::with_limiter(config, id, limiter)
+    }
+
+    /// Create a stream client whose in-flight appends are bounded by the given limiter, which may be
+    /// shared with other stream clients to enforce global limits.
+    pub fn with_limiter(
+        config: Arc<config::Configuration>,
+        id: usize,
+        limiter: Arc<InflightLimiter>,
+    ) -> Self {
+        let thread_count = config.replication.thread_count.max(1);
+        let mut shards = Vec::with_capacity(thread_count);
+        for shard in 0..thread_count {
+            let (tx, rx) = mpsc::unbounded_channel();
+            let config = Arc::clone(&config);
+            // Limits are shared by all runtime threads, so report them in the first one only.
+            let manager_limiter = (shard == 0).then(|| Arc::clone(&limiter));
+            let _ = std::thread::Builder::new()
+                .name(format!("Runtime-{}-{}", id, shard))
+                .spawn(move || {
+                    tokio_uring::builder().entries(32768).start(async move {
+                        let stream_manager = StreamManager::new(config, manager_limiter);
+                        Self::spawn_loop(stream_manager, rx).await;
+                    })
+                });
+            shards.push(tx);
+        }
 
-        Self { tx }
+        Self {
+            shards: Arc::new(shards),
+            round_robin: Arc::new(AtomicUsize::new(0)),
+            limiter,
+        }
+    }
+
+    /// Runtime thread serving the given stream.
+    fn shard(&self, stream_id: u64) -> &mpsc::UnboundedSender<Request> {
+        &self.shards[shard_index(stream_id, self.shards.len())]
     }
 
     async fn spawn_loop(
@@ -53,11 +100,11 @@ impl StreamClient {
                 Request::CloseStream { request, tx } => {
                     stream_manager.close(request, tx);
                 }
-                Request::StartOffset { request, tx } => {
-                    stream_manager.start_offset(request, tx);
+                Request::StartOffset { request, mode, tx } => {
+                    stream_manager.start_offset(request, mode, tx);
                 }
-                Request::NextOffset { request, tx } => {
-                    stream_manager.next_offset(request, tx);
+                Request::NextOffset { request, mode, tx } => {
+                    stream_manager.next_offset(request, mode, tx);
                 }
                 Request::Trim { request, tx } => {
                     stream_manager.trim(request, tx);
@@ -65,6 +112,12 @@ impl StreamClient {
                 Request::Delete { request, tx } => {
                     stream_manager.delete(request, tx);
                 }
+                Request::Kv { request, tx } => {
+                    stream_manager.kv(request, tx);
+                }
+                Request::Admin { request, tx } => {
+                    stream_manager.admin(request, tx);
+                }
             }
         }
     }
@@ -75,15 +128,43 @@ impl StreamClient {
         ack_count: u8,
         retention_period: Duration,
     ) -> Result<u64, EsError> {
-        let request = CreateStreamRequest {
+        self.create(CreateStreamRequest {
             replica,
             ack_count,
             retention_period,
-        };
+            compaction: None,
+            rollover: None,
+        })
+        .await
+    }
+
+    /// Create a compacted stream, which keeps only the latest record of each key. Tombstones are
+    /// removed by compaction after `delete_retention`.
+    pub async fn create_compacted_stream(
+        &self,
+        replica: u8,
+        ack_count: u8,
+        retention_period: Duration,
+        delete_retention: Duration,
+    ) -> Result<u64, EsError> {
+        self.create(CreateStreamRequest {
+            replica,
+            ack_count,
+            retention_period,
+            compaction: Some(delete_retention),
+            rollover: None,
+        })
+        .await
+    }
 
+    /// Create a stream with all options of `CreateStreamRequest`.
+    pub async fn create(&self, request: CreateStreamRequest) -> Result<u64, EsError> {
         let (tx, rx) = oneshot::channel();
         let req = Request::CreateStream { request, tx };
-        self.tx.send(req).expect("create stream send request to tx");
+        // Streams to create are not sharded yet, so spread them across runtime threads.
+        self.any_shard()
+            .send(req)
+            .expect("create stream send request to tx");
         rx.await
             .unwrap_or_else(|_| {
                 Err(EsError::unexpected(
@@ -94,35 +175,74 @@ impl StreamClient {
     }
 
     pub async fn open_stream(&self, stream_id: u64, epoch: u64) -> Result<(), EsError> {
-        let request = OpenStreamRequest { stream_id, epoch };
+        self.open_stream_with(OpenStreamRequest {
+            stream_id,
+            epoch,
+            mode: OpenMode::ReadWrite,
+            rollover: None,
+            layout: ReplicaLayout::Full,
+        })
+        .await
+    }
+
+    /// Open the stream as requested, e.g. for read only, or for write with a rollover policy or
+    /// replica layout of its own.
+    ///
+    /// Unlike writers, read-only streams do not bump the stream epoch, so the active writer is not
+    /// fenced. Their range metadata is kept up to date by watching placement driver, and append, trim
+    /// and delete are rejected.
+    pub async fn open_stream_with(&self, request: OpenStreamRequest) -> Result<(), EsError> {
         let (tx, rx) = oneshot::channel();
+        let stream_id = request.stream_id;
+        let mode = request.mode;
         let req = Request::OpenStream { request, tx };
-        self.tx.send(req).expect("open stream send request to tx");
+        self.shard(stream_id)
+            .send(req)
+            .expect("open stream send request to tx");
         rx.await
             .unwrap_or_else(|_| {
                 Err(EsError::unexpected(
                     "open stream fail to receive response from rx",
                 ))
             })
-            .map(|_| ())
+            .map(|_| {
+                if mode == OpenMode::ReadWrite {
+                    self.limiter.open(stream_id);
+                }
+            })
     }
 
-    pub async fn close_stream(&self, stream_id: u64) -> Result<(), EsError> {
-        let request = CloseStreamRequest { stream_id };
+    pub async fn close_stream(&self, stream_id: u64, mode: OpenMode) -> Result<(), EsError> {
+        let request = CloseStreamRequest { stream_id, mode };
         let (tx, rx) = oneshot::channel();
         let req = Request::CloseStream { request, tx };
-        self.tx.send(req).expect("close stream send request to tx");
-        rx.await.unwrap_or_else(|_| {
+        self.shard(stream_id)
+            .send(req)
+            .expect("close stream send request to tx");
+        let result = rx.await.unwrap_or_else(|_| {
             Err(EsError::unexpected(
                 "close stream fail to receive response from rx",
             ))
-        })
+        });
+        // In-flight appends of the stream are completed or failed once it is closed.
+        if mode == OpenMode::ReadWrite {
+            self.limiter.close(stream_id);
+        }
+        result
     }
 
     pub async fn append(&self, request: AppendRequest) -> Result<AppendResponse, EsError> {
+        // Permits are held till the append completes.
+        let _permit = self
+            .limiter
+            .acquire(request.stream_id, request.record_batch.payload().len())
+            .await?;
+        let stream_id = request.stream_id;
         let (tx, rx) = oneshot::channel();
         let req = Request::Append { tx, request };
-        self.tx.send(req).expect("append send request to tx");
+        self.shard(stream_id)
+            .send(req)
+            .expect("append send request to tx");
         rx.await.unwrap_or_else(|_| {
             Err(EsError::unexpected(
                 "append receive fail to receive response from rx",
@@ -130,10 +250,18 @@ impl StreamClient {
         })
     }
 
+    /// Usage of in-flight appends of all streams sharing the limiter.
+    pub fn inflight_usage(&self) -> InflightUsage {
+        self.limiter.usage()
+    }
+
     pub async fn read(&self, request: ReadRequest) -> Result<ReadResponse, EsError> {
         let (tx, rx) = oneshot::channel();
+        let stream_id = request.stream_id;
         let req = Request::Read { tx, request };
-        self.tx.send(req).expect("read send request to tx");
+        self.shard(stream_id)
+            .send(req)
+            .expect("read send request to tx");
         rx.await.unwrap_or_else(|_| {
             Err(EsError::unexpected(
                 "read receive fail to receive response from rx",
@@ -141,13 +269,16 @@ impl StreamClient {
         })
     }
 
-    pub async fn start_offset(&self, stream_id: u64) -> Result<u64, EsError> {
+    pub async fn start_offset(&self, stream_id: u64, mode: OpenMode) -> Result<u64, EsError> {
         let (tx, rx) = oneshot::channel();
         let req = Request::StartOffset {
             request: stream_id,
+            mode,
             tx,
         };
-        self.tx.send(req).expect("start offset send request to tx");
+        self.shard(stream_id)
+            .send(req)
+            .expect("start offset send request to tx");
         rx.await.unwrap_or_else(|_| {
             Err(EsError::unexpected(
                 "start offset fail to receive response from rx",
@@ -155,13 +286,16 @@ impl StreamClient {
         })
     }
 
-    pub async fn next_offset(&self, stream_id: u64) -> Result<u64, EsError> {
+    pub async fn next_offset(&self, stream_id: u64, mode: OpenMode) -> Result<u64, EsError> {
         let (tx, rx) = oneshot::channel();
         let req = Request::NextOffset {
             request: stream_id,
+            mode,
             tx,
         };
-        self.tx.send(req).expect("next offset send request to tx");
+        self.shard(stream_id)
+            .send(req)
+            .expect("next offset send request to tx");
         rx.await.unwrap_or_else(|_| {
             Err(EsError::unexpected(
                 "next offset fail to receive response from rx",
@@ -171,20 +305,308 @@ impl StreamClient {
 
     pub async fn trim(&self, request: TrimRequest) -> Result<(), EsError> {
         let (tx, rx) = oneshot::channel();
+        let stream_id = request.stream_id;
         let req = Request::Trim { request, tx };
-        self.tx.send(req).expect("trim send request to tx");
+        self.shard(stream_id)
+            .send(req)
+            .expect("trim send request to tx");
         rx.await
             .unwrap_or_else(|_| Err(EsError::unexpected("trim fail to receive response from rx")))
     }
 
     pub async fn delete(&self, request: DeleteRequest) -> Result<(), EsError> {
         let (tx, rx) = oneshot::channel();
+        let stream_id = request.stream_id;
         let req = Request::Delete { request, tx };
-        self.tx.send(req).expect("delete send request to tx");
+        self.shard(stream_id)
+            .send(req)
+            .expect("delete send request to tx");
         rx.await.unwrap_or_else(|_| {
             Err(EsError::unexpected(
                 "delete fail to receive response from rx",
             ))
         })
     }
+
+    /// Get the key-value pair of `key` from the key-value store of placement driver, or those in
+    /// `[key, range_end)` if `range_end` is given.
+    pub async fn kv_range(
+        &self,
+        key: Bytes,
+        range_end: Option<Bytes>,
+    ) -> Result<Vec<(Bytes, Bytes)>, EsError> {
+        match self.kv(KvRequest::Range { key, range_end }).await? {
+            KvResponse::Range { kvs } => Ok(kvs),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Put a key-value pair into the key-value store of placement driver.
+    pub async fn kv_put(&self, key: Bytes, value: Bytes) -> Result<(), EsError> {
+        self.kv(KvRequest::Put { key, value }).await.map(|_| ())
+    }
+
+    /// Put a key-value pair into the key-value store of placement driver, if `key` holds `expected`,
+    /// or does not exist if `expected` is `None`. Fails with `PRECONDITION_FAILED` otherwise.
+    pub async fn kv_compare_and_put(
+        &self,
+        key: Bytes,
+        expected: Option<Bytes>,
+        value: Bytes,
+    ) -> Result<(), EsError> {
+        self.kv(KvRequest::CompareAndPut {
+            key,
+            expected,
+            value,
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Delete `key`, or keys in `[key, range_end)` if `range_end` is given, from the key-value
+    /// store of placement driver.
+    pub async fn kv_delete_range(
+        &self,
+        key: Bytes,
+        range_end: Option<Bytes>,
+    ) -> Result<u64, EsError> {
+        match self.kv(KvRequest::DeleteRange { key, range_end }).await? {
+            KvResponse::DeleteRange { deleted } => Ok(deleted),
+            _ => unreachable!(),
+        }
+    }
+
+    async fn kv(&self, request: KvRequest) -> Result<KvResponse, EsError> {
+        let (tx, rx) = oneshot::channel();
+        let req = Request::Kv { request, tx };
+        self.any_shard().send(req).expect("kv send request to tx");
+        rx.await
+            .unwrap_or_else(|_| Err(EsError::unexpected("kv fail to receive response from rx")))
+    }
+
+    /// Describe metadata of the stream from placement driver.
+    pub async fn describe_stream(&self, stream_id: u64) -> Result<StreamMetadata, EsError> {
+        match self
+            .admin(AdminRequest::DescribeStream { stream_id })
+            .await?
+        {
+            AdminResponse::Stream { stream } => Ok(stream),
+            _ => unreachable!(),
+        }
+    }
+
+    /// List ranges of the stream from placement driver, along with range servers of their replicas.
+    pub async fn list_ranges(&self, stream_id: u64) -> Result<Vec<RangeMetadata>, EsError> {
+        match self.admin(AdminRequest::ListRanges { stream_id }).await? {
+            AdminResponse::Ranges { ranges } => Ok(ranges),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Update replica count, ack count or retention period of the stream. Fields of `None` are left
+    /// unchanged.
+    ///
+    /// Replica count and ack count apply to ranges created afterwards.
+    pub async fn update_stream(
+        &self,
+        stream_id: u64,
+        replica: Option<u8>,
+        ack_count: Option<u8>,
+        retention_period: Option<Duration>,
+    ) -> Result<StreamMetadata, EsError> {
+        let request = AdminRequest::UpdateStream {
+            stream_id,
+            replica,
+            ack_count,
+            retention_period,
+        };
+        match self.admin(request).await? {
+            AdminResponse::Stream { stream } => Ok(stream),
+            _ => unreachable!(),
+        }
+    }
+
+    /// List at most `limit` streams, continuing from a previous list if `continuation` is given.
+    ///
+    /// # Returns
+    /// Listed streams, and the continuation of the next list if there are more streams.
+    pub async fn list_streams(
+        &self,
+        limit: i32,
+        continuation: Option<Bytes>,
+    ) -> Result<(Vec<StreamMetadata>, Option<Bytes>), EsError> {
+        match self
+            .admin(AdminRequest::ListStreams {
+                limit,
+                continuation,
+            })
+            .await?
+        {
+            AdminResponse::Streams {
+                streams,
+                continuation,
+            } => Ok((streams, continuation)),
+            _ => unreachable!(),
+        }
+    }
+
+    async fn admin(&self, request: AdminRequest) -> Result<AdminResponse, EsError> {
+        let (tx, rx) = oneshot::channel();
+        let req = Request::Admin { request, tx };
+        self.any_shard()
+            .send(req)
+            .expect("admin send request to tx");
+        rx.await.unwrap_or_else(|_| {
+            Err(EsError::unexpected(
+                "admin fail to receive response from rx",
+            ))
+        })
+    }
+
+    /// Runtime thread for requests not bound to any stream.
+    fn any_shard(&self) -> &mpsc::UnboundedSender<Request> {
+        let index = self.round_robin.fetch_add(1, Ordering::Relaxed) % self.shards.len();
+        &self.shards[index]
+    }
+}
+
+fn shard_index(stream_id: u64, shards: usize) -> usize {
+    (stream_id % shards as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        collections::HashSet,
+        error::Error,
+        rc::Rc,
+        sync::{atomic::AtomicUsize, Arc},
+    };
+
+    use model::range::ReplicaLayout;
+    use tokio::sync::mpsc;
+
+    use super::{shard_index, StreamClient};
+    use crate::{
+        inflight::InflightLimiter,
+        request::{
+            OpenMode, OpenStreamRequest, OpenStreamResponse, ReadRequest, ReadResponse, Request,
+            TrimRequest,
+        },
+    };
+
+    /// Stream client of `n` shards, each of which is served by a task recording the shard and the
+    /// stream id of requests it receives, in place of a runtime thread.
+    fn sharded_client(n: usize) -> (StreamClient, Rc<RefCell<Vec<(usize, u64)>>>) {
+        let served = Rc::new(RefCell::new(vec![]));
+        let mut shards = Vec::with_capacity(n);
+        for shard in 0..n {
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            let served = Rc::clone(&served);
+            tokio_uring::spawn(async move {
+                while let Some(request) = rx.recv().await {
+                    let stream_id = match request {
+                        Request::OpenStream { request, tx } => {
+                            let _ = tx.send(Ok(OpenStreamResponse {}));
+                            request.stream_id
+                        }
+                        Request::CloseStream { request, tx } => {
+                            let _ = tx.send(Ok(()));
+                            request.stream_id
+                        }
+                        Request::StartOffset { request, tx, .. }
+                        | Request::NextOffset { request, tx, .. } => {
+                            let _ = tx.send(Ok(0));
+                            request
+                        }
+                        Request::Read { request, tx } => {
+                            let _ = tx.send(Ok(ReadResponse { data: vec![] }));
+                            request.stream_id
+                        }
+                        Request::Trim { request, tx } => {
+                            let _ = tx.send(Ok(()));
+                            request.stream_id
+                        }
+                        _ => unreachable!("Unexpected request"),
+                    };
+                    served.borrow_mut().push((shard, stream_id));
+                }
+            });
+            shards.push(tx);
+        }
+        let config = config::Configuration::default();
+        let client = StreamClient {
+            shards: Arc::new(shards),
+            round_robin: Arc::new(AtomicUsize::new(0)),
+            limiter: Arc::new(InflightLimiter::new(&config)),
+        };
+        (client, served)
+    }
+
+    /// Requests of a stream, whether opened for write or read only, are served by the same shard.
+    #[test]
+    fn test_shard_streams() -> Result<(), Box<dyn Error>> {
+        tokio_uring::start(async {
+            let (client, served) = sharded_client(4);
+            for stream_id in 0..8 {
+                client.open_stream(stream_id, 1).await?;
+                client.next_offset(stream_id, OpenMode::ReadWrite).await?;
+                client
+                    .read(ReadRequest {
+                        stream_id,
+                        mode: OpenMode::ReadWrite,
+                        start_offset: 0,
+                        end_offset: 1,
+                        batch_max_bytes: 1024,
+                    })
+                    .await?;
+                client
+                    .trim(TrimRequest {
+                        stream_id,
+                        new_start_offset: 0,
+                    })
+                    .await?;
+                client.close_stream(stream_id, OpenMode::ReadWrite).await?;
+
+                client
+                    .open_stream_with(OpenStreamRequest {
+                        stream_id,
+                        epoch: 0,
+                        mode: OpenMode::ReadOnly,
+                        rollover: None,
+                        layout: ReplicaLayout::Full,
+                    })
+                    .await?;
+                client.start_offset(stream_id, OpenMode::ReadOnly).await?;
+                client.close_stream(stream_id, OpenMode::ReadOnly).await?;
+            }
+
+            let served = served.borrow();
+            assert_eq!(8 * 8, served.len());
+            for stream_id in 0..8 {
+                let shards = served
+                    .iter()
+                    .filter(|(_, id)| *id == stream_id)
+                    .map(|(shard, _)| *shard)
+                    .collect::<HashSet<_>>();
+                assert_eq!(HashSet::from([shard_index(stream_id, 4)]), shards);
+            }
+            // Streams are spread over all shards.
+            let shards = served
+                .iter()
+                .map(|(shard, _)| *shard)
+                .collect::<HashSet<_>>();
+            assert_eq!(4, shards.len());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_shard_index() {
+        assert_eq!(0, shard_index(0, 1));
+        assert_eq!(0, shard_index(42, 1));
+        assert_eq!(2, shard_index(6, 4));
+        assert_eq!(3, shard_index(u64::MAX, 4));
+    }
 }
@@ -0,0 +1,143 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// Scheme prefix of Unix domain socket addresses, for example, `unix:///var/run/range-server.sock`.
+pub const UNIX_SCHEME: &str = "unix://";
+
+/// Address of a network peer, either a TCP socket address or the path of a Unix domain socket.
+///
+/// Textual representation of the former is `host:port` while the latter is `unix://path`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Address {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl Address {
+    /// Resolve the given target into addresses.
+    ///
+    /// Unix domain socket addresses are returned as is, while `host:port` is resolved through DNS if
+    /// host is a domain name.
+    pub fn resolve(target: &str) -> std::io::Result<Vec<Address>> {
+        match target.strip_prefix(UNIX_SCHEME) {
+            Some(path) => Ok(vec![Self::unix(path)?]),
+            None => Ok(target.to_socket_addrs()?.map(Address::Tcp).collect()),
+        }
+    }
+
+    fn unix(path: &str) -> std::io::Result<Address> {
+        if path.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Path of unix domain socket is empty",
+            ));
+        }
+        Ok(Address::Unix(PathBuf::from(path)))
+    }
+
+    pub fn is_unix(&self) -> bool {
+        matches!(self, Address::Unix(_))
+    }
+
+    /// Whether the address is on the current host: a Unix domain socket, or a TCP address whose IP is
+    /// assigned to one of the local interfaces.
+    pub fn is_local(&self) -> bool {
+        match self {
+            Address::Tcp(addr) => {
+                let ip = addr.ip();
+                // Only local IPs can be bound.
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || UdpSocket::bind(SocketAddr::new(ip, 0)).is_ok()
+            }
+            Address::Unix(_) => true,
+        }
+    }
+
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Address::Tcp(addr) => Some(*addr),
+            Address::Unix(_) => None,
+        }
+    }
+}
+
+impl From<SocketAddr> for Address {
+    fn from(addr: SocketAddr) -> Self {
+        Address::Tcp(addr)
+    }
+}
+
+impl FromStr for Address {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(UNIX_SCHEME) {
+            Some(path) => Self::unix(path),
+            None => SocketAddr::from_str(s)
+                .map(Address::Tcp)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e)),
+        }
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Tcp(addr) => write!(f, "{}", addr),
+            Address::Unix(path) => write!(f, "{}{}", UNIX_SCHEME, path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, path::PathBuf, str::FromStr};
+
+    use super::Address;
+
+    #[test]
+    fn test_parse() -> Result<(), Box<dyn Error>> {
+        let addr = Address::from_str("127.0.0.1:10911")?;
+        assert_eq!(Address::Tcp("127.0.0.1:10911".parse()?), addr);
+        assert_eq!("127.0.0.1:10911", addr.to_string());
+        assert!(!addr.is_unix());
+
+        let addr = Address::from_str("unix:///var/run/range-server.sock")?;
+        assert_eq!(
+            Address::Unix(PathBuf::from("/var/run/range-server.sock")),
+            addr
+        );
+        assert_eq!("unix:///var/run/range-server.sock", addr.to_string());
+        assert!(addr.is_unix());
+        assert_eq!(None, addr.socket_addr());
+
+        assert!(Address::from_str("unix://").is_err());
+        assert!(Address::from_str("localhost").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve() -> Result<(), Box<dyn Error>> {
+        let addrs = Address::resolve("localhost:10911")?;
+        assert!(!addrs.is_empty());
+        assert!(addrs.iter().all(|addr| !addr.is_unix()));
+
+        let addrs = Address::resolve("unix:///tmp/es.sock")?;
+        assert_eq!(vec![Address::Unix(PathBuf::from("/tmp/es.sock"))], addrs);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_local() -> Result<(), Box<dyn Error>> {
+        assert!(Address::from_str("127.0.0.1:10911")?.is_local());
+        assert!(Address::from_str("unix:///tmp/es.sock")?.is_local());
+        // Address reserved for documentation, which is not assigned to local interfaces.
+        assert!(!Address::from_str("192.0.2.1:10911")?.is_local());
+        Ok(())
+    }
+}
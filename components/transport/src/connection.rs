@@ -1,7 +1,10 @@
+use crate::address::Address;
 use crate::connection_state::ConnectionState;
+use crate::stream::Stream;
 use crate::ConnectionError;
 use crate::WriteTask;
 use bytes::{Buf, BytesMut};
+use codec::compression::Compression;
 use codec::error::FrameError;
 use codec::frame::Frame;
 use local_sync::{
@@ -10,6 +13,7 @@ use local_sync::{
 };
 use log::debug;
 use log::{error, info, trace, warn};
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::cell::UnsafeCell;
 use std::collections::VecDeque;
@@ -22,24 +26,36 @@ use std::os::fd::AsRawFd;
 use std::os::fd::FromRawFd;
 use std::os::fd::IntoRawFd;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use tokio_uring::net::TcpStream;
+use tokio_uring::net::{TcpStream, UnixStream};
 
 const BUFFER_SIZE: usize = 4 * 1024;
 
+/// Generator of connection IDs, which are unique within the process.
+static CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
 pub struct Connection {
-    /// Underlying TCP stream.
-    stream: Option<Rc<TcpStream>>,
+    /// Process-wide unique ID of the connection.
+    id: u64,
+
+    /// Underlying TCP or Unix domain socket stream.
+    stream: Option<Rc<Stream>>,
 
-    /// Local socket address
-    local_addr: Option<SocketAddr>,
+    /// Local address
+    local_addr: Option<Address>,
 
-    /// Peer socket address
-    remote_addr: SocketAddr,
+    /// Peer address
+    remote_addr: Address,
 
     /// Connection state
     state: Rc<RefCell<ConnectionState>>,
 
+    /// Compression of payloads of outgoing frames, as negotiated with the peer.
+    ///
+    /// Incoming frames are decompressed according to their own flags, regardless of this setting.
+    compression: Rc<Cell<Compression>>,
+
     /// Read buffer for this connection.
     buffer: UnsafeCell<BytesMut>,
 
@@ -54,24 +70,33 @@ pub struct Connection {
 
 impl Connection {
     fn spawn_write_loop(
-        stream: Rc<TcpStream>,
+        stream: Rc<Stream>,
         mut rx: Rx<WriteTask>,
         connection_state: Rc<RefCell<ConnectionState>>,
-        local_addr: SocketAddr,
-        remote_addr: SocketAddr,
+        compression: Rc<Cell<Compression>>,
+        local_addr: Option<Address>,
+        remote_addr: Address,
     ) {
         tokio_uring::spawn(async move {
             trace!("Start write coroutine loop for {remote_addr}");
             loop {
                 match rx.recv().await {
-                    Some(task) => {
+                    Some(mut task) => {
+                        let compression = compression.get();
+                        if compression != Compression::None
+                            && task.frame.payload.is_some()
+                            && task.frame.compression() == Compression::None
+                        {
+                            task.frame.flag_compression(compression);
+                        }
+
                         trace!(
                             "Write-frame-task[stream-id={}] received, start writing to {remote_addr}",
                             task.frame.stream_id,
 
                         );
 
-                        if let Err(e) = Self::write(&stream, &task.frame, remote_addr).await {
+                        if let Err(e) = Self::write(&stream, &task.frame, &remote_addr).await {
                             match e {
                                 ConnectionError::EncodeFrame(e) => {
                                     error!("Failed to encode frame: {}", e);
@@ -80,7 +105,7 @@ impl Connection {
                                 }
 
                                 ConnectionError::Network(e) => {
-                                    error!("Failed to write frame to network due to {}, closing underlying TcpStream", e);
+                                    error!("Failed to write frame to network due to {}, closing underlying stream", e);
                                     *connection_state.borrow_mut() = ConnectionState::Closed;
                                     let _ = stream.shutdown(Shutdown::Both);
                                     break;
@@ -96,7 +121,7 @@ impl Connection {
                     }
                     None => {
                         info!(
-                            "Connection {local_addr} --> {remote_addr} should be closed, stop write coroutine loop"
+                            "Connection {local_addr:?} --> {remote_addr} should be closed, stop write coroutine loop"
                         );
                         break;
                     }
@@ -105,12 +130,17 @@ impl Connection {
         });
     }
 
-    pub fn new(remote_addr: SocketAddr) -> Self {
+    pub fn new<A>(remote_addr: A) -> Self
+    where
+        A: Into<Address>,
+    {
         Self {
-            remote_addr,
+            id: CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            remote_addr: remote_addr.into(),
             stream: None,
             local_addr: None,
             state: Rc::new(RefCell::new(ConnectionState::Unspecified)),
+            compression: Rc::new(Cell::new(Compression::None)),
             buffer: UnsafeCell::new(BytesMut::with_capacity(BUFFER_SIZE)),
             tx: RefCell::new(None),
             tasks: UnsafeCell::new(VecDeque::new()),
@@ -122,9 +152,25 @@ impl Connection {
         stream: TcpStream,
         remote_addr: SocketAddr,
     ) -> Result<Self, ConnectionError> {
-        let local_addr = Self::local_addr_of(&stream).map_err(|e| {
+        Self::with_established(Stream::Tcp(stream), Address::Tcp(remote_addr))
+    }
+
+    /// Create connection with an established `UnixStream`, which is accepted from the listener
+    /// bound to `listen_addr`.
+    ///
+    /// Peers of Unix domain sockets are usually unnamed, so they are identified by the listening
+    /// address and connection ID.
+    pub fn with_unix_stream(
+        stream: UnixStream,
+        listen_addr: Address,
+    ) -> Result<Self, ConnectionError> {
+        Self::with_established(Stream::Unix(stream), listen_addr)
+    }
+
+    fn with_established(stream: Stream, remote_addr: Address) -> Result<Self, ConnectionError> {
+        let local_addr = stream.local_addr().map_err(|e| {
             error!(
-                "Failed to acquire local address from the given TcpStream: {:?}",
+                "Failed to acquire local address from the given stream: {:?}",
                 e
             );
             ConnectionError::Network(e)
@@ -133,19 +179,23 @@ impl Connection {
         let (tx, rx) = unbounded::channel();
         let stream = Rc::new(stream);
         let state = Rc::new(RefCell::new(ConnectionState::Active));
+        let compression = Rc::new(Cell::new(Compression::None));
         Self::spawn_write_loop(
             Rc::clone(&stream),
             rx,
             Rc::clone(&state),
-            local_addr,
-            remote_addr,
+            Rc::clone(&compression),
+            local_addr.clone(),
+            remote_addr.clone(),
         );
 
         Ok(Self {
+            id: CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
             remote_addr,
             stream: Some(stream),
-            local_addr: Some(local_addr),
+            local_addr,
             state,
+            compression,
             buffer: UnsafeCell::new(BytesMut::with_capacity(BUFFER_SIZE)),
             tx: RefCell::new(Some(tx)),
             tasks: UnsafeCell::new(VecDeque::new()),
@@ -159,7 +209,7 @@ impl Connection {
         let _drain = BufferedTaskDrain {
             tasks: unsafe { &mut *self.tasks.get() },
         };
-        let connect = TcpStream::connect(self.remote_addr);
+        let connect = Stream::connect(&self.remote_addr);
         let connect = tokio::time::timeout(timeout, connect);
         let stream = match connect.await {
             Ok(res) => match res {
@@ -180,13 +230,13 @@ impl Connection {
                     self.remote_addr
                 );
                 return Err(ConnectionError::Timeout {
-                    target: self.remote_addr,
+                    target: self.remote_addr.clone(),
                     elapsed,
                 });
             }
         };
 
-        let local_addr = Self::local_addr_of(&stream).map_err(|e| {
+        let local_addr = stream.local_addr().map_err(|e| {
             error!(
                 "Failed to acquire local address of an established connection: {:?}",
                 e
@@ -196,17 +246,12 @@ impl Connection {
             ConnectionError::Network(e)
         })?;
 
-        stream.set_nodelay(true).map_err(|e| {
-            error!("Failed to disable TCP Nagle's algorithm");
-            ConnectionError::Network(e)
-        })?;
-
         let stream = Rc::new(stream);
 
         let (tx, rx) = unbounded::channel();
 
         *self.state.borrow_mut() = ConnectionState::Active;
-        self.local_addr = Some(local_addr);
+        self.local_addr = local_addr.clone();
 
         // While this coroutine is establishing TCP connection, other coroutine tasks
         // may have queued up some pending requests.
@@ -221,8 +266,9 @@ impl Connection {
             Rc::clone(&stream),
             rx,
             Rc::clone(&self.state),
+            Rc::clone(&self.compression),
             local_addr,
-            self.remote_addr,
+            self.remote_addr.clone(),
         );
         self.stream = Some(stream);
 
@@ -230,9 +276,9 @@ impl Connection {
     }
 
     async fn write(
-        stream: &Rc<TcpStream>,
+        stream: &Rc<Stream>,
         frame: &Frame,
-        remote_addr: SocketAddr,
+        remote_addr: &Address,
     ) -> Result<(), ConnectionError> {
         let mut buffers = frame.encode()?;
 
@@ -292,12 +338,16 @@ impl Connection {
         Ok(())
     }
 
-    pub fn remote_addr(&self) -> SocketAddr {
-        self.remote_addr
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn remote_addr(&self) -> &Address {
+        &self.remote_addr
     }
 
-    pub fn local_addr(&self) -> Option<SocketAddr> {
-        self.local_addr
+    pub fn local_addr(&self) -> Option<&Address> {
+        self.local_addr.as_ref()
     }
 
     #[allow(clippy::mut_from_ref)]
@@ -498,6 +548,15 @@ impl Connection {
         *self.state.borrow()
     }
 
+    pub fn compression(&self) -> Compression {
+        self.compression.get()
+    }
+
+    /// Compress payloads of subsequent outgoing frames, once negotiated with the peer.
+    pub fn set_compression(&self, compression: Compression) {
+        self.compression.set(compression);
+    }
+
     /// Expose this method, allowing upper layer to flag this connection as going away.
     pub fn set_state(&self, state: ConnectionState) {
         *self.state.borrow_mut() = state;
@@ -522,7 +581,11 @@ impl Connection {
 
 impl Display for Connection {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self.local_addr {
+        if self.remote_addr.is_unix() {
+            return write!(f, "unix#{} --> {}", self.id, self.remote_addr);
+        }
+
+        match &self.local_addr {
             Some(addr) => {
                 write!(f, "{} --> {}", addr, self.remote_addr)
             }
@@ -638,7 +701,7 @@ mod tests {
         tokio_uring::start(async {
             let address = format!("127.0.0.1:{}", port);
             {
-                let remote_addr = address.parse().unwrap();
+                let remote_addr: std::net::SocketAddr = address.parse().unwrap();
 
                 let mut connection = super::Connection::new(remote_addr);
                 connection.connect(Duration::from_secs(3)).await.unwrap();
@@ -663,4 +726,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_write_frame_over_unix_socket() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        let path = std::env::temp_dir().join(format!("transport-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path)?;
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _addr) = listener.accept().unwrap();
+            let mut buf = vec![];
+            std::io::Read::read_to_end(&mut stream, &mut buf).unwrap();
+            buf.len()
+        });
+
+        tokio_uring::start(async {
+            let remote_addr = crate::Address::Unix(path.clone());
+            let mut connection = super::Connection::new(remote_addr);
+            connection.connect(Duration::from_secs(3)).await.unwrap();
+            assert!(connection.local_addr().is_none());
+
+            let mut frame = codec::frame::Frame::new(OperationCode::ALLOCATE_ID);
+            frame.payload = Some(vec![bytes::Bytes::from_static(&[8u8; 1024])]);
+            connection.write_frame(frame).await.unwrap();
+            connection.close().unwrap();
+        });
+
+        assert_eq!(1024 + 20, handle.join().unwrap());
+        let _ = std::fs::remove_file(&path);
+        Ok(())
+    }
 }
@@ -1,9 +1,9 @@
-use std::net::SocketAddr;
-
 use codec::error::FrameError;
 use thiserror::Error;
 use tokio::time::error::Elapsed;
 
+use crate::Address;
+
 #[derive(Debug, Error)]
 pub enum ConnectionError {
     #[error("Failed to encode frame")]
@@ -12,12 +12,9 @@ pub enum ConnectionError {
     #[error("Network IO")]
     Network(#[from] std::io::Error),
 
-    #[error("TCP connection is not established")]
+    #[error("Connection is not established")]
     NotConnected,
 
     #[error("Connecting to {target} timeout, elapsed {elapsed}")]
-    Timeout {
-        target: SocketAddr,
-        elapsed: Elapsed,
-    },
+    Timeout { target: Address, elapsed: Elapsed },
 }
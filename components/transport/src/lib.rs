@@ -1,10 +1,13 @@
 #![feature(extract_if)]
 
+pub(crate) mod address;
 pub mod connection;
 pub mod connection_state;
 pub(crate) mod error;
+pub(crate) mod stream;
 mod sync;
 pub(crate) mod write_task;
 
+pub use address::{Address, UNIX_SCHEME};
 pub use error::ConnectionError;
 pub(crate) use write_task::WriteTask;
@@ -0,0 +1,76 @@
+use std::{
+    net::Shutdown,
+    os::fd::{AsRawFd, FromRawFd, IntoRawFd},
+};
+
+use bytes::{Bytes, BytesMut};
+use tokio_uring::{
+    net::{TcpStream, UnixStream},
+    BufResult,
+};
+
+use crate::address::Address;
+
+/// Underlying stream of a `Connection`, either over TCP or Unix domain socket.
+pub(crate) enum Stream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Stream {
+    pub(crate) async fn connect(target: &Address) -> std::io::Result<Self> {
+        match target {
+            Address::Tcp(addr) => {
+                let stream = TcpStream::connect(*addr).await?;
+                stream.set_nodelay(true)?;
+                Ok(Stream::Tcp(stream))
+            }
+            Address::Unix(path) => Ok(Stream::Unix(UnixStream::connect(path).await?)),
+        }
+    }
+
+    pub(crate) async fn read(&self, buf: BytesMut) -> BufResult<usize, BytesMut> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf).await,
+            Stream::Unix(stream) => stream.read(buf).await,
+        }
+    }
+
+    pub(crate) async fn writev(&self, buffers: Vec<Bytes>) -> BufResult<usize, Vec<Bytes>> {
+        match self {
+            Stream::Tcp(stream) => stream.writev(buffers).await,
+            Stream::Unix(stream) => stream.writev(buffers).await,
+        }
+    }
+
+    pub(crate) fn shutdown(&self, how: Shutdown) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.shutdown(how),
+            Stream::Unix(stream) => stream.shutdown(how),
+        }
+    }
+
+    /// Local address of the stream.
+    ///
+    /// Client-side Unix domain sockets are usually unnamed, in which case `None` is returned.
+    pub(crate) fn local_addr(&self) -> std::io::Result<Option<Address>> {
+        match self {
+            Stream::Tcp(stream) => {
+                let fd = stream.as_raw_fd();
+                let std_stream = unsafe { std::net::TcpStream::from_raw_fd(fd) };
+                let local_addr = std_stream.local_addr();
+                let _ = std_stream.into_raw_fd();
+                Ok(Some(Address::Tcp(local_addr?)))
+            }
+            Stream::Unix(stream) => {
+                let fd = stream.as_raw_fd();
+                let std_stream = unsafe { std::os::unix::net::UnixStream::from_raw_fd(fd) };
+                let local_addr = std_stream.local_addr();
+                let _ = std_stream.into_raw_fd();
+                Ok(local_addr?
+                    .as_pathname()
+                    .map(|path| Address::Unix(path.to_path_buf())))
+            }
+        }
+    }
+}
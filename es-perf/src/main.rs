@@ -0,0 +1,230 @@
+//! `es-perf` drives streams with producers and consumers at target rates through `frontend-rs`,
+//! reporting throughput and latency percentiles at intervals and in a final summary.
+//!
+//! Latency of producers is the time a record takes to be acknowledged, while latency of consumers
+//! is end to end, from a record being sent to it being read.
+
+use std::{
+    error::Error,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use frontend::{FrontendBuilder, OpenOptions, ProducerOptions, Stream, StreamOptions};
+use log::{info, warn};
+use workload::Context;
+
+mod stats;
+mod workload;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Load generator of Elastic Stream", long_about = None)]
+struct Args {
+    /// Access point of placement driver
+    #[arg(long, env = "ES_PD", default_value = "127.0.0.1:12378")]
+    pd: String,
+
+    /// Configuration file of the client
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Prefix of environment variables overriding configuration of the client
+    #[arg(long)]
+    env_prefix: Option<String>,
+
+    /// Number of streams to create
+    #[arg(long, default_value_t = 1)]
+    streams: usize,
+
+    /// Number of producers, assigned to streams round-robin
+    #[arg(long, default_value_t = 1)]
+    producers: usize,
+
+    /// Number of consumers, assigned to streams round-robin
+    #[arg(long, default_value_t = 1)]
+    consumers: usize,
+
+    /// Target rate of all producers in records per second, zero for unlimited
+    #[arg(long, default_value_t = 1000.0)]
+    rate: f64,
+
+    /// Size of each record in bytes, at least 8 for the send timestamp
+    #[arg(long, default_value_t = 1024)]
+    record_size: usize,
+
+    /// Max time in milliseconds a record waits for more records to join its batch
+    #[arg(long, default_value_t = 5)]
+    linger_ms: u64,
+
+    /// Bytes of accumulated records, on reaching which the batch is sent immediately
+    #[arg(long, default_value_t = 16 * 1024)]
+    batch_size: usize,
+
+    /// Max number of records in flight of each producer
+    #[arg(long, default_value_t = 1024)]
+    max_inflight: usize,
+
+    /// Duration of the workload, in seconds
+    #[arg(long, default_value_t = 60)]
+    duration: u64,
+
+    /// Interval of reports, in seconds
+    #[arg(long, default_value_t = 5)]
+    report_interval: u64,
+
+    /// Time consumers are given to catch up once producers stop, in seconds
+    #[arg(long, default_value_t = 10)]
+    drain_timeout: u64,
+
+    /// Replica count of streams
+    #[arg(long, default_value_t = 1)]
+    replica: u8,
+
+    /// Ack count of streams
+    #[arg(long, default_value_t = 1)]
+    ack: u8,
+
+    /// Keep streams once done, which are deleted by default
+    #[arg(long)]
+    keep_streams: bool,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    frontend::init_log();
+    if args.streams == 0 {
+        return Err("At least one stream is required".into());
+    }
+
+    let mut builder = FrontendBuilder::new(&args.pd);
+    if let Some(path) = &args.config {
+        builder = builder.with_config_file(path);
+    }
+    if let Some(prefix) = &args.env_prefix {
+        builder = builder.with_env_prefix(prefix);
+    }
+    let frontend = builder.build()?;
+
+    tokio_uring::start(async move {
+        let mut streams = Vec::with_capacity(args.streams);
+        for _ in 0..args.streams {
+            let stream_id = frontend
+                .create(StreamOptions {
+                    replica: args.replica,
+                    ack: args.ack,
+                    retention: Duration::from_secs(3600),
+                    compaction: None,
+                    rollover: None,
+                })
+                .await?;
+            streams.push(frontend.open(stream_id, OpenOptions::default()).await?);
+        }
+        info!(
+            "Created streams {:?}",
+            streams.iter().map(|s| s.id()).collect::<Vec<_>>()
+        );
+
+        let context = Rc::new(Context::new());
+
+        let mut consumers = Vec::with_capacity(args.consumers);
+        for i in 0..args.consumers {
+            let stream_id = streams[i % streams.len()].id();
+            let stream = frontend.open(stream_id, OpenOptions::read_only()).await?;
+            let offset = stream.next_offset().await?;
+            consumers.push(tokio_uring::spawn(workload::consume(
+                Rc::clone(&context),
+                stream,
+                offset,
+            )));
+        }
+
+        let options = ProducerOptions {
+            linger_ms: args.linger_ms,
+            batch_size: args.batch_size,
+        };
+        let rate = args.rate / args.producers.max(1) as f64;
+        let producers = (0..args.producers)
+            .map(|i| {
+                let producer = streams[i % streams.len()].producer(options.clone());
+                tokio_uring::spawn(workload::produce(
+                    Rc::clone(&context),
+                    producer,
+                    rate,
+                    args.record_size,
+                    args.max_inflight.max(1),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let start = Instant::now();
+        let deadline = start + Duration::from_secs(args.duration);
+        let interval = Duration::from_secs(args.report_interval.max(1));
+        let mut last = start;
+        while last < deadline {
+            tokio::time::sleep(interval.min(deadline - last)).await;
+            let now = Instant::now();
+            report(&context, now - last);
+            last = now;
+        }
+
+        context.stopped.set(true);
+        for producer in producers {
+            let _ = producer.await;
+        }
+        // Producers are measured till they stop, excluding the time consumers are given to drain.
+        let produced = Instant::now();
+        context.drained.set(true);
+        let drain = async {
+            // Consumers that have caught up are removed, leaving the ones to cancel on timeout.
+            while let Some(consumer) = consumers.last_mut() {
+                let _ = consumer.await;
+                consumers.pop();
+            }
+        };
+        if tokio::time::timeout(Duration::from_secs(args.drain_timeout), drain)
+            .await
+            .is_err()
+        {
+            warn!("Consumers did not catch up in {}s", args.drain_timeout);
+            // Cancel consumers left behind, before their streams are deleted.
+            for consumer in consumers {
+                consumer.abort();
+                let _ = consumer.await;
+            }
+        }
+        let now = Instant::now();
+        report(&context, now - last);
+
+        let elapsed = now - start;
+        println!("Summary of {:.1}s", elapsed.as_secs_f64());
+        println!(
+            "  produce: {}",
+            context.produce.borrow().total(produced - start)
+        );
+        println!("  consume: {}", context.consume.borrow().total(elapsed));
+
+        for stream in streams {
+            cleanup(&stream, args.keep_streams).await;
+        }
+        Ok::<_, Box<dyn Error>>(())
+    })
+}
+
+fn report(context: &Context, elapsed: Duration) {
+    let produce = context.produce.borrow_mut().interval(elapsed);
+    let consume = context.consume.borrow_mut().interval(elapsed);
+    println!("produce: {produce}");
+    println!("consume: {consume}");
+}
+
+async fn cleanup(stream: &Stream, keep: bool) {
+    let result = if keep {
+        stream.close().await
+    } else {
+        stream.delete().await
+    };
+    if let Err(e) = result {
+        warn!("Failed to clean up stream[id={}]: {e}", stream.id());
+    }
+}
@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use hdrhistogram::Histogram;
+use log::warn;
+
+/// Max latency to record, in microseconds, beyond which values are saturated.
+const MAX_LATENCY_US: u64 = 60 * 1_000_000;
+
+/// Counters and latency histogram of an operation, both of the current interval and in total.
+pub struct Stats {
+    records: u64,
+    bytes: u64,
+    failures: u64,
+    latency: Histogram<u64>,
+
+    total_records: u64,
+    total_bytes: u64,
+    total_failures: u64,
+    total_latency: Histogram<u64>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            records: 0,
+            bytes: 0,
+            failures: 0,
+            latency: Histogram::new_with_max(MAX_LATENCY_US, 3).unwrap(),
+            total_records: 0,
+            total_bytes: 0,
+            total_failures: 0,
+            total_latency: Histogram::new_with_max(MAX_LATENCY_US, 3).unwrap(),
+        }
+    }
+
+    pub fn record(&mut self, bytes: usize, latency: Duration) {
+        self.records += 1;
+        self.bytes += bytes as u64;
+        let latency = (latency.as_micros() as u64).min(MAX_LATENCY_US);
+        if let Err(e) = self.latency.record(latency) {
+            warn!("Failed to record latency {latency}us: {e:?}");
+        }
+    }
+
+    pub fn fail(&mut self) {
+        self.failures += 1;
+    }
+
+    /// Report of the interval, which is then merged into the total and reset.
+    pub fn interval(&mut self, elapsed: Duration) -> Report {
+        let report = Report::new(
+            elapsed,
+            self.records,
+            self.bytes,
+            self.failures,
+            &self.latency,
+        );
+        self.total_records += self.records;
+        self.total_bytes += self.bytes;
+        self.total_failures += self.failures;
+        if let Err(e) = self.total_latency.add(&self.latency) {
+            warn!("Failed to merge latency histogram: {e:?}");
+        }
+        self.records = 0;
+        self.bytes = 0;
+        self.failures = 0;
+        self.latency.reset();
+        report
+    }
+
+    /// Report of all intervals so far, excluding the current one.
+    pub fn total(&self, elapsed: Duration) -> Report {
+        Report::new(
+            elapsed,
+            self.total_records,
+            self.total_bytes,
+            self.total_failures,
+            &self.total_latency,
+        )
+    }
+}
+
+/// Throughput and latency percentiles, in microseconds, of a period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub records_per_sec: f64,
+    pub mib_per_sec: f64,
+    pub records: u64,
+    pub failures: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+}
+
+impl Report {
+    fn new(
+        elapsed: Duration,
+        records: u64,
+        bytes: u64,
+        failures: u64,
+        latency: &Histogram<u64>,
+    ) -> Self {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        Self {
+            records_per_sec: records as f64 / secs,
+            mib_per_sec: bytes as f64 / secs / (1024.0 * 1024.0),
+            records,
+            failures,
+            mean: latency.mean(),
+            p50: latency.value_at_quantile(0.5),
+            p99: latency.value_at_quantile(0.99),
+            p999: latency.value_at_quantile(0.999),
+            max: latency.max(),
+        }
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1} records/s, {:.2} MiB/s, {} records, {} failures, latency(us) mean {:.1}, p50 {}, p99 {}, p99.9 {}, max {}",
+            self.records_per_sec,
+            self.mib_per_sec,
+            self.records,
+            self.failures,
+            self.mean,
+            self.p50,
+            self.p99,
+            self.p999,
+            self.max,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Stats;
+
+    #[test]
+    fn test_stats() {
+        let mut stats = Stats::new();
+        for i in 1..=100 {
+            stats.record(1024, Duration::from_micros(i));
+        }
+        stats.fail();
+
+        let report = stats.interval(Duration::from_secs(2));
+        assert_eq!(50.0, report.records_per_sec);
+        assert_eq!(100, report.records);
+        assert_eq!(1, report.failures);
+        assert_eq!(50, report.p50);
+        assert_eq!(100, report.max);
+
+        // Intervals are reset, while totals accumulate.
+        stats.record(1024, Duration::from_micros(1000));
+        let report = stats.interval(Duration::from_secs(1));
+        assert_eq!(1, report.records);
+        assert_eq!(0, report.failures);
+        assert_eq!(1000, report.p50);
+
+        let total = stats.total(Duration::from_secs(3));
+        assert_eq!(101, total.records);
+        assert_eq!(1, total.failures);
+        assert_eq!(1000, total.max);
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use frontend::{Producer, Stream};
+use log::{error, warn};
+use model::record::flat_record::FlatRecordBatch;
+use tokio::sync::Semaphore;
+
+use crate::stats::Stats;
+
+/// Max bytes of record batches a consumer reads at a time.
+const READ_MAX_BYTES: i32 = 4 * 1024 * 1024;
+
+/// Interval of polling a stream once a consumer catches up.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Size of the send timestamp at the head of each record.
+pub const TIMESTAMP_SIZE: usize = 8;
+
+/// State shared by tasks of the workload.
+pub struct Context {
+    /// Base of send timestamps, which records carry to measure end-to-end latency.
+    pub epoch: Instant,
+    pub produce: RefCell<Stats>,
+    pub consume: RefCell<Stats>,
+    /// Producers stop sending once set.
+    pub stopped: Cell<bool>,
+    /// Set once all records sent are acknowledged, after which consumers stop on catching up.
+    pub drained: Cell<bool>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            produce: RefCell::new(Stats::new()),
+            consume: RefCell::new(Stats::new()),
+            stopped: Cell::new(false),
+            drained: Cell::new(false),
+        }
+    }
+}
+
+/// A record of `size` bytes, carrying the send timestamp in microseconds since `epoch`.
+pub fn make_record(epoch: Instant, size: usize) -> Bytes {
+    let mut buf = BytesMut::with_capacity(size.max(TIMESTAMP_SIZE));
+    buf.put_u64(epoch.elapsed().as_micros() as u64);
+    buf.resize(size.max(TIMESTAMP_SIZE), b'x');
+    buf.freeze()
+}
+
+/// Time elapsed since the record was sent.
+pub fn record_latency(epoch: Instant, record: &[u8]) -> Option<Duration> {
+    if record.len() < TIMESTAMP_SIZE {
+        return None;
+    }
+    let sent = Duration::from_micros((&record[..TIMESTAMP_SIZE]).get_u64());
+    Some(epoch.elapsed().saturating_sub(sent))
+}
+
+/// Send records at `rate` records per second, or as fast as `max_inflight` allows if `rate` is zero,
+/// until the workload is stopped.
+pub async fn produce(
+    context: Rc<Context>,
+    producer: Producer,
+    rate: f64,
+    record_size: usize,
+    max_inflight: usize,
+) {
+    let semaphore = Arc::new(Semaphore::new(max_inflight));
+    let period = (rate > 0.0).then(|| Duration::from_secs_f64(1.0 / rate));
+    let start = tokio::time::Instant::now();
+    let mut sent: u32 = 0;
+    while !context.stopped.get() {
+        if let Some(period) = period {
+            tokio::time::sleep_until(start + period * sent).await;
+        }
+        let permit = match Arc::clone(&semaphore).acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => break,
+        };
+        sent = sent.wrapping_add(1);
+
+        let record = make_record(context.epoch, record_size);
+        let send = producer.send(record);
+        let context = Rc::clone(&context);
+        tokio_uring::spawn(async move {
+            let start = Instant::now();
+            match send.await {
+                Ok(_) => context
+                    .produce
+                    .borrow_mut()
+                    .record(record_size, start.elapsed()),
+                Err(e) => {
+                    warn!("Failed to send record: {e}");
+                    context.produce.borrow_mut().fail();
+                }
+            }
+            drop(permit);
+        });
+    }
+    producer.flush();
+    // Wait for in-flight records.
+    let _ = semaphore.acquire_many(max_inflight as u32).await;
+}
+
+/// Read records from `offset` as they are appended, until the workload is drained and all records
+/// are read.
+pub async fn consume(context: Rc<Context>, stream: Stream, mut offset: i64) {
+    loop {
+        // Checked before the next offset, such that records appended before draining are read.
+        let drained = context.drained.get();
+        let next_offset = match stream.next_offset().await {
+            Ok(next_offset) => next_offset,
+            Err(e) => {
+                warn!(
+                    "Failed to get next offset of stream[id={}]: {e}",
+                    stream.id()
+                );
+                context.consume.borrow_mut().fail();
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+        if offset >= next_offset {
+            if drained {
+                return;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        }
+        match stream.read(offset, next_offset, READ_MAX_BYTES).await {
+            Ok(buffers) => match consume_buffers(&context, buffers, offset) {
+                Some(next) if next > offset => offset = next,
+                _ => {
+                    error!(
+                        "No progress consuming stream[id={}] at {offset}",
+                        stream.id()
+                    );
+                    return;
+                }
+            },
+            Err(e) => {
+                warn!("Failed to read stream[id={}] at {offset}: {e}", stream.id());
+                context.consume.borrow_mut().fail();
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Record end-to-end latency of records read, returning the offset to read next.
+fn consume_buffers(context: &Context, buffers: Vec<Bytes>, mut offset: i64) -> Option<i64> {
+    for mut buf in buffers {
+        while buf.has_remaining() {
+            let record_batch = match FlatRecordBatch::decode_to_record_batch(&mut buf) {
+                Ok(record_batch) => record_batch,
+                Err(e) => {
+                    error!("Failed to decode record batch: {e:?}");
+                    return None;
+                }
+            };
+            let base_offset = record_batch.base_offset();
+            let next_offset = base_offset + record_batch.last_offset_delta() as i64;
+            let records = match record_batch.records() {
+                Ok(records) => records,
+                Err(e) => {
+                    error!("Record batch at {base_offset} was not sent by a producer: {e:?}");
+                    return None;
+                }
+            };
+            let mut stats = context.consume.borrow_mut();
+            for record in records {
+                let record = match record {
+                    Ok(record) => record,
+                    Err(e) => {
+                        error!("Failed to decode records at {base_offset}: {e:?}");
+                        return None;
+                    }
+                };
+                if record.offset < offset {
+                    continue;
+                }
+                let Some(value) = record.value else {
+                    continue;
+                };
+                if let Some(latency) = record_latency(context.epoch, &value) {
+                    stats.record(value.len(), latency);
+                }
+            }
+            offset = offset.max(next_offset);
+        }
+    }
+    Some(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{make_record, record_latency};
+
+    #[test]
+    fn test_record_latency() {
+        let epoch = Instant::now() - Duration::from_secs(1);
+        let record = make_record(epoch, 100);
+        assert_eq!(100, record.len());
+        std::thread::sleep(Duration::from_millis(10));
+        let latency = record_latency(epoch, &record).unwrap();
+        assert!(latency >= Duration::from_millis(10));
+        assert!(latency < Duration::from_secs(1));
+
+        // Records are at least as large as their timestamps.
+        assert_eq!(8, make_record(epoch, 1).len());
+        assert_eq!(None, record_latency(epoch, b"short"));
+    }
+}
@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::output::Format;
+
+#[derive(Debug, Parser)]
+#[command(author, version, about = "Command-line client of Elastic Stream", long_about = None)]
+pub struct Cli {
+    /// The address of placement-driver service: `domain-name:port`
+    #[arg(long, env = "ES_PD", default_value = "127.0.0.1:12378", global = true)]
+    pub pd: String,
+
+    /// Path to the client configuration file in YAML format
+    #[arg(long, env = "ES_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Prefix of environment variables overriding client configuration, e.g. `ES` for
+    /// `ES_CLIENT__IO_TIMEOUT_MS`
+    #[arg(long, global = true)]
+    pub env_prefix: Option<String>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Table, global = true)]
+    pub output: Format,
+
+    #[command(subcommand)]
+    pub command: Commands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Manage streams and their records
+    #[command(subcommand)]
+    Stream(StreamCommand),
+
+    /// Diagnose range servers and placement drivers
+    #[command(subcommand)]
+    Server(ServerCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StreamCommand {
+    /// Create a stream
+    Create {
+        /// Number of replicas of each range
+        #[arg(long, default_value_t = 1)]
+        replica: u8,
+
+        /// Number of replicas to acknowledge an append
+        #[arg(long, default_value_t = 1)]
+        ack: u8,
+
+        /// Retention period of records, in seconds
+        #[arg(long, default_value_t = 7 * 24 * 3600)]
+        retention_secs: u64,
+    },
+
+    /// Describe a stream
+    Describe { stream_id: u64 },
+
+    /// List all streams that are not deleted
+    List,
+
+    /// Append records to a stream, one record per line of the input
+    Append {
+        stream_id: u64,
+
+        #[command(flatten)]
+        writer: WriterArgs,
+
+        /// File to read records from, instead of stdin
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Max number of records of a record batch
+        #[arg(long, default_value_t = 1024)]
+        batch_size: usize,
+    },
+
+    /// Read records of a stream to stdout, one record per line
+    Read {
+        stream_id: u64,
+
+        /// Offset of the first record to read, defaults to the start offset of the stream
+        #[arg(long)]
+        from: Option<i64>,
+
+        /// Exclusive offset to stop reading at, defaults to the next offset of the stream
+        #[arg(long)]
+        to: Option<i64>,
+    },
+
+    /// Follow a stream, writing records appended from now on to stdout
+    Tail {
+        stream_id: u64,
+
+        /// Offset to follow from, defaults to the next offset of the stream
+        #[arg(long)]
+        from: Option<i64>,
+
+        /// Interval of polling the stream for new records, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        poll_ms: u64,
+    },
+
+    /// Trim records before the offset
+    Trim {
+        stream_id: u64,
+
+        /// The new start offset of the stream
+        offset: i64,
+
+        #[command(flatten)]
+        writer: WriterArgs,
+    },
+
+    /// Delete a stream
+    Delete {
+        stream_id: u64,
+
+        #[command(flatten)]
+        writer: WriterArgs,
+    },
+}
+
+/// Epoch to open a stream for write with. Either `--epoch` or `--fence` is required.
+#[derive(Debug, Args)]
+pub struct WriterArgs {
+    /// Epoch to open the stream with.
+    ///
+    /// Opening a stream with a greater epoch fences its current writer.
+    #[arg(long)]
+    pub epoch: Option<u64>,
+
+    /// Open the stream with its current epoch plus one, fencing its current writer
+    #[arg(long, conflicts_with = "epoch")]
+    pub fence: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ServerCommand {
+    /// Measure round-trip time of heartbeats to a range server or placement driver
+    Ping {
+        /// Address of the target: `host:port` or `unix:///path/to/socket`
+        target: String,
+
+        /// Number of heartbeats to send
+        #[arg(short, long, default_value_t = 4)]
+        count: u32,
+
+        /// Interval between heartbeats, in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+    },
+
+    /// List ranges, optionally of a range server or a stream
+    ListRanges {
+        /// Id of the range server
+        #[arg(long)]
+        server_id: Option<u32>,
+
+        /// Id of the stream
+        #[arg(long)]
+        stream_id: Option<u64>,
+    },
+
+    /// Send a heartbeat and show the response
+    Heartbeat {
+        /// Address of the target, defaults to the placement driver
+        target: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{error::ErrorKind, CommandFactory, Parser};
+
+    use super::{Cli, Commands, StreamCommand};
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_writer_args() {
+        let cli = Cli::try_parse_from(["esctl", "stream", "append", "1", "--fence"]).unwrap();
+        let Commands::Stream(StreamCommand::Append {
+            stream_id, writer, ..
+        }) = cli.command
+        else {
+            panic!("Expected stream append, got {:?}", cli.command);
+        };
+        assert_eq!(1, stream_id);
+        assert_eq!((None, true), (writer.epoch, writer.fence));
+
+        let cli =
+            Cli::try_parse_from(["esctl", "stream", "trim", "1", "50", "--epoch", "3"]).unwrap();
+        let Commands::Stream(StreamCommand::Trim { offset, writer, .. }) = cli.command else {
+            panic!("Expected stream trim, got {:?}", cli.command);
+        };
+        assert_eq!(50, offset);
+        assert_eq!((Some(3), false), (writer.epoch, writer.fence));
+
+        let cli = Cli::try_parse_from(["esctl", "stream", "delete", "1"]).unwrap();
+        let Commands::Stream(StreamCommand::Delete { writer, .. }) = cli.command else {
+            panic!("Expected stream delete, got {:?}", cli.command);
+        };
+        assert_eq!((None, false), (writer.epoch, writer.fence));
+
+        let error =
+            Cli::try_parse_from(["esctl", "stream", "delete", "1", "--epoch", "3", "--fence"])
+                .unwrap_err();
+        assert_eq!(ErrorKind::ArgumentConflict, error.kind());
+    }
+}
@@ -0,0 +1,41 @@
+//! `esctl` is the command-line client of Elastic Stream for day-to-day operations on streams, as
+//! well as diagnostics of range servers and placement drivers.
+
+use std::sync::Arc;
+
+use clap::Parser;
+use cli::{Cli, Commands};
+use config::Configuration;
+use frontend::FrontendBuilder;
+
+mod cli;
+mod output;
+mod server;
+mod stream;
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    let mut config = match &cli.config {
+        Some(path) => Configuration::from_yaml_file(path)?,
+        None => Configuration::default(),
+    };
+    if let Some(prefix) = &cli.env_prefix {
+        config.apply_env(prefix)?;
+    }
+    config.placement_driver = cli.pd.clone();
+    config.check_client()?;
+
+    tokio_uring::start(async move {
+        match cli.command {
+            Commands::Stream(command) => {
+                let frontend = FrontendBuilder::new(&cli.pd)
+                    .with_configuration(config)
+                    .build()?;
+                stream::run(command, &frontend, cli.output).await
+            }
+            Commands::Server(command) => server::run(command, &Arc::new(config), cli.output).await,
+        }
+    })
+}
@@ -0,0 +1,272 @@
+//! Rendering of command results as aligned tables or JSON.
+
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Columns aligned for humans.
+    Table,
+
+    /// An array of objects, or one object per line for records.
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Int(v)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(v: u64) -> Self {
+        Value::Int(v as i64)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Value::Int(i64::from(v))
+    }
+}
+
+impl From<u32> for Value {
+    fn from(v: u32) -> Self {
+        Value::Int(i64::from(v))
+    }
+}
+
+impl From<u8> for Value {
+    fn from(v: u8) -> Self {
+        Value::Int(i64::from(v))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::Str(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Str(v.to_owned())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+impl Value {
+    fn to_table_cell(&self) -> String {
+        match self {
+            Value::Null => "-".to_owned(),
+            Value::Bool(v) => v.to_string(),
+            Value::Int(v) => v.to_string(),
+            Value::Float(v) => format!("{v:.3}"),
+            Value::Str(v) => v.clone(),
+        }
+    }
+
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(v) => out.push_str(if *v { "true" } else { "false" }),
+            Value::Int(v) => {
+                let _ = write!(out, "{v}");
+            }
+            Value::Float(v) if v.is_finite() => {
+                let _ = write!(out, "{v}");
+            }
+            Value::Float(_) => out.push_str("null"),
+            Value::Str(v) => write_json_string(v, out),
+        }
+    }
+}
+
+fn write_json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Rows of a result, sharing the same columns.
+#[derive(Debug)]
+pub struct Rows {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Rows {
+    /// Columns are snake_case, which are keys of JSON objects and upper-cased as table headers.
+    pub fn new(columns: Vec<&'static str>) -> Self {
+        Self {
+            columns,
+            rows: vec![],
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        debug_assert_eq!(self.columns.len(), row.len());
+        self.rows.push(row);
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Table => self.render_table(),
+            Format::Json => {
+                let mut out = String::from("[");
+                for (i, row) in self.rows.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    self.write_object(row, &mut out);
+                }
+                out.push(']');
+                out
+            }
+        }
+    }
+
+    /// Render a single row, as a line of the table without headers or a JSON object.
+    pub fn render_row(&self, row: &[Value], format: Format) -> String {
+        match format {
+            Format::Table => row
+                .iter()
+                .map(Value::to_table_cell)
+                .collect::<Vec<_>>()
+                .join("  "),
+            Format::Json => {
+                let mut out = String::new();
+                self.write_object(row, &mut out);
+                out
+            }
+        }
+    }
+
+    fn write_object(&self, row: &[Value], out: &mut String) {
+        out.push('{');
+        for (i, (column, value)) in self.columns.iter().zip(row).enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_json_string(column, out);
+            out.push(':');
+            value.write_json(out);
+        }
+        out.push('}');
+    }
+
+    fn render_table(&self) -> String {
+        let headers = self
+            .columns
+            .iter()
+            .map(|column| column.to_uppercase())
+            .collect::<Vec<_>>();
+        let cells = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(Value::to_table_cell).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let widths = headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                cells
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain(std::iter::once(header.len()))
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let mut out = String::new();
+        for line in std::iter::once(&headers).chain(cells.iter()) {
+            let last = line.len().saturating_sub(1);
+            for (i, cell) in line.iter().enumerate() {
+                if i == last {
+                    out.push_str(cell);
+                } else {
+                    let _ = write!(out, "{cell:<width$}  ", width = widths[i]);
+                }
+            }
+            out.push('\n');
+        }
+        out.pop();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, Rows, Value};
+
+    fn rows() -> Rows {
+        let mut rows = Rows::new(vec!["stream_id", "name", "end"]);
+        rows.push(vec![1u64.into(), "a \"b\"".into(), Value::Null]);
+        rows.push(vec![100u64.into(), "c".into(), 42i64.into()]);
+        rows
+    }
+
+    #[test]
+    fn test_render_table() {
+        assert_eq!(
+            "STREAM_ID  NAME   END\n\
+             1          a \"b\"  -\n\
+             100        c      42",
+            rows().render(Format::Table)
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let rows = rows();
+        assert_eq!(
+            r#"[{"stream_id":1,"name":"a \"b\"","end":null},{"stream_id":100,"name":"c","end":42}]"#,
+            rows.render(Format::Json)
+        );
+        assert_eq!(
+            r#"{"stream_id":7,"name":"\n\u0001","end":true}"#,
+            rows.render_row(&[7u64.into(), "\n\u{1}".into(), true.into()], Format::Json)
+        );
+        assert_eq!("[]", Rows::new(vec!["id"]).render(Format::Json));
+    }
+}
@@ -0,0 +1,229 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+use client::{
+    client::Client,
+    request::{Headers, Request},
+    response::Response,
+    DefaultClient,
+};
+use codec::frame::Frame;
+use config::Configuration;
+use model::{range::RangeMetadata, ListRangeCriteria};
+use protocol::rpc::header::{ClientRole, ErrorCode, HeartbeatResponse, OperationCode};
+use tokio::{sync::broadcast, time::Instant};
+use transport::{connection::Connection, Address};
+
+use crate::{
+    cli::ServerCommand,
+    output::{Format, Rows},
+};
+
+pub async fn run(
+    command: ServerCommand,
+    config: &Arc<Configuration>,
+    format: Format,
+) -> anyhow::Result<()> {
+    match command {
+        ServerCommand::Ping {
+            target,
+            count,
+            interval_ms,
+        } => {
+            let connection = connect(&target, config).await?;
+            let mut rows = Rows::new(vec!["seq", "target", "status", "rtt_ms"]);
+            for seq in 0..count {
+                if seq > 0 {
+                    tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+                }
+                let heartbeat = heartbeat(&connection, config).await?;
+                rows.push(vec![
+                    seq.into(),
+                    target.as_str().into(),
+                    code_name(heartbeat.code).into(),
+                    millis(heartbeat.rtt).into(),
+                ]);
+            }
+            println!("{}", rows.render(format));
+        }
+        ServerCommand::ListRanges {
+            server_id,
+            stream_id,
+        } => {
+            let (shutdown, _rx) = broadcast::channel(1);
+            let client = DefaultClient::new(Arc::clone(config), shutdown);
+            let ranges = client
+                .list_ranges(ListRangeCriteria::new(server_id, stream_id))
+                .await?;
+            println!("{}", range_rows(&ranges).render(format));
+        }
+        ServerCommand::Heartbeat { target } => {
+            let target = target.unwrap_or_else(|| config.placement_driver.clone());
+            let connection = connect(&target, config).await?;
+            let heartbeat = heartbeat(&connection, config).await?;
+            let mut rows = Rows::new(vec![
+                "target",
+                "client_id",
+                "client_role",
+                "status",
+                "message",
+                "rtt_ms",
+            ]);
+            rows.push(vec![
+                target.into(),
+                heartbeat.client_id.into(),
+                heartbeat.client_role.into(),
+                code_name(heartbeat.code).into(),
+                heartbeat.message.into(),
+                millis(heartbeat.rtt).into(),
+            ]);
+            println!("{}", rows.render(format));
+        }
+    }
+    Ok(())
+}
+
+fn range_rows(ranges: &[RangeMetadata]) -> Rows {
+    let mut rows = Rows::new(vec![
+        "stream_id",
+        "index",
+        "epoch",
+        "start",
+        "end",
+        "replica_count",
+        "ack_count",
+        "servers",
+    ]);
+    for range in ranges {
+        let servers = range
+            .replica()
+            .iter()
+            .map(|server| format!("{}@{}", server.server_id, server.advertise_address))
+            .collect::<Vec<_>>()
+            .join(",");
+        rows.push(vec![
+            range.stream_id().into(),
+            range.index().into(),
+            range.epoch().into(),
+            range.start().into(),
+            range.end().into(),
+            range.replica_count().into(),
+            range.ack_count().into(),
+            servers.into(),
+        ]);
+    }
+    rows
+}
+
+fn code_name(code: ErrorCode) -> String {
+    code.variant_name()
+        .map_or_else(|| code.0.to_string(), str::to_owned)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+async fn connect(target: &str, config: &Configuration) -> anyhow::Result<Connection> {
+    let address = Address::resolve(target)
+        .with_context(|| format!("Failed to resolve {target}"))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No address of {target}"))?;
+    let mut connection = Connection::new(address);
+    connection
+        .connect(config.client_connect_timeout())
+        .await
+        .with_context(|| format!("Failed to connect to {target}"))?;
+    Ok(connection)
+}
+
+/// Result of a heartbeat round trip.
+struct Heartbeat {
+    rtt: Duration,
+    code: ErrorCode,
+    message: Option<String>,
+    client_id: Option<String>,
+    client_role: Option<String>,
+}
+
+/// Send a heartbeat on the connection and wait for its response.
+///
+/// Sessions of clients do not wait for responses of heartbeats, so the heartbeat is sent on a
+/// dedicated connection.
+async fn heartbeat(connection: &Connection, config: &Configuration) -> anyhow::Result<Heartbeat> {
+    let request = Request {
+        timeout: config.client_io_timeout(),
+        headers: Headers::Heartbeat {
+            client_id: config.client.client_id.clone(),
+            role: ClientRole::CLIENT_ROLE_FRONTEND,
+            range_server: None,
+        },
+        body: None,
+    };
+    let mut frame = Frame::new(OperationCode::HEARTBEAT);
+    frame.header = Some((&request).into());
+    let stream_id = frame.stream_id;
+
+    let start = Instant::now();
+    connection.write_frame(frame).await?;
+    let response = tokio::time::timeout(config.client_io_timeout(), async {
+        loop {
+            match connection.read_frame().await? {
+                Some(frame) if frame.is_response() && frame.stream_id == stream_id => {
+                    return Ok::<_, anyhow::Error>(frame)
+                }
+                Some(_) => continue,
+                None => bail!("Connection to {} is closed", connection.remote_addr()),
+            }
+        }
+    })
+    .await
+    .context("Heartbeat timed out")??;
+    let rtt = start.elapsed();
+
+    if response.system_error() {
+        let mut status = Response::new(OperationCode::HEARTBEAT);
+        status.on_system_error(&response);
+        return Ok(Heartbeat {
+            rtt,
+            code: status.status.code,
+            message: Some(status.status.message),
+            client_id: None,
+            client_role: None,
+        });
+    }
+    let header = response
+        .header
+        .ok_or_else(|| anyhow!("Heartbeat response without header"))?;
+    let heartbeat = flatbuffers::root::<HeartbeatResponse>(&header)?.unpack();
+    Ok(Heartbeat {
+        rtt,
+        code: heartbeat.status.code,
+        message: heartbeat.status.message,
+        client_id: heartbeat.client_id,
+        client_role: heartbeat.client_role.variant_name().map(str::to_owned),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, sync::Arc};
+
+    use mock_server::run_listener;
+
+    use super::{connect, heartbeat};
+
+    #[test]
+    fn test_heartbeat() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        tokio_uring::start(async {
+            let port = run_listener().await;
+            let config = Arc::new(config::Configuration::default());
+            let connection = connect(&format!("127.0.0.1:{port}"), &config).await?;
+            let heartbeat = heartbeat(&connection, &config).await?;
+            assert_eq!(protocol::rpc::header::ErrorCode::OK, heartbeat.code);
+            Ok(())
+        })
+    }
+}
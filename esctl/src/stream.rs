@@ -0,0 +1,349 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use frontend::{Frontend, OpenOptions, Stream, StreamOptions};
+use model::{
+    error::DecodeError,
+    record::{
+        flat_record::FlatRecordBatch,
+        magic1::{Record, RecordsBuilder},
+    },
+    stream::StreamMetadata,
+};
+
+use crate::{
+    cli::{StreamCommand, WriterArgs},
+    output::{Format, Rows, Value},
+};
+
+/// Max bytes of record batches to read at a time.
+const READ_MAX_BYTES: i32 = 1024 * 1024;
+
+pub async fn run(
+    command: StreamCommand,
+    frontend: &Frontend,
+    format: Format,
+) -> anyhow::Result<()> {
+    match command {
+        StreamCommand::Create {
+            replica,
+            ack,
+            retention_secs,
+        } => {
+            let stream_id = frontend
+                .create(StreamOptions {
+                    replica,
+                    ack,
+                    retention: Duration::from_secs(retention_secs),
+                    compaction: None,
+                    rollover: None,
+                })
+                .await?;
+            let stream = frontend.describe(stream_id).await?;
+            println!("{}", stream_rows(&[stream]).render(format));
+        }
+        StreamCommand::Describe { stream_id } => {
+            let stream = frontend.describe(stream_id).await?;
+            println!("{}", stream_rows(&[stream]).render(format));
+        }
+        StreamCommand::List => {
+            let streams = frontend.list_streams().await?;
+            println!("{}", stream_rows(&streams).render(format));
+        }
+        StreamCommand::Append {
+            stream_id,
+            writer,
+            file,
+            batch_size,
+        } => {
+            let input: Box<dyn BufRead> = match &file {
+                Some(path) => Box::new(BufReader::new(
+                    File::open(path).with_context(|| format!("Failed to open {path:?}"))?,
+                )),
+                None => Box::new(io::stdin().lock()),
+            };
+            let stream = open_writer(frontend, stream_id, &writer).await?;
+            let result = append(&stream, input, batch_size.max(1)).await;
+            stream.close().await?;
+            let (records, base_offset) = result?;
+
+            let mut rows = Rows::new(vec!["stream_id", "records", "base_offset"]);
+            rows.push(vec![stream_id.into(), records.into(), base_offset.into()]);
+            println!("{}", rows.render(format));
+        }
+        StreamCommand::Read {
+            stream_id,
+            from,
+            to,
+        } => {
+            let stream = frontend.open(stream_id, OpenOptions::read_only()).await?;
+            let start = match from {
+                Some(offset) => offset,
+                None => stream.start_offset().await?,
+            };
+            let end = match to {
+                Some(offset) => offset,
+                None => stream.next_offset().await?,
+            };
+            read(&stream, start, end, format).await?;
+        }
+        StreamCommand::Tail {
+            stream_id,
+            from,
+            poll_ms,
+        } => {
+            let stream = frontend.open(stream_id, OpenOptions::read_only()).await?;
+            let mut offset = match from {
+                Some(offset) => offset,
+                None => stream.next_offset().await?,
+            };
+            loop {
+                let next_offset = stream.next_offset().await?;
+                if offset < next_offset {
+                    offset = read(&stream, offset, next_offset, format).await?;
+                } else {
+                    tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+                }
+            }
+        }
+        StreamCommand::Trim {
+            stream_id,
+            offset,
+            writer,
+        } => {
+            let stream = open_writer(frontend, stream_id, &writer).await?;
+            let result = stream.trim(offset).await;
+            stream.close().await?;
+            result?;
+            let stream = frontend.describe(stream_id).await?;
+            println!("{}", stream_rows(&[stream]).render(format));
+        }
+        StreamCommand::Delete { stream_id, writer } => {
+            let stream = open_writer(frontend, stream_id, &writer).await?;
+            stream.delete().await?;
+            let mut rows = Rows::new(vec!["stream_id", "deleted"]);
+            rows.push(vec![stream_id.into(), true.into()]);
+            println!("{}", rows.render(format));
+        }
+    }
+    Ok(())
+}
+
+fn stream_rows(streams: &[StreamMetadata]) -> Rows {
+    let mut rows = Rows::new(vec![
+        "stream_id",
+        "replica",
+        "ack_count",
+        "retention_secs",
+        "start_offset",
+        "epoch",
+        "compacted",
+    ]);
+    for stream in streams {
+        rows.push(vec![
+            stream.stream_id.into(),
+            stream.replica.into(),
+            stream.ack_count.into(),
+            stream.retention_period.as_secs().into(),
+            stream.start_offset.into(),
+            stream.epoch.into(),
+            stream.compacted.into(),
+        ]);
+    }
+    rows
+}
+
+/// Open the stream for write with the given epoch, or fence its current writer if asked to.
+async fn open_writer(
+    frontend: &Frontend,
+    stream_id: u64,
+    writer: &WriterArgs,
+) -> anyhow::Result<Stream> {
+    let epoch = match (writer.epoch, writer.fence) {
+        (Some(epoch), _) => epoch,
+        (None, true) => frontend.describe(stream_id).await?.epoch + 1,
+        (None, false) => bail!(
+            "Opening stream[id={stream_id}] for write requires --epoch, or --fence to fence its \
+             current writer"
+        ),
+    };
+    Ok(frontend
+        .open(stream_id, OpenOptions::with_epoch(epoch))
+        .await?)
+}
+
+/// Append lines of the input as records, returning the number of records and the base offset of
+/// the first one.
+async fn append(
+    stream: &Stream,
+    input: Box<dyn BufRead>,
+    batch_size: usize,
+) -> anyhow::Result<(u64, Option<i64>)> {
+    let mut records = 0;
+    let mut base_offset = None;
+    let mut builder = RecordsBuilder::new();
+    for line in input.split(b'\n') {
+        let mut line = line.context("Failed to read input")?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        builder.append(now_millis(), None, Some(Bytes::from(line)), &[]);
+        if builder.len() >= batch_size {
+            let batch = std::mem::take(&mut builder);
+            records += batch.len() as u64;
+            let offset = append_batch(stream, batch).await?;
+            base_offset.get_or_insert(offset);
+        }
+    }
+    if !builder.is_empty() {
+        records += builder.len() as u64;
+        let offset = append_batch(stream, builder).await?;
+        base_offset.get_or_insert(offset);
+    }
+    Ok((records, base_offset))
+}
+
+async fn append_batch(stream: &Stream, builder: RecordsBuilder) -> anyhow::Result<i64> {
+    let record_batch = builder.build(stream.id() as i64)?;
+    let (buffers, _) = FlatRecordBatch::from(record_batch).encode();
+    Ok(stream
+        .append(Bytes::from(buffers.concat()))
+        .await?
+        .base_offset)
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+/// Write records in `[start, end)` to stdout, returning the offset to read next.
+///
+/// Record batches that are not in the format of `RecordMagic::Magic1` are written as a single
+/// record, whose value is the payload of the batch.
+async fn read(stream: &Stream, start: i64, end: i64, format: Format) -> anyhow::Result<i64> {
+    let rows = Rows::new(vec!["offset", "timestamp", "key", "value"]);
+    let mut stdout = io::stdout().lock();
+    let mut offset = start;
+    while offset < end {
+        let buffers = stream.read(offset, end, READ_MAX_BYTES).await?;
+        let mut next_offset = offset;
+        for mut buf in buffers {
+            while !buf.is_empty() {
+                let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf)?;
+                next_offset = record_batch.base_offset() + record_batch.last_offset_delta() as i64;
+                if record_batch.is_control() {
+                    continue;
+                }
+                let records = match record_batch.records() {
+                    Ok(records) => records.collect::<Result<Vec<_>, _>>()?,
+                    Err(DecodeError::InvalidMagic) => vec![Record {
+                        offset: record_batch.base_offset(),
+                        timestamp: record_batch.base_timestamp(),
+                        key: None,
+                        value: Some(record_batch.payload()),
+                        headers: vec![],
+                    }],
+                    Err(e) => return Err(e.into()),
+                };
+                for record in records.into_iter().filter(|r| r.offset >= offset) {
+                    write_record(&mut stdout, &rows, &record, format)?;
+                }
+            }
+        }
+        if next_offset <= offset {
+            bail!("No progress reading stream[id={}] at {offset}", stream.id());
+        }
+        offset = next_offset;
+    }
+    stdout.flush()?;
+    Ok(offset)
+}
+
+/// Write the value of the record as is in table format, or the record as a JSON object per line.
+fn write_record(
+    out: &mut impl Write,
+    rows: &Rows,
+    record: &Record,
+    format: Format,
+) -> io::Result<()> {
+    match format {
+        Format::Table => {
+            out.write_all(record.value.as_deref().unwrap_or_default())?;
+            out.write_all(b"\n")
+        }
+        Format::Json => {
+            let text = |v: &Option<Bytes>| -> Value {
+                v.as_ref()
+                    .map(|v| String::from_utf8_lossy(v).into_owned())
+                    .into()
+            };
+            let row = vec![
+                record.offset.into(),
+                record.timestamp.into(),
+                text(&record.key),
+                text(&record.value),
+            ];
+            writeln!(out, "{}", rows.render_row(&row, format))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use bytes::Bytes;
+    use frontend::FrontendBuilder;
+    use mock_server::run_listener;
+    use model::record::magic1::Record;
+
+    use super::{open_writer, write_record};
+    use crate::{
+        cli::WriterArgs,
+        output::{Format, Rows},
+    };
+
+    #[test]
+    fn test_open_writer_requires_epoch_or_fence() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        tokio_uring::start(async {
+            let port = run_listener().await;
+            let frontend = FrontendBuilder::new(&format!("127.0.0.1:{port}")).build()?;
+            let writer = WriterArgs {
+                epoch: None,
+                fence: false,
+            };
+            match open_writer(&frontend, 1, &writer).await {
+                Ok(_) => panic!("Opening a stream for write should require --epoch or --fence"),
+                Err(e) => assert!(e.to_string().contains("--fence")),
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_write_record() {
+        let rows = Rows::new(vec!["offset", "timestamp", "key", "value"]);
+        let record = Record {
+            offset: 3,
+            timestamp: 1000,
+            key: None,
+            value: Some(Bytes::from("hello")),
+            headers: vec![],
+        };
+        let mut out = vec![];
+        write_record(&mut out, &rows, &record, Format::Table).unwrap();
+        write_record(&mut out, &rows, &record, Format::Json).unwrap();
+        assert_eq!(
+            "hello\n{\"offset\":3,\"timestamp\":1000,\"key\":null,\"value\":\"hello\"}\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use bytes::Bytes;
+use frontend::{Frontend, StreamOptions};
+use log::info;
+
+use crate::error::GatewayError;
+
+/// Key prefix of topics in the key-value store of placement driver. Value of a topic is the
+/// comma-separated stream ids of its partitions, in order of partition index.
+const TOPIC_PREFIX: &str = "kafka/topics/";
+
+/// Key prefix of committed offsets, keyed by `{group}/{topic}/{partition}`. Value is the offset,
+/// followed by the metadata of the commit after a line feed, if any.
+const OFFSET_PREFIX: &str = "kafka/offsets/";
+
+/// Committed offset of a partition, along with the metadata of the commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommittedOffset {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub metadata: Option<String>,
+}
+
+/// Catalog maps topic-partitions onto streams and keeps committed offsets of consumer groups, both
+/// in the key-value store of placement driver.
+///
+/// Mappings of topics are cached once loaded, as partitions of a topic never change.
+pub struct Catalog {
+    frontend: Frontend,
+    topics: RefCell<HashMap<String, Vec<u64>>>,
+}
+
+impl Catalog {
+    pub fn new(frontend: Frontend) -> Self {
+        Self {
+            frontend,
+            topics: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Stream ids of partitions of the topic, `None` if the topic does not exist.
+    pub async fn topic(&self, name: &str) -> Result<Option<Vec<u64>>, GatewayError> {
+        if let Some(streams) = self.topics.borrow().get(name) {
+            return Ok(Some(streams.clone()));
+        }
+        let key = Bytes::from(format!("{TOPIC_PREFIX}{name}"));
+        match self.frontend.kv_range(key, None).await?.into_iter().next() {
+            Some((_, value)) => {
+                let streams = parse_streams(&value)?;
+                self.topics
+                    .borrow_mut()
+                    .insert(name.to_owned(), streams.clone());
+                Ok(Some(streams))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Stream id of the partition of the topic.
+    pub async fn partition(&self, topic: &str, partition: i32) -> Result<u64, GatewayError> {
+        self.topic(topic)
+            .await?
+            .and_then(|streams| streams.get(usize::try_from(partition).ok()?).copied())
+            .ok_or_else(|| GatewayError::UnknownTopicOrPartition(format!("{topic}-{partition}")))
+    }
+
+    /// All topics, along with stream ids of their partitions.
+    pub async fn topics(&self) -> Result<Vec<(String, Vec<u64>)>, GatewayError> {
+        let kvs = self
+            .frontend
+            .kv_range(
+                Bytes::from_static(TOPIC_PREFIX.as_bytes()),
+                Some(prefix_end(TOPIC_PREFIX)),
+            )
+            .await?;
+        let mut topics = vec![];
+        for (key, value) in kvs {
+            let name = String::from_utf8_lossy(&key[TOPIC_PREFIX.len()..]).into_owned();
+            topics.push((name, parse_streams(&value)?));
+        }
+        Ok(topics)
+    }
+
+    /// Create the topic with a stream for each of its partitions.
+    ///
+    /// Topics are not created atomically across gateways, so a topic should be created through a
+    /// single gateway.
+    pub async fn create_topic(
+        &self,
+        name: &str,
+        partitions: i32,
+        options: &StreamOptions,
+    ) -> Result<Vec<u64>, GatewayError> {
+        let mut streams = Vec::with_capacity(partitions as usize);
+        for _ in 0..partitions {
+            streams.push(self.frontend.create(options.clone()).await?);
+        }
+        let value = streams
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        self.frontend
+            .kv_put(
+                Bytes::from(format!("{TOPIC_PREFIX}{name}")),
+                Bytes::from(value),
+            )
+            .await?;
+        info!("Created topic {name} on streams {streams:?}");
+        self.topics
+            .borrow_mut()
+            .insert(name.to_owned(), streams.clone());
+        Ok(streams)
+    }
+
+    pub async fn commit_offset(
+        &self,
+        group: &str,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        metadata: Option<&str>,
+    ) -> Result<(), GatewayError> {
+        let key = format!("{OFFSET_PREFIX}{group}/{topic}/{partition}");
+        let value = match metadata {
+            Some(metadata) => format!("{offset}\n{metadata}"),
+            None => offset.to_string(),
+        };
+        self.frontend
+            .kv_put(Bytes::from(key), Bytes::from(value))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn committed_offset(
+        &self,
+        group: &str,
+        topic: &str,
+        partition: i32,
+    ) -> Result<Option<CommittedOffset>, GatewayError> {
+        let key = Bytes::from(format!("{OFFSET_PREFIX}{group}/{topic}/{partition}"));
+        match self.frontend.kv_range(key, None).await?.into_iter().next() {
+            Some((_, value)) => {
+                let (offset, metadata) = parse_offset(&value)?;
+                Ok(Some(CommittedOffset {
+                    topic: topic.to_owned(),
+                    partition,
+                    offset,
+                    metadata,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Committed offsets of all partitions of the group.
+    pub async fn committed_offsets(
+        &self,
+        group: &str,
+    ) -> Result<Vec<CommittedOffset>, GatewayError> {
+        let prefix = format!("{OFFSET_PREFIX}{group}/");
+        let kvs = self
+            .frontend
+            .kv_range(Bytes::from(prefix.clone()), Some(prefix_end(&prefix)))
+            .await?;
+        let mut offsets = vec![];
+        for (key, value) in kvs {
+            let key = String::from_utf8_lossy(&key[OFFSET_PREFIX.len()..]).into_owned();
+            // Topics and partitions never contain slashes, while groups may.
+            let mut parts = key.rsplitn(3, '/');
+            let (Some(partition), Some(topic), Some(key_group)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            if key_group != group {
+                continue;
+            }
+            let Ok(partition) = partition.parse() else {
+                continue;
+            };
+            let (offset, metadata) = parse_offset(&value)?;
+            offsets.push(CommittedOffset {
+                topic: topic.to_owned(),
+                partition,
+                offset,
+                metadata,
+            });
+        }
+        Ok(offsets)
+    }
+}
+
+/// The smallest key greater than all keys with the prefix.
+fn prefix_end(prefix: &str) -> Bytes {
+    let mut end = prefix.as_bytes().to_vec();
+    if let Some(last) = end.last_mut() {
+        *last += 1;
+    }
+    Bytes::from(end)
+}
+
+fn parse_streams(value: &[u8]) -> Result<Vec<u64>, GatewayError> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| {
+            value
+                .split(',')
+                .map(|id| id.trim().parse().ok())
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or_else(|| GatewayError::Malformed("invalid streams of topic".to_owned()))
+}
+
+fn parse_offset(value: &[u8]) -> Result<(i64, Option<String>), GatewayError> {
+    let value = std::str::from_utf8(value)
+        .map_err(|_| GatewayError::Malformed("invalid committed offset".to_owned()))?;
+    let (offset, metadata) = match value.split_once('\n') {
+        Some((offset, metadata)) => (offset, Some(metadata.to_owned())),
+        None => (value, None),
+    };
+    let offset = offset
+        .parse()
+        .map_err(|_| GatewayError::Malformed("invalid committed offset".to_owned()))?;
+    Ok((offset, metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_offset, parse_streams, prefix_end};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(vec![3, 4, 5], parse_streams(b"3,4,5").unwrap());
+        assert!(parse_streams(b"3,x").is_err());
+
+        assert_eq!((42, None), parse_offset(b"42").unwrap());
+        assert_eq!(
+            (42, Some("meta\ndata".to_owned())),
+            parse_offset(b"42\nmeta\ndata").unwrap()
+        );
+        assert!(parse_offset(b"").is_err());
+    }
+
+    #[test]
+    fn test_prefix_end() {
+        assert_eq!(&b"kafka/topics0"[..], &prefix_end("kafka/topics/")[..]);
+    }
+}
@@ -0,0 +1,225 @@
+//! Primitive types of the Kafka wire protocol, excluding flexible versions with compact encodings
+//! and tagged fields.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::error::GatewayError;
+
+/// Reader of primitive types from a request.
+pub struct Decoder {
+    buf: Bytes,
+}
+
+impl Decoder {
+    pub fn new(buf: Bytes) -> Self {
+        Self { buf }
+    }
+
+    fn ensure(&self, len: usize) -> Result<(), GatewayError> {
+        if self.buf.remaining() < len {
+            return Err(GatewayError::Malformed(format!(
+                "expect {len} more bytes, {} remaining",
+                self.buf.remaining()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn i8(&mut self) -> Result<i8, GatewayError> {
+        self.ensure(1)?;
+        Ok(self.buf.get_i8())
+    }
+
+    pub fn i16(&mut self) -> Result<i16, GatewayError> {
+        self.ensure(2)?;
+        Ok(self.buf.get_i16())
+    }
+
+    pub fn i32(&mut self) -> Result<i32, GatewayError> {
+        self.ensure(4)?;
+        Ok(self.buf.get_i32())
+    }
+
+    pub fn i64(&mut self) -> Result<i64, GatewayError> {
+        self.ensure(8)?;
+        Ok(self.buf.get_i64())
+    }
+
+    pub fn bool(&mut self) -> Result<bool, GatewayError> {
+        Ok(self.i8()? != 0)
+    }
+
+    pub fn nullable_string(&mut self) -> Result<Option<String>, GatewayError> {
+        let len = self.i16()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        self.ensure(len as usize)?;
+        let buf = self.buf.split_to(len as usize);
+        String::from_utf8(buf.to_vec())
+            .map(Some)
+            .map_err(|_| GatewayError::Malformed("string is not valid UTF-8".to_owned()))
+    }
+
+    pub fn string(&mut self) -> Result<String, GatewayError> {
+        self.nullable_string()?
+            .ok_or_else(|| GatewayError::Malformed("unexpected null string".to_owned()))
+    }
+
+    pub fn nullable_bytes(&mut self) -> Result<Option<Bytes>, GatewayError> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        self.ensure(len as usize)?;
+        Ok(Some(self.buf.split_to(len as usize)))
+    }
+
+    /// Read an array, `None` if it is null.
+    pub fn nullable_array<T, F>(&mut self, mut f: F) -> Result<Option<Vec<T>>, GatewayError>
+    where
+        F: FnMut(&mut Self) -> Result<T, GatewayError>,
+    {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        // Each element takes at least one byte, which guards against bogus lengths.
+        self.ensure(len as usize)?;
+        (0..len)
+            .map(|_| f(self))
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+
+    pub fn array<T, F>(&mut self, f: F) -> Result<Vec<T>, GatewayError>
+    where
+        F: FnMut(&mut Self) -> Result<T, GatewayError>,
+    {
+        Ok(self.nullable_array(f)?.unwrap_or_default())
+    }
+}
+
+/// Writer of primitive types to a response.
+#[derive(Default)]
+pub struct Encoder {
+    buf: BytesMut,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn i8(&mut self, v: i8) -> &mut Self {
+        self.buf.put_i8(v);
+        self
+    }
+
+    pub fn i16(&mut self, v: i16) -> &mut Self {
+        self.buf.put_i16(v);
+        self
+    }
+
+    pub fn i32(&mut self, v: i32) -> &mut Self {
+        self.buf.put_i32(v);
+        self
+    }
+
+    pub fn i64(&mut self, v: i64) -> &mut Self {
+        self.buf.put_i64(v);
+        self
+    }
+
+    pub fn bool(&mut self, v: bool) -> &mut Self {
+        self.i8(v as i8)
+    }
+
+    pub fn string(&mut self, v: &str) -> &mut Self {
+        self.buf.put_i16(v.len() as i16);
+        self.buf.extend_from_slice(v.as_bytes());
+        self
+    }
+
+    pub fn nullable_string(&mut self, v: Option<&str>) -> &mut Self {
+        match v {
+            Some(v) => self.string(v),
+            None => self.i16(-1),
+        }
+    }
+
+    pub fn nullable_bytes(&mut self, v: Option<&[u8]>) -> &mut Self {
+        match v {
+            Some(v) => {
+                self.buf.put_i32(v.len() as i32);
+                self.buf.extend_from_slice(v);
+                self
+            }
+            None => self.i32(-1),
+        }
+    }
+
+    pub fn array<T, F>(&mut self, items: &[T], mut f: F) -> &mut Self
+    where
+        F: FnMut(&mut Self, &T),
+    {
+        self.buf.put_i32(items.len() as i32);
+        for item in items {
+            f(self, item);
+        }
+        self
+    }
+
+    pub fn finish(self) -> Bytes {
+        self.buf.freeze()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{Decoder, Encoder};
+
+    #[test]
+    fn test_round_trip() {
+        let mut encoder = Encoder::new();
+        encoder
+            .i8(1)
+            .i16(2)
+            .i32(3)
+            .i64(4)
+            .bool(true)
+            .string("topic")
+            .nullable_string(None)
+            .nullable_bytes(Some(b"records"))
+            .nullable_bytes(None)
+            .array(&[5, 6], |e, v| {
+                e.i32(*v);
+            });
+        let mut decoder = Decoder::new(encoder.finish());
+        assert_eq!(1, decoder.i8().unwrap());
+        assert_eq!(2, decoder.i16().unwrap());
+        assert_eq!(3, decoder.i32().unwrap());
+        assert_eq!(4, decoder.i64().unwrap());
+        assert!(decoder.bool().unwrap());
+        assert_eq!("topic", decoder.string().unwrap());
+        assert_eq!(None, decoder.nullable_string().unwrap());
+        assert_eq!(
+            Some(Bytes::from_static(b"records")),
+            decoder.nullable_bytes().unwrap()
+        );
+        assert_eq!(None, decoder.nullable_bytes().unwrap());
+        assert_eq!(vec![5, 6], decoder.array(|d| d.i32()).unwrap());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut decoder = Decoder::new(Bytes::from_static(&[0, 5, b'a']));
+        assert!(decoder.string().is_err());
+
+        // Array length beyond remaining bytes.
+        let mut decoder = Decoder::new(Bytes::from_static(&[0x7f, 0, 0, 0]));
+        assert!(decoder.array(|d| d.i8()).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use model::error::EsError;
+use thiserror::Error;
+
+use crate::messages::error_code;
+
+#[derive(Debug, Error)]
+pub enum GatewayError {
+    #[error("Malformed request: {0}")]
+    Malformed(String),
+
+    #[error("Unknown topic or partition: {0}")]
+    UnknownTopicOrPartition(String),
+
+    #[error("Invalid record batch: {0}")]
+    InvalidRecords(String),
+
+    #[error("Offset {0} is out of range")]
+    OffsetOutOfRange(i64),
+
+    #[error("An IO error raised")]
+    Io(#[from] std::io::Error),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] EsError),
+}
+
+impl GatewayError {
+    /// Error code of the Kafka protocol reported to clients.
+    pub fn code(&self) -> i16 {
+        match self {
+            GatewayError::Malformed(_) => error_code::INVALID_REQUEST,
+            GatewayError::UnknownTopicOrPartition(_) => error_code::UNKNOWN_TOPIC_OR_PARTITION,
+            GatewayError::InvalidRecords(_) => error_code::CORRUPT_MESSAGE,
+            GatewayError::OffsetOutOfRange(_) => error_code::OFFSET_OUT_OF_RANGE,
+            GatewayError::Io(_) | GatewayError::Storage(_) => error_code::KAFKA_STORAGE_ERROR,
+        }
+    }
+}
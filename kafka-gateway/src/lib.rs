@@ -0,0 +1,15 @@
+//! Gateway speaking the core Kafka wire protocol on top of streams.
+//!
+//! Each partition of a topic is backed by a stream, and mappings of topics onto streams, as well as
+//! committed offsets of consumer groups, are kept in the key-value store of placement driver. The
+//! gateway is the only broker advertised to clients, which leads all partitions.
+
+pub mod catalog;
+pub mod codec;
+pub mod error;
+pub mod messages;
+pub mod partition;
+pub mod server;
+
+pub use crate::error::GatewayError;
+pub use crate::server::{Gateway, GatewayConfig};
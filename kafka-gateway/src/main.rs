@@ -0,0 +1,100 @@
+use std::{error::Error, rc::Rc, time::Duration};
+
+use clap::Parser;
+use frontend::{FrontendBuilder, StreamOptions};
+use kafka_gateway::{Gateway, GatewayConfig};
+use tokio::net::TcpListener;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Kafka wire-protocol gateway on top of streams", long_about = None)]
+struct Args {
+    /// Access point of placement driver
+    #[arg(long, env = "ES_PD", default_value = "127.0.0.1:12378")]
+    pd: String,
+
+    /// Address to listen for Kafka clients
+    #[arg(long, default_value = "0.0.0.0:9092")]
+    listen: String,
+
+    /// Host advertised to Kafka clients
+    #[arg(long, default_value = "127.0.0.1")]
+    advertised_host: String,
+
+    /// Port advertised to Kafka clients, defaults to the listening port
+    #[arg(long)]
+    advertised_port: Option<u16>,
+
+    /// Node id of the gateway
+    #[arg(long, default_value_t = 0)]
+    node_id: i32,
+
+    /// Cluster id reported to Kafka clients
+    #[arg(long, default_value = "elastic-stream")]
+    cluster_id: String,
+
+    /// Do not create topics that do not exist on metadata requests
+    #[arg(long)]
+    no_auto_create_topics: bool,
+
+    /// Number of partitions of auto-created topics
+    #[arg(long, default_value_t = 1)]
+    partitions: i32,
+
+    /// Replica count of streams of auto-created topics
+    #[arg(long, default_value_t = 1)]
+    replica: u8,
+
+    /// Ack count of streams of auto-created topics
+    #[arg(long, default_value_t = 1)]
+    ack: u8,
+
+    /// Retention of streams of auto-created topics, in hours
+    #[arg(long, default_value_t = 168)]
+    retention_hours: u64,
+
+    /// Configuration file of the client
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Prefix of environment variables overriding configuration of the client
+    #[arg(long)]
+    env_prefix: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    frontend::init_log();
+
+    let mut builder = FrontendBuilder::new(&args.pd);
+    if let Some(path) = &args.config {
+        builder = builder.with_config_file(path);
+    }
+    if let Some(prefix) = &args.env_prefix {
+        builder = builder.with_env_prefix(prefix);
+    }
+    let frontend = builder.build()?;
+
+    tokio_uring::start(async move {
+        let listener = TcpListener::bind(&args.listen).await?;
+        let port = listener.local_addr()?.port();
+        let config = GatewayConfig {
+            advertised_host: args.advertised_host,
+            advertised_port: i32::from(args.advertised_port.unwrap_or(port)),
+            node_id: args.node_id,
+            cluster_id: args.cluster_id,
+            auto_create_topics: !args.no_auto_create_topics,
+            partitions: args.partitions,
+            stream_options: StreamOptions {
+                replica: args.replica,
+                ack: args.ack,
+                retention: Duration::from_secs(args.retention_hours * 3600),
+                compaction: None,
+                rollover: None,
+            },
+        };
+        Rc::new(Gateway::new(frontend, config))
+            .serve(listener)
+            .await?;
+        Ok::<_, Box<dyn Error>>(())
+    })
+}
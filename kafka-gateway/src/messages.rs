@@ -0,0 +1,552 @@
+//! Requests and responses of the supported Kafka APIs.
+//!
+//! Only versions before flexible versions are supported. Fields are documented by the Kafka
+//! protocol guide, so only their presence per version is annotated here.
+
+use bytes::Bytes;
+
+use crate::{
+    codec::{Decoder, Encoder},
+    error::GatewayError,
+};
+
+pub mod api_key {
+    pub const PRODUCE: i16 = 0;
+    pub const FETCH: i16 = 1;
+    pub const LIST_OFFSETS: i16 = 2;
+    pub const METADATA: i16 = 3;
+    pub const OFFSET_COMMIT: i16 = 8;
+    pub const OFFSET_FETCH: i16 = 9;
+    pub const FIND_COORDINATOR: i16 = 10;
+    pub const API_VERSIONS: i16 = 18;
+}
+
+pub mod error_code {
+    pub const UNKNOWN_SERVER_ERROR: i16 = -1;
+    pub const NONE: i16 = 0;
+    pub const OFFSET_OUT_OF_RANGE: i16 = 1;
+    pub const CORRUPT_MESSAGE: i16 = 2;
+    pub const UNKNOWN_TOPIC_OR_PARTITION: i16 = 3;
+    pub const COORDINATOR_NOT_AVAILABLE: i16 = 15;
+    pub const UNSUPPORTED_VERSION: i16 = 35;
+    pub const INVALID_REQUEST: i16 = 42;
+    pub const UNSUPPORTED_FOR_MESSAGE_FORMAT: i16 = 43;
+    pub const KAFKA_STORAGE_ERROR: i16 = 56;
+}
+
+/// Supported versions of each API, in `(api_key, min_version, max_version)`.
+pub const SUPPORTED_APIS: &[(i16, i16, i16)] = &[
+    (api_key::PRODUCE, 3, 7),
+    (api_key::FETCH, 4, 6),
+    (api_key::LIST_OFFSETS, 1, 3),
+    (api_key::METADATA, 0, 5),
+    (api_key::OFFSET_COMMIT, 2, 4),
+    (api_key::OFFSET_FETCH, 1, 4),
+    (api_key::FIND_COORDINATOR, 0, 2),
+    (api_key::API_VERSIONS, 0, 2),
+];
+
+pub fn is_supported(api_key: i16, api_version: i16) -> bool {
+    SUPPORTED_APIS
+        .iter()
+        .any(|(key, min, max)| *key == api_key && (*min..=*max).contains(&api_version))
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestHeader {
+    pub api_key: i16,
+    pub api_version: i16,
+    pub correlation_id: i32,
+    pub client_id: Option<String>,
+}
+
+impl RequestHeader {
+    /// Decode request header v1. Tagged fields of header v2 of flexible versions are left in the
+    /// decoder, as such requests are rejected anyway.
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, GatewayError> {
+        Ok(Self {
+            api_key: decoder.i16()?,
+            api_version: decoder.i16()?,
+            correlation_id: decoder.i32()?,
+            client_id: decoder.nullable_string()?,
+        })
+    }
+}
+
+/// Response of `ApiVersions`, which is also returned for requests of unsupported versions.
+#[derive(Debug)]
+pub struct ApiVersionsResponse {
+    pub error_code: i16,
+}
+
+impl ApiVersionsResponse {
+    pub fn encode(&self, version: i16, encoder: &mut Encoder) {
+        encoder
+            .i16(self.error_code)
+            .array(SUPPORTED_APIS, |e, (key, min, max)| {
+                e.i16(*key).i16(*min).i16(*max);
+            });
+        if version >= 1 {
+            encoder.i32(0);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MetadataRequest {
+    /// `None` for all topics.
+    pub topics: Option<Vec<String>>,
+    pub allow_auto_topic_creation: bool,
+}
+
+impl MetadataRequest {
+    pub fn decode(version: i16, decoder: &mut Decoder) -> Result<Self, GatewayError> {
+        let mut topics = decoder.nullable_array(|d| d.string())?;
+        if version == 0 && topics.as_ref().map_or(false, Vec::is_empty) {
+            // An empty list means all topics in version 0.
+            topics = None;
+        }
+        let allow_auto_topic_creation = if version >= 4 { decoder.bool()? } else { true };
+        Ok(Self {
+            topics,
+            allow_auto_topic_creation,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Broker {
+    pub node_id: i32,
+    pub host: String,
+    pub port: i32,
+}
+
+#[derive(Debug)]
+pub struct TopicMetadata {
+    pub error_code: i16,
+    pub name: String,
+    pub partitions: i32,
+}
+
+#[derive(Debug)]
+pub struct MetadataResponse {
+    pub broker: Broker,
+    pub cluster_id: String,
+    pub topics: Vec<TopicMetadata>,
+}
+
+impl MetadataResponse {
+    /// The gateway is the only broker, which leads all partitions.
+    pub fn encode(&self, version: i16, encoder: &mut Encoder) {
+        if version >= 3 {
+            encoder.i32(0);
+        }
+        encoder.array(std::slice::from_ref(&self.broker), |e, broker| {
+            e.i32(broker.node_id).string(&broker.host).i32(broker.port);
+            if version >= 1 {
+                e.nullable_string(None);
+            }
+        });
+        if version >= 2 {
+            encoder.nullable_string(Some(&self.cluster_id));
+        }
+        if version >= 1 {
+            encoder.i32(self.broker.node_id);
+        }
+        let node_id = self.broker.node_id;
+        encoder.array(&self.topics, |e, topic| {
+            e.i16(topic.error_code).string(&topic.name);
+            if version >= 1 {
+                e.bool(false);
+            }
+            let partitions = (0..topic.partitions).collect::<Vec<_>>();
+            e.array(&partitions, |e, index| {
+                e.i16(error_code::NONE).i32(*index).i32(node_id);
+                e.array(&[node_id], |e, id| {
+                    e.i32(*id);
+                });
+                e.array(&[node_id], |e, id| {
+                    e.i32(*id);
+                });
+                if version >= 5 {
+                    e.array(&[] as &[i32], |e, id| {
+                        e.i32(*id);
+                    });
+                }
+            });
+        });
+    }
+}
+
+#[derive(Debug)]
+pub struct ProduceRequest {
+    pub transactional_id: Option<String>,
+    pub acks: i16,
+    pub timeout_ms: i32,
+    pub topics: Vec<(String, Vec<(i32, Option<Bytes>)>)>,
+}
+
+impl ProduceRequest {
+    pub fn decode(_version: i16, decoder: &mut Decoder) -> Result<Self, GatewayError> {
+        Ok(Self {
+            transactional_id: decoder.nullable_string()?,
+            acks: decoder.i16()?,
+            timeout_ms: decoder.i32()?,
+            topics: decoder.array(|d| {
+                Ok((
+                    d.string()?,
+                    d.array(|d| Ok((d.i32()?, d.nullable_bytes()?)))?,
+                ))
+            })?,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ProducePartitionResponse {
+    pub index: i32,
+    pub error_code: i16,
+    pub base_offset: i64,
+    pub log_start_offset: i64,
+}
+
+#[derive(Debug)]
+pub struct ProduceResponse {
+    pub topics: Vec<(String, Vec<ProducePartitionResponse>)>,
+}
+
+impl ProduceResponse {
+    pub fn encode(&self, version: i16, encoder: &mut Encoder) {
+        encoder.array(&self.topics, |e, (name, partitions)| {
+            e.string(name).array(partitions, |e, p| {
+                // Log append time is -1, as records keep their create time.
+                e.i32(p.index).i16(p.error_code).i64(p.base_offset).i64(-1);
+                if version >= 5 {
+                    e.i64(p.log_start_offset);
+                }
+            });
+        });
+        encoder.i32(0);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchPartition {
+    pub partition: i32,
+    pub fetch_offset: i64,
+    pub partition_max_bytes: i32,
+}
+
+#[derive(Debug)]
+pub struct FetchRequest {
+    pub max_wait_ms: i32,
+    pub min_bytes: i32,
+    pub max_bytes: i32,
+    pub isolation_level: i8,
+    pub topics: Vec<(String, Vec<FetchPartition>)>,
+}
+
+impl FetchRequest {
+    pub fn decode(version: i16, decoder: &mut Decoder) -> Result<Self, GatewayError> {
+        let _replica_id = decoder.i32()?;
+        let max_wait_ms = decoder.i32()?;
+        let min_bytes = decoder.i32()?;
+        let max_bytes = decoder.i32()?;
+        let isolation_level = decoder.i8()?;
+        let topics = decoder.array(|d| {
+            Ok((
+                d.string()?,
+                d.array(|d| {
+                    let partition = d.i32()?;
+                    let fetch_offset = d.i64()?;
+                    if version >= 5 {
+                        let _log_start_offset = d.i64()?;
+                    }
+                    Ok(FetchPartition {
+                        partition,
+                        fetch_offset,
+                        partition_max_bytes: d.i32()?,
+                    })
+                })?,
+            ))
+        })?;
+        Ok(Self {
+            max_wait_ms,
+            min_bytes,
+            max_bytes,
+            isolation_level,
+            topics,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FetchPartitionResponse {
+    pub partition: i32,
+    pub error_code: i16,
+    pub high_watermark: i64,
+    pub log_start_offset: i64,
+    pub records: Option<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct FetchResponse {
+    pub topics: Vec<(String, Vec<FetchPartitionResponse>)>,
+}
+
+impl FetchResponse {
+    pub fn encode(&self, version: i16, encoder: &mut Encoder) {
+        encoder.i32(0);
+        encoder.array(&self.topics, |e, (name, partitions)| {
+            e.string(name).array(partitions, |e, p| {
+                // Last stable offset is the high watermark, as there are no transactions.
+                e.i32(p.partition)
+                    .i16(p.error_code)
+                    .i64(p.high_watermark)
+                    .i64(p.high_watermark);
+                if version >= 5 {
+                    e.i64(p.log_start_offset);
+                }
+                e.i32(-1).nullable_bytes(p.records.as_deref());
+            });
+        });
+    }
+}
+
+#[derive(Debug)]
+pub struct ListOffsetsRequest {
+    pub topics: Vec<(String, Vec<(i32, i64)>)>,
+}
+
+impl ListOffsetsRequest {
+    /// Timestamp of a partition to list its latest offset.
+    pub const LATEST: i64 = -1;
+
+    /// Timestamp of a partition to list its earliest offset.
+    pub const EARLIEST: i64 = -2;
+
+    pub fn decode(version: i16, decoder: &mut Decoder) -> Result<Self, GatewayError> {
+        let _replica_id = decoder.i32()?;
+        if version >= 2 {
+            let _isolation_level = decoder.i8()?;
+        }
+        Ok(Self {
+            topics: decoder.array(|d| Ok((d.string()?, d.array(|d| Ok((d.i32()?, d.i64()?)))?)))?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ListOffsetsPartitionResponse {
+    pub partition: i32,
+    pub error_code: i16,
+    pub offset: i64,
+}
+
+#[derive(Debug)]
+pub struct ListOffsetsResponse {
+    pub topics: Vec<(String, Vec<ListOffsetsPartitionResponse>)>,
+}
+
+impl ListOffsetsResponse {
+    pub fn encode(&self, version: i16, encoder: &mut Encoder) {
+        if version >= 2 {
+            encoder.i32(0);
+        }
+        encoder.array(&self.topics, |e, (name, partitions)| {
+            e.string(name).array(partitions, |e, p| {
+                e.i32(p.partition).i16(p.error_code).i64(-1).i64(p.offset);
+            });
+        });
+    }
+}
+
+#[derive(Debug)]
+pub struct FindCoordinatorRequest {
+    pub key: String,
+}
+
+impl FindCoordinatorRequest {
+    pub fn decode(version: i16, decoder: &mut Decoder) -> Result<Self, GatewayError> {
+        let key = decoder.string()?;
+        if version >= 1 {
+            let _key_type = decoder.i8()?;
+        }
+        Ok(Self { key })
+    }
+}
+
+#[derive(Debug)]
+pub struct FindCoordinatorResponse {
+    pub broker: Broker,
+}
+
+impl FindCoordinatorResponse {
+    pub fn encode(&self, version: i16, encoder: &mut Encoder) {
+        if version >= 1 {
+            encoder.i32(0);
+        }
+        encoder.i16(error_code::NONE);
+        if version >= 1 {
+            encoder.nullable_string(None);
+        }
+        encoder
+            .i32(self.broker.node_id)
+            .string(&self.broker.host)
+            .i32(self.broker.port);
+    }
+}
+
+#[derive(Debug)]
+pub struct OffsetCommitRequest {
+    pub group_id: String,
+    pub topics: Vec<(String, Vec<(i32, i64, Option<String>)>)>,
+}
+
+impl OffsetCommitRequest {
+    pub fn decode(_version: i16, decoder: &mut Decoder) -> Result<Self, GatewayError> {
+        let group_id = decoder.string()?;
+        let _generation_id = decoder.i32()?;
+        let _member_id = decoder.string()?;
+        let _retention_time_ms = decoder.i64()?;
+        let topics = decoder.array(|d| {
+            Ok((
+                d.string()?,
+                d.array(|d| Ok((d.i32()?, d.i64()?, d.nullable_string()?)))?,
+            ))
+        })?;
+        Ok(Self { group_id, topics })
+    }
+}
+
+#[derive(Debug)]
+pub struct OffsetCommitResponse {
+    pub topics: Vec<(String, Vec<(i32, i16)>)>,
+}
+
+impl OffsetCommitResponse {
+    pub fn encode(&self, version: i16, encoder: &mut Encoder) {
+        if version >= 3 {
+            encoder.i32(0);
+        }
+        encoder.array(&self.topics, |e, (name, partitions)| {
+            e.string(name).array(partitions, |e, (index, error_code)| {
+                e.i32(*index).i16(*error_code);
+            });
+        });
+    }
+}
+
+#[derive(Debug)]
+pub struct OffsetFetchRequest {
+    pub group_id: String,
+    /// `None` for all committed partitions of the group.
+    pub topics: Option<Vec<(String, Vec<i32>)>>,
+}
+
+impl OffsetFetchRequest {
+    pub fn decode(_version: i16, decoder: &mut Decoder) -> Result<Self, GatewayError> {
+        Ok(Self {
+            group_id: decoder.string()?,
+            topics: decoder.nullable_array(|d| Ok((d.string()?, d.array(|d| d.i32())?)))?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OffsetFetchPartitionResponse {
+    pub partition: i32,
+    /// -1 if there is no committed offset.
+    pub offset: i64,
+    pub metadata: Option<String>,
+    pub error_code: i16,
+}
+
+#[derive(Debug)]
+pub struct OffsetFetchResponse {
+    pub topics: Vec<(String, Vec<OffsetFetchPartitionResponse>)>,
+    pub error_code: i16,
+}
+
+impl OffsetFetchResponse {
+    pub fn encode(&self, version: i16, encoder: &mut Encoder) {
+        if version >= 3 {
+            encoder.i32(0);
+        }
+        encoder.array(&self.topics, |e, (name, partitions)| {
+            e.string(name).array(partitions, |e, p| {
+                e.i32(p.partition)
+                    .i64(p.offset)
+                    .nullable_string(p.metadata.as_deref())
+                    .i16(p.error_code);
+            });
+        });
+        if version >= 2 {
+            encoder.i16(self.error_code);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{api_key, is_supported, MetadataRequest, ProduceRequest, RequestHeader};
+    use crate::codec::{Decoder, Encoder};
+
+    #[test]
+    fn test_is_supported() {
+        assert!(is_supported(api_key::API_VERSIONS, 0));
+        assert!(is_supported(api_key::PRODUCE, 7));
+        assert!(!is_supported(api_key::PRODUCE, 9));
+        assert!(!is_supported(api_key::FETCH, 3));
+        assert!(!is_supported(22, 0));
+    }
+
+    #[test]
+    fn test_decode_produce() {
+        let mut encoder = Encoder::new();
+        encoder
+            .i16(api_key::PRODUCE)
+            .i16(3)
+            .i32(7)
+            .nullable_string(Some("client"))
+            .nullable_string(None)
+            .i16(1)
+            .i32(30000)
+            .array(&["topic"], |e, name| {
+                e.string(name).array(&[0], |e, index| {
+                    e.i32(*index).nullable_bytes(Some(b"batch"));
+                });
+            });
+        let mut decoder = Decoder::new(encoder.finish());
+        let header = RequestHeader::decode(&mut decoder).unwrap();
+        assert_eq!(7, header.correlation_id);
+        assert_eq!(Some("client".to_owned()), header.client_id);
+
+        let request = ProduceRequest::decode(header.api_version, &mut decoder).unwrap();
+        assert_eq!(None, request.transactional_id);
+        assert_eq!(1, request.acks);
+        assert_eq!("topic", request.topics[0].0);
+        assert_eq!(
+            vec![(0, Some(Bytes::from_static(b"batch")))],
+            request.topics[0].1
+        );
+    }
+
+    #[test]
+    fn test_decode_metadata() {
+        let mut encoder = Encoder::new();
+        encoder.array(&[] as &[&str], |e, name| {
+            e.string(name);
+        });
+        let request = MetadataRequest::decode(0, &mut Decoder::new(encoder.finish())).unwrap();
+        assert!(request.topics.is_none());
+
+        let mut encoder = Encoder::new();
+        encoder.i32(-1).bool(false);
+        let request = MetadataRequest::decode(4, &mut Decoder::new(encoder.finish())).unwrap();
+        assert!(request.topics.is_none());
+        assert!(!request.allow_auto_topic_creation);
+    }
+}
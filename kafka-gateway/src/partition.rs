@@ -0,0 +1,335 @@
+//! Partitions are backed by streams, each Kafka record batch of a partition being the payload of a
+//! record batch of its stream.
+//!
+//! Kafka record batches are stored as is, so that fetched batches keep their CRC, compression and
+//! records. Offsets of records in a stream are contiguous like those of a partition, so only the
+//! base offset of a Kafka record batch, which is not covered by CRC, is rewritten on fetch.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use frontend::{Frontend, OpenOptions, Stream};
+use log::{info, warn};
+use model::{
+    record::flat_record::{FlatRecordBatch, RecordMagic},
+    RecordBatch,
+};
+use tokio::sync::Mutex;
+
+use crate::error::GatewayError;
+
+/// Size of the header of a Kafka record batch, with magic 2.
+const BATCH_HEADER_SIZE: usize = 61;
+
+/// Size of `baseOffset` and `batchLength`, which are not counted in `batchLength`.
+const BATCH_LOG_OVERHEAD: usize = 12;
+
+const MAGIC_OFFSET: usize = 16;
+const ATTRIBUTES_OFFSET: usize = 21;
+const LAST_OFFSET_DELTA_OFFSET: usize = 23;
+const BASE_TIMESTAMP_OFFSET: usize = 27;
+
+const KAFKA_MAGIC: i8 = 2;
+
+/// Bit of attributes for transactional batches.
+const TRANSACTIONAL_FLAG: i16 = 0x10;
+
+/// Log offsets of a partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Offsets {
+    pub log_start_offset: i64,
+    pub high_watermark: i64,
+}
+
+/// Records fetched from a partition.
+#[derive(Debug)]
+pub struct Fetched {
+    pub offsets: Offsets,
+    /// Kafka record batches, with base offsets assigned by the stream.
+    pub records: Bytes,
+}
+
+/// Open streams of partitions served by this gateway.
+///
+/// A stream is opened with an epoch greater than the current one on first use, fencing previous
+/// gateways serving the partition.
+pub struct Partitions {
+    frontend: Frontend,
+    streams: RefCell<HashMap<u64, Rc<Stream>>>,
+
+    /// Serializes opening streams, so that a stream is opened once even if requested concurrently.
+    opening: Mutex<()>,
+}
+
+impl Partitions {
+    pub fn new(frontend: Frontend) -> Self {
+        Self {
+            frontend,
+            streams: RefCell::new(HashMap::new()),
+            opening: Mutex::new(()),
+        }
+    }
+
+    async fn stream(&self, stream_id: u64) -> Result<Rc<Stream>, GatewayError> {
+        if let Some(stream) = self.streams.borrow().get(&stream_id) {
+            return Ok(Rc::clone(stream));
+        }
+        let _guard = self.opening.lock().await;
+        if let Some(stream) = self.streams.borrow().get(&stream_id) {
+            return Ok(Rc::clone(stream));
+        }
+        let epoch = self.frontend.describe(stream_id).await?.epoch + 1;
+        let stream = Rc::new(
+            self.frontend
+                .open(stream_id, OpenOptions::with_epoch(epoch))
+                .await?,
+        );
+        info!("Opened stream[id={stream_id}] of partition with epoch {epoch}");
+        self.streams
+            .borrow_mut()
+            .insert(stream_id, Rc::clone(&stream));
+        Ok(stream)
+    }
+
+    /// Evict the stream after a failure, so that it is opened again on next use.
+    fn evict(&self, stream_id: u64, e: &GatewayError) {
+        if let GatewayError::Storage(e) = e {
+            warn!("Evict stream[id={stream_id}] of partition: {e}");
+            self.streams.borrow_mut().remove(&stream_id);
+        }
+    }
+
+    pub async fn offsets(&self, stream_id: u64) -> Result<Offsets, GatewayError> {
+        let result = async {
+            let stream = self.stream(stream_id).await?;
+            Ok(Offsets {
+                log_start_offset: stream.start_offset().await?,
+                high_watermark: stream.next_offset().await?,
+            })
+        }
+        .await;
+        if let Err(e) = &result {
+            self.evict(stream_id, e);
+        }
+        result
+    }
+
+    /// Append Kafka record batches to the stream of the partition.
+    ///
+    /// # Returns
+    /// Base offset of the first record batch.
+    pub async fn produce(&self, stream_id: u64, records: Bytes) -> Result<i64, GatewayError> {
+        let batches = split_batches(records)?;
+        let result = async {
+            let stream = self.stream(stream_id).await?;
+            let mut base_offset = None;
+            for batch in batches {
+                let result = stream.append(to_record_batch(stream_id, batch)?).await?;
+                base_offset.get_or_insert(result.base_offset);
+            }
+            base_offset.ok_or_else(|| GatewayError::InvalidRecords("no record batch".to_owned()))
+        }
+        .await;
+        if let Err(e) = &result {
+            self.evict(stream_id, e);
+        }
+        result
+    }
+
+    /// Fetch Kafka record batches from `offset` of the stream of the partition.
+    ///
+    /// At least one record batch is fetched if available, even if it is larger than `max_bytes`.
+    pub async fn fetch(
+        &self,
+        stream_id: u64,
+        offset: i64,
+        max_bytes: i32,
+    ) -> Result<Fetched, GatewayError> {
+        let result = async {
+            let stream = self.stream(stream_id).await?;
+            let offsets = Offsets {
+                log_start_offset: stream.start_offset().await?,
+                high_watermark: stream.next_offset().await?,
+            };
+            if offset < offsets.log_start_offset || offset > offsets.high_watermark {
+                return Err(GatewayError::OffsetOutOfRange(offset));
+            }
+            let records = if offset == offsets.high_watermark {
+                Bytes::new()
+            } else {
+                let buffers = stream
+                    .read(offset, offsets.high_watermark, max_bytes.max(1))
+                    .await?;
+                to_kafka_batches(buffers, max_bytes.max(0) as usize)?
+            };
+            Ok(Fetched { offsets, records })
+        }
+        .await;
+        if let Err(e) = &result {
+            self.evict(stream_id, e);
+        }
+        result
+    }
+}
+
+/// Split records of a produce request into Kafka record batches.
+fn split_batches(mut records: Bytes) -> Result<Vec<Bytes>, GatewayError> {
+    let mut batches = vec![];
+    while records.has_remaining() {
+        if records.len() < BATCH_HEADER_SIZE {
+            return Err(GatewayError::InvalidRecords(format!(
+                "truncated batch header of {} bytes",
+                records.len()
+            )));
+        }
+        let len = (&records[8..12]).get_i32();
+        let size = BATCH_LOG_OVERHEAD + len.max(0) as usize;
+        if size < BATCH_HEADER_SIZE || size > records.len() {
+            return Err(GatewayError::InvalidRecords(format!(
+                "invalid batch length {len}"
+            )));
+        }
+        let batch = records.split_to(size);
+        if batch[MAGIC_OFFSET] as i8 != KAFKA_MAGIC {
+            return Err(GatewayError::InvalidRecords(format!(
+                "unsupported magic {}",
+                batch[MAGIC_OFFSET] as i8
+            )));
+        }
+        if (&batch[ATTRIBUTES_OFFSET..]).get_i16() & TRANSACTIONAL_FLAG != 0 {
+            return Err(GatewayError::InvalidRecords(
+                "transactional batch is not supported".to_owned(),
+            ));
+        }
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+/// Wrap a Kafka record batch into an encoded record batch of the stream.
+fn to_record_batch(stream_id: u64, batch: Bytes) -> Result<Bytes, GatewayError> {
+    let count = (&batch[LAST_OFFSET_DELTA_OFFSET..]).get_i32() + 1;
+    let base_timestamp = (&batch[BASE_TIMESTAMP_OFFSET..]).get_i64();
+    let record_batch = RecordBatch::new_builder()
+        .with_magic(RecordMagic::Magic0)
+        .with_stream_id(stream_id as i64)
+        .with_range_index(0)
+        .with_base_offset(0)
+        .with_last_offset_delta(count)
+        .with_base_timestamp(base_timestamp)
+        .with_payload(batch)
+        .build()
+        .map_err(|e| GatewayError::InvalidRecords(format!("{e:?}")))?;
+    let (buffers, _) = FlatRecordBatch::from(record_batch).encode();
+    Ok(Bytes::from(buffers.concat()))
+}
+
+/// Unwrap Kafka record batches from encoded record batches read from the stream, assigning their
+/// base offsets.
+fn to_kafka_batches(buffers: Vec<Bytes>, max_bytes: usize) -> Result<Bytes, GatewayError> {
+    let mut records = BytesMut::new();
+    for mut buf in buffers {
+        while buf.has_remaining() {
+            let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf)
+                .map_err(|e| GatewayError::InvalidRecords(format!("{e:?}")))?;
+            let payload = record_batch.payload();
+            if payload.len() < BATCH_HEADER_SIZE {
+                return Err(GatewayError::InvalidRecords(format!(
+                    "record batch at {} is not a Kafka record batch",
+                    record_batch.base_offset()
+                )));
+            }
+            if !records.is_empty() && records.len() + payload.len() > max_bytes {
+                return Ok(records.freeze());
+            }
+            records.put_i64(record_batch.base_offset());
+            records.extend_from_slice(&payload[8..]);
+        }
+    }
+    Ok(records.freeze())
+}
+
+/// A Kafka record batch of `count` records, with opaque bytes as records.
+#[cfg(test)]
+pub(crate) fn kafka_batch(count: i32, records: &[u8]) -> Bytes {
+    let mut buf = BytesMut::new();
+    buf.put_i64(0);
+    buf.put_i32((BATCH_HEADER_SIZE - 12 + records.len()) as i32);
+    buf.put_i32(-1);
+    buf.put_i8(2);
+    buf.put_u32(0xdead_beef);
+    buf.put_i16(0);
+    buf.put_i32(count - 1);
+    buf.put_i64(1000);
+    buf.put_i64(1000);
+    buf.put_i64(-1);
+    buf.put_i16(-1);
+    buf.put_i32(-1);
+    buf.put_i32(count);
+    buf.extend_from_slice(records);
+    buf.freeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Buf, Bytes};
+    use model::record::flat_record::FlatRecordBatch;
+
+    use super::{kafka_batch, split_batches, to_kafka_batches, to_record_batch};
+
+    #[test]
+    fn test_split_batches() {
+        let first = kafka_batch(2, b"ab");
+        let second = kafka_batch(1, b"c");
+        let records = Bytes::from([&first[..], &second[..]].concat());
+        assert_eq!(vec![first.clone(), second], split_batches(records).unwrap());
+
+        assert!(split_batches(first.slice(..first.len() - 1)).is_err());
+
+        let mut v1 = first.to_vec();
+        v1[16] = 1;
+        assert!(split_batches(Bytes::from(v1)).is_err());
+
+        let mut transactional = first.to_vec();
+        transactional[22] = 0x10;
+        assert!(split_batches(Bytes::from(transactional)).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let first = kafka_batch(2, b"ab");
+        let second = kafka_batch(3, b"cde");
+
+        // Assign offsets as appending to a stream does.
+        let mut buffers = vec![];
+        for (base_offset, batch) in [(10, &first), (12, &second)] {
+            let mut buf = to_record_batch(1, batch.clone()).unwrap();
+            let mut record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf).unwrap();
+            let count = record_batch.last_offset_delta();
+            assert_eq!((&batch[23..]).get_i32() as usize + 1, count);
+            record_batch = model::RecordBatch::new_builder()
+                .with_magic(record_batch.magic())
+                .with_stream_id(1)
+                .with_range_index(0)
+                .with_base_offset(base_offset)
+                .with_last_offset_delta(count as i32)
+                .with_payload(record_batch.payload())
+                .build()
+                .unwrap();
+            let (pieces, _) = FlatRecordBatch::from(record_batch).encode();
+            buffers.push(Bytes::from(pieces.concat()));
+        }
+
+        let records = to_kafka_batches(buffers.clone(), usize::MAX).unwrap();
+        let batches = split_batches(records).unwrap();
+        assert_eq!(2, batches.len());
+        assert_eq!(10, (&batches[0][..]).get_i64());
+        assert_eq!(12, (&batches[1][..]).get_i64());
+        assert_eq!(first[8..], batches[0][8..]);
+        assert_eq!(second[8..], batches[1][8..]);
+
+        // The first batch is always fetched, even if it exceeds max bytes.
+        let records = to_kafka_batches(buffers, 1).unwrap();
+        assert_eq!(1, split_batches(records).unwrap().len());
+    }
+}
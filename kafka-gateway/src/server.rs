@@ -0,0 +1,756 @@
+use std::{collections::BTreeMap, rc::Rc, time::Duration};
+
+use bytes::Bytes;
+use frontend::{Frontend, StreamOptions};
+use log::{debug, error, info, warn};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::Instant,
+};
+
+use crate::{
+    catalog::Catalog,
+    codec::{Decoder, Encoder},
+    error::GatewayError,
+    messages::{
+        api_key, error_code, is_supported, ApiVersionsResponse, Broker, FetchPartitionResponse,
+        FetchRequest, FetchResponse, FindCoordinatorRequest, FindCoordinatorResponse,
+        ListOffsetsPartitionResponse, ListOffsetsRequest, ListOffsetsResponse, MetadataRequest,
+        MetadataResponse, OffsetCommitRequest, OffsetCommitResponse, OffsetFetchPartitionResponse,
+        OffsetFetchRequest, OffsetFetchResponse, ProducePartitionResponse, ProduceRequest,
+        ProduceResponse, RequestHeader, TopicMetadata,
+    },
+    partition::Partitions,
+};
+
+/// Max size of a request, beyond which the connection is closed.
+const MAX_REQUEST_SIZE: usize = 100 * 1024 * 1024;
+
+/// Interval of polling partitions while a fetch request waits for `min_bytes`.
+const FETCH_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone)]
+pub struct GatewayConfig {
+    /// Host advertised to clients in metadata, which clients connect to.
+    pub advertised_host: String,
+
+    pub advertised_port: i32,
+
+    /// Node id of the gateway, which leads all partitions.
+    pub node_id: i32,
+
+    pub cluster_id: String,
+
+    /// Whether to create topics that clients request metadata of but do not exist.
+    pub auto_create_topics: bool,
+
+    /// Number of partitions of auto-created topics.
+    pub partitions: i32,
+
+    /// Options of streams backing partitions of auto-created topics.
+    pub stream_options: StreamOptions,
+}
+
+/// Gateway serves the Kafka protocol on a thread, mapping topic-partitions onto streams.
+pub struct Gateway {
+    config: GatewayConfig,
+    catalog: Catalog,
+    partitions: Partitions,
+}
+
+impl Gateway {
+    pub fn new(frontend: Frontend, config: GatewayConfig) -> Self {
+        Self {
+            config,
+            catalog: Catalog::new(frontend.clone()),
+            partitions: Partitions::new(frontend),
+        }
+    }
+
+    /// Serve connections accepted by the listener, each on a task of the current thread.
+    pub async fn serve(self: Rc<Self>, listener: TcpListener) -> Result<(), GatewayError> {
+        info!("Kafka gateway is listening {}", listener.local_addr()?);
+        loop {
+            let (stream, remote_addr) = listener.accept().await?;
+            debug!("Accepted a connection from {remote_addr}");
+            let gateway = Rc::clone(&self);
+            tokio_uring::spawn(async move {
+                if let Err(e) = gateway.serve_connection(stream).await {
+                    warn!("Close connection from {remote_addr}: {e}");
+                }
+            });
+        }
+    }
+
+    /// Requests of a connection are served one at a time, as Kafka clients expect responses in
+    /// order of requests.
+    async fn serve_connection(&self, mut stream: TcpStream) -> Result<(), GatewayError> {
+        loop {
+            let size = match stream.read_i32().await {
+                Ok(size) => size,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+            if size < 0 || size as usize > MAX_REQUEST_SIZE {
+                return Err(GatewayError::Malformed(format!("request size {size}")));
+            }
+            let mut buf = vec![0; size as usize];
+            stream.read_exact(&mut buf).await?;
+
+            let mut decoder = Decoder::new(Bytes::from(buf));
+            let header = RequestHeader::decode(&mut decoder)?;
+            let Some(body) = self.handle(&header, &mut decoder).await? else {
+                continue;
+            };
+            stream.write_i32(body.len() as i32 + 4).await?;
+            stream.write_i32(header.correlation_id).await?;
+            stream.write_all(&body).await?;
+        }
+    }
+
+    /// Handle a request, returning body of the response, or `None` if there is no response.
+    async fn handle(
+        &self,
+        header: &RequestHeader,
+        decoder: &mut Decoder,
+    ) -> Result<Option<Bytes>, GatewayError> {
+        let version = header.api_version;
+        let mut encoder = Encoder::new();
+        if !is_supported(header.api_key, version) {
+            if header.api_key == api_key::API_VERSIONS {
+                // Clients retry with a supported version, as advised by a response of version 0.
+                ApiVersionsResponse {
+                    error_code: error_code::UNSUPPORTED_VERSION,
+                }
+                .encode(0, &mut encoder);
+                return Ok(Some(encoder.finish()));
+            }
+            return Err(GatewayError::Malformed(format!(
+                "unsupported version {version} of API {}",
+                header.api_key
+            )));
+        }
+
+        match header.api_key {
+            api_key::API_VERSIONS => ApiVersionsResponse {
+                error_code: error_code::NONE,
+            }
+            .encode(version, &mut encoder),
+            api_key::METADATA => {
+                let request = MetadataRequest::decode(version, decoder)?;
+                self.metadata(request).await?.encode(version, &mut encoder);
+            }
+            api_key::PRODUCE => {
+                let request = ProduceRequest::decode(version, decoder)?;
+                let acks = request.acks;
+                let response = self.produce(request).await;
+                if acks == 0 {
+                    return Ok(None);
+                }
+                response.encode(version, &mut encoder);
+            }
+            api_key::FETCH => {
+                let request = FetchRequest::decode(version, decoder)?;
+                self.fetch(request).await.encode(version, &mut encoder);
+            }
+            api_key::LIST_OFFSETS => {
+                let request = ListOffsetsRequest::decode(version, decoder)?;
+                self.list_offsets(request)
+                    .await
+                    .encode(version, &mut encoder);
+            }
+            api_key::FIND_COORDINATOR => {
+                let _request = FindCoordinatorRequest::decode(version, decoder)?;
+                FindCoordinatorResponse {
+                    broker: self.broker(),
+                }
+                .encode(version, &mut encoder);
+            }
+            api_key::OFFSET_COMMIT => {
+                let request = OffsetCommitRequest::decode(version, decoder)?;
+                self.offset_commit(request)
+                    .await
+                    .encode(version, &mut encoder);
+            }
+            api_key::OFFSET_FETCH => {
+                let request = OffsetFetchRequest::decode(version, decoder)?;
+                self.offset_fetch(request)
+                    .await
+                    .encode(version, &mut encoder);
+            }
+            _ => unreachable!("API {} is supported", header.api_key),
+        }
+        Ok(Some(encoder.finish()))
+    }
+
+    fn broker(&self) -> Broker {
+        Broker {
+            node_id: self.config.node_id,
+            host: self.config.advertised_host.clone(),
+            port: self.config.advertised_port,
+        }
+    }
+
+    async fn metadata(&self, request: MetadataRequest) -> Result<MetadataResponse, GatewayError> {
+        let topics = match request.topics {
+            None => self
+                .catalog
+                .topics()
+                .await?
+                .into_iter()
+                .map(|(name, streams)| TopicMetadata {
+                    error_code: error_code::NONE,
+                    name,
+                    partitions: streams.len() as i32,
+                })
+                .collect(),
+            Some(names) => {
+                let mut topics = Vec::with_capacity(names.len());
+                for name in names {
+                    let streams = match self.catalog.topic(&name).await? {
+                        Some(streams) => Some(streams),
+                        None if request.allow_auto_topic_creation
+                            && self.config.auto_create_topics =>
+                        {
+                            let streams = self
+                                .catalog
+                                .create_topic(
+                                    &name,
+                                    self.config.partitions,
+                                    &self.config.stream_options,
+                                )
+                                .await;
+                            match streams {
+                                Ok(streams) => Some(streams),
+                                Err(e) => {
+                                    error!("Failed to create topic {name}: {e}");
+                                    None
+                                }
+                            }
+                        }
+                        None => None,
+                    };
+                    topics.push(match streams {
+                        Some(streams) => TopicMetadata {
+                            error_code: error_code::NONE,
+                            name,
+                            partitions: streams.len() as i32,
+                        },
+                        None => TopicMetadata {
+                            error_code: error_code::UNKNOWN_TOPIC_OR_PARTITION,
+                            name,
+                            partitions: 0,
+                        },
+                    });
+                }
+                topics
+            }
+        };
+        Ok(MetadataResponse {
+            broker: self.broker(),
+            cluster_id: self.config.cluster_id.clone(),
+            topics,
+        })
+    }
+
+    async fn produce(&self, request: ProduceRequest) -> ProduceResponse {
+        let mut topics = Vec::with_capacity(request.topics.len());
+        for (name, partitions) in request.topics {
+            let mut responses = Vec::with_capacity(partitions.len());
+            for (index, records) in partitions {
+                let mut response = ProducePartitionResponse {
+                    index,
+                    error_code: error_code::NONE,
+                    base_offset: -1,
+                    log_start_offset: -1,
+                };
+                if request.transactional_id.is_some() {
+                    response.error_code = error_code::INVALID_REQUEST;
+                    responses.push(response);
+                    continue;
+                }
+                let result = async {
+                    let stream_id = self.catalog.partition(&name, index).await?;
+                    let records = records
+                        .ok_or_else(|| GatewayError::InvalidRecords("null records".to_owned()))?;
+                    self.partitions.produce(stream_id, records).await
+                }
+                .await;
+                match result {
+                    Ok(base_offset) => response.base_offset = base_offset,
+                    Err(e) => {
+                        warn!("Failed to produce to {name}-{index}: {e}");
+                        response.error_code = e.code();
+                    }
+                }
+                responses.push(response);
+            }
+            topics.push((name, responses));
+        }
+        ProduceResponse { topics }
+    }
+
+    /// Fetch partitions until `min_bytes` are fetched, `max_wait_ms` elapses, or any partition fails.
+    async fn fetch(&self, request: FetchRequest) -> FetchResponse {
+        let deadline = Instant::now() + Duration::from_millis(request.max_wait_ms.max(0) as u64);
+        loop {
+            let (response, bytes, failed) = self.fetch_once(&request).await;
+            let now = Instant::now();
+            if failed || bytes >= request.min_bytes.max(1) as usize || now >= deadline {
+                return response;
+            }
+            tokio::time::sleep(FETCH_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    /// Fetch partitions once, returning the response, the number of bytes fetched and whether any
+    /// partition failed.
+    async fn fetch_once(&self, request: &FetchRequest) -> (FetchResponse, usize, bool) {
+        let mut remaining = request.max_bytes.max(0) as usize;
+        let mut bytes = 0;
+        let mut failed = false;
+        let mut topics = Vec::with_capacity(request.topics.len());
+        for (name, partitions) in &request.topics {
+            let mut responses = Vec::with_capacity(partitions.len());
+            for partition in partitions {
+                let mut response = FetchPartitionResponse {
+                    partition: partition.partition,
+                    high_watermark: -1,
+                    log_start_offset: -1,
+                    ..Default::default()
+                };
+                let result = async {
+                    let stream_id = self.catalog.partition(name, partition.partition).await?;
+                    if bytes > 0 && remaining == 0 {
+                        // Offsets only, as the response is full.
+                        let offsets = self.partitions.offsets(stream_id).await?;
+                        return Ok((offsets, Bytes::new()));
+                    }
+                    let max_bytes = (partition.partition_max_bytes.max(0) as usize).min(remaining);
+                    let fetched = self
+                        .partitions
+                        .fetch(stream_id, partition.fetch_offset, max_bytes as i32)
+                        .await?;
+                    Ok::<_, GatewayError>((fetched.offsets, fetched.records))
+                }
+                .await;
+                match result {
+                    Ok((offsets, records)) => {
+                        response.high_watermark = offsets.high_watermark;
+                        response.log_start_offset = offsets.log_start_offset;
+                        bytes += records.len();
+                        remaining = remaining.saturating_sub(records.len());
+                        response.records = Some(records);
+                    }
+                    Err(e) => {
+                        warn!("Failed to fetch {name}-{}: {e}", partition.partition);
+                        response.error_code = e.code();
+                        failed = true;
+                    }
+                }
+                responses.push(response);
+            }
+            topics.push((name.clone(), responses));
+        }
+        (FetchResponse { topics }, bytes, failed)
+    }
+
+    async fn list_offsets(&self, request: ListOffsetsRequest) -> ListOffsetsResponse {
+        let mut topics = Vec::with_capacity(request.topics.len());
+        for (name, partitions) in request.topics {
+            let mut responses = Vec::with_capacity(partitions.len());
+            for (partition, timestamp) in partitions {
+                let mut response = ListOffsetsPartitionResponse {
+                    partition,
+                    error_code: error_code::NONE,
+                    offset: -1,
+                };
+                if timestamp != ListOffsetsRequest::LATEST
+                    && timestamp != ListOffsetsRequest::EARLIEST
+                {
+                    // Records are not indexed by timestamp.
+                    response.error_code = error_code::INVALID_REQUEST;
+                    responses.push(response);
+                    continue;
+                }
+                let result = async {
+                    let stream_id = self.catalog.partition(&name, partition).await?;
+                    self.partitions.offsets(stream_id).await
+                }
+                .await;
+                match result {
+                    Ok(offsets) if timestamp == ListOffsetsRequest::LATEST => {
+                        response.offset = offsets.high_watermark
+                    }
+                    Ok(offsets) => response.offset = offsets.log_start_offset,
+                    Err(e) => {
+                        warn!("Failed to list offsets of {name}-{partition}: {e}");
+                        response.error_code = e.code();
+                    }
+                }
+                responses.push(response);
+            }
+            topics.push((name, responses));
+        }
+        ListOffsetsResponse { topics }
+    }
+
+    async fn offset_commit(&self, request: OffsetCommitRequest) -> OffsetCommitResponse {
+        let group = request.group_id;
+        let mut topics = Vec::with_capacity(request.topics.len());
+        for (name, partitions) in request.topics {
+            let mut responses = Vec::with_capacity(partitions.len());
+            for (partition, offset, metadata) in partitions {
+                let code = match self
+                    .catalog
+                    .commit_offset(&group, &name, partition, offset, metadata.as_deref())
+                    .await
+                {
+                    Ok(()) => error_code::NONE,
+                    Err(e) => {
+                        warn!("Failed to commit offset of {name}-{partition} for {group}: {e}");
+                        // Clients retry on coordinator errors.
+                        error_code::COORDINATOR_NOT_AVAILABLE
+                    }
+                };
+                responses.push((partition, code));
+            }
+            topics.push((name, responses));
+        }
+        OffsetCommitResponse { topics }
+    }
+
+    async fn offset_fetch(&self, request: OffsetFetchRequest) -> OffsetFetchResponse {
+        let group = request.group_id;
+        let result = async {
+            let mut topics: BTreeMap<String, Vec<OffsetFetchPartitionResponse>> = BTreeMap::new();
+            match request.topics {
+                None => {
+                    for committed in self.catalog.committed_offsets(&group).await? {
+                        topics.entry(committed.topic).or_default().push(
+                            OffsetFetchPartitionResponse {
+                                partition: committed.partition,
+                                offset: committed.offset,
+                                metadata: committed.metadata,
+                                error_code: error_code::NONE,
+                            },
+                        );
+                    }
+                }
+                Some(requested) => {
+                    for (name, partitions) in requested {
+                        let mut responses = Vec::with_capacity(partitions.len());
+                        for partition in partitions {
+                            let committed = self
+                                .catalog
+                                .committed_offset(&group, &name, partition)
+                                .await?;
+                            responses.push(OffsetFetchPartitionResponse {
+                                partition,
+                                offset: committed.as_ref().map_or(-1, |c| c.offset),
+                                metadata: committed.and_then(|c| c.metadata),
+                                error_code: error_code::NONE,
+                            });
+                        }
+                        topics.insert(name, responses);
+                    }
+                }
+            }
+            Ok::<_, GatewayError>(topics)
+        }
+        .await;
+        match result {
+            Ok(topics) => OffsetFetchResponse {
+                topics: topics.into_iter().collect(),
+                error_code: error_code::NONE,
+            },
+            Err(e) => {
+                warn!("Failed to fetch offsets of {group}: {e}");
+                OffsetFetchResponse {
+                    topics: vec![],
+                    error_code: error_code::COORDINATOR_NOT_AVAILABLE,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, rc::Rc, time::Duration};
+
+    use bytes::{Buf, Bytes};
+    use frontend::{FrontendBuilder, StreamOptions};
+    use range_server::standalone::Standalone;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::{Gateway, GatewayConfig};
+    use crate::{
+        codec::{Decoder, Encoder},
+        messages::{api_key, error_code, ListOffsetsRequest},
+        partition::kafka_batch,
+    };
+
+    /// Send a request with header v1 and return body of its response.
+    async fn request(
+        stream: &mut TcpStream,
+        api_key: i16,
+        api_version: i16,
+        correlation_id: i32,
+        body: Bytes,
+    ) -> Decoder {
+        let mut encoder = Encoder::new();
+        encoder
+            .i16(api_key)
+            .i16(api_version)
+            .i32(correlation_id)
+            .nullable_string(Some("test"));
+        let header = encoder.finish();
+        stream
+            .write_i32((header.len() + body.len()) as i32)
+            .await
+            .unwrap();
+        stream.write_all(&header).await.unwrap();
+        stream.write_all(&body).await.unwrap();
+
+        let size = stream.read_i32().await.unwrap();
+        let mut buf = vec![0; size as usize];
+        stream.read_exact(&mut buf).await.unwrap();
+        let mut decoder = Decoder::new(Bytes::from(buf));
+        assert_eq!(correlation_id, decoder.i32().unwrap());
+        decoder
+    }
+
+    #[test]
+    fn test_gateway() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        let store_dir = tempfile::tempdir()?;
+        let standalone = Standalone::start_for_test(store_dir.path())?;
+        let placement_driver = standalone.placement_driver().to_owned();
+        let result = tokio_uring::start(async move {
+            let frontend = FrontendBuilder::new(&placement_driver).build()?;
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let addr = listener.local_addr()?;
+            let config = GatewayConfig {
+                advertised_host: "127.0.0.1".to_owned(),
+                advertised_port: i32::from(addr.port()),
+                node_id: 1,
+                cluster_id: "test".to_owned(),
+                auto_create_topics: true,
+                partitions: 2,
+                stream_options: StreamOptions {
+                    replica: 1,
+                    ack: 1,
+                    retention: Duration::from_secs(3600),
+                    compaction: None,
+                    rollover: None,
+                },
+            };
+            tokio_uring::spawn(Rc::new(Gateway::new(frontend, config)).serve(listener));
+            let mut stream = TcpStream::connect(addr).await?;
+
+            // Unsupported versions of ApiVersions are answered with version 0.
+            let mut response =
+                request(&mut stream, api_key::API_VERSIONS, 3, 1, Bytes::new()).await;
+            assert_eq!(error_code::UNSUPPORTED_VERSION, response.i16()?);
+            let mut response =
+                request(&mut stream, api_key::API_VERSIONS, 2, 2, Bytes::new()).await;
+            assert_eq!(error_code::NONE, response.i16()?);
+            let apis = response.array(|d| Ok((d.i16()?, d.i16()?, d.i16()?)))?;
+            assert!(apis.iter().any(|(key, _, _)| *key == api_key::FETCH));
+
+            // Metadata creates the topic with partitions onto streams.
+            let mut encoder = Encoder::new();
+            encoder
+                .array(&["orders"], |e, name| {
+                    e.string(name);
+                })
+                .bool(true);
+            let mut response =
+                request(&mut stream, api_key::METADATA, 4, 3, encoder.finish()).await;
+            let _throttle_time_ms = response.i32()?;
+            let brokers = response.array(|d| {
+                let broker = (d.i32()?, d.string()?, d.i32()?);
+                let _rack = d.nullable_string()?;
+                Ok(broker)
+            })?;
+            assert_eq!(
+                vec![(1, "127.0.0.1".to_owned(), i32::from(addr.port()))],
+                brokers
+            );
+            assert_eq!(Some("test".to_owned()), response.nullable_string()?);
+            assert_eq!(1, response.i32()?);
+            let topics = response.array(|d| {
+                let topic = (d.i16()?, d.string()?);
+                let _internal = d.bool()?;
+                let partitions = d.array(|d| {
+                    let partition = (d.i16()?, d.i32()?, d.i32()?);
+                    let _replicas = d.array(|d| d.i32())?;
+                    let _isr = d.array(|d| d.i32())?;
+                    Ok(partition)
+                })?;
+                Ok((topic, partitions))
+            })?;
+            assert_eq!(
+                vec![(
+                    (error_code::NONE, "orders".to_owned()),
+                    vec![(error_code::NONE, 0, 1), (error_code::NONE, 1, 1)]
+                )],
+                topics
+            );
+
+            // Commit and fetch offsets of a group.
+            let mut encoder = Encoder::new();
+            encoder
+                .string("group")
+                .i32(-1)
+                .string("")
+                .i64(-1)
+                .array(&["orders"], |e, name| {
+                    e.string(name).array(&[(1, 42)], |e, (partition, offset)| {
+                        e.i32(*partition).i64(*offset).nullable_string(Some("meta"));
+                    });
+                });
+            let mut response =
+                request(&mut stream, api_key::OFFSET_COMMIT, 2, 4, encoder.finish()).await;
+            let committed =
+                response.array(|d| Ok((d.string()?, d.array(|d| Ok((d.i32()?, d.i16()?)))?)))?;
+            assert_eq!(
+                vec![("orders".to_owned(), vec![(1, error_code::NONE)])],
+                committed
+            );
+
+            let mut encoder = Encoder::new();
+            encoder.string("group").array(&["orders"], |e, name| {
+                e.string(name).array(&[0, 1], |e, partition| {
+                    e.i32(*partition);
+                });
+            });
+            let mut response =
+                request(&mut stream, api_key::OFFSET_FETCH, 2, 5, encoder.finish()).await;
+            let offsets = response.array(|d| {
+                Ok((
+                    d.string()?,
+                    d.array(|d| {
+                        let offset = (d.i32()?, d.i64()?, d.nullable_string()?);
+                        let _error_code = d.i16()?;
+                        Ok(offset)
+                    })?,
+                ))
+            })?;
+            assert_eq!(
+                vec![(
+                    "orders".to_owned(),
+                    vec![(0, -1, None), (1, 42, Some("meta".to_owned()))]
+                )],
+                offsets
+            );
+            assert_eq!(error_code::NONE, response.i16()?);
+
+            // Produce a record batch to a partition.
+            let batch = kafka_batch(2, b"ab");
+            let mut encoder = Encoder::new();
+            encoder
+                .nullable_string(None)
+                .i16(1)
+                .i32(1000)
+                .array(&["orders"], |e, name| {
+                    e.string(name).array(&[0], |e, partition| {
+                        e.i32(*partition).nullable_bytes(Some(&batch[..]));
+                    });
+                });
+            let mut response = request(&mut stream, api_key::PRODUCE, 3, 6, encoder.finish()).await;
+            let produced = response.array(|d| {
+                Ok((
+                    d.string()?,
+                    d.array(|d| {
+                        let partition = (d.i32()?, d.i16()?, d.i64()?);
+                        let _log_append_time = d.i64()?;
+                        Ok(partition)
+                    })?,
+                ))
+            })?;
+            assert_eq!(
+                vec![("orders".to_owned(), vec![(0, error_code::NONE, 0)])],
+                produced
+            );
+            let _throttle_time_ms = response.i32()?;
+
+            // Fetch the record batch back, as produced except its base offset.
+            let mut encoder = Encoder::new();
+            encoder
+                .i32(-1)
+                .i32(100)
+                .i32(1)
+                .i32(1024 * 1024)
+                .i8(0)
+                .array(&["orders"], |e, name| {
+                    e.string(name).array(&[0], |e, partition| {
+                        e.i32(*partition).i64(0).i32(1024 * 1024);
+                    });
+                });
+            let mut response = request(&mut stream, api_key::FETCH, 4, 7, encoder.finish()).await;
+            let _throttle_time_ms = response.i32()?;
+            let mut fetched = response.array(|d| {
+                Ok((
+                    d.string()?,
+                    d.array(|d| {
+                        let partition = (d.i32()?, d.i16()?, d.i64()?);
+                        let _last_stable_offset = d.i64()?;
+                        let _aborted_transactions = d.i32()?;
+                        Ok((partition, d.nullable_bytes()?))
+                    })?,
+                ))
+            })?;
+            assert_eq!(1, fetched.len());
+            let (name, mut partitions) = fetched.remove(0);
+            assert_eq!("orders", name);
+            assert_eq!(1, partitions.len());
+            let (partition, records) = partitions.remove(0);
+            assert_eq!((0, error_code::NONE, 2), partition);
+            let records = records.expect("Records should be fetched");
+            assert_eq!(0, (&records[..]).get_i64());
+            assert_eq!(batch[8..], records[8..]);
+
+            // List the latest and the earliest offsets of the partition.
+            let mut encoder = Encoder::new();
+            encoder.i32(-1).array(&["orders"], |e, name| {
+                e.string(name).array(
+                    &[
+                        (0, ListOffsetsRequest::LATEST),
+                        (0, ListOffsetsRequest::EARLIEST),
+                    ],
+                    |e, (partition, timestamp)| {
+                        e.i32(*partition).i64(*timestamp);
+                    },
+                );
+            });
+            let mut response =
+                request(&mut stream, api_key::LIST_OFFSETS, 1, 8, encoder.finish()).await;
+            let listed = response.array(|d| {
+                Ok((
+                    d.string()?,
+                    d.array(|d| {
+                        let partition = (d.i32()?, d.i16()?);
+                        let _timestamp = d.i64()?;
+                        Ok((partition.0, partition.1, d.i64()?))
+                    })?,
+                ))
+            })?;
+            assert_eq!(
+                vec![(
+                    "orders".to_owned(),
+                    vec![(0, error_code::NONE, 2), (0, error_code::NONE, 0)]
+                )],
+                listed
+            );
+            Ok::<_, Box<dyn Error>>(())
+        });
+        standalone.shutdown();
+        result
+    }
+}
@@ -507,3 +507,21 @@ func (b *mockHandler) WatchResource(req *protocol.WatchResourceRequest, resp *pr
 		b.f(req, resp)
 	}
 }
+
+func (b *mockHandler) KVRange(req *protocol.KVRangeRequest, resp *protocol.KVRangeResponse) {
+	if b.f != nil {
+		b.f(req, resp)
+	}
+}
+
+func (b *mockHandler) KVPut(req *protocol.KVPutRequest, resp *protocol.KVPutResponse) {
+	if b.f != nil {
+		b.f(req, resp)
+	}
+}
+
+func (b *mockHandler) KVDeleteRange(req *protocol.KVDeleteRangeRequest, resp *protocol.KVDeleteRangeResponse) {
+	if b.f != nil {
+		b.f(req, resp)
+	}
+}
@@ -480,3 +480,69 @@ func (wr *WatchResourceRequest) Timeout() int32 {
 func (wr *WatchResourceRequest) LongPoll() bool {
 	return true
 }
+
+// KVRangeRequest is a request to rpcfb.OperationCodeKV_RANGE
+type KVRangeRequest struct {
+	baseRequest
+	baseUnmarshaler
+	nonLongPollRequest
+
+	rpcfb.RangeRequestT
+}
+
+func (kr *KVRangeRequest) unmarshalFlatBuffer(data []byte) error {
+	kr.RangeRequestT = *rpcfb.GetRootAsRangeRequest(data, 0).UnPack()
+	return nil
+}
+
+func (kr *KVRangeRequest) Unmarshal(fmt codec.Format, data []byte) error {
+	return unmarshal(kr, fmt, data)
+}
+
+func (kr *KVRangeRequest) Timeout() int32 {
+	return kr.TimeoutMs
+}
+
+// KVPutRequest is a request to rpcfb.OperationCodeKV_PUT
+type KVPutRequest struct {
+	baseRequest
+	baseUnmarshaler
+	nonLongPollRequest
+
+	rpcfb.PutRequestT
+}
+
+func (kp *KVPutRequest) unmarshalFlatBuffer(data []byte) error {
+	kp.PutRequestT = *rpcfb.GetRootAsPutRequest(data, 0).UnPack()
+	return nil
+}
+
+func (kp *KVPutRequest) Unmarshal(fmt codec.Format, data []byte) error {
+	return unmarshal(kp, fmt, data)
+}
+
+func (kp *KVPutRequest) Timeout() int32 {
+	return kp.TimeoutMs
+}
+
+// KVDeleteRangeRequest is a request to rpcfb.OperationCodeKV_DELETE_RANGE
+type KVDeleteRangeRequest struct {
+	baseRequest
+	baseUnmarshaler
+	nonLongPollRequest
+
+	rpcfb.DeleteRangeRequestT
+}
+
+func (kd *KVDeleteRangeRequest) unmarshalFlatBuffer(data []byte) error {
+	kd.DeleteRangeRequestT = *rpcfb.GetRootAsDeleteRangeRequest(data, 0).UnPack()
+	return nil
+}
+
+func (kd *KVDeleteRangeRequest) Unmarshal(fmt codec.Format, data []byte) error {
+	return unmarshal(kd, fmt, data)
+}
+
+func (kd *KVDeleteRangeRequest) Timeout() int32 {
+	return kd.TimeoutMs
+}
@@ -33,6 +33,9 @@ var _inRequests = []packableInRequest{
 	&TrimStreamRequest{},
 	&HeartbeatRequest{},
 	&IDAllocationRequest{},
+	&KVDeleteRangeRequest{},
+	&KVPutRequest{},
+	&KVRangeRequest{},
 	&ListRangeRequest{},
 	&ListResourceRequest{},
 	&ReportMetricsRequest{},
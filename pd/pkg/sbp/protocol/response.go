@@ -534,3 +534,75 @@ func (wr *WatchResourceResponse) Error(status *rpcfb.StatusT) {
 func (wr *WatchResourceResponse) OK() {
 	wr.Status = &rpcfb.StatusT{Code: rpcfb.ErrorCodeOK}
 }
+
+// KVRangeResponse is a response to rpcfb.OperationCodeKV_RANGE
+type KVRangeResponse struct {
+	baseMarshaller
+	singleResponse
+
+	rpcfb.RangeResponseT
+}
+
+func (kr *KVRangeResponse) marshalFlatBuffer() ([]byte, error) {
+	return fbutil.Marshal(&kr.RangeResponseT), nil
+}
+
+func (kr *KVRangeResponse) Marshal(fmt codec.Format) ([]byte, error) {
+	return marshal(kr, fmt)
+}
+
+func (kr *KVRangeResponse) Error(status *rpcfb.StatusT) {
+	kr.Status = status
+}
+
+func (kr *KVRangeResponse) OK() {
+	kr.Status = &rpcfb.StatusT{Code: rpcfb.ErrorCodeOK}
+}
+
+// KVPutResponse is a response to rpcfb.OperationCodeKV_PUT
+type KVPutResponse struct {
+	baseMarshaller
+	singleResponse
+
+	rpcfb.PutResponseT
+}
+
+func (kp *KVPutResponse) marshalFlatBuffer() ([]byte, error) {
+	return fbutil.Marshal(&kp.PutResponseT), nil
+}
+
+func (kp *KVPutResponse) Marshal(fmt codec.Format) ([]byte, error) {
+	return marshal(kp, fmt)
+}
+
+func (kp *KVPutResponse) Error(status *rpcfb.StatusT) {
+	kp.Status = status
+}
+
+func (kp *KVPutResponse) OK() {
+	kp.Status = &rpcfb.StatusT{Code: rpcfb.ErrorCodeOK}
+}
+
+// KVDeleteRangeResponse is a response to rpcfb.OperationCodeKV_DELETE_RANGE
+type KVDeleteRangeResponse struct {
+	baseMarshaller
+	singleResponse
+
+	rpcfb.DeleteRangeResponseT
+}
+
+func (kd *KVDeleteRangeResponse) marshalFlatBuffer() ([]byte, error) {
+	return fbutil.Marshal(&kd.DeleteRangeResponseT), nil
+}
+
+func (kd *KVDeleteRangeResponse) Marshal(fmt codec.Format) ([]byte, error) {
+	return marshal(kd, fmt)
+}
+
+func (kd *KVDeleteRangeResponse) Error(status *rpcfb.StatusT) {
+	kd.Status = status
+}
+
+func (kd *KVDeleteRangeResponse) OK() {
+	kd.Status = &rpcfb.StatusT{Code: rpcfb.ErrorCodeOK}
+}
@@ -43,6 +43,9 @@ var _outResponses = []packableOutResponse{
 	&TrimStreamResponse{},
 	&HeartbeatResponse{},
 	&IDAllocationResponse{},
+	&KVDeleteRangeResponse{},
+	&KVPutResponse{},
+	&KVRangeResponse{},
 	&ListRangeResponse{},
 	&ListResourceResponse{},
 	&ReportMetricsResponse{},
@@ -37,6 +37,12 @@ type Handler interface {
 	ListResource(req *protocol.ListResourceRequest, resp *protocol.ListResourceResponse)
 	// WatchResource watches resources.
 	WatchResource(req *protocol.WatchResourceRequest, resp *protocol.WatchResourceResponse)
+	// KVRange gets key-value pairs of a key or a range of keys.
+	KVRange(req *protocol.KVRangeRequest, resp *protocol.KVRangeResponse)
+	// KVPut puts a key-value pair.
+	KVPut(req *protocol.KVPutRequest, resp *protocol.KVPutResponse)
+	// KVDeleteRange deletes a key or a range of keys.
+	KVDeleteRange(req *protocol.KVDeleteRangeRequest, resp *protocol.KVDeleteRangeResponse)
 }
 
 var (
@@ -146,6 +152,27 @@ var (
 				handler.WatchResource(req.(*protocol.WatchResourceRequest), resp.(*protocol.WatchResourceResponse))
 			},
 		},
+		rpcfb.OperationCodeKV_RANGE: {
+			newReq:  func() protocol.InRequest { return &protocol.KVRangeRequest{} },
+			newResp: func() protocol.OutResponse { return &protocol.KVRangeResponse{} },
+			act: func(handler Handler, req protocol.InRequest, resp protocol.OutResponse) {
+				handler.KVRange(req.(*protocol.KVRangeRequest), resp.(*protocol.KVRangeResponse))
+			},
+		},
+		rpcfb.OperationCodeKV_PUT: {
+			newReq:  func() protocol.InRequest { return &protocol.KVPutRequest{} },
+			newResp: func() protocol.OutResponse { return &protocol.KVPutResponse{} },
+			act: func(handler Handler, req protocol.InRequest, resp protocol.OutResponse) {
+				handler.KVPut(req.(*protocol.KVPutRequest), resp.(*protocol.KVPutResponse))
+			},
+		},
+		rpcfb.OperationCodeKV_DELETE_RANGE: {
+			newReq:  func() protocol.InRequest { return &protocol.KVDeleteRangeRequest{} },
+			newResp: func() protocol.OutResponse { return &protocol.KVDeleteRangeResponse{} },
+			act: func(handler Handler, req protocol.InRequest, resp protocol.OutResponse) {
+				handler.KVDeleteRange(req.(*protocol.KVDeleteRangeRequest), resp.(*protocol.KVDeleteRangeResponse))
+			},
+		},
 	}
 	_unsupportedAction = Action{
 		newReq:  func() protocol.InRequest { return &protocol.EmptyRequest{} },
@@ -219,5 +219,7 @@ func (c *Cache) RangesOnServer(sID int32) (rangeIDs mapset.Set[model.RangeID]) {
 func isRangeServerEqual(a, b rpcfb.RangeServerT) bool {
 	return a.ServerId == b.ServerId &&
 		a.AdvertiseAddr == b.AdvertiseAddr &&
-		a.State == b.State
+		a.State == b.State &&
+		a.Zone == b.Zone &&
+		a.UnixAddr == b.UnixAddr
 }
@@ -0,0 +1,107 @@
+package cluster
+
+import (
+	"context"
+
+	"github.com/pkg/errors"
+	"go.uber.org/zap"
+
+	"github.com/AutoMQ/pd/pkg/server/model"
+	"github.com/AutoMQ/pd/pkg/server/storage/kv"
+	traceutil "github.com/AutoMQ/pd/pkg/util/trace"
+)
+
+type KVService interface {
+	// GetKVs returns the user key-value pair of the key if rangeEnd is empty, or those in [key, rangeEnd) otherwise.
+	// It returns model.ErrPDNotLeader if the current PD node is not the leader.
+	// It returns model.ErrKVInvalidKey if the key is empty.
+	GetKVs(ctx context.Context, key, rangeEnd []byte, limit int64) (kvs []kv.KeyValue, more bool, err error)
+	// PutKV sets the value of the user key.
+	// It returns model.ErrPDNotLeader if the current PD node is not the leader.
+	// It returns model.ErrKVInvalidKey if the key is empty.
+	PutKV(ctx context.Context, key, value []byte) error
+	// CompareAndPutKV sets the value of the user key if it holds the expected value, or does not exist if
+	// expected is nil.
+	// It returns model.ErrPDNotLeader if the current PD node is not the leader.
+	// It returns model.ErrKVInvalidKey if the key is empty.
+	// It returns model.ErrKVCompareFailed if the key does not hold the expected value.
+	CompareAndPutKV(ctx context.Context, key, expected, value []byte) error
+	// DeleteKVs deletes the user key if rangeEnd is empty, or those in [key, rangeEnd) otherwise.
+	// It returns model.ErrPDNotLeader if the current PD node is not the leader.
+	// It returns model.ErrKVInvalidKey if the key is empty.
+	DeleteKVs(ctx context.Context, key, rangeEnd []byte) (deleted int64, err error)
+}
+
+func (c *RaftCluster) GetKVs(ctx context.Context, key, rangeEnd []byte, limit int64) ([]kv.KeyValue, bool, error) {
+	logger := c.lg.With(zap.ByteString("key", key), zap.ByteString("range-end", rangeEnd), traceutil.TraceLogField(ctx))
+
+	if len(key) == 0 {
+		return nil, false, model.ErrKVInvalidKey
+	}
+
+	logger.Debug("start to get kvs")
+	kvs, more, err := c.storage.GetUserKVs(ctx, key, rangeEnd, limit)
+	logger.Debug("finish getting kvs", zap.Int("count", len(kvs)), zap.Error(err))
+	if err != nil {
+		if errors.Is(err, model.ErrKVTxnFailed) {
+			return nil, false, model.ErrPDNotLeader
+		}
+		return nil, false, err
+	}
+
+	return kvs, more, nil
+}
+
+func (c *RaftCluster) PutKV(ctx context.Context, key, value []byte) error {
+	logger := c.lg.With(zap.ByteString("key", key), traceutil.TraceLogField(ctx))
+
+	if len(key) == 0 {
+		return model.ErrKVInvalidKey
+	}
+
+	logger.Debug("start to put kv")
+	err := c.storage.PutUserKV(ctx, key, value)
+	logger.Debug("finish putting kv", zap.Error(err))
+	if errors.Is(err, model.ErrKVTxnFailed) {
+		return model.ErrPDNotLeader
+	}
+
+	return err
+}
+
+func (c *RaftCluster) CompareAndPutKV(ctx context.Context, key, expected, value []byte) error {
+	logger := c.lg.With(zap.ByteString("key", key), traceutil.TraceLogField(ctx))
+
+	if len(key) == 0 {
+		return model.ErrKVInvalidKey
+	}
+
+	logger.Debug("start to compare and put kv")
+	err := c.storage.CompareAndPutUserKV(ctx, key, expected, value)
+	logger.Debug("finish comparing and putting kv", zap.Error(err))
+	if errors.Is(err, model.ErrKVTxnFailed) {
+		return model.ErrPDNotLeader
+	}
+
+	return err
+}
+
+func (c *RaftCluster) DeleteKVs(ctx context.Context, key, rangeEnd []byte) (int64, error) {
+	logger := c.lg.With(zap.ByteString("key", key), zap.ByteString("range-end", rangeEnd), traceutil.TraceLogField(ctx))
+
+	if len(key) == 0 {
+		return 0, model.ErrKVInvalidKey
+	}
+
+	logger.Debug("start to delete kvs")
+	deleted, err := c.storage.DeleteUserKVs(ctx, key, rangeEnd)
+	logger.Debug("finish deleting kvs", zap.Int64("deleted", deleted), zap.Error(err))
+	if err != nil {
+		if errors.Is(err, model.ErrKVTxnFailed) {
+			return 0, model.ErrPDNotLeader
+		}
+		return 0, err
+	}
+
+	return deleted, nil
+}
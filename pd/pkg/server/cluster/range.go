@@ -37,6 +37,7 @@ type RangeService interface {
 	// It returns model.ErrInvalidRangeIndex if the range index is invalid.
 	// It returns model.ErrCreateRangeBeforeSeal if the last range is not sealed.
 	// It returns model.ErrInvalidRangeStart if the start offset is invalid.
+	// It returns model.ErrInvalidReplicaLayout if the replica layout does not fit the stream.
 	// It returns model.ErrNotEnoughRangeServers if there are not enough range servers to allocate.
 	CreateRange(ctx context.Context, p *model.CreateRangeParam) (*rpcfb.RangeT, error)
 }
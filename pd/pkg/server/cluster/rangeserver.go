@@ -174,6 +174,8 @@ func (c *RaftCluster) fillRangeServerInfo(rangeServer *rpcfb.RangeServerT) {
 	}
 	rangeServer.AdvertiseAddr = rs.AdvertiseAddr
 	rangeServer.State = rs.State
+	rangeServer.Zone = rs.Zone
+	rangeServer.UnixAddr = rs.UnixAddr
 }
 
 func eraseRangeServersInfo(in []*rpcfb.RangeServerT) (out []*rpcfb.RangeServerT) {
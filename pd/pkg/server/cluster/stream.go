@@ -51,12 +51,17 @@ func (c *RaftCluster) CreateStream(ctx context.Context, param *model.CreateStrea
 	}
 
 	stream := &rpcfb.StreamT{
-		StreamId:          int64(sid),
-		Replica:           param.Replica,
-		AckCount:          param.AckCount,
-		RetentionPeriodMs: param.RetentionPeriodMs,
-		StartOffset:       0,
-		Epoch:             0,
+		StreamId:           int64(sid),
+		Replica:            param.Replica,
+		AckCount:           param.AckCount,
+		RetentionPeriodMs:  param.RetentionPeriodMs,
+		StartOffset:        0,
+		Epoch:              0,
+		Compacted:          param.Compacted,
+		DeleteRetentionMs:  param.DeleteRetentionMs,
+		RolloverMaxBytes:   param.RolloverMaxBytes,
+		RolloverMaxRecords: param.RolloverMaxRecords,
+		RolloverMaxAgeMs:   param.RolloverMaxAgeMs,
 	}
 	logger = logger.With(zap.Int64("stream-id", stream.StreamId))
 
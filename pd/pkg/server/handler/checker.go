@@ -114,3 +114,24 @@ func (c Checker) WatchResource(req *protocol.WatchResourceRequest, resp *protoco
 	}
 	c.Handler.WatchResource(req, resp)
 }
+
+func (c Checker) KVRange(req *protocol.KVRangeRequest, resp *protocol.KVRangeResponse) {
+	if !c.Handler.Check(req, resp) {
+		return
+	}
+	c.Handler.KVRange(req, resp)
+}
+
+func (c Checker) KVPut(req *protocol.KVPutRequest, resp *protocol.KVPutResponse) {
+	if !c.Handler.Check(req, resp) {
+		return
+	}
+	c.Handler.KVPut(req, resp)
+}
+
+func (c Checker) KVDeleteRange(req *protocol.KVDeleteRangeRequest, resp *protocol.KVDeleteRangeResponse) {
+	if !c.Handler.Check(req, resp) {
+		return
+	}
+	c.Handler.KVDeleteRange(req, resp)
+}
@@ -19,6 +19,7 @@ type Cluster interface {
 	cluster.MemberService
 	cluster.ObjectService
 	cluster.ResourceService
+	cluster.KVService
 }
 
 // Handler is an sbp handler, implements server.Handler
@@ -298,3 +298,21 @@ func (th timeoutHandler) WatchResource(req *protocol.WatchResourceRequest, resp
 	defer cancel()
 	th.handler.WatchResource(req, resp)
 }
+
+func (th timeoutHandler) KVRange(req *protocol.KVRangeRequest, resp *protocol.KVRangeResponse) {
+	cancel := timeoutReq(req, th.timeout)
+	defer cancel()
+	th.handler.KVRange(req, resp)
+}
+
+func (th timeoutHandler) KVPut(req *protocol.KVPutRequest, resp *protocol.KVPutResponse) {
+	cancel := timeoutReq(req, th.timeout)
+	defer cancel()
+	th.handler.KVPut(req, resp)
+}
+
+func (th timeoutHandler) KVDeleteRange(req *protocol.KVDeleteRangeRequest, resp *protocol.KVDeleteRangeResponse) {
+	cancel := timeoutReq(req, th.timeout)
+	defer cancel()
+	th.handler.KVDeleteRange(req, resp)
+}
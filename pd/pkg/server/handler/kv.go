@@ -0,0 +1,66 @@
+package handler
+
+import (
+	"github.com/pkg/errors"
+
+	"github.com/AutoMQ/pd/api/rpcfb/rpcfb"
+	"github.com/AutoMQ/pd/pkg/sbp/protocol"
+	"github.com/AutoMQ/pd/pkg/server/model"
+)
+
+func (h *Handler) KVRange(req *protocol.KVRangeRequest, resp *protocol.KVRangeResponse) {
+	ctx := req.Context()
+
+	kvs, more, err := h.c.GetKVs(ctx, req.Key, req.RangeEnd, req.Limit)
+	if err != nil {
+		resp.Error(h.kvError(req, err))
+		return
+	}
+
+	resp.Kvs = make([]*rpcfb.KeyValueT, 0, len(kvs))
+	for _, keyValue := range kvs {
+		resp.Kvs = append(resp.Kvs, &rpcfb.KeyValueT{Key: keyValue.Key, Value: keyValue.Value})
+	}
+	resp.More = more
+	resp.Count = int64(len(kvs))
+	resp.OK()
+}
+
+func (h *Handler) KVPut(req *protocol.KVPutRequest, resp *protocol.KVPutResponse) {
+	var err error
+	if req.Compare {
+		err = h.c.CompareAndPutKV(req.Context(), req.Key, req.ExpectedValue, req.Value)
+	} else {
+		err = h.c.PutKV(req.Context(), req.Key, req.Value)
+	}
+	if err != nil {
+		resp.Error(h.kvError(req, err))
+		return
+	}
+
+	resp.OK()
+}
+
+func (h *Handler) KVDeleteRange(req *protocol.KVDeleteRangeRequest, resp *protocol.KVDeleteRangeResponse) {
+	deleted, err := h.c.DeleteKVs(req.Context(), req.Key, req.RangeEnd)
+	if err != nil {
+		resp.Error(h.kvError(req, err))
+		return
+	}
+
+	resp.Deleted = deleted
+	resp.OK()
+}
+
+func (h *Handler) kvError(req protocol.InRequest, err error) *rpcfb.StatusT {
+	switch {
+	case errors.Is(err, model.ErrPDNotLeader):
+		return h.notLeaderError(req.Context())
+	case errors.Is(err, model.ErrKVInvalidKey):
+		return &rpcfb.StatusT{Code: rpcfb.ErrorCodeBAD_REQUEST, Message: err.Error()}
+	case errors.Is(err, model.ErrKVCompareFailed):
+		return &rpcfb.StatusT{Code: rpcfb.ErrorCodePRECONDITION_FAILED, Message: err.Error()}
+	default:
+		return &rpcfb.StatusT{Code: rpcfb.ErrorCodePD_INTERNAL_SERVER_ERROR, Message: err.Error()}
+	}
+}
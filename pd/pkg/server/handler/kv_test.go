@@ -0,0 +1,81 @@
+package handler
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/AutoMQ/pd/api/rpcfb/rpcfb"
+	"github.com/AutoMQ/pd/pkg/sbp/protocol"
+)
+
+func TestHandler_KV(t *testing.T) {
+	re := require.New(t)
+
+	h, closeFunc := startSbpHandler(t, nil, nil, true)
+	defer closeFunc()
+
+	put := func(key, value string) {
+		req := &protocol.KVPutRequest{PutRequestT: rpcfb.PutRequestT{Key: []byte(key), Value: []byte(value)}}
+		resp := &protocol.KVPutResponse{}
+		h.KVPut(req, resp)
+		re.Equal(rpcfb.ErrorCodeOK, resp.Status.Code, resp.Status.Message)
+	}
+	get := func(key, rangeEnd []byte, limit int64) *protocol.KVRangeResponse {
+		req := &protocol.KVRangeRequest{RangeRequestT: rpcfb.RangeRequestT{Key: key, RangeEnd: rangeEnd, Limit: limit}}
+		resp := &protocol.KVRangeResponse{}
+		h.KVRange(req, resp)
+		re.Equal(rpcfb.ErrorCodeOK, resp.Status.Code, resp.Status.Message)
+		return resp
+	}
+
+	put("txn/a", "1")
+	put("txn/b", "2")
+	put("txn/c", "3")
+	put("z", "4")
+
+	// single key
+	resp := get([]byte("txn/b"), nil, 0)
+	re.Equal([]*rpcfb.KeyValueT{{Key: []byte("txn/b"), Value: []byte("2")}}, resp.Kvs)
+	resp = get([]byte("txn/d"), nil, 0)
+	re.Empty(resp.Kvs)
+
+	// prefix with limit
+	resp = get([]byte("txn/"), []byte("txn0"), 2)
+	re.Equal([]*rpcfb.KeyValueT{{Key: []byte("txn/a"), Value: []byte("1")}, {Key: []byte("txn/b"), Value: []byte("2")}}, resp.Kvs)
+	re.True(resp.More)
+
+	// all keys greater than or equal to the key
+	resp = get([]byte("txn/c"), []byte{0}, 0)
+	re.Equal([]*rpcfb.KeyValueT{{Key: []byte("txn/c"), Value: []byte("3")}, {Key: []byte("z"), Value: []byte("4")}}, resp.Kvs)
+	re.False(resp.More)
+
+	// delete
+	dReq := &protocol.KVDeleteRangeRequest{DeleteRangeRequestT: rpcfb.DeleteRangeRequestT{Key: []byte("txn/"), RangeEnd: []byte("txn0")}}
+	dResp := &protocol.KVDeleteRangeResponse{}
+	h.KVDeleteRange(dReq, dResp)
+	re.Equal(rpcfb.ErrorCodeOK, dResp.Status.Code, dResp.Status.Message)
+	re.Equal(int64(3), dResp.Deleted)
+	resp = get([]byte("txn/"), []byte("txn0"), 0)
+	re.Empty(resp.Kvs)
+
+	// compare and put
+	cas := func(key string, expected []byte, value string) rpcfb.ErrorCode {
+		req := &protocol.KVPutRequest{PutRequestT: rpcfb.PutRequestT{Key: []byte(key), Value: []byte(value), Compare: true, ExpectedValue: expected}}
+		resp := &protocol.KVPutResponse{}
+		h.KVPut(req, resp)
+		return resp.Status.Code
+	}
+	re.Equal(rpcfb.ErrorCodeOK, cas("txn/x", nil, "1"))
+	re.Equal(rpcfb.ErrorCodePRECONDITION_FAILED, cas("txn/x", nil, "2"))
+	re.Equal(rpcfb.ErrorCodePRECONDITION_FAILED, cas("txn/x", []byte("2"), "3"))
+	re.Equal(rpcfb.ErrorCodeOK, cas("txn/x", []byte("1"), "3"))
+	resp = get([]byte("txn/x"), nil, 0)
+	re.Equal([]*rpcfb.KeyValueT{{Key: []byte("txn/x"), Value: []byte("3")}}, resp.Kvs)
+
+	// empty key
+	pReq := &protocol.KVPutRequest{}
+	pResp := &protocol.KVPutResponse{}
+	h.KVPut(pReq, pResp)
+	re.Equal(rpcfb.ErrorCodeBAD_REQUEST, pResp.Status.Code)
+}
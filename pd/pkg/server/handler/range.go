@@ -103,6 +103,8 @@ func (h *Handler) CreateRange(req *protocol.CreateRangeRequest, resp *protocol.C
 			resp.Error(&rpcfb.StatusT{Code: rpcfb.ErrorCodeCREATE_RANGE_BEFORE_SEAL, Message: err.Error()})
 		case errors.Is(err, model.ErrInvalidRangeStart):
 			resp.Error(&rpcfb.StatusT{Code: rpcfb.ErrorCodeBAD_REQUEST, Message: err.Error()})
+		case errors.Is(err, model.ErrInvalidReplicaLayout):
+			resp.Error(&rpcfb.StatusT{Code: rpcfb.ErrorCodeBAD_REQUEST, Message: err.Error()})
 		case errors.Is(err, model.ErrNotEnoughRangeServers):
 			resp.Error(&rpcfb.StatusT{Code: rpcfb.ErrorCodePD_NO_AVAILABLE_RS, Message: err.Error()})
 		default:
@@ -60,7 +60,23 @@ func (h *Handler) WatchResource(req *protocol.WatchResourceRequest, resp *protoc
 		return
 	}
 
+	if req.StreamId >= 0 {
+		events = filterRangesOfStream(events, req.StreamId)
+	}
+
 	resp.Events = events
 	resp.ResourceVersion = rv
 	resp.OK()
 }
+
+// filterRangesOfStream drops events of ranges that do not belong to the given stream.
+func filterRangesOfStream(events []*rpcfb.ResourceEventT, streamID int64) []*rpcfb.ResourceEventT {
+	filtered := make([]*rpcfb.ResourceEventT, 0, len(events))
+	for _, event := range events {
+		if event.Resource.Type == rpcfb.ResourceTypeRESOURCE_RANGE && event.Resource.Range.StreamId != streamID {
+			continue
+		}
+		filtered = append(filtered, event)
+	}
+	return filtered
+}
@@ -202,8 +202,9 @@ func TestHandler_ListResource(t *testing.T) {
 
 func TestHandler_WatchResource(t *testing.T) {
 	type args struct {
-		types []rpcfb.ResourceType
-		rv    int64
+		types    []rpcfb.ResourceType
+		rv       int64
+		streamID int64
 	}
 	type want struct {
 		events []rpcfb.ResourceEventT
@@ -220,7 +221,8 @@ func TestHandler_WatchResource(t *testing.T) {
 		{
 			name: "normal case",
 			args: args{
-				types: []rpcfb.ResourceType{rpcfb.ResourceTypeRESOURCE_RANGE_SERVER, rpcfb.ResourceTypeRESOURCE_STREAM, rpcfb.ResourceTypeRESOURCE_RANGE, rpcfb.ResourceTypeRESOURCE_OBJECT},
+				types:    []rpcfb.ResourceType{rpcfb.ResourceTypeRESOURCE_RANGE_SERVER, rpcfb.ResourceTypeRESOURCE_STREAM, rpcfb.ResourceTypeRESOURCE_RANGE, rpcfb.ResourceTypeRESOURCE_OBJECT},
+				streamID: -1,
 			},
 			want: want{
 				events: []rpcfb.ResourceEventT{
@@ -238,8 +240,9 @@ func TestHandler_WatchResource(t *testing.T) {
 		{
 			name: "specify resource version",
 			args: args{
-				types: []rpcfb.ResourceType{rpcfb.ResourceTypeRESOURCE_RANGE_SERVER, rpcfb.ResourceTypeRESOURCE_STREAM, rpcfb.ResourceTypeRESOURCE_RANGE, rpcfb.ResourceTypeRESOURCE_OBJECT},
-				rv:    4,
+				types:    []rpcfb.ResourceType{rpcfb.ResourceTypeRESOURCE_RANGE_SERVER, rpcfb.ResourceTypeRESOURCE_STREAM, rpcfb.ResourceTypeRESOURCE_RANGE, rpcfb.ResourceTypeRESOURCE_OBJECT},
+				rv:       4,
+				streamID: -1,
 			},
 			want: want{
 				events: []rpcfb.ResourceEventT{
@@ -251,6 +254,35 @@ func TestHandler_WatchResource(t *testing.T) {
 				},
 			},
 		},
+		{
+			name: "ranges of the stream",
+			args: args{
+				types:    []rpcfb.ResourceType{rpcfb.ResourceTypeRESOURCE_STREAM, rpcfb.ResourceTypeRESOURCE_RANGE},
+				rv:       4,
+				streamID: 0,
+			},
+			want: want{
+				events: []rpcfb.ResourceEventT{
+					{Type: rpcfb.EventTypeEVENT_ADDED, Resource: &rpcfb.ResourceT{Type: rpcfb.ResourceTypeRESOURCE_STREAM, Stream: &rpcfb.StreamT{Replica: 3, AckCount: 3}}},
+					{Type: rpcfb.EventTypeEVENT_MODIFIED, Resource: &rpcfb.ResourceT{Type: rpcfb.ResourceTypeRESOURCE_STREAM, Stream: &rpcfb.StreamT{Replica: 3, AckCount: 3, Epoch: 1}}},
+					{Type: rpcfb.EventTypeEVENT_ADDED, Resource: &rpcfb.ResourceT{Type: rpcfb.ResourceTypeRESOURCE_RANGE, Range: &rpcfb.RangeT{Epoch: 1, End: -1}}},
+				},
+			},
+		},
+		{
+			name: "ranges of another stream",
+			args: args{
+				types:    []rpcfb.ResourceType{rpcfb.ResourceTypeRESOURCE_STREAM, rpcfb.ResourceTypeRESOURCE_RANGE},
+				rv:       4,
+				streamID: 1,
+			},
+			want: want{
+				events: []rpcfb.ResourceEventT{
+					{Type: rpcfb.EventTypeEVENT_ADDED, Resource: &rpcfb.ResourceT{Type: rpcfb.ResourceTypeRESOURCE_STREAM, Stream: &rpcfb.StreamT{Replica: 3, AckCount: 3}}},
+					{Type: rpcfb.EventTypeEVENT_MODIFIED, Resource: &rpcfb.ResourceT{Type: rpcfb.ResourceTypeRESOURCE_STREAM, Stream: &rpcfb.StreamT{Replica: 3, AckCount: 3, Epoch: 1}}},
+				},
+			},
+		},
 		{
 			name: "empty resource type",
 			args: args{
@@ -296,6 +328,7 @@ func TestHandler_WatchResource(t *testing.T) {
 			req := &protocol.WatchResourceRequest{WatchResourceRequestT: rpcfb.WatchResourceRequestT{
 				ResourceType:    tt.args.types,
 				ResourceVersion: tt.args.rv,
+				StreamId:        tt.args.streamID,
 			}}
 			resp := &protocol.WatchResourceResponse{}
 			h.WatchResource(req, resp)
@@ -70,6 +70,35 @@ func TestHandler_CreateStream(t *testing.T) {
 				errMsg:  "invalid ack count",
 			},
 		},
+		{
+			name: "compacted stream",
+			args: args{
+				stream: &rpcfb.StreamT{Replica: 2, AckCount: 2, RetentionPeriodMs: time.Hour.Milliseconds(), Compacted: true, DeleteRetentionMs: time.Minute.Milliseconds()},
+			},
+			want: want{
+				stream: rpcfb.StreamT{StreamId: 1, Replica: 2, AckCount: 2, RetentionPeriodMs: time.Hour.Milliseconds(), Compacted: true, DeleteRetentionMs: time.Minute.Milliseconds()},
+			},
+		},
+		{
+			name: "stream with rollover policy",
+			args: args{
+				stream: &rpcfb.StreamT{Replica: 2, AckCount: 2, RetentionPeriodMs: time.Hour.Milliseconds(), RolloverMaxBytes: 1024, RolloverMaxRecords: -1, RolloverMaxAgeMs: time.Minute.Milliseconds()},
+			},
+			want: want{
+				stream: rpcfb.StreamT{StreamId: 1, Replica: 2, AckCount: 2, RetentionPeriodMs: time.Hour.Milliseconds(), RolloverMaxBytes: 1024, RolloverMaxRecords: -1, RolloverMaxAgeMs: time.Minute.Milliseconds()},
+			},
+		},
+		{
+			name: "invalid delete retention",
+			args: args{
+				stream: &rpcfb.StreamT{Replica: 2, AckCount: 2, RetentionPeriodMs: time.Hour.Milliseconds(), Compacted: true, DeleteRetentionMs: -1},
+			},
+			want: want{
+				wantErr: true,
+				errCode: rpcfb.ErrorCodeBAD_REQUEST,
+				errMsg:  "invalid delete retention",
+			},
+		},
 	}
 	for _, tt := range tests {
 		tt := tt
@@ -14,6 +14,10 @@ var (
 	ErrKVCompacted = errors.New("requested revision has been compacted")
 	// ErrKVDataModified is returned when the data has been modified when doing transaction.
 	ErrKVDataModified = errors.New("data has been modified")
+	// ErrKVInvalidKey is returned when the key of a user key-value pair is empty.
+	ErrKVInvalidKey = errors.New("invalid key")
+	// ErrKVCompareFailed is returned when a user key-value pair does not hold the expected value.
+	ErrKVCompareFailed = errors.New("compare failed")
 )
 
 // PD errors
@@ -50,6 +54,8 @@ var (
 	ErrCreateRangeBeforeSeal = errors.New("create range before sealing the previous one")
 	// ErrInvalidRangeStart is returned when the start offset is invalid.
 	ErrInvalidRangeStart = errors.New("invalid range start offset")
+	// ErrInvalidReplicaLayout is returned when the replica layout does not fit the stream replica and ack count.
+	ErrInvalidReplicaLayout = errors.New("invalid replica layout")
 
 	// ErrRangeNotFound is returned when the specified range is not found.
 	ErrRangeNotFound = errors.New("range not found")
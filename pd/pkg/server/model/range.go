@@ -59,10 +59,12 @@ func (sr SealRangeParam) Fields() []zap.Field {
 }
 
 type CreateRangeParam struct {
-	StreamID int64
-	Epoch    int64
-	Index    int32
-	Start    int64
+	StreamID      int64
+	Epoch         int64
+	Index         int32
+	Start         int64
+	ReplicaLayout rpcfb.ReplicaLayout
+	DataShards    int8
 }
 
 func NewCreateRangeParam(r *rpcfb.RangeT) (*CreateRangeParam, error) {
@@ -81,12 +83,23 @@ func NewCreateRangeParam(r *rpcfb.RangeT) (*CreateRangeParam, error) {
 	if r.Start < 0 {
 		return nil, errors.Errorf("invalid start %d", r.Start)
 	}
+	switch r.ReplicaLayout {
+	case rpcfb.ReplicaLayoutREPLICA_LAYOUT_FULL:
+	case rpcfb.ReplicaLayoutREPLICA_LAYOUT_REED_SOLOMON:
+		if r.DataShards <= 0 {
+			return nil, errors.Errorf("invalid data shards %d", r.DataShards)
+		}
+	default:
+		return nil, errors.Errorf("invalid replica layout %d", r.ReplicaLayout)
+	}
 
 	return &CreateRangeParam{
-		StreamID: r.StreamId,
-		Epoch:    r.Epoch,
-		Index:    r.Index,
-		Start:    r.Start,
+		StreamID:      r.StreamId,
+		Epoch:         r.Epoch,
+		Index:         r.Index,
+		Start:         r.Start,
+		ReplicaLayout: r.ReplicaLayout,
+		DataShards:    r.DataShards,
 	}, nil
 }
 
@@ -96,5 +109,7 @@ func (cr CreateRangeParam) Fields() []zap.Field {
 		zap.Int64("create-range-epoch", cr.Epoch),
 		zap.Int32("create-range-index", cr.Index),
 		zap.Int64("create-range-start", cr.Start),
+		zap.String("create-range-replica-layout", cr.ReplicaLayout.String()),
+		zap.Int8("create-range-data-shards", cr.DataShards),
 	}
 }
@@ -13,9 +13,15 @@ const (
 )
 
 type CreateStreamParam struct {
-	Replica           int8
-	AckCount          int8
-	RetentionPeriodMs int64
+	Replica            int8
+	AckCount           int8
+	RetentionPeriodMs  int64
+	Compacted          bool
+	DeleteRetentionMs  int64
+	// Rollover policy of the stream, where negative values mean writers apply their default.
+	RolloverMaxBytes   int64
+	RolloverMaxRecords int64
+	RolloverMaxAgeMs   int64
 }
 
 func NewCreateStreamParam(s *rpcfb.StreamT) (*CreateStreamParam, error) {
@@ -28,11 +34,19 @@ func NewCreateStreamParam(s *rpcfb.StreamT) (*CreateStreamParam, error) {
 	if s.AckCount <= 0 {
 		return nil, errors.Errorf("invalid ack count %d", s.AckCount)
 	}
+	if s.Compacted && s.DeleteRetentionMs < 0 {
+		return nil, errors.Errorf("invalid delete retention %d of compacted stream", s.DeleteRetentionMs)
+	}
 
 	return &CreateStreamParam{
-		Replica:           s.Replica,
-		AckCount:          s.AckCount,
-		RetentionPeriodMs: s.RetentionPeriodMs,
+		Replica:            s.Replica,
+		AckCount:           s.AckCount,
+		RetentionPeriodMs:  s.RetentionPeriodMs,
+		Compacted:          s.Compacted,
+		DeleteRetentionMs:  s.DeleteRetentionMs,
+		RolloverMaxBytes:   s.RolloverMaxBytes,
+		RolloverMaxRecords: s.RolloverMaxRecords,
+		RolloverMaxAgeMs:   s.RolloverMaxAgeMs,
 	}, nil
 }
 
@@ -41,6 +55,11 @@ func (cs CreateStreamParam) Fields() []zap.Field {
 		zap.Int8("create-stream-replica", cs.Replica),
 		zap.Int8("create-stream-ack-count", cs.AckCount),
 		zap.Int64("create-stream-retention-period-ms", cs.RetentionPeriodMs),
+		zap.Bool("create-stream-compacted", cs.Compacted),
+		zap.Int64("create-stream-delete-retention-ms", cs.DeleteRetentionMs),
+		zap.Int64("create-stream-rollover-max-bytes", cs.RolloverMaxBytes),
+		zap.Int64("create-stream-rollover-max-records", cs.RolloverMaxRecords),
+		zap.Int64("create-stream-rollover-max-age-ms", cs.RolloverMaxAgeMs),
 	}
 }
 
@@ -52,6 +71,8 @@ type UpdateStreamParam struct {
 	AckCount          int8
 	RetentionPeriodMs int64
 	Epoch             int64
+	// Non-positive value means no change.
+	DeleteRetentionMs int64
 }
 
 func NewUpdateStreamParam(s *rpcfb.StreamT) (*UpdateStreamParam, error) {
@@ -70,7 +91,7 @@ func NewUpdateStreamParam(s *rpcfb.StreamT) (*UpdateStreamParam, error) {
 	if s.StartOffset >= 0 {
 		return nil, errors.Errorf("do not support update start offset %d", s.StartOffset)
 	}
-	if s.Replica < 0 && s.AckCount < 0 && s.RetentionPeriodMs < 0 && s.Epoch < 0 {
+	if s.Replica < 0 && s.AckCount < 0 && s.RetentionPeriodMs < 0 && s.Epoch < 0 && s.DeleteRetentionMs <= 0 {
 		return nil, errors.New("no change")
 	}
 	if s.Replica > 0 && s.AckCount > 0 && s.Replica < s.AckCount {
@@ -83,6 +104,7 @@ func NewUpdateStreamParam(s *rpcfb.StreamT) (*UpdateStreamParam, error) {
 		AckCount:          s.AckCount,
 		RetentionPeriodMs: s.RetentionPeriodMs,
 		Epoch:             s.Epoch,
+		DeleteRetentionMs: s.DeleteRetentionMs,
 	}, nil
 }
 
@@ -93,6 +115,7 @@ func (us UpdateStreamParam) Fields() []zap.Field {
 		zap.Int8("update-stream-ack-count", us.AckCount),
 		zap.Int64("update-stream-retention-period-ms", us.RetentionPeriodMs),
 		zap.Int64("update-stream-epoch", us.Epoch),
+		zap.Int64("update-stream-delete-retention-ms", us.DeleteRetentionMs),
 	}
 }
 
@@ -34,6 +34,10 @@ func TestNoNewFieldsInUpdateStreamParam(t *testing.T) {
 	ignoredFields := []string{
 		"StartOffset",
 		"Deleted",
+		"Compacted",
+		"RolloverMaxBytes",
+		"RolloverMaxRecords",
+		"RolloverMaxAgeMs",
 	}
 	streamFields := testutil.GetAllFields(rpcfb.StreamT{})
 	updateStreamParamFields := testutil.GetAllFields(UpdateStreamParam{})
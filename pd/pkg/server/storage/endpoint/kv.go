@@ -0,0 +1,139 @@
+package endpoint
+
+import (
+	"bytes"
+	"context"
+
+	"github.com/pkg/errors"
+	"go.uber.org/zap"
+
+	"github.com/AutoMQ/pd/pkg/server/model"
+	"github.com/AutoMQ/pd/pkg/server/storage/kv"
+	traceutil "github.com/AutoMQ/pd/pkg/util/trace"
+)
+
+const (
+	// user key-value pairs, isolated from metadata of the PD
+	_userKVPath   = "kv"
+	_userKVPrefix = _userKVPath + kv.KeySeparator
+)
+
+// UserKVEndpoint provides key-value pairs of users, e.g. states of transactions of clients.
+// Keys are relative to a dedicated prefix, so that users never touch metadata of the PD.
+type UserKVEndpoint interface {
+	// GetUserKVs returns the key-value pair of the key if rangeEnd is empty, or the key-value pairs
+	// in [key, rangeEnd) otherwise. A rangeEnd of "\0" means all keys greater than or equal to the key.
+	// It returns at most limit key-value pairs if limit is greater than 0, and whether there are more.
+	GetUserKVs(ctx context.Context, key, rangeEnd []byte, limit int64) (kvs []kv.KeyValue, more bool, err error)
+	// PutUserKV sets the value of the key.
+	PutUserKV(ctx context.Context, key, value []byte) error
+	// CompareAndPutUserKV sets the value of the key if it holds the expected value, or does not exist if
+	// expected is nil. It returns model.ErrKVCompareFailed otherwise.
+	CompareAndPutUserKV(ctx context.Context, key, expected, value []byte) error
+	// DeleteUserKVs deletes the key if rangeEnd is empty, or the keys in [key, rangeEnd) otherwise,
+	// and returns the number of deleted keys.
+	DeleteUserKVs(ctx context.Context, key, rangeEnd []byte) (int64, error)
+}
+
+func (e *Endpoint) GetUserKVs(ctx context.Context, key, rangeEnd []byte, limit int64) ([]kv.KeyValue, bool, error) {
+	logger := e.lg.With(zap.ByteString("key", key), zap.ByteString("range-end", rangeEnd), traceutil.TraceLogField(ctx))
+
+	if len(rangeEnd) == 0 {
+		value, err := e.KV.Get(ctx, userKVPath(key))
+		if err != nil {
+			logger.Error("failed to get user kv", zap.Error(err))
+			return nil, false, errors.WithMessage(err, "get user kv")
+		}
+		if value == nil {
+			return nil, false, nil
+		}
+		return []kv.KeyValue{{Key: key, Value: value}}, false, nil
+	}
+
+	kvs, _, more, err := e.KV.GetByRange(ctx, e.userKVRange(key, rangeEnd), 0, limit, false)
+	if err != nil {
+		logger.Error("failed to get user kvs", zap.Int64("limit", limit), zap.Error(err))
+		return nil, false, errors.WithMessage(err, "get user kvs")
+	}
+	for i := range kvs {
+		kvs[i].Key = bytes.TrimPrefix(kvs[i].Key, []byte(_userKVPrefix))
+	}
+	return kvs, more, nil
+}
+
+func (e *Endpoint) PutUserKV(ctx context.Context, key, value []byte) error {
+	logger := e.lg.With(zap.ByteString("key", key), traceutil.TraceLogField(ctx))
+
+	_, err := e.KV.Put(ctx, userKVPath(key), value, false, 0)
+	if err != nil {
+		logger.Error("failed to put user kv", zap.Error(err))
+		return errors.WithMessage(err, "put user kv")
+	}
+	return nil
+}
+
+func (e *Endpoint) CompareAndPutUserKV(ctx context.Context, key, expected, value []byte) error {
+	logger := e.lg.With(zap.ByteString("key", key), traceutil.TraceLogField(ctx))
+
+	err := e.KV.ExecInTxn(ctx, func(basicKV kv.BasicKV) error {
+		k := userKVPath(key)
+		prevValue, err := basicKV.Get(ctx, k)
+		if err != nil {
+			return errors.WithMessage(err, "get user kv")
+		}
+		if (prevValue == nil) != (expected == nil) || !bytes.Equal(prevValue, expected) {
+			return model.ErrKVCompareFailed
+		}
+		_, _ = basicKV.Put(ctx, k, value, false, 0)
+		return nil
+	})
+	if errors.Is(err, model.ErrKVDataModified) {
+		// Modified concurrently after the value was compared.
+		err = model.ErrKVCompareFailed
+	}
+	if err != nil && !errors.Is(err, model.ErrKVCompareFailed) {
+		logger.Error("failed to compare and put user kv", zap.Error(err))
+		return errors.WithMessage(err, "compare and put user kv")
+	}
+	return err
+}
+
+func (e *Endpoint) DeleteUserKVs(ctx context.Context, key, rangeEnd []byte) (int64, error) {
+	logger := e.lg.With(zap.ByteString("key", key), zap.ByteString("range-end", rangeEnd), traceutil.TraceLogField(ctx))
+
+	if len(rangeEnd) == 0 {
+		prevValue, err := e.KV.Delete(ctx, userKVPath(key), true)
+		if err != nil {
+			logger.Error("failed to delete user kv", zap.Error(err))
+			return 0, errors.WithMessage(err, "delete user kv")
+		}
+		if prevValue == nil {
+			return 0, nil
+		}
+		return 1, nil
+	}
+
+	deleted, err := e.KV.DeleteByRange(ctx, e.userKVRange(key, rangeEnd))
+	if err != nil {
+		logger.Error("failed to delete user kvs", zap.Error(err))
+		return 0, errors.WithMessage(err, "delete user kvs")
+	}
+	return int64(deleted), nil
+}
+
+func (e *Endpoint) userKVRange(key, rangeEnd []byte) kv.Range {
+	r := kv.Range{StartKey: userKVPath(key)}
+	if bytes.Equal(rangeEnd, []byte{0}) {
+		r.EndKey = e.KV.GetPrefixRangeEnd([]byte(_userKVPrefix))
+	} else {
+		r.EndKey = userKVPath(rangeEnd)
+	}
+	return r
+}
+
+func userKVPath(key []byte) []byte {
+	res := make([]byte, 0, len(_userKVPrefix)+len(key))
+	res = append(res, _userKVPrefix...)
+	res = append(res, key...)
+	return res
+}
@@ -44,6 +44,7 @@ type RangeEndpoint interface {
 	// It returns model.ErrInvalidRangeIndex if the range index is invalid.
 	// It returns model.ErrCreateRangeBeforeSeal if the last range is not sealed.
 	// It returns model.ErrInvalidRangeStart if the start offset is invalid.
+	// It returns model.ErrInvalidReplicaLayout if the replica layout does not fit the stream.
 	// It returns model.ErrNotEnoughRangeServers if there are not enough range servers to allocate.
 	CreateRange(ctx context.Context, p *model.CreateRangeParam, f ChooseServersFunc) (*rpcfb.RangeT, error)
 	// SealRange seals the range and returns it.
@@ -93,6 +94,12 @@ func (e *Endpoint) CreateRange(ctx context.Context, p *model.CreateRangeParam, f
 			logger.Error("invalid epoch", zap.Int64("stream-epoch", s.Epoch))
 			return errors.WithMessagef(model.ErrInvalidStreamEpoch, "range %d-%d epoch %d != %d", p.StreamID, p.Index, p.Epoch, s.Epoch)
 		}
+		// erasure coded records are reconstructed from any `DataShards` shards, so there must be parity shards,
+		// and acknowledged records must be reconstructable
+		if p.ReplicaLayout == rpcfb.ReplicaLayoutREPLICA_LAYOUT_REED_SOLOMON && (p.DataShards >= s.Replica || p.DataShards > s.AckCount) {
+			logger.Error("invalid replica layout", zap.Int8("stream-replica", s.Replica), zap.Int8("stream-ack-count", s.AckCount))
+			return errors.WithMessagef(model.ErrInvalidReplicaLayout, "range %d-%d data shards %d with replica %d and ack count %d", p.StreamID, p.Index, p.DataShards, s.Replica, s.AckCount)
+		}
 
 		// make sure the range does not exist
 		rk := rangePathInSteam(p.StreamID, p.Index)
@@ -151,14 +158,16 @@ func (e *Endpoint) CreateRange(ctx context.Context, p *model.CreateRangeParam, f
 			return errors.WithMessagef(err, "choose servers for range %d-%d", p.StreamID, p.Index)
 		}
 		newRange = &rpcfb.RangeT{
-			StreamId:     p.StreamID,
-			Epoch:        p.Epoch,
-			Index:        p.Index,
-			Start:        p.Start,
-			End:          _writableRangeEnd,
-			Servers:      servers,
-			ReplicaCount: s.Replica,
-			AckCount:     s.AckCount,
+			StreamId:      p.StreamID,
+			Epoch:         p.Epoch,
+			Index:         p.Index,
+			Start:         p.Start,
+			End:           _writableRangeEnd,
+			Servers:       servers,
+			ReplicaCount:  s.Replica,
+			AckCount:      s.AckCount,
+			ReplicaLayout: p.ReplicaLayout,
+			DataShards:    p.DataShards,
 			// TODO: choose offload owner by some strategy.
 			OffloadOwner: &rpcfb.OffloadOwnerT{ServerId: servers[0].ServerId},
 		}
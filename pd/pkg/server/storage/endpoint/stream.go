@@ -234,6 +234,9 @@ func (e *Endpoint) UpdateStream(ctx context.Context, p *model.UpdateStreamParam)
 		if p.RetentionPeriodMs >= 0 {
 			oldStream.RetentionPeriodMs = p.RetentionPeriodMs
 		}
+		if p.DeleteRetentionMs > 0 {
+			oldStream.DeleteRetentionMs = p.DeleteRetentionMs
+		}
 		if p.Epoch >= 0 {
 			if p.Epoch < oldStream.Epoch {
 				logger.Error("invalid epoch", zap.Int64("new-epoch", p.Epoch), zap.Int64("old-epoch", oldStream.Epoch))
@@ -30,4 +30,5 @@ type Storage interface {
 	endpoint.RangeServerEndpoint
 	endpoint.ObjectEndpoint
 	endpoint.ResourceEndpoint
+	endpoint.UserKVEndpoint
 }
@@ -2,12 +2,8 @@ use clap::{Args, Parser, Subcommand};
 use config::Configuration;
 use log::{info, trace};
 use nix::{errno::Errno, ifaddrs::getifaddrs};
-use std::{
-    fs::File,
-    net::{Ipv4Addr, SocketAddr},
-    path::Path,
-    str::FromStr,
-};
+use std::{fs::File, net::Ipv4Addr, path::Path, str::FromStr};
+use transport::Address;
 
 #[derive(Debug, Parser, Clone)]
 #[command(author, about, version, long_about = None)]
@@ -28,10 +24,18 @@ pub struct StartArgs {
 
     /// Range server advertising address for clients to connect
     ///
-    /// Default value is the same to `addr`.
+    /// Default value is `addr` with host replaced by the picked IP.
     #[arg(long, env = "ES_ADVERTISE_ADDR")]
     advertise_addr: Option<String>,
 
+    /// Unix domain socket to serve clients on the same host, alongside `addr`, for example
+    /// `unix:///path/to/range-server.sock`.
+    ///
+    /// It is published to placement driver along with `advertise-addr`, and clients on the same host
+    /// connect to it instead.
+    #[arg(long, env = "ES_UNIX_ADDR")]
+    unix_addr: Option<String>,
+
     /// The address of placement-driver service: `domain-name:port`
     ///
     /// Default value: `127.0.0.1:12378`
@@ -57,6 +61,12 @@ pub struct StartArgs {
 #[derive(Debug, Clone, Subcommand)]
 pub enum Commands {
     Start(StartArgs),
+
+    /// Start a range server along with an embedded placement driver in a single process.
+    ///
+    /// The embedded placement driver listens on `--pd` and keeps metadata in memory only.
+    Standalone(StartArgs),
+
     BuildInfo,
 }
 
@@ -107,16 +117,34 @@ impl StartArgs {
             None => String::from("0.0.0.0:10911"),
         };
 
-        let socket_addr = SocketAddr::from_str(&configuration.server.addr)?;
-        let port = socket_addr.port();
+        let socket_addr = match Address::from_str(&configuration.server.addr)? {
+            Address::Tcp(socket_addr) => socket_addr,
+            Address::Unix(_) => {
+                anyhow::bail!(
+                    "`addr` must be a TCP address, use `unix-addr` to serve Unix domain socket"
+                );
+            }
+        };
+
+        if let Some(unix_addr) = &self.unix_addr {
+            match Address::from_str(unix_addr)? {
+                Address::Unix(_) => configuration.server.unix_addr = Some(unix_addr.clone()),
+                Address::Tcp(_) => {
+                    anyhow::bail!("`unix-addr` must be in the form of `unix:///path/to/socket`");
+                }
+            }
+        }
 
         match &self.advertise_addr {
             Some(advertise_addr) => {
+                if let Ok(Address::Unix(_)) = Address::from_str(advertise_addr) {
+                    anyhow::bail!("`advertise-addr` must be a TCP address reachable by clients");
+                }
                 configuration.server.advertise_addr = advertise_addr.clone();
             }
             None => {
-                let addr = pick_ip()?;
-                configuration.server.advertise_addr = addr
+                let port = socket_addr.port();
+                configuration.server.advertise_addr = pick_ip()?
                     .map_or(format!("127.0.0.1:{}", port), |addr| {
                         format!("{}:{}", addr, port)
                     });
@@ -211,4 +239,43 @@ mod tests {
         }
         Ok(())
     }
+
+    fn start_args(addr: &str, unix_addr: Option<&str>) -> super::StartArgs {
+        let store_path =
+            std::env::temp_dir().join(format!("range-server-cli-{}", std::process::id()));
+        super::StartArgs {
+            addr: Some(addr.to_owned()),
+            advertise_addr: None,
+            unix_addr: unix_addr.map(str::to_owned),
+            pd: None,
+            store_path: Some(store_path.to_str().unwrap().to_owned()),
+            config: Some("/non-existent/range-server.yaml".to_owned()),
+            log: None,
+        }
+    }
+
+    #[test]
+    fn test_create_config_with_unix_addr() -> anyhow::Result<()> {
+        ulog::try_init_log();
+        let args = start_args("127.0.0.1:10911", Some("unix:///tmp/range-server.sock"));
+        let config = args.create_config()?;
+        assert_eq!("127.0.0.1:10911", config.server.addr);
+        assert_eq!(
+            Some("unix:///tmp/range-server.sock"),
+            config.server.unix_addr.as_deref()
+        );
+        // Unix domain socket is never advertised, as remote clients cannot reach it.
+        assert!(config.server.advertise_addr.ends_with(":10911"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_config_rejects_unix_addr_as_server_addr() {
+        ulog::try_init_log();
+        let args = start_args("unix:///tmp/range-server.sock", None);
+        assert!(args.create_config().is_err());
+
+        let args = start_args("127.0.0.1:10911", Some("127.0.0.1:10912"));
+        assert!(args.create_config().is_err());
+    }
 }
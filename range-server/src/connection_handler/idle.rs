@@ -69,7 +69,7 @@ impl IdleHandler {
                 match connection.upgrade() {
                     Some(connection) => {
                         if handler.read_idle() && handler.write_idle() {
-                            conn_tracker.borrow_mut().remove(&connection.remote_addr());
+                            conn_tracker.borrow_mut().remove(connection.id());
                             if connection.close().is_ok() {
                                 info!(
                                     "Close connection to {} since read has been idle for {}ms and write has been idle for {}ms",
@@ -1,17 +1,20 @@
-use std::{collections::HashMap, net::SocketAddr};
+use std::collections::HashMap;
 
 use codec::frame::Frame;
 use local_sync::mpsc;
 use log::{info, warn};
 use protocol::rpc::header::{GoAwayFlags, OperationCode};
+use transport::Address;
 
 /// Track all existing connections and send the `GoAway` farewell frame to peers if graceful shutdown is desirable.
 pub(crate) struct ConnectionTracker {
-    /// Map between peer socket address to channel sender.
+    /// Map between connection ID to peer address and channel sender.
+    ///
+    /// Connections are keyed by ID, as peers of Unix domain sockets share the same address.
     ///
     /// Note the reader half of the channel is the connection writer, responsible of writing
     /// frames to remote peer.
-    connections: HashMap<SocketAddr, mpsc::unbounded::Tx<Frame>>,
+    connections: HashMap<u64, (Address, mpsc::unbounded::Tx<Frame>)>,
 }
 
 impl ConnectionTracker {
@@ -21,14 +24,20 @@ impl ConnectionTracker {
         }
     }
 
-    pub(crate) fn insert(&mut self, peer_address: SocketAddr, sender: mpsc::unbounded::Tx<Frame>) {
-        self.connections.insert(peer_address, sender);
-        info!("Start to track connection to {}", peer_address);
+    pub(crate) fn insert(
+        &mut self,
+        id: u64,
+        peer_address: Address,
+        sender: mpsc::unbounded::Tx<Frame>,
+    ) {
+        info!("Start to track connection#{} to {}", id, peer_address);
+        self.connections.insert(id, (peer_address, sender));
     }
 
-    pub(crate) fn remove(&mut self, peer_address: &SocketAddr) {
-        self.connections.remove(peer_address);
-        info!("Connection to {} disconnected", peer_address);
+    pub(crate) fn remove(&mut self, id: u64) {
+        if let Some((peer_address, _)) = self.connections.remove(&id) {
+            info!("Connection#{} to {} disconnected", id, peer_address);
+        }
     }
 
     /// Send `GoAway` frame to all existing connections.
@@ -41,24 +50,26 @@ impl ConnectionTracker {
     /// 2. Send `GoAway` frame to each existing connection, requesting them to disconnect as soon as possible;
     /// 3. Await until all connections are terminated after client migrated to other range servers.
     pub(crate) fn go_away(&mut self) {
-        self.connections.iter().for_each(|(peer_address, sender)| {
-            let mut frame = Frame::new(OperationCode::GOAWAY);
-            frame.flag_go_away(GoAwayFlags::SERVER_MAINTENANCE);
-            match sender.send(frame) {
-                Ok(_) => {
-                    info!(
-                        "GoAway frame sent to channel bounded to connection={}",
-                        peer_address
-                    );
-                }
-                Err(_e) => {
-                    warn!(
-                        "Failed to send GoAway frame to channel bounded to connection={}",
-                        peer_address
-                    );
+        self.connections
+            .values()
+            .for_each(|(peer_address, sender)| {
+                let mut frame = Frame::new(OperationCode::GOAWAY);
+                frame.flag_go_away(GoAwayFlags::SERVER_MAINTENANCE);
+                match sender.send(frame) {
+                    Ok(_) => {
+                        info!(
+                            "GoAway frame sent to channel bounded to connection={}",
+                            peer_address
+                        );
+                    }
+                    Err(_e) => {
+                        warn!(
+                            "Failed to send GoAway frame to channel bounded to connection={}",
+                            peer_address
+                        );
+                    }
                 }
-            }
-        });
+            });
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -29,6 +29,9 @@ pub(crate) struct Append {
     // |  Magic Code(1B)   |  Meta Len(4B)     |       Meta        |  Payload Len(4B) | Record Batch Payload  |
     // +-------------------+-------------------+-------------------+------------------------------------------+
     payload: Bytes,
+
+    /// The time in milliseconds to throttle the client, due to quota violation.
+    throttle_time_ms: i32,
 }
 
 impl Append {
@@ -47,9 +50,47 @@ impl Append {
 
         Ok(Append {
             payload: payload.clone(),
+            throttle_time_ms: 0,
         })
     }
 
+    pub(crate) fn set_throttle_time_ms(&mut self, throttle_time_ms: i32) {
+        self.throttle_time_ms = throttle_time_ms;
+    }
+
+    /// Streams and bytes that this request appends to, which are accounted by quotas.
+    pub(crate) fn usage(&self) -> Vec<(u64, u64)> {
+        let mut usage = vec![];
+        let mut pos = 0;
+        while let Ok((Some(entry), len)) = Payload::parse_append_entry(&self.payload[pos..]) {
+            usage.push((entry.stream_id, len as u64));
+            pos += len;
+        }
+        usage
+    }
+
+    /// Reject the request as the client has exceeded its quota.
+    pub(crate) fn throttled(&self, response: &mut Frame) {
+        let mut builder = FlatBufferBuilder::with_capacity(MIN_BUFFER_SIZE);
+        let message = builder.create_string("Quota exceeded");
+        let status = Status::create(
+            &mut builder,
+            &StatusArgs {
+                code: ErrorCode::TOO_MANY_REQUESTS,
+                message: Some(message),
+                detail: None,
+            },
+        );
+        let res_args = AppendResponseArgs {
+            throttle_time_ms: self.throttle_time_ms,
+            entries: None,
+            status: Some(status),
+        };
+        let response_header = AppendResponse::create(&mut builder, &res_args);
+        let res_header = finish_response_builder(&mut builder, response_header);
+        response.header = Some(res_header);
+    }
+
     fn replicated(&self) -> Result<bool, ErrorCode> {
         if let (Some(entry), _) =
             Payload::parse_append_entry(&self.payload).map_err(|_| ErrorCode::BAD_REQUEST)?
@@ -167,7 +208,7 @@ impl Append {
         let append_results_fb = builder.create_vector(&append_results);
 
         let res_args = AppendResponseArgs {
-            throttle_time_ms: 0,
+            throttle_time_ms: self.throttle_time_ms,
             entries: Some(append_results_fb),
             status: Some(ok_status),
         };
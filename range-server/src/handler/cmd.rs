@@ -100,6 +100,42 @@ impl<'a> Command<'a> {
         }
     }
 
+    /// Streams and bytes touched by this command, which are subject to quotas.
+    ///
+    /// Commands that are not subject to quotas return `None`.
+    pub(crate) fn usage(&self) -> Option<Vec<(u64, u64)>> {
+        match self {
+            Command::Append(cmd) => Some(cmd.usage()),
+            // Bytes of fetch responses are unknown beforehand and accounted once served.
+            Command::Fetch(cmd) => Some(vec![(cmd.stream_id(), 0)]),
+            // Ranges are created and sealed by placement driver and by writers opening streams or
+            // recovering from failures. They are rare compared with appends and fetches, while
+            // throttling them would stall failover and range rollover of the very streams being
+            // throttled, so they are exempt from quotas, as are liveness checks.
+            Command::CreateRange(_)
+            | Command::SealRange(_)
+            | Command::Ping(_)
+            | Command::Heartbeat(_) => None,
+        }
+    }
+
+    pub(crate) fn set_throttle_time_ms(&mut self, throttle_time_ms: i32) {
+        match self {
+            Command::Append(cmd) => cmd.set_throttle_time_ms(throttle_time_ms),
+            Command::Fetch(cmd) => cmd.set_throttle_time_ms(throttle_time_ms),
+            _ => {}
+        }
+    }
+
+    /// Fill the response with `TOO_MANY_REQUESTS` as the client has exceeded its quota.
+    pub(crate) fn throttled(&self, response: &mut Frame) {
+        match self {
+            Command::Append(cmd) => cmd.throttled(response),
+            Command::Fetch(cmd) => cmd.throttled(response),
+            _ => {}
+        }
+    }
+
     pub(crate) async fn apply<M>(&self, range_manager: Rc<M>, response: &mut Frame)
     where
         M: RangeManager,
@@ -15,6 +15,9 @@ use store::{error::FetchError, option::ReadOptions};
 pub(crate) struct Fetch<'a> {
     /// The append request already parsed by flatbuffers
     fetch_request: FetchRequest<'a>,
+
+    /// The time in milliseconds to throttle the client, due to quota violation. `-1` if quota is not enforced.
+    throttle_time_ms: i32,
 }
 
 impl<'a> Fetch<'a> {
@@ -41,7 +44,33 @@ impl<'a> Fetch<'a> {
             }
         };
         trace!("Received {fetch_request:?}");
-        Ok(Fetch { fetch_request })
+        Ok(Fetch {
+            fetch_request,
+            throttle_time_ms: -1,
+        })
+    }
+
+    pub(crate) fn stream_id(&self) -> u64 {
+        self.fetch_request.range().stream_id() as u64
+    }
+
+    pub(crate) fn set_throttle_time_ms(&mut self, throttle_time_ms: i32) {
+        self.throttle_time_ms = throttle_time_ms;
+    }
+
+    /// Reject the request as the client has exceeded its quota.
+    pub(crate) fn throttled(&self, response: &mut Frame) {
+        let mut builder = FlatBufferBuilder::with_capacity(MIN_BUFFER_SIZE);
+        let mut fetch_response = FetchResponseT::default();
+        let mut status = StatusT::default();
+        status.code = ErrorCode::TOO_MANY_REQUESTS;
+        status.message = Some("Quota exceeded".to_owned());
+        fetch_response.status = Box::new(status);
+        fetch_response.throttle_time_ms = self.throttle_time_ms;
+        let fetch_response = fetch_response.pack(&mut builder);
+        builder.finish(fetch_response, None);
+        let data = builder.finished_data();
+        response.header = Some(bytes::Bytes::copy_from_slice(data));
     }
 
     /// Apply the fetch requests to the store
@@ -69,6 +98,7 @@ impl<'a> Fetch<'a> {
             )
             .await;
         read_option(&mut option, cover_all);
+        let (stream_id, range_index) = (option.stream_id, option.range);
 
         let payload = if option.offset >= option.max_offset || option.max_bytes == 0 {
             None
@@ -111,8 +141,11 @@ impl<'a> Fetch<'a> {
 
         let fetch_response_args = FetchResponseArgs {
             status: Some(status),
-            throttle_time_ms: -1,
+            throttle_time_ms: self.throttle_time_ms,
             object_metadata_list: Some(objects),
+            committed: range_manager
+                .committed(stream_id, range_index)
+                .map_or(-1, |committed| committed as i64),
         };
         let fetch_response = FetchResponse::create(&mut builder, &fetch_response_args);
         builder.finish(fetch_response, None);
@@ -202,6 +235,10 @@ mod tests {
     use tokio::sync::mpsc;
 
     fn build_fetch_request() -> Frame {
+        build_fetch_request_with_offset(0)
+    }
+
+    fn build_fetch_request_with_offset(offset: i64) -> Frame {
         let mut request = Frame::new(OperationCode::FETCH);
         let mut builder = flatbuffers::FlatBufferBuilder::new();
         let mut fetch_request = FetchRequestT::default();
@@ -211,7 +248,7 @@ mod tests {
         range.start = 0;
         range.end = 100;
         fetch_request.range = Box::new(range);
-        fetch_request.offset = 0;
+        fetch_request.offset = offset;
         fetch_request.limit = 100;
         fetch_request.max_wait_ms = 1000;
         let fetch_request = fetch_request.pack(&mut builder);
@@ -266,4 +303,38 @@ mod tests {
             Ok(())
         })
     }
+
+    /// Fetching nothing tells the committed offset of the range, which readers that do not own the
+    /// range rely on to follow its confirm offset.
+    #[test]
+    fn test_fetch_committed() -> Result<(), Box<dyn Error>> {
+        let mut range_manager = MockRangeManager::default();
+        range_manager
+            .expect_has_range()
+            .once()
+            .returning_st(|_stream_id, _index| true);
+        range_manager.expect_fetch().never();
+        range_manager
+            .expect_get_objects()
+            .returning(|_, _, _, _, _| (vec![], false));
+        range_manager
+            .expect_committed()
+            .once()
+            .returning_st(|_stream_id, _index| Some(42));
+
+        tokio_uring::start(async move {
+            let range_manager = Rc::new(range_manager);
+            let request = build_fetch_request_with_offset(100);
+            let mut response = Frame::new(OperationCode::FETCH);
+            let handler =
+                super::Fetch::parse_frame(&request).expect("Failed to parse request frame");
+            handler.apply(range_manager, &mut response).await;
+            let header = response.header.unwrap();
+            let fetch_response = flatbuffers::root::<FetchResponse>(&header).unwrap();
+            assert_eq!(ErrorCode::OK, fetch_response.status().code());
+            assert_eq!(42, fetch_response.committed());
+            assert!(response.payload.is_none());
+            Ok(())
+        })
+    }
 }
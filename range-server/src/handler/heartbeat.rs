@@ -23,6 +23,10 @@ impl<'a> Heartbeat<'a> {
         Ok(Self { request })
     }
 
+    pub(crate) fn client_id(&self) -> Option<&str> {
+        self.request.client_id()
+    }
+
     pub(crate) async fn apply<M>(&self, _range_manager: Rc<M>, response: &mut Frame)
     where
         M: RangeManager,
@@ -1,7 +1,7 @@
 //! Server-side handlers, processors for requests of each kind.
 //!
 //! See details docs for each operation code
-use std::rc::Rc;
+use std::{cell::RefCell, rc::Rc};
 
 use bytes::Bytes;
 use local_sync::mpsc;
@@ -11,7 +11,7 @@ use codec::frame::Frame;
 use observation::metrics::range_server::{record_append_operation, record_fetch_operation};
 use protocol::rpc::header::{StatusT, SystemErrorT};
 
-use crate::range_manager::RangeManager;
+use crate::{quota::QuotaManager, range_manager::RangeManager};
 
 use self::cmd::Command;
 
@@ -23,7 +23,7 @@ mod go_away;
 mod heartbeat;
 mod ping;
 mod seal_range;
-mod util;
+pub(crate) mod util;
 
 /// Representation of the incoming request.
 ///
@@ -38,6 +38,12 @@ pub(crate) struct ServerCall<M> {
     pub(crate) sender: mpsc::unbounded::Tx<Frame>,
 
     pub(crate) range_manager: Rc<M>,
+
+    /// Quotas of the worker, shared by all connections.
+    pub(crate) quota: Rc<QuotaManager>,
+
+    /// Identifier of the client, initially the remote address and updated on receiving heartbeat.
+    pub(crate) client_id: Rc<RefCell<String>>,
 }
 
 impl<M> ServerCall<M>
@@ -66,7 +72,7 @@ where
         response.flag_end_of_response_stream();
 
         match Command::from_frame(&self.request) {
-            Ok(cmd) => {
+            Ok(mut cmd) => {
                 // Log the `cmd` object.
                 trace!(
                     "Command of frame[stream-id={}]: {}",
@@ -74,18 +80,38 @@ where
                     cmd
                 );
 
-                // Delegate the request to its dedicated handler.
-                cmd.apply(Rc::clone(&self.range_manager), &mut response)
-                    .await;
-
-                match cmd {
-                    Command::Append(_) => {
-                        record_append_operation(now.elapsed().as_micros() as u64);
+                if let Command::Heartbeat(ref heartbeat) = cmd {
+                    if let Some(client_id) = heartbeat.client_id() {
+                        if !client_id.is_empty() && *self.client_id.borrow() != client_id {
+                            *self.client_id.borrow_mut() = client_id.to_owned();
+                        }
                     }
-                    Command::Fetch(_) => {
-                        record_fetch_operation(now.elapsed().as_micros() as u64);
+                }
+
+                if self.apply_quota(&mut cmd, &mut response) {
+                    cmd.throttled(&mut response);
+                } else {
+                    // Delegate the request to its dedicated handler.
+                    cmd.apply(Rc::clone(&self.range_manager), &mut response)
+                        .await;
+
+                    match cmd {
+                        Command::Append(_) => {
+                            record_append_operation(now.elapsed().as_micros() as u64);
+                        }
+                        Command::Fetch(ref fetch) => {
+                            record_fetch_operation(now.elapsed().as_micros() as u64);
+                            if let Some(ref payload) = response.payload {
+                                let bytes = payload.iter().map(|buf| buf.len() as u64).sum();
+                                self.quota.record_bytes(
+                                    &self.client_id.borrow(),
+                                    fetch.stream_id(),
+                                    bytes,
+                                );
+                            }
+                        }
+                        _ => {}
                     }
-                    _ => {}
                 }
                 trace!(
                     "Generated {:?} response frame for stream-id={}",
@@ -126,18 +152,61 @@ where
             }
         };
     }
+
+    /// Account the command against quotas and populate how long the client should be throttled.
+    ///
+    /// Returns true if the command should be rejected, as the client is already throttled longer than
+    /// the configured max throttle time.
+    fn apply_quota(&self, cmd: &mut Command, response: &mut Frame) -> bool {
+        if !self.quota.enabled() {
+            return false;
+        }
+
+        let usage = match cmd.usage() {
+            Some(usage) => usage,
+            None => return false,
+        };
+
+        let client_id = self.client_id.borrow();
+        let streams = usage
+            .iter()
+            .map(|(stream_id, _)| *stream_id)
+            .collect::<Vec<_>>();
+        let throttle = self.quota.throttle_time(&client_id, &streams);
+        if throttle > self.quota.max_throttle_time() {
+            warn!(
+                "Reject request[stream-id={}] from client[{}], which is throttled for {}ms",
+                response.stream_id,
+                client_id,
+                throttle.as_millis()
+            );
+            cmd.set_throttle_time_ms(throttle.as_millis().min(i32::MAX as u128) as i32);
+            return true;
+        }
+
+        let throttle = self.quota.record(&client_id, &usage);
+        if !throttle.is_zero() {
+            trace!(
+                "Throttle client[{}] for {}ms",
+                client_id,
+                throttle.as_millis()
+            );
+        }
+        cmd.set_throttle_time_ms(throttle.as_millis().min(i32::MAX as u128) as i32);
+        false
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::rc::Rc;
+    use std::{cell::RefCell, rc::Rc, time::Duration};
 
     use local_sync::mpsc;
 
     use codec::frame::Frame;
     use protocol::rpc::header::{ErrorCode, OperationCode, SystemError};
 
-    use crate::range_manager::MockRangeManager;
+    use crate::{quota::QuotaManager, range_manager::MockRangeManager};
 
     use super::ServerCall;
 
@@ -152,6 +221,11 @@ mod tests {
             request,
             sender: tx,
             range_manager: Rc::new(range_manager),
+            quota: Rc::new(QuotaManager::new(
+                config::Quota::default(),
+                Duration::from_secs(5),
+            )),
+            client_id: Rc::new(RefCell::new(String::from("test-client"))),
         };
 
         tokio_uring::start(async move {
@@ -178,6 +252,11 @@ mod tests {
             request,
             sender: tx,
             range_manager: Rc::new(range_manager),
+            quota: Rc::new(QuotaManager::new(
+                config::Quota::default(),
+                Duration::from_secs(5),
+            )),
+            client_id: Rc::new(RefCell::new(String::from("test-client"))),
         };
 
         tokio_uring::start(async move {
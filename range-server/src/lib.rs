@@ -13,12 +13,14 @@ pub mod error;
 pub mod handler;
 pub(crate) mod range_manager;
 pub mod server;
+pub mod standalone;
 mod worker;
 mod worker_config;
 pub use crate::cli::Cli;
 pub(crate) mod connection_handler;
 pub(crate) mod heartbeat;
 pub(crate) mod metadata;
+pub(crate) mod quota;
 pub(crate) mod session;
 
 pub mod built_info {
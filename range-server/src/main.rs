@@ -6,27 +6,27 @@ use tokio::sync::broadcast;
 fn main() {
     let cli = Cli::parse();
 
-    let config = match cli.command {
-        Commands::Start(args) => {
-            args.init_log().unwrap();
-            match args.create_config() {
-                Ok(config) => config,
-                Err(e) => {
-                    eprintln!(
-                            "Failed to create configuration from the specified configuration file. Cause: {:?}",
-                            e
-                        );
-                    return;
-                }
-            }
-        }
-
+    let (standalone, args) = match cli.command {
+        Commands::Start(args) => (false, args),
+        Commands::Standalone(args) => (true, args),
         Commands::BuildInfo => {
             display_built_info();
             return;
         }
     };
 
+    args.init_log().unwrap();
+    let config = match args.create_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!(
+                "Failed to create configuration from the specified configuration file. Cause: {:?}",
+                e
+            );
+            return;
+        }
+    };
+
     let (shutdown_tx, _rx) = broadcast::channel(1);
     let tx = shutdown_tx.clone();
     ctrlc::set_handler(move || {
@@ -37,7 +37,12 @@ fn main() {
     })
     .expect("Failed to set Ctrl-C");
 
-    if let Err(e) = range_server::server::launch(config, shutdown_tx) {
+    let result = if standalone {
+        range_server::standalone::launch(config, shutdown_tx)
+    } else {
+        range_server::server::launch(config, shutdown_tx)
+    };
+    if let Err(e) = result {
         eprintln!("Failed to start range-server: {:?}", e);
     }
 }
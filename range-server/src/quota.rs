@@ -0,0 +1,290 @@
+//! Per-client and per-stream quotas.
+//!
+//! Each worker owns a `QuotaManager`, which tracks byte-rate and request-rate of clients and streams
+//! it serves using token buckets. Instead of delaying requests on the server side, range servers
+//! populate `throttle_time_ms` of responses and clients are expected to back off accordingly.
+//! Requests that would be throttled for longer than the configured max throttle time are rejected
+//! with `TOO_MANY_REQUESTS`.
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use config::Quota;
+
+/// Entries that stay idle longer than this are evicted while the tracked map is oversized.
+const IDLE_EVICTION_DURATION: Duration = Duration::from_secs(60);
+
+/// Threshold of tracked entries, beyond which idle entries are evicted.
+const MAX_TRACKED_ENTRIES: usize = 4096;
+
+/// A token bucket that allows a burst of one-second worth of tokens.
+///
+/// Tokens are allowed to go negative, which represents debt of the consumer. The debt is paid back
+/// as time elapses and the consumer should be throttled until then.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    /// Tokens refilled per second. `0` means unlimited.
+    rate: u64,
+
+    tokens: f64,
+
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if now > self.last_refill {
+            let elapsed = (now - self.last_refill).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+            self.last_refill = now;
+        }
+    }
+
+    /// Duration to wait before the bucket gets out of debt.
+    pub(crate) fn throttle_time(&mut self, now: Instant) -> Duration {
+        if self.rate == 0 {
+            return Duration::ZERO;
+        }
+        self.refill(now);
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate as f64)
+        }
+    }
+
+    /// Consume `amount` tokens and return the resulting throttle time.
+    pub(crate) fn consume(&mut self, amount: u64, now: Instant) -> Duration {
+        if self.rate == 0 {
+            return Duration::ZERO;
+        }
+        self.refill(now);
+        self.tokens -= amount as f64;
+        self.throttle_time(now)
+    }
+
+    fn idle(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_refill) >= IDLE_EVICTION_DURATION
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    bytes: TokenBucket,
+    requests: TokenBucket,
+}
+
+impl Buckets {
+    fn new(byte_rate: u64, request_rate: u64, now: Instant) -> Self {
+        Self {
+            bytes: TokenBucket::new(byte_rate, now),
+            requests: TokenBucket::new(request_rate, now),
+        }
+    }
+
+    fn throttle_time(&mut self, now: Instant) -> Duration {
+        self.bytes
+            .throttle_time(now)
+            .max(self.requests.throttle_time(now))
+    }
+
+    fn idle(&self, now: Instant) -> bool {
+        self.bytes.idle(now) && self.requests.idle(now)
+    }
+}
+
+fn evict_idle<K>(map: &mut HashMap<K, Buckets>, now: Instant)
+where
+    K: Eq + Hash,
+{
+    if map.len() > MAX_TRACKED_ENTRIES {
+        map.retain(|_, buckets| !buckets.idle(now));
+    }
+}
+
+pub(crate) struct QuotaManager {
+    quota: Quota,
+
+    max_throttle_time: Duration,
+
+    clients: RefCell<HashMap<String, Buckets>>,
+
+    streams: RefCell<HashMap<u64, Buckets>>,
+}
+
+impl QuotaManager {
+    pub(crate) fn new(quota: Quota, max_throttle_time: Duration) -> Self {
+        Self {
+            quota,
+            max_throttle_time,
+            clients: RefCell::new(HashMap::new()),
+            streams: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.quota.enabled()
+    }
+
+    pub(crate) fn max_throttle_time(&self) -> Duration {
+        self.max_throttle_time
+    }
+
+    /// Current throttle time of the given client and streams, without consuming any quota.
+    pub(crate) fn throttle_time(&self, client_id: &str, streams: &[u64]) -> Duration {
+        if !self.enabled() {
+            return Duration::ZERO;
+        }
+        let now = Instant::now();
+        let mut throttle = self
+            .clients
+            .borrow_mut()
+            .get_mut(client_id)
+            .map_or(Duration::ZERO, |buckets| buckets.throttle_time(now));
+        let mut tracked_streams = self.streams.borrow_mut();
+        for stream_id in streams {
+            if let Some(buckets) = tracked_streams.get_mut(stream_id) {
+                throttle = throttle.max(buckets.throttle_time(now));
+            }
+        }
+        throttle
+    }
+
+    /// Record a request of the client, which touches the given streams with the specified bytes.
+    ///
+    /// # Arguments
+    /// `client_id` - Identifier of the client.
+    /// `usage` - Pairs of stream-id and bytes of the request.
+    ///
+    /// # Returns
+    /// Duration the client should be throttled for.
+    pub(crate) fn record(&self, client_id: &str, usage: &[(u64, u64)]) -> Duration {
+        if !self.enabled() {
+            return Duration::ZERO;
+        }
+        let now = Instant::now();
+        let total_bytes: u64 = usage.iter().map(|(_, bytes)| bytes).sum();
+
+        let mut clients = self.clients.borrow_mut();
+        evict_idle(&mut clients, now);
+        let client = clients.entry(client_id.to_owned()).or_insert_with(|| {
+            Buckets::new(
+                self.quota.client_byte_rate,
+                self.quota.client_request_rate,
+                now,
+            )
+        });
+        let mut throttle = client
+            .bytes
+            .consume(total_bytes, now)
+            .max(client.requests.consume(1, now));
+
+        let mut streams = self.streams.borrow_mut();
+        evict_idle(&mut streams, now);
+        for (stream_id, bytes) in usage {
+            let stream = streams.entry(*stream_id).or_insert_with(|| {
+                Buckets::new(
+                    self.quota.stream_byte_rate,
+                    self.quota.stream_request_rate,
+                    now,
+                )
+            });
+            throttle = throttle
+                .max(stream.bytes.consume(*bytes, now))
+                .max(stream.requests.consume(1, now));
+        }
+        throttle
+    }
+
+    /// Record bytes served to the client after the request completes, for example, fetched records.
+    ///
+    /// Unlike `record`, request-rate quotas are not consumed.
+    pub(crate) fn record_bytes(&self, client_id: &str, stream_id: u64, bytes: u64) -> Duration {
+        if !self.enabled() {
+            return Duration::ZERO;
+        }
+        let now = Instant::now();
+        let mut throttle = self
+            .clients
+            .borrow_mut()
+            .get_mut(client_id)
+            .map_or(Duration::ZERO, |buckets| buckets.bytes.consume(bytes, now));
+        if let Some(buckets) = self.streams.borrow_mut().get_mut(&stream_id) {
+            throttle = throttle.max(buckets.bytes.consume(bytes, now));
+        }
+        throttle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use config::Quota;
+
+    use super::{QuotaManager, TokenBucket};
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(100, now);
+        assert_eq!(Duration::ZERO, bucket.consume(100, now));
+
+        // 50 tokens in debt, it takes half a second to pay back.
+        assert_eq!(Duration::from_millis(500), bucket.consume(50, now));
+
+        let later = now + Duration::from_millis(250);
+        assert_eq!(Duration::from_millis(250), bucket.throttle_time(later));
+
+        let later = now + Duration::from_secs(2);
+        assert_eq!(Duration::ZERO, bucket.throttle_time(later));
+        // Refill should never exceed one-second burst.
+        assert_eq!(Duration::from_millis(10), bucket.consume(101, later));
+    }
+
+    #[test]
+    fn test_unlimited_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(0, now);
+        assert_eq!(Duration::ZERO, bucket.consume(u32::MAX as u64, now));
+        assert_eq!(Duration::ZERO, bucket.throttle_time(now));
+    }
+
+    #[test]
+    fn test_quota_manager() {
+        let quota = Quota {
+            stream_request_rate: 1,
+            ..Default::default()
+        };
+        let manager = QuotaManager::new(quota, Duration::from_secs(5));
+        assert!(manager.enabled());
+        assert_eq!(Duration::ZERO, manager.record("client-0", &[(1, 1024)]));
+        assert!(manager.record("client-0", &[(1, 1024)]) > Duration::ZERO);
+        assert!(manager.throttle_time("client-1", &[1]) > Duration::ZERO);
+
+        // Other streams are not affected.
+        assert_eq!(Duration::ZERO, manager.throttle_time("client-0", &[2]));
+        assert_eq!(Duration::ZERO, manager.record("client-0", &[(2, 1024)]));
+    }
+
+    #[test]
+    fn test_quota_manager_disabled() {
+        let manager = QuotaManager::new(Quota::default(), Duration::from_secs(5));
+        assert!(!manager.enabled());
+        for _ in 0..10 {
+            assert_eq!(Duration::ZERO, manager.record("client-0", &[(1, 1 << 20)]));
+        }
+        assert_eq!(Duration::ZERO, manager.record_bytes("client-0", 1, 1 << 30));
+    }
+}
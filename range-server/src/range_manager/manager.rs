@@ -160,6 +160,7 @@ where
                     start_offset: 0,
                     epoch: 0,
                     deleted: false,
+                    ..Default::default()
                 };
                 let mut stream = Stream::new(metadata);
                 stream.create_range(range);
@@ -209,6 +210,7 @@ where
                 start_offset: 0,
                 epoch: 0,
                 deleted: false,
+                ..Default::default()
             };
             let mut stream = Stream::new(stream_metadata);
             stream.create_range(range.clone());
@@ -249,6 +251,11 @@ where
         self.get_range(stream_id, index).is_some()
     }
 
+    fn committed(&self, stream_id: u64, index: u32) -> Option<u64> {
+        self.get_range(stream_id, index)
+            .and_then(|range| range.committed())
+    }
+
     async fn get_objects(
         &self,
         stream_id: u64,
@@ -62,6 +62,9 @@ pub(crate) trait RangeManager {
     /// Check if the specified range is being served.
     fn has_range(&self, stream_id: u64, index: u32) -> bool;
 
+    /// Exclusive offset up to which records of the specified range are persisted, if any is.
+    fn committed(&self, stream_id: u64, index: u32) -> Option<u64>;
+
     /// Get objects that in the specified range.
     /// return (objects, cover_all)
     async fn get_objects(
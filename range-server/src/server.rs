@@ -5,9 +5,17 @@ use log::{error, info};
 use model::{error::EsError, resource::ResourceEvent};
 use object_storage::{object_storage::AsyncObjectStorage, ObjectStorage};
 use pd_client::pd_client::DefaultPlacementDriverClient;
-use std::{os::fd::AsRawFd, rc::Rc, sync::Arc, thread};
+use std::{
+    net::{SocketAddr, TcpListener},
+    os::fd::AsRawFd,
+    os::unix::net::UnixListener,
+    rc::Rc,
+    sync::Arc,
+    thread,
+};
 use store::{BufferedStore, ElasticStore, Store};
 use tokio::sync::{broadcast, mpsc::UnboundedReceiver, oneshot};
+use transport::Address;
 
 use crate::{
     metadata::{
@@ -24,6 +32,12 @@ struct Server {
     store: ElasticStore,
     shutdown: broadcast::Sender<()>,
     object_storage: AsyncObjectStorage,
+
+    /// Listener of the TCP address, shared among workers, if it is bound on a port picked by the OS.
+    tcp_listener: Option<TcpListener>,
+
+    /// Listener of the Unix domain socket, shared among workers, if `unix_addr` is configured.
+    unix_listener: Option<UnixListener>,
 }
 
 impl Server {
@@ -31,14 +45,84 @@ impl Server {
         config: Arc<Configuration>,
         store: ElasticStore,
         shutdown: broadcast::Sender<()>,
-    ) -> Self {
+        tcp_listener: Option<TcpListener>,
+    ) -> Result<Self, EsError> {
         let object_storage = AsyncObjectStorage::new(&config, store.clone());
-        Self {
+        let unix_listener = match &config.server.unix_addr {
+            Some(unix_addr) => Some(Self::bind_unix(unix_addr)?),
+            None => None,
+        };
+        Ok(Self {
             config,
             store,
             shutdown,
             object_storage,
+            tcp_listener,
+            unix_listener,
+        })
+    }
+
+    /// Bind the TCP address once if it is on port 0, pointing `addr`, along with `advertise_addr` if it
+    /// is also on port 0, to the port picked by the OS.
+    ///
+    /// Workers binding port 0 each with `SO_REUSEPORT` would end up on different ports, so the listener
+    /// is bound here and shared among workers, like the Unix domain socket.
+    fn bind_any_port(config: &mut Configuration) -> Result<Option<TcpListener>, EsError> {
+        let socket_addr = match config.server.addr.parse::<Address>() {
+            Ok(Address::Tcp(socket_addr)) if socket_addr.port() == 0 => socket_addr,
+            _ => return Ok(None),
+        };
+        let listener = TcpListener::bind(socket_addr).map_err(|e| {
+            error!("Failed to bind {}: {}", config.server.addr, e);
+            EsError::unexpected(&e.to_string())
+        })?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| EsError::unexpected(&e.to_string()))?
+            .port();
+        config.server.addr = SocketAddr::new(socket_addr.ip(), port).to_string();
+        if let Ok(mut advertise_addr) = config.server.advertise_addr.parse::<SocketAddr>() {
+            if advertise_addr.port() == 0 {
+                advertise_addr.set_port(port);
+                config.server.advertise_addr = advertise_addr.to_string();
+            }
         }
+        info!("Bound {}", config.server.addr);
+        Ok(Some(listener))
+    }
+
+    /// Bind the Unix domain socket at `unix://path`, which is served alongside the TCP address.
+    ///
+    /// Unlike TCP, which each worker binds with `SO_REUSEPORT`, a Unix domain socket path can only be
+    /// bound once, so the listener is bound here and shared among workers.
+    fn bind_unix(addr: &str) -> Result<UnixListener, EsError> {
+        let path = match addr.parse::<Address>() {
+            Ok(Address::Unix(path)) => path,
+            _ => {
+                return Err(EsError::unexpected(&format!(
+                    "Invalid unix domain socket address `{addr}`"
+                )))
+            }
+        };
+
+        // Remove the socket file left over by the previous run.
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| {
+                error!(
+                    "Failed to remove stale socket file {}: {}",
+                    path.display(),
+                    e
+                );
+                EsError::unexpected(&e.to_string())
+            })?;
+        }
+
+        let listener = UnixListener::bind(&path).map_err(|e| {
+            error!("Failed to bind {}: {}", addr, e);
+            EsError::unexpected(&e.to_string())
+        })?;
+        info!("Bound unix domain socket {}", path.display());
+        Ok(listener)
     }
 
     fn start_observation(&self) {
@@ -105,6 +189,23 @@ impl Server {
             None
         };
 
+        let tcp_listener = match &self.tcp_listener {
+            Some(listener) => Some(
+                listener
+                    .try_clone()
+                    .map_err(|e| EsError::unexpected(&e.to_string()))?,
+            ),
+            None => None,
+        };
+        let unix_listener = match &self.unix_listener {
+            Some(listener) => Some(
+                listener
+                    .try_clone()
+                    .map_err(|e| EsError::unexpected(&e.to_string()))?,
+            ),
+            None => None,
+        };
+
         thread::Builder::new()
             .name(thread_name)
             .spawn(move || {
@@ -113,6 +214,8 @@ impl Server {
                     server_config: Arc::clone(&server_config),
                     sharing_uring: store.as_raw_fd(),
                     primary,
+                    tcp_listener,
+                    unix_listener,
                 };
 
                 let store = Rc::new(BufferedStore::new(store));
@@ -149,7 +252,9 @@ impl Server {
     }
 }
 
-pub fn launch(config: Configuration, shutdown: broadcast::Sender<()>) -> Result<(), EsError> {
+pub fn launch(mut config: Configuration, shutdown: broadcast::Sender<()>) -> Result<(), EsError> {
+    // Bind before the store allocates server id, which is keyed by the advertise address.
+    let tcp_listener = Server::bind_any_port(&mut config)?;
     let (recovery_completion_tx, recovery_completion_rx) = oneshot::channel();
     // Note we move the configuration into store, letting it either allocate or read existing server-id for us.
     let store = ElasticStore::new(config, recovery_completion_tx).map_err(|e| {
@@ -176,7 +281,7 @@ pub fn launch(config: Configuration, shutdown: broadcast::Sender<()>) -> Result<
     ));
     let pd_client = DefaultPlacementDriverClient::new(client);
     let mut metadata_watcher = DefaultMetadataWatcher::new(pd_client);
-    let mut server = Server::new(config, store, shutdown);
+    let mut server = Server::new(config, store, shutdown, tcp_listener)?;
 
     // Build and start workers
     for core_id in worker_core_ids
@@ -1,16 +1,15 @@
-use std::{cell::RefCell, net::SocketAddr, rc::Rc, sync::Arc};
+use std::{cell::RefCell, rc::Rc, sync::Arc};
 
+use codec::{compression::Compression, frame::Frame};
 use config::Configuration;
 use local_sync::mpsc;
 use log::{info, trace, warn};
-use model::error::EsError;
-use protocol::rpc::header::ErrorCode;
-use tokio_uring::net::TcpStream;
+use protocol::rpc::header::OperationCode;
 use transport::connection::Connection;
 
 use crate::{
     connection_handler, connection_tracker::ConnectionTracker, handler::ServerCall,
-    range_manager::RangeManager,
+    quota::QuotaManager, range_manager::RangeManager,
 };
 
 pub(crate) struct Session<M> {
@@ -18,6 +17,7 @@ pub(crate) struct Session<M> {
     connection: Rc<Connection>,
     range_manager: Rc<M>,
     connection_tracker: Rc<RefCell<ConnectionTracker>>,
+    quota: Rc<QuotaManager>,
 }
 
 impl<M> Session<M>
@@ -26,24 +26,18 @@ where
 {
     pub(crate) fn new(
         config: Arc<Configuration>,
-        stream: TcpStream,
-        addr: SocketAddr,
+        connection: Connection,
         range_manager: Rc<M>,
         connection_tracker: Rc<RefCell<ConnectionTracker>>,
-    ) -> Result<Self, EsError> {
-        let stream = Connection::with_stream(stream, addr).map_err(|e| {
-            EsError::new(
-                ErrorCode::CONNECTION_BROKEN,
-                &format!("TcpStream is broken: {}", e),
-            )
-        })?;
-        let connection = Rc::new(stream);
-        Ok(Self {
+        quota: Rc<QuotaManager>,
+    ) -> Self {
+        Self {
             config,
-            connection,
+            connection: Rc::new(connection),
             range_manager,
             connection_tracker,
-        })
+            quota,
+        }
     }
 
     pub(crate) fn process(self) {
@@ -52,6 +46,7 @@ where
             Self::process0(
                 self.range_manager,
                 self.connection_tracker,
+                self.quota,
                 connection,
                 self.config,
             )
@@ -62,6 +57,7 @@ where
     async fn process0(
         range_manager: Rc<M>,
         connection_tracker: Rc<RefCell<ConnectionTracker>>,
+        quota: Rc<QuotaManager>,
         connection: Rc<Connection>,
         server_config: Arc<Configuration>,
     ) {
@@ -71,9 +67,11 @@ where
         // Put current connection into connection-tracker, such that when TERM/STOP signal is received,
         // servers send go-away frame to each connection, requesting clients to complete and migrate as soon
         // as possible.
-        connection_tracker
-            .borrow_mut()
-            .insert(connection.remote_addr(), tx.clone());
+        connection_tracker.borrow_mut().insert(
+            connection.id(),
+            connection.remote_addr().clone(),
+            tx.clone(),
+        );
 
         let idle_handler = connection_handler::idle::IdleHandler::new(
             Rc::downgrade(&connection),
@@ -81,6 +79,15 @@ where
             Rc::clone(&connection_tracker),
         );
 
+        // Clients are identified by remote address till their heartbeat requests, which carry client-id, arrive.
+        // Peers of Unix domain sockets share the listening address, so they are told apart by connection ID.
+        let client_id = if connection.remote_addr().is_unix() {
+            format!("{}#{}", connection.remote_addr(), connection.id())
+        } else {
+            connection.remote_addr().to_string()
+        };
+        let client_id = Rc::new(RefCell::new(client_id));
+
         // Coroutine to read requests from network connection
         let connection_ = Rc::clone(&connection);
         let read_idle_handler = Rc::clone(&idle_handler);
@@ -90,12 +97,22 @@ where
                     Ok(Some(frame)) => {
                         // Update last read instant.
                         read_idle_handler.on_read();
+
+                        if frame.operation_code == OperationCode::PING
+                            && frame.compression() != Compression::None
+                        {
+                            Self::negotiate_compression(&connection_, &server_config, &frame, &tx);
+                            continue;
+                        }
+
                         let sender = tx.clone();
                         let range_manager = Rc::clone(&range_manager);
                         let mut server_call = ServerCall {
                             request: frame,
                             sender,
                             range_manager,
+                            quota: Rc::clone(&quota),
+                            client_id: Rc::clone(&client_id),
                         };
                         tokio_uring::spawn(async move {
                             server_call.call().await;
@@ -120,9 +137,7 @@ where
                 }
             }
 
-            connection_tracker
-                .borrow_mut()
-                .remove(&connection_.remote_addr());
+            connection_tracker.borrow_mut().remove(connection_.id());
         });
 
         // Coroutine to write responses to network connection
@@ -165,4 +180,41 @@ where
             }
         });
     }
+
+    /// Reply the compression proposal of the client, carried by a PING request.
+    ///
+    /// The proposal is accepted, by flagging the same compression on the pong, if it is enabled by the
+    /// server; Otherwise, the pong is not flagged and frames of the connection stay uncompressed.
+    fn negotiate_compression(
+        connection: &Connection,
+        server_config: &Configuration,
+        request: &Frame,
+        tx: &mpsc::unbounded::Tx<Frame>,
+    ) {
+        let proposal = request.compression();
+        let mut response = Frame::new(OperationCode::PING);
+        response.stream_id = request.stream_id;
+        response.flag_response();
+        if server_config.server.compressions.contains(&proposal) {
+            response.flag_compression(proposal);
+            connection.set_compression(proposal);
+            info!(
+                "Compress frames of connection {} with {:?}",
+                connection, proposal
+            );
+        } else {
+            info!(
+                "Decline compression {:?} proposed by {}",
+                proposal,
+                connection.remote_addr()
+            );
+        }
+
+        if tx.send(response).is_err() {
+            warn!(
+                "Failed to reply compression proposal of {}",
+                connection.remote_addr()
+            );
+        }
+    }
 }
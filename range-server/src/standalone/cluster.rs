@@ -0,0 +1,811 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use log::info;
+use model::error::EsError;
+use protocol::rpc::header::{
+    ErrorCode, EventType, ObjT, OffloadOwnerT, RangeServerState, RangeServerT, RangeT,
+    ReplicaLayout, ResourceEventT, ResourceT, ResourceType, StreamT,
+};
+
+/// Max number of resource events kept for watchers. Watchers lagging behind are told to list again.
+const MAX_EVENTS: usize = 4096;
+
+/// Metadata of the single-node cluster, which the embedded placement driver serves.
+///
+/// Every change to range servers, streams, ranges and objects bumps the resource version and is
+/// recorded as a resource event, from which watchers catch up. Metadata is kept in memory only.
+#[derive(Debug, Default)]
+pub(crate) struct Cluster {
+    next_server_id: i32,
+    next_stream_id: i64,
+
+    range_servers: BTreeMap<i32, RangeServerT>,
+    streams: BTreeMap<i64, StreamT>,
+    /// Ranges keyed by stream id and range index.
+    ranges: BTreeMap<(i64, i32), RangeT>,
+    /// Objects keyed by stream id, range index and start offset.
+    objects: BTreeMap<(i64, i32, i64), ObjT>,
+    kv: BTreeMap<Vec<u8>, Vec<u8>>,
+
+    version: i64,
+    /// Recent resource events along with their versions, in ascending order of versions.
+    events: VecDeque<(i64, ResourceEventT)>,
+
+    /// Count of ranges placed, used to spread ranges among range servers.
+    placements: usize,
+}
+
+impl Cluster {
+    pub(crate) fn version(&self) -> i64 {
+        self.version
+    }
+
+    pub(crate) fn allocate_id(&mut self, host: &str) -> i32 {
+        let id = self.next_server_id;
+        self.next_server_id += 1;
+        info!("Allocated server id {id} for host {host}");
+        id
+    }
+
+    /// Register the range server, or refresh its address and state.
+    pub(crate) fn register_range_server(&mut self, range_server: RangeServerT) {
+        if range_server.server_id < 0 {
+            return;
+        }
+        let event_type = match self.range_servers.get(&range_server.server_id) {
+            Some(current) if *current == range_server => return,
+            Some(_) => EventType::EVENT_MODIFIED,
+            None => {
+                info!(
+                    "Range server[id={}, addr={}] registered",
+                    range_server.server_id, range_server.advertise_addr
+                );
+                EventType::EVENT_ADDED
+            }
+        };
+        self.range_servers
+            .insert(range_server.server_id, range_server.clone());
+        self.publish(event_type, range_server_resource(range_server));
+    }
+
+    /// Whether any range server is registered and writable.
+    pub(crate) fn has_writable_range_server(&self) -> bool {
+        self.range_servers.values().any(writable)
+    }
+
+    pub(crate) fn create_stream(&mut self, mut stream: StreamT) -> Result<StreamT, EsError> {
+        if stream.replica <= 0 || stream.ack_count <= 0 || stream.ack_count > stream.replica {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                "Replica and ack count of stream should be positive, and ack count is at most replica",
+            ));
+        }
+        stream.stream_id = self.next_stream_id;
+        self.next_stream_id += 1;
+        stream.start_offset = 0;
+        stream.epoch = 0;
+        stream.deleted = false;
+        self.streams.insert(stream.stream_id, stream.clone());
+        self.publish(EventType::EVENT_ADDED, stream_resource(stream.clone()));
+        Ok(stream)
+    }
+
+    pub(crate) fn describe_stream(&self, stream_id: i64) -> Result<StreamT, EsError> {
+        self.streams
+            .get(&stream_id)
+            .cloned()
+            .ok_or_else(|| stream_not_exist(stream_id))
+    }
+
+    /// Update fields of the stream that are set, fencing writers of smaller epochs if the epoch is.
+    pub(crate) fn update_stream(&mut self, update: StreamT) -> Result<StreamT, EsError> {
+        let stream = self.stream_mut(update.stream_id)?;
+        if update.epoch >= 0 {
+            if update.epoch < stream.epoch {
+                return Err(expired_epoch(update.stream_id, update.epoch, stream.epoch));
+            }
+            stream.epoch = update.epoch;
+        }
+        if update.replica > 0 {
+            stream.replica = update.replica;
+        }
+        if update.ack_count > 0 {
+            stream.ack_count = update.ack_count;
+        }
+        if update.retention_period_ms >= 0 {
+            stream.retention_period_ms = update.retention_period_ms;
+        }
+        if stream.ack_count > stream.replica {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                "Ack count of stream should be at most replica",
+            ));
+        }
+        let stream = stream.clone();
+        self.publish(EventType::EVENT_MODIFIED, stream_resource(stream.clone()));
+        Ok(stream)
+    }
+
+    /// Delete the stream along with its ranges and objects.
+    pub(crate) fn delete_stream(&mut self, stream_id: i64, epoch: i64) -> Result<StreamT, EsError> {
+        self.check_epoch(stream_id, epoch)?;
+        let mut stream = self
+            .streams
+            .remove(&stream_id)
+            .ok_or_else(|| stream_not_exist(stream_id))?;
+        stream.deleted = true;
+
+        let ranges = self
+            .ranges
+            .extract_if(|(id, _), _| *id == stream_id)
+            .map(|(_, range)| range)
+            .collect::<Vec<_>>();
+        let objects = self
+            .objects
+            .extract_if(|(id, _, _), _| *id == stream_id)
+            .map(|(_, object)| object)
+            .collect::<Vec<_>>();
+        for object in objects {
+            self.publish(EventType::EVENT_DELETED, object_resource(object));
+        }
+        for range in ranges {
+            self.publish(EventType::EVENT_DELETED, range_resource(range));
+        }
+        self.publish(EventType::EVENT_DELETED, stream_resource(stream.clone()));
+        info!("Deleted stream[id={stream_id}]");
+        Ok(stream)
+    }
+
+    /// Trim the stream to `min_offset`, deleting ranges and objects entirely before it.
+    ///
+    /// # Returns
+    /// The stream and its first range after trim, if any.
+    pub(crate) fn trim_stream(
+        &mut self,
+        stream_id: i64,
+        epoch: i64,
+        min_offset: i64,
+    ) -> Result<(StreamT, Option<RangeT>), EsError> {
+        self.check_epoch(stream_id, epoch)?;
+        let stream = self.stream_mut(stream_id)?;
+        if min_offset > stream.start_offset {
+            stream.start_offset = min_offset;
+            let stream = stream.clone();
+            self.publish(EventType::EVENT_MODIFIED, stream_resource(stream));
+
+            let last_index = self.last_range(stream_id).map(|range| range.index);
+            let deleted = self
+                .ranges
+                .extract_if(|(id, index), range| {
+                    // The last range is kept, from which the next range starts.
+                    *id == stream_id
+                        && Some(*index) != last_index
+                        && range.end >= 0
+                        && range.end <= min_offset
+                })
+                .map(|(_, range)| range)
+                .collect::<Vec<_>>();
+            let objects = self
+                .objects
+                .extract_if(|(id, _, start), object| {
+                    *id == stream_id && *start + object.end_offset_delta as i64 <= min_offset
+                })
+                .map(|(_, object)| object)
+                .collect::<Vec<_>>();
+            let trimmed = self
+                .ranges
+                .range_mut((stream_id, i32::MIN)..=(stream_id, i32::MAX))
+                .filter(|(_, range)| {
+                    range.start < min_offset && (range.end < 0 || range.end > min_offset)
+                })
+                .map(|(_, range)| {
+                    range.start = min_offset;
+                    range.clone()
+                })
+                .collect::<Vec<_>>();
+            for object in objects {
+                self.publish(EventType::EVENT_DELETED, object_resource(object));
+            }
+            for range in deleted {
+                self.publish(EventType::EVENT_DELETED, range_resource(range));
+            }
+            for range in trimmed {
+                self.publish(EventType::EVENT_MODIFIED, range_resource(range));
+            }
+        }
+        let stream = self.describe_stream(stream_id)?;
+        let first_range = self
+            .ranges
+            .range((stream_id, i32::MIN)..=(stream_id, i32::MAX))
+            .next()
+            .map(|(_, range)| range.clone());
+        Ok((stream, first_range))
+    }
+
+    /// List ranges of the stream if `stream_id` is non-negative, and those placed on the range
+    /// server if `server_id` is non-negative.
+    pub(crate) fn list_ranges(&self, stream_id: i64, server_id: i32) -> Vec<RangeT> {
+        self.ranges
+            .values()
+            .filter(|range| stream_id < 0 || range.stream_id == stream_id)
+            .filter(|range| server_id < 0 || placed_on(range, server_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Create the next range of the stream, placing its replicas on writable range servers.
+    ///
+    /// Replica count of ranges of full copies is capped by the number of writable range servers, such
+    /// that streams of multiple replicas are writable on a single node.
+    pub(crate) fn create_range(&mut self, mut range: RangeT) -> Result<RangeT, EsError> {
+        self.check_epoch(range.stream_id, range.epoch)?;
+        let stream = self.describe_stream(range.stream_id)?;
+        if let Some(last) = self.last_range(range.stream_id) {
+            if last.index == range.index && last.start == range.start && last.epoch == range.epoch {
+                // Retried by the writer.
+                return Ok(last.clone());
+            }
+            if last.end < 0 {
+                return Err(EsError::new(
+                    ErrorCode::CREATE_RANGE_BEFORE_SEAL,
+                    &format!(
+                        "Range[{}#{}] should be sealed before creating range {}",
+                        range.stream_id, last.index, range.index
+                    ),
+                ));
+            }
+            if range.index != last.index + 1 || range.start != last.end {
+                return Err(EsError::new(
+                    ErrorCode::BAD_REQUEST,
+                    &format!(
+                        "Range {} starting at {} does not follow range[{}#{}] ending at {}",
+                        range.index, range.start, range.stream_id, last.index, last.end
+                    ),
+                ));
+            }
+        } else if range.index != 0 || range.start != stream.start_offset {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                &format!(
+                    "First range of stream[id={}] should be indexed 0 and start at {}",
+                    range.stream_id, stream.start_offset
+                ),
+            ));
+        }
+
+        let candidates = self
+            .range_servers
+            .values()
+            .filter(|server| writable(server))
+            .cloned()
+            .collect::<Vec<_>>();
+        let replica_count = match range.replica_layout {
+            ReplicaLayout::REPLICA_LAYOUT_FULL => (stream.replica as usize).min(candidates.len()),
+            _ => stream.replica as usize,
+        };
+        if replica_count == 0 || candidates.len() < replica_count {
+            return Err(EsError::new(
+                ErrorCode::PD_NO_AVAILABLE_RS,
+                &format!(
+                    "{} writable range servers are not enough for {} replicas",
+                    candidates.len(),
+                    stream.replica
+                ),
+            ));
+        }
+        let servers = (0..replica_count)
+            .map(|i| candidates[(self.placements + i) % candidates.len()].clone())
+            .collect::<Vec<_>>();
+        self.placements += 1;
+
+        range.end = -1;
+        range.replica_count = replica_count as i8;
+        range.ack_count = stream.ack_count.min(replica_count as i8);
+        if range.replica_layout != ReplicaLayout::REPLICA_LAYOUT_FULL
+            && (range.data_shards <= 0
+                || range.data_shards >= range.replica_count
+                || range.data_shards > range.ack_count)
+        {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                "Data shards should be less than replica count and at most ack count",
+            ));
+        }
+        let mut owner = OffloadOwnerT::default();
+        owner.server_id = servers[0].server_id;
+        owner.epoch = 0;
+        range.offload_owner = Some(Box::new(owner));
+        range.servers = Some(servers);
+
+        info!(
+            "Created range[{}#{}] starting at {}",
+            range.stream_id, range.index, range.start
+        );
+        self.ranges
+            .insert((range.stream_id, range.index), range.clone());
+        self.publish(EventType::EVENT_ADDED, range_resource(range.clone()));
+        Ok(range)
+    }
+
+    /// Seal the range with the given end offset.
+    pub(crate) fn seal_range(&mut self, request: &RangeT) -> Result<RangeT, EsError> {
+        self.check_epoch(request.stream_id, request.epoch)?;
+        let range = self
+            .ranges
+            .get_mut(&(request.stream_id, request.index))
+            .ok_or_else(|| {
+                EsError::new(
+                    ErrorCode::RANGE_NOT_FOUND,
+                    &format!(
+                        "Range[{}#{}] is not found",
+                        request.stream_id, request.index
+                    ),
+                )
+            })?;
+        if range.end >= 0 {
+            if range.end == request.end {
+                return Ok(range.clone());
+            }
+            return Err(EsError::new(
+                ErrorCode::RANGE_ALREADY_SEALED,
+                &format!(
+                    "Range[{}#{}] is already sealed at {}",
+                    range.stream_id, range.index, range.end
+                ),
+            ));
+        }
+        if request.end < range.start {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                &format!(
+                    "End offset {} is less than start offset {} of range[{}#{}]",
+                    request.end, range.start, range.stream_id, range.index
+                ),
+            ));
+        }
+        range.end = request.end;
+        let range = range.clone();
+        info!(
+            "Sealed range[{}#{}] at {}",
+            range.stream_id, range.index, range.end
+        );
+        self.publish(EventType::EVENT_MODIFIED, range_resource(range.clone()));
+        Ok(range)
+    }
+
+    pub(crate) fn commit_object(&mut self, object: ObjT) -> Result<(), EsError> {
+        if !self
+            .ranges
+            .contains_key(&(object.stream_id, object.range_index))
+        {
+            return Err(EsError::new(
+                ErrorCode::RANGE_NOT_FOUND,
+                &format!(
+                    "Range[{}#{}] of object is not found",
+                    object.stream_id, object.range_index
+                ),
+            ));
+        }
+        let key = (object.stream_id, object.range_index, object.start_offset);
+        let event_type = if self.objects.contains_key(&key) {
+            EventType::EVENT_MODIFIED
+        } else {
+            EventType::EVENT_ADDED
+        };
+        self.objects.insert(key, object.clone());
+        self.publish(event_type, object_resource(object));
+        Ok(())
+    }
+
+    /// List resources of the given types from `offset`, at most `limit` of them unless it is not
+    /// positive.
+    ///
+    /// # Returns
+    /// Resources listed, and the offset to continue listing from if there are more.
+    pub(crate) fn list_resources(
+        &self,
+        types: &[ResourceType],
+        offset: usize,
+        limit: i32,
+    ) -> (Vec<ResourceT>, Option<usize>) {
+        let limit = if limit > 0 {
+            limit as usize
+        } else {
+            usize::MAX
+        };
+        let mut resources = Vec::new();
+        for resource_type in types {
+            match *resource_type {
+                ResourceType::RESOURCE_RANGE_SERVER => resources.extend(
+                    self.range_servers
+                        .values()
+                        .cloned()
+                        .map(range_server_resource),
+                ),
+                ResourceType::RESOURCE_STREAM => {
+                    resources.extend(self.streams.values().cloned().map(stream_resource))
+                }
+                ResourceType::RESOURCE_RANGE => {
+                    resources.extend(self.ranges.values().cloned().map(range_resource))
+                }
+                ResourceType::RESOURCE_OBJECT => {
+                    resources.extend(self.objects.values().cloned().map(object_resource))
+                }
+                _ => {}
+            }
+        }
+        let end = offset.saturating_add(limit).min(resources.len());
+        let continuation = (end < resources.len()).then_some(end);
+        let page = resources
+            .into_iter()
+            .skip(offset)
+            .take(end.saturating_sub(offset))
+            .collect();
+        (page, continuation)
+    }
+
+    /// Events of resources of the given types after `version`, leaving out events of ranges of
+    /// streams other than `stream_id` if it is given.
+    ///
+    /// # Returns
+    /// Events and the version they bring watchers to, or `PD_COMPACTED` if events after `version`
+    /// are no longer kept.
+    pub(crate) fn events_after(
+        &self,
+        types: &[ResourceType],
+        stream_id: Option<i64>,
+        version: i64,
+    ) -> Result<(Vec<ResourceEventT>, i64), EsError> {
+        if let Some((oldest, _)) = self.events.front() {
+            if version + 1 < *oldest {
+                return Err(EsError::new(
+                    ErrorCode::PD_COMPACTED,
+                    &format!("Resource version {version} is compacted, the oldest is {oldest}"),
+                ));
+            }
+        }
+        let events = self
+            .events
+            .iter()
+            .filter(|(v, _)| *v > version)
+            .filter(|(_, event)| types.contains(&event.resource.type_))
+            .filter(|(_, event)| match (stream_id, &event.resource.range) {
+                (Some(stream_id), Some(range)) => range.stream_id == stream_id,
+                _ => true,
+            })
+            .map(|(_, event)| event.clone())
+            .collect();
+        Ok((events, self.version.max(version)))
+    }
+
+    /// Key-value pairs of `key`, or of `[key, range_end)` if `range_end` is given, where a
+    /// `range_end` of `[0]` means all keys no less than `key`.
+    pub(crate) fn kv_range(
+        &self,
+        key: &[u8],
+        range_end: Option<&[u8]>,
+        limit: i64,
+    ) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let limit = if limit > 0 {
+            limit as usize
+        } else {
+            usize::MAX
+        };
+        self.kv
+            .iter()
+            .filter(|(k, _)| kv_matches(k, key, range_end))
+            .take(limit)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    pub(crate) fn kv_put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.kv.insert(key, value);
+    }
+
+    /// Put the key-value pair if the key holds `expected`, or does not exist if `expected` is `None`.
+    pub(crate) fn kv_compare_and_put(
+        &mut self,
+        key: Vec<u8>,
+        expected: Option<&[u8]>,
+        value: Vec<u8>,
+    ) -> Result<(), EsError> {
+        if self.kv.get(&key).map(|v| &v[..]) != expected {
+            return Err(EsError::new(
+                ErrorCode::PRECONDITION_FAILED,
+                "Key does not hold the expected value",
+            ));
+        }
+        self.kv.insert(key, value);
+        Ok(())
+    }
+
+    /// Delete keys as `kv_range` selects, returning the number of deleted keys.
+    pub(crate) fn kv_delete_range(&mut self, key: &[u8], range_end: Option<&[u8]>) -> usize {
+        self.kv
+            .extract_if(|k, _| kv_matches(k, key, range_end))
+            .count()
+    }
+
+    fn stream_mut(&mut self, stream_id: i64) -> Result<&mut StreamT, EsError> {
+        self.streams
+            .get_mut(&stream_id)
+            .ok_or_else(|| stream_not_exist(stream_id))
+    }
+
+    /// Reject requests of writers whose epoch is fenced.
+    fn check_epoch(&self, stream_id: i64, epoch: i64) -> Result<(), EsError> {
+        let stream = self
+            .streams
+            .get(&stream_id)
+            .ok_or_else(|| stream_not_exist(stream_id))?;
+        if epoch < stream.epoch {
+            return Err(expired_epoch(stream_id, epoch, stream.epoch));
+        }
+        Ok(())
+    }
+
+    fn last_range(&self, stream_id: i64) -> Option<&RangeT> {
+        self.ranges
+            .range((stream_id, i32::MIN)..=(stream_id, i32::MAX))
+            .next_back()
+            .map(|(_, range)| range)
+    }
+
+    fn publish(&mut self, event_type: EventType, resource: ResourceT) {
+        self.version += 1;
+        let mut event = ResourceEventT::default();
+        event.type_ = event_type;
+        event.resource = Box::new(resource);
+        self.events.push_back((self.version, event));
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+}
+
+fn writable(range_server: &RangeServerT) -> bool {
+    !matches!(
+        range_server.state,
+        RangeServerState::RANGE_SERVER_STATE_READ_ONLY
+            | RangeServerState::RANGE_SERVER_STATE_OFFLINE
+    )
+}
+
+fn placed_on(range: &RangeT, server_id: i32) -> bool {
+    range.servers.as_ref().map_or(false, |servers| {
+        servers.iter().any(|s| s.server_id == server_id)
+    })
+}
+
+fn kv_matches(k: &[u8], key: &[u8], range_end: Option<&[u8]>) -> bool {
+    match range_end {
+        Some([0]) => k >= key,
+        Some(end) => k >= key && k < end,
+        None => k == key,
+    }
+}
+
+fn stream_not_exist(stream_id: i64) -> EsError {
+    EsError::new(
+        ErrorCode::STREAM_NOT_EXIST,
+        &format!("Stream {stream_id} does not exist"),
+    )
+}
+
+fn expired_epoch(stream_id: i64, epoch: i64, current: i64) -> EsError {
+    EsError::new(
+        ErrorCode::EXPIRED_STREAM_EPOCH,
+        &format!("Epoch {epoch} of stream[id={stream_id}] is fenced by epoch {current}"),
+    )
+}
+
+fn range_server_resource(range_server: RangeServerT) -> ResourceT {
+    let mut resource = ResourceT::default();
+    resource.type_ = ResourceType::RESOURCE_RANGE_SERVER;
+    resource.range_server = Some(Box::new(range_server));
+    resource
+}
+
+fn stream_resource(stream: StreamT) -> ResourceT {
+    let mut resource = ResourceT::default();
+    resource.type_ = ResourceType::RESOURCE_STREAM;
+    resource.stream = Some(Box::new(stream));
+    resource
+}
+
+fn range_resource(range: RangeT) -> ResourceT {
+    let mut resource = ResourceT::default();
+    resource.type_ = ResourceType::RESOURCE_RANGE;
+    resource.range = Some(Box::new(range));
+    resource
+}
+
+fn object_resource(object: ObjT) -> ResourceT {
+    let mut resource = ResourceT::default();
+    resource.type_ = ResourceType::RESOURCE_OBJECT;
+    resource.object = Some(Box::new(object));
+    resource
+}
+
+#[cfg(test)]
+mod tests {
+    use protocol::rpc::header::{
+        ErrorCode, EventType, RangeServerState, RangeServerT, RangeT, ResourceType, StreamT,
+    };
+
+    use super::Cluster;
+
+    fn range_server(server_id: i32) -> RangeServerT {
+        let mut range_server = RangeServerT::default();
+        range_server.server_id = server_id;
+        range_server.advertise_addr = format!("127.0.0.1:{}", 10911 + server_id);
+        range_server.state = RangeServerState::RANGE_SERVER_STATE_READ_WRITE;
+        range_server
+    }
+
+    fn stream(replica: i8, ack_count: i8) -> StreamT {
+        let mut stream = StreamT::default();
+        stream.replica = replica;
+        stream.ack_count = ack_count;
+        stream.retention_period_ms = 3600 * 1000;
+        stream
+    }
+
+    fn range(stream_id: i64, epoch: i64, index: i32, start: i64, end: i64) -> RangeT {
+        let mut range = RangeT::default();
+        range.stream_id = stream_id;
+        range.epoch = epoch;
+        range.index = index;
+        range.start = start;
+        range.end = end;
+        range
+    }
+
+    #[test]
+    fn test_stream_lifecycle() {
+        let mut cluster = Cluster::default();
+        assert_eq!(0, cluster.allocate_id("localhost"));
+        assert_eq!(1, cluster.allocate_id("localhost"));
+
+        // No range server to place ranges on.
+        let stream_id = cluster.create_stream(stream(3, 2)).unwrap().stream_id;
+        assert_eq!(
+            ErrorCode::PD_NO_AVAILABLE_RS,
+            cluster
+                .create_range(range(stream_id, 0, 0, 0, -1))
+                .unwrap_err()
+                .code
+        );
+
+        // Replica count is capped by the single range server.
+        cluster.register_range_server(range_server(0));
+        let first = cluster.create_range(range(stream_id, 0, 0, 0, -1)).unwrap();
+        assert_eq!(1, first.replica_count);
+        assert_eq!(1, first.ack_count);
+        assert_eq!(0, first.servers.unwrap()[0].server_id);
+        assert_eq!(
+            ErrorCode::CREATE_RANGE_BEFORE_SEAL,
+            cluster
+                .create_range(range(stream_id, 0, 1, 0, -1))
+                .unwrap_err()
+                .code
+        );
+
+        // A new writer fences the old one.
+        let mut update = StreamT::default();
+        update.stream_id = stream_id;
+        update.epoch = 1;
+        assert_eq!(1, cluster.update_stream(update).unwrap().epoch);
+        assert_eq!(
+            ErrorCode::EXPIRED_STREAM_EPOCH,
+            cluster
+                .seal_range(&range(stream_id, 0, 0, 0, 100))
+                .unwrap_err()
+                .code
+        );
+        assert_eq!(
+            100,
+            cluster
+                .seal_range(&range(stream_id, 1, 0, 0, 100))
+                .unwrap()
+                .end
+        );
+        assert_eq!(
+            ErrorCode::RANGE_ALREADY_SEALED,
+            cluster
+                .seal_range(&range(stream_id, 1, 0, 0, 80))
+                .unwrap_err()
+                .code
+        );
+        cluster
+            .create_range(range(stream_id, 1, 1, 100, -1))
+            .unwrap();
+        assert_eq!(2, cluster.list_ranges(stream_id, -1).len());
+        assert_eq!(2, cluster.list_ranges(-1, 0).len());
+        assert!(cluster.list_ranges(-1, 1).is_empty());
+
+        // Sealed ranges before the new start offset are deleted.
+        let (trimmed, first) = cluster.trim_stream(stream_id, 1, 120).unwrap();
+        assert_eq!(120, trimmed.start_offset);
+        let first = first.unwrap();
+        assert_eq!((1, 120), (first.index, first.start));
+
+        cluster.delete_stream(stream_id, 1).unwrap();
+        assert!(cluster.list_ranges(stream_id, -1).is_empty());
+        assert_eq!(
+            ErrorCode::STREAM_NOT_EXIST,
+            cluster.describe_stream(stream_id).unwrap_err().code
+        );
+    }
+
+    #[test]
+    fn test_list_and_watch() {
+        let mut cluster = Cluster::default();
+        cluster.register_range_server(range_server(0));
+        cluster.register_range_server(range_server(0));
+        let stream_id = cluster.create_stream(stream(1, 1)).unwrap().stream_id;
+        cluster.create_range(range(stream_id, 0, 0, 0, -1)).unwrap();
+        let version = cluster.version();
+        assert_eq!(3, version);
+
+        let types = [ResourceType::RESOURCE_STREAM, ResourceType::RESOURCE_RANGE];
+        let (page, continuation) = cluster.list_resources(&types, 0, 1);
+        assert_eq!(ResourceType::RESOURCE_STREAM, page[0].type_);
+        assert_eq!(Some(1), continuation);
+        let (page, continuation) = cluster.list_resources(&types, 1, 1);
+        assert_eq!(ResourceType::RESOURCE_RANGE, page[0].type_);
+        assert_eq!(None, continuation);
+
+        let (events, watched) = cluster.events_after(&types, None, version).unwrap();
+        assert!(events.is_empty());
+        assert_eq!(version, watched);
+
+        cluster.seal_range(&range(stream_id, 0, 0, 0, 10)).unwrap();
+        let (events, watched) = cluster.events_after(&types, None, version).unwrap();
+        assert_eq!(1, events.len());
+        assert_eq!(EventType::EVENT_MODIFIED, events[0].type_);
+        assert_eq!(version + 1, watched);
+
+        // Range servers are not watched.
+        let (events, _) = cluster.events_after(&types, None, 0).unwrap();
+        assert_eq!(3, events.len());
+
+        // Ranges of other streams are left out, while streams are not.
+        let (events, _) = cluster
+            .events_after(&types, Some(stream_id + 1), 0)
+            .unwrap();
+        assert_eq!(1, events.len());
+        assert_eq!(ResourceType::RESOURCE_STREAM, events[0].resource.type_);
+        let (events, _) = cluster.events_after(&types, Some(stream_id), 0).unwrap();
+        assert_eq!(3, events.len());
+    }
+
+    #[test]
+    fn test_kv() {
+        let mut cluster = Cluster::default();
+        cluster.kv_put(b"a/1".to_vec(), b"1".to_vec());
+        cluster.kv_put(b"a/2".to_vec(), b"2".to_vec());
+        cluster.kv_put(b"b/1".to_vec(), b"3".to_vec());
+        assert_eq!(1, cluster.kv_range(b"a/1", None, 0).len());
+        assert_eq!(2, cluster.kv_range(b"a/", Some(b"a0"), 0).len());
+        assert_eq!(1, cluster.kv_range(b"a/", Some(b"a0"), 1).len());
+        assert_eq!(3, cluster.kv_range(b"a", Some(&[0]), 0).len());
+        assert_eq!(2, cluster.kv_delete_range(b"a/", Some(b"a0")));
+        assert_eq!(1, cluster.kv_range(b"", Some(&[0]), 0).len());
+
+        assert!(cluster
+            .kv_compare_and_put(b"b/1".to_vec(), None, b"4".to_vec())
+            .is_err());
+        cluster
+            .kv_compare_and_put(b"b/1".to_vec(), Some(b"3"), b"4".to_vec())
+            .unwrap();
+        cluster
+            .kv_compare_and_put(b"b/2".to_vec(), None, b"5".to_vec())
+            .unwrap();
+        assert_eq!(
+            vec![(b"b/1".to_vec(), b"4".to_vec())],
+            cluster.kv_range(b"b/1", None, 0)
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Standalone mode, which runs a range server along with an embedded placement driver in a single
+//! process, for development, tests and small single-node deployments.
+//!
+//! The embedded placement driver serves the subset of placement driver operations that range servers
+//! and frontends rely on: ID allocation, heartbeats, streams, ranges, objects, listing and watching
+//! resources, and the key-value store. Metadata is kept in memory only and lost once the process
+//! exits, while records persist in the store, so restarting a standalone server over an existing
+//! store does not bring back its streams.
+
+use std::{
+    net::{SocketAddr, ToSocketAddrs},
+    path::Path,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use config::Configuration;
+use log::{error, info};
+use model::error::EsError;
+use protocol::rpc::header::ErrorCode;
+use tokio::sync::broadcast;
+
+use crate::server;
+
+use self::service::Service;
+
+mod cluster;
+mod service;
+
+/// How long `Standalone::start` waits for the range server to register.
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Launch a range server along with an embedded placement driver listening on
+/// `config.placement_driver`, blocking the current thread like `server::launch`.
+pub fn launch(mut config: Configuration, shutdown: broadcast::Sender<()>) -> Result<(), EsError> {
+    start_placement_driver(&mut config, &shutdown, None)?;
+    server::launch(config, shutdown)
+}
+
+/// A standalone range server running in background threads, for tests to start in-process.
+///
+/// ```ignore
+/// let mut config = Configuration::default();
+/// config.placement_driver = "127.0.0.1:0".to_owned();
+/// config.server.addr = "127.0.0.1:10911".to_owned();
+/// config.server.advertise_addr = "127.0.0.1:10911".to_owned();
+/// config.store.path.set_base(store_dir);
+/// config.check_and_apply()?;
+///
+/// let standalone = Standalone::start(config)?;
+/// // Connect frontends to `standalone.placement_driver()`.
+/// standalone.shutdown();
+/// ```
+pub struct Standalone {
+    placement_driver: String,
+    shutdown: broadcast::Sender<()>,
+}
+
+impl Standalone {
+    /// Start the embedded placement driver and the range server, returning once the range server
+    /// registers and streams are writable.
+    ///
+    /// The placement driver may listen on port 0, in which case the actual address is available from
+    /// `placement_driver`.
+    pub fn start(mut config: Configuration) -> Result<Self, EsError> {
+        let (shutdown, _rx) = broadcast::channel(1);
+        let (ready_tx, ready_rx) = mpsc::sync_channel(2);
+        start_placement_driver(&mut config, &shutdown, Some(ready_tx.clone()))?;
+        let placement_driver = config.placement_driver.clone();
+
+        let server_shutdown = shutdown.clone();
+        thread::Builder::new()
+            .name("StandaloneServer".to_owned())
+            .spawn(move || {
+                if let Err(e) = server::launch(config, server_shutdown) {
+                    error!("Standalone range server failed: {e}");
+                    let _ = ready_tx.send(Err(e));
+                }
+            })
+            .map_err(|e| EsError::unexpected(&e.to_string()))?;
+
+        let standalone = Self {
+            placement_driver,
+            shutdown,
+        };
+        match ready_rx.recv_timeout(START_TIMEOUT) {
+            Ok(Ok(())) => {
+                info!(
+                    "Standalone range server is ready, placement driver at {}",
+                    standalone.placement_driver
+                );
+                Ok(standalone)
+            }
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(EsError::new(
+                ErrorCode::RPC_TIMEOUT,
+                "Range server did not register to the embedded placement driver in time",
+            )),
+        }
+    }
+
+    /// Start a standalone range server for tests, storing records under `store_dir`.
+    ///
+    /// Both the placement driver and the range server listen on ports picked by the OS, so that tests
+    /// may run in parallel.
+    pub fn start_for_test(store_dir: &Path) -> Result<Self, EsError> {
+        let mut config = Configuration {
+            placement_driver: "127.0.0.1:0".to_owned(),
+            ..Default::default()
+        };
+        config.server.addr = "127.0.0.1:0".to_owned();
+        config.server.advertise_addr = config.server.addr.clone();
+        config.store.path.set_base(&store_dir.to_string_lossy());
+        config
+            .check_and_apply()
+            .map_err(|e| EsError::new(ErrorCode::BAD_REQUEST, &e.to_string()))?;
+        Self::start(config)
+    }
+
+    /// Address of the embedded placement driver, for clients to connect.
+    pub fn placement_driver(&self) -> &str {
+        &self.placement_driver
+    }
+
+    /// Signal the range server and the placement driver to shut down, without waiting for them.
+    pub fn shutdown(self) {
+        // Shutdown is signaled on drop.
+    }
+}
+
+impl Drop for Standalone {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(());
+    }
+}
+
+/// Start the embedded placement driver in a dedicated thread, pointing `config.placement_driver` to
+/// the address it listens on.
+fn start_placement_driver(
+    config: &mut Configuration,
+    shutdown: &broadcast::Sender<()>,
+    ready: Option<mpsc::SyncSender<Result<(), EsError>>>,
+) -> Result<(), EsError> {
+    let addr = resolve(&config.placement_driver)?;
+    let (bound_tx, bound_rx) = mpsc::sync_channel(1);
+    let shutdown_rx = shutdown.subscribe();
+    thread::Builder::new()
+        .name("PlacementDriver".to_owned())
+        .spawn(move || {
+            tokio_uring::start(async move {
+                let service = match Service::bind(addr) {
+                    Ok(service) => service,
+                    Err(e) => {
+                        let _ = bound_tx.send(Err(e));
+                        return;
+                    }
+                };
+                if let Some(ready) = ready {
+                    service.notify_ready(ready);
+                }
+                let _ = bound_tx.send(Ok(service.advertise_addr().to_owned()));
+                service.run(shutdown_rx).await;
+            });
+        })
+        .map_err(|e| EsError::unexpected(&e.to_string()))?;
+
+    config.placement_driver = bound_rx
+        .recv()
+        .map_err(|_| EsError::unexpected("Embedded placement driver exited before listening"))??;
+    Ok(())
+}
+
+fn resolve(addr: &str) -> Result<SocketAddr, EsError> {
+    addr.to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| {
+            EsError::new(
+                ErrorCode::BAD_REQUEST,
+                &format!("Invalid placement driver address `{addr}`"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, sync::Arc};
+
+    use bytes::Bytes;
+    use config::{Compression, Configuration};
+    use model::record::{flat_record::FlatRecordBatch, magic1::RecordsBuilder};
+    use replication::{
+        request::{AppendRequest, OpenMode, ReadRequest},
+        StreamClient,
+    };
+
+    use super::Standalone;
+
+    #[test]
+    fn test_start() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        let store_dir = tempfile::tempdir()?;
+        let standalone = Standalone::start_for_test(store_dir.path())?;
+        // The placement driver listens on the port picked by the OS.
+        assert!(!standalone.placement_driver().ends_with(":0"));
+
+        let mut client_config = Configuration {
+            placement_driver: standalone.placement_driver().to_owned(),
+            ..Default::default()
+        };
+        client_config.check_client()?;
+        let result = tokio_uring::start(async move {
+            let stream_client = StreamClient::new(Arc::new(client_config), 0);
+            let stream_id = stream_client
+                .create_stream(1, 1, std::time::Duration::from_secs(3600))
+                .await?;
+            stream_client.open_stream(stream_id, 0).await?;
+
+            let mut builder = RecordsBuilder::new();
+            builder.append(1000, None, Some(Bytes::from_static(b"hello")), &[]);
+            builder.append(1001, None, Some(Bytes::from_static(b"world")), &[]);
+            let response = stream_client
+                .append(AppendRequest {
+                    stream_id,
+                    record_batch: builder.build(stream_id as i64)?,
+                })
+                .await?;
+            assert_eq!(0, response.offset);
+            assert_eq!(
+                2,
+                stream_client
+                    .next_offset(stream_id, OpenMode::ReadWrite)
+                    .await?
+            );
+
+            let response = stream_client
+                .read(ReadRequest {
+                    stream_id,
+                    mode: OpenMode::ReadWrite,
+                    start_offset: 0,
+                    end_offset: 2,
+                    batch_max_bytes: 1024 * 1024,
+                })
+                .await?;
+            let mut buf = Bytes::from(response.data.concat());
+            let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf)?;
+            assert!(buf.is_empty());
+            let values = record_batch
+                .records()?
+                .map(|record| record.map(|record| record.value))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(
+                vec![
+                    Some(Bytes::from_static(b"hello")),
+                    Some(Bytes::from_static(b"world"))
+                ],
+                values
+            );
+            Ok::<_, Box<dyn Error>>(())
+        });
+        standalone.shutdown();
+        result
+    }
+
+    /// Clients proposing a compression negotiate it with range servers, and exchange compressed
+    /// appends and fetches.
+    #[test]
+    fn test_compression() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        let store_dir = tempfile::tempdir()?;
+        let standalone = Standalone::start_for_test(store_dir.path())?;
+
+        for compression in [Compression::Lz4, Compression::Zstd] {
+            let mut client_config = Configuration {
+                placement_driver: standalone.placement_driver().to_owned(),
+                ..Default::default()
+            };
+            client_config.client.compression = compression;
+            client_config.check_client()?;
+            tokio_uring::start(async move {
+                let stream_client = StreamClient::new(Arc::new(client_config), 0);
+                let stream_id = stream_client
+                    .create_stream(1, 1, std::time::Duration::from_secs(3600))
+                    .await?;
+                stream_client.open_stream(stream_id, 0).await?;
+
+                // Compressible values, larger than their compressed frames.
+                let value = Bytes::from(vec![b'x'; 64 * 1024]);
+                let mut builder = RecordsBuilder::new();
+                builder.append(1000, None, Some(value.clone()), &[]);
+                builder.append(1001, None, Some(value.clone()), &[]);
+                let response = stream_client
+                    .append(AppendRequest {
+                        stream_id,
+                        record_batch: builder.build(stream_id as i64)?,
+                    })
+                    .await?;
+                assert_eq!(0, response.offset);
+
+                let response = stream_client
+                    .read(ReadRequest {
+                        stream_id,
+                        mode: OpenMode::ReadWrite,
+                        start_offset: 0,
+                        end_offset: 2,
+                        batch_max_bytes: 1024 * 1024,
+                    })
+                    .await?;
+                let mut buf = Bytes::from(response.data.concat());
+                let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf)?;
+                assert!(buf.is_empty());
+                let values = record_batch
+                    .records()?
+                    .map(|record| record.map(|record| record.value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(vec![Some(value.clone()), Some(value)], values);
+                Ok::<_, Box<dyn Error>>(())
+            })?;
+        }
+        standalone.shutdown();
+        Ok(())
+    }
+}
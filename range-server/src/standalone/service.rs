@@ -0,0 +1,583 @@
+use std::{
+    cell::RefCell,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    rc::Rc,
+    sync::mpsc::SyncSender,
+    time::Duration,
+};
+
+use bytes::Bytes;
+use codec::frame::Frame;
+use log::{debug, info, trace, warn};
+use model::error::EsError;
+use protocol::rpc::header::{
+    ClientRole, CommitObjectRequest, CommitObjectResponseT, CreateRangeRequest,
+    CreateRangeResponseT, CreateStreamRequest, CreateStreamResponseT, DeleteRangeRequest,
+    DeleteRangeResponseT, DeleteStreamRequest, DeleteStreamResponseT,
+    DescribePlacementDriverClusterResponseT, DescribeStreamRequest, DescribeStreamResponseT,
+    ErrorCode, HeartbeatRequest, HeartbeatResponseT, IdAllocationRequest, IdAllocationResponseT,
+    KeyValueT, ListRangeRequest, ListRangeResponseT, ListResourceRequest, ListResourceResponseT,
+    OperationCode, PlacementDriverClusterT, PlacementDriverNodeT, PutRequest, PutResponseT,
+    RangeRequest, RangeResponseT, ReportMetricsRequest, ReportMetricsResponseT,
+    ReportRangeProgressResponseT, SealKind, SealRangeRequest, SealRangeResponseT, StatusT,
+    TrimStreamRequest, TrimStreamResponseT, UpdateStreamRequest, UpdateStreamResponseT,
+    WatchResourceRequest, WatchResourceResponseT,
+};
+use tokio::sync::{broadcast, Notify};
+use tokio_uring::net::{TcpListener, TcpStream};
+use transport::connection::Connection;
+
+use crate::handler::util::{root_as_rpc_request, system_error_frame_bytes};
+
+use super::cluster::Cluster;
+
+/// Pack the response into bytes of a frame header.
+macro_rules! pack {
+    ($response:expr) => {{
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let response = $response.pack(&mut builder);
+        crate::handler::util::finish_response_builder(&mut builder, response)
+    }};
+}
+
+/// How long a watch waits for events if the request does not tell.
+const DEFAULT_WATCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Serves requests of placement driver clients, from the range server itself and frontends, against
+/// the in-memory `Cluster`.
+///
+/// Connections are served concurrently on the current thread, and so are requests of a connection,
+/// since watches are long polling.
+pub(crate) struct Service {
+    listener: TcpListener,
+    /// Address advertised to clients as the only placement driver node.
+    advertise_addr: String,
+    cluster: Rc<RefCell<Cluster>>,
+    /// Wakes up pending watches once the cluster changes.
+    changed: Rc<Notify>,
+    /// Notified once a writable range server registers.
+    ready: Rc<RefCell<Option<SyncSender<Result<(), EsError>>>>>,
+}
+
+impl Service {
+    /// Bind to `addr`, advertising the loopback address if it is unspecified.
+    pub(crate) fn bind(addr: SocketAddr) -> Result<Self, EsError> {
+        let listener = TcpListener::bind(addr).map_err(|e| {
+            EsError::unexpected(&format!("Failed to bind placement driver to {addr}: {e}"))
+        })?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| EsError::unexpected(&e.to_string()))?
+            .port();
+        let ip = if addr.ip().is_unspecified() {
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        } else {
+            addr.ip()
+        };
+        Ok(Self {
+            listener,
+            advertise_addr: SocketAddr::new(ip, port).to_string(),
+            cluster: Rc::new(RefCell::new(Cluster::default())),
+            changed: Rc::new(Notify::new()),
+            ready: Rc::new(RefCell::new(None)),
+        })
+    }
+
+    /// Send `Ok(())` to `ready` once a writable range server registers, from when on streams are
+    /// writable.
+    pub(crate) fn notify_ready(&self, ready: SyncSender<Result<(), EsError>>) {
+        *self.ready.borrow_mut() = Some(ready);
+    }
+
+    pub(crate) fn advertise_addr(&self) -> &str {
+        &self.advertise_addr
+    }
+
+    /// Accept and serve connections until shutdown.
+    pub(crate) async fn run(self, mut shutdown: broadcast::Receiver<()>) {
+        info!(
+            "Embedded placement driver serves at {}",
+            self.advertise_addr
+        );
+        let advertise_addr = Rc::new(self.advertise_addr);
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => {
+                    match accepted {
+                        Ok((stream, remote_addr)) => {
+                            let session = Session {
+                                advertise_addr: Rc::clone(&advertise_addr),
+                                cluster: Rc::clone(&self.cluster),
+                                changed: Rc::clone(&self.changed),
+                                ready: Rc::clone(&self.ready),
+                            };
+                            tokio_uring::spawn(session.serve(stream, remote_addr));
+                        }
+                        Err(e) => {
+                            warn!("Embedded placement driver failed to accept a connection: {e}");
+                        }
+                    }
+                }
+                _ = shutdown.recv() => {
+                    info!("Embedded placement driver received shutdown signal");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Session {
+    advertise_addr: Rc<String>,
+    cluster: Rc<RefCell<Cluster>>,
+    changed: Rc<Notify>,
+    ready: Rc<RefCell<Option<SyncSender<Result<(), EsError>>>>>,
+}
+
+impl Session {
+    async fn serve(self, stream: TcpStream, remote_addr: SocketAddr) {
+        let connection = match Connection::with_stream(stream, remote_addr) {
+            Ok(connection) => Rc::new(connection),
+            Err(e) => {
+                warn!("Failed to set up connection from {remote_addr}: {e:?}");
+                return;
+            }
+        };
+        debug!("Embedded placement driver accepted a connection from {remote_addr}");
+        loop {
+            match connection.read_frame().await {
+                Ok(Some(frame)) => {
+                    let session = self.clone();
+                    let connection = Rc::clone(&connection);
+                    tokio_uring::spawn(async move {
+                        let response = session.call(frame).await;
+                        if let Err(e) = connection.write_frame(response).await {
+                            warn!("Failed to write response to {remote_addr}: {e:?}");
+                        }
+                    });
+                }
+                Ok(None) => {
+                    debug!("Connection from {remote_addr} is closed");
+                    break;
+                }
+                Err(e) => {
+                    warn!("Connection from {remote_addr} is reset: {e:?}");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn call(&self, request: Frame) -> Frame {
+        trace!(
+            "Embedded placement driver is processing a `{}` request",
+            request
+                .operation_code
+                .variant_name()
+                .unwrap_or("INVALID_OPCODE")
+        );
+        let mut response = Frame::new(request.operation_code);
+        response.stream_id = request.stream_id;
+        response.flag_response();
+        let header = request.header.as_deref().unwrap_or_default();
+        let result = match request.operation_code {
+            OperationCode::HEARTBEAT => self.heartbeat(header),
+            OperationCode::ALLOCATE_ID => self.allocate_id(header),
+            OperationCode::DESCRIBE_PLACEMENT_DRIVER => Ok(self.describe_placement_driver()),
+            OperationCode::REPORT_METRICS => self.report_metrics(header),
+            OperationCode::REPORT_REPLICA_PROGRESS => Ok(report_range_progress()),
+            OperationCode::CREATE_STREAM => self.create_stream(header),
+            OperationCode::DESCRIBE_STREAM => self.describe_stream(header),
+            OperationCode::UPDATE_STREAM => self.update_stream(header),
+            OperationCode::DELETE_STREAM => self.delete_stream(header),
+            OperationCode::TRIM_STREAM => self.trim_stream(header),
+            OperationCode::LIST_RANGE => self.list_ranges(header),
+            OperationCode::CREATE_RANGE => self.create_range(header),
+            OperationCode::SEAL_RANGE => self.seal_range(header),
+            OperationCode::COMMIT_OBJECT => self.commit_object(header),
+            OperationCode::LIST_RESOURCE => self.list_resource(header),
+            OperationCode::WATCH_RESOURCE => self.watch_resource(header).await,
+            OperationCode::KV_RANGE => self.kv_range(header),
+            OperationCode::KV_PUT => self.kv_put(header),
+            OperationCode::KV_DELETE_RANGE => self.kv_delete_range(header),
+            _ => Err(EsError::new(
+                ErrorCode::UNKNOWN_OPERATION,
+                &format!(
+                    "Operation `{}` is not supported by the embedded placement driver",
+                    request
+                        .operation_code
+                        .variant_name()
+                        .unwrap_or("INVALID_OPCODE")
+                ),
+            )),
+        };
+        match result {
+            Ok(header) => response.header = Some(header),
+            Err(e) => {
+                response.flag_system_err();
+                response.header = Some(system_error_frame_bytes(e.code, &e.message));
+            }
+        }
+        response
+    }
+
+    fn heartbeat(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<HeartbeatRequest>(header).map_err(decode_error)?;
+        if request.client_role() == ClientRole::CLIENT_ROLE_RANGE_SERVER {
+            if let Some(range_server) = request.range_server() {
+                self.update(|cluster| {
+                    cluster.register_range_server(range_server.unpack());
+                    Ok(())
+                })?;
+            }
+        }
+        let mut response = HeartbeatResponseT::default();
+        response.status = ok_status();
+        response.client_id = request.client_id().map(ToOwned::to_owned);
+        response.client_role = request.client_role();
+        response.range_server = request
+            .range_server()
+            .map(|range_server| Box::new(range_server.unpack()));
+        Ok(pack!(response))
+    }
+
+    fn allocate_id(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<IdAllocationRequest>(header).map_err(decode_error)?;
+        let mut response = IdAllocationResponseT::default();
+        response.status = ok_status();
+        response.id = self.cluster.borrow_mut().allocate_id(request.host());
+        Ok(pack!(response))
+    }
+
+    fn describe_placement_driver(&self) -> Bytes {
+        let mut node = PlacementDriverNodeT::default();
+        node.name = "standalone".to_owned();
+        node.advertise_addr = self.advertise_addr.as_ref().clone();
+        node.is_leader = true;
+        let mut cluster = PlacementDriverClusterT::default();
+        cluster.nodes = vec![node];
+        let mut response = DescribePlacementDriverClusterResponseT::default();
+        response.status = ok_status();
+        response.cluster = Box::new(cluster);
+        pack!(response)
+    }
+
+    fn report_metrics(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<ReportMetricsRequest>(header).map_err(decode_error)?;
+        let range_server = request
+            .range_server()
+            .map(|range_server| range_server.unpack());
+        if let Some(ref range_server) = range_server {
+            self.update(|cluster| {
+                cluster.register_range_server(range_server.clone());
+                Ok(())
+            })?;
+        }
+        let mut response = ReportMetricsResponseT::default();
+        response.status = ok_status();
+        response.range_server = range_server.map(Box::new);
+        Ok(pack!(response))
+    }
+
+    fn create_stream(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<CreateStreamRequest>(header).map_err(decode_error)?;
+        let stream = self.update(|cluster| cluster.create_stream(request.stream().unpack()))?;
+        let mut response = CreateStreamResponseT::default();
+        response.status = ok_status();
+        response.stream = Some(Box::new(stream));
+        Ok(pack!(response))
+    }
+
+    fn describe_stream(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<DescribeStreamRequest>(header).map_err(decode_error)?;
+        let mut response = DescribeStreamResponseT::default();
+        match self.cluster.borrow().describe_stream(request.stream_id()) {
+            Ok(stream) => {
+                response.status = ok_status();
+                response.stream = Some(Box::new(stream));
+            }
+            Err(e) => response.status = error_status(e),
+        }
+        Ok(pack!(response))
+    }
+
+    fn update_stream(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<UpdateStreamRequest>(header).map_err(decode_error)?;
+        let mut response = UpdateStreamResponseT::default();
+        match self.update(|cluster| cluster.update_stream(request.stream().unpack())) {
+            Ok(stream) => {
+                response.status = ok_status();
+                response.stream = Box::new(stream);
+            }
+            Err(e) => response.status = error_status(e),
+        }
+        Ok(pack!(response))
+    }
+
+    fn delete_stream(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<DeleteStreamRequest>(header).map_err(decode_error)?;
+        let mut response = DeleteStreamResponseT::default();
+        match self.update(|cluster| cluster.delete_stream(request.stream_id(), request.epoch())) {
+            Ok(stream) => {
+                response.status = ok_status();
+                response.stream = Some(Box::new(stream));
+            }
+            Err(e) => response.status = error_status(e),
+        }
+        Ok(pack!(response))
+    }
+
+    fn trim_stream(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<TrimStreamRequest>(header).map_err(decode_error)?;
+        let mut response = TrimStreamResponseT::default();
+        match self.update(|cluster| {
+            cluster.trim_stream(request.stream_id(), request.epoch(), request.min_offset())
+        }) {
+            Ok((stream, range)) => {
+                response.status = ok_status();
+                response.stream = Some(Box::new(stream));
+                response.range = range.map(Box::new);
+            }
+            Err(e) => response.status = error_status(e),
+        }
+        Ok(pack!(response))
+    }
+
+    fn list_ranges(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<ListRangeRequest>(header).map_err(decode_error)?;
+        let criteria = request.criteria();
+        let mut response = ListRangeResponseT::default();
+        response.status = ok_status();
+        response.ranges = self
+            .cluster
+            .borrow()
+            .list_ranges(criteria.stream_id(), criteria.server_id());
+        Ok(pack!(response))
+    }
+
+    fn create_range(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<CreateRangeRequest>(header).map_err(decode_error)?;
+        let mut response = CreateRangeResponseT::default();
+        match self.update(|cluster| cluster.create_range(request.range().unpack())) {
+            Ok(range) => {
+                response.status = ok_status();
+                response.range = Some(Box::new(range));
+            }
+            Err(e) => response.status = error_status(e),
+        }
+        Ok(pack!(response))
+    }
+
+    fn seal_range(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<SealRangeRequest>(header).map_err(decode_error)?;
+        if request.kind() != SealKind::PLACEMENT_DRIVER {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                "Placement driver only seals ranges of kind `PLACEMENT_DRIVER`",
+            ));
+        }
+        let mut response = SealRangeResponseT::default();
+        match self.update(|cluster| cluster.seal_range(&request.range().unpack())) {
+            Ok(range) => {
+                response.status = ok_status();
+                response.range = Some(Box::new(range));
+            }
+            Err(e) => response.status = error_status(e),
+        }
+        Ok(pack!(response))
+    }
+
+    fn commit_object(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<CommitObjectRequest>(header).map_err(decode_error)?;
+        let object = request
+            .object()
+            .ok_or_else(|| EsError::new(ErrorCode::BAD_REQUEST, "Object to commit is absent"))?
+            .unpack();
+        let mut response = CommitObjectResponseT::default();
+        response.status = match self.update(|cluster| cluster.commit_object(object)) {
+            Ok(()) => ok_status(),
+            Err(e) => error_status(e),
+        };
+        Ok(pack!(response))
+    }
+
+    fn list_resource(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<ListResourceRequest>(header).map_err(decode_error)?;
+        let types = request.resource_type().iter().collect::<Vec<_>>();
+        let offset = match request.continuation() {
+            Some(token) => decode_continuation(token.bytes())?,
+            None => 0,
+        };
+        let cluster = self.cluster.borrow();
+        let (resources, continuation) = cluster.list_resources(&types, offset, request.limit());
+        let mut response = ListResourceResponseT::default();
+        response.status = ok_status();
+        response.resources = resources;
+        response.resource_version = cluster.version();
+        response.continuation = continuation.map(|offset| (offset as u64).to_be_bytes().to_vec());
+        Ok(pack!(response))
+    }
+
+    /// Wait until there are events after the requested version, or the watch times out, in which
+    /// case no event is returned.
+    async fn watch_resource(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<WatchResourceRequest>(header).map_err(decode_error)?;
+        let types = request.resource_type().iter().collect::<Vec<_>>();
+        let version = request.resource_version();
+        let stream_id = Some(request.stream_id()).filter(|id| *id >= 0);
+        // Respond a little ahead of the timeout, before the client gives up the request.
+        let timeout = match request.timeout_ms() {
+            timeout_ms if timeout_ms > 0 => Duration::from_millis(timeout_ms as u64 * 9 / 10),
+            _ => DEFAULT_WATCH_TIMEOUT,
+        };
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut response = WatchResourceResponseT::default();
+        loop {
+            // Register interest before checking events, so that no change is missed in between.
+            let changed = self.changed.notified();
+            let (events, resource_version) = match self
+                .cluster
+                .borrow()
+                .events_after(&types, stream_id, version)
+            {
+                Ok(watched) => watched,
+                Err(e) => {
+                    // Watchers list resources again once told the version is compacted.
+                    response.status = error_status(e);
+                    break;
+                }
+            };
+            if !events.is_empty() || tokio::time::timeout_at(deadline, changed).await.is_err() {
+                response.status = ok_status();
+                response.events = events;
+                response.resource_version = resource_version;
+                break;
+            }
+        }
+        Ok(pack!(response))
+    }
+
+    fn kv_range(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<RangeRequest>(header).map_err(decode_error)?;
+        let key = request.key().map(|key| key.bytes()).unwrap_or_default();
+        let range_end = request.range_end().map(|end| end.bytes());
+        let kvs = self
+            .cluster
+            .borrow()
+            .kv_range(key, range_end, request.limit());
+        let mut response = RangeResponseT::default();
+        response.status = ok_status();
+        response.count = kvs.len() as i64;
+        response.kvs = Some(
+            kvs.into_iter()
+                .map(|(key, value)| {
+                    let mut key_value = KeyValueT::default();
+                    key_value.key = Some(key);
+                    key_value.value = Some(value);
+                    key_value
+                })
+                .collect(),
+        );
+        Ok(pack!(response))
+    }
+
+    fn kv_put(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<PutRequest>(header).map_err(decode_error)?;
+        let key = request.key().map(|key| key.bytes().to_vec());
+        let key = key
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| EsError::new(ErrorCode::BAD_REQUEST, "Key should not be empty"))?;
+        let value = request
+            .value()
+            .map(|value| value.bytes().to_vec())
+            .unwrap_or_default();
+        let mut response = PutResponseT::default();
+        response.status = ok_status();
+        if request.compare() {
+            let expected = request.expected_value().map(|value| value.bytes());
+            if let Err(e) = self
+                .cluster
+                .borrow_mut()
+                .kv_compare_and_put(key, expected, value)
+            {
+                response.status = error_status(e);
+            }
+        } else {
+            self.cluster.borrow_mut().kv_put(key, value);
+        }
+        Ok(pack!(response))
+    }
+
+    fn kv_delete_range(&self, header: &[u8]) -> Result<Bytes, EsError> {
+        let request = root_as_rpc_request::<DeleteRangeRequest>(header).map_err(decode_error)?;
+        let key = request.key().map(|key| key.bytes()).unwrap_or_default();
+        let range_end = request.range_end().map(|end| end.bytes());
+        let mut response = DeleteRangeResponseT::default();
+        response.status = ok_status();
+        response.deleted = self.cluster.borrow_mut().kv_delete_range(key, range_end) as i64;
+        Ok(pack!(response))
+    }
+
+    /// Apply a change to the cluster, waking up watches if any resource changes.
+    fn update<T, F>(&self, f: F) -> Result<T, EsError>
+    where
+        F: FnOnce(&mut Cluster) -> Result<T, EsError>,
+    {
+        let mut cluster = self.cluster.borrow_mut();
+        let version = cluster.version();
+        let result = f(&mut cluster);
+        if cluster.version() != version {
+            self.changed.notify_waiters();
+            if cluster.has_writable_range_server() {
+                if let Some(ready) = self.ready.borrow_mut().take() {
+                    let _ = ready.send(Ok(()));
+                }
+            }
+        }
+        result
+    }
+}
+
+fn report_range_progress() -> Bytes {
+    // Progress of ranges is not tracked, as there is no replica to reassign in a single node.
+    let mut response = ReportRangeProgressResponseT::default();
+    response.status = ok_status();
+    pack!(response)
+}
+
+fn decode_continuation(token: &[u8]) -> Result<usize, EsError> {
+    let token: [u8; 8] = token
+        .try_into()
+        .map_err(|_| EsError::new(ErrorCode::BAD_REQUEST, "Malformed continuation token"))?;
+    Ok(u64::from_be_bytes(token) as usize)
+}
+
+fn decode_error(e: flatbuffers::InvalidFlatbuffer) -> EsError {
+    EsError::new(ErrorCode::BAD_REQUEST, &e.to_string())
+}
+
+fn ok_status() -> Box<StatusT> {
+    let mut status = StatusT::default();
+    status.code = ErrorCode::OK;
+    status.message = Some("OK".to_owned());
+    Box::new(status)
+}
+
+fn error_status(e: EsError) -> Box<StatusT> {
+    let mut status = StatusT::default();
+    status.code = e.code;
+    status.message = Some(e.message);
+    Box::new(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_continuation;
+
+    #[test]
+    fn test_decode_continuation() {
+        assert_eq!(1024, decode_continuation(&1024u64.to_be_bytes()).unwrap());
+        assert!(decode_continuation(&[1, 2, 3]).is_err());
+    }
+}
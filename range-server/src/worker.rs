@@ -7,8 +7,9 @@ use std::{
 };
 
 use log::{debug, error, info, warn};
-use tokio::sync::broadcast;
-use tokio_uring::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio_uring::net::{TcpListener, UnixListener};
+use transport::{connection::Connection, Address, ConnectionError};
 
 use client::{client::Client, DefaultClient};
 use observation::metrics::{
@@ -20,9 +21,72 @@ use protocol::rpc::header::RangeServerState;
 
 use crate::{
     connection_tracker::ConnectionTracker, heartbeat::Heartbeat, metadata::MetadataManager,
-    range_manager::RangeManager, worker_config::WorkerConfig,
+    quota::QuotaManager, range_manager::RangeManager, worker_config::WorkerConfig,
 };
 
+/// Listener of incoming connections, over TCP or Unix domain socket.
+enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener, Address),
+}
+
+impl Listener {
+    /// Accept an incoming connection.
+    ///
+    /// The outer result fails if the listener is broken, while the inner one fails if the accepted stream is.
+    async fn accept(&self) -> std::io::Result<Result<Connection, ConnectionError>> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, remote_addr) = listener.accept().await?;
+                debug!("Accepted a new connection from {remote_addr:?}");
+                stream.set_nodelay(true).unwrap_or_else(|e| {
+                    warn!("Failed to disable Nagle's algorithm. Cause: {e:?}, PeerAddress: {remote_addr:?}");
+                });
+                debug!("Nagle's algorithm turned off");
+                Ok(Connection::with_stream(stream, remote_addr))
+            }
+            Listener::Unix(listener, addr) => {
+                let stream = listener.accept().await?;
+                debug!("Accepted a new connection from {addr}");
+                Ok(Connection::with_unix_stream(stream, addr.clone()))
+            }
+        }
+    }
+
+    /// Accept connections and pass them to `tx`, until shutdown or the listener is broken.
+    ///
+    /// Each listener accepts in its own task, as dropping a pending accept of `tokio_uring` may lose the
+    /// connection being accepted.
+    async fn serve(
+        self,
+        tx: mpsc::UnboundedSender<Connection>,
+        mut shutdown_rx: broadcast::Receiver<()>,
+    ) {
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+
+                incoming = self.accept() => {
+                    match incoming {
+                        Ok(Ok(connection)) => {
+                            if tx.send(connection).is_err() {
+                                break;
+                            }
+                        }
+                        Ok(Err(e)) => {
+                            info!("Failed to process accepted connection. Cause: {}", e);
+                        }
+                        Err(e) => {
+                            error!("Failed to accept a connection. Cause: {}", e.to_string());
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 /// A server aggregates one or more `Worker`s and each `Worker` takes up a dedicated CPU
 /// processor, following the Thread-per-Core design paradigm.
 ///
@@ -40,6 +104,9 @@ pub(crate) struct Worker<M, Meta> {
 
     connection_tracker: Rc<RefCell<ConnectionTracker>>,
 
+    /// Quotas of clients and streams served by this worker.
+    quota: Rc<QuotaManager>,
+
     state: Rc<RefCell<RangeServerState>>,
 }
 
@@ -54,12 +121,17 @@ where
         client: Rc<DefaultClient>,
         metadata_manager: Meta,
     ) -> Self {
+        let quota = Rc::new(QuotaManager::new(
+            config.server_config.server.quota.clone(),
+            config.server_config.server_max_throttle_time(),
+        ));
         Self {
             config,
             range_manager,
             client,
             metadata_manager,
             connection_tracker: Rc::new(RefCell::new(ConnectionTracker::new())),
+            quota,
             state: Rc::new(RefCell::new(
                 RangeServerState::RANGE_SERVER_STATE_READ_WRITE,
             )),
@@ -93,18 +165,13 @@ where
 
                 self.range_manager.start().await;
 
-                let bind_address = &self.config.server_config.server.addr;
-                let listener =
-                    match TcpListener::bind(bind_address.parse().expect("Failed to bind")) {
-                        Ok(listener) => {
-                            info!("Server starts OK, listening {}", bind_address);
-                            listener
-                        }
-                        Err(e) => {
-                            eprintln!("{}", e);
-                            return;
-                        }
-                    };
+                let listeners = match self.bind() {
+                    Ok(listeners) => listeners,
+                    Err(e) => {
+                        eprintln!("Failed to bind: {}", e);
+                        return;
+                    }
+                };
 
                 if self.config.primary {
                     self.report_metrics(shutdown.subscribe());
@@ -115,7 +182,7 @@ where
 
                 self.heartbeat(shutdown.clone(), Rc::clone(&self.state));
 
-                match self.run(listener, shutdown.subscribe()).await {
+                match self.run(listeners, shutdown.subscribe()).await {
                     Ok(_) => {}
                     Err(e) => {
                         error!("Runtime failed. Cause: {}", e.to_string());
@@ -124,6 +191,48 @@ where
             });
     }
 
+    /// Bind the TCP address, and the Unix domain socket alongside if configured.
+    ///
+    /// TCP listeners are bound by each worker with `SO_REUSEPORT`, unless the server has bound one on a port
+    /// picked by the OS, while the Unix domain socket is bound once by the server and shared among workers.
+    fn bind(&self) -> std::io::Result<Vec<Listener>> {
+        let server = &self.config.server_config.server;
+        let tcp_listener = match &self.config.tcp_listener {
+            Some(listener) => TcpListener::from_std(listener.try_clone()?),
+            None => match server.addr.parse::<Address>()? {
+                Address::Tcp(socket_addr) => TcpListener::bind(socket_addr)?,
+                Address::Unix(_) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "Server address must be a TCP address",
+                    ))
+                }
+            },
+        };
+        let mut listeners = vec![Listener::Tcp(tcp_listener)];
+        info!("Server starts OK, listening {}", server.addr);
+
+        if let Some(unix_addr) = &server.unix_addr {
+            let listener = self
+                .config
+                .unix_listener
+                .as_ref()
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "Unix domain socket is not bound",
+                    )
+                })?
+                .try_clone()?;
+            listeners.push(Listener::Unix(
+                UnixListener::from_std(listener),
+                unix_addr.parse::<Address>()?,
+            ));
+            info!("Server starts OK, listening {}", unix_addr);
+        }
+        Ok(listeners)
+    }
+
     fn report_metrics(&self, mut shutdown_rx: broadcast::Receiver<()>) {
         let client = Rc::clone(&self.client);
         let config = Arc::clone(&self.config.server_config);
@@ -189,9 +298,15 @@ where
 
     async fn run(
         &self,
-        listener: TcpListener,
+        listeners: Vec<Listener>,
         mut shutdown_rx: broadcast::Receiver<()>,
     ) -> Result<(), Box<dyn Error>> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for listener in listeners {
+            tokio_uring::spawn(listener.serve(tx.clone(), shutdown_rx.resubscribe()));
+        }
+        drop(tx);
+
         loop {
             tokio::select! {
                 _ = shutdown_rx.recv() => {
@@ -199,39 +314,20 @@ where
                     break;
                 }
 
-                incoming = listener.accept() => {
-                    let (stream, remote_addr) = match incoming {
-                        Ok((stream, remote_addr)) => {
-                            debug!("Accepted a new connection from {remote_addr:?}");
-                            stream.set_nodelay(true).unwrap_or_else(|e| {
-                                warn!("Failed to disable Nagle's algorithm. Cause: {e:?}, PeerAddress: {remote_addr:?}");
-                            });
-                            debug!("Nagle's algorithm turned off");
-                            (stream, remote_addr)
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to accept a connection. Cause: {}",
-                                e.to_string()
-                            );
-                            break;
-                        }
+                connection = rx.recv() => {
+                    // All listeners are broken.
+                    let Some(connection) = connection else {
+                        break;
                     };
 
-                    let res = super::session::Session::new(
+                    let session = super::session::Session::new(
                         Arc::clone(&self.config.server_config),
-                        stream, remote_addr,
+                        connection,
                         Rc::clone(&self.range_manager),
-                        Rc::clone(&self.connection_tracker)
+                        Rc::clone(&self.connection_tracker),
+                        Rc::clone(&self.quota),
                     );
-                    match res {
-                        Ok(session) => {
-                            session.process();
-                        }
-                        Err(_e) => {
-                            info!("Failed to process accepted connection from {}", remote_addr);
-                        }
-                    }
+                    session.process();
                 }
             }
         }
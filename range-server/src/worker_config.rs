@@ -1,5 +1,5 @@
 use core_affinity::CoreId;
-use std::{os::fd::RawFd, sync::Arc};
+use std::{net::TcpListener, os::fd::RawFd, os::unix::net::UnixListener, sync::Arc};
 
 use config::Configuration;
 
@@ -8,4 +8,10 @@ pub(crate) struct WorkerConfig {
     pub(crate) server_config: Arc<Configuration>,
     pub(crate) sharing_uring: RawFd,
     pub(crate) primary: bool,
+
+    /// Listener of the TCP address shared among workers, if it is bound on a port picked by the OS.
+    pub(crate) tcp_listener: Option<TcpListener>,
+
+    /// Listener of the Unix domain socket served alongside TCP, if `unix_addr` is configured.
+    pub(crate) unix_listener: Option<UnixListener>,
 }
@@ -1,11 +1,22 @@
 package com.automq.elasticstream.client.jni;
+import com.automq.elasticstream.client.flatc.header.ListRangeResponse;
+import com.automq.elasticstream.client.flatc.header.ListResourceResponse;
+import com.automq.elasticstream.client.flatc.header.RangeT;
+import com.automq.elasticstream.client.flatc.header.ResourceT;
+import com.automq.elasticstream.client.flatc.header.StreamT;
+import com.automq.elasticstream.client.utils.BytesUtils;
 import io.netty.channel.epoll.Native;
 import io.netty.util.internal.NativeLibraryLoader;
 import io.netty.util.internal.PlatformDependent;
 import org.slf4j.Logger;
 import org.slf4j.LoggerFactory;
 import java.nio.ByteBuffer;
+import java.util.Arrays;
+import java.util.List;
+import java.util.Map;
 import java.util.concurrent.CompletableFuture;
+import java.util.function.Function;
+import java.util.stream.Collectors;
 
 public class Frontend extends ElasticStreamObject {
 
@@ -28,7 +39,23 @@ public class Frontend extends ElasticStreamObject {
     }
 
     public Frontend(String access_point) {
-        this.ptr = getFrontend(access_point);
+        this(access_point, null, null, null);
+    }
+
+    /**
+     * @param access_point Access point of the placement driver
+     * @param config_path  Nullable path of the YAML configuration file
+     * @param env_prefix   Nullable prefix of environment variables overriding the configuration, e.g. ES
+     * @param options      Nullable configuration items keyed by path, e.g. client.io-timeout, overriding the above
+     */
+    public Frontend(String access_point, String config_path, String env_prefix, Map<String, String> options) {
+        String[] items = null;
+        if (options != null) {
+            items = options.entrySet().stream()
+                    .map(e -> e.getKey() + "=" + e.getValue())
+                    .toArray(String[]::new);
+        }
+        this.ptr = getFrontend(access_point, config_path, env_prefix, items);
     }
     public CompletableFuture<Long> create(int replica, int ack, long retention_millis) {
         CompletableFuture<Long> future = new CompletableFuture<>();
@@ -40,9 +67,49 @@ public class Frontend extends ElasticStreamObject {
         open(this.ptr, id, epoch, future);
         return future;
     }
+    public CompletableFuture<StreamT> describe(long id) {
+        CompletableFuture<ByteBuffer> future = new CompletableFuture<>();
+        describe(this.ptr, id, future);
+        return decode(future, buf -> com.automq.elasticstream.client.flatc.header.Stream.getRootAsStream(buf).unpack());
+    }
+    public CompletableFuture<List<RangeT>> listRanges(long id) {
+        CompletableFuture<ByteBuffer> future = new CompletableFuture<>();
+        listRanges(this.ptr, id, future);
+        return decode(future, buf -> Arrays.asList(ListRangeResponse.getRootAsListRangeResponse(buf).unpack().getRanges()));
+    }
+    /**
+     * Update metadata of the stream, of which a negative replica, ack or retention_millis is left unchanged.
+     */
+    public CompletableFuture<StreamT> update(long id, int replica, int ack, long retention_millis) {
+        CompletableFuture<ByteBuffer> future = new CompletableFuture<>();
+        update(this.ptr, id, replica, ack, retention_millis, future);
+        return decode(future, buf -> com.automq.elasticstream.client.flatc.header.Stream.getRootAsStream(buf).unpack());
+    }
+    public CompletableFuture<List<StreamT>> listStreams() {
+        CompletableFuture<ByteBuffer> future = new CompletableFuture<>();
+        listStreams(this.ptr, future);
+        return decode(future, buf -> Arrays.stream(ListResourceResponse.getRootAsListResourceResponse(buf).unpack().getResources())
+                .map(ResourceT::getStream)
+                .collect(Collectors.toList()));
+    }
+
+    // Decode the FlatBuffers table from the direct buffer allocated by the native library, then free it.
+    private static <T> CompletableFuture<T> decode(CompletableFuture<ByteBuffer> future, Function<ByteBuffer, T> decoder) {
+        return future.thenApply(buf -> {
+            try {
+                return decoder.apply(buf);
+            } finally {
+                freeMemory(BytesUtils.getAddress(buf), buf.capacity());
+            }
+        });
+    }
     private native void create(long ptr, int replica, int ack, long retention_millis, CompletableFuture<Long> future);
     private native void open(long ptr, long id, long epoch, CompletableFuture<Stream> future);
-    private native long getFrontend(String access_point);
+    private native void describe(long ptr, long id, CompletableFuture<ByteBuffer> future);
+    private native void listRanges(long ptr, long id, CompletableFuture<ByteBuffer> future);
+    private native void update(long ptr, long id, int replica, int ack, long retention_millis, CompletableFuture<ByteBuffer> future);
+    private native void listStreams(long ptr, CompletableFuture<ByteBuffer> future);
+    private native long getFrontend(String access_point, String config_path, String env_prefix, String[] options);
     private native void freeFrontend(long ptr);
 
     public static native ByteBuffer allocateDirect(int size);
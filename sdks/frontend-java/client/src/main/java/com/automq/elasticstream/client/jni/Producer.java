@@ -0,0 +1,33 @@
+package com.automq.elasticstream.client.jni;
+import java.nio.ByteBuffer;
+import java.util.concurrent.CompletableFuture;
+public class Producer extends ElasticStreamObject {
+    public Producer(long ptr) {
+        this.ptr = ptr;
+    }
+    /**
+     * Send a record, which is accumulated into a record batch of the stream.
+     *
+     * @param record Direct buffer of the record, which should remain valid until the returned future completes.
+     * @return Future of offset of the record.
+     */
+    public CompletableFuture<Long> send(ByteBuffer record) {
+        CompletableFuture<Long> future = new CompletableFuture<>();
+        send(this.ptr, record, future);
+        return future;
+    }
+    /**
+     * Send accumulated records immediately.
+     */
+    public void flush() {
+        flush(this.ptr);
+    }
+
+    private native void send(long ptr, ByteBuffer record, CompletableFuture<Long> future);
+    private native void flush(long ptr);
+    private native void freeProducer(long ptr);
+    @Override
+    public void close() {
+        freeProducer(this.ptr);
+    }
+}
@@ -25,6 +25,9 @@ public class Stream extends ElasticStreamObject {
         read(this.ptr, start_offset, end_offset, batch_max_bytes, future);
         return future;
     }
+    public Producer producer(long lingerMs, int batchSize) {
+        return new Producer(newProducer(this.ptr, lingerMs, batchSize));
+    }
     public CompletableFuture<Void> asyncClose() {
         CompletableFuture<Void> future = new CompletableFuture<>();
         asyncClose(this.ptr, future);
@@ -47,6 +50,7 @@ public class Stream extends ElasticStreamObject {
     private native void nextOffset(long ptr, CompletableFuture<Long> future);
     private native void append(long ptr, ByteBuffer data, CompletableFuture<Long> future);
     private native void read(long ptr, long start_offset, long end_offset, int batch_max_bytes, CompletableFuture<ByteBuffer> future);
+    private native long newProducer(long ptr, long lingerMs, int batchSize);
     private native void asyncClose(long ptr, CompletableFuture<Void> future);
     private native long freeStream(long ptr);
     private native void trim(long ptr, long new_start_offset, CompletableFuture<Void> future);
@@ -0,0 +1,16 @@
+"""Python client of Elastic Stream.
+
+Operations of `Frontend` and `Stream` are coroutines-compatible: each returns an `asyncio.Future`
+of the running event loop, completed by the client runtime threads.
+"""
+
+from ._native import Buffer, ElasticStreamError, Frontend, Record, RecordIterator, Stream
+
+__all__ = [
+    "Buffer",
+    "ElasticStreamError",
+    "Frontend",
+    "Record",
+    "RecordIterator",
+    "Stream",
+]
@@ -0,0 +1,60 @@
+import asyncio
+from typing import Dict, List, Optional, Sequence, Tuple, Union
+
+BytesLike = Union[bytes, bytearray, memoryview, "Buffer"]
+
+class ElasticStreamError(Exception):
+    """Raised with `(code, message)`, where `code` is the `ErrorCode` of `rpc.fbs`."""
+
+    code: int
+    message: str
+
+class Buffer:
+    """Read-only bytes shared with the client without copying, supporting the buffer protocol."""
+
+    def __len__(self) -> int: ...
+    def __bytes__(self) -> bytes: ...
+
+class Record:
+    offset: int
+    timestamp: int
+    key: Optional[Buffer]
+    value: Optional[Buffer]
+
+class Frontend:
+    def __init__(
+        self,
+        access_point: str,
+        config_path: Optional[str] = None,
+        env_prefix: Optional[str] = None,
+        options: Optional[Dict[str, str]] = None,
+    ) -> None: ...
+    def create(
+        self, replica: int = 1, ack: int = 1, retention_ms: int = 7 * 24 * 3600 * 1000
+    ) -> "asyncio.Future[int]": ...
+    def open(self, stream_id: int, epoch: int = 0) -> "asyncio.Future[Stream]": ...
+
+class Stream:
+    @property
+    def id(self) -> int: ...
+    def start_offset(self) -> "asyncio.Future[int]": ...
+    def next_offset(self) -> "asyncio.Future[int]": ...
+    def append(
+        self, records: Sequence[Union[BytesLike, Tuple[Optional[BytesLike], Optional[BytesLike]]]]
+    ) -> "asyncio.Future[int]": ...
+    def read(
+        self, start_offset: int, end_offset: int, max_bytes: int = 1048576
+    ) -> "asyncio.Future[List[Record]]": ...
+    def records(
+        self,
+        start_offset: int,
+        end_offset: Optional[int] = None,
+        max_bytes: int = 1048576,
+        poll_interval_ms: int = 100,
+    ) -> RecordIterator: ...
+    def trim(self, new_start_offset: int) -> "asyncio.Future[None]": ...
+    def close(self) -> "asyncio.Future[None]": ...
+
+class RecordIterator:
+    def __aiter__(self) -> "RecordIterator": ...
+    def __anext__(self) -> "asyncio.Future[Record]": ...
@@ -0,0 +1,35 @@
+//! Python extension module of the frontend, exposed as `elastic_stream._native` and re-exported by
+//! the `elastic_stream` package.
+//!
+//! Operations return `asyncio.Future`s of the running event loop, which are completed by the
+//! runtime of the module, in the same way as the Java binding.
+
+use model::error::EsError;
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+mod record;
+mod runtime;
+mod stream;
+
+create_exception!(
+    elastic_stream,
+    ElasticStreamError,
+    PyException,
+    "Error of Elastic Stream, with arguments `(code, message)`."
+);
+
+/// Convert the error into `ElasticStreamError`, of which `code` is the value of `ErrorCode`.
+pub(crate) fn to_py_err(err: EsError) -> PyErr {
+    ElasticStreamError::new_err((i32::from(err.code.0), err.message))
+}
+
+#[pymodule]
+fn _native(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("ElasticStreamError", py.get_type::<ElasticStreamError>())?;
+    m.add_class::<stream::FrontendHandle>()?;
+    m.add_class::<stream::StreamHandle>()?;
+    m.add_class::<stream::RecordIterator>()?;
+    m.add_class::<record::Record>()?;
+    m.add_class::<record::Buffer>()?;
+    Ok(())
+}
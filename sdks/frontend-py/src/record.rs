@@ -0,0 +1,200 @@
+use std::{
+    ffi::{c_char, c_int, c_void},
+    ptr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::{Buf, Bytes};
+use model::{
+    error::EsError,
+    record::{flat_record::FlatRecordBatch, magic1, magic1::RecordsBuilder},
+};
+use protocol::rpc::header::ErrorCode;
+use pyo3::{
+    buffer::PyBuffer,
+    exceptions::PyBufferError,
+    ffi,
+    prelude::*,
+    types::{PyBytes, PyTuple},
+    AsPyPointer,
+};
+
+/// Read-only bytes exposed through the buffer protocol, sharing memory with `Bytes` read from
+/// range servers.
+#[pyclass(module = "elastic_stream")]
+pub struct Buffer {
+    data: Bytes,
+}
+
+#[pymethods]
+impl Buffer {
+    fn __len__(&self) -> usize {
+        self.data.len()
+    }
+
+    fn __bytes__<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.data)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Buffer(len={})", self.data.len())
+    }
+
+    /// # Safety
+    /// `view` is provided by the interpreter.
+    unsafe fn __getbuffer__(
+        slf: &PyCell<Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("View is null"));
+        }
+        if (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("Buffer is read-only"));
+        }
+        let buffer = slf.borrow();
+        let data = &buffer.data;
+        // The view holds a reference to the buffer, which keeps the memory alive.
+        ffi::Py_INCREF(slf.as_ptr());
+        (*view).obj = slf.as_ptr();
+        (*view).buf = data.as_ptr() as *mut c_void;
+        (*view).len = data.len() as isize;
+        (*view).readonly = 1;
+        (*view).itemsize = 1;
+        (*view).format = if (flags & ffi::PyBUF_FORMAT) == ffi::PyBUF_FORMAT {
+            b"B\0".as_ptr() as *mut c_char
+        } else {
+            ptr::null_mut()
+        };
+        (*view).ndim = 1;
+        (*view).shape = if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+            &mut (*view).len
+        } else {
+            ptr::null_mut()
+        };
+        (*view).strides = if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+            &mut (*view).itemsize
+        } else {
+            ptr::null_mut()
+        };
+        (*view).suboffsets = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        Ok(())
+    }
+}
+
+#[pyclass(module = "elastic_stream")]
+pub struct Record {
+    #[pyo3(get)]
+    offset: i64,
+    /// Milliseconds since the UNIX epoch.
+    #[pyo3(get)]
+    timestamp: i64,
+    key: Option<Bytes>,
+    value: Option<Bytes>,
+}
+
+#[pymethods]
+impl Record {
+    #[getter]
+    fn key(&self, py: Python<'_>) -> PyResult<Option<Py<Buffer>>> {
+        to_buffer(py, &self.key)
+    }
+
+    #[getter]
+    fn value(&self, py: Python<'_>) -> PyResult<Option<Py<Buffer>>> {
+        to_buffer(py, &self.value)
+    }
+
+    fn __repr__(&self) -> String {
+        let len = |data: &Option<Bytes>| data.as_ref().map(Bytes::len);
+        format!(
+            "Record(offset={}, timestamp={}, key_len={:?}, value_len={:?})",
+            self.offset,
+            self.timestamp,
+            len(&self.key),
+            len(&self.value)
+        )
+    }
+}
+
+fn to_buffer(py: Python<'_>, data: &Option<Bytes>) -> PyResult<Option<Py<Buffer>>> {
+    data.as_ref()
+        .map(|data| Py::new(py, Buffer { data: data.clone() }))
+        .transpose()
+}
+
+impl From<magic1::Record> for Record {
+    fn from(record: magic1::Record) -> Self {
+        Self {
+            offset: record.offset,
+            timestamp: record.timestamp,
+            key: record.key,
+            value: record.value,
+        }
+    }
+}
+
+fn to_bytes(py: Python<'_>, obj: &PyAny) -> PyResult<Option<Bytes>> {
+    if obj.is_none() {
+        return Ok(None);
+    }
+    let buffer = PyBuffer::<u8>::get(obj)?;
+    Ok(Some(Bytes::from(buffer.to_vec(py)?)))
+}
+
+/// Encode records into a record batch of `RecordMagic::Magic1`. Each record is either a value,
+/// or a tuple of key and value, both of which are bytes-like objects or `None`.
+pub(crate) fn encode(py: Python<'_>, stream_id: u64, records: &[&PyAny]) -> PyResult<Bytes> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64);
+    let mut builder = RecordsBuilder::new();
+    for record in records {
+        let (key, value) = match record.downcast::<PyTuple>() {
+            Ok(tuple) => {
+                let (key, value): (&PyAny, &PyAny) = tuple.extract()?;
+                (to_bytes(py, key)?, to_bytes(py, value)?)
+            }
+            Err(_) => (None, to_bytes(py, record)?),
+        };
+        builder.append(now, key, value, &[]);
+    }
+    let record_batch = builder
+        .build(stream_id as i64)
+        .map_err(|e| crate::to_py_err(EsError::new(ErrorCode::BAD_REQUEST, &format!("{e:?}"))))?;
+    let (buffers, _) = FlatRecordBatch::from(record_batch).encode();
+    Ok(Bytes::from(buffers.concat()))
+}
+
+/// Decode records at or after `start_offset` from data read, skipping control records.
+///
+/// # Returns
+/// Records and the offset to read next.
+pub(crate) fn decode(
+    buffers: Vec<Bytes>,
+    start_offset: i64,
+) -> Result<(Vec<Record>, i64), EsError> {
+    let decode_error = |e| EsError::new(ErrorCode::DECODE, &format!("{e:?}"));
+    let mut records = vec![];
+    let mut next_offset = start_offset;
+    for mut buf in buffers {
+        while buf.has_remaining() {
+            let record_batch =
+                FlatRecordBatch::decode_to_record_batch(&mut buf).map_err(decode_error)?;
+            next_offset = next_offset
+                .max(record_batch.base_offset() + record_batch.last_offset_delta() as i64);
+            if record_batch.is_control() {
+                continue;
+            }
+            for record in record_batch.records().map_err(decode_error)? {
+                let record = record.map_err(decode_error)?;
+                if record.offset >= start_offset {
+                    records.push(record.into());
+                }
+            }
+        }
+    }
+    Ok((records, next_offset))
+}
@@ -0,0 +1,438 @@
+//! Like the Java binding, operations are dispatched to a dedicated tokio-uring runtime thread, and
+//! completed on callback threads, which hand results over to the asyncio event loop of the caller.
+
+use std::{cell::Cell, rc::Rc, sync::OnceLock, time::Duration};
+
+use bytes::Bytes;
+use crossbeam::channel::{unbounded, Sender};
+use frontend::{Frontend, OpenOptions, Stream, StreamOptions};
+use log::{error, info, trace};
+use model::error::EsError;
+use protocol::rpc::header::ErrorCode;
+use pyo3::{
+    exceptions::{PyRuntimeError, PyStopAsyncIteration},
+    prelude::*,
+    types::{PyCFunction, PyDict, PyTuple},
+};
+use tokio::sync::mpsc;
+
+use crate::{
+    record::{self, Record},
+    stream::{RecordIterator, StreamHandle},
+};
+
+/// Stream shared by operations on the runtime thread, which outlives `close` until operations in
+/// progress complete.
+pub(crate) struct StreamCell {
+    stream: Stream,
+    closed: Cell<bool>,
+}
+
+/// Pointer of a `StreamCell` held by Python, which is only dereferenced by the runtime thread.
+pub(crate) struct StreamPtr(*mut Rc<StreamCell>);
+
+unsafe impl Send for StreamPtr {}
+
+impl StreamPtr {
+    /// # Safety
+    /// Called on the runtime thread, before the stream is closed.
+    unsafe fn get(&self) -> Rc<StreamCell> {
+        Rc::clone(&*self.0)
+    }
+
+    /// Another pointer of the same stream, which is only dispatched before the stream is closed.
+    pub(crate) fn share(&self) -> StreamPtr {
+        StreamPtr(self.0)
+    }
+}
+
+pub(crate) enum Command {
+    CreateStream {
+        frontend: Frontend,
+        options: StreamOptions,
+        completion: Completion,
+    },
+    OpenStream {
+        frontend: Frontend,
+        stream_id: u64,
+        epoch: u64,
+        completion: Completion,
+    },
+    StartOffset {
+        stream: StreamPtr,
+        completion: Completion,
+    },
+    NextOffset {
+        stream: StreamPtr,
+        completion: Completion,
+    },
+    Append {
+        stream: StreamPtr,
+        buf: Bytes,
+        completion: Completion,
+    },
+    Read {
+        stream: StreamPtr,
+        start_offset: i64,
+        end_offset: i64,
+        max_bytes: i32,
+        completion: Completion,
+    },
+    /// Read records from `offset` on, waiting for records to be appended if there are none yet.
+    Poll {
+        stream: StreamPtr,
+        offset: i64,
+        end_offset: Option<i64>,
+        max_bytes: i32,
+        poll_interval: Duration,
+        completion: Completion,
+    },
+    Trim {
+        stream: StreamPtr,
+        new_start_offset: i64,
+        completion: Completion,
+    },
+    /// Close and free the stream. Streams garbage collected before closed are closed without
+    /// completion.
+    CloseStream {
+        stream: StreamPtr,
+        completion: Option<Completion>,
+    },
+}
+
+pub(crate) enum Outcome {
+    StreamId(u64),
+    Stream {
+        stream_id: u64,
+        stream: StreamPtr,
+    },
+    Offset(i64),
+    Records(Vec<Record>),
+    Polled {
+        records: Vec<Record>,
+        next_offset: i64,
+    },
+    End,
+    Unit,
+}
+
+/// An `asyncio.Future` of the caller, and the iterator to advance if any.
+pub(crate) struct Completion {
+    event_loop: PyObject,
+    future: PyObject,
+    iterator: Option<Py<RecordIterator>>,
+}
+
+impl Completion {
+    /// Completion of a new future of the running event loop.
+    pub(crate) fn new(py: Python<'_>) -> PyResult<Self> {
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+        Ok(Self {
+            event_loop: event_loop.into(),
+            future: future.into(),
+            iterator: None,
+        })
+    }
+
+    pub(crate) fn with_iterator(mut self, iterator: Py<RecordIterator>) -> Self {
+        self.iterator = Some(iterator);
+        self
+    }
+
+    pub(crate) fn future(&self, py: Python<'_>) -> PyObject {
+        self.future.clone_ref(py)
+    }
+
+    /// Complete the future on its event loop thread.
+    fn complete(self, py: Python<'_>, result: PyResult<PyObject>) -> PyResult<()> {
+        let (method, arg) = match result {
+            Ok(value) => ("set_result", value),
+            Err(e) => ("set_exception", e.into_value(py).into_py(py)),
+        };
+        let future = self.future;
+        let callback = PyCFunction::new_closure(
+            py,
+            None,
+            None,
+            move |args: &PyTuple, _kwargs: Option<&PyDict>| -> PyResult<()> {
+                let py = args.py();
+                let future = future.as_ref(py);
+                // The future may be cancelled meanwhile.
+                if !future.call_method0("done")?.is_true()? {
+                    future.call_method1(method, (arg.clone_ref(py),))?;
+                }
+                Ok(())
+            },
+        )?;
+        self.event_loop
+            .call_method1(py, "call_soon_threadsafe", (callback,))?;
+        Ok(())
+    }
+}
+
+struct CallbackCommand {
+    completion: Completion,
+    result: Result<Outcome, EsError>,
+}
+
+struct Runtime {
+    tx: mpsc::UnboundedSender<Command>,
+    callback_tx: Sender<CallbackCommand>,
+}
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// The runtime thread and callback threads, which are started on creation of the first frontend.
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        frontend::init_log();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (callback_tx, callback_rx) = unbounded::<CallbackCommand>();
+
+        // Callback thread number is between [2, 4].
+        let cpu_sum = std::cmp::max(std::cmp::min(num_cpus::get(), 4), 2);
+        (0..cpu_sum).for_each(|i| {
+            let callback_rx = callback_rx.clone();
+            let _ = std::thread::Builder::new()
+                .name(format!("CallBackThread-{}", i))
+                .spawn(move || {
+                    while let Ok(command) = callback_rx.recv() {
+                        Python::with_gil(|py| {
+                            let CallbackCommand { completion, result } = command;
+                            let value = to_py(py, &completion, result);
+                            if let Err(e) = completion.complete(py, value) {
+                                error!("Failed to complete future: {e}");
+                            }
+                        });
+                    }
+                    info!("Callback channel is dropped");
+                });
+        });
+        let _ = std::thread::Builder::new()
+            .name("Runtime".to_string())
+            .spawn(move || {
+                trace!("Python runtime thread started");
+                tokio_uring::builder().start(async move {
+                    while let Some(command) = rx.recv().await {
+                        process_command(command);
+                    }
+                    info!("Python command channel is dropped");
+                });
+            });
+        Runtime { tx, callback_tx }
+    })
+}
+
+/// Start the runtime, if not yet.
+pub(crate) fn start() {
+    runtime();
+}
+
+pub(crate) fn dispatch(command: Command) -> PyResult<()> {
+    runtime()
+        .tx
+        .send(command)
+        .map_err(|_| PyRuntimeError::new_err("Failed to dispatch command to tokio-uring runtime"))
+}
+
+/// Spawn a task of the command. Streams are shared before spawning, such that they are not freed
+/// by a `CloseStream` command dispatched later.
+fn process_command(command: Command) {
+    match command {
+        Command::CreateStream {
+            frontend,
+            options,
+            completion,
+        } => spawn(completion, async move {
+            frontend.create(options).await.map(Outcome::StreamId)
+        }),
+        Command::OpenStream {
+            frontend,
+            stream_id,
+            epoch,
+            completion,
+        } => spawn(completion, async move {
+            frontend
+                .open(stream_id, OpenOptions::with_epoch(epoch))
+                .await
+                .map(|stream| {
+                    let cell = Rc::new(StreamCell {
+                        stream,
+                        closed: Cell::new(false),
+                    });
+                    Outcome::Stream {
+                        stream_id,
+                        stream: StreamPtr(Box::into_raw(Box::new(cell))),
+                    }
+                })
+        }),
+        Command::StartOffset { stream, completion } => {
+            let cell = unsafe { stream.get() };
+            spawn(completion, async move {
+                cell.stream.start_offset().await.map(Outcome::Offset)
+            })
+        }
+        Command::NextOffset { stream, completion } => {
+            let cell = unsafe { stream.get() };
+            spawn(completion, async move {
+                cell.stream.next_offset().await.map(Outcome::Offset)
+            })
+        }
+        Command::Append {
+            stream,
+            buf,
+            completion,
+        } => {
+            let cell = unsafe { stream.get() };
+            spawn(completion, async move {
+                cell.stream
+                    .append(buf)
+                    .await
+                    .map(|result| Outcome::Offset(result.base_offset))
+            })
+        }
+        Command::Read {
+            stream,
+            start_offset,
+            end_offset,
+            max_bytes,
+            completion,
+        } => {
+            let cell = unsafe { stream.get() };
+            spawn(completion, async move {
+                let buffers = cell
+                    .stream
+                    .read(start_offset, end_offset, max_bytes)
+                    .await?;
+                record::decode(buffers, start_offset).map(|(records, _)| Outcome::Records(records))
+            })
+        }
+        Command::Poll {
+            stream,
+            offset,
+            end_offset,
+            max_bytes,
+            poll_interval,
+            completion,
+        } => {
+            let cell = unsafe { stream.get() };
+            spawn(
+                completion,
+                poll(cell, offset, end_offset, max_bytes, poll_interval),
+            )
+        }
+        Command::Trim {
+            stream,
+            new_start_offset,
+            completion,
+        } => {
+            let cell = unsafe { stream.get() };
+            spawn(completion, async move {
+                cell.stream
+                    .trim(new_start_offset)
+                    .await
+                    .map(|_| Outcome::Unit)
+            })
+        }
+        Command::CloseStream { stream, completion } => {
+            // Take ownership of the stream, which is dropped on the runtime thread once operations
+            // in progress complete.
+            let cell = *unsafe { Box::from_raw(stream.0) };
+            cell.closed.set(true);
+            let close = async move { cell.stream.close().await.map(|_| Outcome::Unit) };
+            match completion {
+                Some(completion) => spawn(completion, close),
+                None => {
+                    tokio_uring::spawn(async move {
+                        if let Err(e) = close.await {
+                            error!("Failed to close stream: {e}");
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn spawn<F>(completion: Completion, task: F)
+where
+    F: std::future::Future<Output = Result<Outcome, EsError>> + 'static,
+{
+    tokio_uring::spawn(async move {
+        let result = task.await;
+        if runtime()
+            .callback_tx
+            .send(CallbackCommand { completion, result })
+            .is_err()
+        {
+            error!("Failed to hand over completion to callback threads");
+        }
+    });
+}
+
+async fn poll(
+    cell: Rc<StreamCell>,
+    mut offset: i64,
+    end_offset: Option<i64>,
+    max_bytes: i32,
+    poll_interval: Duration,
+) -> Result<Outcome, EsError> {
+    loop {
+        if end_offset.map_or(false, |end| offset >= end) {
+            return Ok(Outcome::End);
+        }
+        if cell.closed.get() {
+            return Err(EsError::new(ErrorCode::BAD_REQUEST, "Stream is closed"));
+        }
+        let next_offset = cell.stream.next_offset().await?;
+        let limit = end_offset.map_or(next_offset, |end| end.min(next_offset));
+        if offset >= limit {
+            tokio::time::sleep(poll_interval).await;
+            continue;
+        }
+        let buffers = cell.stream.read(offset, limit, max_bytes).await?;
+        let (records, next_offset) = record::decode(buffers, offset)?;
+        if next_offset <= offset {
+            return Err(EsError::unexpected(&format!(
+                "No progress reading stream[id={}] at {offset}",
+                cell.stream.id()
+            )));
+        }
+        if !records.is_empty() {
+            return Ok(Outcome::Polled {
+                records,
+                next_offset,
+            });
+        }
+        // Skip record batches of control records only.
+        offset = next_offset;
+    }
+}
+
+/// Convert the outcome into the result of the future, advancing the iterator if any.
+fn to_py(
+    py: Python<'_>,
+    completion: &Completion,
+    result: Result<Outcome, EsError>,
+) -> PyResult<PyObject> {
+    if let Some(iterator) = &completion.iterator {
+        return RecordIterator::advance(iterator, py, result);
+    }
+    match result.map_err(crate::to_py_err)? {
+        Outcome::StreamId(stream_id) => Ok(stream_id.into_py(py)),
+        Outcome::Stream { stream_id, stream } => {
+            Ok(StreamHandle::new(py, stream_id, stream)?.into_py(py))
+        }
+        Outcome::Offset(offset) => Ok(offset.into_py(py)),
+        Outcome::Records(records) => {
+            let records = records
+                .into_iter()
+                .map(|record| Py::new(py, record))
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(records.into_py(py))
+        }
+        Outcome::Polled { .. } | Outcome::End => Err(PyStopAsyncIteration::new_err(())),
+        Outcome::Unit => Ok(py.None()),
+    }
+}
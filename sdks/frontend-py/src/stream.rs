@@ -0,0 +1,332 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use frontend::{Frontend, FrontendBuilder, StreamOptions};
+use log::error;
+use model::error::EsError;
+use pyo3::{
+    exceptions::{PyRuntimeError, PyStopAsyncIteration, PyValueError},
+    prelude::*,
+};
+
+use crate::{
+    record::{self, Record},
+    runtime::{self, Command, Completion, Outcome, StreamPtr},
+    to_py_err,
+};
+
+/// Default max bytes of record batches to read at a time.
+const DEFAULT_MAX_BYTES: i32 = 1024 * 1024;
+
+#[pyclass(name = "Frontend", module = "elastic_stream")]
+pub struct FrontendHandle {
+    frontend: Frontend,
+}
+
+#[pymethods]
+impl FrontendHandle {
+    /// Frontend of the placement driver at `access_point`, see `FrontendBuilder` for the other
+    /// arguments. `options` maps configuration paths to values, e.g. `client.io-timeout` to `20`.
+    #[new]
+    #[pyo3(signature = (access_point, config_path = None, env_prefix = None, options = None))]
+    fn new(
+        access_point: &str,
+        config_path: Option<&str>,
+        env_prefix: Option<&str>,
+        options: Option<HashMap<String, String>>,
+    ) -> PyResult<Self> {
+        let mut builder = FrontendBuilder::new(access_point);
+        if let Some(config_path) = config_path {
+            builder = builder.with_config_file(config_path);
+        }
+        if let Some(env_prefix) = env_prefix {
+            builder = builder.with_env_prefix(env_prefix);
+        }
+        for (path, value) in options.unwrap_or_default() {
+            builder = builder.with_option(&path, &value);
+        }
+        let frontend = builder.build().map_err(to_py_err)?;
+        runtime::start();
+        Ok(Self { frontend })
+    }
+
+    /// Create a stream, resolving to its id.
+    #[pyo3(signature = (replica = 1, ack = 1, retention_ms = 7 * 24 * 3600 * 1000))]
+    fn create(
+        &self,
+        py: Python<'_>,
+        replica: u8,
+        ack: u8,
+        retention_ms: u64,
+    ) -> PyResult<PyObject> {
+        let completion = Completion::new(py)?;
+        let future = completion.future(py);
+        runtime::dispatch(Command::CreateStream {
+            frontend: self.frontend.clone(),
+            options: StreamOptions {
+                replica,
+                ack,
+                retention: Duration::from_millis(retention_ms),
+                compaction: None,
+                rollover: None,
+            },
+            completion,
+        })?;
+        Ok(future)
+    }
+
+    /// Open a stream for write with `epoch`, fencing writers of smaller epochs.
+    #[pyo3(signature = (stream_id, epoch = 0))]
+    fn open(&self, py: Python<'_>, stream_id: u64, epoch: u64) -> PyResult<PyObject> {
+        let completion = Completion::new(py)?;
+        let future = completion.future(py);
+        runtime::dispatch(Command::OpenStream {
+            frontend: self.frontend.clone(),
+            stream_id,
+            epoch,
+            completion,
+        })?;
+        Ok(future)
+    }
+}
+
+/// A stream opened for write. Close it explicitly to wait for the close, otherwise it is closed
+/// once garbage collected.
+#[pyclass(name = "Stream", module = "elastic_stream")]
+pub struct StreamHandle {
+    stream_id: u64,
+    stream: Option<StreamPtr>,
+}
+
+impl StreamHandle {
+    pub(crate) fn new(py: Python<'_>, stream_id: u64, stream: StreamPtr) -> PyResult<Py<Self>> {
+        Py::new(
+            py,
+            Self {
+                stream_id,
+                stream: Some(stream),
+            },
+        )
+    }
+
+    fn stream(&self) -> PyResult<StreamPtr> {
+        self.stream.as_ref().map(StreamPtr::share).ok_or_else(|| {
+            PyRuntimeError::new_err(format!("Stream[id={}] is closed", self.stream_id))
+        })
+    }
+
+    fn dispatch<F>(&self, py: Python<'_>, command: F) -> PyResult<PyObject>
+    where
+        F: FnOnce(StreamPtr, Completion) -> Command,
+    {
+        let stream = self.stream()?;
+        let completion = Completion::new(py)?;
+        let future = completion.future(py);
+        runtime::dispatch(command(stream, completion))?;
+        Ok(future)
+    }
+}
+
+#[pymethods]
+impl StreamHandle {
+    #[getter]
+    fn id(&self) -> u64 {
+        self.stream_id
+    }
+
+    fn start_offset(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.dispatch(py, |stream, completion| Command::StartOffset {
+            stream,
+            completion,
+        })
+    }
+
+    fn next_offset(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.dispatch(py, |stream, completion| Command::NextOffset {
+            stream,
+            completion,
+        })
+    }
+
+    /// Append records as a record batch, resolving to the offset of the first one.
+    ///
+    /// Each record is either a value, or a tuple of key and value, which are bytes-like objects or
+    /// `None`. Records are copied into the record batch before this method returns.
+    fn append(&self, py: Python<'_>, records: Vec<&PyAny>) -> PyResult<PyObject> {
+        if records.is_empty() {
+            return Err(PyValueError::new_err("No records to append"));
+        }
+        let buf = record::encode(py, self.stream_id, &records)?;
+        self.dispatch(py, |stream, completion| Command::Append {
+            stream,
+            buf,
+            completion,
+        })
+    }
+
+    /// Read records in `[start_offset, end_offset)`, of at most about `max_bytes`, resolving to a
+    /// list of records. Values of records share memory with data read, without copying.
+    #[pyo3(signature = (start_offset, end_offset, max_bytes = DEFAULT_MAX_BYTES))]
+    fn read(
+        &self,
+        py: Python<'_>,
+        start_offset: i64,
+        end_offset: i64,
+        max_bytes: i32,
+    ) -> PyResult<PyObject> {
+        if start_offset > end_offset || max_bytes <= 0 {
+            return Err(PyValueError::new_err("Invalid range or max bytes to read"));
+        }
+        self.dispatch(py, |stream, completion| Command::Read {
+            stream,
+            start_offset,
+            end_offset,
+            max_bytes,
+            completion,
+        })
+    }
+
+    /// Asynchronous iterator over records from `start_offset`, until `end_offset` if given, or
+    /// following records as they are appended otherwise.
+    #[pyo3(signature = (start_offset, end_offset = None, max_bytes = DEFAULT_MAX_BYTES, poll_interval_ms = 100))]
+    fn records(
+        slf: Py<Self>,
+        start_offset: i64,
+        end_offset: Option<i64>,
+        max_bytes: i32,
+        poll_interval_ms: u64,
+    ) -> PyResult<RecordIterator> {
+        if max_bytes <= 0 {
+            return Err(PyValueError::new_err("Invalid max bytes to read"));
+        }
+        Ok(RecordIterator {
+            stream: slf,
+            offset: start_offset,
+            end_offset,
+            max_bytes,
+            poll_interval: Duration::from_millis(poll_interval_ms),
+            records: VecDeque::new(),
+            pending: false,
+        })
+    }
+
+    fn trim(&self, py: Python<'_>, new_start_offset: i64) -> PyResult<PyObject> {
+        self.dispatch(py, |stream, completion| Command::Trim {
+            stream,
+            new_start_offset,
+            completion,
+        })
+    }
+
+    fn close(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let future = self.dispatch(py, |stream, completion| Command::CloseStream {
+            stream,
+            completion: Some(completion),
+        })?;
+        self.stream = None;
+        Ok(future)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Stream(id={}, closed={})",
+            self.stream_id,
+            self.stream.is_none()
+        )
+    }
+}
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let command = Command::CloseStream {
+                stream,
+                completion: None,
+            };
+            if runtime::dispatch(command).is_err() {
+                error!("Failed to close stream[id={}] on drop", self.stream_id);
+            }
+        }
+    }
+}
+
+/// Asynchronous iterator over records of a stream, see `Stream.records`.
+#[pyclass(module = "elastic_stream")]
+pub struct RecordIterator {
+    stream: Py<StreamHandle>,
+    offset: i64,
+    end_offset: Option<i64>,
+    max_bytes: i32,
+    poll_interval: Duration,
+    records: VecDeque<Record>,
+    /// Whether a poll is in progress.
+    pending: bool,
+}
+
+impl RecordIterator {
+    /// Advance the iterator with the outcome of a poll, returning the next record.
+    pub(crate) fn advance(
+        iterator: &Py<Self>,
+        py: Python<'_>,
+        result: Result<Outcome, EsError>,
+    ) -> PyResult<PyObject> {
+        let mut iterator = iterator.borrow_mut(py);
+        iterator.pending = false;
+        match result.map_err(to_py_err)? {
+            Outcome::Polled {
+                records,
+                next_offset,
+            } => {
+                iterator.offset = next_offset;
+                iterator.records.extend(records);
+                match iterator.records.pop_front() {
+                    Some(record) => Ok(Py::new(py, record)?.into_py(py)),
+                    None => Err(PyRuntimeError::new_err("No records polled")),
+                }
+            }
+            _ => Err(PyStopAsyncIteration::new_err(())),
+        }
+    }
+}
+
+#[pymethods]
+impl RecordIterator {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Resolve to the next record, or stop once `end_offset` is reached.
+    fn __anext__(slf: &PyCell<Self>, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let mut iterator = slf.borrow_mut();
+        if iterator.pending {
+            return Err(PyRuntimeError::new_err(
+                "Previous __anext__() of the iterator is not completed yet",
+            ));
+        }
+        let completion = Completion::new(py)?;
+        let future = completion.future(py);
+        if let Some(record) = iterator.records.pop_front() {
+            future.call_method1(py, "set_result", (Py::new(py, record)?,))?;
+            return Ok(Some(future));
+        }
+        if iterator
+            .end_offset
+            .map_or(false, |end| iterator.offset >= end)
+        {
+            return Ok(None);
+        }
+        let stream = iterator.stream.borrow(py).stream()?;
+        runtime::dispatch(Command::Poll {
+            stream,
+            offset: iterator.offset,
+            end_offset: iterator.end_offset,
+            max_bytes: iterator.max_bytes,
+            poll_interval: iterator.poll_interval,
+            completion: completion.with_iterator(slf.into()),
+        })?;
+        iterator.pending = true;
+        Ok(Some(future))
+    }
+}
@@ -0,0 +1,164 @@
+"""Tests of the extension module against mock-server, which serves metadata of streams only, and
+against a standalone range server along with its embedded placement driver.
+
+Build both with `cargo build -p mock-server -p range-server` first, or point `MOCK_SERVER` and
+`RANGE_SERVER` to their binaries.
+"""
+
+import asyncio
+import os
+import socket
+import subprocess
+import tempfile
+import time
+from pathlib import Path
+
+import pytest
+
+from elastic_stream import ElasticStreamError, Frontend
+
+ROOT = Path(__file__).resolve().parents[3]
+
+
+@pytest.fixture(scope="module")
+def access_point():
+    binary = os.environ.get("MOCK_SERVER", str(ROOT / "target" / "debug" / "mock-server"))
+    server = subprocess.Popen([binary], stdout=subprocess.PIPE, text=True)
+    try:
+        port = int(server.stdout.readline())
+        yield f"127.0.0.1:{port}"
+    finally:
+        server.kill()
+        server.wait()
+
+
+def free_port():
+    with socket.socket() as s:
+        s.bind(("127.0.0.1", 0))
+        return s.getsockname()[1]
+
+
+@pytest.fixture(scope="module")
+def standalone():
+    """Address of the placement driver embedded in a standalone range server."""
+    binary = os.environ.get("RANGE_SERVER", str(ROOT / "target" / "debug" / "range-server"))
+    with tempfile.TemporaryDirectory() as workdir:
+        log_config = Path(workdir) / "log.yaml"
+        log_config.write_text(
+            "appenders:\n"
+            "  stderr:\n"
+            "    kind: console\n"
+            "    target: stderr\n"
+            "root:\n"
+            "  level: warn\n"
+            "  appenders:\n"
+            "    - stderr\n"
+        )
+        pd = f"127.0.0.1:{free_port()}"
+        addr = f"127.0.0.1:{free_port()}"
+        server = subprocess.Popen(
+            [
+                binary,
+                "standalone",
+                f"--pd={pd}",
+                f"--addr={addr}",
+                f"--advertise-addr={addr}",
+                f"--store-path={Path(workdir) / 'store'}",
+                f"--config={Path(workdir) / 'range-server.yaml'}",
+                f"--log={log_config}",
+            ]
+        )
+        try:
+            # The range server listens once the embedded placement driver is up.
+            host, port = addr.split(":")
+            deadline = time.monotonic() + 30
+            while True:
+                try:
+                    socket.create_connection((host, int(port)), timeout=1).close()
+                    break
+                except OSError:
+                    if time.monotonic() > deadline:
+                        raise
+                    time.sleep(0.1)
+            yield pd
+        finally:
+            server.kill()
+            server.wait()
+
+
+async def create_and_open(access_point):
+    frontend = Frontend(access_point, options={"client.io-timeout": "10"})
+    stream_id = await frontend.create(replica=1, ack=1, retention_ms=3600 * 1000)
+    # Opening a stream creates its first range, once the range server registers.
+    for _ in range(50):
+        try:
+            return await frontend.open(stream_id, epoch=0)
+        except ElasticStreamError:
+            await asyncio.sleep(0.2)
+    return await frontend.open(stream_id, epoch=0)
+
+
+def test_create_stream(access_point):
+    async def create():
+        frontend = Frontend(access_point, options={"client.io-timeout": "10"})
+        return await frontend.create(replica=1, ack=1, retention_ms=3600 * 1000)
+
+    assert asyncio.run(create()) > 0
+
+
+def test_invalid_configuration(access_point):
+    with pytest.raises(ElasticStreamError) as e:
+        Frontend(access_point, options={"client.retry.max-attempt": "0"})
+    code, message = e.value.args
+    assert code > 0
+    assert "configuration" in message
+
+
+def test_operations_require_event_loop(access_point):
+    frontend = Frontend(access_point)
+    with pytest.raises(RuntimeError):
+        frontend.create()
+
+
+def test_append_read_trim(standalone):
+    async def run():
+        stream = await create_and_open(standalone)
+        base_offset = await stream.append([b"hello", (b"key", b"world")])
+        assert base_offset == 0
+        assert await stream.next_offset() == base_offset + 2
+
+        records = await stream.read(base_offset, base_offset + 2)
+        assert [r.offset for r in records] == [base_offset, base_offset + 1]
+        assert [bytes(r.value) for r in records] == [b"hello", b"world"]
+        assert records[0].key is None
+        assert bytes(records[1].key) == b"key"
+        assert memoryview(records[1].value)[1:3] == b"or"
+
+        await stream.trim(base_offset + 1)
+        assert await stream.start_offset() == base_offset + 1
+        records = await stream.read(base_offset + 1, base_offset + 2)
+        assert [bytes(r.value) for r in records] == [b"world"]
+        await stream.close()
+
+    asyncio.run(run())
+
+
+def test_records(standalone):
+    async def run():
+        stream = await create_and_open(standalone)
+        base_offset = await stream.append([b"a", b"b"])
+        await stream.append([b"c"])
+
+        values = [bytes(r.value) async for r in stream.records(base_offset, base_offset + 3)]
+        assert values == [b"a", b"b", b"c"]
+
+        # Without an end offset, the iterator follows records appended afterwards.
+        follower = stream.records(base_offset + 2, poll_interval_ms=10)
+        assert bytes((await follower.__anext__()).value) == b"c"
+        pending = asyncio.ensure_future(follower.__anext__())
+        await stream.append([b"d"])
+        record = await asyncio.wait_for(pending, timeout=10)
+        assert (record.offset, bytes(record.value)) == (base_offset + 3, b"d")
+        await stream.close()
+
+    asyncio.run(run())
@@ -1,6 +1,6 @@
 use bytes::{Bytes, BytesMut};
 use clap::{arg, Parser};
-use frontend::{Frontend, StreamOptions};
+use frontend::{Frontend, OpenOptions, StreamOptions};
 use local_sync::semaphore::Semaphore;
 use log::{error, info};
 use model::{record::flat_record::FlatRecordBatch, RecordBatch};
@@ -84,11 +84,18 @@ fn main() -> Result<(), Box<dyn Error + 'static>> {
                             replica: 1,
                             ack: 1,
                             retention: Duration::from_secs(3600),
+                            compaction: None,
+                            rollover: None,
                         })
                         .await
                         .unwrap();
                     info!("Created stream with id: {}", stream_id);
-                    let stream = Rc::new(frontend.open(stream_id, 0).await.unwrap());
+                    let stream = Rc::new(
+                        frontend
+                            .open(stream_id, OpenOptions::default())
+                            .await
+                            .unwrap(),
+                    );
                     let mut payload = BytesMut::with_capacity(args.payload_size);
                     payload.resize(args.payload_size, b'x');
                     let payload = payload.freeze();
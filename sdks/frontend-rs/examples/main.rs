@@ -1,7 +1,7 @@
 use std::env;
 
 use bytes::{Bytes, BytesMut};
-use frontend::{Frontend, StreamOptions};
+use frontend::{Frontend, OpenOptions, StreamOptions};
 use futures::{future::join_all, FutureExt};
 use log::info;
 use model::{record::flat_record::FlatRecordBatch, RecordBatch};
@@ -17,10 +17,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 replica: 1,
                 ack: 1,
                 retention: Duration::from_secs(3600),
+                compaction: None,
+                rollover: None,
             })
             .await?;
         info!("Created stream with id: {}", stream_id);
-        let stream = frontend.open(stream_id, 0).await?;
+        let stream = frontend.open(stream_id, OpenOptions::default()).await?;
 
         info!("Step1: append 10-record batch");
         let mut append_tasks = vec![];
@@ -87,7 +89,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         drop(stream);
         // await stream close
         sleep(Duration::from_secs(1)).await;
-        let stream = frontend.open(stream_id, 0).await?;
+        let stream = frontend.open(stream_id, OpenOptions::default()).await?;
 
         assert_eq!(100, stream.next_offset().await?);
 
@@ -0,0 +1,188 @@
+/* Generated by cbindgen from src/bindings/c.rs. Do not edit manually. */
+
+#ifndef ELASTIC_STREAM_H
+#define ELASTIC_STREAM_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct EsFrontend EsFrontend;
+typedef struct EsStream EsStream;
+
+/**
+ * Error of an operation, passed to callbacks. Callbacks of successful operations get `NULL`.
+ */
+typedef struct {
+  /**
+   * Value of `ErrorCode` in `rpc.fbs`.
+   */
+  int32_t code;
+  /**
+   * Nul-terminated, human-readable message.
+   */
+  const char *message;
+} EsStatus;
+
+/**
+ * A record of a stream.
+ *
+ * `key` and `value` are `NULL` if absent. On append, `offset` is ignored and a zero `timestamp`
+ * stands for now.
+ */
+typedef struct {
+  int64_t offset;
+  /**
+   * Milliseconds since the UNIX epoch.
+   */
+  int64_t timestamp;
+  const uint8_t *key;
+  size_t key_len;
+  const uint8_t *value;
+  size_t value_len;
+} EsRecord;
+
+typedef void (*EsCallback)(void *ctx, const EsStatus *status);
+
+typedef void (*EsCreateCallback)(void *ctx, const EsStatus *status, uint64_t stream_id);
+
+/**
+ * `stream` is owned by the callee, until it is passed to `es_stream_close`.
+ */
+typedef void (*EsOpenCallback)(void *ctx, const EsStatus *status, EsStream *stream);
+
+typedef void (*EsOffsetCallback)(void *ctx, const EsStatus *status, int64_t offset);
+
+typedef void (*EsReadCallback)(void *ctx,
+                               const EsStatus *status,
+                               const EsRecord *records,
+                               size_t count);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a frontend of the placement driver at `access_point`.
+ *
+ * `config_path` and `env_prefix` are nullable, see `FrontendBuilder`. `options` are `count`
+ * strings of `path=value`, for example, `client.io-timeout=20`.
+ *
+ * Returns `NULL` on invalid arguments or configuration. The frontend is freed by
+ * `es_frontend_free`, after all streams opened from it are closed.
+ *
+ * # Safety
+ * Strings are nul-terminated, and `options` is valid for `count` strings.
+ */
+EsFrontend *es_frontend_new(const char *access_point,
+                            const char *config_path,
+                            const char *env_prefix,
+                            const char *const *options,
+                            size_t count);
+
+/**
+ * # Safety
+ * `frontend` is created by `es_frontend_new` and not freed yet.
+ */
+void es_frontend_free(EsFrontend *frontend);
+
+/**
+ * Create a stream, completing with its id.
+ *
+ * # Safety
+ * `frontend` is created by `es_frontend_new` and not freed yet.
+ */
+int32_t es_stream_create(EsFrontend *frontend,
+                         uint8_t replica,
+                         uint8_t ack_count,
+                         uint64_t retention_ms,
+                         EsCreateCallback callback,
+                         void *ctx);
+
+/**
+ * Open a stream for write with `epoch`, fencing writers of smaller epochs.
+ *
+ * # Safety
+ * `frontend` is created by `es_frontend_new` and not freed yet.
+ */
+int32_t es_stream_open(EsFrontend *frontend,
+                       uint64_t stream_id,
+                       uint64_t epoch,
+                       EsOpenCallback callback,
+                       void *ctx);
+
+/**
+ * # Safety
+ * `stream` is opened by `es_stream_open` and not closed yet.
+ */
+uint64_t es_stream_id(const EsStream *stream);
+
+/**
+ * Get the offset of the first record that is not trimmed, completing with it.
+ *
+ * # Safety
+ * `stream` is opened by `es_stream_open` and not closed yet.
+ */
+int32_t es_stream_start_offset(EsStream *stream, EsOffsetCallback callback, void *ctx);
+
+/**
+ * Get the offset of the next record to append, completing with it.
+ *
+ * # Safety
+ * `stream` is opened by `es_stream_open` and not closed yet.
+ */
+int32_t es_stream_next_offset(EsStream *stream, EsOffsetCallback callback, void *ctx);
+
+/**
+ * Append `count` records as a record batch, completing with the offset of the first one.
+ *
+ * Records are copied before this function returns.
+ *
+ * # Safety
+ * `stream` is opened by `es_stream_open` and not closed yet, and `records` is valid for `count`
+ * records.
+ */
+int32_t es_stream_append(EsStream *stream,
+                         const EsRecord *records,
+                         size_t count,
+                         EsOffsetCallback callback,
+                         void *ctx);
+
+/**
+ * Read records in `[start_offset, end_offset)`, of at most about `batch_max_bytes`, completing
+ * with records read.
+ *
+ * # Safety
+ * `stream` is opened by `es_stream_open` and not closed yet.
+ */
+int32_t es_stream_read(EsStream *stream,
+                       int64_t start_offset,
+                       int64_t end_offset,
+                       int32_t batch_max_bytes,
+                       EsReadCallback callback,
+                       void *ctx);
+
+/**
+ * Trim records before `new_start_offset`.
+ *
+ * # Safety
+ * `stream` is opened by `es_stream_open` and not closed yet.
+ */
+int32_t es_stream_trim(EsStream *stream, int64_t new_start_offset, EsCallback callback, void *ctx);
+
+/**
+ * Close the stream and free it. The stream must not be used once this function returns `0`,
+ * while operations dispatched before still complete.
+ *
+ * # Safety
+ * `stream` is opened by `es_stream_open` and not closed yet.
+ */
+int32_t es_stream_close(EsStream *stream, EsCallback callback, void *ctx);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* ELASTIC_STREAM_H */
@@ -0,0 +1,820 @@
+//! C ABI of the frontend, from which `include/elastic_stream.h` is generated by cbindgen.
+//!
+//! Like the Java binding, operations are dispatched to a dedicated tokio-uring runtime thread, and
+//! complete by invoking the given callback on one of the callback threads. A function returns `0`
+//! once the operation is dispatched, in which case its callback is invoked exactly once, or `-1`
+//! on invalid arguments, in which case the callback is never invoked.
+//!
+//! Status and data handed to callbacks are only valid during the callback. `ctx` is passed to the
+//! callback as is, and may be used from callback threads.
+
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    ptr, slice,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use crossbeam::channel::{unbounded, Sender};
+use log::{error, info, trace};
+use model::{
+    error::EsError,
+    record::{
+        flat_record::FlatRecordBatch,
+        magic1::{Record, RecordsBuilder},
+    },
+};
+use protocol::rpc::header::ErrorCode;
+use tokio::sync::mpsc;
+
+use crate::{Frontend, FrontendBuilder, OpenOptions, Stream, StreamOptions};
+
+/// Error of an operation, passed to callbacks. Callbacks of successful operations get `NULL`.
+#[repr(C)]
+pub struct EsStatus {
+    /// Value of `ErrorCode` in `rpc.fbs`.
+    pub code: i32,
+    /// Nul-terminated, human-readable message.
+    pub message: *const c_char,
+}
+
+/// A record of a stream.
+///
+/// `key` and `value` are `NULL` if absent. On append, `offset` is ignored and a zero `timestamp`
+/// stands for now.
+#[repr(C)]
+pub struct EsRecord {
+    pub offset: i64,
+    /// Milliseconds since the UNIX epoch.
+    pub timestamp: i64,
+    pub key: *const u8,
+    pub key_len: usize,
+    pub value: *const u8,
+    pub value_len: usize,
+}
+
+pub type EsCallback = Option<unsafe extern "C" fn(ctx: *mut c_void, status: *const EsStatus)>;
+
+pub type EsCreateCallback =
+    Option<unsafe extern "C" fn(ctx: *mut c_void, status: *const EsStatus, stream_id: u64)>;
+
+/// `stream` is owned by the callee, until it is passed to `es_stream_close`.
+pub type EsOpenCallback =
+    Option<unsafe extern "C" fn(ctx: *mut c_void, status: *const EsStatus, stream: *mut Stream)>;
+
+pub type EsOffsetCallback =
+    Option<unsafe extern "C" fn(ctx: *mut c_void, status: *const EsStatus, offset: i64)>;
+
+pub type EsReadCallback = Option<
+    unsafe extern "C" fn(
+        ctx: *mut c_void,
+        status: *const EsStatus,
+        records: *const EsRecord,
+        count: usize,
+    ),
+>;
+
+/// Pointers handed out to C, which are only dereferenced by the runtime thread once dispatched.
+struct Ptr<T>(*mut T);
+
+unsafe impl<T> Send for Ptr<T> {}
+
+/// Context of a callback, owned by the caller.
+struct Context(*mut c_void);
+
+unsafe impl Send for Context {}
+
+enum Command {
+    CreateStream {
+        frontend: Ptr<Frontend>,
+        options: StreamOptions,
+        callback: EsCreateCallback,
+        ctx: Context,
+    },
+    OpenStream {
+        frontend: Ptr<Frontend>,
+        stream_id: u64,
+        epoch: u64,
+        callback: EsOpenCallback,
+        ctx: Context,
+    },
+    StartOffset {
+        stream: Ptr<Stream>,
+        callback: EsOffsetCallback,
+        ctx: Context,
+    },
+    NextOffset {
+        stream: Ptr<Stream>,
+        callback: EsOffsetCallback,
+        ctx: Context,
+    },
+    Append {
+        stream: Ptr<Stream>,
+        buf: Bytes,
+        callback: EsOffsetCallback,
+        ctx: Context,
+    },
+    Read {
+        stream: Ptr<Stream>,
+        start_offset: i64,
+        end_offset: i64,
+        batch_max_bytes: i32,
+        callback: EsReadCallback,
+        ctx: Context,
+    },
+    Trim {
+        stream: Ptr<Stream>,
+        new_start_offset: i64,
+        callback: EsCallback,
+        ctx: Context,
+    },
+    CloseStream {
+        stream: Ptr<Stream>,
+        callback: EsCallback,
+        ctx: Context,
+    },
+}
+
+enum CallbackCommand {
+    CreateStream {
+        callback: EsCreateCallback,
+        ctx: Context,
+        result: Result<u64, EsError>,
+    },
+    OpenStream {
+        callback: EsOpenCallback,
+        ctx: Context,
+        result: Result<Ptr<Stream>, EsError>,
+    },
+    Offset {
+        callback: EsOffsetCallback,
+        ctx: Context,
+        result: Result<i64, EsError>,
+    },
+    Read {
+        callback: EsReadCallback,
+        ctx: Context,
+        result: Result<Vec<Record>, EsError>,
+    },
+    Void {
+        callback: EsCallback,
+        ctx: Context,
+        result: Result<(), EsError>,
+    },
+}
+
+struct Runtime {
+    tx: mpsc::UnboundedSender<Command>,
+    callback_tx: Sender<CallbackCommand>,
+}
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// The runtime thread and callback threads, which are started on creation of the first frontend.
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        crate::init_log();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (callback_tx, callback_rx) = unbounded();
+
+        // Callback thread number is between [2, 4].
+        let cpu_sum = std::cmp::max(std::cmp::min(num_cpus::get(), 4), 2);
+        (0..cpu_sum).for_each(|i| {
+            let callback_rx = callback_rx.clone();
+            let _ = std::thread::Builder::new()
+                .name(format!("CallBackThread-{}", i))
+                .spawn(move || {
+                    while let Ok(command) = callback_rx.recv() {
+                        unsafe { complete(command) };
+                    }
+                    info!("Callback channel is dropped");
+                });
+        });
+        let _ = std::thread::Builder::new()
+            .name("Runtime".to_string())
+            .spawn(move || {
+                trace!("C runtime thread started");
+                tokio_uring::builder().start(async move {
+                    while let Some(command) = rx.recv().await {
+                        tokio_uring::spawn(async move { process_command(command).await });
+                    }
+                    info!("C command channel is dropped");
+                });
+            });
+        Runtime { tx, callback_tx }
+    })
+}
+
+fn dispatch(command: Command) -> i32 {
+    match runtime().tx.send(command) {
+        Ok(_) => 0,
+        Err(_) => {
+            error!("Failed to dispatch command to tokio-uring runtime");
+            -1
+        }
+    }
+}
+
+async fn process_command(command: Command) {
+    let callback_tx = &runtime().callback_tx;
+    let callback_command = match command {
+        Command::CreateStream {
+            frontend,
+            options,
+            callback,
+            ctx,
+        } => {
+            let frontend = unsafe { &*frontend.0 };
+            CallbackCommand::CreateStream {
+                callback,
+                ctx,
+                result: frontend.create(options).await,
+            }
+        }
+        Command::OpenStream {
+            frontend,
+            stream_id,
+            epoch,
+            callback,
+            ctx,
+        } => {
+            let frontend = unsafe { &*frontend.0 };
+            let result = frontend
+                .open(stream_id, OpenOptions::with_epoch(epoch))
+                .await
+                .map(|stream| Ptr(Box::into_raw(Box::new(stream))));
+            CallbackCommand::OpenStream {
+                callback,
+                ctx,
+                result,
+            }
+        }
+        Command::StartOffset {
+            stream,
+            callback,
+            ctx,
+        } => {
+            let stream = unsafe { &*stream.0 };
+            CallbackCommand::Offset {
+                callback,
+                ctx,
+                result: stream.start_offset().await,
+            }
+        }
+        Command::NextOffset {
+            stream,
+            callback,
+            ctx,
+        } => {
+            let stream = unsafe { &*stream.0 };
+            CallbackCommand::Offset {
+                callback,
+                ctx,
+                result: stream.next_offset().await,
+            }
+        }
+        Command::Append {
+            stream,
+            buf,
+            callback,
+            ctx,
+        } => {
+            let stream = unsafe { &*stream.0 };
+            let result = stream.append(buf).await.map(|result| result.base_offset);
+            CallbackCommand::Offset {
+                callback,
+                ctx,
+                result,
+            }
+        }
+        Command::Read {
+            stream,
+            start_offset,
+            end_offset,
+            batch_max_bytes,
+            callback,
+            ctx,
+        } => {
+            let stream = unsafe { &*stream.0 };
+            let result = match stream
+                .read_records(start_offset, end_offset, batch_max_bytes)
+                .await
+            {
+                Ok(records) => records
+                    .filter(|record| {
+                        record
+                            .as_ref()
+                            .map_or(true, |record| record.offset >= start_offset)
+                    })
+                    .collect(),
+                Err(e) => Err(e),
+            };
+            CallbackCommand::Read {
+                callback,
+                ctx,
+                result,
+            }
+        }
+        Command::Trim {
+            stream,
+            new_start_offset,
+            callback,
+            ctx,
+        } => {
+            let stream = unsafe { &*stream.0 };
+            CallbackCommand::Void {
+                callback,
+                ctx,
+                result: stream.trim(new_start_offset).await,
+            }
+        }
+        Command::CloseStream {
+            stream,
+            callback,
+            ctx,
+        } => {
+            // Take ownership of the stream, which is dropped on the runtime thread once closed.
+            let stream = unsafe { Box::from_raw(stream.0) };
+            CallbackCommand::Void {
+                callback,
+                ctx,
+                result: stream.close().await,
+            }
+        }
+    };
+    if callback_tx.send(callback_command).is_err() {
+        error!("Failed to hand over completion to callback threads");
+    }
+}
+
+/// Invoke the callback of a completed operation.
+///
+/// # Safety
+/// Callbacks and their contexts are provided by the caller of the operation.
+unsafe fn complete(command: CallbackCommand) {
+    match command {
+        CallbackCommand::CreateStream {
+            callback,
+            ctx,
+            result,
+        } => {
+            if let Some(callback) = callback {
+                match result {
+                    Ok(stream_id) => callback(ctx.0, ptr::null(), stream_id),
+                    Err(e) => with_status(&e, |status| callback(ctx.0, status, 0)),
+                }
+            }
+        }
+        CallbackCommand::OpenStream {
+            callback,
+            ctx,
+            result,
+        } => match (callback, result) {
+            (Some(callback), Ok(stream)) => callback(ctx.0, ptr::null(), stream.0),
+            (Some(callback), Err(e)) => {
+                with_status(&e, |status| callback(ctx.0, status, ptr::null_mut()))
+            }
+            (None, Ok(_)) => error!("Stream opened without callback is leaked"),
+            (None, Err(_)) => {}
+        },
+        CallbackCommand::Offset {
+            callback,
+            ctx,
+            result,
+        } => {
+            if let Some(callback) = callback {
+                match result {
+                    Ok(offset) => callback(ctx.0, ptr::null(), offset),
+                    Err(e) => with_status(&e, |status| callback(ctx.0, status, -1)),
+                }
+            }
+        }
+        CallbackCommand::Read {
+            callback,
+            ctx,
+            result,
+        } => {
+            if let Some(callback) = callback {
+                match result {
+                    Ok(records) => {
+                        let records = records.iter().map(to_es_record).collect::<Vec<_>>();
+                        callback(ctx.0, ptr::null(), records.as_ptr(), records.len())
+                    }
+                    Err(e) => with_status(&e, |status| callback(ctx.0, status, ptr::null(), 0)),
+                }
+            }
+        }
+        CallbackCommand::Void {
+            callback,
+            ctx,
+            result,
+        } => {
+            if let Some(callback) = callback {
+                match result {
+                    Ok(()) => callback(ctx.0, ptr::null()),
+                    Err(e) => with_status(&e, |status| callback(ctx.0, status)),
+                }
+            }
+        }
+    }
+}
+
+fn with_status<F>(err: &EsError, f: F)
+where
+    F: FnOnce(*const EsStatus),
+{
+    let message = CString::new(err.message.replace('\0', " ")).unwrap_or_default();
+    let status = EsStatus {
+        code: i32::from(err.code.0),
+        message: message.as_ptr(),
+    };
+    f(&status);
+}
+
+fn to_es_record(record: &Record) -> EsRecord {
+    let (key, key_len) = raw_parts(record.key.as_ref());
+    let (value, value_len) = raw_parts(record.value.as_ref());
+    EsRecord {
+        offset: record.offset,
+        timestamp: record.timestamp,
+        key,
+        key_len,
+        value,
+        value_len,
+    }
+}
+
+fn raw_parts(data: Option<&Bytes>) -> (*const u8, usize) {
+    data.map_or((ptr::null(), 0), |data| (data.as_ptr(), data.len()))
+}
+
+/// # Safety
+/// `data` is either `NULL` or valid for `len` bytes.
+unsafe fn to_bytes(data: *const u8, len: usize) -> Option<Bytes> {
+    if data.is_null() {
+        None
+    } else {
+        Some(Bytes::copy_from_slice(slice::from_raw_parts(data, len)))
+    }
+}
+
+/// # Safety
+/// `s` is either `NULL` or a nul-terminated string.
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Encode records into a record batch of `RecordMagic::Magic1`.
+///
+/// # Safety
+/// `records` is valid for `count` records.
+unsafe fn encode_records(
+    stream_id: u64,
+    records: *const EsRecord,
+    count: usize,
+) -> Result<Bytes, EsError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64);
+    let mut builder = RecordsBuilder::new();
+    for record in slice::from_raw_parts(records, count) {
+        let timestamp = if record.timestamp == 0 {
+            now
+        } else {
+            record.timestamp
+        };
+        builder.append(
+            timestamp,
+            to_bytes(record.key, record.key_len),
+            to_bytes(record.value, record.value_len),
+            &[],
+        );
+    }
+    let record_batch = builder
+        .build(stream_id as i64)
+        .map_err(|e| EsError::new(ErrorCode::BAD_REQUEST, &format!("{e:?}")))?;
+    let (buffers, _) = FlatRecordBatch::from(record_batch).encode();
+    Ok(Bytes::from(buffers.concat()))
+}
+
+/// Create a frontend of the placement driver at `access_point`.
+///
+/// `config_path` and `env_prefix` are nullable, see `FrontendBuilder`. `options` are `count`
+/// strings of `path=value`, for example, `client.io-timeout=20`.
+///
+/// Returns `NULL` on invalid arguments or configuration. The frontend is freed by
+/// `es_frontend_free`, after all streams opened from it are closed.
+///
+/// # Safety
+/// Strings are nul-terminated, and `options` is valid for `count` strings.
+#[no_mangle]
+pub unsafe extern "C" fn es_frontend_new(
+    access_point: *const c_char,
+    config_path: *const c_char,
+    env_prefix: *const c_char,
+    options: *const *const c_char,
+    count: usize,
+) -> *mut Frontend {
+    let Some(access_point) = to_str(access_point) else {
+        error!("Access point is not a valid string");
+        return ptr::null_mut();
+    };
+    let mut builder = FrontendBuilder::new(access_point);
+    if let Some(config_path) = to_str(config_path) {
+        builder = builder.with_config_file(config_path);
+    }
+    if let Some(env_prefix) = to_str(env_prefix) {
+        builder = builder.with_env_prefix(env_prefix);
+    }
+    if !options.is_null() {
+        for &option in slice::from_raw_parts(options, count) {
+            let Some((path, value)) = to_str(option).and_then(|option| option.split_once('='))
+            else {
+                error!("Option is not in the form of `path=value`");
+                return ptr::null_mut();
+            };
+            builder = builder.with_option(path.trim(), value.trim());
+        }
+    }
+    // Start the runtime before handing out any frontend.
+    runtime();
+    match builder.build() {
+        Ok(frontend) => Box::into_raw(Box::new(frontend)),
+        Err(e) => {
+            error!("Failed to create frontend: {e}");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+/// `frontend` is created by `es_frontend_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_frontend_free(frontend: *mut Frontend) {
+    if !frontend.is_null() {
+        let _ = Box::from_raw(frontend);
+    }
+}
+
+/// Create a stream, completing with its id.
+///
+/// # Safety
+/// `frontend` is created by `es_frontend_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_create(
+    frontend: *mut Frontend,
+    replica: u8,
+    ack_count: u8,
+    retention_ms: u64,
+    callback: EsCreateCallback,
+    ctx: *mut c_void,
+) -> i32 {
+    if frontend.is_null() {
+        return -1;
+    }
+    dispatch(Command::CreateStream {
+        frontend: Ptr(frontend),
+        options: StreamOptions {
+            replica,
+            ack: ack_count,
+            retention: Duration::from_millis(retention_ms),
+            compaction: None,
+            rollover: None,
+        },
+        callback,
+        ctx: Context(ctx),
+    })
+}
+
+/// Open a stream for write with `epoch`, fencing writers of smaller epochs.
+///
+/// # Safety
+/// `frontend` is created by `es_frontend_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_open(
+    frontend: *mut Frontend,
+    stream_id: u64,
+    epoch: u64,
+    callback: EsOpenCallback,
+    ctx: *mut c_void,
+) -> i32 {
+    if frontend.is_null() {
+        return -1;
+    }
+    dispatch(Command::OpenStream {
+        frontend: Ptr(frontend),
+        stream_id,
+        epoch,
+        callback,
+        ctx: Context(ctx),
+    })
+}
+
+/// # Safety
+/// `stream` is opened by `es_stream_open` and not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_id(stream: *const Stream) -> u64 {
+    if stream.is_null() {
+        return 0;
+    }
+    (*stream).id()
+}
+
+/// Get the offset of the first record that is not trimmed, completing with it.
+///
+/// # Safety
+/// `stream` is opened by `es_stream_open` and not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_start_offset(
+    stream: *mut Stream,
+    callback: EsOffsetCallback,
+    ctx: *mut c_void,
+) -> i32 {
+    if stream.is_null() {
+        return -1;
+    }
+    dispatch(Command::StartOffset {
+        stream: Ptr(stream),
+        callback,
+        ctx: Context(ctx),
+    })
+}
+
+/// Get the offset of the next record to append, completing with it.
+///
+/// # Safety
+/// `stream` is opened by `es_stream_open` and not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_next_offset(
+    stream: *mut Stream,
+    callback: EsOffsetCallback,
+    ctx: *mut c_void,
+) -> i32 {
+    if stream.is_null() {
+        return -1;
+    }
+    dispatch(Command::NextOffset {
+        stream: Ptr(stream),
+        callback,
+        ctx: Context(ctx),
+    })
+}
+
+/// Append `count` records as a record batch, completing with the offset of the first one.
+///
+/// Records are copied before this function returns.
+///
+/// # Safety
+/// `stream` is opened by `es_stream_open` and not closed yet, and `records` is valid for `count`
+/// records.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_append(
+    stream: *mut Stream,
+    records: *const EsRecord,
+    count: usize,
+    callback: EsOffsetCallback,
+    ctx: *mut c_void,
+) -> i32 {
+    if stream.is_null() || records.is_null() || count == 0 {
+        return -1;
+    }
+    let buf = match encode_records((*stream).id(), records, count) {
+        Ok(buf) => buf,
+        Err(e) => {
+            error!("Failed to encode records: {e}");
+            return -1;
+        }
+    };
+    dispatch(Command::Append {
+        stream: Ptr(stream),
+        buf,
+        callback,
+        ctx: Context(ctx),
+    })
+}
+
+/// Read records in `[start_offset, end_offset)`, of at most about `batch_max_bytes`, completing
+/// with records read.
+///
+/// # Safety
+/// `stream` is opened by `es_stream_open` and not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_read(
+    stream: *mut Stream,
+    start_offset: i64,
+    end_offset: i64,
+    batch_max_bytes: i32,
+    callback: EsReadCallback,
+    ctx: *mut c_void,
+) -> i32 {
+    if stream.is_null() || start_offset > end_offset || batch_max_bytes <= 0 {
+        return -1;
+    }
+    dispatch(Command::Read {
+        stream: Ptr(stream),
+        start_offset,
+        end_offset,
+        batch_max_bytes,
+        callback,
+        ctx: Context(ctx),
+    })
+}
+
+/// Trim records before `new_start_offset`.
+///
+/// # Safety
+/// `stream` is opened by `es_stream_open` and not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_trim(
+    stream: *mut Stream,
+    new_start_offset: i64,
+    callback: EsCallback,
+    ctx: *mut c_void,
+) -> i32 {
+    if stream.is_null() {
+        return -1;
+    }
+    dispatch(Command::Trim {
+        stream: Ptr(stream),
+        new_start_offset,
+        callback,
+        ctx: Context(ctx),
+    })
+}
+
+/// Close the stream and free it. The stream must not be used once this function returns `0`,
+/// while operations dispatched before still complete.
+///
+/// # Safety
+/// `stream` is opened by `es_stream_open` and not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn es_stream_close(
+    stream: *mut Stream,
+    callback: EsCallback,
+    ctx: *mut c_void,
+) -> i32 {
+    if stream.is_null() {
+        return -1;
+    }
+    dispatch(Command::CloseStream {
+        stream: Ptr(stream),
+        callback,
+        ctx: Context(ctx),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use bytes::Bytes;
+    use model::record::flat_record::FlatRecordBatch;
+
+    use super::{encode_records, es_stream_append, EsRecord};
+
+    #[test]
+    fn test_encode_records() {
+        let value = b"hello";
+        let key = b"k";
+        let records = [
+            EsRecord {
+                offset: 0,
+                timestamp: 1000,
+                key: key.as_ptr(),
+                key_len: key.len(),
+                value: value.as_ptr(),
+                value_len: value.len(),
+            },
+            EsRecord {
+                offset: 0,
+                timestamp: 1001,
+                key: ptr::null(),
+                key_len: 0,
+                value: ptr::null(),
+                value_len: 0,
+            },
+        ];
+        let mut buf = unsafe { encode_records(7, records.as_ptr(), records.len()) }.unwrap();
+        let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf).unwrap();
+        assert_eq!(7, record_batch.stream_id());
+        let records = record_batch
+            .records()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(2, records.len());
+        assert_eq!(Some(Bytes::from_static(b"k")), records[0].key);
+        assert_eq!(Some(Bytes::from_static(b"hello")), records[0].value);
+        assert_eq!(1001, records[1].timestamp);
+        assert_eq!(None, records[1].value);
+
+        // Invalid arguments are rejected without dispatching.
+        assert_eq!(-1, unsafe {
+            es_stream_append(ptr::null_mut(), records.as_ptr(), 1, None, ptr::null_mut())
+        });
+    }
+}
@@ -5,7 +5,7 @@ use jni::objects::GlobalRef;
 use model::error::EsError;
 
 use super::tracing::Tracer;
-use crate::{Frontend, Stream};
+use crate::{Frontend, Producer, Stream, StreamUpdate};
 
 pub enum Command<'a> {
     CreateStream {
@@ -21,6 +21,26 @@ pub enum Command<'a> {
         epoch: u64,
         future: GlobalRef,
     },
+    DescribeStream {
+        front_end: &'a mut Frontend,
+        stream_id: u64,
+        future: GlobalRef,
+    },
+    ListRanges {
+        front_end: &'a mut Frontend,
+        stream_id: u64,
+        future: GlobalRef,
+    },
+    UpdateStream {
+        front_end: &'a mut Frontend,
+        stream_id: u64,
+        update: StreamUpdate,
+        future: GlobalRef,
+    },
+    ListStreams {
+        front_end: &'a mut Frontend,
+        future: GlobalRef,
+    },
     StartOffset {
         stream: &'a mut Stream,
         future: GlobalRef,
@@ -61,8 +81,30 @@ pub enum Command<'a> {
         stream: &'a mut Stream,
         future: GlobalRef,
     },
+
+    ProducerSend {
+        producer: ProducerPtr,
+        buf: Bytes,
+        future: GlobalRef,
+    },
+
+    ProducerFlush {
+        producer: ProducerPtr,
+    },
+
+    FreeProducer {
+        producer: ProducerPtr,
+    },
 }
 
+/// Pointer of a `Producer` handed out to Java.
+///
+/// `Producer` is NOT `Send`. It is created by a Java thread without spawning any task, and from then on
+/// only accessed by the tokio-uring runtime thread, including when it is dropped.
+pub struct ProducerPtr(pub *mut Producer);
+
+unsafe impl Send for ProducerPtr {}
+
 pub enum CallbackCommand {
     Append {
         future: GlobalRef,
@@ -82,6 +124,26 @@ pub enum CallbackCommand {
         future: GlobalRef,
         ptr: i64,
     },
+    /// Completes with a FlatBuffers `Stream` table.
+    DescribeStream {
+        future: GlobalRef,
+        buf: Bytes,
+    },
+    /// Completes with a FlatBuffers `ListRangeResponse` table.
+    ListRanges {
+        future: GlobalRef,
+        buf: Bytes,
+    },
+    /// Completes with a FlatBuffers `Stream` table.
+    UpdateStream {
+        future: GlobalRef,
+        buf: Bytes,
+    },
+    /// Completes with a FlatBuffers `ListResourceResponse` table, of which resources are streams.
+    ListStreams {
+        future: GlobalRef,
+        buf: Bytes,
+    },
     StartOffset {
         future: GlobalRef,
         offset: i64,
@@ -99,6 +161,10 @@ pub enum CallbackCommand {
     CloseStream {
         future: GlobalRef,
     },
+    ProducerSend {
+        future: GlobalRef,
+        offset: i64,
+    },
     ClientError {
         future: GlobalRef,
         err: EsError,
@@ -1,13 +1,18 @@
 use bytes::Bytes;
+use flatbuffers::FlatBufferBuilder;
 use jni::objects::{
-    GlobalRef, JByteBuffer, JClass, JMethodID, JObject, JString, JValue, JValueGen,
+    GlobalRef, JByteBuffer, JClass, JMethodID, JObject, JObjectArray, JString, JValue, JValueGen,
 };
 use jni::sys::{jint, jlong, JNINativeInterface_, JNI_VERSION_1_8};
 use jni::{JNIEnv, JavaVM};
 use log::{error, info, trace};
 use minitrace::future::FutureExt;
 use minitrace::Span;
-use model::error::EsError;
+use model::{error::EsError, range::RangeMetadata, stream::StreamMetadata};
+use protocol::rpc::header::{
+    ErrorCode, ListRangeResponseT, ListResourceResponseT, RangeT, ResourceT, ResourceType, StatusT,
+    StreamT,
+};
 use std::alloc::Layout;
 use std::cell::{OnceCell, RefCell};
 use std::ffi::c_void;
@@ -16,10 +21,13 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-use crate::{Frontend, Stopwatch, Stream, StreamOptions};
+use crate::{
+    Frontend, FrontendBuilder, OpenOptions, Producer, ProducerOptions, Stopwatch, Stream,
+    StreamOptions, StreamUpdate,
+};
 use crossbeam::channel::{unbounded, Sender};
 
-use super::cmd::{CallbackCommand, Command};
+use super::cmd::{CallbackCommand, Command, ProducerPtr};
 use super::tracing::{Tracer, TracingService};
 
 static mut TX: OnceCell<mpsc::UnboundedSender<Command>> = OnceCell::new();
@@ -59,6 +67,57 @@ async fn process_command(cmd: Command<'_>) {
         } => {
             process_open_stream_command(front_end, stream_id, epoch, future).await;
         }
+        Command::DescribeStream {
+            front_end,
+            stream_id,
+            future,
+        } => {
+            let result = front_end
+                .describe(stream_id)
+                .await
+                .map(|stream| encode_stream(&stream));
+            complete_with_flat_buffer(result, future, |future, buf| {
+                CallbackCommand::DescribeStream { future, buf }
+            });
+        }
+        Command::ListRanges {
+            front_end,
+            stream_id,
+            future,
+        } => {
+            let result = front_end
+                .list_ranges(stream_id)
+                .await
+                .map(|ranges| encode_ranges(&ranges));
+            complete_with_flat_buffer(result, future, |future, buf| CallbackCommand::ListRanges {
+                future,
+                buf,
+            });
+        }
+        Command::UpdateStream {
+            front_end,
+            stream_id,
+            update,
+            future,
+        } => {
+            let result = front_end
+                .update(stream_id, update)
+                .await
+                .map(|stream| encode_stream(&stream));
+            complete_with_flat_buffer(result, future, |future, buf| {
+                CallbackCommand::UpdateStream { future, buf }
+            });
+        }
+        Command::ListStreams { front_end, future } => {
+            let result = front_end
+                .list_streams()
+                .await
+                .map(|streams| encode_streams(&streams));
+            complete_with_flat_buffer(result, future, |future, buf| CallbackCommand::ListStreams {
+                future,
+                buf,
+            });
+        }
         Command::StartOffset { stream, future } => {
             process_start_offset_command(stream, future).await;
         }
@@ -104,9 +163,41 @@ async fn process_command(cmd: Command<'_>) {
         Command::Delete { stream, future } => {
             process_delete_stream_command(stream, future).await;
         }
+        Command::ProducerSend {
+            producer,
+            buf,
+            future,
+        } => {
+            process_producer_send_command(producer, buf, future).await;
+        }
+        Command::ProducerFlush { producer } => {
+            let producer = unsafe { &*producer.0 };
+            producer.flush();
+        }
+        Command::FreeProducer { producer } => {
+            // Dropping the producer sends records accumulated.
+            let _ = unsafe { Box::from_raw(producer.0) };
+        }
     }
 }
 
+async fn process_producer_send_command(producer: ProducerPtr, buf: Bytes, future: GlobalRef) {
+    trace!("Start processing producer send command");
+    // Accumulate the record before awaiting, so that records are appended in the order of commands.
+    let result = unsafe { &*producer.0 }.send(buf);
+    match result.await {
+        Ok(offset) => {
+            let tx = unsafe { CALLBACK_TX.get() }.unwrap();
+            let _ = tx.send(CallbackCommand::ProducerSend { future, offset });
+        }
+        Err(err) => {
+            let tx = unsafe { CALLBACK_TX.get() }.unwrap();
+            let _ = tx.send(CallbackCommand::ClientError { future, err });
+        }
+    };
+    trace!("Producer send command finished");
+}
+
 async fn process_close_stream_command(stream: &Stream, future: GlobalRef) {
     trace!("Start processing close command");
     let result = stream.close().await;
@@ -226,7 +317,9 @@ async fn process_open_stream_command(
     future: GlobalRef,
 ) {
     trace!("Start processing open_stream command");
-    let result = front_end.open(stream_id, epoch).await;
+    let result = front_end
+        .open(stream_id, OpenOptions::with_epoch(epoch))
+        .await;
     match result {
         Ok(stream) => {
             let ptr = Box::into_raw(Box::new(stream)) as jlong;
@@ -255,6 +348,8 @@ async fn process_create_stream_command(
         replica,
         ack: ack_count,
         retention,
+        compaction: None,
+        rollover: None,
     };
     let result = front_end.create(options).await;
     match result {
@@ -275,6 +370,59 @@ async fn process_create_stream_command(
     trace!("Create_stream command finished");
 }
 
+/// Hand the encoded result over to callback threads, which complete `future` with it.
+fn complete_with_flat_buffer<F>(result: Result<Bytes, EsError>, future: GlobalRef, command: F)
+where
+    F: FnOnce(GlobalRef, Bytes) -> CallbackCommand,
+{
+    let tx = unsafe { CALLBACK_TX.get() }.unwrap();
+    let _ = match result {
+        Ok(buf) => tx.send(command(future, buf)),
+        Err(err) => tx.send(CallbackCommand::ClientError { future, err }),
+    };
+}
+
+fn encode_stream(stream: &StreamMetadata) -> Bytes {
+    let mut builder = FlatBufferBuilder::new();
+    let stream = StreamT::from(stream).pack(&mut builder);
+    builder.finish(stream, None);
+    Bytes::copy_from_slice(builder.finished_data())
+}
+
+fn encode_ranges(ranges: &[RangeMetadata]) -> Bytes {
+    let mut response = ListRangeResponseT::default();
+    response.status = Box::new(ok_status());
+    response.ranges = ranges.iter().map(RangeT::from).collect();
+    let mut builder = FlatBufferBuilder::new();
+    let response = response.pack(&mut builder);
+    builder.finish(response, None);
+    Bytes::copy_from_slice(builder.finished_data())
+}
+
+fn encode_streams(streams: &[StreamMetadata]) -> Bytes {
+    let mut response = ListResourceResponseT::default();
+    response.status = Box::new(ok_status());
+    response.resources = streams
+        .iter()
+        .map(|stream| {
+            let mut resource = ResourceT::default();
+            resource.type_ = ResourceType::RESOURCE_STREAM;
+            resource.stream = Some(Box::new(StreamT::from(stream)));
+            resource
+        })
+        .collect();
+    let mut builder = FlatBufferBuilder::new();
+    let response = response.pack(&mut builder);
+    builder.finish(response, None);
+    Bytes::copy_from_slice(builder.finished_data())
+}
+
+fn ok_status() -> StatusT {
+    let mut status = StatusT::default();
+    status.code = ErrorCode::OK;
+    status
+}
+
 async fn process_trim_stream_command(stream: &Stream, new_start_offset: i64, future: GlobalRef) {
     let result = stream.trim(new_start_offset).await;
     match result {
@@ -403,6 +551,12 @@ pub extern "system" fn JNI_OnLoad(vm: JavaVM, _: *mut c_void) -> jint {
                             CallbackCommand::OpenStream { future, ptr } => {
                                 complete_future_with_stream(future, ptr);
                             }
+                            CallbackCommand::DescribeStream { future, buf }
+                            | CallbackCommand::ListRanges { future, buf }
+                            | CallbackCommand::UpdateStream { future, buf }
+                            | CallbackCommand::ListStreams { future, buf } => {
+                                complete_future_with_direct_byte_buffer(future, vec![buf]);
+                            }
                             CallbackCommand::StartOffset { future, offset } => {
                                 complete_future_with_jlong(future, offset);
                             }
@@ -421,6 +575,9 @@ pub extern "system" fn JNI_OnLoad(vm: JavaVM, _: *mut c_void) -> jint {
                             CallbackCommand::Delete { future } => {
                                 complete_future_with_void(future);
                             }
+                            CallbackCommand::ProducerSend { future, offset } => {
+                                complete_future_with_jlong(future, offset);
+                            }
                         },
                         Err(_) => {
                             info!("Callback channel is dropped");
@@ -468,23 +625,55 @@ pub extern "system" fn Java_com_automq_elasticstream_client_jni_Frontend_getFron
     mut env: JNIEnv,
     _class: JClass,
     access_point: JString,
+    config_path: JString,
+    env_prefix: JString,
+    options: JObjectArray,
 ) -> jlong {
-    if let Ok(access_point) = env.get_string(&access_point) {
-        let access_point: String = access_point.into();
-        let result = Frontend::new(&access_point);
-        match result {
+    match frontend_builder(&mut env, access_point, config_path, env_prefix, options) {
+        Some(builder) => match builder.build() {
             Ok(frontend) => Box::into_raw(Box::new(frontend)) as jlong,
             Err(err) => {
                 throw_exception(&mut env, &err.to_string());
                 0
             }
+        },
+        None => {
+            info!("Failed to construct GetFrontend command. Ignore a GetFrontend request");
+            0
         }
-    } else {
-        info!("Failed to construct GetFrontend command. Ignore a GetFrontend request");
-        0
     }
 }
 
+/// Builder of the frontend from arguments of `getFrontend`, of which `config_path`, `env_prefix`
+/// and `options` are nullable. Each option is a `path=value` string, see `FrontendBuilder::with_option`.
+fn frontend_builder(
+    env: &mut JNIEnv,
+    access_point: JString,
+    config_path: JString,
+    env_prefix: JString,
+    options: JObjectArray,
+) -> Option<FrontendBuilder> {
+    let access_point: String = env.get_string(&access_point).ok()?.into();
+    let mut builder = FrontendBuilder::new(&access_point);
+    if !config_path.is_null() {
+        let config_path: String = env.get_string(&config_path).ok()?.into();
+        builder = builder.with_config_file(config_path);
+    }
+    if !env_prefix.is_null() {
+        let env_prefix: String = env.get_string(&env_prefix).ok()?.into();
+        builder = builder.with_env_prefix(&env_prefix);
+    }
+    if !options.is_null() {
+        for i in 0..env.get_array_length(&options).ok()? {
+            let option = JString::from(env.get_object_array_element(&options, i).ok()?);
+            let option: String = env.get_string(&option).ok()?.into();
+            let (path, value) = option.split_once('=')?;
+            builder = builder.with_option(path.trim(), value.trim());
+        }
+    }
+    Some(builder)
+}
+
 /// # Safety
 ///
 /// Expose `C` API to Java
@@ -625,6 +814,100 @@ pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Frontend_
     }
 }
 
+/// # Safety
+///
+/// Expose `C` API to Java
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Frontend_describe(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: *mut Frontend,
+    stream_id: jlong,
+    future: JObject,
+) {
+    let command = env.new_global_ref(future).map(|future| {
+        let front_end = unsafe { &mut *ptr };
+        Command::DescribeStream {
+            front_end,
+            stream_id: stream_id as u64,
+            future,
+        }
+    });
+    send_command(env, command);
+}
+
+/// # Safety
+///
+/// Expose `C` API to Java
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Frontend_listRanges(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: *mut Frontend,
+    stream_id: jlong,
+    future: JObject,
+) {
+    let command = env.new_global_ref(future).map(|future| {
+        let front_end = unsafe { &mut *ptr };
+        Command::ListRanges {
+            front_end,
+            stream_id: stream_id as u64,
+            future,
+        }
+    });
+    send_command(env, command);
+}
+
+/// # Safety
+///
+/// Expose `C` API to Java. Negative `replica`, `ack` or `retention_millis` leaves the field unchanged.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Frontend_update(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: *mut Frontend,
+    stream_id: jlong,
+    replica: jint,
+    ack: jint,
+    retention_millis: jlong,
+    future: JObject,
+) {
+    let command = env.new_global_ref(future).map(|future| {
+        let front_end = unsafe { &mut *ptr };
+        let update = StreamUpdate {
+            replica: (replica >= 0).then_some(replica as u8),
+            ack: (ack >= 0).then_some(ack as u8),
+            retention: (retention_millis >= 0)
+                .then(|| Duration::from_millis(retention_millis as u64)),
+        };
+        Command::UpdateStream {
+            front_end,
+            stream_id: stream_id as u64,
+            update,
+            future,
+        }
+    });
+    send_command(env, command);
+}
+
+/// # Safety
+///
+/// Expose `C` API to Java
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Frontend_listStreams(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: *mut Frontend,
+    future: JObject,
+) {
+    let command = env.new_global_ref(future).map(|future| {
+        let front_end = unsafe { &mut *ptr };
+        Command::ListStreams { front_end, future }
+    });
+    send_command(env, command);
+}
+
 /// # Safety
 ///
 /// Expose `C` API to Java
@@ -919,6 +1202,93 @@ pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Stream_re
     }
 }
 
+/// # Safety
+///
+/// Expose `C` API to Java
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Stream_newProducer(
+    _env: JNIEnv,
+    _class: JClass,
+    ptr: *mut Stream,
+    linger_ms: jlong,
+    batch_size: jint,
+) -> jlong {
+    let stream = unsafe { &*ptr };
+    let options = ProducerOptions {
+        linger_ms: linger_ms.max(0) as u64,
+        batch_size: batch_size.max(0) as usize,
+    };
+    // Creating a producer spawns no task, so it is safe to do so on the Java thread.
+    let producer = stream.producer(options);
+    Box::into_raw(Box::new(producer)) as jlong
+}
+
+/// # Safety
+///
+/// Expose `C` API to Java
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Producer_send(
+    mut env: JNIEnv,
+    _class: JClass,
+    ptr: *mut Producer,
+    data: JObject,
+    future: JObject,
+) {
+    trace!("Started jni_Producer_send");
+    let buf = env.get_direct_buffer_address((&data).into());
+    let len = env.get_direct_buffer_capacity((&data).into());
+    let command = match (buf, len, env.new_global_ref(future)) {
+        (Ok(buf), Ok(len), Ok(future)) => {
+            // # Safety
+            // Java caller guarantees that `buf` will remain valid until `Future#complete` is called.
+            // As a result, we can safely treat the slice as 'static.
+            let slice = unsafe { slice::from_raw_parts(buf, len) };
+            Ok(Command::ProducerSend {
+                producer: ProducerPtr(ptr),
+                buf: Bytes::from_static(slice),
+                future,
+            })
+        }
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => Err(e),
+    };
+    send_command(env, command);
+}
+
+/// # Safety
+///
+/// Expose `C` API to Java
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Producer_flush(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: *mut Producer,
+) {
+    send_command(
+        env,
+        Ok(Command::ProducerFlush {
+            producer: ProducerPtr(ptr),
+        }),
+    );
+}
+
+/// # Safety
+///
+/// Expose `C` API to Java
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_automq_elasticstream_client_jni_Producer_freeProducer(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: *mut Producer,
+) {
+    // The producer is dropped by the runtime thread, which flushes records accumulated.
+    send_command(
+        env,
+        Ok(Command::FreeProducer {
+            producer: ProducerPtr(ptr),
+        }),
+    );
+}
+
 #[inline]
 fn send_command(mut env: JNIEnv, command: jni::errors::Result<Command<'static>>) {
     if let Ok(command) = command {
@@ -1153,3 +1523,74 @@ fn complete_future_with_direct_byte_buffer(future: GlobalRef, buffers: Vec<Bytes
         error!("Bad alignment");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use model::{range::RangeMetadata, range_server::RangeServer, stream::StreamMetadata};
+    use protocol::rpc::header::{
+        ErrorCode, ListRangeResponse, ListResourceResponse, RangeServerState, ResourceType, Stream,
+    };
+
+    use super::{encode_ranges, encode_stream, encode_streams};
+
+    fn stream(stream_id: u64) -> StreamMetadata {
+        StreamMetadata {
+            stream_id,
+            replica: 3,
+            ack_count: 2,
+            retention_period: Duration::from_secs(3600),
+            start_offset: 10,
+            epoch: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_encode_stream() {
+        let buf = encode_stream(&stream(1));
+        let stream = flatbuffers::root::<Stream>(&buf).unwrap();
+        assert_eq!(1, stream.stream_id());
+        assert_eq!(3, stream.replica());
+        assert_eq!(2, stream.ack_count());
+        assert_eq!(3_600_000, stream.retention_period_ms());
+        assert_eq!(10, stream.start_offset());
+        assert_eq!(1, stream.epoch());
+    }
+
+    #[test]
+    fn test_encode_ranges() {
+        let mut range = RangeMetadata::new(1, 0, 1, 0, Some(100));
+        range.replica_mut().push(RangeServer::new(
+            7,
+            "127.0.0.1:10911",
+            RangeServerState::RANGE_SERVER_STATE_READ_WRITE,
+        ));
+        let buf = encode_ranges(&[range, RangeMetadata::new(1, 1, 1, 100, None)]);
+        let response = flatbuffers::root::<ListRangeResponse>(&buf).unwrap();
+        assert_eq!(ErrorCode::OK, response.status().code());
+        let ranges = response.ranges();
+        assert_eq!(2, ranges.len());
+        assert_eq!(100, ranges.get(0).end());
+        let servers = ranges.get(0).servers().unwrap();
+        assert_eq!(1, servers.len());
+        assert_eq!(7, servers.get(0).server_id());
+        assert_eq!("127.0.0.1:10911", servers.get(0).advertise_addr());
+        assert_eq!(100, ranges.get(1).start());
+        assert_eq!(-1, ranges.get(1).end());
+    }
+
+    #[test]
+    fn test_encode_streams() {
+        let buf = encode_streams(&[stream(1), stream(2)]);
+        let response = flatbuffers::root::<ListResourceResponse>(&buf).unwrap();
+        assert_eq!(ErrorCode::OK, response.status().code());
+        let resources = response.resources();
+        assert_eq!(2, resources.len());
+        for (i, resource) in resources.iter().enumerate() {
+            assert_eq!(ResourceType::RESOURCE_STREAM, resource.type_());
+            assert_eq!(i as i64 + 1, resource.stream().unwrap().stream_id());
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod c;
 pub(crate) mod cmd;
 pub mod java;
 pub(crate) mod stopwatch;
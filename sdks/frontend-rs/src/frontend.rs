@@ -1,73 +1,298 @@
-use std::{cell::RefCell, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::{Stream, StreamOptions};
+use crate::{OpenOptions, Stream, StreamOptions, StreamUpdate, TransactionCoordinator};
 
-use config::Configuration;
+use bytes::Bytes;
+use config::{error::ConfigurationError, Configuration};
 use log::info;
-use model::error::EsError;
-use replication::StreamClient;
+use model::{
+    error::EsError,
+    range::{RangeMetadata, ReplicaLayout},
+    stream::StreamMetadata,
+};
+use protocol::rpc::header::ErrorCode;
+use replication::{
+    request::{CreateStreamRequest, OpenMode, OpenStreamRequest},
+    InflightUsage, StreamClient,
+};
+
+/// Builder of `Frontend` with the full configuration surface of clients.
+///
+/// Configuration is assembled in the following order, later ones taking precedence:
+/// 1. `with_configuration` or `with_config_file`, defaults to `Configuration::default()`;
+/// 2. environment variables of `with_env_prefix`, see `Configuration::apply_env`;
+/// 3. items of `with_option`, see `Configuration::set`;
+/// 4. the access point.
+///
+/// The assembled configuration is validated on `build`.
+#[derive(Debug)]
+pub struct FrontendBuilder {
+    access_point: String,
+    config: Option<Configuration>,
+    config_file: Option<PathBuf>,
+    env_prefix: Option<String>,
+    options: Vec<(String, String)>,
+}
+
+impl FrontendBuilder {
+    pub fn new(access_point: &str) -> Self {
+        Self {
+            access_point: access_point.to_owned(),
+            config: None,
+            config_file: None,
+            env_prefix: None,
+            options: vec![],
+        }
+    }
+
+    /// Base configuration, replacing a previously set config file.
+    pub fn with_configuration(mut self, config: Configuration) -> Self {
+        self.config = Some(config);
+        self.config_file = None;
+        self
+    }
+
+    /// YAML file of the base configuration, replacing a previously set configuration.
+    pub fn with_config_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.config_file = Some(path.as_ref().to_path_buf());
+        self.config = None;
+        self
+    }
+
+    pub fn with_env_prefix(mut self, prefix: &str) -> Self {
+        self.env_prefix = Some(prefix.to_owned());
+        self
+    }
+
+    /// Set a configuration item, for example, `client.io-timeout` to `20`.
+    pub fn with_option(mut self, path: &str, value: &str) -> Self {
+        self.options.push((path.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn build(self) -> Result<Frontend, EsError> {
+        let config = self.configuration().map_err(|e| {
+            EsError::new(
+                ErrorCode::BAD_REQUEST,
+                &format!("invalid frontend configuration, {e}"),
+            )
+        })?;
+        Ok(Frontend::with_configuration(config))
+    }
+
+    fn configuration(self) -> Result<Configuration, ConfigurationError> {
+        let mut config = match (self.config, self.config_file) {
+            (Some(config), _) => config,
+            (None, Some(path)) => Configuration::from_yaml_file(path)?,
+            (None, None) => Configuration::default(),
+        };
+        if let Some(prefix) = &self.env_prefix {
+            config.apply_env(prefix)?;
+        }
+        for (path, value) in &self.options {
+            config.set(path, value)?;
+        }
+        config.placement_driver = self.access_point;
+        config.check_client()?;
+        Ok(config)
+    }
+}
+
+/// Max number of streams to list from placement driver at a time.
+const LIST_STREAMS_LIMIT: i32 = 1024;
 
 #[derive(Debug, Clone)]
 pub struct Frontend {
     #[allow(dead_code)]
     config: Arc<Configuration>,
-    stream_clients: Vec<StreamClient>,
-    round_robin: RefCell<usize>,
+    stream_client: StreamClient,
 }
 
 impl Frontend {
+    /// Frontend of the given placement driver access point, with default configuration.
+    ///
+    /// Use `FrontendBuilder` to customize configuration, e.g. timeouts and retry policy of clients.
     pub fn new(access_point: &str) -> Result<Self, EsError> {
-        info!("New frontend with access-point: {access_point}");
-        let mut config = Configuration {
-            placement_driver: access_point.to_owned(),
-            ..Default::default()
-        };
-        config.replication.connection_pool_size = 2;
-        config.replication.thread_count = 4;
+        FrontendBuilder::new(access_point).build()
+    }
+
+    fn with_configuration(config: Configuration) -> Self {
+        info!(
+            "New frontend with access-point: {}",
+            config.placement_driver
+        );
         let config = Arc::new(config);
 
-        let mut stream_clients = vec![];
-        for i in 0..config.replication.thread_count {
-            let stream_client = StreamClient::new(Arc::clone(&config), i);
-            stream_clients.push(stream_client);
-        }
-        Ok(Self {
+        // Streams are sharded across runtime threads of the stream client by stream id.
+        let stream_client = StreamClient::new(Arc::clone(&config), 0);
+        Self {
             config,
-            stream_clients,
-            round_robin: RefCell::new(0),
-        })
+            stream_client,
+        }
     }
 
     pub async fn create(&self, options: StreamOptions) -> Result<u64, EsError> {
         info!("Creating stream {options:?}");
-        let stream_id = self.stream_clients[0]
-            .create_stream(options.replica, options.ack, options.retention)
+        let stream_id = self
+            .stream_client
+            .create(CreateStreamRequest {
+                replica: options.replica,
+                ack_count: options.ack,
+                retention_period: options.retention,
+                compaction: options.compaction,
+                rollover: options.rollover,
+            })
             .await?;
         info!("Created Stream[id={stream_id}]");
         Ok(stream_id)
     }
 
-    pub async fn open(&self, stream_id: u64, epoch: u64) -> Result<Stream, EsError> {
-        info!("Opening stream[id={stream_id}]");
-        let stream_client = self.route_client()?;
-        stream_client.open_stream(stream_id, epoch).await?;
+    /// Open the stream with the given options, for write by default.
+    pub async fn open(&self, stream_id: u64, options: OpenOptions) -> Result<Stream, EsError> {
+        info!("Opening stream[id={stream_id}] with {options:?}");
+        let mode = if options.read_only {
+            if options.rollover.is_some() || options.layout != ReplicaLayout::Full {
+                return Err(EsError::new(
+                    ErrorCode::BAD_REQUEST,
+                    "Rollover policy and replica layout are not applicable to read-only streams",
+                ));
+            }
+            OpenMode::ReadOnly
+        } else {
+            OpenMode::ReadWrite
+        };
+        let stream_client = self.stream_client.clone();
+        stream_client
+            .open_stream_with(OpenStreamRequest {
+                stream_id,
+                epoch: if options.read_only { 0 } else { options.epoch },
+                mode,
+                rollover: options.rollover,
+                layout: options.layout,
+            })
+            .await?;
         info!("Opened Stream[id={stream_id}]");
-        Ok(Stream::new(stream_id, stream_client))
+        Ok(Stream::new(stream_id, mode, stream_client))
     }
 
-    fn route_client(&self) -> Result<StreamClient, EsError> {
-        debug_assert!(
-            !self.stream_clients.is_empty(),
-            "Clients should NOT be empty"
-        );
-        let index = *self.round_robin.borrow() % self.stream_clients.len();
-        *self.round_robin.borrow_mut() += 1;
-        self.stream_clients
-            .get(index)
-            .cloned()
-            .ok_or(EsError::unexpected(&format!(
-                "Cannot find client for index {}",
-                index
-            )))
+    /// Describe metadata of the stream, which need not be opened.
+    pub async fn describe(&self, stream_id: u64) -> Result<StreamMetadata, EsError> {
+        self.stream_client.describe_stream(stream_id).await
+    }
+
+    /// List ranges of the stream, along with range servers that hold their replicas.
+    pub async fn list_ranges(&self, stream_id: u64) -> Result<Vec<RangeMetadata>, EsError> {
+        self.stream_client.list_ranges(stream_id).await
+    }
+
+    /// Update replica count, ack count or retention of the stream.
+    pub async fn update(
+        &self,
+        stream_id: u64,
+        update: StreamUpdate,
+    ) -> Result<StreamMetadata, EsError> {
+        if update.is_empty() {
+            return Err(EsError::new(ErrorCode::BAD_REQUEST, "Nothing to update"));
+        }
+        info!("Updating stream[id={stream_id}] with {update:?}");
+        let stream = self
+            .stream_client
+            .update_stream(stream_id, update.replica, update.ack, update.retention)
+            .await?;
+        info!("Updated Stream[id={stream_id}] to {stream:?}");
+        Ok(stream)
+    }
+
+    /// List all streams that are not deleted.
+    pub async fn list_streams(&self) -> Result<Vec<StreamMetadata>, EsError> {
+        let mut streams = vec![];
+        let mut continuation = None;
+        loop {
+            let (page, next) = self
+                .stream_client
+                .list_streams(LIST_STREAMS_LIMIT, continuation)
+                .await?;
+            streams.extend(page);
+            match next {
+                Some(next) if !next.is_empty() => continuation = Some(next),
+                _ => break,
+            }
+        }
+        Ok(streams)
+    }
+
+    /// Get the key-value pair of `key` from the key-value store of placement driver, or those in
+    /// `[key, range_end)` if `range_end` is given.
+    pub async fn kv_range(
+        &self,
+        key: Bytes,
+        range_end: Option<Bytes>,
+    ) -> Result<Vec<(Bytes, Bytes)>, EsError> {
+        self.stream_client.kv_range(key, range_end).await
+    }
+
+    /// Put a key-value pair into the key-value store of placement driver.
+    pub async fn kv_put(&self, key: Bytes, value: Bytes) -> Result<(), EsError> {
+        self.stream_client.kv_put(key, value).await
+    }
+
+    /// Delete `key`, or keys in `[key, range_end)` if `range_end` is given, from the key-value
+    /// store of placement driver, returning the number of deleted keys.
+    pub async fn kv_delete_range(
+        &self,
+        key: Bytes,
+        range_end: Option<Bytes>,
+    ) -> Result<u64, EsError> {
+        self.stream_client.kv_delete_range(key, range_end).await
+    }
+
+    /// Coordinator of transactions appending to multiple streams atomically on behalf of the
+    /// producer. See `TransactionCoordinator` for requirements on `producer_id`.
+    pub fn transaction_coordinator(&self, producer_id: &str) -> TransactionCoordinator {
+        TransactionCoordinator::new(producer_id.to_owned(), self.stream_client.clone())
+    }
+
+    /// Usage of in-flight appends of all streams.
+    pub fn inflight_usage(&self) -> InflightUsage {
+        self.stream_client.inflight_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use config::Configuration;
+
+    use super::FrontendBuilder;
+
+    #[test]
+    fn test_builder_configuration() -> Result<(), Box<dyn Error>> {
+        let mut base = Configuration::default();
+        base.client.io_timeout = 10;
+        base.client.connect_timeout = 10;
+        let config = FrontendBuilder::new("10.0.0.1:12378")
+            .with_configuration(base)
+            .with_option("client.io-timeout", "50")
+            .with_option("replication.connection-pool-size", "0")
+            .configuration()?;
+        assert_eq!("10.0.0.1:12378", config.placement_driver);
+        assert_eq!(50, config.client.io_timeout);
+        assert_eq!(10, config.client.connect_timeout);
+        assert!(config.replication.connection_pool_size > 0);
+        assert!(!config.client.client_id.is_empty());
+
+        assert!(FrontendBuilder::new("10.0.0.1:12378")
+            .with_option("client.retry.max-attempt", "0")
+            .configuration()
+            .is_err());
+        assert!(FrontendBuilder::new("10.0.0.1:12378")
+            .with_config_file("/no/such/file.yaml")
+            .configuration()
+            .is_err());
+        Ok(())
     }
 }
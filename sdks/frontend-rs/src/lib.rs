@@ -3,17 +3,25 @@ pub mod bindings;
 pub mod error;
 pub mod frontend;
 pub mod log;
+pub mod producer;
+pub mod reader;
+pub mod records;
 pub mod stream;
 pub mod stream_options;
 mod time_format;
+pub mod transaction;
 
 pub use crate::append_result::AppendResult;
 pub(crate) use crate::bindings::stopwatch::Stopwatch;
 pub use crate::error::ClientError;
-pub use crate::frontend::Frontend;
+pub use crate::frontend::{Frontend, FrontendBuilder};
 pub use crate::log::init_log;
+pub use crate::producer::{Producer, ProducerOptions};
+pub use crate::reader::{StreamReader, StreamReaderOptions};
+pub use crate::records::Records;
 pub use crate::stream::Stream;
-pub use crate::stream_options::StreamOptions;
+pub use crate::stream_options::{OpenOptions, StreamOptions, StreamUpdate};
+pub use crate::transaction::{IsolationLevel, Transaction, TransactionCoordinator};
 
 #[cfg(test)]
 mod tests {
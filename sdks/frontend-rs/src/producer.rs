@@ -0,0 +1,300 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use log::{error, trace};
+use model::{error::EsError, record::magic1::RecordsBuilder};
+use protocol::rpc::header::ErrorCode;
+use replication::StreamClient;
+use tokio::sync::oneshot;
+
+/// Options of `Producer`, on which records are accumulated into record batches.
+#[derive(Debug, Clone)]
+pub struct ProducerOptions {
+    /// Max time in milliseconds a record waits for more records to join its batch. Zero means each
+    /// record is sent in a batch of its own.
+    pub linger_ms: u64,
+
+    /// Bytes of accumulated records, on reaching which the batch is sent immediately.
+    pub batch_size: usize,
+}
+
+impl Default for ProducerOptions {
+    fn default() -> Self {
+        Self {
+            linger_ms: 5,
+            batch_size: 16 * 1024,
+        }
+    }
+}
+
+/// `Producer` appends individual records to a stream, accumulating them into record batches by
+/// `linger_ms` and `batch_size`.
+///
+/// Record batches sent by `Producer` are in the format of `RecordMagic::Magic1`, stamped with the time
+/// records are sent. Use `Stream::read_records` or `StreamReader` to read them back.
+///
+/// `Producer` is intended to be used in thread-per-core usage case. It is NOT `Send`.
+pub struct Producer {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    stream_id: u64,
+    stream_client: StreamClient,
+    options: ProducerOptions,
+    accumulator: RefCell<RecordAccumulator>,
+}
+
+impl Producer {
+    pub(crate) fn new(
+        stream_id: u64,
+        stream_client: StreamClient,
+        options: ProducerOptions,
+    ) -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                stream_id,
+                stream_client,
+                options,
+                accumulator: RefCell::new(RecordAccumulator::default()),
+            }),
+        }
+    }
+
+    /// Send a record to the stream.
+    ///
+    /// The record is accumulated as soon as this method is called, so records are appended in the order
+    /// of calls. The returned future resolves with offset of the record once its batch is appended.
+    pub fn send(&self, record: Bytes) -> impl Future<Output = Result<i64, EsError>> {
+        self.send0(None, Some(record))
+    }
+
+    /// Send a keyed record to a compacted stream. A `None` value is a tombstone, which deletes the key
+    /// once compacted.
+    pub fn send_keyed(
+        &self,
+        key: Bytes,
+        value: Option<Bytes>,
+    ) -> impl Future<Output = Result<i64, EsError>> {
+        self.send0(Some(key), value)
+    }
+
+    fn send0(
+        &self,
+        key: Option<Bytes>,
+        value: Option<Bytes>,
+    ) -> impl Future<Output = Result<i64, EsError>> {
+        let (tx, rx) = oneshot::channel();
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let (batch_bytes, generation) = self
+            .inner
+            .accumulator
+            .borrow_mut()
+            .push(timestamp, key, value, tx);
+        if batch_bytes >= self.inner.options.batch_size || self.inner.options.linger_ms == 0 {
+            Inner::flush(&self.inner);
+        } else if let Some(generation) = generation {
+            // The record opens a new batch, which is sent once lingered.
+            Inner::schedule_flush(
+                Rc::downgrade(&self.inner),
+                generation,
+                Duration::from_millis(self.inner.options.linger_ms),
+            );
+        }
+        async move {
+            rx.await.unwrap_or_else(|_| {
+                Err(EsError::unexpected(
+                    "Producer dropped the record before sending it",
+                ))
+            })
+        }
+    }
+
+    /// Send accumulated records immediately, without waiting for `linger_ms`.
+    pub fn flush(&self) {
+        Inner::flush(&self.inner);
+    }
+}
+
+impl Drop for Producer {
+    fn drop(&mut self) {
+        // Records accumulated are still sent, such that their futures resolve.
+        Inner::flush(&self.inner);
+    }
+}
+
+impl Inner {
+    fn schedule_flush(inner: Weak<Inner>, generation: u64, linger: Duration) {
+        tokio_uring::spawn(async move {
+            tokio::time::sleep(linger).await;
+            if let Some(inner) = inner.upgrade() {
+                if inner.accumulator.borrow().generation() == Some(generation) {
+                    Inner::flush(&inner);
+                }
+            }
+        });
+    }
+
+    fn flush(inner: &Rc<Inner>) {
+        let batch = match inner.accumulator.borrow_mut().drain() {
+            Some(batch) => batch,
+            None => return,
+        };
+        let count = batch.txs.len();
+        let record_batch = match batch.builder.build(inner.stream_id as i64) {
+            Ok(record_batch) => record_batch,
+            Err(e) => {
+                error!("Failed to build record batch of {count} records: {e:?}");
+                let err = EsError::new(ErrorCode::BAD_REQUEST, "Invalid record batch");
+                batch.txs.into_iter().for_each(|tx| {
+                    let _ = tx.send(Err(err.clone()));
+                });
+                return;
+            }
+        };
+        let request = replication::request::AppendRequest {
+            stream_id: inner.stream_id,
+            record_batch,
+        };
+        let stream_client = inner.stream_client.clone();
+        let stream_id = inner.stream_id;
+        tokio_uring::spawn(async move {
+            let result = stream_client.append(request).await;
+            match result {
+                Ok(response) => {
+                    trace!(
+                        "{count} records appended to stream[id={stream_id}], base offset={}",
+                        response.offset
+                    );
+                    batch.txs.into_iter().enumerate().for_each(|(i, tx)| {
+                        let _ = tx.send(Ok(response.offset as i64 + i as i64));
+                    });
+                }
+                Err(e) => {
+                    batch.txs.into_iter().for_each(|tx| {
+                        let _ = tx.send(Err(e.clone()));
+                    });
+                }
+            }
+        });
+    }
+}
+
+struct PendingBatch {
+    builder: RecordsBuilder,
+    txs: Vec<oneshot::Sender<Result<i64, EsError>>>,
+}
+
+/// Accumulates records into the pending batch.
+#[derive(Default)]
+struct RecordAccumulator {
+    pending: Option<PendingBatch>,
+    /// Generation of the pending batch, which tells whether a lingered batch is already sent.
+    generation: u64,
+}
+
+impl RecordAccumulator {
+    /// Push a record, returning bytes of the pending batch and, if the record opens a new batch,
+    /// generation of the batch.
+    fn push(
+        &mut self,
+        timestamp: i64,
+        key: Option<Bytes>,
+        value: Option<Bytes>,
+        tx: oneshot::Sender<Result<i64, EsError>>,
+    ) -> (usize, Option<u64>) {
+        let mut generation = None;
+        let batch = self.pending.get_or_insert_with(|| {
+            self.generation += 1;
+            generation = Some(self.generation);
+            PendingBatch {
+                builder: RecordsBuilder::new(),
+                txs: vec![],
+            }
+        });
+        batch.builder.append(timestamp, key, value, &[]);
+        batch.txs.push(tx);
+        (batch.builder.size(), generation)
+    }
+
+    fn generation(&self) -> Option<u64> {
+        self.pending.as_ref().map(|_| self.generation)
+    }
+
+    fn drain(&mut self) -> Option<PendingBatch> {
+        self.pending.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use model::record::{flat_record::FlatRecordBatch, magic1::Record};
+    use tokio::sync::oneshot;
+
+    use super::RecordAccumulator;
+
+    #[test]
+    fn test_accumulate() {
+        let mut accumulator = RecordAccumulator::default();
+        let (tx, _rx) = oneshot::channel();
+        // Length, offset delta, timestamp delta, key length, value length and header count of the
+        // record, and its value.
+        let (bytes, generation) =
+            accumulator.push(1000, None, Some(Bytes::from_static(b"abc")), tx);
+        assert_eq!((4 + 4 + 8 + 4 + 4 + 4 + 3, Some(1)), (bytes, generation));
+        let (tx, _rx) = oneshot::channel();
+        let (_, generation) = accumulator.push(
+            1005,
+            Some(Bytes::from_static(b"k")),
+            Some(Bytes::from_static(b"de")),
+            tx,
+        );
+        assert_eq!(None, generation);
+        let (tx, _rx) = oneshot::channel();
+        accumulator.push(1010, Some(Bytes::from_static(b"k")), None, tx);
+        assert_eq!(Some(1), accumulator.generation());
+
+        let batch = accumulator.drain().unwrap();
+        assert_eq!(3, batch.txs.len());
+        assert_eq!(None, accumulator.generation());
+
+        // Round trip through the layout record batches are appended and read in.
+        let record_batch = batch.builder.build(1).unwrap();
+        assert_eq!(3, record_batch.last_offset_delta());
+        let (buffers, _) = FlatRecordBatch::from(record_batch).encode();
+        let mut buf = Bytes::from(buffers.concat());
+        let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf).unwrap();
+        let records: Vec<Record> = record_batch
+            .records()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let record =
+            |offset, timestamp, key: Option<&'static [u8]>, value: Option<&'static [u8]>| Record {
+                offset,
+                timestamp,
+                key: key.map(Bytes::from_static),
+                value: value.map(Bytes::from_static),
+                headers: vec![],
+            };
+        assert_eq!(
+            vec![
+                record(0, 1000, None, Some(b"abc")),
+                record(1, 1005, Some(b"k"), Some(b"de")),
+                // Tombstone
+                record(2, 1010, Some(b"k"), None),
+            ],
+            records
+        );
+
+        // Next batch has a new generation.
+        let (tx, _rx) = oneshot::channel();
+        assert_eq!(Some(2), accumulator.push(1020, None, None, tx).1);
+    }
+}
@@ -0,0 +1,473 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures::Stream;
+use log::{info, trace, warn};
+use model::{error::EsError, range::RangeMetadata, RecordBatch};
+use protocol::rpc::header::ErrorCode;
+use replication::{request::OpenMode, StreamClient};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::transaction;
+
+/// Max number of consecutive reads failing as `UNEXPECTED` to retry, after which the error is
+/// yielded to the reader.
+const MAX_UNEXPECTED_RETRIES: usize = 8;
+
+/// Options of `StreamReader`.
+#[derive(Debug, Clone)]
+pub struct StreamReaderOptions {
+    /// Max bytes of record batches to read at a time.
+    pub batch_max_bytes: i32,
+
+    /// Max number of record batches read ahead of the consumer. Reads are paused once as many batches
+    /// are buffered.
+    pub prefetch: usize,
+
+    /// Interval to check for new records, once the reader catches up with the stream.
+    pub poll_interval: Duration,
+}
+
+impl Default for StreamReaderOptions {
+    fn default() -> Self {
+        Self {
+            batch_max_bytes: 1024 * 1024,
+            prefetch: 64,
+            poll_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// `StreamReader` reads record batches of a stream from an offset, following records as they are
+/// appended.
+///
+/// Record batches are read ahead in background, up to `StreamReaderOptions::prefetch` of them, and
+/// each read is confined to a single range, such that reads never span a sealed range and its
+/// successor. Control record batches of transactions are yielded as well.
+///
+/// The reader yields `Err` once on a read that cannot be retried, such as reading offsets already
+/// trimmed, and ends after it. Use `seek` to resume reading from another offset.
+///
+/// Like `Producer`, `StreamReader` is intended to be used in thread-per-core usage case. It is NOT
+/// `Send`. Readers of a stream opened by its writer may wait for in-flight appends to be confirmed, so
+/// consider opening it for read only, see `OpenOptions::read_only`, for consumers.
+pub struct StreamReader {
+    stream_id: u64,
+    mode: OpenMode,
+    stream_client: StreamClient,
+    options: StreamReaderOptions,
+    rx: mpsc::Receiver<Result<RecordBatch, EsError>>,
+    task: JoinHandle<()>,
+    /// Offset the next record batch yielded is expected to contain.
+    offset: i64,
+    ended: bool,
+}
+
+impl StreamReader {
+    pub(crate) fn new(
+        stream_id: u64,
+        mode: OpenMode,
+        stream_client: StreamClient,
+        start_offset: i64,
+        options: StreamReaderOptions,
+    ) -> Self {
+        let (rx, task) = Fetcher::spawn(
+            stream_id,
+            mode,
+            stream_client.clone(),
+            options.clone(),
+            start_offset,
+        );
+        Self {
+            stream_id,
+            mode,
+            stream_client,
+            options,
+            rx,
+            task,
+            offset: start_offset,
+            ended: false,
+        }
+    }
+
+    /// Offset of the next record to read, which is the end offset of the last record batch yielded.
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Continue reading from `offset`, discarding record batches read ahead.
+    ///
+    /// The first record batch yielded afterwards is the one containing `offset`, which may start
+    /// before it.
+    pub fn seek(&mut self, offset: i64) {
+        info!(
+            "Seek reader of stream[id={}] from {} to {offset}",
+            self.stream_id, self.offset
+        );
+        self.task.abort();
+        let (rx, task) = Fetcher::spawn(
+            self.stream_id,
+            self.mode,
+            self.stream_client.clone(),
+            self.options.clone(),
+            offset,
+        );
+        self.rx = rx;
+        self.task = task;
+        self.offset = offset;
+        self.ended = false;
+    }
+}
+
+impl Stream for StreamReader {
+    type Item = Result<RecordBatch, EsError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.ended {
+            return Poll::Ready(None);
+        }
+        let item = match self.rx.poll_recv(cx) {
+            Poll::Ready(item) => item,
+            Poll::Pending => return Poll::Pending,
+        };
+        match &item {
+            Some(Ok(batch)) => self.offset = end_offset(batch),
+            Some(Err(_)) | None => self.ended = true,
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl Drop for StreamReader {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Background task reading record batches ahead of `StreamReader`.
+struct Fetcher {
+    stream_id: u64,
+    /// Mode the stream is opened in, whose handle serves the reads.
+    mode: OpenMode,
+    stream_client: StreamClient,
+    options: StreamReaderOptions,
+    /// Ranges of the stream known so far, ordered by index.
+    ranges: Vec<RangeMetadata>,
+    /// Next offset of the stream known so far.
+    next_offset: i64,
+}
+
+impl Fetcher {
+    fn spawn(
+        stream_id: u64,
+        mode: OpenMode,
+        stream_client: StreamClient,
+        options: StreamReaderOptions,
+        offset: i64,
+    ) -> (mpsc::Receiver<Result<RecordBatch, EsError>>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel(options.prefetch.max(1));
+        let fetcher = Self {
+            stream_id,
+            mode,
+            stream_client,
+            options,
+            ranges: vec![],
+            next_offset: 0,
+        };
+        let task = tokio_uring::spawn(async move {
+            if let Err(e) = fetcher.run(offset, &tx).await {
+                let _ = tx.send(Err(e)).await;
+            }
+        });
+        (rx, task)
+    }
+
+    /// Read record batches from `offset` until the reader is dropped or an error that cannot be
+    /// retried occurs.
+    async fn run(
+        mut self,
+        mut offset: i64,
+        tx: &mpsc::Sender<Result<RecordBatch, EsError>>,
+    ) -> Result<(), EsError> {
+        self.refresh_ranges().await?;
+        let mut unexpected_retries = 0;
+        loop {
+            if offset >= self.next_offset {
+                self.next_offset = self
+                    .stream_client
+                    .next_offset(self.stream_id, self.mode)
+                    .await? as i64;
+                if offset >= self.next_offset {
+                    tokio::time::sleep(self.options.poll_interval).await;
+                    continue;
+                }
+            }
+            if read_end(&self.ranges, offset, self.next_offset) <= offset {
+                // The range holding `offset` is not known yet.
+                self.refresh_ranges().await?;
+            }
+            let end = read_end(&self.ranges, offset, self.next_offset);
+            let data = match self.read(offset, end).await {
+                Ok(data) => {
+                    unexpected_retries = 0;
+                    data
+                }
+                Err(e) if retriable(&e, unexpected_retries) => {
+                    if e.code == ErrorCode::UNEXPECTED {
+                        unexpected_retries += 1;
+                    }
+                    let start_offset = self
+                        .stream_client
+                        .start_offset(self.stream_id, self.mode)
+                        .await? as i64;
+                    if offset < start_offset {
+                        return Err(EsError::new(
+                            ErrorCode::OFFSET_OUT_OF_RANGE_BOUNDS,
+                            &format!("Offset {offset} is trimmed, start offset is {start_offset}"),
+                        ));
+                    }
+                    warn!(
+                        "Failed to read [{offset}, {end}) of stream[id={}], retrying: {e}",
+                        self.stream_id
+                    );
+                    self.refresh_ranges().await?;
+                    tokio::time::sleep(self.options.poll_interval).await;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let mut progressed = false;
+            for batch in transaction::decode_all(data)? {
+                if end_offset(&batch) <= offset {
+                    continue;
+                }
+                offset = end_offset(&batch);
+                progressed = true;
+                if tx.send(Ok(batch)).await.is_err() {
+                    // The reader is dropped or seeks elsewhere.
+                    return Ok(());
+                }
+            }
+            if !progressed {
+                tokio::time::sleep(self.options.poll_interval).await;
+            }
+        }
+    }
+
+    async fn read(&self, start_offset: i64, end_offset: i64) -> Result<Vec<Bytes>, EsError> {
+        trace!(
+            "Reading ahead [{start_offset}, {end_offset}) of stream[id={}]",
+            self.stream_id
+        );
+        let request = replication::request::ReadRequest {
+            stream_id: self.stream_id,
+            mode: self.mode,
+            start_offset: start_offset as u64,
+            end_offset: end_offset as u64,
+            batch_max_bytes: self.options.batch_max_bytes as u32,
+        };
+        self.stream_client
+            .read(request)
+            .await
+            .map(|response| response.data)
+    }
+
+    async fn refresh_ranges(&mut self) -> Result<(), EsError> {
+        let mut ranges = self.stream_client.list_ranges(self.stream_id).await?;
+        ranges.sort_by_key(RangeMetadata::index);
+        trace!(
+            "Reader of stream[id={}] refreshed ranges {ranges:?}",
+            self.stream_id
+        );
+        self.ranges = ranges;
+        Ok(())
+    }
+}
+
+/// Exclusive end offset of a read from `offset`, which stops at the end of the range containing it.
+///
+/// Ranges unknown to the reader are read up to `next_offset`, which fails once they turn out to be
+/// sealed and ranges are refreshed afterwards.
+fn read_end(ranges: &[RangeMetadata], offset: i64, next_offset: i64) -> i64 {
+    let range = ranges
+        .iter()
+        .rev()
+        .find(|range| range.start() as i64 <= offset);
+    match range.and_then(RangeMetadata::end) {
+        Some(end) if offset < end as i64 => next_offset.min(end as i64),
+        // Sealed before `offset`, whose successor is not known yet.
+        Some(_) => offset,
+        None => next_offset,
+    }
+}
+
+fn end_offset(batch: &RecordBatch) -> i64 {
+    batch.base_offset() + batch.last_offset_delta() as i64
+}
+
+/// Whether a failed read may succeed once ranges are refreshed or records are confirmed.
+///
+/// Reads spanning ranges unknown to the reader fail as `UNEXPECTED`, which is also how other
+/// failures are reported, so it is retried only `MAX_UNEXPECTED_RETRIES` times in a row.
+fn retriable(e: &EsError, unexpected_retries: usize) -> bool {
+    match e.code {
+        ErrorCode::NO_NEW_RECORD
+        | ErrorCode::OFFSET_OVERFLOW
+        | ErrorCode::OFFSET_OUT_OF_RANGE_BOUNDS
+        | ErrorCode::RANGE_NOT_FOUND => true,
+        ErrorCode::UNEXPECTED => unexpected_retries < MAX_UNEXPECTED_RETRIES,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, time::Duration};
+
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use model::{
+        error::EsError,
+        range::RangeMetadata,
+        record::{flat_record::FlatRecordBatch, magic1::RecordsBuilder},
+        stream::RolloverPolicy,
+    };
+    use protocol::rpc::header::ErrorCode;
+    use range_server::standalone::Standalone;
+
+    use super::{read_end, retriable, StreamReaderOptions, MAX_UNEXPECTED_RETRIES};
+    use crate::{FrontendBuilder, OpenOptions, Stream, StreamOptions};
+
+    /// Append a record batch of `count` records, returning its base offset.
+    async fn append(stream: &Stream, count: usize) -> Result<i64, Box<dyn Error>> {
+        let mut builder = RecordsBuilder::new();
+        for i in 0..count {
+            builder.append(0, None, Some(Bytes::from(format!("record-{i}"))), &[]);
+        }
+        let (buffers, _) = FlatRecordBatch::from(builder.build(stream.id() as i64)?).encode();
+        Ok(stream
+            .append(Bytes::from(buffers.concat()))
+            .await?
+            .base_offset)
+    }
+
+    #[test]
+    fn test_retriable() {
+        let error = |code| EsError::new(code, "test");
+        assert!(retriable(&error(ErrorCode::NO_NEW_RECORD), 0));
+        assert!(retriable(
+            &error(ErrorCode::RANGE_NOT_FOUND),
+            MAX_UNEXPECTED_RETRIES
+        ));
+        assert!(retriable(&error(ErrorCode::UNEXPECTED), 0));
+        assert!(retriable(
+            &error(ErrorCode::UNEXPECTED),
+            MAX_UNEXPECTED_RETRIES - 1
+        ));
+        assert!(!retriable(
+            &error(ErrorCode::UNEXPECTED),
+            MAX_UNEXPECTED_RETRIES
+        ));
+        assert!(!retriable(&error(ErrorCode::BAD_REQUEST), 0));
+    }
+
+    #[test]
+    fn test_stream_reader() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        let store_dir = tempfile::tempdir()?;
+        let standalone = Standalone::start_for_test(store_dir.path())?;
+        let placement_driver = standalone.placement_driver().to_owned();
+        let result = tokio_uring::start(async move {
+            let frontend = FrontendBuilder::new(&placement_driver).build()?;
+            let stream_id = frontend
+                .create(StreamOptions {
+                    replica: 1,
+                    ack: 1,
+                    retention: Duration::from_secs(3600),
+                    compaction: None,
+                    rollover: None,
+                })
+                .await?;
+            // Ranges are sealed every 4 records, such that batches span several ranges.
+            let options = OpenOptions {
+                rollover: Some(RolloverPolicy {
+                    max_records: Some(4),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let stream = frontend.open(stream_id, options).await?;
+            for i in 0..5 {
+                assert_eq!(i * 2, append(&stream, 2).await?);
+            }
+            let ranges = frontend.list_ranges(stream_id).await?;
+            assert!(ranges.len() > 1);
+            assert!(ranges.iter().any(|range| range.end().is_some()));
+
+            // Reads ahead one batch at a time, and moves on from sealed ranges to the next.
+            let options = StreamReaderOptions {
+                prefetch: 1,
+                poll_interval: Duration::from_millis(10),
+                ..Default::default()
+            };
+            let mut reader = stream.reader(0, options.clone());
+            for i in 0..5 {
+                let batch = reader.next().await.expect("Reader should not end")?;
+                assert_eq!((i * 2, 2), (batch.base_offset(), batch.last_offset_delta()));
+                assert_eq!(i * 2 + 2, reader.offset());
+            }
+
+            // Seeking yields the batch containing the offset first.
+            reader.seek(5);
+            assert_eq!(5, reader.offset());
+            let batch = reader.next().await.expect("Reader should not end")?;
+            assert_eq!(4, batch.base_offset());
+            assert_eq!(6, reader.offset());
+
+            // Records appended afterwards are followed.
+            let mut reader = stream.reader(10, options);
+            let pending = tokio_uring::spawn(async move { reader.next().await });
+            assert_eq!(10, append(&stream, 3).await?);
+            let batch = tokio::time::timeout(Duration::from_secs(10), pending)
+                .await??
+                .expect("Reader should not end")?;
+            assert_eq!((10, 3), (batch.base_offset(), batch.last_offset_delta()));
+
+            // Reading trimmed records fails, and ends the reader.
+            stream.trim(4).await?;
+            let mut reader = stream.reader(0, StreamReaderOptions::default());
+            assert!(matches!(reader.next().await, Some(Err(_))));
+            assert!(reader.next().await.is_none());
+            Ok::<_, Box<dyn Error>>(())
+        });
+        standalone.shutdown();
+        result
+    }
+
+    #[test]
+    fn test_read_end() {
+        let ranges = vec![
+            RangeMetadata::new(1, 0, 0, 0, Some(100)),
+            RangeMetadata::new(1, 1, 0, 100, Some(100)),
+            RangeMetadata::new(1, 2, 0, 100, Some(250)),
+            RangeMetadata::new(1, 3, 0, 250, None),
+        ];
+        // Reads stop at the end of sealed ranges.
+        assert_eq!(100, read_end(&ranges, 0, 300));
+        assert_eq!(100, read_end(&ranges, 99, 300));
+        assert_eq!(80, read_end(&ranges, 10, 80));
+        // Empty range sealed at its start is skipped.
+        assert_eq!(250, read_end(&ranges, 100, 300));
+        // The open range is read up to the next offset.
+        assert_eq!(300, read_end(&ranges, 250, 300));
+
+        // Successor of the sealed range is not known yet.
+        assert_eq!(250, read_end(&ranges[..3], 250, 300));
+        // No range is known.
+        assert_eq!(300, read_end(&[], 10, 300));
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+use log::error;
+use model::{
+    error::{DecodeError, EsError},
+    record::{
+        flat_record::FlatRecordBatch,
+        magic1::{Record, RecordIter},
+    },
+};
+use protocol::rpc::header::ErrorCode;
+
+/// Iterator over records of data read from a stream, whose record batches are in the format of
+/// `RecordMagic::Magic1`. Control records of transactions are skipped.
+///
+/// Iteration stops after the first error.
+pub struct Records {
+    data: VecDeque<Bytes>,
+    batch: Option<RecordIter>,
+}
+
+impl Records {
+    pub fn new(data: Vec<Bytes>) -> Self {
+        Self {
+            data: data.into(),
+            batch: None,
+        }
+    }
+
+    fn next_batch(&mut self) -> Option<Result<RecordIter, DecodeError>> {
+        loop {
+            let buf = self.data.front_mut()?;
+            if buf.is_empty() {
+                self.data.pop_front();
+                continue;
+            }
+            let record_batch = match FlatRecordBatch::decode_to_record_batch(buf) {
+                Ok(record_batch) => record_batch,
+                Err(e) => return Some(Err(e)),
+            };
+            if record_batch.is_control() {
+                continue;
+            }
+            return Some(record_batch.records());
+        }
+    }
+}
+
+impl Iterator for Records {
+    type Item = Result<Record, EsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.batch.as_mut().and_then(Iterator::next) {
+                if record.is_err() {
+                    self.data.clear();
+                    self.batch = None;
+                }
+                return Some(record.map_err(decode_error));
+            }
+            match self.next_batch()? {
+                Ok(batch) => self.batch = Some(batch),
+                Err(e) => {
+                    self.data.clear();
+                    return Some(Err(decode_error(e)));
+                }
+            }
+        }
+    }
+}
+
+fn decode_error(e: DecodeError) -> EsError {
+    error!("Failed to decode records: {e:?}");
+    EsError::new(ErrorCode::RECORDS_PARSE_ERROR, &e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use model::{
+        record::{
+            flat_record::{FlatRecordBatch, RecordMagic},
+            magic1::RecordsBuilder,
+        },
+        RecordBatch,
+    };
+
+    use super::Records;
+
+    /// Encode a record batch as if it is read from a stream at `base_offset`.
+    fn encode(base_offset: i64, values: &[&'static str]) -> Bytes {
+        let mut builder = RecordsBuilder::new();
+        for value in values {
+            builder.append(0, None, Some(Bytes::from(*value)), &[]);
+        }
+        let record_batch = builder.build(1).unwrap();
+        let record_batch = RecordBatch::new_builder()
+            .with_magic(RecordMagic::Magic1)
+            .with_stream_id(1)
+            .with_range_index(0)
+            .with_base_offset(base_offset)
+            .with_last_offset_delta(record_batch.last_offset_delta() as i32)
+            .with_base_timestamp(record_batch.base_timestamp())
+            .with_payload(record_batch.payload())
+            .build()
+            .unwrap();
+        Bytes::from(FlatRecordBatch::from(record_batch).encode().0.concat())
+    }
+
+    #[test]
+    fn test_records() {
+        let mut first = encode(10, &["a", "b"]).to_vec();
+        first.extend_from_slice(&encode(12, &["c"]));
+        let data = vec![Bytes::from(first), encode(13, &["d"])];
+
+        let records: Vec<_> = Records::new(data)
+            .map(|record| {
+                let record = record.unwrap();
+                (record.offset, record.value.unwrap())
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (10, Bytes::from("a")),
+                (11, Bytes::from("b")),
+                (12, Bytes::from("c")),
+                (13, Bytes::from("d")),
+            ],
+            records
+        );
+    }
+
+    #[test]
+    fn test_records_of_magic0() {
+        let record_batch = RecordBatch::new_builder()
+            .with_stream_id(1)
+            .with_range_index(0)
+            .with_base_offset(0)
+            .with_last_offset_delta(1)
+            .with_payload(Bytes::from("opaque"))
+            .build()
+            .unwrap();
+        let data = vec![Bytes::from(
+            FlatRecordBatch::from(record_batch).encode().0.concat(),
+        )];
+        let mut records = Records::new(data);
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().is_none());
+    }
+}
@@ -1,31 +1,50 @@
 use bytes::Bytes;
 use log::{error, info, trace};
-use model::{error::EsError, record::flat_record::FlatRecordBatch};
+use model::{error::EsError, record::flat_record::FlatRecordBatch, RecordBatch};
 use protocol::rpc::header::ErrorCode;
-use replication::StreamClient;
+use replication::{request::OpenMode, StreamClient};
 
-use crate::AppendResult;
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::{
+    transaction::{
+        self, ControlType, OutcomeCache, ReadCommittedResult, TransactionState, DEADLINE_GRACE_MS,
+    },
+    AppendResult, Producer, ProducerOptions, Records, StreamReader, StreamReaderOptions,
+};
 
 pub struct Stream {
     id: u64,
+    mode: OpenMode,
     stream_client: StreamClient,
+    /// Outcomes of transactions learned by `read_committed`.
+    outcomes: RefCell<OutcomeCache>,
 }
 
 impl Stream {
-    pub(crate) fn new(id: u64, stream_client: StreamClient) -> Self {
-        Self { id, stream_client }
+    pub(crate) fn new(id: u64, mode: OpenMode, stream_client: StreamClient) -> Self {
+        Self {
+            id,
+            mode,
+            stream_client,
+            outcomes: RefCell::new(OutcomeCache::default()),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
     }
 
     pub async fn start_offset(&self) -> Result<i64, EsError> {
         self.stream_client
-            .start_offset(self.id)
+            .start_offset(self.id, self.mode)
             .await
             .map(|v| v as i64)
     }
 
     pub async fn next_offset(&self) -> Result<i64, EsError> {
         self.stream_client
-            .next_offset(self.id)
+            .next_offset(self.id, self.mode)
             .await
             .map(|v| v as i64)
     }
@@ -65,6 +84,24 @@ impl Stream {
         })
     }
 
+    /// Create a producer that appends individual records to the stream, accumulating them into record
+    /// batches according to the given options.
+    pub fn producer(&self, options: ProducerOptions) -> Producer {
+        Producer::new(self.id, self.stream_client.clone(), options)
+    }
+
+    /// Create a reader that yields record batches of the stream from `start_offset`, reading ahead
+    /// according to the given options.
+    pub fn reader(&self, start_offset: i64, options: StreamReaderOptions) -> StreamReader {
+        StreamReader::new(
+            self.id,
+            self.mode,
+            self.stream_client.clone(),
+            start_offset,
+            options,
+        )
+    }
+
     /// Read data from the stream.
     ///
     /// # Arguments
@@ -88,6 +125,7 @@ impl Stream {
         );
         let request = replication::request::ReadRequest {
             stream_id: self.id,
+            mode: self.mode,
             start_offset: start_offset as u64,
             end_offset: end_offset as u64,
             batch_max_bytes: batch_max_bytes as u32,
@@ -99,6 +137,132 @@ impl Stream {
         })
     }
 
+    /// Read records from the stream, whose record batches are in the format of `RecordMagic::Magic1`.
+    ///
+    /// Arguments are the same as `read`.
+    pub async fn read_records(
+        &self,
+        start_offset: i64,
+        end_offset: i64,
+        batch_max_bytes: i32,
+    ) -> Result<Records, EsError> {
+        self.read(start_offset, end_offset, batch_max_bytes)
+            .await
+            .map(Records::new)
+    }
+
+    /// Read data from the stream with `IsolationLevel::ReadCommitted`.
+    ///
+    /// Control records and record batches of aborted transactions are skipped. Data is returned up to
+    /// the first record batch of an in-flight transaction, which may leave nothing to return until the
+    /// transaction completes or expires. Outcome of transactions in `[start_offset, end_offset)` may be
+    /// recorded beyond `end_offset`, so the stream is scanned forward for their control records. Outcomes
+    /// learned are cached by the stream, such that successive reads scan each control record once.
+    ///
+    /// # Returns
+    /// The data visible to the reader and the offset to continue reading from.
+    pub async fn read_committed(
+        &self,
+        start_offset: i64,
+        end_offset: i64,
+        batch_max_bytes: i32,
+    ) -> Result<ReadCommittedResult, EsError> {
+        let data = self.read(start_offset, end_offset, batch_max_bytes).await?;
+        let batches = transaction::decode_all(data)?;
+        let cursor = batches.last().map_or(start_offset, end_offset_of);
+
+        let mut outcomes = HashMap::new();
+        transaction::collect_outcomes(&batches, &mut outcomes);
+        self.outcomes
+            .borrow_mut()
+            .scanned(start_offset, cursor, &outcomes);
+        self.resolve_pending(&batches, cursor, batch_max_bytes, &mut outcomes)
+            .await?;
+
+        let result = transaction::filter_committed(start_offset, batches, &outcomes);
+        trace!(
+            "{} record batches visible to read-committed reader of stream[id={}], next offset={}",
+            result.data.len(),
+            self.id,
+            result.next_offset
+        );
+        Ok(result)
+    }
+
+    /// Learn outcomes of transactions pending among `batches`, from the cache, then control records
+    /// beyond `cursor`. Transactions past their deadline are aborted, unless they are prepared to commit.
+    async fn resolve_pending(
+        &self,
+        batches: &[RecordBatch],
+        mut cursor: i64,
+        batch_max_bytes: i32,
+        outcomes: &mut HashMap<String, ControlType>,
+    ) -> Result<(), EsError> {
+        let pending = |outcomes: &HashMap<String, ControlType>| -> Vec<String> {
+            transaction::pending_transactions(batches, outcomes)
+                .into_iter()
+                .map(String::from)
+                .collect()
+        };
+        for id in pending(outcomes) {
+            if let Some(control) = self.outcomes.borrow().get(&id) {
+                outcomes.insert(id, control);
+            }
+        }
+
+        // Scan again at most once, for expired transactions whose logs are removed on completion.
+        let mut rescan = true;
+        loop {
+            let mut pending = pending(outcomes);
+            if pending.is_empty() {
+                return Ok(());
+            }
+            let next_offset = self.next_offset().await?;
+            cursor = self.outcomes.borrow().resume_from(cursor);
+            while !pending.is_empty() && cursor < next_offset {
+                let data = self.read(cursor, next_offset, batch_max_bytes).await?;
+                let ahead = transaction::decode_all(data)?;
+                let Some(last) = ahead.last() else {
+                    break;
+                };
+                let from = cursor;
+                cursor = end_offset_of(last);
+                transaction::collect_outcomes(&ahead, outcomes);
+                self.outcomes.borrow_mut().scanned(from, cursor, outcomes);
+                pending.retain(|id| !outcomes.contains_key(id));
+            }
+
+            let now = chrono::Utc::now().timestamp_millis();
+            let expired: Vec<String> =
+                transaction::expired_transactions(batches, outcomes, now - DEADLINE_GRACE_MS)
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+            let mut removed = false;
+            let mut aborted = HashMap::new();
+            for id in expired {
+                match transaction::fence_expired(&self.stream_client, &id).await? {
+                    // The coordinator, or its recovery, commits the transaction.
+                    Some(TransactionState::PrepareCommit) => {}
+                    Some(_) => {
+                        info!(
+                            "Transaction[id={id}] in stream[id={}] expired, treat it as aborted",
+                            self.id
+                        );
+                        aborted.insert(id, ControlType::Abort);
+                    }
+                    None => removed = true,
+                }
+            }
+            self.outcomes.borrow_mut().insert(&aborted);
+            outcomes.extend(aborted);
+            if !removed || !rescan {
+                return Ok(());
+            }
+            rescan = false;
+        }
+    }
+
     pub async fn trim(&self, new_start_offset: i64) -> Result<(), EsError> {
         let request = replication::request::TrimRequest {
             stream_id: self.id,
@@ -121,17 +285,23 @@ impl Stream {
     pub async fn close(&self) -> Result<(), EsError> {
         let stream_id = self.id;
         info!("Closing stream[id={}]", stream_id);
-        self.stream_client.close_stream(stream_id).await
+        self.stream_client.close_stream(stream_id, self.mode).await
     }
 }
 
+/// Offset next to the last record of `batch`.
+fn end_offset_of(batch: &RecordBatch) -> i64 {
+    batch.base_offset() + batch.last_offset_delta() as i64
+}
+
 impl Drop for Stream {
     fn drop(&mut self) {
         let client = self.stream_client.clone();
         let stream_id = self.id;
+        let mode = self.mode;
         info!("Dropping stream[id={}]", stream_id);
         tokio_uring::spawn(async move {
-            match client.close_stream(stream_id).await {
+            match client.close_stream(stream_id, mode).await {
                 Ok(_) => {
                     info!("Closed stream[id={stream_id}]");
                 }
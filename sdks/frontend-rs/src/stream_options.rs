@@ -1,8 +1,76 @@
 use std::time::Duration;
 
+use model::range::ReplicaLayout;
+use replication::RolloverPolicy;
+
 #[derive(Debug, Clone)]
 pub struct StreamOptions {
     pub replica: u8,
     pub ack: u8,
     pub retention: Duration,
+
+    /// Create a compacted stream, which keeps only the latest record of each key, if set. The value is
+    /// the time to keep tombstones before compaction removes them.
+    ///
+    /// Records of compacted streams must be keyed, see `Producer::send_keyed`.
+    pub compaction: Option<Duration>,
+
+    /// Rollover policy persisted with the stream, which writers apply instead of their configured
+    /// default unless given one on open, see `OpenOptions::rollover`.
+    pub rollover: Option<RolloverPolicy>,
+}
+
+/// Options of opening a stream, see `Frontend::open`.
+#[derive(Debug, Clone, Default)]
+pub struct OpenOptions {
+    /// Epoch of the writer, which fences writers of lower epochs. Ignored if read only.
+    pub epoch: u64,
+
+    /// Open the stream for read only, without fencing the active writer.
+    ///
+    /// Any number of readers may open the same stream concurrently. Append, trim and delete of the
+    /// opened stream are rejected.
+    pub read_only: bool,
+
+    /// Rollover policy of the writer, instead of the one persisted with the stream or the configured
+    /// default one.
+    pub rollover: Option<RolloverPolicy>,
+
+    /// Layout of replicas of ranges created by the writer, e.g. Reed-Solomon erasure coded ranges of
+    /// which each replica holds a shard of record batches.
+    pub layout: ReplicaLayout,
+}
+
+impl OpenOptions {
+    /// Options of opening the stream for write with the given epoch.
+    pub fn with_epoch(epoch: u64) -> Self {
+        Self {
+            epoch,
+            ..Default::default()
+        }
+    }
+
+    /// Options of opening the stream for read only.
+    pub fn read_only() -> Self {
+        Self {
+            read_only: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Changes to metadata of a stream. Fields of `None` are left unchanged.
+///
+/// Replica and ack count apply to ranges created afterwards, while existing ranges keep theirs.
+#[derive(Debug, Clone, Default)]
+pub struct StreamUpdate {
+    pub replica: Option<u8>,
+    pub ack: Option<u8>,
+    pub retention: Option<Duration>,
+}
+
+impl StreamUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.replica.is_none() && self.ack.is_none() && self.retention.is_none()
+    }
 }
@@ -0,0 +1,1009 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeSet, HashMap},
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use log::{error, info, trace, warn};
+use model::{
+    error::EsError,
+    record::{flags, flat_record::FlatRecordBatch},
+    RecordBatch,
+};
+use protocol::rpc::header::ErrorCode;
+use replication::{request::AppendRequest, StreamClient};
+
+use crate::{AppendResult, Stream};
+
+/// Property of transactional record batches, holding id of the transaction.
+pub const TXN_ID_PROPERTY: &str = "txn.id";
+
+/// Property of control record batches, holding one of `begin`, `commit` and `abort`.
+pub const TXN_CONTROL_PROPERTY: &str = "txn.control";
+
+/// Property of transactional record batches, holding the deadline of the transaction in milliseconds
+/// since epoch.
+pub const TXN_DEADLINE_PROPERTY: &str = "txn.deadline";
+
+/// Prefix of keys of transaction logs in the key-value store of placement driver.
+const TXN_KEY_PREFIX: &str = "txn/";
+
+/// Time for a transaction to complete, unless configured by `TransactionCoordinator::with_timeout`.
+pub const DEFAULT_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Tolerance of clock skew between producers and readers, beyond the deadline of a transaction,
+/// before readers judge it expired.
+pub(crate) const DEADLINE_GRACE_MS: i64 = 5_000;
+
+/// Max outcomes of transactions cached per stream.
+const MAX_CACHED_OUTCOMES: usize = 4096;
+
+/// Type of control records, which mark boundaries of a transaction in each of its streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlType {
+    Begin,
+    Commit,
+    Abort,
+}
+
+impl ControlType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ControlType::Begin => "begin",
+            ControlType::Commit => "commit",
+            ControlType::Abort => "abort",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "begin" => Some(ControlType::Begin),
+            "commit" => Some(ControlType::Commit),
+            "abort" => Some(ControlType::Abort),
+            _ => None,
+        }
+    }
+}
+
+/// Isolation level of reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IsolationLevel {
+    /// All record batches are returned, including control records and those of aborted or
+    /// in-flight transactions.
+    #[default]
+    ReadUncommitted,
+
+    /// Only non-transactional record batches and those of committed transactions are returned.
+    /// Reads stop before the first record batch of an in-flight transaction.
+    ReadCommitted,
+}
+
+/// Result of a read under `IsolationLevel::ReadCommitted`.
+#[derive(Debug, Default)]
+pub struct ReadCommittedResult {
+    /// Encoded record batches visible to the reader.
+    pub data: Vec<Bytes>,
+
+    /// Offset to continue reading from. It may be larger than the end offset of the last returned
+    /// record batch as control records and aborted record batches are skipped.
+    pub next_offset: i64,
+}
+
+/// State of a transaction, persisted in the key-value store of placement driver so that
+/// transactions interrupted by a crash can be completed by `TransactionCoordinator::recover`.
+///
+/// Transitions from `Ongoing` are conditional on the log being unchanged, so that a transaction fenced
+/// as `Aborted` by readers once it expires can no longer be prepared to commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TransactionState {
+    Ongoing,
+    PrepareCommit,
+    PrepareAbort,
+    /// Aborted by readers as the transaction expired in flight.
+    Aborted,
+}
+
+impl TransactionState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TransactionState::Ongoing => "ongoing",
+            TransactionState::PrepareCommit => "prepare-commit",
+            TransactionState::PrepareAbort => "prepare-abort",
+            TransactionState::Aborted => "aborted",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ongoing" => Some(TransactionState::Ongoing),
+            "prepare-commit" => Some(TransactionState::PrepareCommit),
+            "prepare-abort" => Some(TransactionState::PrepareAbort),
+            "aborted" => Some(TransactionState::Aborted),
+            _ => None,
+        }
+    }
+}
+
+/// Encode transaction log as `{state};{deadline};{stream-id},{stream-id},...`.
+fn encode_log(state: TransactionState, deadline: i64, streams: &BTreeSet<u64>) -> Bytes {
+    let streams = streams
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    Bytes::from(format!("{};{deadline};{streams}", state.as_str()))
+}
+
+fn decode_log(value: &[u8]) -> Option<(TransactionState, i64, BTreeSet<u64>)> {
+    let value = std::str::from_utf8(value).ok()?;
+    let mut parts = value.splitn(3, ';');
+    let state = TransactionState::parse(parts.next()?)?;
+    let deadline = parts.next()?.parse().ok()?;
+    let mut ids = BTreeSet::new();
+    for id in parts.next()?.split(',').filter(|id| !id.is_empty()) {
+        ids.insert(id.parse().ok()?);
+    }
+    Some((state, deadline, ids))
+}
+
+/// Coordinator of transactions of a producer.
+///
+/// A transaction appends record batches to multiple streams atomically: readers with
+/// `IsolationLevel::ReadCommitted` observe either all or none of them. Progress of each transaction
+/// is logged in the key-value store of placement driver under `txn/{producer-id}/{txn-id}`.
+///
+/// Producer id must be unique among live producers. Restarted producers shall call `recover` with the
+/// same producer id, after opening streams involved, to complete transactions left behind.
+///
+/// Each transaction has a deadline, stamped on its record batches. Readers treat transactions that are
+/// still in flight past the deadline as aborted, unless they are prepared to commit, so that an
+/// abandoned transaction does not block them forever.
+#[derive(Debug, Clone)]
+pub struct TransactionCoordinator {
+    producer_id: String,
+    stream_client: StreamClient,
+    sequence: Arc<AtomicU64>,
+    timeout: Duration,
+}
+
+impl TransactionCoordinator {
+    pub(crate) fn new(producer_id: String, stream_client: StreamClient) -> Self {
+        Self {
+            producer_id,
+            stream_client,
+            sequence: Arc::new(AtomicU64::new(0)),
+            timeout: DEFAULT_TRANSACTION_TIMEOUT,
+        }
+    }
+
+    /// Set the time for transactions to complete since they begin, which should be well above the
+    /// time to commit them.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn producer_id(&self) -> &str {
+        &self.producer_id
+    }
+
+    /// Begin a new transaction.
+    pub fn begin(&self) -> Transaction {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let now = chrono::Utc::now().timestamp_millis();
+        let id = format!("{}/{}-{}", self.producer_id, now, sequence);
+        let deadline = now + self.timeout.as_millis() as i64;
+        info!("Begin transaction[id={id}], deadline={deadline}");
+        Transaction {
+            id,
+            deadline,
+            stream_client: self.stream_client.clone(),
+            streams: RefCell::new(BTreeSet::new()),
+            log: RefCell::new(None),
+            finished: Cell::new(false),
+        }
+    }
+
+    /// Complete transactions of this producer left behind by a previous incarnation: those prepared
+    /// to commit are committed and the others are aborted.
+    ///
+    /// # Returns
+    /// Number of transactions completed.
+    pub async fn recover(&self) -> Result<usize, EsError> {
+        let prefix = format!("{TXN_KEY_PREFIX}{}/", self.producer_id);
+        let kvs = self
+            .stream_client
+            .kv_range(Bytes::from(prefix.clone()), Some(prefix_end(&prefix)))
+            .await?;
+        let mut completed = 0;
+        for (key, value) in kvs {
+            let id = match std::str::from_utf8(&key) {
+                Ok(key) => key[TXN_KEY_PREFIX.len()..].to_owned(),
+                Err(_) => {
+                    warn!("Skip transaction log with malformed key {key:?}");
+                    continue;
+                }
+            };
+            let (state, deadline, streams) = decode_log(&value).ok_or_else(|| {
+                error!("Malformed log of transaction[id={id}]: {value:?}");
+                EsError::new(ErrorCode::UNEXPECTED, "Malformed transaction log")
+            })?;
+            let control = match state {
+                TransactionState::PrepareCommit => ControlType::Commit,
+                TransactionState::Ongoing
+                | TransactionState::PrepareAbort
+                | TransactionState::Aborted => ControlType::Abort,
+            };
+            info!("Recovering transaction[id={id}] of state {state:?} by {control:?}");
+            complete(
+                &self.stream_client,
+                &id,
+                deadline,
+                &streams,
+                control,
+                Some(value),
+            )
+            .await?;
+            completed += 1;
+        }
+        Ok(completed)
+    }
+}
+
+/// A transaction spanning one or more streams.
+///
+/// A transaction must be completed by `commit` or `abort`. One dropped otherwise is left in flight,
+/// which readers treat as aborted once it expires and `TransactionCoordinator::recover` aborts.
+///
+/// `Transaction` is intended to be used in thread-per-core usage case. It is NOT `Send`.
+#[must_use = "transactions should be committed or aborted"]
+pub struct Transaction {
+    id: String,
+    /// Deadline of the transaction, in milliseconds since epoch.
+    deadline: i64,
+    stream_client: StreamClient,
+    streams: RefCell<BTreeSet<u64>>,
+    /// Log of the transaction last written, if any.
+    log: RefCell<Option<Bytes>>,
+    finished: Cell<bool>,
+}
+
+impl Transaction {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Deadline of the transaction, in milliseconds since epoch.
+    pub fn deadline(&self) -> i64 {
+        self.deadline
+    }
+
+    fn expired(&self) -> bool {
+        chrono::Utc::now().timestamp_millis() >= self.deadline
+    }
+
+    /// Append data to the stream within the transaction.
+    ///
+    /// On first append to a stream, the stream is added to the transaction log and a `begin` control
+    /// record is appended ahead of the data.
+    ///
+    /// # Arguments
+    ///
+    /// `buffer` - Encoded representation of the `RecordBatch`. It contains exactly one append entry.
+    pub async fn append(
+        &self,
+        stream: &Stream,
+        mut buffer: Bytes,
+    ) -> Result<AppendResult, EsError> {
+        if self.finished.get() {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                "Transaction has already completed",
+            ));
+        }
+        if self.expired() {
+            return Err(EsError::new(
+                ErrorCode::BAD_REQUEST,
+                "Transaction has expired",
+            ));
+        }
+
+        let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buffer).map_err(|e| {
+            error!("Invalid record batch {e:?}");
+            EsError::new(ErrorCode::BAD_REQUEST, "Invalid record batch")
+        })?;
+        let record_batch = mark_transactional(record_batch, &self.id, self.deadline)?;
+
+        let stream_id = stream.id();
+        let first_touch = self.streams.borrow_mut().insert(stream_id);
+        if first_touch {
+            let log = encode_log(
+                TransactionState::Ongoing,
+                self.deadline,
+                &self.streams.borrow(),
+            );
+            let expected = self.log.borrow().clone();
+            if let Err(e) =
+                compare_and_put_log(&self.stream_client, &self.id, expected, log.clone()).await
+            {
+                self.streams.borrow_mut().remove(&stream_id);
+                if e.code == ErrorCode::PRECONDITION_FAILED {
+                    return Err(EsError::new(
+                        ErrorCode::BAD_REQUEST,
+                        "Transaction has expired and is aborted",
+                    ));
+                }
+                return Err(e);
+            }
+            *self.log.borrow_mut() = Some(log);
+            append_control(
+                &self.stream_client,
+                stream_id,
+                &self.id,
+                self.deadline,
+                ControlType::Begin,
+            )
+            .await?;
+        }
+
+        let response = self
+            .stream_client
+            .append(AppendRequest {
+                stream_id,
+                record_batch,
+            })
+            .await?;
+        trace!(
+            "Appended to stream[id={stream_id}] within transaction[id={}], base offset={}",
+            self.id,
+            response.offset
+        );
+        Ok(AppendResult {
+            base_offset: response.offset as i64,
+        })
+    }
+
+    /// Commit the transaction, making all its appends visible to read-committed readers.
+    ///
+    /// A transaction past its deadline may be treated as aborted by readers already, so it is
+    /// aborted instead, failing the commit. So is one whose log readers have fenced as aborted.
+    pub async fn commit(self) -> Result<(), EsError> {
+        if !self.expired() {
+            match self.finish(ControlType::Commit).await {
+                Err(e) if e.code == ErrorCode::PRECONDITION_FAILED => {}
+                result => return result,
+            }
+        }
+        warn!(
+            "Transaction[id={}] expired at {}, aborting it",
+            self.id, self.deadline
+        );
+        self.finish(ControlType::Abort).await?;
+        Err(EsError::new(
+            ErrorCode::BAD_REQUEST,
+            "Transaction has expired and is aborted",
+        ))
+    }
+
+    /// Abort the transaction, hiding all its appends from read-committed readers.
+    pub async fn abort(self) -> Result<(), EsError> {
+        self.finish(ControlType::Abort).await
+    }
+
+    async fn finish(&self, control: ControlType) -> Result<(), EsError> {
+        self.finished.set(true);
+        let streams = self.streams.borrow().clone();
+        if streams.is_empty() {
+            return Ok(());
+        }
+        let log = self.log.borrow().clone();
+        complete(
+            &self.stream_client,
+            &self.id,
+            self.deadline,
+            &streams,
+            control,
+            log,
+        )
+        .await
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if self.finished.get() || self.streams.borrow().is_empty() {
+            return;
+        }
+        warn!(
+            "Transaction[id={}] is dropped without commit or abort, it is treated as aborted after {}",
+            self.id, self.deadline
+        );
+    }
+}
+
+/// Drive a transaction to completion: log the decision, append control records to all its streams,
+/// then remove the log.
+///
+/// The decision to commit is logged only if the log still holds `log`, failing with
+/// `PRECONDITION_FAILED` otherwise, e.g. once readers fenced the transaction as aborted.
+async fn complete(
+    stream_client: &StreamClient,
+    id: &str,
+    deadline: i64,
+    streams: &BTreeSet<u64>,
+    control: ControlType,
+    log: Option<Bytes>,
+) -> Result<(), EsError> {
+    let key = txn_key(id);
+    match control {
+        ControlType::Commit => {
+            let decision = encode_log(TransactionState::PrepareCommit, deadline, streams);
+            compare_and_put_log(stream_client, id, log, decision).await?;
+        }
+        _ => {
+            let decision = encode_log(TransactionState::PrepareAbort, deadline, streams);
+            stream_client.kv_put(key.clone(), decision).await?;
+        }
+    }
+    for stream_id in streams {
+        append_control(stream_client, *stream_id, id, deadline, control).await?;
+    }
+    stream_client.kv_delete_range(key, None).await?;
+    info!("Completed transaction[id={id}] by {control:?}");
+    Ok(())
+}
+
+async fn append_control(
+    stream_client: &StreamClient,
+    stream_id: u64,
+    id: &str,
+    deadline: i64,
+    control: ControlType,
+) -> Result<(), EsError> {
+    let record_batch = RecordBatch::new_builder()
+        .with_stream_id(stream_id as i64)
+        .with_range_index(0)
+        .with_base_offset(0)
+        .with_last_offset_delta(1)
+        .with_flags(flags::TRANSACTIONAL | flags::CONTROL)
+        .with_base_timestamp(chrono::Utc::now().timestamp_millis())
+        .with_property(TXN_ID_PROPERTY.to_owned(), id.to_owned())
+        .with_property(TXN_DEADLINE_PROPERTY.to_owned(), deadline.to_string())
+        .with_property(TXN_CONTROL_PROPERTY.to_owned(), control.as_str().to_owned())
+        .with_payload(Bytes::new())
+        .build()
+        .map_err(|e| {
+            error!("Failed to build control record: {e:?}");
+            EsError::unexpected("Failed to build control record")
+        })?;
+    stream_client
+        .append(AppendRequest {
+            stream_id,
+            record_batch,
+        })
+        .await
+        .map(|_| ())
+}
+
+/// Rebuild the record batch with transactional flag, id and deadline of the transaction.
+fn mark_transactional(
+    record_batch: RecordBatch,
+    id: &str,
+    deadline: i64,
+) -> Result<RecordBatch, EsError> {
+    let mut builder = RecordBatch::new_builder()
+        .with_magic(record_batch.magic())
+        .with_stream_id(record_batch.stream_id())
+        .with_range_index(record_batch.range_index())
+        .with_base_offset(record_batch.base_offset())
+        .with_last_offset_delta(record_batch.last_offset_delta() as i32)
+        .with_flags(record_batch.flags() | flags::TRANSACTIONAL)
+        .with_base_timestamp(record_batch.base_timestamp())
+        .with_payload(record_batch.payload());
+    if let Some(properties) = record_batch.properties() {
+        for kv in properties {
+            builder = builder.with_property(kv.key.clone(), kv.value.clone());
+        }
+    }
+    builder
+        .with_property(TXN_ID_PROPERTY.to_owned(), id.to_owned())
+        .with_property(TXN_DEADLINE_PROPERTY.to_owned(), deadline.to_string())
+        .build()
+        .map_err(|e| {
+            error!("Invalid record batch {e:?}");
+            EsError::new(ErrorCode::BAD_REQUEST, "Invalid record batch")
+        })
+}
+
+fn txn_key(id: &str) -> Bytes {
+    Bytes::from(format!("{TXN_KEY_PREFIX}{id}"))
+}
+
+/// Log of the transaction, or `None` if it is removed, in which case control records of its outcome
+/// are appended to all its streams.
+async fn read_log(stream_client: &StreamClient, id: &str) -> Result<Option<Bytes>, EsError> {
+    let kvs = stream_client.kv_range(txn_key(id), None).await?;
+    Ok(kvs.into_iter().next().map(|(_, value)| value))
+}
+
+/// Replace the log of the transaction with `log` if it still holds `expected`, or does not exist if
+/// `expected` is `None`. A replacement already applied by a retried request is not a failure.
+async fn compare_and_put_log(
+    stream_client: &StreamClient,
+    id: &str,
+    expected: Option<Bytes>,
+    log: Bytes,
+) -> Result<(), EsError> {
+    match stream_client
+        .kv_compare_and_put(txn_key(id), expected, log.clone())
+        .await
+    {
+        Err(e) if e.code == ErrorCode::PRECONDITION_FAILED => {
+            if read_log(stream_client, id).await?.as_ref() == Some(&log) {
+                return Ok(());
+            }
+            warn!("Log of transaction[id={id}] has been changed by others");
+            Err(e)
+        }
+        result => result,
+    }
+}
+
+/// Fence an expired transaction from being prepared to commit by logging it as aborted, unless it is
+/// already prepared to complete.
+///
+/// # Returns
+/// State of the transaction in its log, or `None` if the log is removed, in which case control
+/// records of its outcome are appended to all its streams.
+pub(crate) async fn fence_expired(
+    stream_client: &StreamClient,
+    id: &str,
+) -> Result<Option<TransactionState>, EsError> {
+    loop {
+        let Some(value) = read_log(stream_client, id).await? else {
+            return Ok(None);
+        };
+        let (state, deadline, streams) = decode_log(&value).ok_or_else(|| {
+            error!("Malformed log of transaction[id={id}]: {value:?}");
+            EsError::new(ErrorCode::UNEXPECTED, "Malformed transaction log")
+        })?;
+        if state != TransactionState::Ongoing {
+            return Ok(Some(state));
+        }
+        let aborted = encode_log(TransactionState::Aborted, deadline, &streams);
+        match compare_and_put_log(stream_client, id, Some(value), aborted).await {
+            Ok(()) => {
+                info!("Fenced expired transaction[id={id}] as aborted");
+                return Ok(Some(TransactionState::Aborted));
+            }
+            // The log changes in the meantime, check it again.
+            Err(e) if e.code == ErrorCode::PRECONDITION_FAILED => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Smallest key greater than all keys prefixed with `prefix`.
+fn prefix_end(prefix: &str) -> Bytes {
+    let mut end = prefix.as_bytes().to_vec();
+    // Prefixes here always end with '/', which is far from 0xFF.
+    *end.last_mut().expect("prefix should not be empty") += 1;
+    Bytes::from(end)
+}
+
+/// Decode all record batches in `data`, each element of which holds one or more encoded record
+/// batches.
+pub(crate) fn decode_all(data: Vec<Bytes>) -> Result<Vec<RecordBatch>, EsError> {
+    let mut batches = vec![];
+    for mut buf in data {
+        while !buf.is_empty() {
+            let batch = FlatRecordBatch::decode_to_record_batch(&mut buf).map_err(|e| {
+                error!("Failed to decode record batch read: {e:?}");
+                EsError::new(ErrorCode::UNEXPECTED, "Invalid record batch read")
+            })?;
+            batches.push(batch);
+        }
+    }
+    Ok(batches)
+}
+
+/// Collect outcomes of transactions from control records among `batches`.
+pub(crate) fn collect_outcomes(
+    batches: &[RecordBatch],
+    outcomes: &mut HashMap<String, ControlType>,
+) {
+    for batch in batches.iter().filter(|batch| batch.is_control()) {
+        let id = batch.property(TXN_ID_PROPERTY);
+        let control = batch
+            .property(TXN_CONTROL_PROPERTY)
+            .and_then(ControlType::parse);
+        if let (Some(id), Some(control @ (ControlType::Commit | ControlType::Abort))) =
+            (id, control)
+        {
+            outcomes.insert(id.to_owned(), control);
+        }
+    }
+}
+
+/// Ids of transactions among `batches` whose outcome is not in `outcomes`.
+pub(crate) fn pending_transactions<'a>(
+    batches: &'a [RecordBatch],
+    outcomes: &HashMap<String, ControlType>,
+) -> BTreeSet<&'a str> {
+    batches
+        .iter()
+        .filter(|batch| batch.is_transactional() && !batch.is_control())
+        .filter_map(|batch| batch.property(TXN_ID_PROPERTY))
+        .filter(|id| !outcomes.contains_key(*id))
+        .collect()
+}
+
+/// Ids of transactions pending among `batches` whose deadline is before `now`, in milliseconds since
+/// epoch.
+pub(crate) fn expired_transactions<'a>(
+    batches: &'a [RecordBatch],
+    outcomes: &HashMap<String, ControlType>,
+    now: i64,
+) -> BTreeSet<&'a str> {
+    batches
+        .iter()
+        .filter(|batch| batch.is_transactional() && !batch.is_control())
+        .filter(|batch| {
+            batch
+                .property(TXN_DEADLINE_PROPERTY)
+                .and_then(|deadline| deadline.parse::<i64>().ok())
+                .map_or(false, |deadline| deadline < now)
+        })
+        .filter_map(|batch| batch.property(TXN_ID_PROPERTY))
+        .filter(|id| !outcomes.contains_key(*id))
+        .collect()
+}
+
+/// Outcomes of transactions learned by read-committed reads of a stream, along with the range of
+/// offsets whose control records are all collected, so that successive reads do not scan the same
+/// control records again.
+#[derive(Debug, Default)]
+pub(crate) struct OutcomeCache {
+    outcomes: HashMap<String, ControlType>,
+    scanned: Range<i64>,
+}
+
+impl OutcomeCache {
+    pub(crate) fn get(&self, id: &str) -> Option<ControlType> {
+        self.outcomes.get(id).copied()
+    }
+
+    /// Offset to scan forward from for outcomes, instead of `offset`, skipping the range scanned.
+    pub(crate) fn resume_from(&self, offset: i64) -> i64 {
+        if self.scanned.contains(&offset) {
+            self.scanned.end
+        } else {
+            offset
+        }
+    }
+
+    /// Cache `outcomes`, which include those of all control records in `[start, end)`.
+    ///
+    /// The range scanned is extended if it is contiguous with `[start, end)`, otherwise it restarts
+    /// along with the outcomes cached.
+    pub(crate) fn scanned(
+        &mut self,
+        start: i64,
+        end: i64,
+        outcomes: &HashMap<String, ControlType>,
+    ) {
+        let contiguous =
+            !self.scanned.is_empty() && start <= self.scanned.end && end >= self.scanned.start;
+        if !contiguous || self.outcomes.len() + outcomes.len() > MAX_CACHED_OUTCOMES {
+            self.outcomes.clear();
+            self.scanned = start..end;
+        } else {
+            self.scanned = self.scanned.start.min(start)..self.scanned.end.max(end);
+        }
+        self.insert(outcomes);
+    }
+
+    /// Cache `outcomes` without extending the range scanned.
+    pub(crate) fn insert(&mut self, outcomes: &HashMap<String, ControlType>) {
+        self.outcomes
+            .extend(outcomes.iter().map(|(id, control)| (id.clone(), *control)));
+    }
+}
+
+/// Filter record batches, in offset order from `start_offset`, for read-committed readers.
+///
+/// Control records and record batches of aborted transactions are skipped. Filtering stops at the
+/// first record batch of a transaction whose outcome is unknown.
+pub(crate) fn filter_committed(
+    start_offset: i64,
+    batches: Vec<RecordBatch>,
+    outcomes: &HashMap<String, ControlType>,
+) -> ReadCommittedResult {
+    let mut result = ReadCommittedResult {
+        data: vec![],
+        next_offset: start_offset,
+    };
+    for batch in batches {
+        if batch.is_transactional() && !batch.is_control() {
+            match batch
+                .property(TXN_ID_PROPERTY)
+                .and_then(|id| outcomes.get(id))
+            {
+                Some(ControlType::Commit) => {}
+                Some(_) => {
+                    result.next_offset = batch.base_offset() + batch.last_offset_delta() as i64;
+                    continue;
+                }
+                None => break,
+            }
+        }
+        result.next_offset = batch.base_offset() + batch.last_offset_delta() as i64;
+        if batch.is_control() {
+            continue;
+        }
+        let (buffers, len) = FlatRecordBatch::from(batch).encode();
+        let mut buf = BytesMut::with_capacity(len as usize);
+        buffers.iter().for_each(|b| buf.extend_from_slice(b));
+        result.data.push(buf.freeze());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeSet, HashMap},
+        error::Error,
+        time::Duration,
+    };
+
+    use bytes::Bytes;
+    use model::{
+        record::{flags, flat_record::FlatRecordBatch, magic1::RecordsBuilder},
+        RecordBatch,
+    };
+    use range_server::standalone::Standalone;
+
+    use super::{
+        collect_outcomes, decode_log, encode_log, expired_transactions, fence_expired,
+        filter_committed, pending_transactions, ControlType, OutcomeCache, TransactionState,
+        TXN_CONTROL_PROPERTY, TXN_DEADLINE_PROPERTY, TXN_ID_PROPERTY,
+    };
+    use crate::{FrontendBuilder, OpenOptions, Stream, StreamOptions};
+
+    fn batch(base_offset: i64, txn: Option<&str>, control: Option<ControlType>) -> RecordBatch {
+        let mut builder = RecordBatch::new_builder()
+            .with_stream_id(1)
+            .with_range_index(0)
+            .with_base_offset(base_offset)
+            .with_last_offset_delta(1)
+            .with_payload(Bytes::from_static(b"data"));
+        let mut flag = 0;
+        if let Some(id) = txn {
+            flag |= flags::TRANSACTIONAL;
+            builder = builder
+                .with_property(TXN_ID_PROPERTY.to_owned(), id.to_owned())
+                .with_property(TXN_DEADLINE_PROPERTY.to_owned(), "1000".to_owned());
+        }
+        if let Some(control) = control {
+            flag |= flags::CONTROL;
+            builder =
+                builder.with_property(TXN_CONTROL_PROPERTY.to_owned(), control.as_str().to_owned());
+        }
+        builder.with_flags(flag).build().unwrap()
+    }
+
+    #[test]
+    fn test_transaction_log() {
+        let streams = BTreeSet::from([3, 1]);
+        let log = encode_log(TransactionState::PrepareCommit, 1000, &streams);
+        assert_eq!(Bytes::from("prepare-commit;1000;1,3"), log);
+        assert_eq!(
+            Some((TransactionState::PrepareCommit, 1000, streams)),
+            decode_log(&log)
+        );
+        assert_eq!(
+            Some((TransactionState::Ongoing, 1000, BTreeSet::new())),
+            decode_log(b"ongoing;1000;")
+        );
+        assert_eq!(
+            Some((TransactionState::Aborted, 1000, BTreeSet::from([1]))),
+            decode_log(b"aborted;1000;1")
+        );
+        assert_eq!(None, decode_log(b"unknown;1000;1"));
+        assert_eq!(None, decode_log(b"ongoing;1"));
+    }
+
+    fn records(stream: &Stream) -> Result<Bytes, Box<dyn Error>> {
+        let mut builder = RecordsBuilder::new();
+        builder.append(0, None, Some(Bytes::from_static(b"record")), &[]);
+        let (buffers, _) = FlatRecordBatch::from(builder.build(stream.id() as i64)?).encode();
+        Ok(Bytes::from(buffers.concat()))
+    }
+
+    /// Number of record batches visible to read-committed readers of the stream.
+    async fn committed(stream: &Stream) -> Result<usize, Box<dyn Error>> {
+        let next_offset = stream.next_offset().await?;
+        let result = stream.read_committed(0, next_offset, 1024 * 1024).await?;
+        assert_eq!(next_offset, result.next_offset);
+        Ok(result.data.len())
+    }
+
+    #[test]
+    fn test_fence_expired() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        let store_dir = tempfile::tempdir()?;
+        let standalone = Standalone::start_for_test(store_dir.path())?;
+        let placement_driver = standalone.placement_driver().to_owned();
+        let result = tokio_uring::start(async move {
+            let frontend = FrontendBuilder::new(&placement_driver).build()?;
+            let coordinator = frontend.transaction_coordinator("test");
+            let stream_client = &coordinator.stream_client;
+            let mut streams = vec![];
+            for _ in 0..3 {
+                let stream_id = frontend
+                    .create(StreamOptions {
+                        replica: 1,
+                        ack: 1,
+                        retention: Duration::from_secs(3600),
+                        compaction: None,
+                        rollover: None,
+                    })
+                    .await?;
+                streams.push(frontend.open(stream_id, OpenOptions::default()).await?);
+            }
+
+            // Readers fence the transaction before it commits, so the commit fails and aborts it.
+            let txn = coordinator.begin();
+            txn.append(&streams[0], records(&streams[0])?).await?;
+            assert_eq!(
+                Some(TransactionState::Aborted),
+                fence_expired(stream_client, txn.id()).await?
+            );
+            assert!(txn.commit().await.is_err());
+            assert_eq!(0, committed(&streams[0]).await?);
+
+            // Transactions prepared to commit are not fenced.
+            let txn = coordinator.begin();
+            txn.append(&streams[1], records(&streams[1])?).await?;
+            let log = encode_log(
+                TransactionState::PrepareCommit,
+                txn.deadline(),
+                &BTreeSet::from([streams[1].id()]),
+            );
+            stream_client.kv_put(super::txn_key(txn.id()), log).await?;
+            assert_eq!(
+                Some(TransactionState::PrepareCommit),
+                fence_expired(stream_client, txn.id()).await?
+            );
+            // As if a retried request of the commit has applied it.
+            txn.commit().await?;
+            assert_eq!(None, fence_expired(stream_client, txn.id()).await?);
+            assert_eq!(1, committed(&streams[1]).await?);
+
+            // Either the commit or the fence wins the race, and readers agree with the winner.
+            let txn = coordinator.begin();
+            txn.append(&streams[2], records(&streams[2])?).await?;
+            let id = txn.id().to_owned();
+            let (commit, fenced) = futures::join!(txn.commit(), fence_expired(stream_client, &id));
+            match fenced? {
+                Some(TransactionState::Aborted) => {
+                    assert!(commit.is_err());
+                    assert_eq!(0, committed(&streams[2]).await?);
+                }
+                Some(TransactionState::PrepareCommit) | None => {
+                    commit?;
+                    assert_eq!(1, committed(&streams[2]).await?);
+                }
+                state => panic!("Unexpected state {state:?}"),
+            }
+            Ok::<_, Box<dyn Error>>(())
+        });
+        standalone.shutdown();
+        result
+    }
+
+    #[test]
+    fn test_filter_committed() {
+        let batches = vec![
+            batch(10, None, None),
+            batch(11, Some("a"), Some(ControlType::Begin)),
+            batch(12, Some("a"), None),
+            batch(13, Some("b"), Some(ControlType::Begin)),
+            batch(14, Some("b"), None),
+            batch(15, Some("b"), Some(ControlType::Abort)),
+            batch(16, Some("a"), Some(ControlType::Commit)),
+            batch(17, None, None),
+        ];
+        let mut outcomes = HashMap::new();
+        collect_outcomes(&batches, &mut outcomes);
+        assert!(pending_transactions(&batches, &outcomes).is_empty());
+
+        let result = filter_committed(10, batches, &outcomes);
+        assert_eq!(18, result.next_offset);
+        let offsets: Vec<_> = super::decode_all(result.data)
+            .unwrap()
+            .iter()
+            .map(|batch| batch.base_offset())
+            .collect();
+        assert_eq!(vec![10, 12, 17], offsets);
+    }
+
+    #[test]
+    fn test_filter_stops_at_pending_transaction() {
+        let batches = vec![
+            batch(10, None, None),
+            batch(11, Some("a"), Some(ControlType::Begin)),
+            batch(12, Some("a"), None),
+            batch(13, None, None),
+        ];
+        let mut outcomes = HashMap::new();
+        collect_outcomes(&batches, &mut outcomes);
+        assert_eq!(
+            BTreeSet::from(["a"]),
+            pending_transactions(&batches, &outcomes)
+        );
+
+        let result = filter_committed(10, batches, &outcomes);
+        assert_eq!(12, result.next_offset);
+        assert_eq!(1, super::decode_all(result.data).unwrap().len());
+    }
+
+    #[test]
+    fn test_expired_transactions() {
+        let batches = vec![
+            batch(10, Some("a"), Some(ControlType::Begin)),
+            batch(11, Some("a"), None),
+            batch(12, Some("b"), None),
+            batch(13, Some("b"), Some(ControlType::Commit)),
+        ];
+        let mut outcomes = HashMap::new();
+        collect_outcomes(&batches, &mut outcomes);
+        assert!(expired_transactions(&batches, &outcomes, 1000).is_empty());
+        assert_eq!(
+            BTreeSet::from(["a"]),
+            expired_transactions(&batches, &outcomes, 1001)
+        );
+
+        // Expired transactions are treated as aborted.
+        outcomes.insert("a".to_owned(), ControlType::Abort);
+        let result = filter_committed(10, batches, &outcomes);
+        assert_eq!(14, result.next_offset);
+        let offsets: Vec<_> = super::decode_all(result.data)
+            .unwrap()
+            .iter()
+            .map(|batch| batch.base_offset())
+            .collect();
+        assert_eq!(vec![12], offsets);
+    }
+
+    #[test]
+    fn test_outcome_cache() {
+        let mut cache = OutcomeCache::default();
+        let outcomes = HashMap::from([("a".to_owned(), ControlType::Commit)]);
+        cache.scanned(10, 20, &outcomes);
+        assert_eq!(Some(ControlType::Commit), cache.get("a"));
+        assert_eq!(5, cache.resume_from(5));
+        assert_eq!(20, cache.resume_from(15));
+        assert_eq!(25, cache.resume_from(25));
+
+        // Contiguous ranges extend the range scanned.
+        let outcomes = HashMap::from([("b".to_owned(), ControlType::Abort)]);
+        cache.scanned(20, 30, &outcomes);
+        assert_eq!(30, cache.resume_from(10));
+        assert_eq!(Some(ControlType::Commit), cache.get("a"));
+        assert_eq!(Some(ControlType::Abort), cache.get("b"));
+
+        // Others restart it.
+        cache.scanned(40, 50, &HashMap::new());
+        assert_eq!(10, cache.resume_from(10));
+        assert_eq!(50, cache.resume_from(40));
+        assert_eq!(None, cache.get("a"));
+    }
+}
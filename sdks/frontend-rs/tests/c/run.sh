@@ -0,0 +1,48 @@
+#!/usr/bin/env bash
+# Build the frontend and range-server, then run the C test program against a standalone range server
+# along with its embedded placement driver.
+set -euo pipefail
+
+BASEDIR=$(dirname "$0")
+cd "$BASEDIR/../../../.."
+
+cargo build -p frontend -p range-server
+TARGET_DIR=target/debug
+OUT_DIR=$(mktemp -d)
+
+cc -std=c11 -Wall -Wextra -Werror -o "$OUT_DIR/stream_test" \
+    -I sdks/frontend-rs/include sdks/frontend-rs/tests/c/stream_test.c \
+    -L "$TARGET_DIR" -lfrontend -lpthread -ldl -lm
+
+cat > "$OUT_DIR/log.yaml" <<EOF
+appenders:
+  stderr:
+    kind: console
+    target: stderr
+root:
+  level: warn
+  appenders:
+    - stderr
+EOF
+
+PD_PORT=${PD_PORT:-12378}
+SERVER_PORT=${SERVER_PORT:-10911}
+"$TARGET_DIR/range-server" standalone \
+    --pd "127.0.0.1:$PD_PORT" \
+    --addr "127.0.0.1:$SERVER_PORT" \
+    --advertise-addr "127.0.0.1:$SERVER_PORT" \
+    --store-path "$OUT_DIR/store" \
+    --config "$OUT_DIR/range-server.yaml" \
+    --log "$OUT_DIR/log.yaml" &
+RANGE_SERVER_PID=$!
+trap 'kill $RANGE_SERVER_PID 2>/dev/null || true; rm -rf "$OUT_DIR"' EXIT
+
+# The range server listens once the embedded placement driver is up.
+for _ in $(seq 1 100); do
+    if (exec 3<>"/dev/tcp/127.0.0.1/$SERVER_PORT") 2>/dev/null; then
+        break
+    fi
+    sleep 0.1
+done
+
+LD_LIBRARY_PATH="$TARGET_DIR" "$OUT_DIR/stream_test" "127.0.0.1:$PD_PORT"
@@ -0,0 +1,204 @@
+/*
+ * Exercise the C binding of the frontend against a standalone range server, see run.sh.
+ */
+#define _POSIX_C_SOURCE 200809L
+
+#include <assert.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+
+#include "elastic_stream.h"
+
+typedef struct {
+  pthread_mutex_t mutex;
+  pthread_cond_t cond;
+  int done;
+  int ok;
+  char message[256];
+  uint64_t stream_id;
+  EsStream *stream;
+  int64_t offset;
+  size_t count;
+  int64_t offsets[4];
+  char values[4][16];
+} Waiter;
+
+static void waiter_init(Waiter *waiter) {
+  memset(waiter, 0, sizeof(*waiter));
+  pthread_mutex_init(&waiter->mutex, NULL);
+  pthread_cond_init(&waiter->cond, NULL);
+}
+
+static void waiter_wait(Waiter *waiter) {
+  pthread_mutex_lock(&waiter->mutex);
+  while (!waiter->done) {
+    pthread_cond_wait(&waiter->cond, &waiter->mutex);
+  }
+  waiter->done = 0;
+  pthread_mutex_unlock(&waiter->mutex);
+}
+
+/* Record the status and wake up the waiter. Must be called with the mutex held. */
+static void waiter_notify(Waiter *waiter, const EsStatus *status) {
+  waiter->ok = status == NULL;
+  if (status != NULL) {
+    snprintf(waiter->message, sizeof(waiter->message), "%d: %s", status->code, status->message);
+  }
+  waiter->done = 1;
+  pthread_cond_signal(&waiter->cond);
+}
+
+static void on_create(void *ctx, const EsStatus *status, uint64_t stream_id) {
+  Waiter *waiter = ctx;
+  pthread_mutex_lock(&waiter->mutex);
+  waiter->stream_id = stream_id;
+  waiter_notify(waiter, status);
+  pthread_mutex_unlock(&waiter->mutex);
+}
+
+static void on_open(void *ctx, const EsStatus *status, EsStream *stream) {
+  Waiter *waiter = ctx;
+  pthread_mutex_lock(&waiter->mutex);
+  waiter->stream = stream;
+  waiter_notify(waiter, status);
+  pthread_mutex_unlock(&waiter->mutex);
+}
+
+static void on_offset(void *ctx, const EsStatus *status, int64_t offset) {
+  Waiter *waiter = ctx;
+  pthread_mutex_lock(&waiter->mutex);
+  waiter->offset = offset;
+  waiter_notify(waiter, status);
+  pthread_mutex_unlock(&waiter->mutex);
+}
+
+static void on_read(void *ctx, const EsStatus *status, const EsRecord *records, size_t count) {
+  Waiter *waiter = ctx;
+  pthread_mutex_lock(&waiter->mutex);
+  waiter->count = count;
+  /* Records are only valid during the callback, so copy those to assert. */
+  for (size_t i = 0; i < count && i < 4; i++) {
+    waiter->offsets[i] = records[i].offset;
+    snprintf(waiter->values[i], sizeof(waiter->values[i]), "%.*s", (int)records[i].value_len,
+             (const char *)records[i].value);
+  }
+  waiter_notify(waiter, status);
+  pthread_mutex_unlock(&waiter->mutex);
+}
+
+static void on_done(void *ctx, const EsStatus *status) {
+  Waiter *waiter = ctx;
+  pthread_mutex_lock(&waiter->mutex);
+  waiter_notify(waiter, status);
+  pthread_mutex_unlock(&waiter->mutex);
+}
+
+static void report(const char *operation, const Waiter *waiter) {
+  if (waiter->ok) {
+    printf("%s: ok\n", operation);
+  } else {
+    printf("%s: failed, %s\n", operation, waiter->message);
+  }
+}
+
+static void exercise_stream(EsStream *stream, Waiter *waiter) {
+  const char *values[] = {"hello", "world"};
+  EsRecord records[2];
+  memset(records, 0, sizeof(records));
+  for (int i = 0; i < 2; i++) {
+    records[i].value = (const uint8_t *)values[i];
+    records[i].value_len = strlen(values[i]);
+  }
+  assert(es_stream_append(stream, records, 2, on_offset, waiter) == 0);
+  waiter_wait(waiter);
+  report("append", waiter);
+  assert(waiter->ok);
+  int64_t base_offset = waiter->offset;
+  assert(base_offset == 0);
+
+  assert(es_stream_next_offset(stream, on_offset, waiter) == 0);
+  waiter_wait(waiter);
+  assert(waiter->ok);
+  assert(waiter->offset == base_offset + 2);
+
+  assert(es_stream_read(stream, base_offset, base_offset + 2, 1024 * 1024, on_read, waiter) == 0);
+  waiter_wait(waiter);
+  report("read", waiter);
+  assert(waiter->ok);
+  assert(waiter->count == 2);
+  for (int i = 0; i < 2; i++) {
+    assert(waiter->offsets[i] == base_offset + i);
+    assert(strcmp(waiter->values[i], values[i]) == 0);
+  }
+
+  assert(es_stream_trim(stream, base_offset + 1, on_done, waiter) == 0);
+  waiter_wait(waiter);
+  report("trim", waiter);
+  assert(waiter->ok);
+
+  assert(es_stream_start_offset(stream, on_offset, waiter) == 0);
+  waiter_wait(waiter);
+  assert(waiter->ok);
+  assert(waiter->offset == base_offset + 1);
+
+  /* Records before the start offset are trimmed. */
+  assert(es_stream_read(stream, base_offset + 1, base_offset + 2, 1024 * 1024, on_read, waiter) == 0);
+  waiter_wait(waiter);
+  assert(waiter->ok);
+  assert(waiter->count == 1);
+  assert(strcmp(waiter->values[0], "world") == 0);
+}
+
+int main(int argc, char **argv) {
+  if (argc != 2) {
+    fprintf(stderr, "Usage: %s <placement-driver-address>\n", argv[0]);
+    return 1;
+  }
+
+  /* Invalid arguments are rejected without invoking callbacks. */
+  assert(es_frontend_new(NULL, NULL, NULL, NULL, 0) == NULL);
+  const char *invalid_options[] = {"client.io-timeout"};
+  assert(es_frontend_new(argv[1], NULL, NULL, invalid_options, 1) == NULL);
+  assert(es_stream_create(NULL, 1, 1, 3600 * 1000, on_create, NULL) == -1);
+  assert(es_stream_close(NULL, on_done, NULL) == -1);
+
+  const char *options[] = {"client.io-timeout=10"};
+  EsFrontend *frontend = es_frontend_new(argv[1], NULL, NULL, options, 1);
+  assert(frontend != NULL);
+
+  Waiter waiter;
+  waiter_init(&waiter);
+
+  assert(es_stream_create(frontend, 1, 1, 3600 * 1000, on_create, &waiter) == 0);
+  waiter_wait(&waiter);
+  report("create", &waiter);
+  assert(waiter.ok);
+  assert(waiter.stream_id > 0);
+
+  /* Opening a stream creates its first range, once the range server registers. */
+  for (int attempt = 0; attempt < 50; attempt++) {
+    assert(es_stream_open(frontend, waiter.stream_id, 0, on_open, &waiter) == 0);
+    waiter_wait(&waiter);
+    if (waiter.ok) {
+      break;
+    }
+    struct timespec interval = {0, 200 * 1000 * 1000};
+    nanosleep(&interval, NULL);
+  }
+  report("open", &waiter);
+  assert(waiter.ok);
+  EsStream *stream = waiter.stream;
+  assert(es_stream_id(stream) == waiter.stream_id);
+  exercise_stream(stream, &waiter);
+
+  assert(es_stream_close(stream, on_done, &waiter) == 0);
+  waiter_wait(&waiter);
+  report("close", &waiter);
+  assert(waiter.ok);
+
+  es_frontend_free(frontend);
+  printf("C binding test passed\n");
+  return 0;
+}
@@ -0,0 +1,136 @@
+//! Per-request auth hooks, which authorize each request before it reaches streams.
+
+use std::{collections::HashSet, net::SocketAddr};
+
+use hyper::{header::AUTHORIZATION, HeaderMap};
+
+use crate::error::GatewayError;
+
+/// Operation of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Append,
+    Read,
+    Tail,
+    Trim,
+    Describe,
+}
+
+impl Operation {
+    /// Whether the operation changes the stream.
+    pub fn is_write(&self) -> bool {
+        matches!(self, Operation::Append | Operation::Trim)
+    }
+}
+
+/// What an auth hook knows about a request, from either gRPC or HTTP.
+#[derive(Debug)]
+pub struct RequestContext {
+    pub operation: Operation,
+    pub stream_id: u64,
+    /// Metadata of gRPC requests, or headers of HTTP requests.
+    pub headers: HeaderMap,
+    pub remote_addr: Option<SocketAddr>,
+}
+
+impl RequestContext {
+    /// Token of the `authorization: Bearer <token>` header, if any.
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.headers
+            .get(AUTHORIZATION)?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")
+            .map(str::trim)
+    }
+}
+
+/// Hook authorizing requests, which is called on every request before it is served.
+///
+/// Hooks are called on threads serving gRPC and HTTP, so they should not block for long.
+pub trait AuthHook: Send + Sync + 'static {
+    /// Reject the request with `GatewayError::Unauthenticated` or `GatewayError::PermissionDenied`.
+    fn authorize(&self, request: &RequestContext) -> Result<(), GatewayError>;
+}
+
+/// Allow all requests, which is the default.
+#[derive(Debug, Default)]
+pub struct AllowAll;
+
+impl AuthHook for AllowAll {
+    fn authorize(&self, _request: &RequestContext) -> Result<(), GatewayError> {
+        Ok(())
+    }
+}
+
+/// Allow requests bearing any of the given tokens.
+#[derive(Debug)]
+pub struct BearerTokens {
+    tokens: HashSet<String>,
+}
+
+impl BearerTokens {
+    pub fn new<I, T>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self {
+            tokens: tokens.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl AuthHook for BearerTokens {
+    fn authorize(&self, request: &RequestContext) -> Result<(), GatewayError> {
+        let token = request
+            .bearer_token()
+            .ok_or_else(|| GatewayError::Unauthenticated("Bearer token is absent".to_owned()))?;
+        if !self.tokens.contains(token) {
+            return Err(GatewayError::PermissionDenied(format!(
+                "Token is not allowed to {:?} stream[id={}]",
+                request.operation, request.stream_id
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{header::AUTHORIZATION, HeaderMap};
+
+    use super::{AuthHook, BearerTokens, Operation, RequestContext};
+    use crate::error::GatewayError;
+
+    fn request(authorization: Option<&'static str>) -> RequestContext {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = authorization {
+            headers.insert(AUTHORIZATION, value.parse().unwrap());
+        }
+        RequestContext {
+            operation: Operation::Append,
+            stream_id: 1,
+            headers,
+            remote_addr: None,
+        }
+    }
+
+    #[test]
+    fn test_bearer_tokens() {
+        let hook = BearerTokens::new(["secret"]);
+        assert!(hook.authorize(&request(Some("Bearer secret"))).is_ok());
+        assert!(matches!(
+            hook.authorize(&request(Some("Bearer guess"))),
+            Err(GatewayError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            hook.authorize(&request(Some("Basic c2VjcmV0"))),
+            Err(GatewayError::Unauthenticated(_))
+        ));
+        assert!(matches!(
+            hook.authorize(&request(None)),
+            Err(GatewayError::Unauthenticated(_))
+        ));
+    }
+}
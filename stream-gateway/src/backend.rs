@@ -0,0 +1,387 @@
+//! `Frontend` and its streams are NOT `Send`, so they are served by a dedicated tokio-uring runtime
+//! thread, to which gRPC and HTTP handlers dispatch commands, like the language bindings do.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use bytes::Bytes;
+use frontend::{Frontend, OpenOptions, Stream, StreamReaderOptions};
+use futures::StreamExt;
+use log::{info, warn};
+use model::error::EsError;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use crate::{
+    error::GatewayError,
+    proto::{DescribeResponse, ReadResponse, TailResponse},
+    record,
+};
+
+/// Max bytes of record batches to read if requests do not tell.
+const DEFAULT_MAX_BYTES: i32 = 1024 * 1024;
+
+/// Number of tail responses buffered for a slow caller, before reads of the tail are paused.
+const TAIL_BUFFER: usize = 16;
+
+type Reply<T> = oneshot::Sender<Result<T, GatewayError>>;
+
+enum Command {
+    Append {
+        stream_id: u64,
+        batch: Bytes,
+        reply: Reply<i64>,
+    },
+    Read {
+        stream_id: u64,
+        offset: i64,
+        max_bytes: i32,
+        reply: Reply<ReadResponse>,
+    },
+    Tail {
+        stream_id: u64,
+        offset: i64,
+        tx: mpsc::Sender<Result<TailResponse, GatewayError>>,
+    },
+    Trim {
+        stream_id: u64,
+        offset: i64,
+        reply: Reply<()>,
+    },
+    Describe {
+        stream_id: u64,
+        reply: Reply<DescribeResponse>,
+    },
+}
+
+/// Handle of the runtime thread serving streams, shared by gRPC and HTTP handlers.
+#[derive(Debug, Clone)]
+pub(crate) struct Backend {
+    tx: mpsc::UnboundedSender<Command>,
+}
+
+impl Backend {
+    /// Start the runtime thread. Streams are opened for read only to read, tail and describe them. If
+    /// `writable`, streams are also opened with a new epoch on first append or trim, fencing other
+    /// writers, and otherwise append and trim are rejected.
+    pub(crate) fn start(frontend: Frontend, writable: bool) -> Result<Self, GatewayError> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        std::thread::Builder::new()
+            .name("GatewayRuntime".to_owned())
+            .spawn(move || {
+                tokio_uring::start(async move {
+                    let streams = Rc::new(Streams::new(frontend, writable));
+                    while let Some(command) = rx.recv().await {
+                        tokio_uring::spawn(Rc::clone(&streams).process(command));
+                    }
+                    info!("Gateway command channel is dropped");
+                });
+            })
+            .map_err(|e| {
+                EsError::unexpected("Failed to spawn gateway runtime thread").set_source(e)
+            })?;
+        Ok(Self { tx })
+    }
+
+    pub(crate) async fn append(&self, stream_id: u64, batch: Bytes) -> Result<i64, GatewayError> {
+        self.call(|reply| Command::Append {
+            stream_id,
+            batch,
+            reply,
+        })
+        .await
+    }
+
+    pub(crate) async fn read(
+        &self,
+        stream_id: u64,
+        offset: i64,
+        max_bytes: i32,
+    ) -> Result<ReadResponse, GatewayError> {
+        self.call(|reply| Command::Read {
+            stream_id,
+            offset,
+            max_bytes,
+            reply,
+        })
+        .await
+    }
+
+    /// Tail the stream from `offset`, until the returned receiver is dropped.
+    pub(crate) fn tail(
+        &self,
+        stream_id: u64,
+        offset: i64,
+    ) -> Result<mpsc::Receiver<Result<TailResponse, GatewayError>>, GatewayError> {
+        let (tx, rx) = mpsc::channel(TAIL_BUFFER);
+        self.tx
+            .send(Command::Tail {
+                stream_id,
+                offset,
+                tx,
+            })
+            .map_err(|_| GatewayError::Unavailable)?;
+        Ok(rx)
+    }
+
+    pub(crate) async fn trim(&self, stream_id: u64, offset: i64) -> Result<(), GatewayError> {
+        self.call(|reply| Command::Trim {
+            stream_id,
+            offset,
+            reply,
+        })
+        .await
+    }
+
+    pub(crate) async fn describe(&self, stream_id: u64) -> Result<DescribeResponse, GatewayError> {
+        self.call(|reply| Command::Describe { stream_id, reply })
+            .await
+    }
+
+    async fn call<T, F>(&self, command: F) -> Result<T, GatewayError>
+    where
+        F: FnOnce(Reply<T>) -> Command,
+    {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(command(reply))
+            .map_err(|_| GatewayError::Unavailable)?;
+        rx.await.map_err(|_| GatewayError::Unavailable)?
+    }
+}
+
+/// Streams opened by the gateway, living on the runtime thread.
+///
+/// A stream may be opened twice: for read only to read, tail and describe it, without fencing its
+/// writer, and for write to append and trim, if the gateway is writable.
+struct Streams {
+    frontend: Frontend,
+    writable: bool,
+    readers: RefCell<HashMap<u64, Rc<Stream>>>,
+    writers: RefCell<HashMap<u64, Rc<Stream>>>,
+
+    /// Serializes opening streams, so that a stream is opened once even if requested concurrently.
+    opening: Mutex<()>,
+}
+
+impl Streams {
+    fn new(frontend: Frontend, writable: bool) -> Self {
+        Self {
+            frontend,
+            writable,
+            readers: RefCell::new(HashMap::new()),
+            writers: RefCell::new(HashMap::new()),
+            opening: Mutex::new(()),
+        }
+    }
+
+    async fn process(self: Rc<Self>, command: Command) {
+        match command {
+            Command::Append {
+                stream_id,
+                batch,
+                reply,
+            } => {
+                let result = self.append(stream_id, batch).await;
+                let _ = reply.send(self.evict_on_error(stream_id, result));
+            }
+            Command::Read {
+                stream_id,
+                offset,
+                max_bytes,
+                reply,
+            } => {
+                let result = self.read(stream_id, offset, max_bytes).await;
+                let _ = reply.send(self.evict_on_error(stream_id, result));
+            }
+            Command::Tail {
+                stream_id,
+                offset,
+                tx,
+            } => {
+                let result = self.tail(stream_id, offset, &tx).await;
+                if let Err(e) = self.evict_on_error(stream_id, result) {
+                    let _ = tx.send(Err(e)).await;
+                }
+            }
+            Command::Trim {
+                stream_id,
+                offset,
+                reply,
+            } => {
+                let result = self.trim(stream_id, offset).await;
+                let _ = reply.send(self.evict_on_error(stream_id, result));
+            }
+            Command::Describe { stream_id, reply } => {
+                let result = self.describe(stream_id).await;
+                let _ = reply.send(self.evict_on_error(stream_id, result));
+            }
+        }
+    }
+
+    /// The stream opened for read only.
+    async fn reader(&self, stream_id: u64) -> Result<Rc<Stream>, GatewayError> {
+        if let Some(stream) = self.readers.borrow().get(&stream_id) {
+            return Ok(Rc::clone(stream));
+        }
+        let _guard = self.opening.lock().await;
+        if let Some(stream) = self.readers.borrow().get(&stream_id) {
+            return Ok(Rc::clone(stream));
+        }
+        let stream = Rc::new(
+            self.frontend
+                .open(stream_id, OpenOptions::read_only())
+                .await?,
+        );
+        info!("Opened stream[id={stream_id}] for read only");
+        self.readers
+            .borrow_mut()
+            .insert(stream_id, Rc::clone(&stream));
+        Ok(stream)
+    }
+
+    /// The stream opened for write with a new epoch, fencing its other writers.
+    async fn writer(&self, stream_id: u64) -> Result<Rc<Stream>, GatewayError> {
+        if !self.writable {
+            return Err(GatewayError::ReadOnly);
+        }
+        if let Some(stream) = self.writers.borrow().get(&stream_id) {
+            return Ok(Rc::clone(stream));
+        }
+        let _guard = self.opening.lock().await;
+        if let Some(stream) = self.writers.borrow().get(&stream_id) {
+            return Ok(Rc::clone(stream));
+        }
+        let epoch = self.frontend.describe(stream_id).await?.epoch + 1;
+        let stream = Rc::new(
+            self.frontend
+                .open(stream_id, OpenOptions::with_epoch(epoch))
+                .await?,
+        );
+        info!("Opened stream[id={stream_id}] with epoch {epoch}");
+        self.writers
+            .borrow_mut()
+            .insert(stream_id, Rc::clone(&stream));
+        Ok(stream)
+    }
+
+    /// Evict the stream after a storage failure, so that it is opened again on next use.
+    fn evict_on_error<T>(
+        &self,
+        stream_id: u64,
+        result: Result<T, GatewayError>,
+    ) -> Result<T, GatewayError> {
+        if let Err(GatewayError::Storage(e)) = &result {
+            warn!("Evict stream[id={stream_id}]: {e}");
+            self.readers.borrow_mut().remove(&stream_id);
+            self.writers.borrow_mut().remove(&stream_id);
+        }
+        result
+    }
+
+    async fn append(&self, stream_id: u64, batch: Bytes) -> Result<i64, GatewayError> {
+        let stream = self.writer(stream_id).await?;
+        Ok(stream.append(batch).await?.base_offset)
+    }
+
+    /// Read records available when the request arrives.
+    ///
+    /// Records are read through a `StreamReader`, which follows ranges of the stream, such that reads
+    /// never span a sealed range and its successor.
+    async fn read(
+        &self,
+        stream_id: u64,
+        offset: i64,
+        max_bytes: i32,
+    ) -> Result<ReadResponse, GatewayError> {
+        let stream = self.reader(stream_id).await?;
+        let start_offset = stream.start_offset().await?;
+        let next_offset = stream.next_offset().await?;
+        if offset < start_offset || offset > next_offset {
+            return Err(GatewayError::OffsetOutOfRange {
+                offset,
+                start_offset,
+                next_offset,
+            });
+        }
+        let max_bytes = if max_bytes > 0 {
+            max_bytes
+        } else {
+            DEFAULT_MAX_BYTES
+        };
+
+        let mut response = ReadResponse {
+            records: vec![],
+            next_offset: offset,
+        };
+        let options = StreamReaderOptions {
+            batch_max_bytes: max_bytes,
+            prefetch: 1,
+            ..Default::default()
+        };
+        let mut reader = stream.reader(offset, options);
+        let mut bytes = 0;
+        while response.next_offset < next_offset && bytes < max_bytes as usize {
+            let Some(record_batch) = reader.next().await.transpose()? else {
+                break;
+            };
+            bytes += record_batch.payload().len();
+            record::decode(&record_batch, offset, &mut response.records)?;
+            response.next_offset = record::end_offset(&record_batch);
+        }
+        Ok(response)
+    }
+
+    /// Send records from `offset` on to `tx`, until it is closed.
+    async fn tail(
+        &self,
+        stream_id: u64,
+        offset: i64,
+        tx: &mpsc::Sender<Result<TailResponse, GatewayError>>,
+    ) -> Result<(), GatewayError> {
+        let stream = self.reader(stream_id).await?;
+        let mut reader = stream.reader(offset, StreamReaderOptions::default());
+        loop {
+            let record_batch = tokio::select! {
+                _ = tx.closed() => break,
+                item = reader.next() => match item {
+                    Some(record_batch) => record_batch?,
+                    None => break,
+                },
+            };
+            let mut response = TailResponse {
+                records: vec![],
+                next_offset: record::end_offset(&record_batch),
+            };
+            record::decode(&record_batch, offset, &mut response.records)?;
+            if response.records.is_empty() {
+                continue;
+            }
+            if tx.send(Ok(response)).await.is_err() {
+                break;
+            }
+        }
+        info!("Stop tailing stream[id={stream_id}]");
+        Ok(())
+    }
+
+    async fn trim(&self, stream_id: u64, offset: i64) -> Result<(), GatewayError> {
+        let stream = self.writer(stream_id).await?;
+        stream.trim(offset).await?;
+        Ok(())
+    }
+
+    async fn describe(&self, stream_id: u64) -> Result<DescribeResponse, GatewayError> {
+        let metadata = self.frontend.describe(stream_id).await?;
+        let stream = self.reader(stream_id).await?;
+        let next_offset = stream.next_offset().await?;
+        Ok(DescribeResponse {
+            stream_id,
+            replica: u32::from(metadata.replica),
+            ack_count: u32::from(metadata.ack_count),
+            retention_ms: metadata.retention_period.as_millis() as u64,
+            epoch: metadata.epoch,
+            compacted: metadata.compacted,
+            start_offset: metadata.start_offset as i64,
+            next_offset,
+        })
+    }
+}
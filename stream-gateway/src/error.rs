@@ -0,0 +1,108 @@
+use hyper::StatusCode;
+use model::error::EsError;
+use protocol::rpc::header::ErrorCode;
+use thiserror::Error;
+use tonic::{Code, Status};
+
+#[derive(Debug, Error)]
+pub enum GatewayError {
+    #[error("Unauthenticated: {0}")]
+    Unauthenticated(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("Offset {offset} is out of range [{start_offset}, {next_offset}]")]
+    OffsetOutOfRange {
+        offset: i64,
+        start_offset: i64,
+        next_offset: i64,
+    },
+
+    #[error("Streams are read only through this gateway")]
+    ReadOnly,
+
+    #[error("Gateway is shutting down")]
+    Unavailable,
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] EsError),
+}
+
+impl GatewayError {
+    /// Status code of the HTTP/JSON mapping.
+    pub fn http_status(&self) -> StatusCode {
+        match Code::from(self) {
+            Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Code::PermissionDenied => StatusCode::FORBIDDEN,
+            Code::InvalidArgument => StatusCode::BAD_REQUEST,
+            Code::OutOfRange => StatusCode::RANGE_NOT_SATISFIABLE,
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::FailedPrecondition => StatusCode::CONFLICT,
+            Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<&GatewayError> for Code {
+    fn from(e: &GatewayError) -> Self {
+        match e {
+            GatewayError::Unauthenticated(_) => Code::Unauthenticated,
+            GatewayError::PermissionDenied(_) => Code::PermissionDenied,
+            GatewayError::InvalidArgument(_) => Code::InvalidArgument,
+            GatewayError::OffsetOutOfRange { .. } => Code::OutOfRange,
+            GatewayError::ReadOnly => Code::FailedPrecondition,
+            GatewayError::Unavailable => Code::Unavailable,
+            GatewayError::Storage(e) => match e.code {
+                ErrorCode::STREAM_NOT_EXIST | ErrorCode::NOT_FOUND => Code::NotFound,
+                ErrorCode::BAD_REQUEST | ErrorCode::RECORDS_PARSE_ERROR => Code::InvalidArgument,
+                ErrorCode::OFFSET_OUT_OF_RANGE_BOUNDS | ErrorCode::OFFSET_OVERFLOW => {
+                    Code::OutOfRange
+                }
+                // Another writer opens the stream with a greater epoch.
+                ErrorCode::EXPIRED_STREAM_EPOCH => Code::FailedPrecondition,
+                ErrorCode::RPC_TIMEOUT => Code::DeadlineExceeded,
+                _ => Code::Internal,
+            },
+        }
+    }
+}
+
+impl From<GatewayError> for Status {
+    fn from(e: GatewayError) -> Self {
+        Status::new(Code::from(&e), e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::StatusCode;
+    use model::error::EsError;
+    use protocol::rpc::header::ErrorCode;
+    use tonic::{Code, Status};
+
+    use super::GatewayError;
+
+    #[test]
+    fn test_codes() {
+        let e = GatewayError::Storage(EsError::new(ErrorCode::STREAM_NOT_EXIST, "Stream 1"));
+        assert_eq!(StatusCode::NOT_FOUND, e.http_status());
+        assert_eq!(Code::NotFound, Status::from(e).code());
+
+        let e = GatewayError::OffsetOutOfRange {
+            offset: 10,
+            start_offset: 20,
+            next_offset: 30,
+        };
+        assert_eq!(StatusCode::RANGE_NOT_SATISFIABLE, e.http_status());
+        assert_eq!(Code::OutOfRange, Status::from(e).code());
+
+        let e = GatewayError::Unauthenticated("Bearer token is absent".to_owned());
+        assert_eq!(StatusCode::UNAUTHORIZED, e.http_status());
+    }
+}
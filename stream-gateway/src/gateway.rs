@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use frontend::Frontend;
+use tokio::sync::mpsc;
+
+use crate::{
+    auth::{AllowAll, AuthHook, RequestContext},
+    backend::Backend,
+    error::GatewayError,
+    proto::{DescribeResponse, ReadResponse, Record, TailResponse},
+    record,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct GatewayConfig {
+    /// Serve appends and trims as well, opening streams for write with a new epoch on first use, which
+    /// fences their other writers. Reads never fence writers.
+    pub writable: bool,
+}
+
+/// Gateway authorizes requests of both gRPC and HTTP, then serves them on streams.
+///
+/// `Gateway` is cheap to clone and `Send`, while streams are served on a dedicated runtime thread.
+#[derive(Clone)]
+pub struct Gateway {
+    backend: Backend,
+    auth_hook: Arc<dyn AuthHook>,
+}
+
+impl Gateway {
+    pub fn new(frontend: Frontend, config: GatewayConfig) -> Result<Self, GatewayError> {
+        Ok(Self {
+            backend: Backend::start(frontend, config.writable)?,
+            auth_hook: Arc::new(AllowAll),
+        })
+    }
+
+    /// Authorize requests by the hook, instead of allowing all.
+    pub fn with_auth_hook<H: AuthHook>(mut self, hook: H) -> Self {
+        self.auth_hook = Arc::new(hook);
+        self
+    }
+
+    /// Append records as a single record batch, returning the offset of the first record.
+    pub async fn append(
+        &self,
+        context: &RequestContext,
+        records: Vec<Record>,
+    ) -> Result<i64, GatewayError> {
+        self.auth_hook.authorize(context)?;
+        // Encode on the calling thread, sparing the runtime thread serving streams.
+        let batch = record::encode(context.stream_id, records)?;
+        self.backend.append(context.stream_id, batch).await
+    }
+
+    pub async fn read(
+        &self,
+        context: &RequestContext,
+        offset: i64,
+        max_bytes: i32,
+    ) -> Result<ReadResponse, GatewayError> {
+        self.auth_hook.authorize(context)?;
+        self.backend
+            .read(context.stream_id, offset, max_bytes)
+            .await
+    }
+
+    /// Tail the stream from `offset`. Tailing stops once the returned receiver is dropped, or after an
+    /// error is received.
+    pub fn tail(
+        &self,
+        context: &RequestContext,
+        offset: i64,
+    ) -> Result<mpsc::Receiver<Result<TailResponse, GatewayError>>, GatewayError> {
+        self.auth_hook.authorize(context)?;
+        self.backend.tail(context.stream_id, offset)
+    }
+
+    pub async fn trim(&self, context: &RequestContext, offset: i64) -> Result<(), GatewayError> {
+        self.auth_hook.authorize(context)?;
+        self.backend.trim(context.stream_id, offset).await
+    }
+
+    pub async fn describe(
+        &self,
+        context: &RequestContext,
+    ) -> Result<DescribeResponse, GatewayError> {
+        self.auth_hook.authorize(context)?;
+        self.backend.describe(context.stream_id).await
+    }
+}
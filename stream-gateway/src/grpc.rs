@@ -0,0 +1,264 @@
+use std::pin::Pin;
+
+use futures::Stream;
+use tonic::{Request, Response, Status};
+
+use crate::{
+    auth::{Operation, RequestContext},
+    gateway::Gateway,
+    proto::{
+        stream_service_server::{StreamService, StreamServiceServer},
+        AppendRequest, AppendResponse, DescribeRequest, DescribeResponse, ReadRequest,
+        ReadResponse, TailRequest, TailResponse, TrimRequest, TrimResponse,
+    },
+};
+
+/// `StreamService` of `proto/gateway.proto`, served by the gateway.
+pub struct GrpcService {
+    gateway: Gateway,
+}
+
+impl GrpcService {
+    pub fn new(gateway: Gateway) -> Self {
+        Self { gateway }
+    }
+
+    pub fn into_server(self) -> StreamServiceServer<Self> {
+        StreamServiceServer::new(self)
+    }
+}
+
+fn context<T>(request: &Request<T>, operation: Operation, stream_id: u64) -> RequestContext {
+    RequestContext {
+        operation,
+        stream_id,
+        headers: request.metadata().clone().into_headers(),
+        remote_addr: request.remote_addr(),
+    }
+}
+
+type TailStream = Pin<Box<dyn Stream<Item = Result<TailResponse, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl StreamService for GrpcService {
+    async fn append(
+        &self,
+        request: Request<AppendRequest>,
+    ) -> Result<Response<AppendResponse>, Status> {
+        let context = context(&request, Operation::Append, request.get_ref().stream_id);
+        let base_offset = self
+            .gateway
+            .append(&context, request.into_inner().records)
+            .await?;
+        Ok(Response::new(AppendResponse { base_offset }))
+    }
+
+    async fn read(&self, request: Request<ReadRequest>) -> Result<Response<ReadResponse>, Status> {
+        let ReadRequest {
+            stream_id,
+            offset,
+            max_bytes,
+        } = *request.get_ref();
+        let context = context(&request, Operation::Read, stream_id);
+        let response = self.gateway.read(&context, offset, max_bytes).await?;
+        Ok(Response::new(response))
+    }
+
+    type TailStream = TailStream;
+
+    async fn tail(&self, request: Request<TailRequest>) -> Result<Response<TailStream>, Status> {
+        let TailRequest { stream_id, offset } = *request.get_ref();
+        let context = context(&request, Operation::Tail, stream_id);
+        let rx = self.gateway.tail(&context, offset)?;
+        // Dropping the stream on cancellation drops the receiver, which stops tailing.
+        let stream = futures::stream::unfold(rx, |mut rx| async move {
+            let item = rx.recv().await?.map_err(Status::from);
+            Some((item, rx))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn trim(&self, request: Request<TrimRequest>) -> Result<Response<TrimResponse>, Status> {
+        let TrimRequest { stream_id, offset } = *request.get_ref();
+        let context = context(&request, Operation::Trim, stream_id);
+        self.gateway.trim(&context, offset).await?;
+        Ok(Response::new(TrimResponse {}))
+    }
+
+    async fn describe(
+        &self,
+        request: Request<DescribeRequest>,
+    ) -> Result<Response<DescribeResponse>, Status> {
+        let context = context(&request, Operation::Describe, request.get_ref().stream_id);
+        let response = self.gateway.describe(&context).await?;
+        Ok(Response::new(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, time::Duration};
+
+    use frontend::{FrontendBuilder, StreamOptions};
+    use futures::StreamExt;
+    use range_server::standalone::Standalone;
+    use tonic::{Code, Request};
+
+    use super::GrpcService;
+    use crate::{
+        proto::{
+            stream_service_server::StreamService, AppendRequest, DescribeRequest, ReadRequest,
+            Record, TailRequest, TrimRequest,
+        },
+        Gateway, GatewayConfig,
+    };
+
+    fn service(placement_driver: &str, writable: bool) -> Result<GrpcService, Box<dyn Error>> {
+        let frontend = FrontendBuilder::new(placement_driver).build()?;
+        let gateway = Gateway::new(frontend, GatewayConfig { writable })?;
+        Ok(GrpcService::new(gateway))
+    }
+
+    fn records(values: &[&str]) -> Vec<Record> {
+        values
+            .iter()
+            .map(|value| Record {
+                value: Some(value.as_bytes().to_vec()),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn values(records: &[Record]) -> Vec<(i64, &[u8])> {
+        records
+            .iter()
+            .map(|record| (record.offset, record.value.as_deref().unwrap_or_default()))
+            .collect()
+    }
+
+    #[test]
+    fn test_append_read_tail() -> Result<(), Box<dyn Error>> {
+        ulog::try_init_log();
+        let store_dir = tempfile::tempdir()?;
+        let standalone = Standalone::start_for_test(store_dir.path())?;
+        let pd = standalone.placement_driver().to_owned();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let result = runtime.block_on(async move {
+            let stream_id = FrontendBuilder::new(&pd)
+                .build()?
+                .create(StreamOptions {
+                    replica: 1,
+                    ack: 1,
+                    retention: Duration::from_secs(3600),
+                    compaction: None,
+                    rollover: None,
+                })
+                .await?;
+            let writer = service(&pd, true)?;
+            let reader = service(&pd, false)?;
+
+            // Gateways that are not writable reject appends and trims.
+            let status = reader
+                .append(Request::new(AppendRequest {
+                    stream_id,
+                    records: records(&["a"]),
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(Code::FailedPrecondition, status.code());
+            let status = reader
+                .trim(Request::new(TrimRequest {
+                    stream_id,
+                    offset: 0,
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(Code::FailedPrecondition, status.code());
+
+            let response = writer
+                .append(Request::new(AppendRequest {
+                    stream_id,
+                    records: records(&["hello", "world"]),
+                }))
+                .await?
+                .into_inner();
+            assert_eq!(0, response.base_offset);
+
+            let response = reader
+                .read(Request::new(ReadRequest {
+                    stream_id,
+                    offset: 0,
+                    max_bytes: 0,
+                }))
+                .await?
+                .into_inner();
+            assert_eq!(
+                vec![(0, &b"hello"[..]), (1, &b"world"[..])],
+                values(&response.records)
+            );
+            assert_eq!(2, response.next_offset);
+
+            let mut tail = reader
+                .tail(Request::new(TailRequest {
+                    stream_id,
+                    offset: 1,
+                }))
+                .await?
+                .into_inner();
+            let response = tokio::time::timeout(Duration::from_secs(10), tail.next())
+                .await?
+                .expect("Tail should not end")?;
+            assert_eq!(vec![(1, &b"world"[..])], values(&response.records));
+            assert_eq!(2, response.next_offset);
+
+            // Reads through either gateway do not fence the writer.
+            let response = writer
+                .read(Request::new(ReadRequest {
+                    stream_id,
+                    offset: 1,
+                    max_bytes: 0,
+                }))
+                .await?
+                .into_inner();
+            assert_eq!(vec![(1, &b"world"[..])], values(&response.records));
+            let response = writer
+                .append(Request::new(AppendRequest {
+                    stream_id,
+                    records: records(&["again"]),
+                }))
+                .await?
+                .into_inner();
+            assert_eq!(2, response.base_offset);
+            let response = tokio::time::timeout(Duration::from_secs(10), tail.next())
+                .await?
+                .expect("Tail should not end")?;
+            assert_eq!(vec![(2, &b"again"[..])], values(&response.records));
+
+            let response = reader
+                .describe(Request::new(DescribeRequest { stream_id }))
+                .await?
+                .into_inner();
+            assert_eq!(
+                (1, 0, 3),
+                (response.epoch, response.start_offset, response.next_offset)
+            );
+
+            writer
+                .trim(Request::new(TrimRequest {
+                    stream_id,
+                    offset: 1,
+                }))
+                .await?;
+            let response = writer
+                .describe(Request::new(DescribeRequest { stream_id }))
+                .await?
+                .into_inner();
+            assert_eq!((1, 1), (response.epoch, response.start_offset));
+            Ok::<_, Box<dyn Error>>(())
+        });
+        standalone.shutdown();
+        result
+    }
+}
@@ -0,0 +1,383 @@
+//! HTTP/JSON mapping of the gateway API, where bytes of records are encoded in standard base64.
+//!
+//! - `POST /v1/streams/{id}/records` appends `{"records": [...]}`.
+//! - `GET /v1/streams/{id}/records?offset=&max_bytes=` reads records.
+//! - `GET /v1/streams/{id}/tail?offset=` tails records, replying newline-delimited JSON.
+//! - `POST /v1/streams/{id}/trim` trims to `{"offset": ...}`.
+//! - `GET /v1/streams/{id}` describes the stream.
+//!
+//! Errors are replied as `{"error": "..."}`, with status codes by `GatewayError::http_status`.
+
+use std::{convert::Infallible, future::Future, net::SocketAddr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use hyper::{
+    header::CONTENT_TYPE,
+    server::conn::AddrStream,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    auth::{Operation, RequestContext},
+    error::GatewayError,
+    gateway::Gateway,
+    proto::{Header, Record},
+};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JsonHeader {
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JsonRecord {
+    #[serde(default)]
+    offset: i64,
+    #[serde(default)]
+    timestamp_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<JsonHeader>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppendBody {
+    records: Vec<JsonRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrimBody {
+    offset: i64,
+}
+
+fn decode_base64(field: &str, value: Option<String>) -> Result<Option<Vec<u8>>, GatewayError> {
+    value
+        .map(|value| {
+            STANDARD.decode(value).map_err(|e| {
+                GatewayError::InvalidArgument(format!("Invalid base64 of {field}: {e}"))
+            })
+        })
+        .transpose()
+}
+
+impl TryFrom<JsonRecord> for Record {
+    type Error = GatewayError;
+
+    fn try_from(record: JsonRecord) -> Result<Self, Self::Error> {
+        Ok(Record {
+            offset: record.offset,
+            timestamp_ms: record.timestamp_ms,
+            key: decode_base64("key", record.key)?,
+            value: decode_base64("value", record.value)?,
+            headers: record
+                .headers
+                .into_iter()
+                .map(|header| {
+                    Ok(Header {
+                        value: decode_base64("header", header.value)?,
+                        key: header.key,
+                    })
+                })
+                .collect::<Result<_, GatewayError>>()?,
+        })
+    }
+}
+
+impl From<Record> for JsonRecord {
+    fn from(record: Record) -> Self {
+        JsonRecord {
+            offset: record.offset,
+            timestamp_ms: record.timestamp_ms,
+            key: record.key.map(|key| STANDARD.encode(key)),
+            value: record.value.map(|value| STANDARD.encode(value)),
+            headers: record
+                .headers
+                .into_iter()
+                .map(|header| JsonHeader {
+                    key: header.key,
+                    value: header.value.map(|value| STANDARD.encode(value)),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn json_records(records: Vec<Record>) -> Vec<JsonRecord> {
+    records.into_iter().map(JsonRecord::from).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    Append(u64),
+    Read(u64),
+    Tail(u64),
+    Trim(u64),
+    Describe(u64),
+}
+
+impl Route {
+    fn operation(&self) -> Operation {
+        match self {
+            Route::Append(_) => Operation::Append,
+            Route::Read(_) => Operation::Read,
+            Route::Tail(_) => Operation::Tail,
+            Route::Trim(_) => Operation::Trim,
+            Route::Describe(_) => Operation::Describe,
+        }
+    }
+
+    fn stream_id(&self) -> u64 {
+        match *self {
+            Route::Append(id)
+            | Route::Read(id)
+            | Route::Tail(id)
+            | Route::Trim(id)
+            | Route::Describe(id) => id,
+        }
+    }
+}
+
+fn route(method: &Method, path: &str) -> Option<Route> {
+    let mut segments = path.strip_prefix("/v1/streams/")?.split('/');
+    let stream_id = segments.next()?.parse().ok()?;
+    let route = match (method, segments.next(), segments.next()) {
+        (&Method::POST, Some("records"), None) => Route::Append(stream_id),
+        (&Method::GET, Some("records"), None) => Route::Read(stream_id),
+        (&Method::GET, Some("tail"), None) => Route::Tail(stream_id),
+        (&Method::POST, Some("trim"), None) => Route::Trim(stream_id),
+        (&Method::GET, None, None) => Route::Describe(stream_id),
+        _ => return None,
+    };
+    Some(route)
+}
+
+/// Integer parameter of the query string, if present.
+fn query_param(query: Option<&str>, name: &str) -> Result<Option<i64>, GatewayError> {
+    query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| {
+            value.parse().map_err(|_| {
+                GatewayError::InvalidArgument(format!("Invalid query parameter {name}: {value}"))
+            })
+        })
+        .transpose()
+}
+
+async fn json_body<T: DeserializeOwned>(body: Body) -> Result<T, GatewayError> {
+    let buf = hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| GatewayError::InvalidArgument(format!("Failed to read body: {e}")))?;
+    serde_json::from_slice(&buf)
+        .map_err(|e| GatewayError::InvalidArgument(format!("Invalid JSON body: {e}")))
+}
+
+fn json_response(status: StatusCode, value: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(value.to_string()))
+        .expect("Response of valid status and header")
+}
+
+fn error_response(e: &GatewayError) -> Response<Body> {
+    json_response(e.http_status(), json!({ "error": e.to_string() }))
+}
+
+async fn serve_request(
+    gateway: Gateway,
+    remote_addr: SocketAddr,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let Some(route) = route(request.method(), request.uri().path()) else {
+        return Ok(json_response(
+            StatusCode::NOT_FOUND,
+            json!({ "error": format!("No route for {} {}", request.method(), request.uri().path()) }),
+        ));
+    };
+    let response = match dispatch(&gateway, route, remote_addr, request).await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!("Failed to serve {route:?} from {remote_addr}: {e}");
+            error_response(&e)
+        }
+    };
+    Ok(response)
+}
+
+async fn dispatch(
+    gateway: &Gateway,
+    route: Route,
+    remote_addr: SocketAddr,
+    request: Request<Body>,
+) -> Result<Response<Body>, GatewayError> {
+    let (parts, body) = request.into_parts();
+    let context = RequestContext {
+        operation: route.operation(),
+        stream_id: route.stream_id(),
+        headers: parts.headers,
+        remote_addr: Some(remote_addr),
+    };
+    let query = parts.uri.query();
+    let response = match route {
+        Route::Append(_) => {
+            let body: AppendBody = json_body(body).await?;
+            let records = body
+                .records
+                .into_iter()
+                .map(Record::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            let base_offset = gateway.append(&context, records).await?;
+            json_response(StatusCode::OK, json!({ "base_offset": base_offset }))
+        }
+        Route::Read(_) => {
+            let offset = query_param(query, "offset")?.unwrap_or_default();
+            let max_bytes = query_param(query, "max_bytes")?.unwrap_or_default();
+            let max_bytes = i32::try_from(max_bytes).unwrap_or(i32::MAX);
+            let response = gateway.read(&context, offset, max_bytes).await?;
+            json_response(
+                StatusCode::OK,
+                json!({
+                    "records": json_records(response.records),
+                    "next_offset": response.next_offset,
+                }),
+            )
+        }
+        Route::Tail(_) => {
+            let offset = query_param(query, "offset")?.unwrap_or_default();
+            let rx = gateway.tail(&context, offset)?;
+            // Each line is a batch of records, or the error ending the tail.
+            let lines = futures::stream::unfold(rx, |mut rx| async move {
+                let line = match rx.recv().await? {
+                    Ok(response) => json!({
+                        "records": json_records(response.records),
+                        "next_offset": response.next_offset,
+                    }),
+                    Err(e) => json!({ "error": e.to_string() }),
+                };
+                Some((Ok::<_, Infallible>(Bytes::from(format!("{line}\n"))), rx))
+            });
+            Response::builder()
+                .header(CONTENT_TYPE, "application/x-ndjson")
+                .body(Body::wrap_stream(lines))
+                .expect("Response of valid header")
+        }
+        Route::Trim(_) => {
+            let body: TrimBody = json_body(body).await?;
+            gateway.trim(&context, body.offset).await?;
+            json_response(StatusCode::OK, json!({}))
+        }
+        Route::Describe(_) => {
+            let response = gateway.describe(&context).await?;
+            json_response(
+                StatusCode::OK,
+                json!({
+                    "stream_id": response.stream_id,
+                    "replica": response.replica,
+                    "ack_count": response.ack_count,
+                    "retention_ms": response.retention_ms,
+                    "epoch": response.epoch,
+                    "compacted": response.compacted,
+                    "start_offset": response.start_offset,
+                    "next_offset": response.next_offset,
+                }),
+            )
+        }
+    };
+    Ok(response)
+}
+
+/// Serve the HTTP/JSON API on `addr`, until `shutdown` completes.
+pub async fn serve<F>(gateway: Gateway, addr: SocketAddr, shutdown: F) -> Result<(), hyper::Error>
+where
+    F: Future<Output = ()>,
+{
+    let make_service = make_service_fn(move |conn: &AddrStream| {
+        let gateway = gateway.clone();
+        let remote_addr = conn.remote_addr();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                serve_request(gateway.clone(), remote_addr, request)
+            }))
+        }
+    });
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    info!("HTTP gateway is listening {}", server.local_addr());
+    server.with_graceful_shutdown(shutdown).await
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::Method;
+
+    use super::{query_param, route, JsonRecord, Route};
+    use crate::proto::Record;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(
+            Some(Route::Append(1)),
+            route(&Method::POST, "/v1/streams/1/records")
+        );
+        assert_eq!(
+            Some(Route::Read(1)),
+            route(&Method::GET, "/v1/streams/1/records")
+        );
+        assert_eq!(
+            Some(Route::Tail(2)),
+            route(&Method::GET, "/v1/streams/2/tail")
+        );
+        assert_eq!(
+            Some(Route::Trim(3)),
+            route(&Method::POST, "/v1/streams/3/trim")
+        );
+        assert_eq!(
+            Some(Route::Describe(4)),
+            route(&Method::GET, "/v1/streams/4")
+        );
+        assert_eq!(None, route(&Method::DELETE, "/v1/streams/4"));
+        assert_eq!(None, route(&Method::GET, "/v1/streams/abc/records"));
+        assert_eq!(None, route(&Method::GET, "/v1/streams/1/records/2"));
+        assert_eq!(None, route(&Method::GET, "/v2/streams/1"));
+    }
+
+    #[test]
+    fn test_query_param() {
+        let query = Some("offset=10&max_bytes=4096");
+        assert_eq!(Some(10), query_param(query, "offset").unwrap());
+        assert_eq!(Some(4096), query_param(query, "max_bytes").unwrap());
+        assert_eq!(None, query_param(query, "limit").unwrap());
+        assert_eq!(None, query_param(None, "offset").unwrap());
+        assert!(query_param(Some("offset=x"), "offset").is_err());
+    }
+
+    #[test]
+    fn test_json_record() {
+        let record: JsonRecord = serde_json::from_str(
+            r#"{"key": "aw==", "value": "dg==", "headers": [{"key": "h", "value": "eA=="}]}"#,
+        )
+        .unwrap();
+        let record = Record::try_from(record).unwrap();
+        assert_eq!(Some(b"k".to_vec()), record.key);
+        assert_eq!(Some(b"v".to_vec()), record.value);
+        assert_eq!(Some(b"x".to_vec()), record.headers[0].value);
+
+        let json = serde_json::to_value(JsonRecord::from(record)).unwrap();
+        assert_eq!("dg==", json["value"]);
+
+        let record: JsonRecord = serde_json::from_str(r#"{"value": "not base64!"}"#).unwrap();
+        assert!(Record::try_from(record).is_err());
+    }
+}
@@ -0,0 +1,21 @@
+//! Gateway serving streams to clients of other languages, over gRPC and HTTP/JSON.
+//!
+//! Both APIs append, read, tail, trim and describe streams, addressed by stream id. Each request is
+//! authorized by an `AuthHook` before it reaches streams, which are served by a dedicated tokio-uring
+//! runtime thread.
+
+pub mod auth;
+mod backend;
+pub mod error;
+pub mod gateway;
+pub mod grpc;
+pub mod http;
+mod record;
+
+/// Messages and service of `proto/gateway.proto`.
+pub mod proto {
+    tonic::include_proto!("elasticstream.gateway.v1");
+}
+
+pub use crate::error::GatewayError;
+pub use crate::gateway::{Gateway, GatewayConfig};
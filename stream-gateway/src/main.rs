@@ -0,0 +1,97 @@
+use std::{error::Error, net::SocketAddr};
+
+use clap::Parser;
+use frontend::FrontendBuilder;
+use log::info;
+use stream_gateway::{auth::BearerTokens, grpc::GrpcService, http, Gateway, GatewayConfig};
+use tokio::sync::watch;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "gRPC and HTTP/JSON gateway of streams", long_about = None)]
+struct Args {
+    /// Access point of placement driver
+    #[arg(long, env = "ES_PD", default_value = "127.0.0.1:12378")]
+    pd: String,
+
+    /// Address to listen for gRPC clients
+    #[arg(long, default_value = "0.0.0.0:50051")]
+    grpc_listen: SocketAddr,
+
+    /// Address to listen for HTTP/JSON clients, which is disabled if absent
+    #[arg(long)]
+    http_listen: Option<SocketAddr>,
+
+    /// Serve appends and trims as well, opening streams for write with a new epoch, which fences other
+    /// writers of streams. Only reads are served if absent
+    #[arg(long)]
+    writable: bool,
+
+    /// Bearer token allowed to access streams, which may be repeated. All requests are allowed if
+    /// absent
+    #[arg(long = "token")]
+    tokens: Vec<String>,
+
+    /// Configuration file of the client
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Prefix of environment variables overriding configuration of the client
+    #[arg(long)]
+    env_prefix: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    frontend::init_log();
+
+    let mut builder = FrontendBuilder::new(&args.pd);
+    if let Some(path) = &args.config {
+        builder = builder.with_config_file(path);
+    }
+    if let Some(prefix) = &args.env_prefix {
+        builder = builder.with_env_prefix(prefix);
+    }
+    let frontend = builder.build()?;
+
+    let config = GatewayConfig {
+        writable: args.writable,
+    };
+    let mut gateway = Gateway::new(frontend, config)?;
+    if !args.tokens.is_empty() {
+        gateway = gateway.with_auth_hook(BearerTokens::new(args.tokens));
+    }
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .thread_name("Gateway")
+        .enable_all()
+        .build()?;
+    runtime.block_on(async move {
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let shutdown = move || {
+            let mut rx = shutdown_rx.clone();
+            async move {
+                let _ = rx.changed().await;
+            }
+        };
+
+        let http_server = args
+            .http_listen
+            .map(|addr| tokio::spawn(http::serve(gateway.clone(), addr, shutdown())));
+
+        tokio::spawn(async move {
+            let _ = tokio::signal::ctrl_c().await;
+            info!("Shutting down the gateway");
+            let _ = shutdown_tx.send(());
+        });
+
+        info!("gRPC gateway is listening {}", args.grpc_listen);
+        tonic::transport::Server::builder()
+            .add_service(GrpcService::new(gateway).into_server())
+            .serve_with_shutdown(args.grpc_listen, shutdown())
+            .await?;
+        if let Some(http_server) = http_server {
+            http_server.await??;
+        }
+        Ok::<_, Box<dyn Error>>(())
+    })
+}
@@ -0,0 +1,145 @@
+//! Conversion between records of the gateway API and record batches of streams, which are in the
+//! format of `RecordMagic::Magic1`.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use model::{
+    error::EsError,
+    record::{
+        flat_record::FlatRecordBatch,
+        magic1::{self, RecordsBuilder},
+    },
+    RecordBatch,
+};
+use protocol::rpc::header::ErrorCode;
+
+use crate::{
+    error::GatewayError,
+    proto::{Header, Record},
+};
+
+/// Encode records into a single record batch of the stream.
+pub(crate) fn encode(stream_id: u64, records: Vec<Record>) -> Result<Bytes, GatewayError> {
+    if records.is_empty() {
+        return Err(GatewayError::InvalidArgument(
+            "At least one record should be appended".to_owned(),
+        ));
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64);
+    let mut builder = RecordsBuilder::new();
+    for record in records {
+        let timestamp = if record.timestamp_ms > 0 {
+            record.timestamp_ms
+        } else {
+            now
+        };
+        let headers = record
+            .headers
+            .into_iter()
+            .map(|header| magic1::Header::new(header.key, header.value.map(Bytes::from)))
+            .collect::<Vec<_>>();
+        builder.append(
+            timestamp,
+            record.key.map(Bytes::from),
+            record.value.map(Bytes::from),
+            &headers,
+        );
+    }
+    let record_batch = builder
+        .build(stream_id as i64)
+        .map_err(|e| GatewayError::InvalidArgument(format!("{e:?}")))?;
+    let (buffers, _) = FlatRecordBatch::from(record_batch).encode();
+    Ok(Bytes::from(buffers.concat()))
+}
+
+/// Decode records at or after `start_offset` of the record batch into `records`. Control record
+/// batches of transactions are skipped.
+pub(crate) fn decode(
+    record_batch: &RecordBatch,
+    start_offset: i64,
+    records: &mut Vec<Record>,
+) -> Result<(), GatewayError> {
+    if record_batch.is_control() {
+        return Ok(());
+    }
+    let decode_error = |e| EsError::new(ErrorCode::RECORDS_PARSE_ERROR, &format!("{e:?}"));
+    for record in record_batch.records().map_err(decode_error)? {
+        let record = record.map_err(decode_error)?;
+        if record.offset < start_offset {
+            continue;
+        }
+        records.push(Record {
+            offset: record.offset,
+            timestamp_ms: record.timestamp,
+            key: record.key.map(Vec::from),
+            value: record.value.map(Vec::from),
+            headers: record
+                .headers
+                .into_iter()
+                .map(|header| Header {
+                    key: header.key,
+                    value: header.value.map(Vec::from),
+                })
+                .collect(),
+        });
+    }
+    Ok(())
+}
+
+/// Exclusive end offset of the record batch.
+pub(crate) fn end_offset(record_batch: &RecordBatch) -> i64 {
+    record_batch.base_offset() + record_batch.last_offset_delta() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use model::record::flat_record::FlatRecordBatch;
+
+    use super::{decode, encode, end_offset};
+    use crate::proto::{Header, Record};
+
+    #[test]
+    fn test_encode_decode() {
+        let records = vec![
+            Record {
+                timestamp_ms: 1000,
+                value: Some(b"a".to_vec()),
+                ..Default::default()
+            },
+            Record {
+                key: Some(b"k".to_vec()),
+                value: Some(b"b".to_vec()),
+                headers: vec![Header {
+                    key: "h".to_owned(),
+                    value: None,
+                }],
+                ..Default::default()
+            },
+        ];
+        let mut buf: Bytes = encode(1, records).unwrap();
+        let record_batch = FlatRecordBatch::decode_to_record_batch(&mut buf).unwrap();
+        assert_eq!(1, record_batch.stream_id());
+        assert_eq!(2, end_offset(&record_batch));
+
+        let mut decoded = vec![];
+        decode(&record_batch, 0, &mut decoded).unwrap();
+        assert_eq!(2, decoded.len());
+        assert_eq!(1000, decoded[0].timestamp_ms);
+        assert_eq!(Some(b"a".to_vec()), decoded[0].value);
+        assert!(decoded[1].timestamp_ms > 1000);
+        assert_eq!(Some(b"k".to_vec()), decoded[1].key);
+        assert_eq!("h", decoded[1].headers[0].key);
+
+        // Records before the start offset are skipped.
+        let mut decoded = vec![];
+        decode(&record_batch, 1, &mut decoded).unwrap();
+        assert_eq!(1, decoded.len());
+        assert_eq!(1, decoded[0].offset);
+
+        assert!(encode(1, vec![]).is_err());
+    }
+}